@@ -14,63 +14,53 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
         .iter()
         .find(|attr| attr.path().is_ident("fragment"));
 
-    let strategy_expr = if let Some(fragment_attr) = fragment_attr {
-        // Parse the nested meta to get strategy = "value" or with_fn = "function_name"
-        let nested_meta: syn::MetaNameValue = fragment_attr.parse_args()?;
-
-        if nested_meta.path.is_ident("strategy") {
-            // Direct strategy specification: #[fragment(strategy = "innerHTML")]
-            let strategy_lit = match &nested_meta.value {
-                syn::Expr::Lit(expr_lit) => match &expr_lit.lit {
-                    syn::Lit::Str(lit_str) => lit_str,
-                    _ => {
+    let mut strategy_expr = None;
+    let mut oob_render = false;
+
+    if let Some(fragment_attr) = fragment_attr {
+        // Parse the comma-separated options: strategy = "value" or with_fn = "function_name",
+        // and the `oob_render` flag
+        let options = fragment_attr.parse_args_with(
+            syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+        )?;
+
+        for option in options {
+            match option {
+                syn::Meta::Path(path) if path.is_ident("oob_render") => oob_render = true,
+                syn::Meta::NameValue(nested_meta)
+                    if nested_meta.path.is_ident("strategy")
+                        || nested_meta.path.is_ident("with_fn") =>
+                {
+                    if strategy_expr.is_some() {
                         return Err(syn::Error::new_spanned(
-                            &nested_meta.value,
-                            "strategy must be a string literal",
+                            &nested_meta.path,
+                            "only one of 'strategy' or 'with_fn' can be specified",
                         ));
                     }
-                },
-                _ => {
+
+                    strategy_expr = Some(parse_strategy(&nested_meta)?);
+                }
+                option => {
                     return Err(syn::Error::new_spanned(
-                        &nested_meta.value,
-                        "strategy must be a string literal",
+                        option.path(),
+                        "expected 'strategy', 'with_fn' or 'oob_render' attribute",
                     ));
                 }
-            };
-
-            let strategy_value = strategy_lit.value();
-
-            // Map the string to the appropriate InsertStrategy variant
-            // Using exact HTMX strings as documented at https://htmx.org/attributes/hx-swap/
-            match strategy_value.as_str() {
-                "innerHTML" => quote! { htmxology::htmx::InsertStrategy::InnerHtml },
-                "outerHTML" => quote! { htmxology::htmx::InsertStrategy::OuterHtml },
-                "textContent" => quote! { htmxology::htmx::InsertStrategy::TextContent },
-                "beforebegin" => quote! { htmxology::htmx::InsertStrategy::BeforeBegin },
-                "afterbegin" => quote! { htmxology::htmx::InsertStrategy::AfterBegin },
-                "beforeend" => quote! { htmxology::htmx::InsertStrategy::BeforeEnd },
-                "afterend" => quote! { htmxology::htmx::InsertStrategy::AfterEnd },
-                "delete" => quote! { htmxology::htmx::InsertStrategy::Delete },
-                "none" => quote! { htmxology::htmx::InsertStrategy::None },
-                other => {
-                    // Allow custom strategies
-                    quote! { htmxology::htmx::InsertStrategy::Custom(#other.to_string()) }
-                }
             }
-        } else if nested_meta.path.is_ident("with_fn") {
-            // Function-based strategy: #[fragment(with_fn = "Foo::get_strategy")]
-            let fn_path = crate::utils::parse_with_fn_attribute_as_path(&nested_meta)?;
-            quote! { #fn_path(self) }
-        } else {
-            return Err(syn::Error::new_spanned(
-                &nested_meta.path,
-                "expected 'strategy' or 'with_fn' attribute",
-            ));
         }
-    } else {
-        // No attribute provided, default to outerHTML (Issue #8)
-        quote! { htmxology::htmx::InsertStrategy::OuterHtml }
-    };
+    }
+
+    // No strategy provided, default to outerHTML (Issue #8)
+    let strategy_expr =
+        strategy_expr.unwrap_or_else(|| quote! { htmxology::htmx::InsertStrategy::OuterHtml });
+
+    let oob_render_fn = oob_render.then(|| {
+        quote! {
+            fn oob_render(&self) -> Option<String> {
+                htmxology::htmx::oob_render_with_id_attribute(self)
+            }
+        }
+    });
 
     Ok(quote! {
         impl<#root_generics_params> htmxology::htmx::Fragment for #root_ident<#root_param_idents>
@@ -79,10 +69,60 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
             fn insert_strategy(&self) -> htmxology::htmx::InsertStrategy {
                 #strategy_expr
             }
+
+            #oob_render_fn
         }
     })
 }
 
+/// Parse a `strategy = "value"` or `with_fn = "function_name"` option into a strategy expression.
+fn parse_strategy(nested_meta: &syn::MetaNameValue) -> syn::Result<proc_macro2::TokenStream> {
+    if nested_meta.path.is_ident("strategy") {
+        // Direct strategy specification: #[fragment(strategy = "innerHTML")]
+        let strategy_lit = match &nested_meta.value {
+            syn::Expr::Lit(expr_lit) => match &expr_lit.lit {
+                syn::Lit::Str(lit_str) => lit_str,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        &nested_meta.value,
+                        "strategy must be a string literal",
+                    ));
+                }
+            },
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &nested_meta.value,
+                    "strategy must be a string literal",
+                ));
+            }
+        };
+
+        let strategy_value = strategy_lit.value();
+
+        // Map the string to the appropriate InsertStrategy variant
+        // Using exact HTMX strings as documented at https://htmx.org/attributes/hx-swap/
+        Ok(match strategy_value.as_str() {
+            "innerHTML" => quote! { htmxology::htmx::InsertStrategy::InnerHtml },
+            "outerHTML" => quote! { htmxology::htmx::InsertStrategy::OuterHtml },
+            "textContent" => quote! { htmxology::htmx::InsertStrategy::TextContent },
+            "beforebegin" => quote! { htmxology::htmx::InsertStrategy::BeforeBegin },
+            "afterbegin" => quote! { htmxology::htmx::InsertStrategy::AfterBegin },
+            "beforeend" => quote! { htmxology::htmx::InsertStrategy::BeforeEnd },
+            "afterend" => quote! { htmxology::htmx::InsertStrategy::AfterEnd },
+            "delete" => quote! { htmxology::htmx::InsertStrategy::Delete },
+            "none" => quote! { htmxology::htmx::InsertStrategy::None },
+            other => {
+                // Allow custom strategies
+                quote! { htmxology::htmx::InsertStrategy::Custom(#other.to_string()) }
+            }
+        })
+    } else {
+        // Function-based strategy: #[fragment(with_fn = "Foo::get_strategy")]
+        let fn_path = crate::utils::parse_with_fn_attribute_as_path(nested_meta)?;
+        Ok(quote! { #fn_path(self) })
+    }
+}
+
 #[cfg(test)]
 mod snapshot_tests {
     use super::*;
@@ -141,6 +181,26 @@ mod snapshot_tests {
         assert_snapshot!(test_fragment(input));
     }
 
+    #[test]
+    fn oob_render() {
+        let input = r#"
+            #[fragment(strategy = "innerHTML", oob_render)]
+            struct Counter {
+                value: u32,
+            }
+        "#;
+        assert_snapshot!(test_fragment(input));
+    }
+
+    #[test]
+    fn oob_render_only() {
+        let input = r#"
+            #[fragment(oob_render)]
+            struct Status;
+        "#;
+        assert_snapshot!(test_fragment(input));
+    }
+
     #[test]
     fn generic_with_default() {
         let input = r#"
@@ -0,0 +1,647 @@
+//! HtmlForm derive macro.
+
+use convert_case::{Case, Casing};
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+
+/// The name of the `html_form` attribute.
+const HTML_FORM: &str = "html_form";
+
+/// The primitive numeric types, rendered as `number` inputs.
+const NUMERIC_TYPES: &[&str] = &[
+    "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize", "f32",
+    "f64",
+];
+
+/// The form-level options of a `#[html_form(...)]` attribute.
+#[derive(Default)]
+struct FormSpec {
+    /// The route type handling the submission of the form.
+    route: Option<syn::Type>,
+
+    /// The function returning the route handling the submission of the form.
+    ///
+    /// Defaults to converting a clone of the form into the route.
+    action: Option<syn::Path>,
+
+    /// The label of the submit button, if any.
+    submit: Option<syn::LitStr>,
+
+    /// The function converting the generated validation routes into routes of the application.
+    validation_route: Option<syn::Path>,
+}
+
+/// The field-level options of a `#[html_form(...)]` attribute.
+#[derive(Default)]
+struct FieldSpec {
+    /// The name of the input, overriding the serde name of the field.
+    name: Option<syn::LitStr>,
+
+    /// The type of the input, overriding the inferred one.
+    input_type: Option<syn::LitStr>,
+
+    /// The label of the input, if any.
+    label: Option<syn::LitStr>,
+
+    /// Whether the input is required.
+    required: bool,
+
+    /// Whether the field has no input.
+    skip: bool,
+
+    /// The function validating the value of the field, if any.
+    validate: Option<syn::Path>,
+}
+
+pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let root_ident = &input.ident;
+    let root_generics_params = &input.generics.params;
+    let root_param_idents = crate::utils::extract_generic_param_idents(&input.generics.params);
+    let root_where_clause = &input.generics.where_clause;
+
+    let fields = match &input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => &fields.named,
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "HtmlForm can only be derived for structs with named fields",
+            ));
+        }
+    };
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+    {
+        if serde_attribute_value(attr, "rename_all")?.is_some() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`#[serde(rename_all = \"...\")]` is not supported by HtmlForm: use `#[serde(rename = \"...\")]` on fields instead",
+            ));
+        }
+    }
+
+    let form_spec = parse_form_spec(&input.attrs)?;
+
+    let validation_ident = format_ident!("{root_ident}Validation");
+    let mut input_fns = Vec::new();
+    let mut input_fn_idents = Vec::new();
+    let mut validation_variants = Vec::new();
+    let mut validation_arms = Vec::new();
+
+    for field in fields {
+        let field_spec = parse_field_spec(&field.attrs)?;
+
+        if field_spec.skip {
+            continue;
+        }
+
+        let field_ident = field.ident.as_ref().expect("fields are named");
+        let fn_ident = format_ident!("{field_ident}_input");
+
+        // The name defaults to the serde name of the field, so that the form deserializes.
+        let name_lit = match field_spec.name {
+            Some(name) => name,
+            None => {
+                let mut name = None;
+
+                for attr in field
+                    .attrs
+                    .iter()
+                    .filter(|attr| attr.path().is_ident("serde"))
+                {
+                    if let Some(rename) = serde_attribute_value(attr, "rename")? {
+                        name = Some(rename);
+                    }
+                }
+
+                name.unwrap_or_else(|| {
+                    syn::LitStr::new(
+                        field_ident.to_string().trim_start_matches("r#"),
+                        field_ident.span(),
+                    )
+                })
+            }
+        };
+
+        crate::utils::validate_html_identifier(&name_lit.value(), name_lit.span(), "name")?;
+
+        let (inner_ty, optional) = match crate::utils::option_inner_type(&field.ty) {
+            Some(inner_ty) => (inner_ty, true),
+            None => (&field.ty, false),
+        };
+        let is_bool = type_is_one_of(inner_ty, &["bool"]);
+
+        let input_type = match field_spec.input_type {
+            Some(input_type) => input_type.value(),
+            None if type_name_is(inner_ty, "SubmissionToken") => "hidden".to_owned(),
+            None if is_bool => "checkbox".to_owned(),
+            None if type_is_one_of(inner_ty, NUMERIC_TYPES) => "number".to_owned(),
+            None => "text".to_owned(),
+        };
+
+        let label = field_spec.label.map(|label| {
+            quote! { .with_label(#label) }
+        });
+        let required = field_spec.required.then(|| {
+            quote! { .with_required(true) }
+        });
+
+        let input = quote! {
+            htmxology::htmx::FormInput::new(
+                htmxology::htmx::HtmlName::from_static(#name_lit)
+                    .expect("name was validated at compile time"),
+            )
+            .with_input_type(#input_type)
+            #label
+            #required
+        };
+
+        let validation_route = match (&field_spec.validate, &form_spec.validation_route) {
+            (Some(validate), Some(validation_route)) => {
+                let variant_ident = syn::Ident::new(
+                    &field_ident
+                        .to_string()
+                        .trim_start_matches("r#")
+                        .to_case(Case::UpperCamel),
+                    field_ident.span(),
+                );
+                let path = field_ident.to_string().trim_start_matches("r#").to_owned();
+                let field_ty = &field.ty;
+
+                let parse = if is_bool && !optional {
+                    quote! { __values.parse::<bool>(#name_lit).map(Option::unwrap_or_default) }
+                } else if optional {
+                    quote! { __values.parse_optional::<#inner_ty>(#name_lit) }
+                } else {
+                    quote! {
+                        __values
+                            .parse::<#field_ty>(#name_lit)
+                            .and_then(|value| value.ok_or_else(|| "this field is missing".to_owned()))
+                    }
+                };
+                let value = if is_bool {
+                    quote! { input.with_value("true").with_checked(__values.get(#name_lit).is_some()) }
+                } else {
+                    quote! {
+                        match __values.get(#name_lit) {
+                            Some(value) => input.with_value(value),
+                            None => input,
+                        }
+                    }
+                };
+
+                validation_variants.push(quote! {
+                    #[route(#path, method = "POST")]
+                    #variant_ident(#[body] htmxology::htmx::FormValues)
+                });
+                validation_arms.push(quote! {
+                    Self::#variant_ident(__values) => {
+                        let input = #input;
+                        let input = #value;
+                        let result = #parse.and_then(|value: #field_ty| #validate(&value));
+
+                        input
+                            .with_validation_route(&#validation_route(self.clone()))
+                            .with_validation(result)
+                    }
+                });
+
+                Some(quote! {
+                    .with_validation_route(&#validation_route(
+                        #validation_ident::#variant_ident(Default::default()),
+                    ))
+                })
+            }
+            (Some(validate), None) => {
+                return Err(syn::Error::new_spanned(
+                    validate,
+                    "`validate` requires `validation_route` to be specified on the form",
+                ));
+            }
+            (None, _) => None,
+        };
+
+        let value = if is_bool {
+            let checked = if optional {
+                quote! { self.#field_ident.unwrap_or_default() }
+            } else {
+                quote! { self.#field_ident }
+            };
+
+            quote! { input.with_value("true").with_checked(#checked) }
+        } else if optional {
+            quote! {
+                match &self.#field_ident {
+                    Some(value) => input.with_value(value.to_string()),
+                    None => input,
+                }
+            }
+        } else {
+            quote! { input.with_value(self.#field_ident.to_string()) }
+        };
+
+        let doc = format!(" Get the `<input>` element of the `{field_ident}` field.");
+
+        input_fns.push(quote! {
+            #[doc = #doc]
+            pub fn #fn_ident(&self) -> htmxology::htmx::FormInput {
+                let input = #input #validation_route;
+
+                #value
+            }
+        });
+        input_fn_idents.push(fn_ident);
+    }
+
+    let form_impls = match &form_spec.route {
+        Some(route_ty) => {
+            // Without an action, the form converts into its route, like for `GET|POST` routes.
+            let action_route = match &form_spec.action {
+                Some(action_fn) => quote! { #action_fn(self) },
+                None => quote! { <#route_ty as From<Self>>::from(self.clone()) },
+            };
+            let submit = form_spec.submit.map(|submit| {
+                let button = format!(
+                    r#"<button type="submit">{}</button>"#,
+                    escape_text(&submit.value())
+                );
+
+                quote! { f.write_str(#button)?; }
+            });
+
+            quote! {
+                impl<#root_generics_params> htmxology::htmx::HtmlForm for #root_ident<#root_param_idents>
+                    #root_where_clause
+                {
+                    type Route = #route_ty;
+
+                    fn action_route(&self) -> Self::Route {
+                        #action_route
+                    }
+                }
+
+                impl<#root_generics_params> std::fmt::Display for #root_ident<#root_param_idents>
+                    #root_where_clause
+                {
+                    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        write!(f, "<form {}>", htmxology::htmx::HtmlForm::action_attribute(self))?;
+
+                        for input in self.inputs() {
+                            write!(f, "{input}")?;
+                        }
+
+                        #submit
+                        f.write_str("</form>")
+                    }
+                }
+            }
+        }
+        None => {
+            if let Some(action) = &form_spec.action {
+                return Err(syn::Error::new_spanned(
+                    action,
+                    "`action` requires `route` to be specified",
+                ));
+            }
+
+            if let Some(submit) = &form_spec.submit {
+                return Err(syn::Error::new_spanned(
+                    submit,
+                    "`submit` requires `route` to be specified",
+                ));
+            }
+
+            quote! {}
+        }
+    };
+
+    let validation_impls = match &form_spec.validation_route {
+        Some(validation_route) if validation_variants.is_empty() => {
+            return Err(syn::Error::new_spanned(
+                validation_route,
+                "`validation_route` requires at least one field with a `validate` function",
+            ));
+        }
+        Some(validation_route) if !input.generics.params.is_empty() => {
+            return Err(syn::Error::new_spanned(
+                validation_route,
+                "`validation_route` is not supported on generic forms",
+            ));
+        }
+        Some(_) => {
+            let vis = &input.vis;
+            let doc = format!(
+                " The routes validating the fields of [`{root_ident}`], which are posted the values of the form."
+            );
+
+            quote! {
+                #[doc = #doc]
+                #[derive(Debug, Clone, PartialEq, htmxology::Route)]
+                #vis enum #validation_ident {
+                    #(#validation_variants),*
+                }
+
+                impl #validation_ident {
+                    /// Validate the field of the route, returning its input with the validation
+                    /// result.
+                    pub fn validate(&self) -> htmxology::htmx::FormInput {
+                        match self {
+                            #(#validation_arms)*
+                        }
+                    }
+                }
+            }
+        }
+        None => quote! {},
+    };
+
+    Ok(quote! {
+        impl<#root_generics_params> #root_ident<#root_param_idents>
+            #root_where_clause
+        {
+            #(#input_fns)*
+
+            /// Get the `<input>` elements of the form, in field order.
+            pub fn inputs(&self) -> Vec<htmxology::htmx::FormInput> {
+                vec![#(self.#input_fn_idents()),*]
+            }
+        }
+
+        #form_impls
+        #validation_impls
+    })
+}
+
+/// Parse the form-level `#[html_form(...)]` attribute, if any.
+fn parse_form_spec(attrs: &[syn::Attribute]) -> syn::Result<FormSpec> {
+    let mut spec = FormSpec::default();
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident(HTML_FORM)) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("route") {
+                spec.route = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("action") {
+                let lit: syn::LitStr = meta.value()?.parse()?;
+                spec.action = Some(lit.parse().map_err(|_| {
+                    syn::Error::new(lit.span(), "action must be a valid function path")
+                })?);
+            } else if meta.path.is_ident("submit") {
+                spec.submit = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("validation_route") {
+                let lit: syn::LitStr = meta.value()?.parse()?;
+                spec.validation_route = Some(lit.parse().map_err(|_| {
+                    syn::Error::new(lit.span(), "validation_route must be a valid function path")
+                })?);
+            } else {
+                return Err(
+                    meta.error("expected `route`, `action`, `submit` or `validation_route`")
+                );
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(spec)
+}
+
+/// Parse the field-level `#[html_form(...)]` attribute, if any.
+fn parse_field_spec(attrs: &[syn::Attribute]) -> syn::Result<FieldSpec> {
+    let mut spec = FieldSpec::default();
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident(HTML_FORM)) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                spec.name = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("input_type") {
+                spec.input_type = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("label") {
+                spec.label = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("required") {
+                spec.required = true;
+            } else if meta.path.is_ident("skip") {
+                spec.skip = true;
+            } else if meta.path.is_ident("validate") {
+                let lit: syn::LitStr = meta.value()?.parse()?;
+                spec.validate = Some(lit.parse().map_err(|_| {
+                    syn::Error::new(lit.span(), "validate must be a valid function path")
+                })?);
+            } else {
+                return Err(meta.error(
+                    "expected `name`, `input_type`, `label`, `required`, `skip` or `validate`",
+                ));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(spec)
+}
+
+/// Get the string value of a `key = "value"` entry of a `#[serde(...)]` attribute.
+fn serde_attribute_value(attr: &syn::Attribute, key: &str) -> syn::Result<Option<syn::LitStr>> {
+    let metas = attr.parse_args_with(
+        syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+    )?;
+
+    for meta in metas {
+        if let syn::Meta::NameValue(name_value) = meta
+            && name_value.path.is_ident(key)
+            && let syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(lit),
+                ..
+            }) = name_value.value
+        {
+            return Ok(Some(lit));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Check whether a type is one of the specified single-identifier types.
+fn type_is_one_of(ty: &syn::Type, idents: &[&str]) -> bool {
+    match ty {
+        syn::Type::Path(type_path) => type_path
+            .path
+            .get_ident()
+            .is_some_and(|ident| idents.iter().any(|candidate| ident == candidate)),
+        _ => false,
+    }
+}
+
+/// Check whether a type is a path whose last segment is the specified identifier, like
+/// `htmxology::session::SubmissionToken`.
+fn type_name_is(ty: &syn::Type, ident: &str) -> bool {
+    match ty {
+        syn::Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == ident),
+        _ => false,
+    }
+}
+
+/// Escape a value for an HTML text node.
+fn escape_text(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod snapshot_tests {
+    use super::*;
+    use crate::utils::testing::test_derive;
+    use insta::assert_snapshot;
+
+    fn test_html_form(input: &str) -> String {
+        test_derive(input, derive)
+    }
+
+    #[test]
+    fn simple_struct() {
+        let input = r#"
+            struct LoginForm {
+                #[html_form(input_type = "email", label = "E-mail", required)]
+                email: String,
+                #[html_form(input_type = "password", required)]
+                password: String,
+                remember_me: bool,
+            }
+        "#;
+        assert_snapshot!(test_html_form(input));
+    }
+
+    #[test]
+    fn struct_with_options_and_renames() {
+        let input = r#"
+            struct SearchForm {
+                #[serde(rename = "q")]
+                query: Option<String>,
+                #[html_form(name = "page-size")]
+                page_size: u32,
+                #[html_form(skip)]
+                internal: Vec<u8>,
+            }
+        "#;
+        assert_snapshot!(test_html_form(input));
+    }
+
+    #[test]
+    fn struct_with_action() {
+        let input = r#"
+            #[html_form(route = AppRoute, action = "Self::action", submit = "Save & exit")]
+            struct ProfileForm<T> {
+                name: T,
+            }
+        "#;
+        assert_snapshot!(test_html_form(input));
+    }
+
+    #[test]
+    fn struct_with_route_conversion() {
+        let input = r#"
+            #[html_form(route = AppRoute)]
+            struct SettingsForm {
+                theme: String,
+            }
+        "#;
+        assert_snapshot!(test_html_form(input));
+    }
+
+    #[test]
+    fn struct_with_submission_token() {
+        let input = r#"
+            #[html_form(route = AppRoute, submit = "Pay")]
+            struct PaymentForm {
+                amount: u32,
+                token: htmxology::session::SubmissionToken,
+            }
+        "#;
+        assert_snapshot!(test_html_form(input));
+    }
+
+    #[test]
+    fn struct_with_validation() {
+        let input = r#"
+            #[html_form(route = AppRoute, validation_route = "AppRoute::ValidateSignup")]
+            pub struct SignupForm {
+                #[html_form(input_type = "email", validate = "validate_email")]
+                email: String,
+                #[html_form(validate = "validate_age")]
+                age: Option<u8>,
+                nickname: String,
+            }
+        "#;
+        assert_snapshot!(test_html_form(input));
+    }
+
+    #[test]
+    fn invalid_name() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            struct Form {
+                #[html_form(name = "-invalid")]
+                value: String,
+            }
+        "#,
+        )
+        .unwrap();
+
+        assert!(derive(&mut input).is_err());
+    }
+
+    #[test]
+    fn action_without_route() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            #[html_form(action = "Self::action")]
+            struct Form {
+                value: String,
+            }
+        "#,
+        )
+        .unwrap();
+
+        assert!(derive(&mut input).is_err());
+    }
+
+    #[test]
+    fn validate_without_validation_route() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            struct Form {
+                #[html_form(validate = "validate_value")]
+                value: String,
+            }
+        "#,
+        )
+        .unwrap();
+
+        assert!(derive(&mut input).is_err());
+    }
+
+    #[test]
+    fn validation_route_without_validate() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            #[html_form(validation_route = "AppRoute::Validate")]
+            struct Form {
+                value: String,
+            }
+        "#,
+        )
+        .unwrap();
+
+        assert!(derive(&mut input).is_err());
+    }
+}
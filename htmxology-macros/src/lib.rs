@@ -4,8 +4,10 @@ use syn::parse_macro_input;
 
 mod display_delegate;
 mod fragment;
+mod html_form;
 mod identity;
 mod named;
+mod preserved;
 mod route;
 mod routing_controller;
 mod utils;
@@ -13,7 +15,149 @@ mod utils;
 /// Derive a route type.
 ///
 /// Route types are enum types that represent the possible routes in an HTMX application.
-#[proc_macro_derive(Route, attributes(route, subroute, catch_all, query, body))]
+///
+/// Route variants with named fields can be annotated with `#[builder]` to generate a
+/// builder-style constructor: `MyRoute::search().query_with(|q| q.page = 2).build()`. Path
+/// parameters and subroutes are arguments of the constructor, while `#[query]` and `#[body]`
+/// fields default to `Default::default()`.
+///
+/// `#[catch_all]` variants can be scoped to a host with `#[host("admin.{base}")]`, where `{base}`
+/// stands for the host of the server base URL. Host-scoped variants are matched first when
+/// parsing requests, but can't be parsed from a path with `FromStr` or `Route::from_uri`. They
+/// are displayed as a path, which is only a valid link on their host: `Route::host()` returns
+/// their host pattern, and `Route::to_absolute_url` links to them from other hosts.
+///
+/// Path parameters can be constrained, so that invalid values don't match the route at all and
+/// variants can safely overlap on the same path shape:
+///
+/// - `#[route("items/{id:u32}")]` only matches integers of the specified type, and
+///   `#[route("items/{id:u32:1..}")]` only matches integers within a range (`a..b`, `a..=b`, `a..`
+///   or `..b`).
+/// - `#[param(id, regex = "[0-9a-f]{8}")]` only matches values matching the whole regex.
+///
+/// Constrained parameters are matched before unconstrained ones on the same path shape.
+///
+/// Path parameters are percent-encoded by the generated `Display` implementation, so that values
+/// containing `/`, spaces or `#` still produce valid URLs, and percent-decoded when parsed.
+/// Paths whose parameters contain invalid escapes, like `%ZZ` or `%FF` (which is not UTF-8), don't
+/// match the route, just like unknown paths. Empty values can't round-trip: `/users/{name}` with an
+/// empty name formats as `/users/`, which doesn't match the route. `#[param(slug, encoded)]` declares the values of a parameter as already encoded, so that they
+/// are formatted and parsed verbatim. Query strings are always encoded by `serde_html_form`.
+///
+/// A last `{*rest}` path parameter captures the remaining path, slashes included, like in
+/// `#[route("files/{*path}")]`. The remaining path is captured as a `String`, or as a
+/// `Vec<String>` of its segments, through the `RestPath` trait. Rest parameters are matched after
+/// the other parameters on the same path shape.
+///
+/// Subroute prefixes are matched before the other routes, which are matched with the most specific
+/// URLs first, so `users/new` is matched before `users/{id}`. Variants that can never be reached,
+/// like `users/new` after a `users/` subroute prefix, `users/{id}` after `users/{slug}`, or a
+/// second variant with the same URL and method, are compile errors pointing at both variants.
+///
+/// Simple and form variants can also match legacy URLs with `#[route_alias("old/{id}")]`, which
+/// can be repeated. Aliases must have the same path parameters as the route URL, in the same
+/// order, to which the `#[param(...)]` constraints of the variant also apply. They are matched
+/// when parsing requests, with `FromStr` and with `Route::from_uri`, while `Display` always
+/// produces the route URL. With `#[route_alias("old/{id}", redirect)]`,
+/// requests to the alias are answered with a permanent redirect to the route URL instead, through
+/// `RouteExt::as_permanent_redirect_response`.
+///
+/// `#[route("settings", method = "GET|POST")]` declares a form route, rendering a form on `GET`
+/// and submitting it on `POST`. Form routes require an `Option<...>` `#[body]` field, which is
+/// `None` for `GET` requests and decoded from the request body for `POST` requests, and their
+/// `method()` depends on whether the body is set. When the body is the only field of the variant,
+/// the route implements `From` the form, which `HtmlForm` types use as their action route.
+///
+/// `#[body]` fields are decoded from `application/x-www-form-urlencoded` bodies by default. With
+/// `#[body("multipart/form-data")]`, the field is an `htmxology::MultipartForm<T>`, holding the
+/// text fields of the body deserialized into `T` and its uploaded files, with their file name,
+/// content type and bytes. Multipart bodies are buffered in memory, within the body limit of the
+/// router. `#[body("application/json")]` fields are decoded with `axum::Json`, and serialized back
+/// by `Route::json_body` with `serde_json`, which the crate must depend on. Fields accepting
+/// several content types, with `#[body(any("application/json", "application/x-www-form-urlencoded"))]`
+/// or several `#[body]` attributes, are decoded according to the `Content-Type` of the request,
+/// and other content types are rejected with `415 Unsupported Media Type`.
+///
+/// `#[route("foods", target = "#food-list")]` declares the element a route is meant to be swapped
+/// into, which must be an id selector, as HTMX only sends the `id` of the target element in the
+/// `HX-Target` header. The target is returned by `Route::expected_target`, listed in the route
+/// descriptions, and inherited by the variants of subroutes without a target of their own. In
+/// debug builds, htmx requests targeting another element are reported by the sanity checks.
+///
+/// `GET` variants can be annotated with `#[coalesce]` to let concurrent identical requests share a
+/// single handler execution, through `Route::coalesce_requests` and a `CoalescingController`.
+///
+/// Fields of simple and form variants can be annotated with `#[extract]` to resolve them from
+/// the request with any `axum::extract::FromRequestParts` implementation, like
+/// `TypedHeader<Cookie>` or an authentication extractor: `Dashboard { #[extract] user: User }`.
+/// Extracted fields are not part of the URL of the route, which can't be parsed from a URL with
+/// `FromStr` or `Route::from_uri` anymore, and are arguments of `#[builder]` constructors. The
+/// rejections of the extractors are returned as responses.
+///
+/// Routes can link to in-page anchors with a fragment, appended to their URL by `Display` and
+/// returned by `Route::fragment`. The fragment is either static, like in
+/// `#[route("docs/{page}#install")]`, or a field annotated with `#[fragment_anchor]`, formatted
+/// with `Display` and percent-encoded, and omitted when empty or `None`. As browsers don't send
+/// fragments to servers, they are ignored when parsing routes, and `#[fragment_anchor]` fields are
+/// `Default::default()`. Fragments can't be declared on subroute, catch-all or included variants,
+/// whose fragment is the one of the route they delegate to.
+///
+/// Large route types can be split across modules with `#[route_include]` variants, holding a route
+/// type whose variants are matched as if they were declared in place, without a prefix:
+/// `#[route_include] Blog(blog::BlogRoute)`. Their routes are ordered by specificity together
+/// with the routes declared directly in the enum, exactly like in-place declarations: an included
+/// `about` route wins over a `{slug}` route of the enum, and a route of the enum wins over an
+/// identical included route. Only the catch-all and host variants of included route types are
+/// tried after the routes of the enum, before its catch-all variant. Included routes are listed in
+/// place in the route table.
+///
+/// As included route types are only known at runtime, their routes are not checked for conflicts
+/// at compile time. Instead, in debug builds, `Route::route_table` (which is called when building
+/// a `ControllerRouter`) panics if a route can never be reached because of a route from another
+/// route type, like a `{slug}` route of the enum and a `{tag}` route of an included route type.
+///
+/// `Route::describe` lists the routes with their parameters, their Rust types and the doc comments
+/// of their variants, in route table order. Variants of subroutes without doc comments inherit
+/// the doc comments of their parent variant. The descriptions can be converted into an OpenAPI 3
+/// document with the `openapi` feature of `htmxology`.
+///
+/// Large route types can reduce the size of the generated code with `#[route_opts(...)]` on the
+/// enum:
+///
+/// - `no_from_str` implements `FromStr` through `Route::from_uri` with the `GET` method, instead
+///   of dedicated parsing code and regexes.
+/// - `no_display` implements `Display` through `Route::path` and `Route::query`, instead of
+///   dedicated formatting code.
+///
+/// These implementations are slightly slower, as they go through an extra URI parse or allocation.
+///
+/// They mostly save compile time in release builds, on route types with many variants: debug
+/// build times barely change. They don't make binaries smaller, as `Route::from_uri` keeps its own
+/// regexes, and its more general parsing code is a bit larger than the one `no_from_str` skips.
+///
+/// `#[route_opts(case_insensitive)]` matches the literal segments of the route URLs regardless of
+/// their case, so that `/About` and `/ABOUT` both parse as `#[route("about")]`. Parameters keep
+/// their casing, and `Display` always uses the casing of the route URLs. The option only applies
+/// to the URLs of the enum: subroutes need their own `#[route_opts(case_insensitive)]`.
+#[proc_macro_derive(
+    Route,
+    attributes(
+        route,
+        subroute,
+        catch_all,
+        route_include,
+        query,
+        body,
+        extract,
+        fragment_anchor,
+        builder,
+        coalesce,
+        host,
+        param,
+        route_opts,
+        route_alias
+    )
+)]
 pub fn derive_route(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let mut input = parse_macro_input!(input as syn::DeriveInput);
 
@@ -42,22 +186,36 @@ pub fn derive_display_delegate(input: proc_macro::TokenStream) -> proc_macro::To
 ///
 /// # Attributes
 ///
-/// - `#[controller(RouteType, args = ArgsType, pre_handler = "function", extra_derives = (Trait1, Trait2))]` - Specifies the route enum type and optional configuration:
+/// - `#[controller(RouteType, args = ArgsType, pre_handler = "function", post_handle = "function", extra_derives = (Trait1, Trait2))]` - Specifies the route enum type and optional configuration:
 ///   - `RouteType` - The route enum type for this controller (required)
 ///   - `args = ArgsType` - The Args type passed to handle_request (optional, defaults to `()`)
-///   - `pre_handler = "function"` - Async function called before routing (optional)
+///   - `pre_handler = "function"` - Async function called before routing (optional), also
+///     accepted as `pre_handle = "function"`
 ///     - Signature: `async fn(&self, &Route, &htmx::Request, &http::request::Parts, &ServerInfo, &mut Args) -> Option<Response>`
 ///     - Returns `Some(response)` to short-circuit routing and return immediately
 ///     - Returns `None` to proceed with normal routing
 ///     - Use case: Authentication, rate limiting, request validation
+///   - `post_handle = "function"` - Async function transforming the response of the subcontrollers (optional)
+///     - Signature: `async fn(&self, &htmx::Request, &http::request::Parts, &ServerInfo, Response) -> Response`
+///     - Called with the converted response of every subcontroller, but not with the responses
+///       returned by the pre-handler
+///     - Use case: Wrapping fragments in a layout, appending debug panels, adding headers
 ///   - `extra_derives = (Trait1, Trait2, ...)` - Additional derive traits for the generated route enum (optional)
 ///     - Useful for adding `PartialEq`, `Eq`, `Hash`, `Serialize`, `Deserialize`, etc.
 /// - `#[subcontroller(...)]` - Defines a subcontroller with the following options:
 ///   - `route = VariantName` - The route variant name (required)
 ///   - `path = "path/"` - URL path for this subcontroller (optional)
+///   - `host = "admin.{base}"` - Host this subcontroller is mounted on, instead of a path (optional)
+///     - `{base}` stands for the host of the server base URL
+///     - Cannot be combined with `path` or `params`
 ///   - `params(name: Type, ...)` - Path parameters to extract (optional)
 ///   - `convert_with = "function"` - Custom function to create the subcontroller (optional)
 ///   - `convert_response = "function"` - Custom function to convert the subcontroller's response (optional)
+///   - `error_boundary = "function"` - Custom function rendering the errors of the subcontroller (optional)
+///     - Signature: `fn(&self, &htmx::Request, &http::request::Parts, &ServerInfo, Error) -> Response`
+///     - Called instead of the response conversion when the subcontroller returns `Err(error)`
+///     - Use case: Rendering an inline error fragment in the section's target, while the rest of
+///       the page remains functional
 ///   - `doc = "description"` - Documentation for the route variant (optional)
 ///
 /// # Response Type Conversion
@@ -78,6 +236,36 @@ pub fn derive_display_delegate(input: proc_macro::TokenStream) -> proc_macro::To
 /// )]
 /// ```
 ///
+/// # Error Boundaries
+///
+/// The `error_boundary` attribute isolates the failures of a subcontroller, like component error
+/// boundaries in SPA frameworks. As htmx doesn't swap error responses by default, the rendered
+/// fragment should typically be returned with a success status:
+///
+/// ```ignore
+/// #[subcontroller(
+///     SidebarController,
+///     route = Sidebar,
+///     path = "sidebar/",
+///     error_boundary = "Self::render_section_error"
+/// )]
+/// struct MainController { /* ... */ }
+///
+/// impl MainController {
+///     fn render_section_error(
+///         &self,
+///         htmx: &htmx::Request,
+///         parts: &http::request::Parts,
+///         server_info: &ServerInfo,
+///         err: axum::response::Response,
+///     ) -> Result<axum::response::Response, axum::response::Response> {
+///         tracing::error!("Sidebar failed with status {}", err.status());
+///
+///         Ok(Html(r#"<p class="error">This section is unavailable.</p>"#).into_response())
+///     }
+/// }
+/// ```
+///
 /// # Example
 ///
 /// ```ignore
@@ -229,6 +417,161 @@ pub fn derive_named(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
         .into()
 }
 
+/// Derive the `Preserved` trait for a type.
+///
+/// This macro implements the `Preserved` trait, which marks an element that must survive swaps
+/// with `hx-preserve`, like a video player or a text area being edited. Templates render its
+/// `id` and `hx-preserve` attributes with `preserve_attributes()`.
+///
+/// Note: The type must also implement `Identity` (either manually or via derive), with an `id`
+/// that is stable across renders.
+///
+/// # Examples
+///
+/// ```ignore
+/// use htmxology::htmx::{Identity, Preserved};
+///
+/// #[derive(Identity, Preserved)]
+/// #[identity(id = "video-player")]
+/// struct VideoPlayer {
+///     src: String,
+/// }
+/// ```
+///
+/// With a template rendering:
+///
+/// ```html
+/// <video {{ preserve_attributes()|safe }} src="{{ src }}" controls></video>
+/// ```
+#[proc_macro_derive(Preserved)]
+pub fn derive_preserved(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let mut input = parse_macro_input!(input as syn::DeriveInput);
+
+    preserved::derive(&mut input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive form helpers for a struct of form fields.
+///
+/// This macro generates, for each field, a `<field>_input` method returning the
+/// `htmx::FormInput` of the field, and an `inputs` method returning all of them in field order.
+/// `FormInput` implements `Named` and renders as an `<input>` element whose `name` matches the
+/// serde name of the field, so that the struct itself can be used as a `#[body]` field of a
+/// `Route` when it also derives `serde::Deserialize`.
+///
+/// The input type is inferred from the type of the field: `bool` fields are checkboxes, numeric
+/// fields are `number` inputs, `SubmissionToken` fields are `hidden` inputs, and other fields are
+/// `text` inputs whose value is rendered with `Display`. `Option<T>` fields render no value when
+/// `None`.
+///
+/// As browsers don't submit unchecked checkboxes, `bool` fields should be annotated with
+/// `#[serde(default)]`.
+///
+/// # Attributes
+///
+/// - `#[html_form(route = RouteType, action = "Self::action", submit = "Label")]` on the struct
+///   (optional) - Implements the `HtmlForm` trait, with `action` returning the route handling
+///   the submission, and `Display` as a complete `<form>` element, with a submit button if
+///   `submit` is specified. Without `action`, a clone of the form is converted into the route,
+///   which `GET|POST` form routes support.
+/// - `#[html_form(validation_route = "AppRoute::ValidateSignup")]` on the struct (optional) -
+///   Generates a `{Form}Validation` route enum, with a `POST` route per validated field named
+///   after it (like `email`), posted the values of the form. Its `validate()` method parses the
+///   field and returns its input with the validation result. The function converts the routes
+///   into routes of the application, typically a variant with a `#[subroute]`.
+/// - `#[html_form(...)]` on fields (optional):
+///   - `name = "..."` - The name of the input, instead of the serde name of the field.
+///   - `input_type = "..."` - The type of the input, like `email` or `password`.
+///   - `label = "..."` - A label, wrapping the input.
+///   - `required` - Adds the `required` attribute to the input.
+///   - `skip` - Generates no input for the field.
+///   - `validate = "path"` - A `fn(&FieldType) -> Result<(), String>` validating the field
+///     (`&str` for `String` fields).
+///     Requires `validation_route`: the input is wrapped in an element re-rendered from its
+///     validation route whenever it changes, showing its error or success state.
+///
+/// `#[serde(rename_all = "...")]` is not supported.
+///
+/// # Example
+///
+/// ```ignore
+/// use htmxology::HtmlForm;
+///
+/// #[derive(Debug, Clone, serde::Deserialize, HtmlForm)]
+/// #[html_form(route = AppRoute, action = "Self::action", submit = "Sign in")]
+/// struct LoginForm {
+///     #[html_form(input_type = "email", label = "E-mail", required)]
+///     email: String,
+///     #[html_form(input_type = "password", label = "Password", required)]
+///     password: String,
+///     #[serde(default)]
+///     remember_me: bool,
+/// }
+///
+/// impl LoginForm {
+///     fn action(&self) -> AppRoute {
+///         AppRoute::Login(self.clone())
+///     }
+/// }
+/// ```
+///
+/// With a `GET|POST` form route, the action is derived from the route:
+///
+/// ```ignore
+/// #[derive(Debug, Clone, Route)]
+/// enum AppRoute {
+///     #[route("settings", method = "GET|POST")]
+///     Settings {
+///         #[body]
+///         form: Option<SettingsForm>,
+///     },
+/// }
+///
+/// #[derive(Debug, Clone, serde::Deserialize, HtmlForm)]
+/// #[html_form(route = AppRoute, submit = "Save")]
+/// struct SettingsForm {
+///     theme: String,
+/// }
+/// ```
+///
+/// With per-field validation:
+///
+/// ```ignore
+/// #[derive(Debug, Clone, serde::Deserialize, HtmlForm)]
+/// #[html_form(validation_route = "AppRoute::ValidateSignup")]
+/// pub struct SignupForm {
+///     #[html_form(input_type = "email", validate = "validate_email")]
+///     email: String,
+/// }
+///
+/// #[derive(Debug, Clone, Route)]
+/// enum AppRoute {
+///     // Routes `POST /signup/validate/email` to `SignupFormValidation::Email`.
+///     #[route("signup/validate/")]
+///     ValidateSignup(#[subroute] SignupFormValidation),
+/// }
+///
+/// // In the controller, the validated field is swapped in place of the input.
+/// AppRoute::ValidateSignup(route) => Ok(Html(route.validate().to_string()).into_response()),
+/// ```
+///
+/// Without `route`, the inputs can be rendered in templates:
+///
+/// ```html
+/// <form hx-post="/login">
+///   {% for input in form.inputs() %}{{ input|safe }}{% endfor %}
+/// </form>
+/// ```
+#[proc_macro_derive(HtmlForm, attributes(html_form))]
+pub fn derive_html_form(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let mut input = parse_macro_input!(input as syn::DeriveInput);
+
+    html_form::derive(&mut input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
 /// Derive the `Fragment` trait for a type.
 ///
 /// This macro implements the `Fragment` trait, which extends `Identity` and specifies
@@ -274,6 +617,24 @@ pub fn derive_named(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 /// }
 /// ```
 ///
+/// Rendering the `hx-swap-oob` attribute from the template, for templates declaring the `id` of
+/// their root element with `id_attribute()`:
+///
+/// ```ignore
+/// use htmxology::htmx::{Identity, Fragment};
+///
+/// #[derive(Identity, Fragment)]
+/// #[identity("counter")]
+/// #[fragment(strategy = "innerHTML", oob_render)]
+/// struct Counter {
+///     value: u32,
+/// }
+/// ```
+///
+/// The `oob_render` option implements `Fragment::oob_render()`, so that out-of-band swaps of the
+/// fragment declare `hx-swap-oob` next to its `id` attribute at render time, instead of having
+/// the attribute injected in the rendered HTML.
+///
 /// # Supported strategies
 ///
 /// The macro accepts HTMX-standard strategy strings:
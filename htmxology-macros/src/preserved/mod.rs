@@ -0,0 +1,52 @@
+//! Preserved derive macro.
+
+use quote::quote;
+
+pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let root_ident = &input.ident;
+    let root_generics_params = &input.generics.params;
+    let root_param_idents = crate::utils::extract_generic_param_idents(&input.generics.params);
+    let root_where_clause = &input.generics.where_clause;
+
+    Ok(quote! {
+        impl<#root_generics_params> htmxology::htmx::Preserved for #root_ident<#root_param_idents>
+            #root_where_clause
+        {
+        }
+    })
+}
+
+#[cfg(test)]
+mod snapshot_tests {
+    use super::*;
+    use crate::utils::testing::test_derive;
+    use insta::assert_snapshot;
+
+    fn test_preserved(input: &str) -> String {
+        test_derive(input, derive)
+    }
+
+    #[test]
+    fn simple_struct() {
+        let input = r#"
+            #[identity(id = "video-player")]
+            struct VideoPlayer {
+                src: String,
+            }
+        "#;
+        assert_snapshot!(test_preserved(input));
+    }
+
+    #[test]
+    fn struct_with_lifetime_and_bounds() {
+        let input = r#"
+            struct Editor<'a, T: Display>
+            where
+                T: Clone,
+            {
+                draft: &'a T,
+            }
+        "#;
+        assert_snapshot!(test_preserved(input));
+    }
+}
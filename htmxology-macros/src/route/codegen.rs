@@ -4,7 +4,10 @@
 //! By extracting these helpers, we eliminate duplication between Named and Unnamed
 //! field handling and make the code easier to maintain.
 
-use super::config::{FieldRole, FieldsConfig, VariantConfig};
+use super::config::{
+    FieldConfig, FieldRole, FieldsConfig, JSON_BODY_CONTENT_TYPE, MULTIPART_BODY_CONTENT_TYPE,
+    VariantConfig,
+};
 use super::route_type::{MethodExt, RouteType, append_query_arg, to_block};
 use proc_macro2::TokenStream;
 use quote::{quote, quote_spanned};
@@ -55,13 +58,13 @@ pub fn generate_pattern(config: &VariantConfig, mode: PatternMode) -> TokenStrea
             quote! { Self::#ident {} }
         }
         (FieldsConfig::Named(fields), PatternMode::Simple) => {
-            // Include all fields in the pattern, but use _ for body fields
+            // Include all fields in the pattern, but use _ for body and extracted fields
             // since they don't appear in URLs
             let bindings: Vec<_> = fields
                 .iter()
                 .map(|f| {
                     let ident = &f.ident;
-                    if f.is_body() {
+                    if f.is_body() || f.is_extract() {
                         quote! { #ident: _ }
                     } else {
                         quote! { #ident }
@@ -83,12 +86,12 @@ pub fn generate_pattern(config: &VariantConfig, mode: PatternMode) -> TokenStrea
             quote! { Self::#ident() }
         }
         (FieldsConfig::Unnamed(fields), PatternMode::Simple) => {
-            // Include all fields in the pattern, but use _ for body fields
+            // Include all fields in the pattern, but use _ for body and extracted fields
             // since they don't appear in URLs
             let bindings: Vec<_> = fields
                 .iter()
                 .map(|f| {
-                    if f.is_body() {
+                    if f.is_body() || f.is_extract() {
                         quote! { _ }
                     } else {
                         let ident = &f.ident;
@@ -110,7 +113,8 @@ pub fn generate_pattern(config: &VariantConfig, mode: PatternMode) -> TokenStrea
 
 /// Generates the URL formatting code for the Display implementation.
 ///
-/// This creates the code that formats the route URL with path and query parameters.
+/// This creates the code that formats the route URL with path and, if `with_query` is set, query
+/// parameters.
 ///
 /// # Example Output
 ///
@@ -127,7 +131,7 @@ pub fn generate_pattern(config: &VariantConfig, mode: PatternMode) -> TokenStrea
 ///     }
 /// }
 /// ```
-pub fn generate_url_format(config: &VariantConfig) -> syn::Result<TokenStream> {
+pub fn generate_url_format(config: &VariantConfig, with_query: bool) -> syn::Result<TokenStream> {
     // Collect path parameters
     let path_params: Vec<_> = config.fields.iter().filter(|f| f.is_path_param()).collect();
 
@@ -163,16 +167,54 @@ pub fn generate_url_format(config: &VariantConfig) -> syn::Result<TokenStream> {
     };
 
     // Add query parameter formatting if present
-    if let Some(query_field) = config.query_param() {
+    if with_query && let Some(query_field) = config.query_param() {
         append_query_arg(&mut statements, Some(&query_field.ident));
     }
 
+    // Add fragment formatting if present
+    if with_query && let Some(fragment) = &config.fragment {
+        let fragment = format!("#{fragment}");
+
+        statements.push(quote! { f.write_str(#fragment)?; });
+    } else if with_query && let Some(fragment) = generate_fragment(config) {
+        statements.push(quote! {
+            if let Some(__fragment) = #fragment {
+                write!(f, "#{__fragment}")?;
+            }
+        });
+    }
+
     Ok(to_block(statements))
 }
 
+/// Generates the expression of the fragment of a variant, as an `Option<String>`, if it has one.
+///
+/// # Example Output
+///
+/// ```ignore
+/// section.as_ref().and_then(|__value| htmxology::format_fragment(__value))
+/// ```
+fn generate_fragment(config: &VariantConfig) -> Option<TokenStream> {
+    if let Some(fragment) = &config.fragment {
+        return Some(quote! { Some(String::from(#fragment)) });
+    }
+
+    let fragment_field = config.fragment_param()?;
+    let ident = &fragment_field.ident;
+
+    Some(
+        if crate::utils::option_inner_type(&fragment_field.ty).is_some() {
+            quote! { #ident.as_ref().and_then(|__value| htmxology::format_fragment(__value)) }
+        } else {
+            quote! { htmxology::format_fragment(#ident) }
+        },
+    )
+}
+
 /// Generates the request parsing code for the FromRequest implementation.
 ///
-/// This creates the code that extracts path, query, and body parameters from the request.
+/// This creates the code that extracts path, query, extracted, and body parameters from the
+/// request.
 ///
 /// # Example Output
 ///
@@ -190,18 +232,26 @@ pub fn generate_url_format(config: &VariantConfig) -> syn::Result<TokenStream> {
 pub fn generate_request_parsing(config: &VariantConfig) -> TokenStream {
     let path_parse = generate_path_parsing(config);
     let query_parse = generate_query_parsing(config);
+    let extract_parse = generate_extract_parsing(config);
     let body_parse = generate_body_parsing(config);
+    let fragment_parse = generate_fragment_parsing(config);
     let construction = generate_variant_construction(config);
 
     // Check if we need any parsing - if not, just return the construction directly
-    let has_parsing = !path_parse.is_empty() || !query_parse.is_empty() || !body_parse.is_empty();
+    let has_parsing = !path_parse.is_empty()
+        || !query_parse.is_empty()
+        || !extract_parse.is_empty()
+        || !body_parse.is_empty()
+        || !fragment_parse.is_empty();
 
     if has_parsing {
         quote! {
             {
                 #path_parse
                 #query_parse
+                #extract_parse
                 #body_parse
+                #fragment_parse
                 #construction
             }
         }
@@ -210,6 +260,67 @@ pub fn generate_request_parsing(config: &VariantConfig) -> TokenStream {
     }
 }
 
+/// Generates the request parsing code of a form route, for a `GET` or a `POST` request.
+///
+/// The body field is `None` for `GET` requests, and decoded from the request body for `POST`
+/// requests.
+pub fn generate_form_request_parsing(config: &VariantConfig, submission: bool) -> TokenStream {
+    let path_parse = generate_path_parsing(config);
+    let query_parse = generate_query_parsing(config);
+    let extract_parse = generate_extract_parsing(config);
+    let body_parse = if submission {
+        let body_parse = generate_body_parsing(config);
+        let ident = &config
+            .body_param()
+            .expect("form routes should have a body field")
+            .ident;
+
+        quote! {
+            #body_parse
+            let #ident = Some(#ident);
+        }
+    } else {
+        generate_empty_form_body(config)
+    };
+    let fragment_parse = generate_fragment_parsing(config);
+    let construction = generate_variant_construction(config);
+
+    quote! {
+        {
+            #path_parse
+            #query_parse
+            #extract_parse
+            #body_parse
+            #fragment_parse
+            #construction
+        }
+    }
+}
+
+/// Generates the declaration of the empty body of a form route.
+fn generate_empty_form_body(config: &VariantConfig) -> TokenStream {
+    match (&config.route_type, config.body_param()) {
+        (RouteType::Form, Some(body_field)) => {
+            let ident = &body_field.ident;
+
+            quote! { let #ident = None; }
+        }
+        _ => quote!(),
+    }
+}
+
+/// Generates the declaration of the fragment anchor field, which is never sent to servers.
+fn generate_fragment_parsing(config: &VariantConfig) -> TokenStream {
+    match config.fragment_param() {
+        Some(fragment_field) => {
+            let ident = &fragment_field.ident;
+
+            quote! { let #ident = Default::default(); }
+        }
+        None => quote!(),
+    }
+}
+
 /// Generates path parameter parsing code.
 fn generate_path_parsing(config: &VariantConfig) -> TokenStream {
     let path_params: Vec<_> = config.fields.iter().filter(|f| f.is_path_param()).collect();
@@ -218,14 +329,26 @@ fn generate_path_parsing(config: &VariantConfig) -> TokenStream {
         return quote!();
     }
 
+    let rest_ident = config.rest_param().map(|field| &field.ident);
+    let decode_fn = |field: &FieldConfig| {
+        if Some(&field.ident) == rest_ident {
+            quote! { htmxology::decode_rest_path_argument }
+        } else if config.is_encoded_path_param(field) {
+            quote! { htmxology::decode_encoded_path_argument }
+        } else {
+            quote! { htmxology::decode_path_argument }
+        }
+    };
+
     if config.fields.is_named() {
         // Named fields: use parameter names
         let parse_stmts: Vec<_> = path_params
             .iter()
             .map(|field| {
                 let ident = &field.ident;
+                let decode_fn = decode_fn(field);
                 quote! {
-                    let #ident = htmxology::decode_path_argument(
+                    let #ident = #decode_fn(
                         stringify!(#ident),
                         &__captures[stringify!(#ident)]
                     )?;
@@ -241,9 +364,10 @@ fn generate_path_parsing(config: &VariantConfig) -> TokenStream {
             .enumerate()
             .map(|(i, field)| {
                 let ident = &field.ident;
+                let decode_fn = decode_fn(field);
                 let idx = i + 1; // Regex capture groups are 1-indexed
                 quote! {
-                    let #ident = htmxology::decode_path_argument(
+                    let #ident = #decode_fn(
                         stringify!(#ident),
                         &__captures[#idx]
                     )?;
@@ -274,17 +398,65 @@ fn generate_query_parsing(config: &VariantConfig) -> TokenStream {
     }
 }
 
+/// Generates the parsing code of the fields extracted from the request parts.
+///
+/// The rejections of the extractors are returned as responses, as for query parameters.
+fn generate_extract_parsing(config: &VariantConfig) -> TokenStream {
+    let extract_stmts: Vec<_> = config
+        .extract_params()
+        .map(|field| {
+            let ident = &field.ident;
+            let ty = &field.ty;
+
+            quote! {
+                let #ident = <#ty as axum::extract::FromRequestParts<S>>::from_request_parts(
+                    &mut __parts,
+                    __state
+                )
+                .await
+                .map_err(|err| err.into_response())?;
+            }
+        })
+        .collect();
+
+    if extract_stmts.is_empty() {
+        return quote!();
+    }
+
+    quote! {
+        let (mut __parts, __body) = __req.into_parts();
+        #(#extract_stmts)*
+        let __req = http::Request::from_parts(__parts, __body);
+    }
+}
+
 /// Generates body parameter parsing code.
 fn generate_body_parsing(config: &VariantConfig) -> TokenStream {
     if let Some(body_field) = config.body_param() {
         let ident = &body_field.ident;
-        quote! {
-            let axum_extra::extract::Form(#ident) = axum_extra::extract::Form::from_request(
-                __req,
-                __state
-            )
-            .await
-            .map_err(|err| err.into_response())?;
+        let FieldRole::Body { content_types } = &body_field.role else {
+            unreachable!("body field should have a body role");
+        };
+
+        let [content_type] = content_types.as_slice() else {
+            return quote! {
+                let #ident =
+                    htmxology::decode_negotiated_body(__req, __state, &[#(#content_types),*]).await?;
+            };
+        };
+
+        if content_type == MULTIPART_BODY_CONTENT_TYPE {
+            quote! {
+                let #ident = htmxology::decode_multipart_body(__req, __state).await?;
+            }
+        } else if content_type == JSON_BODY_CONTENT_TYPE {
+            quote! {
+                let #ident = htmxology::decode_json_body(__req, __state).await?;
+            }
+        } else {
+            quote! {
+                let #ident = htmxology::decode_form_body(__req, __state, #content_type).await?;
+            }
         }
     } else {
         quote!()
@@ -321,8 +493,39 @@ pub fn generate_method_match(config: &VariantConfig) -> TokenStream {
 
     match &config.route_type {
         RouteType::Simple { method } => {
-            let method_ident = method.to_ident();
-            quote_spanned! { span => #pattern => http::Method::#method_ident }
+            let method = method.to_expr();
+            quote_spanned! { span => #pattern => #method }
+        }
+        RouteType::Form => {
+            // Form routes are submitted with `POST` when they carry a body
+            let body_field = config
+                .body_param()
+                .expect("form routes should have a body field");
+            let body_ident = &body_field.ident;
+            let ident = &config.ident;
+            let pattern = match &config.fields {
+                FieldsConfig::Unnamed(fields) => {
+                    let pattern_args: Vec<_> = fields
+                        .iter()
+                        .map(|f| {
+                            if f.is_body() {
+                                quote! { #body_ident }
+                            } else {
+                                quote! { _ }
+                            }
+                        })
+                        .collect();
+
+                    quote! { Self::#ident(#(#pattern_args),*) }
+                }
+                _ => quote! { Self::#ident { #body_ident, .. } },
+            };
+
+            quote_spanned! { span => #pattern => if #body_ident.is_some() {
+                http::Method::POST
+            } else {
+                http::Method::GET
+            } }
         }
         RouteType::SubRoute => {
             // For subroutes, delegate to the subroute's method
@@ -363,16 +566,325 @@ pub fn generate_method_match(config: &VariantConfig) -> TokenStream {
                 quote_spanned! { span => #pattern => http::Method::GET }
             }
         }
-        RouteType::CatchAll => {
-            // For catch-all, extract the inner route and delegate
+        RouteType::CatchAll | RouteType::Include => {
+            // For catch-all and included routes, extract the inner route and delegate
             let ident = &config.ident;
             quote_spanned! { span => Self::#ident(catch_all) => catch_all.method() }
         }
     }
 }
 
+/// Generates a match pattern binding a single field of a variant, and ignoring the others.
+fn generate_field_pattern(config: &VariantConfig, field: &FieldConfig) -> TokenStream {
+    let ident = &config.ident;
+    let field_ident = &field.ident;
+
+    match &config.fields {
+        FieldsConfig::Unnamed(fields) => {
+            let pattern_args = fields.iter().map(|f| {
+                if f.ident == *field_ident {
+                    quote! { #field_ident }
+                } else {
+                    quote! { _ }
+                }
+            });
+
+            quote! { Self::#ident(#(#pattern_args),*) }
+        }
+        _ => quote! { Self::#ident { #field_ident, .. } },
+    }
+}
+
+/// Generates the `coalesce_requests` method of the `Route` implementation.
+///
+/// The method is only generated when a variant is annotated with `#[coalesce]`, or delegates to
+/// another route type which may have such variants.
+pub fn generate_coalesce_requests(configs: &[VariantConfig]) -> TokenStream {
+    let mut arms = Vec::new();
+
+    for config in configs {
+        let ident = &config.ident;
+
+        match &config.route_type {
+            RouteType::SubRoute => {
+                let Some(subroute_field) = config.subroute_param() else {
+                    continue;
+                };
+                let subroute_ident = &subroute_field.ident;
+                let pattern = generate_field_pattern(config, subroute_field);
+
+                arms.push(quote! { #pattern => #subroute_ident.coalesce_requests() });
+            }
+            RouteType::CatchAll | RouteType::Include => {
+                arms.push(quote! { Self::#ident(catch_all) => catch_all.coalesce_requests() });
+            }
+            _ if config.coalesce => {
+                let pattern = generate_pattern(config, PatternMode::WithWildcard);
+
+                arms.push(quote! { #pattern => true });
+            }
+            _ => {}
+        }
+    }
+
+    if arms.is_empty() {
+        return TokenStream::new();
+    }
+
+    if arms.len() < configs.len() {
+        arms.push(quote! { _ => false });
+    }
+
+    quote! {
+        fn coalesce_requests(&self) -> bool {
+            match self {
+                #(#arms),*
+            }
+        }
+    }
+}
+
+/// Generates the `json_body` method of the `Route` implementation.
+///
+/// The method is only generated when a variant has a `#[body("application/json")]` field, or
+/// delegates to another route type which may have such variants.
+pub fn generate_json_body(configs: &[VariantConfig]) -> TokenStream {
+    let mut arms = Vec::new();
+
+    for config in configs {
+        let ident = &config.ident;
+
+        match &config.route_type {
+            RouteType::SubRoute => {
+                let Some(subroute_field) = config.subroute_param() else {
+                    continue;
+                };
+                let subroute_ident = &subroute_field.ident;
+                let pattern = generate_field_pattern(config, subroute_field);
+
+                arms.push(quote! { #pattern => #subroute_ident.json_body() });
+            }
+            RouteType::CatchAll | RouteType::Include => {
+                arms.push(quote! { Self::#ident(catch_all) => catch_all.json_body() });
+            }
+            route_type => {
+                let Some(body_field) = config.body_param() else {
+                    continue;
+                };
+                let FieldRole::Body { content_types } = &body_field.role else {
+                    unreachable!("body field should have a body role");
+                };
+
+                if !content_types
+                    .iter()
+                    .any(|content_type| content_type == JSON_BODY_CONTENT_TYPE)
+                {
+                    continue;
+                }
+
+                let body_ident = &body_field.ident;
+                let pattern = generate_field_pattern(config, body_field);
+                let json = if matches!(route_type, RouteType::Form) {
+                    quote! { #body_ident.as_ref().and_then(|body| serde_json::to_string(body).ok()) }
+                } else {
+                    quote! { serde_json::to_string(#body_ident).ok() }
+                };
+
+                arms.push(quote! { #pattern => #json });
+            }
+        }
+    }
+
+    if arms.is_empty() {
+        return TokenStream::new();
+    }
+
+    if arms.len() < configs.len() {
+        arms.push(quote! { _ => None });
+    }
+
+    quote! {
+        fn json_body(&self) -> Option<String> {
+            match self {
+                #(#arms),*
+            }
+        }
+    }
+}
+
+/// Generates the `expected_target` method of the `Route` implementation.
+///
+/// The method is only generated when a variant has a `target`, or delegates to another route type
+/// which may have such variants. Subroutes without a target of their own inherit the target of
+/// their parent variant.
+pub fn generate_expected_target(configs: &[VariantConfig]) -> TokenStream {
+    let mut arms = Vec::new();
+
+    for config in configs {
+        let ident = &config.ident;
+        let fallback = match &config.target {
+            Some(target) => quote! { .or(Some(#target)) },
+            None => TokenStream::new(),
+        };
+
+        match &config.route_type {
+            RouteType::SubRoute => {
+                let Some(subroute_field) = config.subroute_param() else {
+                    continue;
+                };
+                let subroute_ident = &subroute_field.ident;
+                let pattern = generate_field_pattern(config, subroute_field);
+
+                arms.push(quote! { #pattern => #subroute_ident.expected_target() #fallback });
+            }
+            RouteType::CatchAll | RouteType::Include => {
+                arms.push(quote! { Self::#ident(catch_all) => catch_all.expected_target() });
+            }
+            _ => {
+                let Some(target) = &config.target else {
+                    continue;
+                };
+                let pattern = generate_pattern(config, PatternMode::WithWildcard);
+
+                arms.push(quote! { #pattern => Some(#target) });
+            }
+        }
+    }
+
+    if arms.is_empty() {
+        return TokenStream::new();
+    }
+
+    if arms.len() < configs.len() {
+        arms.push(quote! { _ => None });
+    }
+
+    quote! {
+        fn expected_target(&self) -> Option<&'static str> {
+            match self {
+                #(#arms),*
+            }
+        }
+    }
+}
+
+/// Generates the `host` method of the `Route` implementation.
+///
+/// The method is only generated when a variant is scoped to a host, or delegates to another route
+/// type which may have such variants.
+pub fn generate_host(configs: &[VariantConfig]) -> TokenStream {
+    let mut arms = Vec::new();
+
+    for config in configs {
+        let ident = &config.ident;
+
+        match (&config.route_type, &config.host) {
+            (RouteType::CatchAll, Some(host)) => {
+                arms.push(quote! { Self::#ident(..) => Some(#host) });
+            }
+            (RouteType::SubRoute, _) => {
+                let Some(subroute_field) = config.subroute_param() else {
+                    continue;
+                };
+                let subroute_ident = &subroute_field.ident;
+                let pattern = generate_field_pattern(config, subroute_field);
+
+                arms.push(quote! { #pattern => #subroute_ident.host() });
+            }
+            (RouteType::CatchAll | RouteType::Include, None) => {
+                arms.push(quote! { Self::#ident(catch_all) => catch_all.host() });
+            }
+            _ => {}
+        }
+    }
+
+    if arms.is_empty() {
+        return TokenStream::new();
+    }
+
+    if arms.len() < configs.len() {
+        arms.push(quote! { _ => None });
+    }
+
+    quote! {
+        fn host(&self) -> Option<&'static str> {
+            match self {
+                #(#arms),*
+            }
+        }
+    }
+}
+
+/// Generates the `variant_name` method, naming variants as in the route table.
+///
+/// # Example Output
+///
+/// ```ignore
+/// fn variant_name(&self) -> std::borrow::Cow<'static, str> {
+///     match self {
+///         Self::Home => std::borrow::Cow::Borrowed("Home"),
+///         Self::Blog(subroute) => format!("Blog::{}", subroute.variant_name()).into(),
+///     }
+/// }
+/// ```
+pub fn generate_variant_name(configs: &[VariantConfig]) -> TokenStream {
+    let arms = configs.iter().map(|config| {
+        let ident = &config.ident;
+        let name = ident.to_string();
+        let nested = |inner: TokenStream| {
+            let format = format!("{name}::{{}}");
+
+            quote! { format!(#format, htmxology::Route::variant_name(#inner)).into() }
+        };
+
+        match (&config.route_type, config.subroute_param()) {
+            (RouteType::SubRoute, Some(subroute_field)) => {
+                let subroute_ident = &subroute_field.ident;
+                let pattern = match &config.fields {
+                    FieldsConfig::Unnamed(fields) => {
+                        let pattern_args = fields.iter().map(|f| {
+                            if f.is_subroute() {
+                                quote! { #subroute_ident }
+                            } else {
+                                quote! { _ }
+                            }
+                        });
+
+                        quote! { Self::#ident(#(#pattern_args),*) }
+                    }
+                    _ => quote! { Self::#ident { #subroute_ident, .. } },
+                };
+                let value = nested(quote! { #subroute_ident });
+
+                quote! { #pattern => #value }
+            }
+            (RouteType::CatchAll | RouteType::Include, _) => {
+                let value = nested(quote! { catch_all });
+
+                quote! { Self::#ident(catch_all) => #value }
+            }
+            _ => {
+                let pattern = generate_pattern(config, PatternMode::WithWildcard);
+
+                quote! { #pattern => std::borrow::Cow::Borrowed(#name) }
+            }
+        }
+    });
+
+    quote! {
+        fn variant_name(&self) -> std::borrow::Cow<'static, str> {
+            match self {
+                #(#arms),*
+            }
+        }
+    }
+}
+
 /// Generates the Display match arm for a variant.
 ///
+/// Without `with_query`, this generates the match arm formatting the path of the variant only,
+/// with subroutes and catch-all routes formatted with their `path()` method.
+///
 /// # Example Output
 ///
 /// ```ignore
@@ -383,9 +895,12 @@ pub fn generate_method_match(config: &VariantConfig) -> TokenStream {
 ///     user_id.fmt(f)?;
 /// }
 /// ```
-pub fn generate_display_match(config: &VariantConfig) -> syn::Result<TokenStream> {
+pub fn generate_display_match(
+    config: &VariantConfig,
+    with_query: bool,
+) -> syn::Result<TokenStream> {
     let pattern = generate_pattern(config, PatternMode::Simple);
-    let url_format = generate_url_format(config)?;
+    let url_format = generate_url_format(config, with_query)?;
     let span = config.ident.span();
 
     // For subroutes and catch-all, handle delegation
@@ -426,20 +941,109 @@ pub fn generate_display_match(config: &VariantConfig) -> syn::Result<TokenStream
         };
 
         // Add subroute delegation
-        statements.push(quote! { #subroute_ident.fmt(f)?; });
+        if with_query {
+            statements.push(quote! { #subroute_ident.fmt(f)?; });
+        } else {
+            statements.push(quote! { f.write_str(&htmxology::Route::path(#subroute_ident))?; });
+        }
 
         let block = to_block(statements);
         return Ok(quote_spanned! { span => #pattern => #block });
     }
 
-    if matches!(config.route_type, RouteType::CatchAll) {
+    if matches!(config.route_type, RouteType::CatchAll | RouteType::Include) {
         let ident = &config.ident;
-        return Ok(quote_spanned! { span => Self::#ident(catch_all) => catch_all.fmt(f)? });
+
+        return Ok(if with_query {
+            quote_spanned! { span => Self::#ident(catch_all) => catch_all.fmt(f)? }
+        } else {
+            quote_spanned! { span =>
+                Self::#ident(catch_all) => f.write_str(&htmxology::Route::path(catch_all))?
+            }
+        });
     }
 
     Ok(quote_spanned! { span => #pattern => #url_format })
 }
 
+/// Generates the `query()` match arm for a variant.
+///
+/// # Example Output
+///
+/// ```ignore
+/// Self::Search { query } => serde_html_form::to_string(query)
+///     .ok()
+///     .filter(|qs| !qs.is_empty())
+/// ```
+pub fn generate_query_match(config: &VariantConfig) -> TokenStream {
+    let span = config.ident.span();
+
+    if matches!(config.route_type, RouteType::CatchAll | RouteType::Include) {
+        let ident = &config.ident;
+
+        return quote_spanned! { span =>
+            Self::#ident(catch_all) => htmxology::Route::query(catch_all)
+        };
+    }
+
+    let pattern = generate_pattern(config, PatternMode::Simple);
+
+    if matches!(config.route_type, RouteType::SubRoute)
+        && let Some(subroute_field) = config.subroute_param()
+    {
+        let subroute_ident = &subroute_field.ident;
+
+        return quote_spanned! { span => #pattern => htmxology::Route::query(#subroute_ident) };
+    }
+
+    match config.query_param() {
+        Some(query_field) => {
+            let query_ident = &query_field.ident;
+
+            quote_spanned! { span =>
+                #pattern => serde_html_form::to_string(#query_ident)
+                    .ok()
+                    .filter(|qs| !qs.is_empty())
+            }
+        }
+        None => quote_spanned! { span => #pattern => None },
+    }
+}
+
+/// Generates the `fragment()` match arm for a variant.
+///
+/// # Example Output
+///
+/// ```ignore
+/// Self::Docs { page, section } => htmxology::format_fragment(section)
+/// ```
+pub fn generate_fragment_match(config: &VariantConfig) -> TokenStream {
+    let span = config.ident.span();
+
+    if matches!(config.route_type, RouteType::CatchAll | RouteType::Include) {
+        let ident = &config.ident;
+
+        return quote_spanned! { span =>
+            Self::#ident(catch_all) => htmxology::Route::fragment(catch_all)
+        };
+    }
+
+    let pattern = generate_pattern(config, PatternMode::Simple);
+
+    if matches!(config.route_type, RouteType::SubRoute)
+        && let Some(subroute_field) = config.subroute_param()
+    {
+        let subroute_ident = &subroute_field.ident;
+
+        return quote_spanned! { span => #pattern => htmxology::Route::fragment(#subroute_ident) };
+    }
+
+    match generate_fragment(config) {
+        Some(fragment) => quote_spanned! { span => #pattern => #fragment },
+        None => quote_spanned! { span => #pattern => None },
+    }
+}
+
 /// Generates the parsing code for FromStr implementation (GET routes only).
 ///
 /// This creates code that extracts path and query parameters from a URL string
@@ -448,6 +1052,8 @@ pub fn generate_from_str_parsing(config: &VariantConfig) -> TokenStream {
     // Path parameters
     let path_params: Vec<_> = config.fields.iter().filter(|f| f.is_path_param()).collect();
 
+    let rest_ident = config.rest_param().map(|field| &field.ident);
+
     let path_parse = if path_params.is_empty() {
         quote!()
     } else if config.fields.is_named() {
@@ -457,20 +1063,23 @@ pub fn generate_from_str_parsing(config: &VariantConfig) -> TokenStream {
             .map(|field| {
                 let ident = &field.ident;
                 let ty = &field.ty;
-                quote! {
-                    let #ident: #ty = __captures
-                        .name(stringify!(#ident))
-                        .ok_or_else(|| htmxology::ParseError::MissingPathParam {
-                            param_name: stringify!(#ident).to_string(),
-                        })?
-                        .as_str()
-                        .parse::<#ty>()
-                        .map_err(|e| htmxology::ParseError::PathParamParse {
-                            param_name: stringify!(#ident).to_string(),
-                            value: __captures.name(stringify!(#ident)).unwrap().as_str().to_string(),
-                            error: e.to_string(),
-                        })?;
+
+                if Some(ident) == rest_ident {
+                    return generate_rest_from_str_parsing(
+                        ident,
+                        ty,
+                        quote! { __captures.name(stringify!(#ident)) },
+                        quote! { stringify!(#ident).to_string() },
+                    );
                 }
+
+                generate_param_from_str_parsing(
+                    ident,
+                    ty,
+                    quote! { __captures.name(stringify!(#ident)) },
+                    quote! { stringify!(#ident).to_string() },
+                    config.is_encoded_path_param(field),
+                )
             })
             .collect();
 
@@ -484,20 +1093,23 @@ pub fn generate_from_str_parsing(config: &VariantConfig) -> TokenStream {
                 let ident = &field.ident;
                 let ty = &field.ty;
                 let idx = i + 1; // Regex capture groups are 1-indexed
-                quote! {
-                    let #ident: #ty = __captures
-                        .get(#idx)
-                        .ok_or_else(|| htmxology::ParseError::MissingPathParam {
-                            param_name: format!("arg{}", #idx),
-                        })?
-                        .as_str()
-                        .parse::<#ty>()
-                        .map_err(|e| htmxology::ParseError::PathParamParse {
-                            param_name: format!("arg{}", #idx),
-                            value: __captures.get(#idx).unwrap().as_str().to_string(),
-                            error: e.to_string(),
-                        })?;
+
+                if Some(ident) == rest_ident {
+                    return generate_rest_from_str_parsing(
+                        ident,
+                        ty,
+                        quote! { __captures.get(#idx) },
+                        quote! { format!("arg{}", #idx) },
+                    );
                 }
+
+                generate_param_from_str_parsing(
+                    ident,
+                    ty,
+                    quote! { __captures.get(#idx) },
+                    quote! { format!("arg{}", #idx) },
+                    config.is_encoded_path_param(field),
+                )
             })
             .collect();
 
@@ -518,13 +1130,21 @@ pub fn generate_from_str_parsing(config: &VariantConfig) -> TokenStream {
         quote!()
     };
 
+    let body_parse = generate_empty_form_body(config);
+    let fragment_parse = generate_fragment_parsing(config);
     let construction = generate_variant_construction(config);
 
-    if !path_parse.is_empty() || !query_parse.is_empty() {
+    if !path_parse.is_empty()
+        || !query_parse.is_empty()
+        || !body_parse.is_empty()
+        || !fragment_parse.is_empty()
+    {
         quote! {
             {
                 #path_parse
                 #query_parse
+                #body_parse
+                #fragment_parse
                 #construction
             }
         }
@@ -532,3 +1152,184 @@ pub fn generate_from_str_parsing(config: &VariantConfig) -> TokenStream {
         construction
     }
 }
+
+/// Generates the FromStr parsing code of a path parameter, percent-decoding its value unless it is
+/// already encoded.
+pub fn generate_param_from_str_parsing(
+    ident: &syn::Ident,
+    ty: &syn::Type,
+    capture: TokenStream,
+    param_name: TokenStream,
+    encoded: bool,
+) -> TokenStream {
+    let parse_fn = if encoded {
+        quote! { htmxology::parse_encoded_path_argument }
+    } else {
+        quote! { htmxology::parse_path_argument }
+    };
+
+    quote! {
+        let #ident: #ty = {
+            let __value = #capture
+                .ok_or_else(|| htmxology::ParseError::MissingPathParam {
+                    param_name: #param_name,
+                })?
+                .as_str();
+
+            #parse_fn::<#ty>(__value).map_err(|error| {
+                htmxology::ParseError::PathParamParse {
+                    param_name: #param_name,
+                    value: __value.to_string(),
+                    error,
+                }
+            })?
+        };
+    }
+}
+
+/// Generates the FromStr parsing code of a `{*rest}` path parameter.
+fn generate_rest_from_str_parsing(
+    ident: &syn::Ident,
+    ty: &syn::Type,
+    capture: TokenStream,
+    param_name: TokenStream,
+) -> TokenStream {
+    quote! {
+        let #ident: #ty = {
+            let __value = #capture
+                .ok_or_else(|| htmxology::ParseError::MissingPathParam {
+                    param_name: #param_name,
+                })?
+                .as_str();
+
+            <#ty as htmxology::RestPath>::from_rest_path(__value).map_err(|e| {
+                htmxology::ParseError::PathParamParse {
+                    param_name: #param_name,
+                    value: __value.to_string(),
+                    error: e.to_string(),
+                }
+            })?
+        };
+    }
+}
+
+/// Generates a builder-style constructor for a `#[builder]` variant.
+///
+/// Required fields (path parameters and subroutes) are arguments of the constructor, while query
+/// and body fields default to `Default::default()` and can be set through the builder.
+///
+/// # Example Output
+///
+/// ```ignore
+/// impl MyRoute {
+///     pub fn search() -> MyRouteSearchBuilder {
+///         MyRouteSearchBuilder { query: Default::default() }
+///     }
+/// }
+///
+/// pub struct MyRouteSearchBuilder {
+///     query: SearchQuery,
+/// }
+///
+/// impl MyRouteSearchBuilder {
+///     pub fn query(mut self, query: impl Into<SearchQuery>) -> Self { ... }
+///     pub fn query_with(mut self, f: impl FnOnce(&mut SearchQuery)) -> Self { ... }
+///     pub fn build(self) -> MyRoute { ... }
+/// }
+/// ```
+pub fn generate_builder(
+    config: &VariantConfig,
+    root_ident: &syn::Ident,
+    vis: &syn::Visibility,
+) -> TokenStream {
+    use convert_case::{Case, Casing};
+
+    let ident = &config.ident;
+    let span = ident.span();
+    let constructor_ident = syn::Ident::new(&ident.to_string().to_case(Case::Snake), span);
+    let builder_ident = quote::format_ident!("{}{}Builder", root_ident, ident);
+    let constructor_doc = format!(" Start building a [`{root_ident}::{ident}`] route.");
+    let builder_doc = format!(" A builder for [`{root_ident}::{ident}`] routes.");
+
+    let (optional_fields, required_fields): (Vec<_>, Vec<_>) =
+        config.fields.iter().partition(|f| f.is_optional());
+
+    let field_decls = config.fields.iter().map(|f| {
+        let ident = &f.ident;
+        let ty = &f.ty;
+        quote! { #ident: #ty }
+    });
+
+    let required_args = required_fields.iter().map(|f| {
+        let ident = &f.ident;
+        let ty = &f.ty;
+        quote! { #ident: #ty }
+    });
+
+    let field_inits = config.fields.iter().map(|f| {
+        let ident = &f.ident;
+        if f.is_optional() {
+            quote! { #ident: Default::default() }
+        } else {
+            quote! { #ident }
+        }
+    });
+
+    let setters = optional_fields.iter().map(|f| {
+        let ident = &f.ident;
+        let ty = &f.ty;
+        let with_ident = quote::format_ident!("{}_with", ident);
+        let set_doc = format!(" Set the `{ident}` field.");
+        let with_doc = format!(" Update the `{ident}` field in place.");
+
+        quote! {
+            #[doc = #set_doc]
+            #vis fn #ident(mut self, #ident: impl Into<#ty>) -> Self {
+                self.#ident = #ident.into();
+                self
+            }
+
+            #[doc = #with_doc]
+            #vis fn #with_ident(mut self, f: impl FnOnce(&mut #ty)) -> Self {
+                f(&mut self.#ident);
+                self
+            }
+        }
+    });
+
+    let field_idents: Vec<_> = config.fields.iter().map(|f| &f.ident).collect();
+
+    quote_spanned! { span =>
+        impl #root_ident {
+            #[doc = #constructor_doc]
+            #vis fn #constructor_ident(#(#required_args),*) -> #builder_ident {
+                #builder_ident {
+                    #(#field_inits),*
+                }
+            }
+        }
+
+        #[doc = #builder_doc]
+        #[must_use]
+        #vis struct #builder_ident {
+            #(#field_decls),*
+        }
+
+        impl #builder_ident {
+            #(#setters)*
+
+            /// Build the route.
+            #vis fn build(self) -> #root_ident {
+                #root_ident::#ident {
+                    #(#field_idents: self.#field_idents),*
+                }
+            }
+        }
+
+        impl From<#builder_ident> for #root_ident {
+            fn from(builder: #builder_ident) -> Self {
+                builder.build()
+            }
+        }
+    }
+}
@@ -6,9 +6,11 @@
 
 use super::route_type::RouteType;
 use super::route_url::RouteUrl;
-use super::{attributes, parse_route_info};
+use super::{attributes, parse_route_info, parse_route_url};
 use quote::format_ident;
-use syn::{Error, Field, Fields, Ident, Type, Variant};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned as _;
+use syn::{Attribute, Error, Expr, Field, Fields, Ident, LitStr, Meta, Token, Type, Variant};
 
 /// Configuration for a single route variant.
 ///
@@ -27,6 +29,37 @@ pub struct VariantConfig {
 
     /// The fields configuration for this variant.
     pub fields: FieldsConfig,
+
+    /// Whether to generate a builder-style constructor for this variant (`#[builder]`).
+    pub builder: bool,
+
+    /// Whether concurrent identical requests to this route can be coalesced (`#[coalesce]`).
+    pub coalesce: bool,
+
+    /// The host pattern this catch-all variant is scoped to (`#[host("admin.{base}")]`), if any.
+    pub host: Option<String>,
+
+    /// The documentation of the variant, from its doc comments.
+    pub doc: Option<String>,
+
+    /// The id selector of the element the route is swapped into (`target = "#food-list"`), if any.
+    pub target: Option<String>,
+
+    /// The static fragment appended to the URL of the route (`#[route("docs#install")]`), if any.
+    pub fragment: Option<String>,
+
+    /// The legacy URLs also matched by the route (`#[route_alias("old/{id}")]`).
+    pub aliases: Vec<RouteAlias>,
+}
+
+/// A legacy URL of a route, declared with `#[route_alias("...")]`.
+#[derive(Clone)]
+pub struct RouteAlias {
+    /// The URL of the alias, with the same path parameters as the route URL.
+    pub route_url: RouteUrl,
+
+    /// Whether requests to the alias are redirected to the route URL (`redirect`).
+    pub redirect: bool,
 }
 
 /// Configuration for the fields of a variant.
@@ -87,6 +120,7 @@ impl FieldsConfig {
         let mut query_count = 0;
         let mut body_count = 0;
         let mut subroute_count = 0;
+        let mut fragment_count = 0;
 
         for field in fields {
             match field.role {
@@ -99,7 +133,7 @@ impl FieldsConfig {
                         ));
                     }
                 }
-                FieldRole::Body => {
+                FieldRole::Body { .. } => {
                     body_count += 1;
                     if body_count > 1 {
                         return Err(Error::new_spanned(
@@ -117,7 +151,16 @@ impl FieldsConfig {
                         ));
                     }
                 }
-                FieldRole::PathParam { .. } => {}
+                FieldRole::Fragment => {
+                    fragment_count += 1;
+                    if fragment_count > 1 {
+                        return Err(Error::new_spanned(
+                            &field.ident,
+                            "only one field can be a fragment anchor",
+                        ));
+                    }
+                }
+                FieldRole::PathParam { .. } | FieldRole::Extract => {}
                 FieldRole::CatchAll => {
                     // CatchAll fields are always valid, no validation needed
                 }
@@ -193,18 +236,32 @@ impl FieldConfig {
             .iter()
             .any(|attr| attr.path().is_ident(attributes::QUERY));
 
-        let is_body = field
+        let body_attrs: Vec<_> = field
             .attrs
             .iter()
-            .any(|attr| attr.path().is_ident(attributes::BODY));
+            .filter(|attr| attr.path().is_ident(attributes::BODY))
+            .collect();
+        let is_body = !body_attrs.is_empty();
 
         let is_subroute = field
             .attrs
             .iter()
             .any(|attr| attr.path().is_ident(attributes::SUBROUTE));
 
+        let extract_attr = field
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident(attributes::EXTRACT));
+        let is_extract = extract_attr.is_some();
+
+        let fragment_attr = field
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident(attributes::FRAGMENT_ANCHOR));
+        let is_fragment = fragment_attr.is_some();
+
         // Validate that only one attribute is present
-        let attr_count = [is_query, is_body, is_subroute]
+        let attr_count = [is_query, is_body, is_subroute, is_extract, is_fragment]
             .iter()
             .filter(|&&b| b)
             .count();
@@ -212,15 +269,58 @@ impl FieldConfig {
         if attr_count > 1 {
             return Err(Error::new_spanned(
                 field,
-                "field cannot have multiple role attributes (query, body, subroute)",
+                "field cannot have multiple role attributes (query, body, subroute, extract, fragment_anchor)",
             ));
         }
 
         // Determine the role
-        if is_query {
+        if let Some(extract_attr) = extract_attr {
+            if !matches!(extract_attr.meta, Meta::Path(_)) {
+                return Err(Error::new_spanned(
+                    extract_attr,
+                    format!(
+                        "`{}` attribute does not take any arguments",
+                        attributes::EXTRACT
+                    ),
+                ));
+            }
+
+            // Extracted fields can't be delegated to other route types, which parse them from
+            // URIs
+            if !matches!(route_type, RouteType::Simple { .. } | RouteType::Form) {
+                return Err(Error::new_spanned(
+                    field,
+                    "extract attribute can only be used on simple and form route variants",
+                ));
+            }
+
+            Ok(FieldRole::Extract)
+        } else if let Some(fragment_attr) = fragment_attr {
+            if !matches!(fragment_attr.meta, Meta::Path(_)) {
+                return Err(Error::new_spanned(
+                    fragment_attr,
+                    format!(
+                        "`{}` attribute does not take any arguments",
+                        attributes::FRAGMENT_ANCHOR
+                    ),
+                ));
+            }
+
+            // The fragment of delegated routes is formatted by the route they delegate to
+            if !matches!(route_type, RouteType::Simple { .. } | RouteType::Form) {
+                return Err(Error::new_spanned(
+                    field,
+                    "fragment_anchor attribute can only be used on simple and form route variants",
+                ));
+            }
+
+            Ok(FieldRole::Fragment)
+        } else if is_query {
             Ok(FieldRole::Query)
         } else if is_body {
-            Ok(FieldRole::Body)
+            Ok(FieldRole::Body {
+                content_types: parse_body_content_types(&body_attrs)?,
+            })
         } else if is_subroute {
             // Validate that subroute is only used with SubRoute route type
             if !matches!(route_type, RouteType::SubRoute) {
@@ -230,8 +330,8 @@ impl FieldConfig {
                 ));
             }
             Ok(FieldRole::Subroute)
-        } else if matches!(route_type, RouteType::CatchAll) {
-            // For catch-all variants, the field is the catch-all route
+        } else if matches!(route_type, RouteType::CatchAll | RouteType::Include) {
+            // For catch-all and included variants, the field is the delegated route
             Ok(FieldRole::CatchAll)
         } else {
             // Default to path parameter
@@ -251,13 +351,32 @@ impl FieldConfig {
 
     /// Returns true if this field is a body parameter.
     pub fn is_body(&self) -> bool {
-        matches!(self.role, FieldRole::Body)
+        matches!(self.role, FieldRole::Body { .. })
+    }
+
+    /// Returns true if this field is extracted from the request parts.
+    pub fn is_extract(&self) -> bool {
+        matches!(self.role, FieldRole::Extract)
     }
 
     /// Returns true if this field is a subroute.
     pub fn is_subroute(&self) -> bool {
         matches!(self.role, FieldRole::Subroute)
     }
+
+    /// Returns true if this field is the fragment anchor of the URL.
+    pub fn is_fragment(&self) -> bool {
+        matches!(self.role, FieldRole::Fragment)
+    }
+
+    /// Returns true if this field can be omitted from a builder, and defaults to
+    /// `Default::default()`.
+    pub fn is_optional(&self) -> bool {
+        matches!(
+            self.role,
+            FieldRole::Query | FieldRole::Body { .. } | FieldRole::Fragment
+        )
+    }
 }
 
 /// The role of a field in routing.
@@ -266,8 +385,10 @@ impl FieldConfig {
 /// - Path parameters are extracted from the URL path
 /// - Query parameters are extracted from the query string
 /// - Body parameters are extracted from the request body
+/// - Extracted fields are resolved from the request parts by an axum extractor
+/// - Fragment anchors are appended to the URL, and default when parsing
 /// - Subroutes delegate to another route type
-/// - CatchAll handles any unmatched routes
+/// - CatchAll handles any unmatched routes, or the routes of an included route type
 #[derive(Debug, Clone)]
 pub enum FieldRole {
     /// A path parameter extracted from the URL.
@@ -284,15 +405,378 @@ pub enum FieldRole {
     Query,
 
     /// A body parameter extracted from the request body (annotated with `#[body]`).
-    Body,
+    Body {
+        /// The normalized media types accepted for the body (e.g.
+        /// `application/x-www-form-urlencoded`), in declaration order.
+        content_types: Vec<String>,
+    },
+
+    /// A field resolved from the request parts by its `FromRequestParts` implementation
+    /// (annotated with `#[extract]`).
+    Extract,
+
+    /// The fragment appended to the URL (annotated with `#[fragment_anchor]`).
+    ///
+    /// As browsers don't send fragments to servers, the field is `Default::default()` when
+    /// parsing.
+    Fragment,
 
     /// A subroute that delegates to another route type (annotated with `#[subroute]`).
     Subroute,
 
-    /// A catch-all field that handles unmatched routes (used in `#[catch_all]` variants).
+    /// A field that delegates to another route type (used in `#[catch_all]` and
+    /// `#[route_include]` variants).
     CatchAll,
 }
 
+/// The media type of bodies when none is specified.
+const DEFAULT_BODY_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+/// The media type of multipart bodies, decoded into an `htmxology::MultipartForm`.
+pub const MULTIPART_BODY_CONTENT_TYPE: &str = "multipart/form-data";
+
+/// The media type of JSON bodies.
+pub const JSON_BODY_CONTENT_TYPE: &str = "application/json";
+
+/// The supported media types of bodies.
+const SUPPORTED_BODY_CONTENT_TYPES: [&str; 3] = [
+    DEFAULT_BODY_CONTENT_TYPE,
+    MULTIPART_BODY_CONTENT_TYPE,
+    JSON_BODY_CONTENT_TYPE,
+];
+
+/// Parse the content types of the `#[body]`, `#[body("...")]` or `#[body(any("...", ...))]`
+/// attributes of a field.
+///
+/// A field with several content types decodes the body according to the `Content-Type` of the
+/// request. Multipart bodies are decoded into a different type, and can't be combined with other
+/// content types.
+fn parse_body_content_types(attrs: &[&Attribute]) -> syn::Result<Vec<String>> {
+    let mut content_types: Vec<String> = Vec::new();
+
+    for attr in attrs {
+        let lits = match &attr.meta {
+            Meta::Path(_) => vec![LitStr::new(DEFAULT_BODY_CONTENT_TYPE, attr.span())],
+            Meta::List(list) => {
+                if let Ok(lit) = attr.parse_args::<LitStr>() {
+                    vec![lit]
+                } else {
+                    let any: syn::ExprCall = syn::parse2(list.tokens.clone())
+                        .map_err(|_| Error::new_spanned(attr, BODY_ATTRIBUTE_SYNTAX_ERROR))?;
+
+                    if !matches!(&*any.func, syn::Expr::Path(path) if path.path.is_ident("any")) {
+                        return Err(Error::new_spanned(attr, BODY_ATTRIBUTE_SYNTAX_ERROR));
+                    }
+
+                    any.args
+                        .iter()
+                        .map(|arg| match arg {
+                            syn::Expr::Lit(syn::ExprLit {
+                                lit: syn::Lit::Str(lit),
+                                ..
+                            }) => Ok(lit.clone()),
+                            _ => Err(Error::new_spanned(arg, "expected a content type string")),
+                        })
+                        .collect::<syn::Result<_>>()?
+                }
+            }
+            Meta::NameValue(_) => {
+                return Err(Error::new_spanned(attr, BODY_ATTRIBUTE_SYNTAX_ERROR));
+            }
+        };
+
+        for lit in lits {
+            let content_type = parse_body_content_type(&lit)?;
+
+            if content_types.contains(&content_type) {
+                return Err(Error::new_spanned(
+                    lit,
+                    format!("duplicate body content type `{content_type}`"),
+                ));
+            }
+
+            content_types.push(content_type);
+        }
+    }
+
+    if content_types.len() > 1
+        && content_types
+            .iter()
+            .any(|content_type| content_type == MULTIPART_BODY_CONTENT_TYPE)
+    {
+        return Err(Error::new_spanned(
+            attrs[0],
+            format!(
+                "`{MULTIPART_BODY_CONTENT_TYPE}` bodies can't be combined with other content types"
+            ),
+        ));
+    }
+
+    Ok(content_types)
+}
+
+/// The error of malformed `#[body]` attributes.
+const BODY_ATTRIBUTE_SYNTAX_ERROR: &str =
+    "expected `#[body]`, `#[body(\"<content-type>\")]` or `#[body(any(\"<content-type>\", ...))]`";
+
+/// Parse a body content type.
+///
+/// The content type is validated and normalized to its lowercase type and subtype, as any
+/// parameters (like `charset`) are taken from the request at runtime.
+fn parse_body_content_type(lit: &LitStr) -> syn::Result<String> {
+    let value = lit.value();
+    let essence = value
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    match essence.split_once('/') {
+        Some((type_, subtype)) if !type_.is_empty() && !subtype.is_empty() => {}
+        _ => {
+            return Err(Error::new_spanned(
+                lit,
+                format!("invalid body content type `{value}`"),
+            ));
+        }
+    }
+
+    if !SUPPORTED_BODY_CONTENT_TYPES.contains(&essence.as_str()) {
+        return Err(Error::new_spanned(
+            lit,
+            format!(
+                "unsupported body content type `{value}`: only `{DEFAULT_BODY_CONTENT_TYPE}`, `{MULTIPART_BODY_CONTENT_TYPE}` and `{JSON_BODY_CONTENT_TYPE}` are supported"
+            ),
+        ));
+    }
+
+    Ok(essence)
+}
+
+/// Parse the `#[host("...")]` attribute of a variant, if any.
+///
+/// Host-scoped variants must be catch-all variants, as they match any path on their host.
+fn parse_host(variant: &Variant, route_type: &RouteType) -> syn::Result<Option<String>> {
+    let mut host = None;
+
+    for attr in &variant.attrs {
+        if !attr.path().is_ident(attributes::HOST) {
+            continue;
+        }
+
+        if host.is_some() {
+            return Err(Error::new_spanned(
+                attr,
+                format!("at most one `{}` attribute is allowed", attributes::HOST),
+            ));
+        }
+
+        if !matches!(route_type, RouteType::CatchAll) {
+            return Err(Error::new_spanned(
+                attr,
+                format!(
+                    "`{}` attribute can only be used on `{}` variants",
+                    attributes::HOST,
+                    attributes::CATCH_ALL
+                ),
+            ));
+        }
+
+        let lit = attr.parse_args::<LitStr>()?;
+        let value = lit.value();
+
+        if value.is_empty()
+            || !value
+                .replace("{base}", "")
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+        {
+            return Err(Error::new_spanned(
+                lit,
+                format!("invalid host pattern `{value}`"),
+            ));
+        }
+
+        host = Some(value);
+    }
+
+    Ok(host)
+}
+
+/// Parse the `#[route_alias("...")]` and `#[route_alias("...", redirect)]` attributes of a
+/// variant.
+///
+/// Aliases must have the same path parameters as the route URL, in the same order, and the
+/// `#[param(...)]` constraints of the variant apply to them as well.
+fn parse_aliases(
+    variant: &Variant,
+    route_type: &RouteType,
+    route_url: &RouteUrl,
+) -> syn::Result<Vec<RouteAlias>> {
+    let mut aliases = Vec::new();
+
+    for attr in &variant.attrs {
+        if !attr.path().is_ident(attributes::ROUTE_ALIAS) {
+            continue;
+        }
+
+        if !matches!(route_type, RouteType::Simple { .. } | RouteType::Form) {
+            return Err(Error::new_spanned(
+                attr,
+                format!(
+                    "`{}` attribute can only be used on simple and form route variants",
+                    attributes::ROUTE_ALIAS
+                ),
+            ));
+        }
+
+        let mut exprs = attr
+            .parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated)?
+            .into_iter();
+        let raw_url = exprs.next().ok_or_else(|| {
+            Error::new_spanned(attr, "expected a route alias URL as the first argument")
+        })?;
+        let mut redirect = false;
+
+        for expr in exprs {
+            match &expr {
+                Expr::Path(path) if path.path.is_ident("redirect") && !redirect => {
+                    redirect = true;
+                }
+                _ => return Err(Error::new_spanned(expr, "expected `redirect`")),
+            }
+        }
+
+        let (mut alias_url, fragment) = parse_route_url(raw_url.clone())?;
+
+        if fragment.is_some() {
+            return Err(Error::new_spanned(
+                raw_url,
+                "route aliases can't have a fragment",
+            ));
+        }
+
+        if alias_url.is_prefix() {
+            return Err(Error::new_spanned(
+                raw_url,
+                "route aliases can't end with `/`, which is reserved for subroutes",
+            ));
+        }
+
+        // Unnamed fields are parsed from the path parameters by position.
+        if alias_url.parameter_names() != route_url.parameter_names()
+            || alias_url.rest_parameter() != route_url.rest_parameter()
+        {
+            return Err(Error::new_spanned(
+                raw_url,
+                format!(
+                    "route alias `{alias_url}` must have the same path parameters as `{route_url}`, in the same order"
+                ),
+            ));
+        }
+
+        parse_param_constraints(variant, &mut alias_url)?;
+
+        if alias_url == *route_url {
+            return Err(Error::new_spanned(
+                raw_url,
+                format!("route alias `{alias_url}` is the route URL itself"),
+            ));
+        }
+
+        aliases.push(RouteAlias {
+            route_url: alias_url,
+            redirect,
+        });
+    }
+
+    Ok(aliases)
+}
+
+/// Apply the `#[param(name, regex = "...", encoded)]` attributes of a variant to its route URL.
+///
+/// The regex must match the whole parameter value, and can't contain capture groups as they would
+/// shift the positions of the other path parameters. `encoded` declares the values of the
+/// parameter as already percent-encoded, so that they are formatted and parsed verbatim.
+fn parse_param_constraints(variant: &Variant, route_url: &mut RouteUrl) -> syn::Result<()> {
+    for attr in &variant.attrs {
+        if !attr.path().is_ident(attributes::PARAM) {
+            continue;
+        }
+
+        let (name, regex, encoded) = attr.parse_args_with(|input: syn::parse::ParseStream| {
+            let name: Ident = input.parse()?;
+            let mut regex: Option<LitStr> = None;
+            let mut encoded = None;
+
+            while !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+
+                if input.is_empty() {
+                    break;
+                }
+
+                let key: Ident = input.parse()?;
+
+                if key == "regex" && regex.is_none() {
+                    input.parse::<syn::Token![=]>()?;
+                    regex = Some(input.parse()?);
+                } else if key == "encoded" && encoded.is_none() {
+                    encoded = Some(key);
+                } else if key == "regex" || key == "encoded" {
+                    return Err(Error::new_spanned(
+                        &key,
+                        format!("duplicate `{key}` argument"),
+                    ));
+                } else {
+                    return Err(Error::new_spanned(key, "expected `regex` or `encoded`"));
+                }
+            }
+
+            if regex.is_none() && encoded.is_none() {
+                return Err(Error::new_spanned(&name, "expected `regex` or `encoded`"));
+            }
+
+            Ok((name, regex, encoded))
+        })?;
+
+        if let Some(encoded) = encoded {
+            route_url
+                .set_param_encoded(&name.to_string())
+                .map_err(|err| Error::new_spanned(encoded, err))?;
+        }
+
+        let Some(regex) = regex else {
+            continue;
+        };
+
+        let value = regex.value();
+
+        match regex::Regex::new(&value) {
+            Ok(compiled) if compiled.captures_len() > 1 => {
+                return Err(Error::new_spanned(
+                    regex,
+                    "path parameter regexes can't contain capture groups, use `(?:...)` instead",
+                ));
+            }
+            Ok(_) => {}
+            Err(err) => {
+                return Err(Error::new_spanned(
+                    regex,
+                    format!("invalid path parameter regex: {err}"),
+                ));
+            }
+        }
+
+        route_url
+            .set_param_regex(&name.to_string(), value)
+            .map_err(|err| Error::new_spanned(name, err))?;
+    }
+
+    Ok(())
+}
+
 impl VariantConfig {
     /// Creates a new `VariantConfig` from a syn Variant.
     ///
@@ -300,17 +784,107 @@ impl VariantConfig {
     /// necessary information from the variant's attributes and fields.
     pub fn from_variant(variant: &Variant) -> syn::Result<Self> {
         let ident = variant.ident.clone();
-        let (route_url, route_type) = parse_route_info(variant)?;
+        let (mut route_url, route_type, target, fragment) = parse_route_info(variant)?;
+        parse_param_constraints(variant, &mut route_url)?;
         let fields = FieldsConfig::from_fields(&variant.fields, &route_type)?;
+        let builder_attr = variant
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident(attributes::BUILDER));
+
+        if let Some(builder_attr) = builder_attr {
+            if !matches!(builder_attr.meta, Meta::Path(_)) {
+                return Err(Error::new_spanned(
+                    builder_attr,
+                    format!(
+                        "`{}` attribute does not take any arguments",
+                        attributes::BUILDER
+                    ),
+                ));
+            }
+
+            if matches!(route_type, RouteType::CatchAll | RouteType::Include) || !fields.is_named()
+            {
+                return Err(Error::new_spanned(
+                    builder_attr,
+                    format!(
+                        "`{}` attribute can only be used on route variants with named fields",
+                        attributes::BUILDER
+                    ),
+                ));
+            }
+        }
+
+        let coalesce_attr = variant
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident(attributes::COALESCE));
+
+        if let Some(coalesce_attr) = coalesce_attr {
+            if !matches!(coalesce_attr.meta, Meta::Path(_)) {
+                return Err(Error::new_spanned(
+                    coalesce_attr,
+                    format!(
+                        "`{}` attribute does not take any arguments",
+                        attributes::COALESCE
+                    ),
+                ));
+            }
+
+            if !matches!(&route_type, RouteType::Simple { method } if method == http::Method::GET) {
+                return Err(Error::new_spanned(
+                    coalesce_attr,
+                    format!(
+                        "`{}` attribute can only be used on `GET` routes",
+                        attributes::COALESCE
+                    ),
+                ));
+            }
+        }
+
+        if matches!(route_type, RouteType::Form)
+            && !fields.iter().any(|field| {
+                field.is_body() && crate::utils::option_inner_type(&field.ty).is_some()
+            })
+        {
+            return Err(Error::new_spanned(
+                variant,
+                "`GET|POST` routes require an `Option<...>` `#[body]` field, which is `None` for `GET` requests",
+            ));
+        }
+
+        if let Some(fragment_field) = fields.iter().find(|field| field.is_fragment())
+            && fragment.is_some()
+        {
+            return Err(Error::new_spanned(
+                &fragment_field.ident,
+                "a route can't have both a fragment in its URL and a fragment anchor field",
+            ));
+        }
+
+        let host = parse_host(variant, &route_type)?;
+        let aliases = parse_aliases(variant, &route_type, &route_url)?;
 
         Ok(Self {
             ident,
             route_url,
             route_type,
             fields,
+            builder: builder_attr.is_some(),
+            coalesce: coalesce_attr.is_some(),
+            host,
+            doc: crate::utils::doc_comment(&variant.attrs),
+            target,
+            fragment,
+            aliases,
         })
     }
 
+    /// Returns the route URL followed by the URLs of the aliases of the route.
+    pub fn urls(&self) -> impl Iterator<Item = &RouteUrl> {
+        std::iter::once(&self.route_url).chain(self.aliases.iter().map(|alias| &alias.route_url))
+    }
+
     /// Returns the query parameter field, if any.
     pub fn query_param(&self) -> Option<&FieldConfig> {
         self.fields.iter().find(|f| f.is_query())
@@ -321,6 +895,48 @@ impl VariantConfig {
         self.fields.iter().find(|f| f.is_body())
     }
 
+    /// Returns the fields extracted from the request parts.
+    pub fn extract_params(&self) -> impl Iterator<Item = &FieldConfig> {
+        self.fields.iter().filter(|f| f.is_extract())
+    }
+
+    /// Returns the `{*rest}` path parameter field, if any.
+    ///
+    /// As rest path parameters are always last, they are the last path parameter of unnamed
+    /// variants.
+    pub fn rest_param(&self) -> Option<&FieldConfig> {
+        let rest_name = self.route_url.rest_parameter()?;
+
+        if self.fields.is_named() {
+            self.fields.iter().find(|f| {
+                matches!(&f.role, FieldRole::PathParam { name: Some(name) } if name == rest_name)
+            })
+        } else {
+            self.fields.iter().filter(|f| f.is_path_param()).last()
+        }
+    }
+
+    /// Check whether the values of a path parameter field are already percent-encoded, and thus
+    /// parsed verbatim.
+    pub fn is_encoded_path_param(&self, field: &FieldConfig) -> bool {
+        match &field.role {
+            FieldRole::PathParam { name: Some(name) } => self.route_url.is_param_encoded(name),
+            FieldRole::PathParam { name: None } => self
+                .fields
+                .iter()
+                .filter(|f| f.is_path_param())
+                .position(|f| f.ident == field.ident)
+                .and_then(|index| self.route_url.parameter_names().get(index).copied())
+                .is_some_and(|name| self.route_url.is_param_encoded(name)),
+            _ => false,
+        }
+    }
+
+    /// Returns the fragment anchor field, if any.
+    pub fn fragment_param(&self) -> Option<&FieldConfig> {
+        self.fields.iter().find(|f| f.is_fragment())
+    }
+
     /// Returns the subroute field, if any.
     pub fn subroute_param(&self) -> Option<&FieldConfig> {
         self.fields.iter().find(|f| f.is_subroute())
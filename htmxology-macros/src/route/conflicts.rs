@@ -0,0 +1,175 @@
+//! Detection of conflicting routes.
+//!
+//! Requests are matched against the subroutes first, then against the simple routes, both with
+//! the more specific URLs first (see [`RouteUrl`]'s ordering). A route whose every path is matched
+//! by a route tried before it is unreachable, which is reported as a compile error instead of
+//! being found at runtime.
+
+use syn::Error;
+
+use super::VariantConfig;
+use super::route_type::RouteType;
+use super::route_url::RouteUrl;
+
+/// A route of a variant, as matched when parsing requests.
+struct MatchedRoute<'a> {
+    /// The variant of the route.
+    config: &'a VariantConfig,
+
+    /// The URL of the route, which is either the route URL of the variant or one of its aliases.
+    url: &'a RouteUrl,
+
+    /// The methods of the route, or `None` for a subroute prefix, which matches all methods.
+    methods: Option<Vec<http::Method>>,
+}
+
+impl MatchedRoute<'_> {
+    fn url(&self) -> &RouteUrl {
+        self.url
+    }
+
+    fn kind(&self) -> &'static str {
+        match self.methods {
+            Some(_) if self.url != &self.config.route_url => "route alias",
+            Some(_) => "route",
+            None => "subroute prefix",
+        }
+    }
+
+    /// Check whether the route is tried before another route when parsing requests.
+    fn is_tried_before(&self, other: &Self) -> bool {
+        match (&self.methods, &other.methods) {
+            (None, Some(_)) => true,
+            (Some(_), None) => false,
+            _ => self.url() > other.url(),
+        }
+    }
+}
+
+/// Check that no variant is shadowed by another, returning all the conflicts as one error.
+pub(super) fn check(configs: &[VariantConfig]) -> syn::Result<()> {
+    let mut errors = Vec::new();
+    let mut catch_all: Option<&VariantConfig> = None;
+
+    let routes: Vec<_> = configs
+        .iter()
+        .filter_map(|config| {
+            let methods = match &config.route_type {
+                RouteType::Simple { method } => Some(vec![method.clone()]),
+                RouteType::Form => Some(vec![http::Method::GET, http::Method::POST]),
+                RouteType::SubRoute => None,
+                // Host-scoped catch-all variants only match their host.
+                RouteType::CatchAll if config.host.is_some() => return None,
+                RouteType::CatchAll => {
+                    match catch_all {
+                        Some(previous) => errors.push(conflict(
+                            config,
+                            format!(
+                                "duplicate catch-all variant: `{}` is already the catch-all variant",
+                                previous.ident
+                            ),
+                            previous,
+                        )),
+                        None => catch_all = Some(config),
+                    }
+
+                    return None;
+                }
+                // The routes of included route types are only known at runtime, and are checked
+                // by `htmxology::check_route_patterns` when building the route table.
+                RouteType::Include => return None,
+            };
+
+            Some(config.urls().map(move |url| MatchedRoute {
+                config,
+                url,
+                methods: methods.clone(),
+            }))
+        })
+        .flatten()
+        .collect();
+
+    for (index, route) in routes.iter().enumerate() {
+        let Some(methods) = &route.methods else {
+            // Subroutes with the same prefix are reported when generating the code.
+            if let Some(shadowing) = routes
+                .iter()
+                .find(|other| other.is_tried_before(route) && other.url().covers(route.url()))
+            {
+                errors.push(shadowed(route, shadowing));
+            }
+
+            continue;
+        };
+
+        // Routes with the same URL are matched together, on their method.
+        if let Some((previous, method)) = routes[..index].iter().find_map(|previous| {
+            let previous_methods = previous.methods.as_ref()?;
+
+            if previous.url() != route.url() {
+                return None;
+            }
+
+            methods
+                .iter()
+                .find(|method| previous_methods.contains(method))
+                .map(|method| (previous, method))
+        }) {
+            errors.push(conflict(
+                route.config,
+                format!(
+                    "duplicate route `{method} {}`: it is already declared by `{}`",
+                    route.url(),
+                    previous.config.ident
+                ),
+                previous.config,
+            ));
+
+            continue;
+        }
+
+        if let Some(shadowing) = routes
+            .iter()
+            .find(|other| other.is_tried_before(route) && other.url().covers(route.url()))
+        {
+            errors.push(shadowed(route, shadowing));
+        }
+    }
+
+    match errors.into_iter().reduce(|mut errors, error| {
+        errors.combine(error);
+        errors
+    }) {
+        Some(errors) => Err(errors),
+        None => Ok(()),
+    }
+}
+
+/// Get the error of a route shadowed by another.
+fn shadowed(route: &MatchedRoute<'_>, shadowing: &MatchedRoute<'_>) -> Error {
+    conflict(
+        route.config,
+        format!(
+            "the {} `{}` of `{}` is unreachable, as all its paths are matched first by the {} `{}` of `{}`",
+            route.kind(),
+            route.url(),
+            route.config.ident,
+            shadowing.kind(),
+            shadowing.url(),
+            shadowing.config.ident,
+        ),
+        shadowing.config,
+    )
+}
+
+/// Get the error of a conflict between two variants, spanning both.
+fn conflict(config: &VariantConfig, message: String, other: &VariantConfig) -> Error {
+    let mut error = Error::new_spanned(&config.ident, message);
+
+    error.combine(Error::new_spanned(
+        &other.ident,
+        format!("`{}` conflicts with this variant", config.ident),
+    ));
+
+    error
+}
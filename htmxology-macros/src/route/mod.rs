@@ -8,24 +8,93 @@ use syn::{Error, Expr, Token, Variant, punctuated::Punctuated};
 
 mod codegen;
 mod config;
+mod conflicts;
 mod route_type;
 mod route_url;
 
+use config::{FieldRole, RouteAlias};
 pub(crate) use config::{FieldsConfig, VariantConfig};
 use route_type::{MethodExt, RouteType};
-use route_url::{ParseError, RouteUrl};
+use route_url::{ParseError, RouteUrl, is_valid_url_path_character};
 
 mod attributes {
     pub(super) const ROUTE: &str = "route";
     pub(super) const CATCH_ALL: &str = "catch_all";
+    pub(super) const ROUTE_INCLUDE: &str = "route_include";
     pub(super) const METHOD: &str = "method";
+    pub(super) const TARGET: &str = "target";
     pub(super) const SUBROUTE: &str = "subroute";
     pub(super) const QUERY: &str = "query";
     pub(super) const BODY: &str = "body";
+    pub(super) const EXTRACT: &str = "extract";
+    pub(super) const FRAGMENT_ANCHOR: &str = "fragment_anchor";
+    pub(super) const BUILDER: &str = "builder";
+    pub(super) const COALESCE: &str = "coalesce";
+    pub(super) const HOST: &str = "host";
+    pub(super) const PARAM: &str = "param";
+    pub(super) const ROUTE_OPTS: &str = "route_opts";
+    pub(super) const ROUTE_ALIAS: &str = "route_alias";
+}
+
+/// The `method` value of form routes.
+const FORM_METHODS: &str = "GET|POST";
+
+/// The options of the enum-level `#[route_opts(...)]` attribute.
+#[derive(Debug, Default)]
+struct RouteOptions {
+    /// Implement `FromStr` through `Route::from_uri`, instead of dedicated parsing code.
+    no_from_str: bool,
+
+    /// Implement `Display` through the path and query of the route, instead of dedicated
+    /// formatting code.
+    no_display: bool,
+
+    /// Match the literal segments of the route URLs case-insensitively.
+    case_insensitive: bool,
+}
+
+impl RouteOptions {
+    /// Parse the `#[route_opts(...)]` attributes of an enum.
+    fn from_attrs(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut options = Self::default();
+
+        for attr in attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident(attributes::ROUTE_OPTS))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("no_from_str") {
+                    options.no_from_str = true;
+                } else if meta.path.is_ident("no_display") {
+                    options.no_display = true;
+                } else if meta.path.is_ident("case_insensitive") {
+                    options.case_insensitive = true;
+                } else {
+                    return Err(
+                        meta.error("expected `no_from_str`, `no_display` or `case_insensitive`")
+                    );
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(options)
+    }
+
+    /// Get the path regex of a route URL.
+    fn path_regex(&self, url: &RouteUrl) -> String {
+        if self.case_insensitive {
+            url.to_case_insensitive_path_regex()
+        } else {
+            url.to_path_regex()
+        }
+    }
 }
 
 pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let root_ident = &input.ident;
+    let options = RouteOptions::from_attrs(&input.attrs)?;
     let data = expect_enum(input, "Route")?;
 
     // Parse all variants into configurations
@@ -35,8 +104,24 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
         .map(VariantConfig::from_variant)
         .collect::<syn::Result<Vec<_>>>()?;
 
+    conflicts::check(&configs)?;
+
+    let builders: Vec<_> = configs
+        .iter()
+        .filter(|config| config.builder)
+        .map(|config| codegen::generate_builder(config, root_ident, &input.vis))
+        .collect();
+
     let mut to_urls = Vec::with_capacity(configs.len());
+    let mut to_paths = Vec::with_capacity(configs.len());
+    let mut queries = Vec::with_capacity(configs.len());
+    let mut fragments = Vec::with_capacity(configs.len());
     let mut methods = Vec::with_capacity(configs.len());
+    let coalesce_requests = codegen::generate_coalesce_requests(&configs);
+    let json_body = codegen::generate_json_body(&configs);
+    let variant_name = codegen::generate_variant_name(&configs);
+    let expected_target = codegen::generate_expected_target(&configs);
+    let route_host = codegen::generate_host(&configs);
 
     let mut simple_routes = BTreeMap::new();
     let mut sub_routes = BTreeMap::new();
@@ -45,30 +130,151 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
         Err(http::StatusCode::NOT_FOUND.into_response())
     };
     let mut catch_all_from_str: Option<proc_macro2::TokenStream> = None;
+    let mut from_uri_routes = BTreeMap::new();
+    let mut from_uri_sub_routes = BTreeMap::new();
+    let mut catch_all_from_uri: Option<proc_macro2::TokenStream> = None;
+    let mut host_routes = Vec::new();
+    let mut includes = Vec::new();
+    let mut include_from_str = Vec::new();
+    let mut include_from_uri = Vec::new();
+    let mut include_types = Vec::new();
+    let mut patterns = Vec::new();
+    let mut route_table = Vec::with_capacity(configs.len());
+    let mut descriptions = Vec::with_capacity(configs.len());
+    let mut form_froms = Vec::new();
 
     for config in &configs {
         // Generate Display and method() match arms
-        let display_match = codegen::generate_display_match(config)?;
+        let display_match = codegen::generate_display_match(config, true)?;
         let method_match = codegen::generate_method_match(config);
 
         to_urls.push(display_match);
+        to_paths.push(codegen::generate_display_match(config, false)?);
+        queries.push(codegen::generate_query_match(config));
+        fragments.push(codegen::generate_fragment_match(config));
         methods.push(method_match);
+        route_table.push(generate_route_table_entries(config));
+        descriptions.push(generate_route_descriptions(config));
+
+        let variant = format!("{root_ident}::{}", config.ident);
+
+        match &config.route_type {
+            RouteType::Simple { .. } | RouteType::Form => {
+                for url in config.urls() {
+                    patterns.push(generate_route_pattern(&variant, url, false, &options));
+                }
+            }
+            RouteType::SubRoute => {
+                patterns.push(generate_route_pattern(
+                    &variant,
+                    &config.route_url,
+                    true,
+                    &options,
+                ));
+            }
+            RouteType::CatchAll | RouteType::Include => {}
+        }
 
         // Generate routing logic based on route type
         match &config.route_type {
             RouteType::Simple { method } => {
                 let handler = codegen::generate_request_parsing(config);
+
+                for alias in &config.aliases {
+                    simple_routes
+                        .entry(alias.route_url.clone())
+                        .or_insert_with(Vec::new)
+                        .push((method.clone(), generate_alias_result(alias, &handler)));
+                }
+
                 simple_routes
                     .entry(config.route_url.clone())
                     .or_insert_with(Vec::new)
-                    .push((method.clone(), handler));
+                    .push((method.clone(), quote! { Ok(#handler) }));
+
+                // Collect all routes for from_uri, which can't parse bodies or extract fields
+                let from_uri_handler = if config.extract_params().next().is_some() {
+                    generate_extraction_required(quote! { __method })
+                } else if config.body_param().is_some() {
+                    quote! {
+                        Err(htmxology::ParseError::BodyRequired {
+                            url: __s.to_string(),
+                            method: __method.to_string(),
+                        })
+                    }
+                } else {
+                    let handler = codegen::generate_from_str_parsing(config);
+                    quote! { Ok(#handler) }
+                };
+
+                for url in config.urls() {
+                    from_uri_routes
+                        .entry(url.clone())
+                        .or_insert_with(Vec::new)
+                        .push((method.clone(), from_uri_handler.clone()));
+
+                    // Collect GET routes for FromStr
+                    if method == http::Method::GET {
+                        get_only_routes
+                            .entry(url.clone())
+                            .or_insert_with(|| generate_from_str_result(config));
+                    }
+                }
+            }
+            RouteType::Form => {
+                let get_handler = codegen::generate_form_request_parsing(config, false);
+                let post_handler = codegen::generate_form_request_parsing(config, true);
+
+                for alias in &config.aliases {
+                    let routes = simple_routes
+                        .entry(alias.route_url.clone())
+                        .or_insert_with(Vec::new);
+
+                    routes.push((
+                        http::Method::GET,
+                        generate_alias_result(alias, &get_handler),
+                    ));
+                    routes.push((
+                        http::Method::POST,
+                        generate_alias_result(alias, &post_handler),
+                    ));
+                }
+
+                let routes = simple_routes
+                    .entry(config.route_url.clone())
+                    .or_insert_with(Vec::new);
+
+                routes.push((http::Method::GET, quote! { Ok(#get_handler) }));
+                routes.push((http::Method::POST, quote! { Ok(#post_handler) }));
+
+                let from_str_result = generate_from_str_result(config);
+                let from_uri_result = if config.extract_params().next().is_some() {
+                    generate_extraction_required(quote! { __method })
+                } else {
+                    from_str_result.clone()
+                };
+
+                for url in config.urls() {
+                    let routes = from_uri_routes.entry(url.clone()).or_insert_with(Vec::new);
+
+                    routes.push((http::Method::GET, from_uri_result.clone()));
+                    routes.push((
+                        http::Method::POST,
+                        quote! {
+                            Err(htmxology::ParseError::BodyRequired {
+                                url: __s.to_string(),
+                                method: __method.to_string(),
+                            })
+                        },
+                    ));
 
-                // Collect GET routes for FromStr
-                if method == http::Method::GET {
-                    let from_str_handler = codegen::generate_from_str_parsing(config);
                     get_only_routes
-                        .entry(config.route_url.clone())
-                        .or_insert(from_str_handler);
+                        .entry(url.clone())
+                        .or_insert_with(|| from_str_result.clone());
+                }
+
+                if let Some(form_from) = generate_form_from(config, root_ident) {
+                    form_froms.push(form_from);
                 }
             }
             RouteType::SubRoute => {
@@ -84,16 +290,38 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
                 }
 
                 // Add subroute to get_only_routes for FromStr
-                let from_str_handler = generate_subroute_from_str_handler(config)?;
+                let from_str_handler = generate_subroute_from_str_handler(config, false)?;
                 get_only_routes
                     .entry(config.route_url.clone())
-                    .or_insert(from_str_handler);
+                    .or_insert(quote! { Ok(#from_str_handler) });
+
+                let from_uri_handler = generate_subroute_from_str_handler(config, true)?;
+                from_uri_sub_routes.insert(config.route_url.clone(), from_uri_handler);
+            }
+            RouteType::CatchAll if config.host.is_some() => {
+                // Host-scoped routes can't be parsed from a path alone, so they are only
+                // considered when parsing requests.
+                let host = config.host.as_deref();
+                let handler = generate_catch_all_handler(config)?;
+
+                host_routes.push(quote! {
+                    if htmxology::request_matches_host(&__req, #host) {
+                        return #handler;
+                    }
+                });
+            }
+            RouteType::Include => {
+                include_types.push(include_field(config)?.0);
+                includes.push(generate_include_handler(config)?);
+                include_from_str.push(generate_include_from_str_handler(config, false)?);
+                include_from_uri.push(generate_include_from_str_handler(config, true)?);
             }
             RouteType::CatchAll => {
                 catch_all = generate_catch_all_handler(config)?;
 
                 // Store catch-all FromStr handler separately to use as fallback
-                catch_all_from_str = Some(generate_catch_all_from_str_handler(config)?);
+                catch_all_from_str = Some(generate_catch_all_from_str_handler(config, false)?);
+                catch_all_from_uri = Some(generate_catch_all_from_str_handler(config, true)?);
             }
         }
     }
@@ -102,64 +330,158 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
 
     // We add the subroutes first, so that they are matched before the simple routes.
     for (prefix, handler) in sub_routes.into_iter().rev() {
-        let prefix = prefix.to_path_regex();
-
-        parsing.push(quote! {{
-            static RE: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| regex::Regex::new(#prefix).unwrap());
-
-            if let Some(__captures) = RE.captures(&__req.uri().path()) {
-                #handler
-            }
-        }});
+        let captures = generate_captures(&prefix, quote! { &__req.uri().path() });
+
+        parsing.push((
+            prefix,
+            true,
+            quote! {
+                if let Some(__captures) = #captures {
+                    #handler
+                }
+            },
+        ));
     }
 
     // Then we add the simple routes, with more specific routes first.
     for (url, methods_and_handlers) in simple_routes.into_iter().rev() {
-        let url = url.to_path_regex();
-        let local_methods: Vec<_> = methods_and_handlers
+        let captures = generate_captures(&url, quote! { &__req.uri().path() });
+        // Non-standard methods can't be matched as patterns and may be tunneled through a
+        // `POST` request, so they are matched with guards before the standard methods.
+        let (custom_methods, standard_methods): (Vec<_>, Vec<_>) = methods_and_handlers
+            .into_iter()
+            .partition(|(method, _)| method.standard_ident().is_none());
+
+        let local_methods: Vec<_> = custom_methods
             .into_iter()
             .map(|(method, handler)| {
-                let method = method.to_ident();
+                let method = method.as_str();
 
-                quote! {&http::Method::#method => Ok(#handler)}
+                quote! {_ if htmxology::request_has_method(&__req, #method) => #handler}
             })
-            .collect();
+            .chain(standard_methods.into_iter().map(|(method, handler)| {
+                let method = method
+                    .standard_ident()
+                    .expect("standard method should have an identifier");
 
-        parsing.push(quote! {{
-            static RE: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| regex::Regex::new(#url).unwrap());
+                quote! {&http::Method::#method => #handler}
+            }))
+            .collect();
 
-            if let Some(__captures) = RE.captures(&__req.uri().path()) {
-                return match __req.method() {
-                    #(#local_methods),*,
-                    _ => Err(http::StatusCode::METHOD_NOT_ALLOWED.into_response()),
-                };
-            }
-        }});
+        parsing.push((
+            url,
+            false,
+            quote! {
+                if let Some(__captures) = #captures {
+                    return match __req.method() {
+                        #(#local_methods),*,
+                        _ => Err(http::StatusCode::METHOD_NOT_ALLOWED.into_response()),
+                    };
+                }
+            },
+        ));
     }
 
     // Generate FromStr parsing logic for GET-only routes
     let mut from_str_parsing = Vec::new();
 
     for (url, handler) in get_only_routes.into_iter().rev() {
-        let url = url.to_path_regex();
+        let captures = generate_captures(&url, quote! { __path });
+
+        from_str_parsing.push((
+            url,
+            false,
+            quote! {
+                if let Some(__captures) = #captures {
+                    return #handler;
+                }
+            },
+        ));
+    }
+
+    // Add catch-all handler as fallback if present, or error if not
+    let from_str_fallback = if let Some(catch_all_handler) = catch_all_from_str {
+        quote! {
+            Ok(#catch_all_handler)
+        }
+    } else {
+        quote! {
+            Err(htmxology::ParseError::NoMatchingRoute {
+                url: __s.to_string(),
+                method: http::Method::GET.to_string(),
+                suggestions: Vec::new(),
+            })
+        }
+    };
 
-        from_str_parsing.push(quote! {{
-            static RE: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| regex::Regex::new(#url).unwrap());
+    // Generate from_uri parsing logic, subroutes first as for requests
+    let mut from_uri_parsing = Vec::new();
 
-            // Split path and query string before matching regex
-            let (__path, __query_str): (&str, &str) = match __s.split_once('?') {
-                Some((p, q)) => (p, q),
-                None => (__s, ""),
-            };
+    for (prefix, handler) in from_uri_sub_routes.into_iter().rev() {
+        let captures = generate_captures(&prefix, quote! { __path });
 
-            if let Some(__captures) = RE.captures(__path) {
-                return Ok(#handler);
-            }
-        }});
+        from_uri_parsing.push((
+            prefix,
+            true,
+            quote! {
+                if let Some(__captures) = #captures {
+                    return Ok(#handler);
+                }
+            },
+        ));
     }
 
-    // Add catch-all handler as fallback if present, or error if not
-    let from_str_fallback = if let Some(catch_all_handler) = catch_all_from_str {
+    for (url, methods_and_handlers) in from_uri_routes.into_iter().rev() {
+        let captures = generate_captures(&url, quote! { __path });
+        let local_methods: Vec<_> = methods_and_handlers
+            .into_iter()
+            .map(|(method, handler)| {
+                let method = method.as_str();
+
+                quote! {
+                    if __method.as_str() == #method {
+                        return #handler;
+                    }
+                }
+            })
+            .collect();
+
+        from_uri_parsing.push((
+            url,
+            false,
+            quote! {
+                if let Some(__captures) = #captures {
+                    #(#local_methods)*
+
+                    return Err(htmxology::ParseError::MethodNotAllowed {
+                        url: __s.to_string(),
+                        method: __method.to_string(),
+                    });
+                }
+            },
+        ));
+    }
+
+    // Included route types are matched in place by their route patterns, and are still tried
+    // afterwards, before the catch-all variant, for their own catch-all and host routes.
+    let matcher_set = MatcherSet {
+        root_ident,
+        options: &options,
+        include_types: &include_types,
+    };
+    let parsing = matcher_set.generate(parsing, &includes, true, quote! { __req.uri().path() });
+    let from_str_parsing = matcher_set.generate(
+        from_str_parsing,
+        &include_from_str,
+        false,
+        quote! { __path },
+    );
+    let from_uri_parsing =
+        matcher_set.generate(from_uri_parsing, &include_from_uri, true, quote! { __path });
+    let route_patterns = generate_route_patterns(&patterns, &include_types);
+    let check_route_patterns = generate_check_route_patterns(&patterns, &include_types);
+
+    let from_uri_fallback = if let Some(catch_all_handler) = catch_all_from_uri {
         quote! {
             Ok(#catch_all_handler)
         }
@@ -167,42 +489,220 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
         quote! {
             Err(htmxology::ParseError::NoMatchingRoute {
                 url: __s.to_string(),
+                method: __method.to_string(),
+                suggestions: Vec::new(),
             })
         }
     };
 
+    let display = if options.no_display {
+        quote! {
+            impl std::fmt::Display for #root_ident {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    htmxology::fmt_with_path_prefix(f, |f| {
+                        self.__fmt_path(f)?;
+
+                        if let Some(__query) = htmxology::Route::query(self) {
+                            write!(f, "?{__query}")?;
+                        }
+
+                        match htmxology::Route::fragment(self) {
+                            Some(__fragment) => write!(f, "#{__fragment}"),
+                            None => Ok(()),
+                        }
+                    })
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl std::fmt::Display for #root_ident {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    htmxology::fmt_with_path_prefix(f, |f| {
+                        match self {
+                            #(#to_urls),*
+                        };
+
+                        Ok(())
+                    })
+                }
+            }
+        }
+    };
+
+    let from_str = if options.no_from_str {
+        quote! {
+            impl std::str::FromStr for #root_ident {
+                type Err = htmxology::ParseError;
+
+                fn from_str(__s: &str) -> Result<Self, Self::Err> {
+                    // Fragments are never part of the route
+                    let __s = __s.split_once('#').map_or(__s, |(__s, _)| __s);
+                    let __uri: http::Uri = __s.parse().map_err(|_| htmxology::ParseError::NoMatchingRoute {
+                        url: __s.to_string(),
+                        method: http::Method::GET.to_string(),
+                        suggestions: Vec::new(),
+                    })?;
+
+                    <Self as htmxology::Route>::from_uri(&__uri, &http::Method::GET)
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl std::str::FromStr for #root_ident {
+                type Err = htmxology::ParseError;
+
+                fn from_str(__s: &str) -> Result<Self, Self::Err> {
+                    // Fragments are never part of the route
+                    let __s = __s.split_once('#').map_or(__s, |(__s, _)| __s);
+
+                    // Split path and query string before matching regex
+                    let (__path, __query_str): (&str, &str) = match __s.split_once('?') {
+                        Some((p, q)) => (p, q),
+                        None => (__s, ""),
+                    };
+
+                    #from_str_parsing
+
+                    #(#include_from_str)*
+
+                    #from_str_fallback
+                }
+            }
+        }
+    };
+
+    // Extracted fields must be extractable with the state of the request.
+    let mut extract_types = Vec::new();
+
+    for field in configs.iter().flat_map(|config| config.extract_params()) {
+        let ty = &field.ty;
+
+        if !extract_types
+            .iter()
+            .any(|extract_type| quote!(#extract_type).to_string() == quote!(#ty).to_string())
+        {
+            extract_types.push(ty.clone());
+        }
+    }
+
+    let from_request_bounds = if extract_types.is_empty() {
+        quote!()
+    } else {
+        quote! { where #(#extract_types: axum::extract::FromRequestParts<S>),* }
+    };
+
+    // Forms shared by several routes can't be converted unambiguously.
+    let form_froms: Vec<_> = form_froms
+        .iter()
+        .filter(|(form_ty, _)| {
+            form_froms
+                .iter()
+                .filter(|(other_ty, _)| other_ty == form_ty)
+                .count()
+                == 1
+        })
+        .map(|(_, form_from)| form_from)
+        .collect();
+
     Ok(quote! {
         use axum::response::IntoResponse as _;
 
+        #(#builders)*
+
+        #(#form_froms)*
+
+        impl #root_ident {
+            /// Format the path of the route, without its query.
+            #[allow(unused_variables)]
+            fn __fmt_path(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                use std::fmt::Display as _;
+
+                match self {
+                    #(#to_paths),*
+                };
+
+                Ok(())
+            }
+        }
+
         impl htmxology::Route for #root_ident {
             fn method(&self) -> http::Method {
                 match self {
                     #(#methods),*
                 }
             }
-        }
 
-        impl std::fmt::Display for #root_ident {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            #coalesce_requests
+
+            #json_body
+
+            #variant_name
+
+            #expected_target
+
+            #route_host
+
+            fn path(&self) -> String {
+                struct __RoutePath<'a>(&'a #root_ident);
+
+                impl std::fmt::Display for __RoutePath<'_> {
+                    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        self.0.__fmt_path(f)
+                    }
+                }
+
+                __RoutePath(self).to_string()
+            }
+
+            #[allow(unused_variables)]
+            fn query(&self) -> Option<String> {
                 match self {
-                    #(#to_urls),*
-                };
+                    #(#queries),*
+                }
+            }
 
-                Ok(())
+            #[allow(unused_variables)]
+            fn fragment(&self) -> Option<String> {
+                match self {
+                    #(#fragments),*
+                }
+            }
+
+            fn route_table() -> Vec<htmxology::RouteEntry> {
+                #check_route_patterns
+
+                let __tables: &[Vec<htmxology::RouteEntry>] = &[#(#route_table),*];
+
+                __tables.concat()
+            }
+
+            #route_patterns
+
+            fn describe() -> Vec<htmxology::RouteDescription> {
+                let __descriptions: &[Vec<htmxology::RouteDescription>] = &[#(#descriptions),*];
+
+                __descriptions.concat()
             }
-        }
 
-        impl std::str::FromStr for #root_ident {
-            type Err = htmxology::ParseError;
+            fn from_uri(__uri: &http::Uri, __method: &http::Method) -> Result<Self, htmxology::ParseError> {
+                let __s = __uri.path_and_query().map(|__pq| __pq.as_str()).unwrap_or("/");
+                let (__path, __query_str): (&str, &str) = (__uri.path(), __uri.query().unwrap_or_default());
 
-            fn from_str(__s: &str) -> Result<Self, Self::Err> {
-                #(#from_str_parsing)*
+                #from_uri_parsing
 
-                #from_str_fallback
+                #(#include_from_uri)*
+
+                #from_uri_fallback
             }
         }
 
-        impl<S: Send + Sync> axum::extract::FromRequest<S> for #root_ident {
+        #display
+
+        #from_str
+
+        impl<S: Send + Sync> axum::extract::FromRequest<S> for #root_ident #from_request_bounds {
             type Rejection = axum::response::Response;
 
             async fn from_request(
@@ -211,7 +711,11 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
             ) -> Result<Self, Self::Rejection> {
                 use axum::extract::FromRequestParts;
 
-                #(#parsing)*
+                #(#host_routes)*
+
+                #parsing
+
+                #(#includes)*
 
                 #catch_all
             }
@@ -219,6 +723,29 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
     })
 }
 
+/// Generates the `FromStr` result of a simple or form variant.
+///
+/// Variants with extracted fields can't be parsed from a URL alone.
+fn generate_from_str_result(config: &VariantConfig) -> proc_macro2::TokenStream {
+    if config.extract_params().next().is_some() {
+        return generate_extraction_required(quote! { http::Method::GET });
+    }
+
+    let handler = codegen::generate_from_str_parsing(config);
+
+    quote! { Ok(#handler) }
+}
+
+/// Generates the error of a variant with extracted fields, which can't be parsed from a URI.
+fn generate_extraction_required(method: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    quote! {
+        Err(htmxology::ParseError::ExtractionRequired {
+            url: __s.to_string(),
+            method: #method.to_string(),
+        })
+    }
+}
+
 /// Generates the handler code for a subroute variant.
 fn generate_subroute_handler(config: &VariantConfig) -> syn::Result<proc_macro2::TokenStream> {
     let subroute_field = config
@@ -313,9 +840,70 @@ fn generate_catch_all_handler(config: &VariantConfig) -> syn::Result<proc_macro2
     ))
 }
 
+/// Generates the handler code for an included variant.
+///
+/// The request is only delegated to the included route type if it has a route matching the path
+/// of the request, as the request can't be delegated to another variant afterwards.
+fn generate_include_handler(config: &VariantConfig) -> syn::Result<proc_macro2::TokenStream> {
+    let (field_ty, ident) = include_field(config)?;
+
+    Ok(quote! {
+        if !matches!(
+            <#field_ty as htmxology::Route>::from_uri(__req.uri(), __req.method()),
+            Err(htmxology::ParseError::NoMatchingRoute { .. })
+        ) {
+            return <#field_ty as axum::extract::FromRequest<S>>::from_request(__req, __state)
+                .await
+                .map(Self::#ident);
+        }
+    })
+}
+
+/// Generates the FromStr handler code for an included variant.
+///
+/// When `from_uri` is set, the included route is parsed with `Route::from_uri` instead of
+/// `FromStr`.
+fn generate_include_from_str_handler(
+    config: &VariantConfig,
+    from_uri: bool,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let (field_ty, ident) = include_field(config)?;
+
+    let parse = if from_uri {
+        quote! { <#field_ty as htmxology::Route>::from_uri(__uri, __method) }
+    } else {
+        quote! { <#field_ty as std::str::FromStr>::from_str(__s) }
+    };
+
+    Ok(quote! {
+        match #parse {
+            Err(htmxology::ParseError::NoMatchingRoute { .. }) => {}
+            __result => return __result.map(Self::#ident),
+        }
+    })
+}
+
+/// Get the type of the field of an included variant, and the identifier of the variant.
+fn include_field(config: &VariantConfig) -> syn::Result<(&syn::Type, &syn::Ident)> {
+    if let FieldsConfig::Unnamed(fields) = &config.fields
+        && fields.len() == 1
+    {
+        return Ok((&fields[0].ty, &config.ident));
+    }
+
+    Err(Error::new_spanned(
+        &config.ident,
+        "included variant must have exactly one unnamed field",
+    ))
+}
+
 /// Generates the FromStr handler code for a subroute variant.
+///
+/// When `from_uri` is set, the subroute is parsed with `Route::from_uri` instead of `FromStr`,
+/// forwarding the query string and method.
 fn generate_subroute_from_str_handler(
     config: &VariantConfig,
+    from_uri: bool,
 ) -> syn::Result<proc_macro2::TokenStream> {
     let subroute_field = config
         .subroute_param()
@@ -335,21 +923,14 @@ fn generate_subroute_from_str_handler(
             .iter()
             .map(|field| {
                 let field_ident = &field.ident;
-                let ty = &field.ty;
-                quote! {
-                    let #field_ident: #ty = __captures
-                        .name(stringify!(#field_ident))
-                        .ok_or_else(|| htmxology::ParseError::MissingPathParam {
-                            param_name: stringify!(#field_ident).to_string(),
-                        })?
-                        .as_str()
-                        .parse::<#ty>()
-                        .map_err(|e| htmxology::ParseError::PathParamParse {
-                            param_name: stringify!(#field_ident).to_string(),
-                            value: __captures.name(stringify!(#field_ident)).unwrap().as_str().to_string(),
-                            error: e.to_string(),
-                        })?;
-                }
+
+                codegen::generate_param_from_str_parsing(
+                    field_ident,
+                    &field.ty,
+                    quote! { __captures.name(stringify!(#field_ident)) },
+                    quote! { stringify!(#field_ident).to_string() },
+                    config.is_encoded_path_param(field),
+                )
             })
             .collect();
         quote! { #(#parse_stmts)* }
@@ -358,23 +939,15 @@ fn generate_subroute_from_str_handler(
             .iter()
             .enumerate()
             .map(|(i, field)| {
-                let field_ident = &field.ident;
-                let ty = &field.ty;
                 let idx = i + 1;
-                quote! {
-                    let #field_ident: #ty = __captures
-                        .get(#idx)
-                        .ok_or_else(|| htmxology::ParseError::MissingPathParam {
-                            param_name: format!("arg{}", #idx),
-                        })?
-                        .as_str()
-                        .parse::<#ty>()
-                        .map_err(|e| htmxology::ParseError::PathParamParse {
-                            param_name: format!("arg{}", #idx),
-                            value: __captures.get(#idx).unwrap().as_str().to_string(),
-                            error: e.to_string(),
-                        })?;
-                }
+
+                codegen::generate_param_from_str_parsing(
+                    &field.ident,
+                    &field.ty,
+                    quote! { __captures.get(#idx) },
+                    quote! { format!("arg{}", #idx) },
+                    config.is_encoded_path_param(field),
+                )
             })
             .collect();
         quote! { #(#parse_stmts)* }
@@ -393,19 +966,46 @@ fn generate_subroute_from_str_handler(
         _ => unreachable!("SubRoute must have fields"),
     };
 
+    let subroute_parse = if from_uri {
+        quote! {
+            let __subroute_path = __captures.name("subroute").unwrap().as_str();
+            let __subroute_uri: http::Uri = if __query_str.is_empty() {
+                __subroute_path.parse()
+            } else {
+                format!("{__subroute_path}?{__query_str}").parse()
+            }
+            .map_err(|_| htmxology::ParseError::NoMatchingRoute {
+                url: __s.to_string(),
+                method: __method.to_string(),
+                suggestions: Vec::new(),
+            })?;
+            let #subroute_ident = <#subroute_ty as htmxology::Route>::from_uri(&__subroute_uri, __method)
+                .map_err(|__err| __err.with_url(__s))?;
+        }
+    } else {
+        quote! {
+            let __subroute_path = __captures.name("subroute").unwrap().as_str();
+            let #subroute_ident = #subroute_ty::from_str(__subroute_path)
+                .map_err(|__err| __err.with_url(__s))?;
+        }
+    };
+
     Ok(quote! {{
         #path_parse
 
-        let __subroute_path = __captures.name("subroute").unwrap().as_str();
-        let #subroute_ident = #subroute_ty::from_str(__subroute_path)?;
+        #subroute_parse
 
         #construction
     }})
 }
 
 /// Generates the FromStr handler code for a catch-all variant.
+///
+/// When `from_uri` is set, the catch-all route is parsed with `Route::from_uri` instead of
+/// `FromStr`.
 fn generate_catch_all_from_str_handler(
     config: &VariantConfig,
+    from_uri: bool,
 ) -> syn::Result<proc_macro2::TokenStream> {
     // Catch-all should have exactly one unnamed field
     if let FieldsConfig::Unnamed(fields) = &config.fields
@@ -414,8 +1014,14 @@ fn generate_catch_all_from_str_handler(
         let field_ty = &fields[0].ty;
         let ident = &config.ident;
 
+        let parse = if from_uri {
+            quote! { <#field_ty as htmxology::Route>::from_uri(__uri, __method)? }
+        } else {
+            quote! { #field_ty::from_str(__s)? }
+        };
+
         return Ok(quote! {{
-            let catch_all = #field_ty::from_str(__s)?;
+            let catch_all = #parse;
             Self::#ident(catch_all)
         }});
     }
@@ -426,172 +1032,1421 @@ fn generate_catch_all_from_str_handler(
     ))
 }
 
-fn parse_route_info(variant: &Variant) -> syn::Result<(RouteUrl, RouteType)> {
-    let mut result = None;
-
-    for attr in &variant.attrs {
-        if attr.path().is_ident(attributes::ROUTE) {
-            if result.is_some() {
-                return Err(Error::new_spanned(
-                    attr,
-                    format!(
-                        "expected exactly one `{}` or `{}` attribute",
-                        attributes::ROUTE,
-                        attributes::CATCH_ALL
-                    ),
-                ));
-            }
+/// Generate the conversion from the form of a form route, if the form is its only field.
+///
+/// The conversion builds the submission of the form, and lets `HtmlForm` types derive their action
+/// route. Returns the conversion along with the form type, as forms used by several routes can't be
+/// converted unambiguously.
+fn generate_form_from(
+    config: &VariantConfig,
+    root_ident: &syn::Ident,
+) -> Option<(String, proc_macro2::TokenStream)> {
+    let mut fields = config.fields.iter();
+    let body_field = fields.next()?;
 
-            let mut exprs = attr
-                .parse_args_with(Punctuated::<syn::Expr, Token![,]>::parse_terminated)?
-                .into_iter();
+    if fields.next().is_some() {
+        return None;
+    }
 
-            let raw_url = exprs.next().ok_or_else(|| {
-                Error::new_spanned(attr, "expected a route URL as the first argument")
-            })?;
+    let form_ty = crate::utils::option_inner_type(&body_field.ty)?;
+    let ident = &config.ident;
+    let body_ident = &body_field.ident;
+    let construction = if config.fields.is_named() {
+        quote! { Self::#ident { #body_ident: Some(#body_ident) } }
+    } else {
+        quote! { Self::#ident(Some(#body_ident)) }
+    };
 
-            let url = parse_route_url(raw_url)?;
+    Some((
+        quote! { #form_ty }.to_string(),
+        quote! {
+            impl From<#form_ty> for #root_ident {
+                fn from(#body_ident: #form_ty) -> Self {
+                    #construction
+                }
+            }
+        },
+    ))
+}
+
+/// Generate the expression listing the route table entries of a variant.
+fn generate_route_table_entries(config: &VariantConfig) -> proc_macro2::TokenStream {
+    let variant = config.ident.to_string();
+    let pattern = config.route_url.to_string();
+
+    match &config.route_type {
+        RouteType::Simple { method } => {
+            let method = method.as_str();
+
+            quote! { vec![htmxology::RouteEntry::new(#variant, #method, #pattern)] }
+        }
+        RouteType::Form => quote! {
+            vec![
+                htmxology::RouteEntry::new(#variant, "GET", #pattern),
+                htmxology::RouteEntry::new(#variant, "POST", #pattern),
+            ]
+        },
+        RouteType::SubRoute | RouteType::CatchAll | RouteType::Include => {
+            let ty = match config.subroute_param() {
+                Some(field) => &field.ty,
+                None => match config.fields.iter().next() {
+                    Some(field) => &field.ty,
+                    None => return quote! { Vec::new() },
+                },
+            };
+            let prefix = match config.route_type {
+                RouteType::SubRoute => pattern,
+                _ => String::new(),
+            };
+            let host = match &config.host {
+                Some(host) => quote! { Some(#host) },
+                None => quote! { None },
+            };
+
+            quote! {
+                htmxology::RouteEntry::nested(
+                    <#ty as htmxology::Route>::route_table(),
+                    #variant,
+                    #prefix,
+                    #host,
+                )
+            }
+        }
+    }
+}
+
+/// Generate the expression listing the route descriptions of a variant.
+fn generate_route_descriptions(config: &VariantConfig) -> proc_macro2::TokenStream {
+    let variant = config.ident.to_string();
+    let pattern = config.route_url.to_string();
+    let doc = config.doc.as_ref().map(|doc| quote! { .with_doc(#doc) });
+    let target = config
+        .target
+        .as_ref()
+        .map(|target| quote! { .with_target(#target) });
+    let url_names = config.route_url.parameter_names();
+    let mut path_names = url_names.iter();
+
+    let parameters: Vec<_> = config
+        .fields
+        .iter()
+        .filter_map(|field| {
+            let ty = crate::utils::type_name(&field.ty);
+            let name = match &field.role {
+                FieldRole::PathParam { name } => name
+                    .clone()
+                    .or_else(|| path_names.next().map(|name| (*name).to_owned()))?,
+                FieldRole::Query | FieldRole::Body { .. } if config.fields.is_named() => {
+                    field.ident.to_string()
+                }
+                FieldRole::Query => "query".to_owned(),
+                FieldRole::Body { .. } => "body".to_owned(),
+                _ => return None,
+            };
+            let parameter = match &field.role {
+                FieldRole::PathParam { .. } => quote! {
+                    htmxology::RouteParameter::new(#name, htmxology::ParameterLocation::Path, #ty)
+                },
+                FieldRole::Query => quote! {
+                    htmxology::RouteParameter::new(#name, htmxology::ParameterLocation::Query, #ty)
+                },
+                FieldRole::Body { content_types } => quote! {
+                    htmxology::RouteParameter::new(#name, htmxology::ParameterLocation::Body, #ty)
+                        .with_content_types(&[#(#content_types),*])
+                },
+                _ => return None,
+            };
+
+            Some((field.is_body(), parameter))
+        })
+        .collect();
+
+    let description = |method: &str, with_body: bool| {
+        let parameters = parameters
+            .iter()
+            .filter(|(is_body, _)| with_body || !is_body)
+            .map(|(_, parameter)| parameter);
+
+        quote! {
+            htmxology::RouteDescription::new(#variant, #method, #pattern)
+                #doc
+                #target
+                #(.with_parameter(#parameters))*
+        }
+    };
+
+    match &config.route_type {
+        RouteType::Simple { method } => {
+            let description = description(method.as_str(), true);
+
+            quote! { vec![#description] }
+        }
+        RouteType::Form => {
+            let get = description("GET", false);
+            let post = description("POST", true);
+
+            quote! { vec![#get, #post] }
+        }
+        RouteType::SubRoute | RouteType::CatchAll | RouteType::Include => {
+            let ty = match config.subroute_param() {
+                Some(field) => &field.ty,
+                None => match config.fields.iter().next() {
+                    Some(field) => &field.ty,
+                    None => return quote! { Vec::new() },
+                },
+            };
+            let prefix = match config.route_type {
+                RouteType::SubRoute => pattern.clone(),
+                _ => String::new(),
+            };
+            let host = match &config.host {
+                Some(host) => quote! { Some(#host) },
+                None => quote! { None },
+            };
+            let parent = description("", false);
+
+            quote! {
+                htmxology::RouteDescription::nested(
+                    <#ty as htmxology::Route>::describe(),
+                    #parent,
+                    #prefix,
+                    #host,
+                )
+            }
+        }
+    }
+}
+
+/// The context to generate the matching of paths against the routes of a route type.
+struct MatcherSet<'a> {
+    root_ident: &'a syn::Ident,
+    options: &'a RouteOptions,
+    include_types: &'a [&'a syn::Type],
+}
+
+impl MatcherSet<'_> {
+    /// Generate the matching of a path against an ordered list of route URLs and their matchers.
+    ///
+    /// The regexes are first matched together with a `RegexSet`, in a single pass over the path,
+    /// so that only the matchers of the matching regexes run, in order, with their own regex bound
+    /// to `RE` to extract their captures. This keeps route types with many variants from running
+    /// one regex per variant on each request.
+    ///
+    /// The patterns of the included route types are only known at runtime: if there are any, the
+    /// matchers and the include handlers are tried in the order of a `RouteOrder` instead, so that
+    /// included routes are matched as if they were declared in place.
+    fn generate(
+        &self,
+        matchers: Vec<(RouteUrl, bool, proc_macro2::TokenStream)>,
+        include_handlers: &[proc_macro2::TokenStream],
+        subroutes_first: bool,
+        path: proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
+        if !self.include_types.is_empty() {
+            return self.generate_ordered(matchers, include_handlers, subroutes_first, path);
+        }
+
+        if matchers.is_empty() {
+            return quote!();
+        }
+
+        let regexes: Vec<_> = matchers
+            .iter()
+            .map(|(url, ..)| self.options.path_regex(url))
+            .collect();
+        let matchers = regexes.iter().zip(matchers).enumerate().map(
+            |(index, (regex, (_, _, matcher)))| {
+                let index = proc_macro2::Literal::usize_unsuffixed(index);
+
+                quote! {
+                    if __candidates.matched(#index) {
+                        static RE: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| regex::Regex::new(#regex).unwrap());
+
+                        #matcher
+                    }
+                }
+            },
+        );
+
+        quote! {{
+            static SET: std::sync::LazyLock<regex::RegexSet> = std::sync::LazyLock::new(|| regex::RegexSet::new([#(#regexes),*]).unwrap());
+
+            let __candidates = SET.matches(#path);
+
+            #(#matchers)*
+        }}
+    }
+
+    /// Generate the matching of a path against the routes of a route type and of its included
+    /// route types, merged by a `RouteOrder`.
+    fn generate_ordered(
+        &self,
+        matchers: Vec<(RouteUrl, bool, proc_macro2::TokenStream)>,
+        include_handlers: &[proc_macro2::TokenStream],
+        subroutes_first: bool,
+        path: proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
+        let include_types = self.include_types;
+        let patterns = matchers.iter().map(|(url, subroute, _)| {
+            generate_route_pattern(&self.root_ident.to_string(), url, *subroute, self.options)
+        });
+        let matchers = matchers.iter().enumerate().map(|(index, (url, _, matcher))| {
+            let index = proc_macro2::Literal::usize_unsuffixed(index);
+            let regex = self.options.path_regex(url);
+
+            quote! {
+                htmxology::RouteMatch::Local(#index) => {
+                    static RE: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| regex::Regex::new(#regex).unwrap());
+
+                    #matcher
+                }
+            }
+        });
+        let include_handlers = include_handlers.iter().enumerate().map(|(index, handler)| {
+            let index = proc_macro2::Literal::usize_unsuffixed(index);
+
+            quote! {
+                htmxology::RouteMatch::Include(#index) => {
+                    #handler
+                }
+            }
+        });
+
+        quote! {{
+            static ORDER: std::sync::LazyLock<htmxology::RouteOrder> = std::sync::LazyLock::new(|| {
+                htmxology::RouteOrder::new(
+                    &[#(#patterns),*],
+                    &[#(<#include_types as htmxology::Route>::route_patterns()),*],
+                    #subroutes_first,
+                )
+            });
+
+            for __match in ORDER.matches(#path) {
+                match __match {
+                    #(#matchers)*
+                    #(#include_handlers)*
+                    _ => {}
+                }
+            }
+        }}
+    }
+}
+
+/// Generate a `RoutePattern` for a route URL of a variant.
+fn generate_route_pattern(
+    variant: &str,
+    url: &RouteUrl,
+    subroute: bool,
+    options: &RouteOptions,
+) -> proc_macro2::TokenStream {
+    let pattern = url.to_string();
+    let segments = url.to_pattern_segments();
+    let regex = options.path_regex(url);
+
+    quote! {
+        htmxology::RoutePattern {
+            variant: #variant,
+            pattern: #pattern,
+            segments: #segments,
+            regex: #regex,
+            subroute: #subroute,
+        }
+    }
+}
+
+/// Generate the `route_patterns` method, listing the route patterns of the route type, followed by
+/// the ones of its included route types.
+fn generate_route_patterns(
+    patterns: &[proc_macro2::TokenStream],
+    include_types: &[&syn::Type],
+) -> proc_macro2::TokenStream {
+    if include_types.is_empty() {
+        return quote! {
+            fn route_patterns() -> Vec<htmxology::RoutePattern> {
+                vec![#(#patterns),*]
+            }
+        };
+    }
+
+    quote! {
+        fn route_patterns() -> Vec<htmxology::RoutePattern> {
+            let mut __patterns = vec![#(#patterns),*];
+
+            #(__patterns.extend(<#include_types as htmxology::Route>::route_patterns());)*
+
+            __patterns
+        }
+    }
+}
+
+/// Generate the check of the routes of the included route types against the other routes, which
+/// can only run once the included route types are known.
+///
+/// The check runs once, when building the route table in debug builds.
+fn generate_check_route_patterns(
+    patterns: &[proc_macro2::TokenStream],
+    include_types: &[&syn::Type],
+) -> proc_macro2::TokenStream {
+    if include_types.is_empty() {
+        return quote!();
+    }
+
+    quote! {
+        #[cfg(debug_assertions)]
+        {
+            static CHECK: std::sync::Once = std::sync::Once::new();
+
+            CHECK.call_once(|| {
+                htmxology::check_route_patterns(
+                    &[#(#patterns),*],
+                    &[#(<#include_types as htmxology::Route>::route_patterns()),*],
+                );
+            });
+        }
+    }
+}
+
+/// Generate the expression matching a path against the `RE` regex of a route URL.
+///
+/// Path parameter constraints that can't be expressed in the regex are checked on the captures, so
+/// that paths violating them don't match the route at all.
+fn generate_captures(url: &RouteUrl, path: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    match url.to_captures_filter() {
+        Some(filter) => quote! { RE.captures(#path).filter(#filter) },
+        None => quote! { RE.captures(#path) },
+    }
+}
+
+/// Generate the result of a request matching a route alias, from the parsing of the route.
+///
+/// Redirecting aliases parse the route anyway, to redirect to its canonical URL.
+fn generate_alias_result(
+    alias: &RouteAlias,
+    handler: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    if alias.redirect {
+        quote! {
+            {
+                // Parsing the body of the route consumes the request.
+                let __alias_method = __req.method().clone();
+                let __route = #handler;
+
+                Err(htmxology::RouteExt::as_permanent_redirect_response(
+                    &__route,
+                    &__alias_method,
+                ))
+            }
+        }
+    } else {
+        quote! { Ok(#handler) }
+    }
+}
+
+/// The route URL, route type, target and static fragment of a variant.
+type RouteInfo = (RouteUrl, RouteType, Option<String>, Option<String>);
+
+fn parse_route_info(variant: &Variant) -> syn::Result<RouteInfo> {
+    let mut result = None;
+    let expected = format!(
+        "`{}`, `{}` or `{}` attribute",
+        attributes::ROUTE,
+        attributes::CATCH_ALL,
+        attributes::ROUTE_INCLUDE
+    );
+
+    for attr in &variant.attrs {
+        if attr.path().is_ident(attributes::ROUTE) {
+            if result.is_some() {
+                return Err(Error::new_spanned(
+                    attr,
+                    format!("expected exactly one {expected}"),
+                ));
+            }
+
+            let mut exprs = attr
+                .parse_args_with(Punctuated::<syn::Expr, Token![,]>::parse_terminated)?
+                .into_iter();
+
+            let raw_url = exprs.next().ok_or_else(|| {
+                Error::new_spanned(attr, "expected a route URL as the first argument")
+            })?;
+
+            let (url, fragment) = parse_route_url(raw_url)?;
+            let mut route_type = None;
+            let mut target = None;
+
+            for expr in exprs {
+                if let Some(value) = parse_target(&expr)? {
+                    if target.is_some() {
+                        return Err(Error::new_spanned(
+                            expr,
+                            format!("duplicate `{}` argument", attributes::TARGET),
+                        ));
+                    }
+
+                    target = Some(value);
+                } else if route_type.is_none() && !url.is_prefix() {
+                    route_type = Some(parse_method(expr)?);
+                } else {
+                    return Err(Error::new_spanned(attr, "expected at most two arguments"));
+                }
+            }
+
+            if url.is_prefix() && fragment.is_some() {
+                return Err(Error::new_spanned(
+                    attr,
+                    "subroute variants can't have a fragment, which is the one of their subroute",
+                ));
+            }
+
+            let route_type = if url.is_prefix() {
+                RouteType::SubRoute
+            } else {
+                route_type.unwrap_or(RouteType::Simple {
+                    method: http::Method::GET,
+                })
+            };
+
+            result = Some((url, route_type, target, fragment));
+        } else if let Some((name, route_type)) = [
+            (attributes::CATCH_ALL, RouteType::CatchAll),
+            (attributes::ROUTE_INCLUDE, RouteType::Include),
+        ]
+        .into_iter()
+        .find(|(name, _)| attr.path().is_ident(name))
+        {
+            if result.is_some() {
+                return Err(Error::new_spanned(
+                    attr,
+                    format!("expected exactly one {expected}"),
+                ));
+            }
+
+            if !matches!(attr.meta, syn::Meta::Path(_)) {
+                return Err(Error::new_spanned(
+                    attr,
+                    format!("`{name}` attribute does not take any arguments"),
+                ));
+            }
+
+            result = Some((RouteUrl::default(), route_type, None, None));
+        }
+    }
+
+    result.ok_or_else(|| Error::new_spanned(variant, format!("expected one {expected}")))
+}
+
+/// Parse a `target = "#..."` argument, returning `None` for other arguments.
+///
+/// As HTMX only sends the `id` of the target element in the `HX-Target` header, the target must
+/// be an id selector.
+fn parse_target(expr: &Expr) -> syn::Result<Option<String>> {
+    let Expr::Assign(assign) = expr else {
+        return Ok(None);
+    };
+
+    let Expr::Path(left) = &*assign.left else {
+        return Ok(None);
+    };
+
+    if !left.path.is_ident(attributes::TARGET) {
+        return Ok(None);
+    }
+
+    let value = parse_raw_url(&assign.right)?;
+    let is_valid = value.strip_prefix('#').is_some_and(|id| {
+        let mut chars = id.chars();
+        let first = chars.next();
+        let second = chars.next();
+
+        let starts_with_digit = match (first, second) {
+            (Some('-'), Some(c)) | (Some(c), _) => c.is_ascii_digit(),
+            (None, _) => true,
+        };
+
+        !starts_with_digit
+            && id != "-"
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii())
+    });
+
+    if !is_valid {
+        return Err(Error::new_spanned(
+            &assign.right,
+            format!(
+                "invalid route target `{value}`: expected an id selector like `#food-list`, as HTMX only sends the `id` of the target element in `HX-Target`"
+            ),
+        ));
+    }
+
+    Ok(Some(value))
+}
+
+fn parse_raw_url(expr: &Expr) -> syn::Result<String> {
+    match expr {
+        Expr::Lit(expr) => match expr.lit {
+            syn::Lit::Str(ref lit_str) => Ok(lit_str.value()),
+            _ => Err(Error::new_spanned(
+                expr.lit.clone(),
+                "expected a string literal",
+            )),
+        },
+        _ => Err(Error::new_spanned(expr, "expected a string literal")),
+    }
+}
+
+/// Parse a route URL, and the static fragment following its `#`, if any.
+fn parse_route_url(expr: Expr) -> syn::Result<(RouteUrl, Option<String>)> {
+    let raw_url = parse_raw_url(&expr)?;
+    let (url, fragment) = match raw_url.split_once('#') {
+        Some((url, fragment)) => {
+            if fragment.is_empty()
+                || !fragment
+                    .chars()
+                    .all(|c| c == '?' || is_valid_url_path_character(c))
+            {
+                return Err(Error::new_spanned(
+                    expr,
+                    format!("invalid route fragment `{fragment}`"),
+                ));
+            }
+
+            (url, Some(fragment.to_owned()))
+        }
+        None => (raw_url.as_str(), None),
+    };
+
+    let url = url.parse().map_err(|err: ParseError| {
+        Error::new_spanned(&expr, format!("{err}\n{}", err.detail(url)))
+    })?;
+
+    Ok((url, fragment))
+}
+
+/// Parse a `method = "..."` argument.
+///
+/// The special `GET|POST` value declares a form route.
+fn parse_method(expr: Expr) -> syn::Result<RouteType> {
+    match expr {
+        Expr::Assign(expr) => {
+            let left = match *expr.left {
+                Expr::Path(expr) => expr.path.require_ident()?.to_string(),
+                expr => {
+                    return Err(Error::new_spanned(expr, "expected path"));
+                }
+            };
+
+            match left.as_str() {
+                attributes::METHOD => match *expr.right {
+                    Expr::Lit(expr) => match expr.lit {
+                        syn::Lit::Str(ref lit_str) if lit_str.value() == FORM_METHODS => {
+                            Ok(RouteType::Form)
+                        }
+                        syn::Lit::Str(ref lit_str) if lit_str.value().contains('|') => {
+                            Err(Error::new_spanned(
+                                lit_str,
+                                format!("only `{FORM_METHODS}` can combine several methods"),
+                            ))
+                        }
+                        syn::Lit::Str(ref lit_str) => {
+                            parse_method_name(lit_str).map(|method| RouteType::Simple { method })
+                        }
+                        _ => Err(Error::new_spanned(expr, "expected string literal")),
+                    },
+                    expr => Err(Error::new_spanned(expr, "expected path")),
+                },
+                _ => Err(Error::new_spanned(
+                    left,
+                    format!("expected `{}`", attributes::METHOD),
+                )),
+            }
+        }
+        _ => Err(Error::new_spanned(
+            expr,
+            format!("expected `{} = \"<GET|POST|...>\"`", attributes::METHOD),
+        )),
+    }
+}
+
+/// Parse a HTTP method name.
+///
+/// Any valid method token is accepted, including extension methods like the WebDAV `PROPFIND` or
+/// `REPORT` verbs. Since methods are case-sensitive, a differently-cased standard method is
+/// rejected rather than silently treated as an extension method.
+fn parse_method_name(lit_str: &syn::LitStr) -> syn::Result<http::Method> {
+    let value = lit_str.value();
+    let method = http::Method::from_bytes(value.as_bytes())
+        .map_err(|_| Error::new_spanned(lit_str, format!("invalid HTTP method `{value}`")))?;
+
+    if method.standard_ident().is_none() {
+        let uppercase = value.to_ascii_uppercase();
+
+        if let Ok(standard) = http::Method::from_bytes(uppercase.as_bytes())
+            && standard.standard_ident().is_some()
+        {
+            return Err(Error::new_spanned(
+                lit_str,
+                format!("HTTP methods are case-sensitive: did you mean `{uppercase}`?"),
+            ));
+        }
+    }
+
+    Ok(method)
+}
+
+#[cfg(test)]
+mod snapshot_tests {
+    use super::*;
+    use crate::utils::testing::test_derive;
+    use insta::assert_snapshot;
+
+    fn test_route_derive(input: &str) -> String {
+        test_derive(input, derive)
+    }
+
+    #[test]
+    fn unit_variant_get() {
+        let input = r#"
+            enum MyRoute {
+                #[route("")]
+                Home,
+            }
+        "#;
+        assert_snapshot!(test_route_derive(input));
+    }
+
+    #[test]
+    fn route_opts() {
+        let input = r#"
+            #[route_opts(no_from_str, no_display)]
+            enum MyRoute {
+                #[route("")]
+                Home,
+                #[route("items/{id}")]
+                Item {
+                    id: u32,
+                    #[query]
+                    query: ItemQuery,
+                },
+            }
+        "#;
+        assert_snapshot!(test_route_derive(input));
+    }
+
+    #[test]
+    fn case_insensitive_route_opts() {
+        let input = r#"
+            #[route_opts(case_insensitive)]
+            enum MyRoute {
+                #[route("")]
+                Home,
+                #[route("Summer-Sale/{code}")]
+                Promo { code: String },
+                #[route("Blog/")]
+                Blog(#[subroute] BlogRoute),
+            }
+        "#;
+        assert_snapshot!(test_route_derive(input));
+    }
+
+    #[test]
+    fn invalid_route_opts() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            #[route_opts(no_debug)]
+            enum MyRoute {
+                #[route("")]
+                Home,
+            }
+        "#,
+        )
+        .unwrap();
+
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            "expected `no_from_str`, `no_display` or `case_insensitive`"
+        );
+    }
+
+    #[test]
+    fn extracted_fields() {
+        let input = r#"
+            enum MyRoute {
+                #[route("")]
+                Home,
+                #[route("dashboard/{id}")]
+                Dashboard {
+                    id: u32,
+                    #[extract]
+                    user: User,
+                },
+                #[route("settings", method = "GET|POST")]
+                Settings(#[extract] User, #[body] Option<SettingsForm>),
+            }
+        "#;
+        assert_snapshot!(test_route_derive(input));
+    }
+
+    #[test]
+    fn invalid_extracted_subroute() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("admin/")]
+                Admin {
+                    #[subroute]
+                    #[extract]
+                    subroute: AdminRoute,
+                },
+            }
+        "#,
+        )
+        .unwrap();
+
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            "field cannot have multiple role attributes (query, body, subroute, extract, fragment_anchor)"
+        );
+
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[catch_all]
+                Other(#[extract] User),
+            }
+        "#,
+        )
+        .unwrap();
+
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            "extract attribute can only be used on simple and form route variants"
+        );
+    }
+
+    #[test]
+    fn unit_variant_post() {
+        let input = r#"
+            enum MyRoute {
+                #[route("submit", method = "POST")]
+                Submit,
+            }
+        "#;
+        assert_snapshot!(test_route_derive(input));
+    }
+
+    #[test]
+    fn custom_methods() {
+        let input = r#"
+            enum MyRoute {
+                #[route("calendar/{id}", method = "PROPFIND")]
+                Properties { id: u32 },
+                #[route("calendar/{id}", method = "REPORT")]
+                Report { id: u32 },
+                #[route("calendar/{id}", method = "POST")]
+                Update { id: u32 },
+            }
+        "#;
+        assert_snapshot!(test_route_derive(input));
+    }
+
+    #[test]
+    fn invalid_method_case() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("submit", method = "post")]
+                Submit,
+            }
+        "#,
+        )
+        .unwrap();
+
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            "HTTP methods are case-sensitive: did you mean `POST`?"
+        );
+    }
+
+    #[test]
+    fn form_route() {
+        let input = r#"
+            enum MyRoute {
+                #[route("settings", method = "GET|POST")]
+                Settings {
+                    #[body]
+                    form: Option<SettingsForm>,
+                },
+                #[route("items/{id}/edit", method = "GET|POST")]
+                EditItem(u32, #[body] Option<ItemForm>),
+            }
+        "#;
+        assert_snapshot!(test_route_derive(input));
+    }
+
+    #[test]
+    fn rest_path_parameters() {
+        let input = r#"
+            enum MyRoute {
+                #[route("files/{*path}")]
+                File { path: String },
+                #[route("tree/{id}/{*segments}")]
+                Tree(u32, Vec<String>),
+            }
+        "#;
+        assert_snapshot!(test_route_derive(input));
+    }
+
+    #[test]
+    fn form_route_without_optional_body() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("settings", method = "GET|POST")]
+                Settings {
+                    #[body]
+                    form: SettingsForm,
+                },
+            }
+        "#,
+        )
+        .unwrap();
+
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            "`GET|POST` routes require an `Option<...>` `#[body]` field, which is `None` for `GET` requests"
+        );
+    }
+
+    #[test]
+    fn coalesced_routes() {
+        let input = r#"
+            enum MyRoute {
+                #[route("")]
+                Home,
+                #[route("reports/{id}")]
+                #[coalesce]
+                Report { id: u32 },
+                #[route("admin/")]
+                Admin(#[subroute] AdminRoute),
+            }
+        "#;
+        assert_snapshot!(test_route_derive(input));
+    }
+
+    #[test]
+    fn included_routes() {
+        let input = r#"
+            enum MyRoute {
+                #[route("")]
+                Home,
+                #[route_include]
+                Blog(blog::BlogRoute),
+                #[route_include]
+                Admin(admin::AdminRoute),
+            }
+        "#;
+        assert_snapshot!(test_route_derive(input));
+    }
+
+    #[test]
+    fn include_named_fields() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route_include]
+                Blog { route: BlogRoute },
+            }
+        "#,
+        )
+        .unwrap();
+
+        assert!(derive(&mut input).is_err());
+    }
+
+    #[test]
+    fn coalesce_non_get_route() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("reports", method = "POST")]
+                #[coalesce]
+                CreateReport,
+            }
+        "#,
+        )
+        .unwrap();
+
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            "`coalesce` attribute can only be used on `GET` routes"
+        );
+    }
+
+    #[test]
+    fn unsupported_method_combination() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("settings", method = "GET|PUT")]
+                Settings,
+            }
+        "#,
+        )
+        .unwrap();
+
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            "only `GET|POST` can combine several methods"
+        );
+    }
+
+    #[test]
+    fn duplicate_body_content_type() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("submit", method = "POST")]
+                Submit {
+                    #[body]
+                    #[body(any("application/json", "Application/X-WWW-Form-URLEncoded"))]
+                    data: FormData,
+                },
+            }
+        "#,
+        )
+        .unwrap();
+
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            "duplicate body content type `application/x-www-form-urlencoded`"
+        );
+    }
+
+    #[test]
+    fn combined_multipart_body_content_type() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("submit", method = "POST")]
+                Submit {
+                    #[body(any("multipart/form-data", "application/json"))]
+                    data: FormData,
+                },
+            }
+        "#,
+        )
+        .unwrap();
+
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            "`multipart/form-data` bodies can't be combined with other content types"
+        );
+    }
+
+    #[test]
+    fn invalid_body_attribute() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("submit", method = "POST")]
+                Submit {
+                    #[body(all("application/json"))]
+                    data: FormData,
+                },
+            }
+        "#,
+        )
+        .unwrap();
+
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            r#"expected `#[body]`, `#[body("<content-type>")]` or `#[body(any("<content-type>", ...))]`"#
+        );
+    }
+
+    #[test]
+    fn unsupported_body_content_type() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("submit", method = "POST")]
+                Submit {
+                    #[body("text/plain")]
+                    data: String,
+                },
+            }
+        "#,
+        )
+        .unwrap();
 
-            let route_type = if url.is_prefix() {
-                RouteType::SubRoute
-            } else {
-                let method = match exprs.next() {
-                    Some(raw_method) => parse_method(raw_method)?,
-                    None => http::Method::GET,
-                };
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            "unsupported body content type `text/plain`: only `application/x-www-form-urlencoded`, `multipart/form-data` and `application/json` are supported"
+        );
+    }
 
-                RouteType::Simple { method }
-            };
+    #[test]
+    fn param_constraints() {
+        let input = r#"
+            enum MyRoute {
+                #[route("items/{id:u32:1..}")]
+                Item { id: u32 },
 
-            if exprs.next().is_none() {
-                result = Some((url, route_type));
-            } else {
-                return Err(Error::new_spanned(attr, "expected at most two arguments"));
+                #[route("items/{slug}")]
+                #[param(slug, regex = "[0-9a-f]{8}")]
+                ItemBySlug { slug: String },
             }
-        } else if attr.path().is_ident(attributes::CATCH_ALL) {
-            if result.is_some() {
-                return Err(Error::new_spanned(
-                    attr,
-                    format!(
-                        "expected exactly one `{}` or `{}` attribute",
-                        attributes::ROUTE,
-                        attributes::CATCH_ALL
-                    ),
-                ));
+        "#;
+        assert_snapshot!(test_route_derive(input));
+    }
+
+    #[test]
+    fn invalid_param_constraint() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("items/{id:u8:1..300}")]
+                Item { id: u8 },
             }
+        "#,
+        )
+        .unwrap();
 
-            if !matches!(attr.meta, syn::Meta::Path(_)) {
-                return Err(Error::new_spanned(
-                    attr,
-                    format!(
-                        "`{}` attribute does not take any arguments",
-                        attributes::CATCH_ALL
-                    ),
-                ));
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            "the path parameter has an invalid constraint: the range `1..300` is out of bounds for `u8`\nitems/{id:^u8:1..300^}"
+        );
+    }
+
+    #[test]
+    fn param_regex_with_capture_group() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("items/{slug}")]
+                #[param(slug, regex = "(a|b)+")]
+                Item { slug: String },
             }
+        "#,
+        )
+        .unwrap();
 
-            result = Some((RouteUrl::default(), RouteType::CatchAll));
-        }
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            "path parameter regexes can't contain capture groups, use `(?:...)` instead"
+        );
     }
 
-    result.ok_or_else(|| {
-        Error::new_spanned(
-            variant,
-            format!(
-                "expected one `{}` or `{}` attribute",
-                attributes::ROUTE,
-                attributes::CATCH_ALL
-            ),
+    #[test]
+    fn param_regex_unknown_param() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("items/{slug}")]
+                #[param(id, regex = "[0-9]+")]
+                Item { slug: String },
+            }
+        "#,
         )
-    })
-}
+        .unwrap();
 
-fn parse_raw_url(expr: &Expr) -> syn::Result<String> {
-    match expr {
-        Expr::Lit(expr) => match expr.lit {
-            syn::Lit::Str(ref lit_str) => Ok(lit_str.value()),
-            _ => Err(Error::new_spanned(
-                expr.lit.clone(),
-                "expected a string literal",
-            )),
-        },
-        _ => Err(Error::new_spanned(expr, "expected a string literal")),
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(err.to_string(), "the route URL has no path parameter `id`");
     }
-}
 
-fn parse_route_url(expr: Expr) -> syn::Result<RouteUrl> {
-    let url = parse_raw_url(&expr)?;
+    #[test]
+    fn encoded_path_params() {
+        let input = r#"
+            enum MyRoute {
+                #[route("pages/{slug}/{version}")]
+                #[param(slug, encoded)]
+                Page { slug: String, version: String },
 
-    url.parse()
-        .map_err(|err: ParseError| Error::new_spanned(expr, format!("{err}\n{}", err.detail(&url))))
-}
+                #[route("tags/{tag}")]
+                #[param(tag, regex = "[a-z%0-9]+", encoded)]
+                Tag(String),
+            }
+        "#;
+        assert_snapshot!(test_route_derive(input));
+    }
 
-fn parse_method(expr: Expr) -> syn::Result<http::Method> {
-    match expr {
-        Expr::Assign(expr) => {
-            let left = match *expr.left {
-                Expr::Path(expr) => expr.path.require_ident()?.to_string(),
-                expr => {
-                    return Err(Error::new_spanned(expr, "expected path"));
-                }
-            };
+    #[test]
+    fn encoded_rest_param() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("files/{*path}")]
+                #[param(path, encoded)]
+                File { path: String },
+            }
+        "#,
+        )
+        .unwrap();
 
-            match left.as_str() {
-                attributes::METHOD => match *expr.right {
-                    Expr::Lit(expr) => match expr.lit {
-                        syn::Lit::Str(ref lit_str) => lit_str
-                            .value()
-                            .parse()
-                            .map_err(|_| Error::new_spanned(expr, "invalid HTTP method")),
-                        _ => Err(Error::new_spanned(expr, "expected string literal")),
-                    },
-                    expr => Err(Error::new_spanned(expr, "expected path")),
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            "the rest path parameter `path` is encoded and decoded by its `RestPath` implementation"
+        );
+    }
+
+    #[test]
+    fn param_without_arguments() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("items/{slug}")]
+                #[param(slug)]
+                Item { slug: String },
+            }
+        "#,
+        )
+        .unwrap();
+
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(err.to_string(), "expected `regex` or `encoded`");
+    }
+
+    #[test]
+    fn fragment_anchors() {
+        let input = r#"
+            enum MyRoute {
+                #[route("docs/{page}#install")]
+                Install { page: String },
+
+                #[route("docs/{page}/sections")]
+                Section {
+                    page: String,
+                    #[fragment_anchor]
+                    section: Option<String>,
                 },
-                _ => Err(Error::new_spanned(
-                    left,
-                    format!("expected `{}`", attributes::METHOD),
-                )),
+
+                #[route("changelog")]
+                Changelog(#[fragment_anchor] Version),
             }
-        }
-        _ => Err(Error::new_spanned(
-            expr,
-            format!("expected `{} = \"<GET|POST|...>\"`", attributes::METHOD),
-        )),
+        "#;
+        assert_snapshot!(test_route_derive(input));
     }
-}
 
-#[cfg(test)]
-mod snapshot_tests {
-    use super::*;
-    use crate::utils::testing::test_derive;
-    use insta::assert_snapshot;
+    #[test]
+    fn fragment_anchor_with_static_fragment() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("docs#install")]
+                Docs {
+                    #[fragment_anchor]
+                    section: String,
+                },
+            }
+        "#,
+        )
+        .unwrap();
 
-    fn test_route_derive(input: &str) -> String {
-        test_derive(input, derive)
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            "a route can't have both a fragment in its URL and a fragment anchor field"
+        );
     }
 
     #[test]
-    fn unit_variant_get() {
+    fn fragment_on_subroute() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("docs/#install")]
+                Docs(#[subroute] DocsRoute),
+            }
+        "#,
+        )
+        .unwrap();
+
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            "subroute variants can't have a fragment, which is the one of their subroute"
+        );
+    }
+
+    #[test]
+    fn invalid_fragment() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("docs#first step")]
+                Docs,
+            }
+        "#,
+        )
+        .unwrap();
+
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(err.to_string(), "invalid route fragment `first step`");
+    }
+
+    #[test]
+    fn route_aliases() {
         let input = r#"
             enum MyRoute {
-                #[route("")]
-                Home,
+                #[route("articles/{id}")]
+                #[route_alias("posts/{id}")]
+                #[route_alias("blog/{id}.html", redirect)]
+                Article { id: u32 },
+
+                #[route("settings", method = "GET|POST")]
+                #[route_alias("preferences", redirect)]
+                Settings {
+                    #[body]
+                    form: Option<SettingsForm>,
+                },
             }
         "#;
         assert_snapshot!(test_route_derive(input));
     }
 
+    fn route_alias_error(input: &str) -> String {
+        let mut input: syn::DeriveInput = syn::parse_str(input).unwrap();
+
+        derive(&mut input)
+            .expect_err("derive should fail")
+            .to_string()
+    }
+
     #[test]
-    fn unit_variant_post() {
+    fn invalid_route_aliases() {
+        assert_eq!(
+            route_alias_error(
+                r#"
+                enum MyRoute {
+                    #[route("articles/{id}/{page}")]
+                    #[route_alias("posts/{page}/{id}")]
+                    Article { id: u32, page: u32 },
+                }
+            "#
+            ),
+            "route alias `/posts/{page}/{id}` must have the same path parameters as `/articles/{id}/{page}`, in the same order"
+        );
+        assert_eq!(
+            route_alias_error(
+                r#"
+                enum MyRoute {
+                    #[route("articles/")]
+                    #[route_alias("posts/")]
+                    Articles(#[subroute] ArticleRoute),
+                }
+            "#
+            ),
+            "`route_alias` attribute can only be used on simple and form route variants"
+        );
+        assert_eq!(
+            route_alias_error(
+                r#"
+                enum MyRoute {
+                    #[route("articles")]
+                    #[route_alias("posts#top")]
+                    Articles,
+                }
+            "#
+            ),
+            "route aliases can't have a fragment"
+        );
+        assert_eq!(
+            route_alias_error(
+                r#"
+                enum MyRoute {
+                    #[route("articles")]
+                    #[route_alias("posts", permanent)]
+                    Articles,
+                }
+            "#
+            ),
+            "expected `redirect`"
+        );
+        assert_eq!(
+            route_alias_error(
+                r#"
+                enum MyRoute {
+                    #[route("articles")]
+                    #[route_alias("articles")]
+                    Articles,
+                }
+            "#
+            ),
+            "route alias `/articles` is the route URL itself"
+        );
+    }
+
+    #[test]
+    fn conflicting_route_alias() {
+        assert_eq!(
+            route_alias_error(
+                r#"
+                enum MyRoute {
+                    #[route("news")]
+                    News,
+
+                    #[route("articles")]
+                    #[route_alias("news")]
+                    Articles,
+                }
+            "#
+            ),
+            "duplicate route `GET /news`: it is already declared by `News`"
+        );
+    }
+
+    #[test]
+    fn builder() {
         let input = r#"
-            enum MyRoute {
-                #[route("submit", method = "POST")]
-                Submit,
+            pub enum MyRoute {
+                #[route("users/{user_id}/posts")]
+                #[builder]
+                UserPosts {
+                    user_id: u32,
+                    #[query]
+                    query: PostsQuery,
+                },
             }
         "#;
         assert_snapshot!(test_route_derive(input));
     }
 
+    #[test]
+    fn builder_on_unit_variant() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("")]
+                #[builder]
+                Home,
+            }
+        "#,
+        )
+        .unwrap();
+
+        let err = derive(&mut input).expect_err("derive should fail");
+        assert_eq!(
+            err.to_string(),
+            "`builder` attribute can only be used on route variants with named fields"
+        );
+    }
+
     #[test]
     fn named_single_path_param() {
         let input = r#"
@@ -704,6 +2559,208 @@ mod snapshot_tests {
         assert_snapshot!(test_route_derive(input));
     }
 
+    #[test]
+    fn multipart_body_param() {
+        let input = r#"
+            enum MyRoute {
+                #[route("avatar", method = "POST")]
+                UploadAvatar {
+                    #[body("multipart/form-data")]
+                    upload: htmxology::MultipartForm<AvatarForm>,
+                },
+            }
+        "#;
+        assert_snapshot!(test_route_derive(input));
+    }
+
+    #[test]
+    fn route_target() {
+        let input = r##"
+            enum MyRoute {
+                #[route("")]
+                Home,
+                #[route("foods", target = "#food-list")]
+                Foods,
+                #[route("foods", method = "POST", target = "#food-list")]
+                AddFood(#[body] NewFood),
+                #[route("settings/", target = "#settings")]
+                Settings(#[subroute] SettingsRoute),
+            }
+        "##;
+        assert_snapshot!(test_route_derive(input));
+    }
+
+    #[test]
+    fn invalid_route_target() {
+        for (target, expected) in [
+            (
+                r#""food-list""#,
+                "invalid route target `food-list`: expected an id selector like `#food-list`, as HTMX only sends the `id` of the target element in `HX-Target`",
+            ),
+            (
+                r##""#1st""##,
+                "invalid route target `#1st`: expected an id selector like `#food-list`, as HTMX only sends the `id` of the target element in `HX-Target`",
+            ),
+            (
+                r##""#list .item""##,
+                "invalid route target `#list .item`: expected an id selector like `#food-list`, as HTMX only sends the `id` of the target element in `HX-Target`",
+            ),
+            (
+                r##""#list", target = "#other""##,
+                "duplicate `target` argument",
+            ),
+        ] {
+            let mut input: syn::DeriveInput = syn::parse_str(&format!(
+                r#"
+                enum MyRoute {{
+                    #[route("foods", target = {target})]
+                    Foods,
+                }}
+            "#
+            ))
+            .unwrap();
+
+            let err = derive(&mut input).expect_err("derive should fail");
+            assert_eq!(err.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn conflicting_routes() {
+        for (variants, expected) in [
+            (
+                r#"
+                #[route("users/")]
+                Users(#[subroute] UserRoute),
+                #[route("users/new")]
+                NewUser,
+                "#,
+                [
+                    "the route `/users/new` of `NewUser` is unreachable, as all its paths are matched first by the subroute prefix `/users` of `Users`",
+                    "`NewUser` conflicts with this variant",
+                ],
+            ),
+            (
+                r#"
+                #[route("users/{id}")]
+                User { id: u32 },
+                #[route("users/{slug}", method = "DELETE")]
+                DeleteUser { slug: String },
+                "#,
+                [
+                    "the route `/users/{id}` of `User` is unreachable, as all its paths are matched first by the route `/users/{slug}` of `DeleteUser`",
+                    "`User` conflicts with this variant",
+                ],
+            ),
+            (
+                r#"
+                #[route("{*path}")]
+                Page { path: String },
+                #[route("")]
+                Home,
+                "#,
+                [
+                    "the route `/` of `Home` is unreachable, as all its paths are matched first by the route `/{*path}` of `Page`",
+                    "`Home` conflicts with this variant",
+                ],
+            ),
+            (
+                r#"
+                #[route("settings", method = "GET|POST")]
+                Settings { #[body] form: Option<SettingsForm> },
+                #[route("settings", method = "POST")]
+                SaveSettings { #[body] form: SettingsForm },
+                "#,
+                [
+                    "duplicate route `POST /settings`: it is already declared by `Settings`",
+                    "`SaveSettings` conflicts with this variant",
+                ],
+            ),
+            (
+                r#"
+                #[catch_all]
+                NotFound(NotFoundRoute),
+                #[catch_all]
+                Fallback(FallbackRoute),
+                "#,
+                [
+                    "duplicate catch-all variant: `NotFound` is already the catch-all variant",
+                    "`Fallback` conflicts with this variant",
+                ],
+            ),
+        ] {
+            let mut input: syn::DeriveInput =
+                syn::parse_str(&format!("enum MyRoute {{ {variants} }}")).unwrap();
+
+            let err = derive(&mut input).expect_err("derive should fail");
+            assert_eq!(
+                err.into_iter()
+                    .map(|err| err.to_string())
+                    .collect::<Vec<_>>(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn overlapping_routes() {
+        // More specific routes are matched first, so overlapping routes are fine as long as each
+        // of them can still be reached.
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            enum MyRoute {
+                #[route("users/{id}")]
+                User { id: String },
+                #[route("users/new")]
+                NewUser,
+                #[route("users/{id:u32}", method = "DELETE")]
+                DeleteUser { id: u32 },
+                #[route("users/{id}/")]
+                UserSettings { id: String, #[subroute] route: SettingsRoute },
+                #[route("users", method = "POST")]
+                CreateUser,
+                #[route("{*path}")]
+                Page { path: String },
+            }
+        "#,
+        )
+        .unwrap();
+
+        assert!(derive(&mut input).is_ok());
+    }
+
+    #[test]
+    fn json_body_param() {
+        let input = r#"
+            enum MyRoute {
+                #[route("")]
+                Home,
+                #[route("api/items", method = "POST")]
+                CreateItem(#[body("application/json")] NewItem),
+                #[route("settings", method = "GET|POST")]
+                Settings {
+                    #[body("application/json")]
+                    settings: Option<Settings>,
+                },
+            }
+        "#;
+        assert_snapshot!(test_route_derive(input));
+    }
+
+    #[test]
+    fn negotiated_body_param() {
+        let input = r#"
+            enum MyRoute {
+                #[route("api/items", method = "POST")]
+                CreateItem {
+                    #[body(any("application/json", "application/x-www-form-urlencoded"))]
+                    item: NewItem,
+                },
+            }
+        "#;
+        assert_snapshot!(test_route_derive(input));
+    }
+
     #[test]
     fn unnamed_body_param() {
         let input = r#"
@@ -784,6 +2841,22 @@ mod snapshot_tests {
         assert_snapshot!(test_route_derive(input));
     }
 
+    #[test]
+    fn host_catch_all() {
+        let input = r#"
+            enum MyRoute {
+                #[route("")]
+                Home,
+                #[host("admin.{base}")]
+                #[catch_all]
+                Admin(AdminRoute),
+                #[catch_all]
+                NotFound(NotFoundRoute),
+            }
+        "#;
+        assert_snapshot!(test_route_derive(input));
+    }
+
     #[test]
     fn full_application() {
         let input = r#"
@@ -5,8 +5,6 @@ use quote::quote;
 use syn::Ident;
 
 /// A route info.
-///
-/// This is a subset of the standard HTTP methods, that are commonly used in web applications.
 #[derive(Debug, Clone)]
 pub enum RouteType {
     /// A simple HTTP route.
@@ -15,38 +13,58 @@ pub enum RouteType {
         method: http::Method,
     },
 
+    /// A form route, rendering a form on `GET` and submitting it on `POST`.
+    ///
+    /// The body field of the variant is an `Option`, which is `None` for `GET` requests.
+    Form,
+
     /// A prefixed sub-route.
     SubRoute,
 
     /// A catch-all route.
     CatchAll,
+
+    /// A route type whose variants are matched as if they were declared in place.
+    Include,
 }
 
 /// An extension trait for `http::Method`.
 pub(crate) trait MethodExt {
-    /// Convert the method to an identifier.
-    fn to_ident(&self) -> TokenStream;
+    /// Get the identifier of the associated constant on `http::Method`, if the method is a
+    /// standard one.
+    fn standard_ident(&self) -> Option<Ident>;
+
+    /// Convert the method to an expression that evaluates to a `http::Method`.
+    fn to_expr(&self) -> TokenStream;
 }
 
 impl MethodExt for http::Method {
-    fn to_ident(&self) -> TokenStream {
-        let method = Ident::new(
-            match self {
-                &Self::GET => "GET",
-                &Self::POST => "POST",
-                &Self::PUT => "PUT",
-                &Self::DELETE => "DELETE",
-                &Self::HEAD => "HEAD",
-                &Self::OPTIONS => "OPTIONS",
-                &Self::CONNECT => "CONNECT",
-                &Self::PATCH => "PATCH",
-                &Self::TRACE => "TRACE",
-                method => method.as_str(),
-            },
-            Span::call_site(),
-        );
+    fn standard_ident(&self) -> Option<Ident> {
+        let ident = match *self {
+            Self::GET => "GET",
+            Self::POST => "POST",
+            Self::PUT => "PUT",
+            Self::DELETE => "DELETE",
+            Self::HEAD => "HEAD",
+            Self::OPTIONS => "OPTIONS",
+            Self::CONNECT => "CONNECT",
+            Self::PATCH => "PATCH",
+            Self::TRACE => "TRACE",
+            _ => return None,
+        };
 
-        quote! { #method }
+        Some(Ident::new(ident, Span::call_site()))
+    }
+
+    fn to_expr(&self) -> TokenStream {
+        match self.standard_ident() {
+            Some(ident) => quote! { http::Method::#ident },
+            None => {
+                let method = syn::LitByteStr::new(self.as_str().as_bytes(), Span::call_site());
+
+                quote! { http::Method::from_bytes(#method).expect("valid HTTP method") }
+            }
+        }
     }
 }
 
@@ -29,16 +29,269 @@ pub enum RouteUrlSegment {
     /// A slash separator.
     Separator,
 
+    /// A rest path parameter (e.g. `{*path}`), capturing the remaining path.
+    ///
+    /// Rest parameters are ordered before regular parameters, so that they are matched last.
+    Rest {
+        /// The name of the parameter and its identifier.
+        name: String,
+    },
+
     //// A path parameter.
+    ///
+    /// The constraint comes first, so that constrained parameters are ordered after unconstrained
+    /// ones, and thus matched before them.
     Parameter {
+        /// The constraint on the values of the parameter, if any.
+        constraint: Option<ParamConstraint>,
+
         /// The name of the parameter and its identifier.
         name: String,
+
+        /// Whether the values of the parameter are already percent-encoded, and thus formatted
+        /// and parsed verbatim (see `#[param(name, encoded)]`).
+        encoded: bool,
     },
 
     /// A static segment.
     Literal(String),
 }
 
+/// A constraint on the values of a path parameter.
+///
+/// Constraints are compiled into the matching regex, so that requests with invalid values do not
+/// match the route at all.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ParamConstraint {
+    /// An integer, optionally within inclusive bounds (e.g. `{id:u32:1..}`).
+    Integer {
+        /// The integer type (e.g. `u32`).
+        ty: String,
+
+        /// The inclusive lower bound, if any.
+        min: Option<i128>,
+
+        /// The inclusive upper bound, if any.
+        max: Option<i128>,
+    },
+
+    /// A regular expression the whole value must match (e.g. `#[param(id, regex = "[0-9a-f]+")]`).
+    Regex(String),
+}
+
+impl ParamConstraint {
+    /// Parse an inline constraint, as found after the parameter name (e.g. `u32:1..`).
+    fn parse_inline(s: &str) -> Result<Self, String> {
+        let (ty, range) = match s.split_once(':') {
+            Some((ty, range)) => (ty, Some(range)),
+            None => (s, None),
+        };
+
+        let (type_min, type_max) = integer_bounds(ty).ok_or_else(|| {
+            format!("unsupported path parameter type `{ty}`, expected an integer type")
+        })?;
+
+        let (min, max) = match range {
+            Some(range) => parse_range(range)?,
+            None => (None, None),
+        };
+
+        let range = range.unwrap_or_default();
+
+        if [min, max]
+            .into_iter()
+            .flatten()
+            .any(|bound| bound < type_min || bound > type_max)
+        {
+            return Err(format!("the range `{range}` is out of bounds for `{ty}`"));
+        }
+
+        if let (Some(min), Some(max)) = (min, max)
+            && min > max
+        {
+            return Err(format!("the range `{range}` is empty"));
+        }
+
+        Ok(Self::Integer {
+            ty: ty.to_string(),
+            min,
+            max,
+        })
+    }
+
+    /// Get the regex pattern matching the values of the parameter.
+    fn to_pattern(&self) -> String {
+        match self {
+            Self::Integer { ty, .. } if ty.starts_with('u') => "[0-9]+".to_string(),
+            Self::Integer { .. } => "-?[0-9]+".to_string(),
+            Self::Regex(regex) => format!("(?:{regex})"),
+        }
+    }
+
+    /// Get an expression checking the value of the parameter, if the regex pattern is not enough.
+    ///
+    /// The generated expression evaluates to a `bool`, and expects the value to be bound to
+    /// `__value` as a `&str`.
+    fn to_check(&self) -> Option<TokenStream> {
+        match self {
+            Self::Integer { ty, min, max } => {
+                let ty = Ident::new(ty, proc_macro2::Span::call_site());
+                let min = min.map(integer_literal);
+                let max = max.map(integer_literal);
+
+                let range = match (min, max) {
+                    (None, None) => return Some(quote! { __value.parse::<#ty>().is_ok() }),
+                    (Some(min), None) => quote! { #min.. },
+                    (None, Some(max)) => quote! { ..=#max },
+                    (Some(min), Some(max)) => quote! { #min..=#max },
+                };
+
+                Some(quote! {
+                    __value.parse::<#ty>().is_ok_and(|__value| (#range).contains(&__value))
+                })
+            }
+            Self::Regex(_) => None,
+        }
+    }
+
+    /// Get the inclusive bounds of the values of an integer constraint.
+    fn integer_range(&self) -> Option<(i128, i128)> {
+        match self {
+            Self::Integer { ty, min, max } => {
+                let (type_min, type_max) = integer_bounds(ty)?;
+
+                Some((min.unwrap_or(type_min), max.unwrap_or(type_max)))
+            }
+            Self::Regex(_) => None,
+        }
+    }
+
+    /// Check whether a path segment value satisfies the constraint.
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            Self::Integer { ty, .. } => {
+                let digits = match value.strip_prefix('-') {
+                    Some(digits) if !ty.starts_with('u') => digits,
+                    _ => value,
+                };
+
+                !digits.is_empty()
+                    && digits.bytes().all(|b| b.is_ascii_digit())
+                    && self.integer_range().is_some_and(|(min, max)| {
+                        value
+                            .parse::<i128>()
+                            .is_ok_and(|value| (min..=max).contains(&value))
+                    })
+            }
+            Self::Regex(regex) => regex::Regex::new(&format!("^(?:{regex})$"))
+                .is_ok_and(|regex| regex.is_match(value)),
+        }
+    }
+
+    /// Check whether the constraint accepts all the values accepted by another constraint.
+    ///
+    /// Regex constraints are only known to accept the values of identical regex constraints.
+    fn covers(&self, other: &Self) -> bool {
+        match (self, other) {
+            // Signed integers also match `-0`, which unsigned integers don't.
+            (Self::Integer { ty, .. }, Self::Integer { ty: other_ty, .. })
+                if ty.starts_with('u') && !other_ty.starts_with('u') =>
+            {
+                false
+            }
+            (Self::Integer { .. }, Self::Integer { .. }) => {
+                match (self.integer_range(), other.integer_range()) {
+                    (Some((min, max)), Some((other_min, other_max))) => {
+                        min <= other_min && other_max <= max
+                    }
+                    _ => false,
+                }
+            }
+            _ => self == other,
+        }
+    }
+}
+
+impl Display for ParamConstraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Integer { ty, min, max } => {
+                f.write_str(ty)?;
+
+                if min.is_some() || max.is_some() {
+                    f.write_str(":")?;
+
+                    if let Some(min) = min {
+                        write!(f, "{min}")?;
+                    }
+
+                    f.write_str("..")?;
+
+                    if let Some(max) = max {
+                        write!(f, "={max}")?;
+                    }
+                }
+
+                Ok(())
+            }
+            Self::Regex(regex) => write!(f, "/{regex}/"),
+        }
+    }
+}
+
+/// Get the inclusive bounds of an integer type.
+fn integer_bounds(ty: &str) -> Option<(i128, i128)> {
+    Some(match ty {
+        "u8" => (0, u8::MAX.into()),
+        "u16" => (0, u16::MAX.into()),
+        "u32" => (0, u32::MAX.into()),
+        "u64" => (0, u64::MAX.into()),
+        "usize" => (0, u64::MAX.into()),
+        "i8" => (i8::MIN.into(), i8::MAX.into()),
+        "i16" => (i16::MIN.into(), i16::MAX.into()),
+        "i32" => (i32::MIN.into(), i32::MAX.into()),
+        "i64" => (i64::MIN.into(), i64::MAX.into()),
+        "isize" => (i64::MIN.into(), i64::MAX.into()),
+        _ => return None,
+    })
+}
+
+/// Parse a range (e.g. `1..`, `..10`, `1..=10`) into inclusive bounds.
+fn parse_range(range: &str) -> Result<(Option<i128>, Option<i128>), String> {
+    let invalid = || format!("invalid range `{range}`, expected `a..b`, `a..=b`, `a..` or `..b`");
+    let parse_bound = |bound: &str| {
+        if bound.is_empty() {
+            Ok(None)
+        } else {
+            bound.parse::<i128>().map(Some).map_err(|_| invalid())
+        }
+    };
+
+    let (start, end) = range.split_once("..").ok_or_else(invalid)?;
+    let min = parse_bound(start)?;
+
+    let max = match end.strip_prefix('=') {
+        Some("") => return Err(invalid()),
+        Some(end) => parse_bound(end)?,
+        None => parse_bound(end)?
+            .map(|end| end.checked_sub(1).ok_or_else(invalid))
+            .transpose()?,
+    };
+
+    Ok((min, max))
+}
+
+/// Get an unsuffixed integer literal, which may be negative.
+fn integer_literal(value: i128) -> TokenStream {
+    let literal = proc_macro2::Literal::u128_unsuffixed(value.unsigned_abs());
+
+    if value < 0 {
+        quote! { -#literal }
+    } else {
+        quote! { #literal }
+    }
+}
+
 /// An error that can occur when parsing a route URL.
 #[derive(Debug, thiserror::Error)]
 pub enum ParseError {
@@ -76,6 +329,29 @@ pub enum ParseError {
         character: char,
     },
 
+    /// A path parameter has an invalid constraint.
+    #[error("the path parameter has an invalid constraint: {reason}")]
+    InvalidParameterConstraint {
+        /// The position at which the constraint starts.
+        start: usize,
+
+        /// The position at which the constraint ends.
+        end: usize,
+
+        /// The reason why the constraint is invalid.
+        reason: String,
+    },
+
+    /// A rest path parameter is not the last segment of the route URL.
+    #[error("a rest path parameter (`{{*...}}`) must be the last segment of the route URL")]
+    RestParameterNotLast {
+        /// The position at which the rest path parameter was opened.
+        start: usize,
+
+        /// The end position.
+        end: usize,
+    },
+
     /// A path parameter is not closed.
     #[error("the path parameter is not closed")]
     UnclosedParameter {
@@ -97,6 +373,8 @@ impl ParseError {
             Self::InvalidParameterCharacter {
                 start, position, ..
             } => *start..=*position,
+            Self::InvalidParameterConstraint { start, end, .. } => *start..=*end,
+            Self::RestParameterNotLast { start, end } => *start..=*end,
             Self::UnclosedParameter { start, end } => *start..=*end,
         }
     }
@@ -116,14 +394,168 @@ impl ParseError {
     }
 }
 
+/// An element of the path regex of a route URL, to compare the paths matched by route URLs.
+#[derive(Debug, Clone, Copy)]
+enum PathAtom<'a> {
+    /// A slash.
+    Slash,
+
+    /// A static segment.
+    Literal(&'a str),
+
+    /// A path parameter, matching a non-empty segment satisfying its constraint, if any.
+    Parameter(Option<&'a ParamConstraint>),
+
+    /// The remaining path, slashes included, which may be empty.
+    Rest,
+}
+
+impl PathAtom<'_> {
+    /// Check whether the atom matches all the strings matched by another atom.
+    fn covers(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Rest, _) | (Self::Slash, Self::Slash) => true,
+            (Self::Literal(literal), Self::Literal(other)) => literal == other,
+            (Self::Parameter(None), Self::Literal(_)) => true,
+            (Self::Parameter(Some(constraint)), Self::Literal(value)) => constraint.matches(value),
+            // Regex constraints may match slashes, so only integers are known to be segments.
+            (
+                Self::Parameter(None),
+                Self::Parameter(None | Some(ParamConstraint::Integer { .. })),
+            ) => true,
+            (Self::Parameter(Some(constraint)), Self::Parameter(Some(other))) => {
+                constraint.covers(other)
+            }
+            _ => false,
+        }
+    }
+}
+
 impl RouteUrl {
     /// Check if the route URL is a prefix.
     pub fn is_prefix(&self) -> bool {
         matches!(self.0.last(), Some(RouteUrlSegment::Separator) if self.0.len() > 1)
     }
 
+    /// Get the names of the path parameters of the route URL, in order.
+    pub fn parameter_names(&self) -> Vec<&str> {
+        self.0
+            .iter()
+            .filter_map(|segment| match segment {
+                RouteUrlSegment::Parameter { name, .. } | RouteUrlSegment::Rest { name } => {
+                    Some(name.as_str())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Get the name of the rest path parameter of the route URL, if any.
+    pub fn rest_parameter(&self) -> Option<&str> {
+        match self.0.last() {
+            Some(RouteUrlSegment::Rest { name }) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Check whether the route URL matches all the paths matched by another route URL, in which
+    /// case the other route URL is unreachable when matched after this one.
+    ///
+    /// The check is conservative: it can miss that a route URL matches all the paths of another,
+    /// for instance when comparing regex constraints, but never reports it wrongly.
+    pub fn covers(&self, other: &Self) -> bool {
+        let atoms = self.path_atoms();
+        let other_atoms = other.path_atoms();
+        let mut atoms = atoms.iter();
+        let mut other_atoms = other_atoms.iter();
+
+        loop {
+            match (atoms.next(), other_atoms.next()) {
+                (None, None) | (Some(PathAtom::Rest), _) => return true,
+                (Some(atom), Some(other)) if atom.covers(other) => {}
+                _ => return false,
+            }
+        }
+    }
+
+    /// Get the elements of the path regex of the route URL.
+    fn path_atoms(&self) -> Vec<PathAtom<'_>> {
+        let mut atoms = Vec::with_capacity(self.0.len() + 1);
+
+        for (position, segment) in self.0.iter().with_position() {
+            match (position, segment) {
+                (Position::Last, RouteUrlSegment::Separator) => {
+                    atoms.extend([PathAtom::Slash, PathAtom::Rest]);
+                }
+                (_, RouteUrlSegment::Separator) => atoms.push(PathAtom::Slash),
+                (_, RouteUrlSegment::Literal(s)) => atoms.push(PathAtom::Literal(s)),
+                (_, RouteUrlSegment::Parameter { constraint, .. }) => {
+                    atoms.push(PathAtom::Parameter(constraint.as_ref()));
+                }
+                (_, RouteUrlSegment::Rest { .. }) => atoms.push(PathAtom::Rest),
+            }
+        }
+
+        atoms
+    }
+
+    /// Get the segments of the route URL as a `&[htmxology::RoutePatternSegment]` expression,
+    /// ordered at runtime like route URLs are.
+    pub fn to_pattern_segments(&self) -> TokenStream {
+        let segments = self.0.iter().map(|segment| match segment {
+            RouteUrlSegment::Separator => quote! { htmxology::RoutePatternSegment::Separator },
+            RouteUrlSegment::Rest { name } => {
+                quote! { htmxology::RoutePatternSegment::Rest(#name) }
+            }
+            RouteUrlSegment::Parameter {
+                constraint,
+                name,
+                encoded,
+            } => {
+                let constraint = match constraint {
+                    Some(ParamConstraint::Integer { ty, min, max }) => {
+                        let [min, max] = [min, max].map(|bound| match bound {
+                            Some(bound) => {
+                                let bound = integer_literal(*bound);
+
+                                quote! { Some(#bound) }
+                            }
+                            None => quote! { None },
+                        });
+
+                        quote! {
+                            Some(htmxology::RoutePatternConstraint::Integer(#ty, #min, #max))
+                        }
+                    }
+                    Some(ParamConstraint::Regex(regex)) => {
+                        quote! { Some(htmxology::RoutePatternConstraint::Regex(#regex)) }
+                    }
+                    None => quote! { None },
+                };
+
+                quote! { htmxology::RoutePatternSegment::Parameter(#constraint, #name, #encoded) }
+            }
+            RouteUrlSegment::Literal(literal) => {
+                quote! { htmxology::RoutePatternSegment::Literal(#literal) }
+            }
+        });
+
+        quote! { &[#(#segments),*] }
+    }
+
     /// Get an Axum router path from the route URL path.
     pub fn to_path_regex(&self) -> String {
+        self.path_regex(false)
+    }
+
+    /// Get a path regex from the route URL path, matching its literal segments
+    /// case-insensitively.
+    pub fn to_case_insensitive_path_regex(&self) -> String {
+        self.path_regex(true)
+    }
+
+    /// Get a path regex from the route URL path.
+    fn path_regex(&self, case_insensitive: bool) -> String {
         // As good a guess as any...
         let mut result = String::with_capacity(64);
 
@@ -137,11 +569,33 @@ impl RouteUrl {
                     result.push_str("(?P<subroute>/.*)");
                 }
                 (_, RouteUrlSegment::Separator) => result.push('/'),
+                (_, RouteUrlSegment::Literal(s)) if case_insensitive => {
+                    result.push_str("(?i:");
+                    result.push_str(s);
+                    result.push(')');
+                }
                 (_, RouteUrlSegment::Literal(s)) => result.push_str(s),
-                (_, RouteUrlSegment::Parameter { name }) => {
+                (
+                    _,
+                    RouteUrlSegment::Parameter {
+                        name, constraint, ..
+                    },
+                ) => {
                     result.push_str("(?P<");
                     result.push_str(name);
-                    result.push_str(">[^/]+)");
+                    result.push('>');
+
+                    match constraint {
+                        Some(constraint) => result.push_str(&constraint.to_pattern()),
+                        None => result.push_str("[^/]+"),
+                    }
+
+                    result.push(')');
+                }
+                (_, RouteUrlSegment::Rest { name }) => {
+                    result.push_str("(?P<");
+                    result.push_str(name);
+                    result.push_str(">.*)");
                 }
             }
         }
@@ -151,6 +605,110 @@ impl RouteUrl {
         result
     }
 
+    /// Set a regex constraint on a path parameter.
+    pub fn set_param_regex(&mut self, param_name: &str, regex: String) -> Result<(), String> {
+        let constraint = self
+            .0
+            .iter_mut()
+            .find_map(|segment| match segment {
+                RouteUrlSegment::Parameter {
+                    name, constraint, ..
+                } if name == param_name => Some(constraint),
+                _ => None,
+            })
+            .ok_or_else(|| format!("the route URL has no path parameter `{param_name}`"))?;
+
+        if constraint.is_some() {
+            return Err(format!(
+                "the path parameter `{param_name}` already has a constraint"
+            ));
+        }
+
+        *constraint = Some(ParamConstraint::Regex(regex));
+
+        Ok(())
+    }
+
+    /// Declare the values of a path parameter as already percent-encoded.
+    pub fn set_param_encoded(&mut self, param_name: &str) -> Result<(), String> {
+        for segment in &mut self.0 {
+            match segment {
+                RouteUrlSegment::Parameter { name, encoded, .. } if name == param_name => {
+                    *encoded = true;
+
+                    return Ok(());
+                }
+                RouteUrlSegment::Rest { name } if name == param_name => {
+                    return Err(format!(
+                        "the rest path parameter `{param_name}` is encoded and decoded by its `RestPath` implementation"
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        Err(format!(
+            "the route URL has no path parameter `{param_name}`"
+        ))
+    }
+
+    /// Check whether the values of a path parameter are already percent-encoded.
+    pub fn is_param_encoded(&self, param_name: &str) -> bool {
+        self.0.iter().any(|segment| {
+            matches!(segment, RouteUrlSegment::Parameter { name, encoded: true, .. } if name == param_name)
+        })
+    }
+
+    /// Get an expression filtering regex captures on the path parameter constraints that can't be
+    /// expressed in the regex itself, if any.
+    ///
+    /// The values of the parameters that are not already encoded must also be validly
+    /// percent-encoded, so that paths with invalid escapes don't match the route at all.
+    ///
+    /// The generated expression is a closure taking a `&regex::Captures`.
+    pub fn to_captures_filter(&self) -> Option<TokenStream> {
+        let checks: Vec<_> = self
+            .0
+            .iter()
+            .filter_map(|segment| {
+                let (name, check) = match segment {
+                    RouteUrlSegment::Parameter {
+                        name,
+                        constraint,
+                        encoded,
+                    } => {
+                        let constraint = constraint.as_ref().and_then(ParamConstraint::to_check);
+                        // Integer values can't contain escapes.
+                        let check = match (constraint, *encoded) {
+                            (Some(check), _) => check,
+                            (None, true) => return None,
+                            (None, false) => quote! { htmxology::is_valid_path_argument(__value) },
+                        };
+
+                        (name, check)
+                    }
+                    RouteUrlSegment::Rest { name } => {
+                        (name, quote! { htmxology::is_valid_path_argument(__value) })
+                    }
+                    RouteUrlSegment::Separator | RouteUrlSegment::Literal(_) => return None,
+                };
+
+                Some(quote! {
+                    __captures.name(#name).is_some_and(|__value| {
+                        let __value = __value.as_str();
+                        #check
+                    })
+                })
+            })
+            .collect();
+
+        if checks.is_empty() {
+            None
+        } else {
+            Some(quote! { |__captures: &regex::Captures| #(#checks)&&* })
+        }
+    }
+
     /// Get the URL as a list of format statements, failing if there are any required path parameters.
     pub fn to_unparameterized_string(&self, ctx: impl ToTokens) -> syn::Result<Vec<TokenStream>> {
         let mut statements = Vec::with_capacity(self.0.len());
@@ -161,7 +719,7 @@ impl RouteUrl {
                     statements.push(quote! {std::fmt::Write::write_char(f, '/')?;})
                 }
                 RouteUrlSegment::Literal(s) => statements.push(quote! {f.write_str(#s)?;}),
-                RouteUrlSegment::Parameter { name } => {
+                RouteUrlSegment::Parameter { name, .. } | RouteUrlSegment::Rest { name } => {
                     return Err(syn::Error::new_spanned(
                         ctx,
                         format!(
@@ -195,7 +753,7 @@ impl RouteUrl {
                     statements.push(quote! {std::fmt::Write::write_char(f, '/')?;})
                 }
                 RouteUrlSegment::Literal(s) => statements.push(quote! {f.write_str(#s)?;}),
-                RouteUrlSegment::Parameter { name } => {
+                RouteUrlSegment::Parameter { name, .. } | RouteUrlSegment::Rest { name } => {
                     let ident = name_params.remove(name.as_str()).ok_or_else(|| {
                         syn::Error::new_spanned(
                             ctx,
@@ -203,7 +761,7 @@ impl RouteUrl {
                         )
                     })?;
 
-                    statements.push(quote! {#ident.fmt(f)?;});
+                    statements.push(element.to_format_statement(&ident));
                 }
             }
         }
@@ -238,7 +796,7 @@ impl RouteUrl {
                     statements.push(quote! {std::fmt::Write::write_char(f, '/')?;})
                 }
                 RouteUrlSegment::Literal(s) => statements.push(quote! {f.write_str(#s)?;}),
-                RouteUrlSegment::Parameter { .. } => {
+                RouteUrlSegment::Parameter { .. } | RouteUrlSegment::Rest { .. } => {
                     let ident = unnamed_params.next().ok_or_else(|| {
                         syn::Error::new_spanned(
                             ctx,
@@ -246,7 +804,7 @@ impl RouteUrl {
                         )
                     })?;
 
-                    statements.push(quote! {#ident.fmt(f)?;});
+                    statements.push(element.to_format_statement(&ident));
                 }
             }
         }
@@ -267,6 +825,18 @@ impl RouteUrl {
     }
 }
 
+impl RouteUrlSegment {
+    /// Get the statement formatting the value of a path parameter segment, percent-encoded
+    /// unless it already is.
+    fn to_format_statement(&self, ident: &Ident) -> TokenStream {
+        match self {
+            Self::Rest { .. } => quote! {htmxology::RestPath::fmt_rest_path(#ident, f)?;},
+            Self::Parameter { encoded: true, .. } => quote! {#ident.fmt(f)?;},
+            _ => quote! {htmxology::fmt_path_segment(#ident, f)?;},
+        }
+    }
+}
+
 impl FromStr for RouteUrl {
     type Err = ParseError;
 
@@ -310,8 +880,10 @@ impl FromStr for RouteUrl {
                         return Err(ParseError::ParameterNotAllowed { position: i });
                     }
 
-                    let start = i + 1;
+                    let rest = chars.next_if(|&(_, c)| c == '*').is_some();
+                    let start = if rest { i + 2 } else { i + 1 };
                     let mut stop = None;
+                    let mut colon = None;
 
                     for (i, c) in chars.by_ref() {
                         if c == '}' {
@@ -320,6 +892,18 @@ impl FromStr for RouteUrl {
                             break;
                         }
 
+                        // Everything after the first colon is the constraint, which is validated
+                        // once the parameter is closed.
+                        if colon.is_some() {
+                            continue;
+                        }
+
+                        if c == ':' && !rest {
+                            colon = Some(i);
+
+                            continue;
+                        }
+
                         if !c.is_alphanumeric() && c != '_' {
                             return Err(ParseError::InvalidParameterCharacter {
                                 start,
@@ -334,8 +918,39 @@ impl FromStr for RouteUrl {
                         end: s.len() - 1,
                     })?;
 
+                    if rest {
+                        if chars.peek().is_some() {
+                            return Err(ParseError::RestParameterNotLast {
+                                start: i,
+                                end: s.len() - 1,
+                            });
+                        }
+
+                        segments.push(RouteUrlSegment::Rest {
+                            name: s[start..stop].to_string(),
+                        });
+
+                        continue;
+                    }
+
+                    let (name, constraint) = match colon {
+                        Some(colon) => {
+                            let constraint = ParamConstraint::parse_inline(&s[colon + 1..stop])
+                                .map_err(|reason| ParseError::InvalidParameterConstraint {
+                                    start: colon + 1,
+                                    end: stop - 1,
+                                    reason,
+                                })?;
+
+                            (&s[start..colon], Some(constraint))
+                        }
+                        None => (&s[start..stop], None),
+                    };
+
                     segments.push(RouteUrlSegment::Parameter {
-                        name: s[start..stop].to_string(),
+                        name: name.to_string(),
+                        constraint,
+                        encoded: false,
                     });
                 }
                 c if is_valid_url_path_character(c) => {
@@ -370,11 +985,22 @@ impl Display for RouteUrl {
                 (Position::Last, RouteUrlSegment::Separator) => {}
                 (_, RouteUrlSegment::Separator) => f.write_str("/")?,
                 (_, RouteUrlSegment::Literal(s)) => f.write_str(s)?,
-                (_, RouteUrlSegment::Parameter { name }) => {
+                (
+                    _,
+                    RouteUrlSegment::Parameter {
+                        name, constraint, ..
+                    },
+                ) => {
                     f.write_str("{")?;
                     f.write_str(name)?;
+
+                    if let Some(constraint) = constraint {
+                        write!(f, ":{constraint}")?;
+                    }
+
                     f.write_str("}")?;
                 }
+                (_, RouteUrlSegment::Rest { name }) => write!(f, "{{*{name}}}")?,
             }
         }
 
@@ -387,7 +1013,7 @@ impl Display for RouteUrl {
 /// Valid URL path characters, as per
 /// [RFC3986](https://datatracker.ietf.org/doc/html/rfc3986#section-3.3) are: A–Z, a–z, 0–9, -, .,
 /// _, ~, !, $, &, ', (, ), *, +, ,, ;, =, :, @, as well as % and /.
-fn is_valid_url_path_character(c: char) -> bool {
+pub(super) fn is_valid_url_path_character(c: char) -> bool {
     matches!(c, 'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '.' | '_' | '~' | '!' | '$' | '&' | '\''
         | '(' | ')' | '*' | '+' | ',' | ';' | '=' | ':' | '@' | '%' | '/')
 }
@@ -427,6 +1053,158 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_case_insensitive_path_regex() {
+        let u: RouteUrl = "Promo/{code}/Landing/".parse().unwrap();
+        assert_eq!(
+            u.to_case_insensitive_path_regex(),
+            "^/(?i:Promo)/(?P<code>[^/]+)/(?i:Landing)(?P<subroute>/.*)$"
+        );
+
+        let re = regex::Regex::new(&u.to_case_insensitive_path_regex()).unwrap();
+        let captures = re.captures("/PROMO/Summer/landing/").unwrap();
+        assert_eq!(&captures["code"], "Summer");
+    }
+
+    #[test]
+    fn test_parse_route_url_constraints() {
+        let u: RouteUrl = "items/{id:u32}".parse().unwrap();
+        assert_eq!(u.to_string(), "/items/{id:u32}");
+        assert_eq!(u.to_path_regex(), "^/items/(?P<id>[0-9]+)$");
+
+        let u: RouteUrl = "items/{id:i64:-10..10}/{name}".parse().unwrap();
+        assert_eq!(u.to_string(), "/items/{id:i64:-10..=9}/{name}");
+        assert_eq!(
+            u.to_path_regex(),
+            "^/items/(?P<id>-?[0-9]+)/(?P<name>[^/]+)$"
+        );
+
+        let mut u: RouteUrl = "items/{slug}".parse().unwrap();
+        u.set_param_regex("slug", "[a-z]+".to_string()).unwrap();
+        assert_eq!(u.to_path_regex(), "^/items/(?P<slug>(?:[a-z]+))$");
+        // Only the percent-encoding of the value is left to check.
+        assert!(u.to_captures_filter().is_some());
+        u.set_param_encoded("slug").unwrap();
+        assert!(u.to_captures_filter().is_none());
+        assert!(u.set_param_regex("slug", "[0-9]+".to_string()).is_err());
+        assert!(u.set_param_regex("id", "[0-9]+".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_constrained_params_are_ordered_last() {
+        let constrained: RouteUrl = "items/{id:u32}".parse().unwrap();
+        let unconstrained: RouteUrl = "items/{slug}".parse().unwrap();
+        let literal: RouteUrl = "items/new".parse().unwrap();
+
+        assert!(unconstrained < constrained);
+        assert!(constrained < literal);
+    }
+
+    #[test]
+    fn test_covers() {
+        let url = |s: &str| s.parse::<RouteUrl>().unwrap();
+
+        assert!(url("users/{id}").covers(&url("users/new")));
+        assert!(url("users/{id}").covers(&url("users/{slug}")));
+        assert!(url("users/{id}").covers(&url("users/{id:u32}")));
+        assert!(!url("users/{id}").covers(&url("users/{id}/edit")));
+        assert!(!url("users/new").covers(&url("users/{id}")));
+        assert!(url("users/").covers(&url("users/new")));
+        assert!(url("users/").covers(&url("users/{id}/edit")));
+        assert!(!url("users/").covers(&url("users")));
+        assert!(url("{*path}").covers(&url("")));
+        assert!(url("files/{*path}").covers(&url("files/")));
+        assert!(!url("files/{id}").covers(&url("files/{*path}")));
+
+        assert!(url("items/{id:u32}").covers(&url("items/42")));
+        assert!(!url("items/{id:u32}").covers(&url("items/-1")));
+        assert!(!url("items/{id:u8}").covers(&url("items/256")));
+        assert!(url("items/{id:i32}").covers(&url("items/-1")));
+        assert!(url("items/{id:u32}").covers(&url("items/{id:u8}")));
+        assert!(url("items/{id:i64:-10..10}").covers(&url("items/{id:u8:..5}")));
+        assert!(!url("items/{id:u64}").covers(&url("items/{id:i8:0..}")));
+        assert!(!url("items/{id:u8}").covers(&url("items/{id:u32}")));
+
+        let mut slug = url("items/{slug}");
+        slug.set_param_regex("slug", "[a-z]+".to_string()).unwrap();
+
+        assert!(slug.covers(&url("items/new")));
+        assert!(!slug.covers(&url("items/42")));
+        assert!(!slug.covers(&url("items/{id:u32}")));
+        assert!(!url("items/{id}").covers(&slug));
+    }
+
+    #[test]
+    fn test_parse_route_url_rest_parameter() {
+        let u: RouteUrl = "files/{*path}".parse().unwrap();
+        assert_eq!(u.to_string(), "/files/{*path}");
+        assert_eq!(u.rest_parameter(), Some("path"));
+        assert!(!u.is_prefix());
+
+        let re = regex::Regex::new(&u.to_path_regex()).unwrap();
+        let caps = re.captures("/files/docs/a.txt").unwrap();
+        assert_eq!(caps.name("path").unwrap().as_str(), "docs/a.txt");
+        assert_eq!(
+            re.captures("/files/")
+                .unwrap()
+                .name("path")
+                .unwrap()
+                .as_str(),
+            ""
+        );
+        assert!(re.captures("/files").is_none());
+
+        let u: RouteUrl = "files/{name}".parse().unwrap();
+        assert_eq!(u.rest_parameter(), None);
+
+        // Rest parameters are matched after regular parameters.
+        let rest: RouteUrl = "files/{*path}".parse().unwrap();
+        assert!(rest < u);
+
+        let err = "files/{*path}/edit".parse::<RouteUrl>().unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::RestParameterNotLast { start: 6, end: 17 }
+        ));
+
+        let err = "files/{*path:u32}".parse::<RouteUrl>().unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::InvalidParameterCharacter { character: ':', .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("1..").unwrap(), (Some(1), None));
+        assert_eq!(parse_range("..10").unwrap(), (None, Some(9)));
+        assert_eq!(parse_range("..=10").unwrap(), (None, Some(10)));
+        assert_eq!(parse_range("-5..=5").unwrap(), (Some(-5), Some(5)));
+        assert!(parse_range("1").is_err());
+        assert!(parse_range("1..=").is_err());
+        assert!(parse_range("a..b").is_err());
+    }
+
+    #[test]
+    fn test_parse_route_url_invalid_constraint() {
+        let err = "items/{id:string}".parse::<RouteUrl>().unwrap_err();
+
+        match &err {
+            ParseError::InvalidParameterConstraint { start, end, reason } => {
+                assert_eq!(*start, 10);
+                assert_eq!(*end, 15);
+                assert_eq!(
+                    reason,
+                    "unsupported path parameter type `string`, expected an integer type"
+                );
+            }
+            _ => panic!("unexpected error: {err:?}"),
+        }
+
+        let err = "items/{id:u32:5..1}".parse::<RouteUrl>().unwrap_err();
+        assert!(matches!(err, ParseError::InvalidParameterConstraint { .. }));
+    }
+
     #[test]
     fn test_subroute_regex_match() {
         let u: RouteUrl = "foo/{bar}/".parse().unwrap();
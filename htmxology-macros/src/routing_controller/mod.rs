@@ -13,13 +13,17 @@ pub(super) const CONTROLLER: &str = "controller";
 pub(super) const SUBCONTROLLER: &str = "subcontroller";
 pub(super) const ROUTE: &str = "route";
 pub(super) const PATH: &str = "path";
+pub(super) const HOST: &str = "host";
 pub(super) const DOC: &str = "doc";
 pub(super) const CONVERT_WITH: &str = "convert_with";
 pub(super) const CONVERT_RESPONSE: &str = "convert_response";
+pub(super) const ERROR_BOUNDARY: &str = "error_boundary";
 pub(super) const PARAMS: &str = "params";
 pub(super) const RESPONSE: &str = "response";
 pub(super) const ARGS: &str = "args";
 pub(super) const PRE_HANDLER: &str = "pre_handler";
+pub(super) const PRE_HANDLE: &str = "pre_handle";
+pub(super) const POST_HANDLE: &str = "post_handle";
 pub(super) const EXTRA_DERIVES: &str = "extra_derives";
 
 pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
@@ -38,8 +42,8 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
         if attr.path().is_ident(SUBCONTROLLER) {
             let spec: SubcontrollerSpec = attr.parse_args()?;
 
-            // Check for multiple default subcontrollers (without `path`)
-            if spec.path.is_none() {
+            // Check for multiple default subcontrollers (without `path` or `host`)
+            if spec.path.is_none() && spec.host.is_none() {
                 if let Some(ref existing) = default_subcontroller_route {
                     return Err(syn::Error::new_spanned(
                         &spec.route_variant,
@@ -58,6 +62,7 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
             let route_variant = &spec.route_variant;
             let controller_type = &spec.controller_type;
             let convert_response_fn = &spec.convert_response_fn;
+            let error_boundary_fn = &spec.error_boundary_fn;
 
             let doc_attr = if let Some(doc) = &spec.doc {
                 quote_spanned! { spec.controller_type.span() =>
@@ -68,7 +73,15 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
             };
 
             // Generate route variant - tuple variant if no params, struct variant if params
-            route_variants.push(if spec.params.is_empty() {
+            route_variants.push(if let Some(host) = &spec.host {
+                // Host-scoped - catch-all tuple variant restricted to the host
+                quote_spanned! { spec.route_variant.span() =>
+                    #doc_attr
+                    #[host(#host)]
+                    #[catch_all]
+                    #route_variant(<#controller_type as htmxology::Controller>::Route),
+                }
+            } else if spec.params.is_empty() {
                 // No params - simple tuple variant
                 match spec.path {
                     Some(path) => {
@@ -136,29 +149,41 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
                 }
             };
 
+            // Errors of the subcontroller are rendered by the error boundary, if any
+            let conversion_logic = if let Some(fn_expr) = error_boundary_fn {
+                quote! {
+                    match response {
+                        Err(err) => #fn_expr(self, &htmx, &parts_for_convert, server_info, err),
+                        response => #conversion_logic,
+                    }
+                }
+            } else {
+                conversion_logic
+            };
+
+            // If using custom convert_response or error_boundary, clone values for them before moving
+            let parts_clone = if convert_response_fn.is_some() || error_boundary_fn.is_some() {
+                quote! { let parts_for_convert = parts.clone(); }
+            } else {
+                quote! {}
+            };
+            let args_clone = if convert_response_fn.is_some() {
+                quote! { let args_for_convert = args.clone(); }
+            } else {
+                quote! {}
+            };
+
             // Generate handle_request match arm
             handle_request_variants.push(if spec.params.is_empty() {
                 // No params - simple tuple variant, pass parent args through
-                if convert_response_fn.is_some() {
-                    // If using custom convert_response, clone values for convert_response before moving
-                    quote_spanned! { spec.route_variant.span() =>
-                        Self::Route::#route_variant(route) => {
-                            let parts_for_convert = parts.clone();
-                            let args_for_convert = args.clone();
-                            let response = htmxology::SubcontrollerExt::get_subcontroller::<#controller_type>(self)
-                                .handle_request(route, htmx.clone(), parts, server_info, args)
-                                .await;
-                            #conversion_logic
-                        }
-                    }
-                } else {
-                    quote_spanned! { spec.route_variant.span() =>
-                        Self::Route::#route_variant(route) => {
-                            let response = htmxology::SubcontrollerExt::get_subcontroller::<#controller_type>(self)
-                                .handle_request(route, htmx.clone(), parts, server_info, args)
-                                .await;
-                            #conversion_logic
-                        }
+                quote_spanned! { spec.route_variant.span() =>
+                    Self::Route::#route_variant(route) => {
+                        #parts_clone
+                        #args_clone
+                        let response = htmxology::SubcontrollerExt::get_subcontroller::<#controller_type>(self)
+                            .handle_request(route, htmx.clone(), parts, server_info, args)
+                            .await;
+                        #conversion_logic
                     }
                 }
             } else {
@@ -166,32 +191,17 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
                 let param_names = spec.params.iter().map(|p| &p.name);
                 let param_names_for_construction = spec.params.iter().map(|p| &p.name);
 
-                if convert_response_fn.is_some() {
-                    // If using custom convert_response, clone values for convert_response before moving
-                    quote_spanned! { spec.route_variant.span() =>
-                        Self::Route::#route_variant { #(#param_names,)* subroute } => {
-                            let parts_for_convert = parts.clone();
-                            let args_for_convert = args.clone();
-                            // Construct Args from parent args and path parameters
-                            // User must implement From<(ParentArgs, param1, param2, ...)> for ChildArgs
-                            let sub_args = <#controller_type as htmxology::Controller>::Args::from((args, #(#param_names_for_construction,)*));
-                            let response = htmxology::SubcontrollerExt::get_subcontroller::<#controller_type>(self)
-                                .handle_request(subroute, htmx.clone(), parts, server_info, sub_args)
-                                .await;
-                            #conversion_logic
-                        }
-                    }
-                } else {
-                    quote_spanned! { spec.route_variant.span() =>
-                        Self::Route::#route_variant { #(#param_names,)* subroute } => {
-                            // Construct Args from parent args and path parameters
-                            // User must implement From<(ParentArgs, param1, param2, ...)> for ChildArgs
-                            let sub_args = <#controller_type as htmxology::Controller>::Args::from((args, #(#param_names_for_construction,)*));
-                            let response = htmxology::SubcontrollerExt::get_subcontroller::<#controller_type>(self)
-                                .handle_request(subroute, htmx.clone(), parts, server_info, sub_args)
-                                .await;
-                            #conversion_logic
-                        }
+                quote_spanned! { spec.route_variant.span() =>
+                    Self::Route::#route_variant { #(#param_names,)* subroute } => {
+                        #parts_clone
+                        #args_clone
+                        // Construct Args from parent args and path parameters
+                        // User must implement From<(ParentArgs, param1, param2, ...)> for ChildArgs
+                        let sub_args = <#controller_type as htmxology::Controller>::Args::from((args, #(#param_names_for_construction,)*));
+                        let response = htmxology::SubcontrollerExt::get_subcontroller::<#controller_type>(self)
+                            .handle_request(subroute, htmx.clone(), parts, server_info, sub_args)
+                            .await;
+                        #conversion_logic
                     }
                 }
             });
@@ -225,6 +235,7 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
         .args_type
         .unwrap_or_else(|| parse_quote!(()));
     let pre_handler = controller_spec.pre_handler;
+    let post_handle = controller_spec.post_handle;
     let extra_derives = controller_spec.extra_derives;
 
     // Build the derive attribute with default and extra derives
@@ -256,6 +267,26 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
         (quote! {}, quote! {})
     };
 
+    // Generate the routing, with the post-handle call if configured
+    let routing = if let Some(post_handle_fn) = &post_handle {
+        quote! {
+            // The parts are moved to the subcontrollers
+            let parts_for_post_handle = parts.clone();
+
+            let response = match route {
+                #(#handle_request_variants)*
+            };
+
+            #post_handle_fn(self, &htmx, &parts_for_post_handle, server_info, response).await
+        }
+    } else {
+        quote! {
+            match route {
+                #(#handle_request_variants)*
+            }
+        }
+    };
+
     let controller_impl = quote_spanned! { root_ident.span() =>
         impl htmxology::Controller for #root_ident {
             type Route = #route_ident;
@@ -272,9 +303,7 @@ pub fn derive(input: &mut syn::DeriveInput) -> syn::Result<proc_macro2::TokenStr
             ) -> Self::Response {
                 #pre_handler_call
 
-                match route {
-                    #(#handle_request_variants)*
-                }
+                #routing
             }
         }
     };
@@ -291,9 +320,11 @@ struct SubcontrollerSpec {
     controller_type: Type,
     route_variant: Ident,
     path: Option<String>,
+    host: Option<String>,
     doc: Option<String>,
     params: Vec<ParamSpec>,
     convert_response_fn: Option<proc_macro2::TokenStream>,
+    error_boundary_fn: Option<proc_macro2::TokenStream>,
 }
 
 /// A parameter specification for a subcontroller route.
@@ -306,8 +337,10 @@ struct ParamSpec {
 enum SubcontrollerArg {
     Route(Ident, Ident),
     Path(Ident, String),
+    Host(Ident, LitStr),
     ConvertWith(proc_macro2::TokenStream),
     ConvertResponse(proc_macro2::TokenStream),
+    ErrorBoundary(Ident, proc_macro2::TokenStream),
     Doc(Ident, String),
     Params(Vec<ParamSpec>),
 }
@@ -329,6 +362,12 @@ impl Parse for SubcontrollerArg {
 
                 Ok(Self::Path(key, path.value()))
             }
+            HOST => {
+                input.parse::<Token![=]>()?;
+                let host: LitStr = input.parse()?;
+
+                Ok(Self::Host(key, host))
+            }
             CONVERT_WITH => {
                 input.parse::<Token![=]>()?;
                 let fn_name: LitStr = input.parse()?;
@@ -353,6 +392,18 @@ impl Parse for SubcontrollerArg {
 
                 Ok(Self::ConvertResponse(fn_expr))
             }
+            ERROR_BOUNDARY => {
+                input.parse::<Token![=]>()?;
+                let fn_name: LitStr = input.parse()?;
+                let fn_expr = fn_name.value().parse().map_err(|err| {
+                    syn::Error::new_spanned(
+                        fn_name,
+                        format!("failed to parse function name: {err}"),
+                    )
+                })?;
+
+                Ok(Self::ErrorBoundary(key, fn_expr))
+            }
             DOC => {
                 input.parse::<Token![=]>()?;
                 let desc: LitStr = input.parse()?;
@@ -371,7 +422,7 @@ impl Parse for SubcontrollerArg {
             }
             _ => Err(syn::Error::new_spanned(
                 key,
-                "expected `route`, `path`, `convert_with`, `convert_response`, `doc`, or `params`",
+                "expected `route`, `path`, `host`, `convert_with`, `convert_response`, `error_boundary`, `doc`, or `params`",
             )),
         }
     }
@@ -393,6 +444,7 @@ struct ControllerSpec {
     response_type: Option<Type>,
     args_type: Option<Type>,
     pre_handler: Option<proc_macro2::TokenStream>,
+    post_handle: Option<proc_macro2::TokenStream>,
     extra_derives: Vec<Ident>,
 }
 
@@ -404,6 +456,7 @@ impl Parse for ControllerSpec {
         let mut response_type = None;
         let mut args_type = None;
         let mut pre_handler = None;
+        let mut post_handle = None;
         let mut extra_derives = Vec::new();
 
         // Check if there's a comma followed by named arguments
@@ -434,11 +487,12 @@ impl Parse for ControllerSpec {
                     }
                     args_type = Some(input.parse()?);
                 }
-                PRE_HANDLER => {
+                // `pre_handle` is an alias of `pre_handler`, named after `handle_request`.
+                PRE_HANDLER | PRE_HANDLE => {
                     if pre_handler.is_some() {
                         return Err(syn::Error::new_spanned(
                             &key,
-                            "duplicate `pre_handler` parameter",
+                            "duplicate `pre_handler` or `pre_handle` parameter",
                         ));
                     }
                     let fn_name: LitStr = input.parse()?;
@@ -449,6 +503,21 @@ impl Parse for ControllerSpec {
                         )
                     })?);
                 }
+                POST_HANDLE => {
+                    if post_handle.is_some() {
+                        return Err(syn::Error::new_spanned(
+                            &key,
+                            "duplicate `post_handle` parameter",
+                        ));
+                    }
+                    let fn_name: LitStr = input.parse()?;
+                    post_handle = Some(fn_name.value().parse().map_err(|err| {
+                        syn::Error::new_spanned(
+                            fn_name,
+                            format!("failed to parse function name: {err}"),
+                        )
+                    })?);
+                }
                 EXTRA_DERIVES => {
                     if !extra_derives.is_empty() {
                         return Err(syn::Error::new_spanned(
@@ -467,7 +536,7 @@ impl Parse for ControllerSpec {
                     return Err(syn::Error::new_spanned(
                         &key,
                         format!(
-                            "expected `{RESPONSE}`, `{ARGS}`, `{PRE_HANDLER}`, or `{EXTRA_DERIVES}`, found `{key}`"
+                            "expected `{RESPONSE}`, `{ARGS}`, `{PRE_HANDLER}`, `{POST_HANDLE}`, or `{EXTRA_DERIVES}`, found `{key}`"
                         ),
                     ));
                 }
@@ -479,6 +548,7 @@ impl Parse for ControllerSpec {
             response_type,
             args_type,
             pre_handler,
+            post_handle,
             extra_derives,
         })
     }
@@ -499,10 +569,12 @@ impl Parse for SubcontrollerSpec {
 
         let mut route = None;
         let mut path = None;
+        let mut host: Option<(Ident, String)> = None;
         let mut doc = None;
         let mut params = Vec::new();
         let mut body_impl = quote! { self.into() };
         let mut convert_response_fn = None;
+        let mut error_boundary_fn = None;
 
         if input.peek(Token![,]) {
             input.parse::<Token![,]>()?;
@@ -517,6 +589,16 @@ impl Parse for SubcontrollerSpec {
                     SubcontrollerArg::ConvertResponse(fn_expr) => {
                         convert_response_fn = Some(fn_expr);
                     }
+                    SubcontrollerArg::ErrorBoundary(key, fn_expr) => {
+                        if error_boundary_fn.is_some() {
+                            return Err(syn::Error::new_spanned(
+                                key,
+                                "at most one `error_boundary` can be specified",
+                            ));
+                        }
+
+                        error_boundary_fn = Some(fn_expr);
+                    }
                     SubcontrollerArg::Route(key, ident) => {
                         if route.is_some() {
                             return Err(syn::Error::new_spanned(
@@ -537,6 +619,16 @@ impl Parse for SubcontrollerSpec {
 
                         path = Some(rpath);
                     }
+                    SubcontrollerArg::Host(key, rhost) => {
+                        if host.is_some() {
+                            return Err(syn::Error::new_spanned(
+                                key,
+                                "at most one `host` can be specified",
+                            ));
+                        }
+
+                        host = Some((key, rhost.value()));
+                    }
                     SubcontrollerArg::Doc(key, desc) => {
                         if doc.is_some() {
                             return Err(syn::Error::new_spanned(
@@ -571,6 +663,23 @@ impl Parse for SubcontrollerSpec {
             }
         };
 
+        let host = match host {
+            Some((key, _)) if path.is_some() => {
+                return Err(syn::Error::new_spanned(
+                    key,
+                    "`host` and `path` cannot be specified together",
+                ));
+            }
+            Some((key, _)) if !params.is_empty() => {
+                return Err(syn::Error::new_spanned(
+                    key,
+                    "`host` and `params` cannot be specified together",
+                ));
+            }
+            Some((_, host)) => Some(host),
+            None => None,
+        };
+
         // Build the conversion body for as_subcontroller
         // No params anymore, so just use body_impl
         let conversion_body = body_impl;
@@ -604,9 +713,11 @@ impl Parse for SubcontrollerSpec {
             controller_type,
             route_variant: route,
             path,
+            host,
             doc,
             params,
             convert_response_fn,
+            error_boundary_fn,
         })
     }
 }
@@ -631,18 +742,16 @@ fn replace_first_lifetime_mut(ty: &mut Type, new_lifetime: Lifetime) -> bool {
             for segment in &mut path.segments {
                 if let syn::PathArguments::AngleBracketed(args) = &mut segment.arguments {
                     for arg in &mut args.args {
-                        match arg {
-                            GenericArgument::Lifetime(lt) => {
-                                *lt = new_lifetime;
-                                return true;
-                            }
-                            GenericArgument::Type(ty) => {
-                                if replace_first_lifetime_mut(ty, new_lifetime.clone()) {
-                                    return true;
-                                }
-                            }
-                            _ => {}
-                        };
+                        if let GenericArgument::Lifetime(lt) = arg {
+                            *lt = new_lifetime;
+                            return true;
+                        }
+
+                        if let GenericArgument::Type(ty) = arg
+                            && replace_first_lifetime_mut(ty, new_lifetime.clone())
+                        {
+                            return true;
+                        }
                     }
                 }
             }
@@ -829,6 +938,20 @@ mod snapshot_tests {
         assert_snapshot!(test_routing_controller(input));
     }
 
+    #[test]
+    fn subcontroller_with_error_boundary() {
+        let input = r#"
+            #[controller(AppRoute)]
+            #[subcontroller(SidebarController, route = Sidebar, path = "sidebar/", error_boundary = "Self::render_section_error")]
+            #[subcontroller(FeedController, route = Feed, path = "feed/", error_boundary = "Self::render_section_error", convert_response = "Self::convert_feed")]
+            struct AppController {
+                sidebar: SidebarController,
+                feed: FeedController,
+            }
+        "#;
+        assert_snapshot!(test_routing_controller(input));
+    }
+
     #[test]
     fn subcontroller_with_doc() {
         let input = r#"
@@ -900,6 +1023,61 @@ mod snapshot_tests {
         assert_snapshot!(test_routing_controller(input));
     }
 
+    #[test]
+    fn with_pre_handle_alias() {
+        let with_alias = test_routing_controller(
+            r#"
+            #[controller(AppRoute, args = Session, pre_handle = "Self::authenticate")]
+            #[subcontroller(DashboardController, route = Dashboard, path = "dashboard/")]
+            struct AppController {
+                dashboard: DashboardController,
+            }
+        "#,
+        );
+        let with_pre_handler = test_routing_controller(
+            r#"
+            #[controller(AppRoute, args = Session, pre_handler = "Self::authenticate")]
+            #[subcontroller(DashboardController, route = Dashboard, path = "dashboard/")]
+            struct AppController {
+                dashboard: DashboardController,
+            }
+        "#,
+        );
+
+        assert_eq!(with_alias, with_pre_handler);
+    }
+
+    #[test]
+    fn duplicate_pre_handler() {
+        let mut input: syn::DeriveInput = syn::parse_str(
+            r#"
+            #[controller(AppRoute, pre_handler = "Self::a", pre_handle = "Self::b")]
+            struct AppController;
+        "#,
+        )
+        .unwrap();
+
+        let err = derive(&mut input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "duplicate `pre_handler` or `pre_handle` parameter"
+        );
+    }
+
+    #[test]
+    fn with_post_handle() {
+        let input = r#"
+            #[controller(AppRoute, pre_handler = "Self::authenticate", post_handle = "Self::layout")]
+            #[subcontroller(DashboardController, route = Dashboard, path = "dashboard/")]
+            #[subcontroller(AdminController, route = Admin, path = "admin/", convert_response = "Self::convert")]
+            struct AppController {
+                dashboard: DashboardController,
+                admin: AdminController,
+            }
+        "#;
+        assert_snapshot!(test_routing_controller(input));
+    }
+
     #[test]
     fn without_pre_handler() {
         let input = r#"
@@ -950,6 +1128,32 @@ mod snapshot_tests {
         assert_snapshot!(test_routing_controller(input));
     }
 
+    #[test]
+    fn subcontroller_with_host() {
+        let input = r#"
+            #[controller(AppRoute)]
+            #[subcontroller(AdminController, route = Admin, host = "admin.{base}")]
+            #[subcontroller(MainController, route = Main)]
+            struct AppController;
+        "#;
+        assert_snapshot!(test_routing_controller(input));
+    }
+
+    #[test]
+    fn subcontroller_with_host_and_path_error() {
+        let input = r#"
+            #[controller(AppRoute)]
+            #[subcontroller(AdminController, route = Admin, host = "admin.{base}", path = "admin/")]
+            struct AppController;
+        "#;
+        let mut parsed: syn::DeriveInput = syn::parse_str(input).expect("Failed to parse input");
+        let error_message = derive(&mut parsed).unwrap_err().to_string();
+        assert_eq!(
+            error_message,
+            "`host` and `path` cannot be specified together"
+        );
+    }
+
     #[test]
     fn multiple_default_subcontrollers_error() {
         let input = r#"
@@ -158,6 +158,71 @@ pub fn extract_generic_param_idents(
     quote! { #(#idents),* }
 }
 
+/// Get the inner type of an `Option<T>` type.
+pub fn option_inner_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Get the documentation of an item from its doc comments, if any.
+///
+/// The lines of the doc comments are joined, without their leading space.
+pub fn doc_comment(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines: Vec<_> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(lit),
+                        ..
+                    }),
+                ..
+            }) => Some(lit.value()),
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').map(str::to_owned).unwrap_or(line))
+        .collect();
+
+    let doc = lines.join("\n").trim().to_owned();
+
+    (!doc.is_empty()).then_some(doc)
+}
+
+/// Get the name of a type as written in the source, like `Option<Vec<u32>>`.
+pub fn type_name(ty: &syn::Type) -> String {
+    let tokens = quote::ToTokens::to_token_stream(ty).to_string();
+    let chars: Vec<_> = tokens.chars().collect();
+    let is_punct = |c: Option<&char>| c.is_some_and(|c| "<>:&()[]".contains(*c));
+
+    chars
+        .iter()
+        .enumerate()
+        .filter(|&(i, c)| {
+            *c != ' '
+                || !(is_punct(i.checked_sub(1).and_then(|i| chars.get(i)))
+                    || is_punct(chars.get(i + 1))
+                    || chars.get(i + 1) == Some(&','))
+        })
+        .map(|(_, c)| c)
+        .collect()
+}
+
 #[cfg(test)]
 pub mod testing {
     //! Test utilities for snapshot testing derive macros.
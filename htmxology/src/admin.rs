@@ -0,0 +1,575 @@
+//! Administrative endpoints, to inspect and purge the cache and session stores.
+//!
+//! The endpoints are mounted on a router with
+//! [`ControllerRouter::with_admin`](crate::ControllerRouter::with_admin), and render HTML
+//! fragments that can be embedded in an administration page:
+//!
+//! ```rust,ignore
+//! let admin = Admin::new(|parts| is_administrator(parts)).with_cache_store(store.clone());
+//!
+//! let router = ControllerRouter::new(controller, |_| async {}).with_admin("/admin", admin);
+//! ```
+//!
+//! With the page loading the fragments:
+//!
+//! ```html
+//! <div hx-get="/admin/cache" hx-trigger="load" hx-swap="outerHTML"></div>
+//! ```
+//!
+//! Each fragment lists a page of entries, with buttons to purge a single entry or all of them
+//! after a confirmation. As purges are `POST` requests, pages protected against cross-site
+//! request forgery must send the CSRF header, for instance with
+//! [`CsrfToken::hx_headers_attribute`](crate::csrf::CsrfToken::hx_headers_attribute).
+
+use std::{fmt::Display, sync::Arc, time::Duration};
+
+use axum::response::IntoResponse;
+
+use crate::{
+    CacheStore,
+    htmx::{HtmlId, Identity, escape_attribute_value, escape_text},
+};
+
+/// The default number of entries per page.
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// The `id` of the cache fragment.
+const CACHE_FRAGMENT_ID: &str = "htmxology-admin-cache";
+
+/// The `id` of the sessions fragment.
+#[cfg(feature = "session")]
+const SESSIONS_FRAGMENT_ID: &str = "htmxology-admin-sessions";
+
+/// A function deciding whether a request is allowed to use the administrative endpoints.
+type Authorize = Arc<dyn Fn(&http::request::Parts) -> bool + Send + Sync>;
+
+/// The configuration of the administrative endpoints.
+///
+/// The cache store fragment is served at `<path>/cache`, and the session store fragment at
+/// `<path>/sessions`, for the stores that are set.
+#[derive(Clone)]
+pub struct Admin {
+    authorize: Authorize,
+    cache_store: Option<Arc<dyn CacheStore>>,
+    #[cfg(feature = "session")]
+    session_store: Option<crate::session::MemorySessionStore>,
+    page_size: usize,
+}
+
+impl std::fmt::Debug for Admin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Admin")
+            .field("cache_store", &self.cache_store.is_some())
+            .field("page_size", &self.page_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Admin {
+    /// Create the administrative endpoints, allowing the requests for which `authorize` returns
+    /// `true`.
+    ///
+    /// Other requests are rejected with `403 Forbidden`. The function typically checks the
+    /// session of the request, or a header set by an authenticating proxy.
+    pub fn new(authorize: impl Fn(&http::request::Parts) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            authorize: Arc::new(authorize),
+            cache_store: None,
+            #[cfg(feature = "session")]
+            session_store: None,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    /// Serve the fragment of a cache store.
+    pub fn with_cache_store(mut self, cache_store: impl CacheStore) -> Self {
+        self.cache_store = Some(Arc::new(cache_store));
+        self
+    }
+
+    /// Serve the fragment of an in-memory session store.
+    ///
+    /// Sessions are listed by an opaque handle, which does not reveal their cookie.
+    #[cfg(feature = "session")]
+    pub fn with_session_store(mut self, session_store: crate::session::MemorySessionStore) -> Self {
+        self.session_store = Some(session_store);
+        self
+    }
+
+    /// Set the number of entries per page.
+    ///
+    /// # Panics
+    ///
+    /// If `page_size` is zero, the call will panic.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        assert!(page_size > 0, "page size must be greater than zero");
+
+        self.page_size = page_size;
+        self
+    }
+
+    /// Get the paths of the listing endpoints, under the specified path.
+    pub(crate) fn paths(&self, path: &str) -> Vec<String> {
+        let path = path.trim_end_matches('/');
+        let mut paths = Vec::new();
+
+        if self.cache_store.is_some() {
+            paths.push(format!("{path}/cache"));
+        }
+
+        #[cfg(feature = "session")]
+        if self.session_store.is_some() {
+            paths.push(format!("{path}/sessions"));
+        }
+
+        paths
+    }
+
+    /// Build the router of the endpoints, under the specified path.
+    pub(crate) fn router(self, path: &str) -> axum::Router {
+        let path = path.trim_end_matches('/');
+        let mut router = axum::Router::new();
+
+        if let Some(store) = self.cache_store {
+            let url = format!("{path}/cache");
+            let section = Section {
+                id: CACHE_FRAGMENT_ID,
+                url: url.clone(),
+                headers: &["Key", "Status", "Size", "Expires in"],
+                page_size: self.page_size,
+            };
+
+            let list = {
+                let (section, store) = (section.clone(), store.clone());
+
+                move |query: axum::extract::Query<PageQuery>| {
+                    std::future::ready(section.render(cache_rows(&*store), query.page))
+                }
+            };
+            let purge = move |form: axum::Form<PurgeForm>| {
+                match &form.key {
+                    Some(key) => {
+                        store.purge(key);
+                    }
+                    None if form.all => {
+                        store.purge_all();
+                    }
+                    None => {}
+                }
+
+                std::future::ready(section.render(cache_rows(&*store), form.page))
+            };
+
+            router = router
+                .route(&url, axum::routing::get(list))
+                .route(&format!("{url}/purge"), axum::routing::post(purge));
+        }
+
+        #[cfg(feature = "session")]
+        if let Some(store) = self.session_store {
+            let url = format!("{path}/sessions");
+            let section = Section {
+                id: SESSIONS_FRAGMENT_ID,
+                url: url.clone(),
+                headers: &["Handle", "Keys", "Expires in"],
+                page_size: self.page_size,
+            };
+
+            let list = {
+                let (section, store) = (section.clone(), store.clone());
+
+                move |query: axum::extract::Query<PageQuery>| {
+                    std::future::ready(section.render(session_rows(&store), query.page))
+                }
+            };
+            let purge = move |form: axum::Form<PurgeForm>| {
+                match &form.key {
+                    Some(handle) => {
+                        store.purge(handle);
+                    }
+                    None if form.all => {
+                        store.purge_all();
+                    }
+                    None => {}
+                }
+
+                std::future::ready(section.render(session_rows(&store), form.page))
+            };
+
+            router = router
+                .route(&url, axum::routing::get(list))
+                .route(&format!("{url}/purge"), axum::routing::post(purge));
+        }
+
+        let authorize = self.authorize;
+
+        router.route_layer(axum::middleware::from_fn(
+            move |request: axum::extract::Request, next: axum::middleware::Next| {
+                let authorized = {
+                    let (parts, body) = request.into_parts();
+                    let authorized = authorize(&parts);
+
+                    (authorized, axum::extract::Request::from_parts(parts, body))
+                };
+
+                async move {
+                    match authorized {
+                        (true, request) => next.run(request).await,
+                        (false, _) => http::StatusCode::FORBIDDEN.into_response(),
+                    }
+                }
+            },
+        ))
+    }
+}
+
+/// The query of a listing request.
+#[derive(Debug, serde::Deserialize)]
+struct PageQuery {
+    page: Option<usize>,
+}
+
+/// The form of a purge request, purging a single entry or all of them.
+#[derive(Debug, serde::Deserialize)]
+struct PurgeForm {
+    key: Option<String>,
+    #[serde(default)]
+    all: bool,
+    page: Option<usize>,
+}
+
+/// A row of a listing, with the key to purge it and its cells.
+struct Row {
+    key: String,
+    cells: Vec<String>,
+}
+
+fn cache_rows(store: &dyn CacheStore) -> Vec<Row> {
+    store
+        .entries()
+        .into_iter()
+        .map(|entry| Row {
+            cells: vec![
+                entry.key.clone(),
+                entry.status.as_u16().to_string(),
+                format!("{} B", entry.size),
+                format_duration(entry.expires_in),
+            ],
+            key: entry.key,
+        })
+        .collect()
+}
+
+#[cfg(feature = "session")]
+fn session_rows(store: &crate::session::MemorySessionStore) -> Vec<Row> {
+    store
+        .entries()
+        .into_iter()
+        .map(|entry| Row {
+            cells: vec![
+                entry.handle.clone(),
+                entry.keys.join(", "),
+                entry
+                    .expires_in
+                    .map(format_duration)
+                    .unwrap_or_else(|| "never".to_owned()),
+            ],
+            key: entry.handle,
+        })
+        .collect()
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{}s", duration.as_secs())
+}
+
+/// A section of the administrative endpoints, listing the entries of a store.
+#[derive(Debug, Clone)]
+struct Section {
+    id: &'static str,
+    url: String,
+    headers: &'static [&'static str],
+    page_size: usize,
+}
+
+impl Section {
+    /// Render a page of rows, clamping the 1-based page number to the existing pages.
+    fn render(&self, rows: Vec<Row>, page: Option<usize>) -> axum::response::Response {
+        let page_count = rows.len().div_ceil(self.page_size).max(1);
+        let page = page.unwrap_or(1).clamp(1, page_count);
+        let total = rows.len();
+        let rows = rows
+            .into_iter()
+            .skip((page - 1) * self.page_size)
+            .take(self.page_size)
+            .collect();
+
+        axum::response::Html(
+            SectionFragment {
+                section: self,
+                rows,
+                page,
+                page_count,
+                total,
+            }
+            .to_string(),
+        )
+        .into_response()
+    }
+}
+
+/// The HTML fragment of a page of a section.
+struct SectionFragment<'s> {
+    section: &'s Section,
+    rows: Vec<Row>,
+    page: usize,
+    page_count: usize,
+    total: usize,
+}
+
+impl Identity for SectionFragment<'_> {
+    fn id(&self) -> HtmlId {
+        HtmlId::from_static(self.section.id).expect("admin fragment id should be valid")
+    }
+}
+
+impl SectionFragment<'_> {
+    /// Write a purge form, for a single entry or for all of them.
+    fn write_purge_form(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        key: Option<&str>,
+    ) -> std::fmt::Result {
+        let (confirm, field, label) = match key {
+            Some(key) => (
+                format!("Purge `{key}`?"),
+                format!(
+                    r#"<input type="hidden" name="key" value="{}">"#,
+                    escape_attribute_value(key)
+                ),
+                "Purge",
+            ),
+            None => (
+                format!("Purge all the {} entries?", self.total),
+                r#"<input type="hidden" name="all" value="true">"#.to_owned(),
+                "Purge all",
+            ),
+        };
+
+        write!(
+            f,
+            r#"<form hx-post="{}/purge" hx-confirm="{}">{field}<input type="hidden" name="page" value="{}"><button type="submit">{label}</button></form>"#,
+            escape_attribute_value(&self.section.url),
+            escape_attribute_value(&confirm),
+            self.page,
+        )
+    }
+
+    /// Write a link to another page, if it exists.
+    fn write_page_link(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        page: usize,
+        label: &str,
+    ) -> std::fmt::Result {
+        if page == 0 || page > self.page_count {
+            return Ok(());
+        }
+
+        write!(
+            f,
+            r#"<button type="button" hx-get="{}?page={page}">{label}</button>"#,
+            escape_attribute_value(&self.section.url),
+        )
+    }
+}
+
+impl Display for SectionFragment<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            r#"<div {} hx-target="this" hx-swap="outerHTML"><p>{} entries</p>"#,
+            self.id_attribute(),
+            self.total
+        )?;
+
+        if self.total > 0 {
+            self.write_purge_form(f, None)?;
+        }
+
+        f.write_str("<table><thead><tr>")?;
+
+        for header in self.section.headers {
+            write!(f, "<th>{header}</th>")?;
+        }
+
+        f.write_str("<th></th></tr></thead><tbody>")?;
+
+        for row in &self.rows {
+            f.write_str("<tr>")?;
+
+            for cell in &row.cells {
+                write!(f, "<td>{}</td>", escape_text(cell))?;
+            }
+
+            f.write_str("<td>")?;
+            self.write_purge_form(f, Some(&row.key))?;
+            f.write_str("</td></tr>")?;
+        }
+
+        f.write_str("</tbody></table><nav>")?;
+        self.write_page_link(f, self.page - 1, "Previous")?;
+        write!(f, "<span>Page {} of {}</span>", self.page, self.page_count)?;
+        self.write_page_link(f, self.page + 1, "Next")?;
+
+        f.write_str("</nav></div>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_service::Service as _;
+
+    use super::*;
+    use crate::MemoryCacheStore;
+
+    async fn populate(store: &MemoryCacheStore, urls: &[&str]) {
+        for url in urls {
+            store
+                .get_or_render(
+                    format!("GET {url}"),
+                    &http::HeaderMap::new(),
+                    Duration::from_secs(60),
+                    Box::new(|_: &http::HeaderMap| Some(1024)),
+                    Box::new(|| Box::pin(async { Ok("cached".into_response()) })),
+                )
+                .await
+                .unwrap();
+        }
+    }
+
+    async fn call(
+        router: &mut axum::Router,
+        request: http::request::Builder,
+        body: &str,
+    ) -> (http::StatusCode, String) {
+        let response = router
+            .call(
+                request
+                    .body(axum::body::Body::from(body.to_owned()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn admin(store: &MemoryCacheStore) -> axum::Router {
+        Admin::new(|parts| parts.headers.contains_key("x-admin"))
+            .with_cache_store(store.clone())
+            .with_page_size(2)
+            .router("/admin/")
+    }
+
+    #[tokio::test]
+    async fn test_cache_listing() {
+        let store = MemoryCacheStore::default();
+        populate(&store, &["/c", "/a", "/b<"]).await;
+        let mut router = admin(&store);
+
+        let (status, _) = call(&mut router, http::Request::get("/admin/cache"), "").await;
+        assert_eq!(status, http::StatusCode::FORBIDDEN);
+
+        let (status, body) = call(
+            &mut router,
+            http::Request::get("/admin/cache").header("x-admin", "1"),
+            "",
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert!(body.starts_with(r#"<div id="htmxology-admin-cache" hx-target="this""#));
+        assert!(body.contains("<p>3 entries</p>"));
+        assert!(body.contains("<td>GET /a</td>"));
+        assert!(body.contains("<td>GET /b&lt;</td>"));
+        assert!(body.contains(r#"<input type="hidden" name="key" value="GET /b&lt;">"#));
+        assert!(!body.contains("GET /c"));
+        assert!(body.contains(r#"hx-confirm="Purge all the 3 entries?""#));
+        assert!(!body.contains("Previous"));
+        assert!(body.contains(r#"hx-get="/admin/cache?page=2">Next"#));
+
+        // Out of range pages are clamped.
+        let (_, body) = call(
+            &mut router,
+            http::Request::get("/admin/cache?page=9").header("x-admin", "1"),
+            "",
+        )
+        .await;
+        assert!(body.contains("<td>GET /c</td>"));
+        assert!(body.contains("<span>Page 2 of 2</span>"));
+        assert!(body.contains(r#"hx-get="/admin/cache?page=1">Previous"#));
+        assert!(!body.contains("Next"));
+    }
+
+    #[tokio::test]
+    async fn test_cache_purge() {
+        let store = MemoryCacheStore::default();
+        populate(&store, &["/a", "/b", "/c"]).await;
+        let mut router = admin(&store);
+
+        let purge = || {
+            http::Request::post("/admin/cache/purge")
+                .header(
+                    http::header::CONTENT_TYPE,
+                    "application/x-www-form-urlencoded",
+                )
+                .header("x-admin", "1")
+        };
+
+        let (status, body) = call(&mut router, purge(), "key=GET+%2Fc&page=2").await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert!(body.contains("<p>2 entries</p>"));
+        assert!(body.contains("<span>Page 1 of 1</span>"));
+        assert_eq!(store.metrics().entries, 2);
+
+        let (_, body) = call(&mut router, purge(), "all=true").await;
+        assert!(body.contains("<p>0 entries</p>"));
+        assert!(!body.contains("Purge all"));
+        assert!(store.entries().is_empty());
+    }
+
+    #[cfg(feature = "session")]
+    #[tokio::test]
+    async fn test_sessions() {
+        use crate::session::{MemorySessionStore, SessionData, SessionStore as _};
+
+        let store = MemorySessionStore::default();
+        let cookie = store.save(None, SessionData::new()).await.unwrap();
+        let mut router = Admin::new(|_| true)
+            .with_session_store(store.clone())
+            .router("/admin");
+
+        let (_, body) = call(&mut router, http::Request::get("/admin/sessions"), "").await;
+        let handle = &store.entries()[0].handle;
+        assert!(body.contains(r#"<div id="htmxology-admin-sessions""#));
+        assert!(body.contains(&format!("<td>{handle}</td>")));
+        assert!(body.contains("<td>never</td>"));
+        assert!(!body.contains(&cookie));
+
+        let (_, body) = call(
+            &mut router,
+            http::Request::post("/admin/sessions/purge").header(
+                http::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            ),
+            &format!("key={handle}"),
+        )
+        .await;
+        assert!(body.contains("<p>0 entries</p>"));
+        assert!(store.is_empty());
+    }
+}
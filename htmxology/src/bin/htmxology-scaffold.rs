@@ -0,0 +1,82 @@
+//! Generate a starter htmxology project.
+//!
+//! ```not_rust
+//! htmxology-scaffold <name> [--dir <dir>] [--no-sessions] [--no-auth] [--no-static-assets]
+//!     [--htmxology-path <path>]
+//! ```
+
+use std::{path::PathBuf, process::ExitCode};
+
+use htmxology::scaffold::Scaffold;
+
+const USAGE: &str = "usage: htmxology-scaffold <name> [--dir <dir>] [--no-sessions] [--no-auth] \
+                     [--no-static-assets] [--htmxology-path <path>]";
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let mut name = None;
+    let mut dir = None;
+    let mut sessions = true;
+    let mut auth = true;
+    let mut static_assets = true;
+    let mut htmxology_path = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dir" => dir = args.next().map(PathBuf::from),
+            "--no-sessions" => sessions = false,
+            "--no-auth" => auth = false,
+            "--no-static-assets" => static_assets = false,
+            "--htmxology-path" => htmxology_path = args.next().map(PathBuf::from),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+
+                return ExitCode::SUCCESS;
+            }
+            _ if arg.starts_with('-') || name.is_some() => {
+                eprintln!("unexpected argument `{arg}`\n{USAGE}");
+
+                return ExitCode::from(2);
+            }
+            _ => name = Some(arg),
+        }
+    }
+
+    let Some(name) = name else {
+        eprintln!("{USAGE}");
+
+        return ExitCode::from(2);
+    };
+
+    let scaffold = match Scaffold::new(name) {
+        Ok(scaffold) => scaffold
+            .with_sessions(sessions)
+            .with_auth(auth)
+            .with_static_assets(static_assets),
+        Err(err) => {
+            eprintln!("{err}");
+
+            return ExitCode::from(2);
+        }
+    };
+    let scaffold = match htmxology_path {
+        Some(path) => scaffold.with_htmxology_path(path),
+        None => scaffold,
+    };
+    let dir = dir.unwrap_or_else(|| PathBuf::from(scaffold.name()));
+
+    match scaffold.write_to(&dir) {
+        Ok(paths) => {
+            for path in paths {
+                println!("created {}", path.display());
+            }
+
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{err}");
+
+            ExitCode::FAILURE
+        }
+    }
+}
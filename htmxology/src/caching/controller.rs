@@ -2,11 +2,17 @@ use std::{future::Future, sync::Arc};
 
 use tracing::warn;
 
+use crate::Route;
+
 /// A controller that adds caching strategy support to another controller.
 ///
 /// Only requests that result in a `Result::Ok` from the `handle_request` method will be considered
 /// for caching. Requests that result in a `Result::Err` will bypass the cache and be handled
 /// directly by the inner controller.
+///
+/// If the cache has a [`CacheStore`](super::CacheStore), responses to `GET` routes are rendered
+/// once and served from the store until they expire, to the requests with the same
+/// [cache key](super::CacheKey).
 pub struct Controller<C: crate::Controller> {
     pub controller: C,
     pub cache: Arc<super::Cache<C::Route>>,
@@ -23,8 +29,9 @@ impl<C: crate::Controller> Clone for Controller<C> {
 
 impl<C> crate::Controller for Controller<C>
 where
-    C: crate::Controller<Response = Result<axum::response::Response, axum::response::Response>>,
-    C::Route: crate::Route + Send + Sync + axum::extract::FromRequest<Self>,
+    C: crate::Controller<Response = Result<axum::response::Response, axum::response::Response>>
+        + 'static,
+    C::Route: crate::Route + Send + Sync + axum::extract::FromRequest<Self> + 'static,
 {
     type Route = C::Route;
     type Args = C::Args;
@@ -39,17 +46,59 @@ where
         args: Self::Args,
     ) -> impl Future<Output = Self::Response> + Send {
         let cache_control = self.cache.get_cache_control(&route, &htmx, &parts);
+        let directives = self.cache.get_cache_directives(&route, &htmx);
         let url = route.to_string();
+        let store_key = self
+            .cache
+            .get_store_key(&route, &htmx, &parts)
+            .map(|store_key| (store_key, parts.headers.clone()));
+
+        let debug_key = self.cache.debug_headers.then(|| match &store_key {
+            Some((store_key, _)) => store_key.to_string(),
+            None => format!("{} {url}", route.method()),
+        });
 
         async move {
-            let response = self
-                .controller
-                .handle_request(route, htmx, parts, server_info, args)
-                .await?;
+            let response = match store_key {
+                Some((store_key, request_headers)) => {
+                    self.cache
+                        .get_or_render(
+                            store_key,
+                            &request_headers,
+                            &cache_control,
+                            &directives,
+                            // The render may run in the background, if the store revalidates
+                            // expired responses.
+                            {
+                                let controller = self.controller.clone();
+                                let server_info = server_info.clone();
+
+                                move || async move {
+                                    controller
+                                        .handle_request(route, htmx, parts, &server_info, args)
+                                        .await
+                                }
+                            },
+                        )
+                        .await?
+                }
+                None => {
+                    self.controller
+                        .handle_request(route, htmx, parts, server_info, args)
+                        .await?
+                }
+            };
+
+            let debug_info =
+                debug_key.map(|key| super::CacheDebugInfo::new(key, &cache_control, &response));
 
             self.cache
-                .check_cache_control(cache_control, response)
+                .check_cache_control(cache_control, &directives, response)
                 .await
+                .map(|response| match debug_info {
+                    Some(debug_info) => debug_info.apply(response),
+                    None => response,
+                })
                 .inspect_err(|_| warn!("Cache control failed for route: {url}"))
         }
     }
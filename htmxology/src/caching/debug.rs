@@ -0,0 +1,181 @@
+//! Debug headers describing cache decisions.
+
+use std::time::Instant;
+
+use tracing::debug;
+
+use super::{CacheControl, CacheHit, CacheRevalidating, CacheStale, CacheStored};
+
+/// The header describing the cache decision.
+const CACHE_HEADER: http::HeaderName = http::HeaderName::from_static("x-htmxology-cache");
+
+/// The header holding the computed cache key.
+const CACHE_KEY_HEADER: http::HeaderName = http::HeaderName::from_static("x-htmxology-cache-key");
+
+/// The header holding the remaining time to live of the stored response, in seconds.
+const CACHE_TTL_HEADER: http::HeaderName = http::HeaderName::from_static("x-htmxology-cache-ttl");
+
+/// How the cache handled a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decision {
+    /// The response was served from the store.
+    Hit,
+
+    /// The response was rendered, and possibly stored.
+    Miss,
+
+    /// A stale response was served in place of a failed render.
+    Stale,
+
+    /// A stale response was served while it is revalidated in the background.
+    Revalidating,
+
+    /// The client already had the response, which was answered with `304 Not Modified`.
+    Revalidated,
+
+    /// The request asked to bypass the cache.
+    Bypass,
+}
+
+impl Decision {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hit => "hit",
+            Self::Miss => "miss",
+            Self::Stale => "stale",
+            Self::Revalidating => "revalidating",
+            Self::Revalidated => "revalidated",
+            Self::Bypass => "bypass",
+        }
+    }
+}
+
+/// The description of a cache decision, captured before the cache control is checked.
+pub(crate) struct CacheDebugInfo {
+    key: String,
+    decision: Decision,
+    expires_at: Option<Instant>,
+}
+
+impl CacheDebugInfo {
+    /// Describe how the cache handled a rendered or stored response.
+    pub(crate) fn new(
+        key: String,
+        cache_control: &CacheControl,
+        response: &axum::response::Response,
+    ) -> Self {
+        let hit = response.extensions().get::<CacheHit>();
+        let stored = response.extensions().get::<CacheStored>();
+
+        let (decision, expires_at) = match (cache_control, hit, stored) {
+            (CacheControl::NoCache, _, _) => (Decision::Bypass, None),
+            _ if response.extensions().get::<CacheStale>().is_some() => (Decision::Stale, None),
+            _ if response.extensions().get::<CacheRevalidating>().is_some() => {
+                (Decision::Revalidating, None)
+            }
+            (_, Some(hit), _) => (Decision::Hit, Some(hit.expires_at)),
+            (_, None, Some(stored)) => (Decision::Miss, Some(stored.expires_at)),
+            (_, None, None) => (Decision::Miss, None),
+        };
+
+        Self {
+            key,
+            decision,
+            expires_at,
+        }
+    }
+
+    /// Log the decision and add the debug headers to the final response.
+    pub(crate) fn apply(self, mut response: axum::response::Response) -> axum::response::Response {
+        let decision = match self.decision {
+            Decision::Hit | Decision::Miss
+                if response.status() == http::StatusCode::NOT_MODIFIED =>
+            {
+                Decision::Revalidated
+            }
+            decision => decision,
+        }
+        .as_str();
+        let ttl = self.expires_at.map(|expires_at| {
+            expires_at
+                .saturating_duration_since(Instant::now())
+                .as_secs()
+        });
+
+        match ttl {
+            Some(ttl) => debug!("Cache {decision} for `{}`, expiring in {ttl}s", self.key),
+            None => debug!("Cache {decision} for `{}`", self.key),
+        }
+
+        let headers = response.headers_mut();
+
+        headers.insert(CACHE_HEADER, http::HeaderValue::from_static(decision));
+
+        if let Ok(key) = http::HeaderValue::from_str(&self.key) {
+            headers.insert(CACHE_KEY_HEADER, key);
+        }
+
+        if let Some(ttl) = ttl {
+            headers.insert(CACHE_TTL_HEADER, ttl.into());
+        }
+
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::response::IntoResponse;
+
+    use super::*;
+
+    fn headers(response: &axum::response::Response) -> [Option<&str>; 3] {
+        [CACHE_HEADER, CACHE_KEY_HEADER, CACHE_TTL_HEADER]
+            .map(|name| response.headers().get(name).map(|v| v.to_str().unwrap()))
+    }
+
+    #[test]
+    fn test_decisions() {
+        let if_none_match = CacheControl::IfNoneMatch(Default::default());
+        let expires_at = Instant::now() + Duration::from_secs(30);
+
+        let mut hit = "Hello".into_response();
+        hit.extensions_mut().insert(CacheHit { expires_at });
+        let response = CacheDebugInfo::new("GET /".to_owned(), &if_none_match, &hit).apply(hit);
+        let [decision, key, ttl] = headers(&response);
+        assert_eq!(decision, Some("hit"));
+        assert_eq!(key, Some("GET /"));
+        assert!(matches!(ttl, Some("29" | "30")));
+
+        let miss = "Hello".into_response();
+        let response = CacheDebugInfo::new("GET /".to_owned(), &if_none_match, &miss).apply(miss);
+        assert_eq!(headers(&response), [Some("miss"), Some("GET /"), None]);
+
+        let mut stored = "Hello".into_response();
+        stored.extensions_mut().insert(CacheStored { expires_at });
+        let info = CacheDebugInfo::new("GET /".to_owned(), &if_none_match, &stored);
+        let response = info.apply(http::StatusCode::NOT_MODIFIED.into_response());
+        assert_eq!(headers(&response)[0], Some("revalidated"));
+
+        let bypass = "Hello".into_response();
+        let response =
+            CacheDebugInfo::new("GET /".to_owned(), &CacheControl::NoCache, &bypass).apply(bypass);
+        assert_eq!(headers(&response), [Some("bypass"), Some("GET /"), None]);
+
+        let mut stale = "Hello".into_response();
+        stale.extensions_mut().insert(CacheStale);
+        let response = CacheDebugInfo::new("GET /".to_owned(), &if_none_match, &stale).apply(stale);
+        assert_eq!(headers(&response), [Some("stale"), Some("GET /"), None]);
+
+        let mut revalidating = "Hello".into_response();
+        revalidating.extensions_mut().insert(CacheRevalidating);
+        let response = CacheDebugInfo::new("GET /".to_owned(), &if_none_match, &revalidating)
+            .apply(revalidating);
+        assert_eq!(
+            headers(&response),
+            [Some("revalidating"), Some("GET /"), None]
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! Cache key derivation.
+
+use std::collections::BTreeSet;
+
+use crate::Route;
+
+/// A function identifying the principal of a request, like its user.
+type PrincipalFn = dyn Fn(&http::request::Parts) -> Option<String> + Send + Sync;
+
+/// The derivation of the keys of the responses kept in a [`CacheStore`](super::CacheStore).
+///
+/// Requests with the same key are served the same stored response, so the key must capture
+/// everything the response depends on: responses rendered for a user must be keyed by that user,
+/// or they would be served to all the others.
+///
+/// Functions taking the route, the HTMX request and the request parts implement this trait:
+///
+/// ```rust,ignore
+/// let cache = Cache::default()
+///     .with_store(MemoryCacheStore::default())
+///     .with_cache_key(|route: &AppRoute, htmx: &htmx::Request, parts: &http::request::Parts| {
+///         let key = RequestCacheKey::default().cache_key(route, htmx, parts)?;
+///
+///         Some(format!("{key} [user: {}]", user_id(parts)?))
+///     });
+/// ```
+pub trait CacheKey<R>: Send + Sync {
+    /// Get the key of the response to a request, or `None` if it must not be stored.
+    fn cache_key(
+        &self,
+        route: &R,
+        htmx: &crate::htmx::Request,
+        parts: &http::request::Parts,
+    ) -> Option<String>;
+
+    /// Get the request headers the keys depend on.
+    ///
+    /// They are added to the `Vary` header of the responses, so that shared caches key them the
+    /// same way.
+    fn vary(&self) -> Vec<http::HeaderName> {
+        Vec::new()
+    }
+}
+
+impl<R, F> CacheKey<R> for F
+where
+    F: Fn(&R, &crate::htmx::Request, &http::request::Parts) -> Option<String> + Send + Sync,
+{
+    fn cache_key(
+        &self,
+        route: &R,
+        htmx: &crate::htmx::Request,
+        parts: &http::request::Parts,
+    ) -> Option<String> {
+        self(route, htmx, parts)
+    }
+}
+
+/// A cache key made of the URL of the route, the `HX-Request` and `HX-Target` headers, and
+/// selected parts of the request.
+///
+/// The default cache key keys responses by their whole URL and HTMX headers.
+///
+/// ```rust,ignore
+/// let key = RequestCacheKey::default()
+///     .with_query_params(["page", "sort"])
+///     .with_header(http::header::ACCEPT_LANGUAGE)
+///     .with_cookie("theme")
+///     .with_principal(|parts| user_id(parts).map(|id| id.to_string()));
+/// ```
+#[derive(Default)]
+pub struct RequestCacheKey {
+    query_params: Option<BTreeSet<String>>,
+    headers: Vec<http::HeaderName>,
+    cookies: Vec<String>,
+    principal: Option<Box<PrincipalFn>>,
+}
+
+impl std::fmt::Debug for RequestCacheKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestCacheKey")
+            .field("query_params", &self.query_params)
+            .field("headers", &self.headers)
+            .field("cookies", &self.cookies)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RequestCacheKey {
+    /// Only key responses by the specified query parameters, ignoring the others, like tracking
+    /// parameters.
+    ///
+    /// Parameter names are compared as they appear in the URL.
+    pub fn with_query_params<I>(mut self, names: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.query_params
+            .get_or_insert_default()
+            .extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Key responses by the value of a request header.
+    pub fn with_header(mut self, name: http::HeaderName) -> Self {
+        self.headers.push(name);
+        self
+    }
+
+    /// Key responses by the value of a request cookie.
+    pub fn with_cookie(mut self, name: impl Into<String>) -> Self {
+        self.cookies.push(name.into());
+        self
+    }
+
+    /// Key responses by the principal of the request, like its authenticated user.
+    ///
+    /// Responses are only stored for requests with a principal.
+    pub fn with_principal(
+        mut self,
+        principal: impl Fn(&http::request::Parts) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.principal = Some(Box::new(principal));
+        self
+    }
+
+    /// Get the URL of a route, keeping only the selected query parameters.
+    fn url(&self, route: &impl Route) -> String {
+        let url = super::route_url(route);
+
+        let (Some(query_params), Some((path, query))) = (&self.query_params, url.split_once('?'))
+        else {
+            return url;
+        };
+
+        let query = query
+            .split('&')
+            .filter(|param| {
+                let name = param.split_once('=').map_or(*param, |(name, _)| name);
+
+                query_params.contains(name)
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+
+        if query.is_empty() {
+            path.to_owned()
+        } else {
+            format!("{path}?{query}")
+        }
+    }
+
+    /// Get the value of a cookie of a request.
+    fn cookie<'a>(parts: &'a http::request::Parts, name: &str) -> Option<&'a str> {
+        parts
+            .headers
+            .get_all(http::header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .filter_map(|cookie| cookie.trim().split_once('='))
+            .find(|(cookie_name, _)| *cookie_name == name)
+            .map(|(_, value)| value)
+    }
+}
+
+impl<R: Route> CacheKey<R> for RequestCacheKey {
+    fn cache_key(
+        &self,
+        route: &R,
+        htmx: &crate::htmx::Request,
+        parts: &http::request::Parts,
+    ) -> Option<String> {
+        let mut key = super::StoreKey::new(self.url(route), htmx).to_string();
+        let mut components = Vec::new();
+
+        for name in &self.headers {
+            let values = parts
+                .headers
+                .get_all(name)
+                .iter()
+                .map(|value| String::from_utf8_lossy(value.as_bytes()))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            components.push(format!("{name}: {values}"));
+        }
+
+        for name in &self.cookies {
+            components.push(format!(
+                "cookie {name}: {}",
+                Self::cookie(parts, name).unwrap_or_default()
+            ));
+        }
+
+        if let Some(principal) = &self.principal {
+            components.push(format!("principal: {}", principal(parts)?));
+        }
+
+        if !components.is_empty() {
+            key = format!("{key} [{}]", components.join(", "));
+        }
+
+        Some(key)
+    }
+
+    fn vary(&self) -> Vec<http::HeaderName> {
+        let mut vary = self.headers.clone();
+
+        if !self.cookies.is_empty() || self.principal.is_some() {
+            vary.push(http::header::COOKIE);
+        }
+
+        vary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Search(&'static str);
+
+    impl std::fmt::Display for Search {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "/search{}", self.0)
+        }
+    }
+
+    impl std::str::FromStr for Search {
+        type Err = crate::ParseError;
+
+        fn from_str(_s: &str) -> Result<Self, Self::Err> {
+            Ok(Self(""))
+        }
+    }
+
+    impl Route for Search {
+        fn method(&self) -> http::Method {
+            http::Method::GET
+        }
+    }
+
+    fn parts(headers: &[(&str, &str)]) -> http::request::Parts {
+        let mut request = http::Request::builder();
+
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        request.body(()).unwrap().into_parts().0
+    }
+
+    #[test]
+    fn test_default() {
+        let key = RequestCacheKey::default();
+
+        assert_eq!(
+            key.cache_key(
+                &Search("?q=rust&utm_source=mail"),
+                &crate::htmx::Request::Classic,
+                &parts(&[("cookie", "theme=dark")]),
+            )
+            .as_deref(),
+            Some("GET /search?q=rust&utm_source=mail")
+        );
+        assert!(CacheKey::<Search>::vary(&key).is_empty());
+    }
+
+    #[test]
+    fn test_request_parts() {
+        let key = RequestCacheKey::default()
+            .with_query_params(["q", "page"])
+            .with_header(http::header::ACCEPT_LANGUAGE)
+            .with_cookie("theme")
+            .with_principal(|parts| {
+                parts
+                    .headers
+                    .get("x-user")
+                    .map(|value| value.to_str().unwrap().to_owned())
+            });
+        let parts = parts(&[
+            ("accept-language", "fr"),
+            ("cookie", "session=abc; theme=dark"),
+            ("x-user", "42"),
+        ]);
+
+        assert_eq!(
+            key.cache_key(
+                &Search("?utm_source=mail&q=rust"),
+                &crate::htmx::Request::Classic,
+                &parts,
+            )
+            .as_deref(),
+            Some("GET /search?q=rust [accept-language: fr, cookie theme: dark, principal: 42]")
+        );
+        assert_eq!(
+            key.cache_key(
+                &Search("?utm_source=mail"),
+                &crate::htmx::Request::Classic,
+                &parts,
+            )
+            .as_deref(),
+            Some("GET /search [accept-language: fr, cookie theme: dark, principal: 42]")
+        );
+        assert_eq!(
+            CacheKey::<Search>::vary(&key),
+            [http::header::ACCEPT_LANGUAGE, http::header::COOKIE]
+        );
+
+        // Requests without a principal are not stored.
+        assert_eq!(
+            key.cache_key(
+                &Search(""),
+                &crate::htmx::Request::Classic,
+                &self::parts(&[])
+            ),
+            None
+        );
+    }
+}
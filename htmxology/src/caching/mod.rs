@@ -1,54 +1,439 @@
 //! Caching utilities.
 
 mod controller;
+mod debug;
+mod key;
+mod shell;
+mod store;
 
-use std::collections::BTreeSet;
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
 
-use axum::response::IntoResponse;
+use axum::{body::HttpBody, response::IntoResponse};
 pub use controller::{Controller, ControllerExt};
+use debug::CacheDebugInfo;
+pub use key::{CacheKey, RequestCacheKey};
 use md5::Digest;
-use tracing::{error, warn};
+pub use shell::{CachePart, DynamicFragment};
+pub use store::{
+    CacheBodyLimit, CacheRender, CacheStore, CacheStoreEntry, CacheStoreMetrics, CacheTags,
+    MemoryCacheStore,
+};
+pub(crate) use store::{CacheHit, CacheRevalidating, CacheStale, CacheStored, StoreKey};
+use tracing::{debug, error, warn};
 
 use crate::Route;
 
 /// The default maximum body size.
 const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024; // 1 MB
 
+/// The content types never buffered by default.
+const DEFAULT_STREAMING_CONTENT_TYPES: &[&str] = &["text/event-stream"];
+
 /// The default caching duration.
 const DEFAULT_CACHE_DURATION: std::time::Duration = std::time::Duration::from_secs(60);
 
+/// A function deciding the caching directives of a route.
+type CachePolicy<R> = Arc<dyn Fn(&R, &crate::htmx::Request) -> CacheDirectives + Send + Sync>;
+
 /// A caching strategy.
 pub struct Cache<R> {
-    max_body_size: usize,
+    body_limits: Arc<BodyLimits>,
     cache_duration: std::time::Duration,
-    _phantom: std::marker::PhantomData<R>,
+    shell_cache_duration: Option<std::time::Duration>,
+    store: Option<Arc<dyn CacheStore>>,
+    debug_headers: bool,
+    policy: Option<CachePolicy<R>>,
+    key: Option<Arc<dyn CacheKey<R>>>,
 }
 
 impl<R> Default for Cache<R> {
     fn default() -> Self {
         Self {
-            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            body_limits: Default::default(),
             cache_duration: DEFAULT_CACHE_DURATION,
-            _phantom: Default::default(),
+            shell_cache_duration: None,
+            store: None,
+            debug_headers: false,
+            policy: None,
+            key: None,
+        }
+    }
+}
+
+/// The body size limits of a [`Cache`].
+#[derive(Debug, Clone)]
+struct BodyLimits {
+    /// The default maximum body size.
+    max_body_size: usize,
+
+    /// The maximum body sizes of content types and kinds of content types, or `None` if they
+    /// must not be buffered.
+    content_types: Vec<(String, Option<usize>)>,
+}
+
+impl Default for BodyLimits {
+    fn default() -> Self {
+        Self {
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            content_types: DEFAULT_STREAMING_CONTENT_TYPES
+                .iter()
+                .map(|content_type| (content_type.to_string(), None))
+                .collect(),
+        }
+    }
+}
+
+impl BodyLimits {
+    /// Set the maximum body size of a content type, replacing any previous one.
+    fn set_content_type(&mut self, content_type: String, max_body_size: Option<usize>) {
+        let content_type = content_type.trim().to_ascii_lowercase();
+
+        self.content_types
+            .retain(|(existing, _)| *existing != content_type);
+        self.content_types.push((content_type, max_body_size));
+    }
+
+    /// Get the maximum body size of a response, or `None` if it must not be buffered.
+    ///
+    /// The maximum body size of the route, if any, takes precedence over the content type ones.
+    fn get(&self, route_max_body_size: Option<usize>, headers: &http::HeaderMap) -> Option<usize> {
+        let essence = headers
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|essence| essence.trim().to_ascii_lowercase())
+            .unwrap_or_default();
+        let kind = essence.split_once('/').map(|(kind, _)| format!("{kind}/*"));
+
+        // Exact content types take precedence over kinds.
+        let content_type_max_body_size = self
+            .content_types
+            .iter()
+            .find(|(content_type, _)| *content_type == essence)
+            .or_else(|| {
+                self.content_types
+                    .iter()
+                    .find(|(content_type, _)| Some(content_type) == kind.as_ref())
+            })
+            .map(|(_, max_body_size)| *max_body_size);
+
+        match content_type_max_body_size {
+            Some(None) => None,
+            Some(Some(max_body_size)) => Some(route_max_body_size.unwrap_or(max_body_size)),
+            None => Some(route_max_body_size.unwrap_or(self.max_body_size)),
         }
     }
 }
 
+/// The caching directives of a response, as decided by the policy of a [`Cache`] for its route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheDirectives {
+    /// How long the response can be cached, by clients and in the [`CacheStore`].
+    pub max_age: Duration,
+
+    /// Whether shared caches can cache the response, with a `public` directive instead of a
+    /// `private` one.
+    pub public: bool,
+
+    /// Whether the response must not be cached at all, with a `no-store` directive.
+    ///
+    /// Such responses are never kept in the [`CacheStore`].
+    pub no_store: bool,
+
+    /// Whether to add an ETag to the response, and answer requests already having it with
+    /// `304 Not Modified`.
+    ///
+    /// Computing the ETag of a response reads its whole body, which prevents streaming it, unless
+    /// the response already has one, like a weak ETag supplied by a [`CacheableResponse`].
+    pub etag: bool,
+
+    /// The maximum body size of the response, overriding the limits of the [`Cache`].
+    pub max_body_size: Option<usize>,
+
+    /// How long clients and shared caches can serve the response once expired, while they
+    /// revalidate it in the background, with a `stale-while-revalidate` directive.
+    pub stale_while_revalidate: Option<Duration>,
+
+    /// How long clients and shared caches can serve the response once expired, when revalidating
+    /// it fails, with a `stale-if-error` directive.
+    pub stale_if_error: Option<Duration>,
+}
+
+impl CacheDirectives {
+    /// Cache the response privately, for the specified duration, with an ETag.
+    pub fn private(max_age: Duration) -> Self {
+        Self {
+            max_age,
+            public: false,
+            no_store: false,
+            etag: true,
+            max_body_size: None,
+            stale_while_revalidate: None,
+            stale_if_error: None,
+        }
+    }
+
+    /// Cache the response publicly, for the specified duration, with an ETag.
+    pub fn public(max_age: Duration) -> Self {
+        Self {
+            public: true,
+            ..Self::private(max_age)
+        }
+    }
+
+    /// Never cache the response, nor add an ETag to it.
+    pub fn no_store() -> Self {
+        Self {
+            max_age: Duration::ZERO,
+            public: false,
+            no_store: true,
+            etag: false,
+            max_body_size: None,
+            stale_while_revalidate: None,
+            stale_if_error: None,
+        }
+    }
+
+    /// Set whether to add an ETag to the response.
+    pub fn with_etag(mut self, etag: bool) -> Self {
+        self.etag = etag;
+        self
+    }
+
+    /// Set the maximum body size of the response, for routes rendering larger responses than the
+    /// other ones, like exports.
+    ///
+    /// It takes precedence over the limits of the [`Cache`], except for the content types that
+    /// are [never buffered](Cache::with_streaming_content_type).
+    pub fn with_max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = Some(max_body_size);
+        self
+    }
+
+    /// Let clients and shared caches serve the response for the specified duration once expired,
+    /// while they revalidate it in the background.
+    ///
+    /// This only sets the `stale-while-revalidate` directive of the response: the
+    /// [`MemoryCacheStore`] has its own [setting](MemoryCacheStore::with_stale_while_revalidate).
+    pub fn with_stale_while_revalidate(mut self, stale_while_revalidate: Duration) -> Self {
+        self.stale_while_revalidate = Some(stale_while_revalidate);
+        self
+    }
+
+    /// Let clients and shared caches serve the response for the specified duration once expired,
+    /// when revalidating it fails.
+    ///
+    /// This only sets the `stale-if-error` directive of the response: the [`MemoryCacheStore`] has
+    /// its own [setting](MemoryCacheStore::with_stale_if_error).
+    pub fn with_stale_if_error(mut self, stale_if_error: Duration) -> Self {
+        self.stale_if_error = Some(stale_if_error);
+        self
+    }
+}
+
 impl<R> Cache<R> {
-    /// Create a new cache using in-memory storage.
+    /// Set the maximum size of the response bodies read to compute their ETag or to store them.
+    ///
+    /// Responses known to be larger are passed through, without ETag and without being stored.
+    /// Defaults to 1 MB.
     pub fn with_max_body_size(mut self, max_body_size: usize) -> Self {
-        self.max_body_size = max_body_size;
+        Arc::make_mut(&mut self.body_limits).max_body_size = max_body_size;
+        self
+    }
+
+    /// Set the maximum body size of the responses of a content type, like `text/csv`, or of all
+    /// the content types of a kind, like `image/*`.
+    ///
+    /// The content type parameters, like `charset`, are ignored.
+    pub fn with_content_type_max_body_size(
+        mut self,
+        content_type: impl Into<String>,
+        max_body_size: usize,
+    ) -> Self {
+        Arc::make_mut(&mut self.body_limits)
+            .set_content_type(content_type.into(), Some(max_body_size));
+        self
+    }
+
+    /// Never buffer the responses of a content type, like `application/zip`, or of all the
+    /// content types of a kind, like `video/*`.
+    ///
+    /// Such responses are streamed as is, without ETag and without being stored. Event streams
+    /// (`text/event-stream`) are never buffered by default.
+    pub fn with_streaming_content_type(mut self, content_type: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.body_limits).set_content_type(content_type.into(), None);
         self
     }
 
     /// Set the cache duration.
+    ///
+    /// Routes are cached privately for that duration, unless a [policy](Cache::with_policy)
+    /// decides otherwise.
     pub fn with_cache_duration(mut self, cache_duration: std::time::Duration) -> Self {
         self.cache_duration = cache_duration;
         self
     }
+
+    /// Enable shell caching, caching page shells publicly for the specified duration.
+    ///
+    /// Responses marked as [`CachePart::Shell`] get a `public` cache control directive, so that
+    /// they can be served to all visitors by shared caches, while responses marked as
+    /// [`CachePart::Dynamic`] keep a `private` one. Shells should render the visitor-specific
+    /// parts of pages as [`DynamicFragment`] placeholders, which HTMX loads from their own routes.
+    ///
+    /// Shells that set cookies are cached privately, as their content is likely visitor-specific.
+    pub fn with_shell_caching(mut self, shell_cache_duration: std::time::Duration) -> Self {
+        self.shell_cache_duration = Some(shell_cache_duration);
+        self
+    }
+
+    /// Store rendered responses on the server, in the specified store.
+    ///
+    /// Stored responses are kept for the cache duration, and shared by all the requests for the
+    /// same `GET` route, as keyed by the [cache key](Cache::with_cache_key). Requests with a
+    /// `no-cache` directive bypass the store.
+    ///
+    /// Without a cache key, responses are also keyed by the `Cookie` and `Authorization` headers
+    /// of the requests, so that responses built from them are only served back to the same user.
+    /// Configure a cache key to share them between users.
+    ///
+    /// The store can be a [`MemoryCacheStore`], or any other [`CacheStore`] implementation.
+    pub fn with_store(mut self, store: impl CacheStore) -> Self {
+        self.store = Some(Arc::new(store));
+        self
+    }
+
+    /// Decide the caching directives of each route, instead of caching all of them privately for
+    /// the cache duration.
+    ///
+    /// ```rust,ignore
+    /// let cache = Cache::default().with_policy(|route: &AppRoute, _htmx| match route {
+    ///     AppRoute::Home => CacheDirectives::public(Duration::from_secs(300)),
+    ///     AppRoute::Cart => CacheDirectives::no_store(),
+    ///     AppRoute::Feed => CacheDirectives::private(Duration::from_secs(10)).with_etag(false),
+    /// });
+    /// ```
+    ///
+    /// Responses that already have a `Cache-Control` header keep it, and
+    /// [shell caching](Cache::with_shell_caching) takes precedence for page shells.
+    pub fn with_policy(
+        mut self,
+        policy: impl Fn(&R, &crate::htmx::Request) -> CacheDirectives + Send + Sync + 'static,
+    ) -> Self {
+        self.policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// Set how the keys of the stored responses are derived from requests, instead of keying them
+    /// by their URL, their `HX-Request` and `HX-Target` headers and their credentials.
+    ///
+    /// Responses depending on other parts of the request, like a cookie or the authenticated
+    /// user, must be keyed by them, or they would be served to all the requests for the same URL:
+    ///
+    /// ```rust,ignore
+    /// let cache = Cache::default()
+    ///     .with_store(MemoryCacheStore::default())
+    ///     .with_cache_key(RequestCacheKey::default().with_cookie("theme"));
+    /// ```
+    ///
+    /// The headers the keys depend on are added to the `Vary` header of the responses.
+    pub fn with_cache_key(mut self, key: impl CacheKey<R> + 'static) -> Self {
+        self.key = Some(Arc::new(key));
+        self
+    }
+
+    /// Get the caching directives of a route, as decided by the policy.
+    pub fn get_cache_directives(&self, route: &R, htmx: &crate::htmx::Request) -> CacheDirectives {
+        match &self.policy {
+            Some(policy) => policy(route, htmx),
+            None => CacheDirectives::private(self.cache_duration),
+        }
+    }
+
+    /// Describe the cache decisions in response headers, and log them.
+    ///
+    /// This is meant to make the cache behavior observable during development, for instance with
+    /// `.with_debug_headers(cfg!(debug_assertions))`. Responses get the following headers:
+    ///
+    /// - `X-Htmxology-Cache`: `hit` if the response was served from the store, `miss` if it was
+    ///   rendered, `stale` if a stale response was served in place of a failed render,
+    ///   `revalidating` if a stale response was served while it is rendered again in the
+    ///   background, `revalidated` if it was answered with `304 Not Modified`, or `bypass` if the
+    ///   request asked to bypass the cache.
+    /// - `X-Htmxology-Cache-Key`: the computed cache key of the response.
+    /// - `X-Htmxology-Cache-Ttl`: the remaining time to live of the stored response, in seconds.
+    ///
+    /// Disabled by default.
+    pub fn with_debug_headers(mut self, debug_headers: bool) -> Self {
+        self.debug_headers = debug_headers;
+        self
+    }
+
+    /// Apply the cache duration and debug headers of a profile.
+    pub fn with_profile(self, settings: &crate::ProfileSettings) -> Self {
+        self.with_cache_duration(settings.cache_duration)
+            .with_debug_headers(settings.cache_debug_headers)
+    }
+
+    /// Get or render a response, using the server-side store if one is configured.
+    ///
+    /// Responses with a `no-store` directive bypass the store. The render may run in the
+    /// background, if the store
+    /// [revalidates](MemoryCacheStore::with_stale_while_revalidate) expired responses.
+    pub(crate) async fn get_or_render<F, Fut>(
+        &self,
+        key: StoreKey,
+        request_headers: &http::HeaderMap,
+        cache_control: &CacheControl,
+        directives: &CacheDirectives,
+        render: F,
+    ) -> Result<axum::response::Response, axum::response::Response>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = Result<axum::response::Response, axum::response::Response>>
+            + Send
+            + 'static,
+    {
+        match &self.store {
+            Some(store) if *cache_control != CacheControl::NoCache && !directives.no_store => {
+                let body_limits = self.body_limits.clone();
+                let route_max_body_size = directives.max_body_size;
+
+                store
+                    .get_or_render(
+                        key.to_string(),
+                        request_headers,
+                        directives.max_age,
+                        Box::new(move |headers: &http::HeaderMap| {
+                            body_limits.get(route_max_body_size, headers)
+                        }),
+                        Box::new(move || Box::pin(render())),
+                    )
+                    .await
+            }
+            _ => render().await,
+        }
+    }
 }
 
 impl<R: Route> Cache<R> {
+    /// Get the key of the stored response to a request, if it can be stored.
+    pub(crate) fn get_store_key(
+        &self,
+        route: &R,
+        htmx: &crate::htmx::Request,
+        parts: &http::request::Parts,
+    ) -> Option<StoreKey> {
+        if route.method() != http::Method::GET {
+            return None;
+        }
+
+        match &self.key {
+            Some(key) => key.cache_key(route, htmx, parts).map(StoreKey::custom),
+            None => Some(StoreKey::new(route_url(route), htmx).with_credentials(&parts.headers)),
+        }
+    }
+
     /// Get the cache control for a request.
     pub fn get_cache_control(
         &self,
@@ -56,7 +441,6 @@ impl<R: Route> Cache<R> {
         htmx: &crate::htmx::Request,
         parts: &http::request::Parts,
     ) -> CacheControl {
-        // TODO: Allow customization of the cache key based on the request parameters.
         let _ = route;
         let _ = htmx;
 
@@ -75,43 +459,43 @@ impl<R: Route> Cache<R> {
             }
         }
 
-        let if_none_match = parts
-            .headers
-            .get_all(http::header::IF_NONE_MATCH)
-            .into_iter()
-            .flat_map(|value| match value.to_str() {
-                Ok(value) => value
-                    .split(',')
-                    .map(|s| s.trim().to_string())
-                    .collect::<Vec<_>>(),
-                Err(err) => {
-                    warn!("Failed to parse If-None-Match header: {err}");
-
-                    Vec::new()
-                }
-            })
-            .collect();
-
-        CacheControl::IfNoneMatch(if_none_match)
+        CacheControl::IfNoneMatch(if_none_match(&parts.headers))
     }
 
     /// Decorates an HTTP response with caching headers, respecting the provided cache control
-    /// directive.
+    /// directive of the request and caching directives of the route.
     ///
-    /// If the provided response already contains an ETag, it will be used directly. Otherwise, one
-    /// will be computed from the response body, effectively disabling any streaming. If the
-    /// response body is a stream that fails during reading, the original response will be lost and
-    /// replaced with an error response.
+    /// If the directives enable ETags and the provided response already contains an ETag, it will
+    /// be used directly, without buffering the response body, whatever its size. Otherwise, one
+    /// will be computed from the response body, effectively disabling any streaming. If the response body is a stream that fails during reading, the
+    /// original response will be lost and replaced with an error response.
     ///
     /// If the provided response already contains a `Cache-Control` header, it will be left
     /// untouched.
     pub async fn check_cache_control(
         &self,
         cache_control: CacheControl,
+        directives: &CacheDirectives,
         mut response: axum::response::Response,
     ) -> Result<axum::response::Response, axum::response::Response> {
+        let cache_part = response.extensions().get::<CachePart>().copied();
+        let sets_cookies = response.headers().contains_key(http::header::SET_COOKIE);
+
+        let max_body_size = self
+            .body_limits
+            .get(directives.max_body_size, response.headers());
+        let has_etag = response.headers().contains_key(http::header::ETAG);
+        let bufferable = has_etag
+            || max_body_size.is_some_and(|max_body_size| {
+                response.body().size_hint().lower() <= max_body_size as u64
+            });
+
+        if max_body_size.is_some() && !bufferable {
+            debug!("Response body is too large to compute its ETag, streaming it");
+        }
+
         let mut response = match cache_control {
-            CacheControl::IfNoneMatch(if_none_match) => {
+            CacheControl::IfNoneMatch(if_none_match) if directives.etag && bufferable => {
                 // If the response already has an ETag, we can use it directly.
                 let etag = match response.headers().get(http::header::ETAG) {
                     Some(etag) => etag
@@ -124,7 +508,7 @@ impl<R: Route> Cache<R> {
                         .to_string(),
                     None => {
                         let (parts, body) = response.into_parts();
-                        let body = axum::body::to_bytes(body, self.max_body_size)
+                        let body = axum::body::to_bytes(body, max_body_size.unwrap_or_default())
                             .await
                             .map_err(|err| {
                                 error!("Failed to read response body: {err}");
@@ -145,7 +529,7 @@ impl<R: Route> Cache<R> {
                     }
                 };
 
-                if if_none_match.contains(&etag.to_string()) {
+                if etag_matches(&if_none_match, &etag) {
                     let mut response = axum::response::Response::default();
                     *response.status_mut() = http::StatusCode::NOT_MODIFIED;
 
@@ -154,23 +538,155 @@ impl<R: Route> Cache<R> {
                     response.with_etag(&etag)?
                 }
             }
-            CacheControl::NoCache => response,
+            _ => response,
         };
 
-        // TODO: Implement real options here.
-
         if response
             .headers()
             .get(http::header::CACHE_CONTROL)
             .is_none()
         {
-            response = response.with_caching(self.cache_duration);
+            response = match (cache_part, self.shell_cache_duration) {
+                (Some(CachePart::Shell), Some(shell_cache_duration)) if !sets_cookies => {
+                    response.with_public_caching(shell_cache_duration)
+                }
+                (Some(CachePart::Shell), Some(_)) => {
+                    warn!("Page shell sets cookies, caching it privately");
+
+                    response.with_caching(directives.max_age)
+                }
+                _ => response.with_cache_directives(directives),
+            };
+        }
+
+        if let Some(key) = &self.key {
+            for name in key.vary() {
+                response = with_vary(response, &name);
+            }
         }
 
         Ok(response)
     }
 }
 
+/// Get the URL identifying a route in the store keys.
+///
+/// Routes scoped to a host are prefixed with their host pattern, so that they don't share the keys
+/// of routes with the same path on other hosts.
+pub(crate) fn route_url(route: &impl Route) -> String {
+    match route.host() {
+        Some(host) => format!("//{host}{route}"),
+        None => route.to_string(),
+    }
+}
+
+/// Add a header to the `Vary` header of a response, unless it is already listed.
+fn with_vary(
+    mut response: axum::response::Response,
+    name: &http::HeaderName,
+) -> axum::response::Response {
+    let vary = response
+        .headers()
+        .get_all(http::header::VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .collect::<Vec<_>>();
+
+    if vary
+        .iter()
+        .any(|value| *value == "*" || value.eq_ignore_ascii_case(name.as_str()))
+    {
+        return response;
+    }
+
+    let vary = vary
+        .into_iter()
+        .chain([name.as_str()])
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if let Ok(vary) = http::HeaderValue::from_str(&vary) {
+        response.headers_mut().insert(http::header::VARY, vary);
+    }
+
+    response
+}
+
+/// Get the ETags of the `If-None-Match` headers of a request.
+pub(crate) fn if_none_match(headers: &http::HeaderMap) -> BTreeSet<String> {
+    headers
+        .get_all(http::header::IF_NONE_MATCH)
+        .into_iter()
+        .flat_map(|value| match value.to_str() {
+            Ok(value) => value
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .collect::<Vec<_>>(),
+            Err(err) => {
+                warn!("Failed to parse If-None-Match header: {err}");
+
+                Vec::new()
+            }
+        })
+        .collect()
+}
+
+/// Check whether an ETag matches the ETags of an `If-None-Match` header.
+///
+/// ETags are compared weakly, as `If-None-Match` requires: weak and strong ETags with the same
+/// opaque tag match.
+pub(crate) fn etag_matches(if_none_match: &BTreeSet<String>, etag: &str) -> bool {
+    let opaque_tag = |etag: &str| etag.trim_start_matches("W/").to_owned();
+    let etag = opaque_tag(etag);
+
+    if_none_match
+        .iter()
+        .any(|candidate| candidate == "*" || opaque_tag(candidate) == etag)
+}
+
+/// A response whose ETag is known without reading its body.
+///
+/// Responses rendered from a versioned model can use its version or last update time as a weak
+/// validator: the ETag of the response is then supplied by [`CacheableResponse::weak_etag`]
+/// instead of being computed from its body, which is never buffered and can be streamed,
+/// whatever its size.
+///
+/// ```rust,ignore
+/// impl CacheableResponse for ArticlePage {
+///     fn weak_etag(&self) -> Option<String> {
+///         Some(format!("article-{}-{}", self.article.id, self.article.version))
+///     }
+/// }
+///
+/// // In `Controller::handle_request`:
+/// ArticlePage { article }.into_cacheable_response()
+/// ```
+pub trait CacheableResponse: IntoResponse + Sized {
+    /// Get the weak validator of the response, like the version of the model it renders.
+    ///
+    /// The value is the opaque tag of the ETag, without the `W/` prefix and the quotes. Returning
+    /// `None` lets the [`Cache`] compute a strong ETag from the body of the response.
+    fn weak_etag(&self) -> Option<String>;
+
+    /// Convert into a response, with the weak ETag of the response.
+    ///
+    /// The weak validator must only contain characters allowed in an ETag or an error will be
+    /// returned.
+    #[expect(clippy::result_large_err)]
+    fn into_cacheable_response(self) -> Result<axum::response::Response, axum::response::Response> {
+        let weak_etag = self.weak_etag();
+        let response = self.into_response();
+
+        match weak_etag {
+            Some(weak_etag) => response.with_weak_etag(&weak_etag),
+            None => Ok(response),
+        }
+    }
+}
+
 /// An opaque cache key.
 ///
 /// You should never need to instantiate this type directly nor should you store it across
@@ -197,12 +713,50 @@ pub trait CachingResponseExt {
     /// The response will be cached for the provided duration, using the `private` directive.
     fn with_caching(self, duration: std::time::Duration) -> axum::response::Response;
 
+    /// Decorate the response with a public cache control directive.
+    ///
+    /// The response will be cached for the provided duration by clients and shared caches alike,
+    /// so it must not contain any visitor-specific content.
+    fn with_public_caching(self, duration: std::time::Duration) -> axum::response::Response;
+
+    /// Decorate the response with the cache control directives of a [`CacheDirectives`],
+    /// including its `stale-while-revalidate` and `stale-if-error` directives.
+    ///
+    /// Responses with stale directives don't get the `must-revalidate` directive, which would
+    /// forbid serving them stale.
+    fn with_cache_directives(self, directives: &CacheDirectives) -> axum::response::Response;
+
+    /// Mark the response as the shell or a dynamic fragment of a page, for shell caching.
+    ///
+    /// See [`Cache::with_shell_caching`].
+    fn with_cache_part(self, cache_part: CachePart) -> axum::response::Response;
+
+    /// Tag the response, so that it can be removed from a [`CacheStore`] with
+    /// [`CacheStore::invalidate_tag`].
+    ///
+    /// Tags usually name the data the response was rendered from, like `food` for a list of foods
+    /// and `food:3` for a single food. Tags add up with the tags already set on the response.
+    fn with_cache_tags<I>(self, tags: I) -> axum::response::Response
+    where
+        I: IntoIterator,
+        I::Item: Into<String>;
+
     /// Add an ETag to the response.
     ///
     /// The etag value must be convertible to a valid HTTP header value or an error will be
     /// returned.
     #[expect(clippy::result_large_err)]
     fn with_etag(self, etag: &str) -> Result<axum::response::Response, axum::response::Response>;
+
+    /// Add a weak ETag to the response, from its opaque tag.
+    ///
+    /// The opaque tag must only contain characters allowed in an ETag, which excludes double
+    /// quotes, or an error will be returned.
+    #[expect(clippy::result_large_err)]
+    fn with_weak_etag(
+        self,
+        opaque_tag: &str,
+    ) -> Result<axum::response::Response, axum::response::Response>;
 }
 
 impl CachingResponseExt for axum::response::Response {
@@ -215,20 +769,63 @@ impl CachingResponseExt for axum::response::Response {
         self
     }
 
-    fn with_caching(mut self, duration: std::time::Duration) -> axum::response::Response {
-        let cache_control = http::header::HeaderValue::from_str(&format!(
-            "private, max-age={}, must-revalidate",
-            duration.as_secs()
-        ))
-        .expect("Failed to parse Cache-Control header");
+    fn with_caching(self, duration: std::time::Duration) -> axum::response::Response {
+        self.with_cache_directives(&CacheDirectives::private(duration))
+    }
 
-        self.headers_mut()
-            .insert(http::header::CACHE_CONTROL, cache_control);
-        self.headers_mut().insert(
-            http::header::VARY,
-            http::header::HeaderValue::from_static("Hx-Request, Hx-Target"),
-        );
+    fn with_public_caching(self, duration: std::time::Duration) -> axum::response::Response {
+        self.with_cache_directives(&CacheDirectives::public(duration))
+    }
+
+    fn with_cache_directives(mut self, directives: &CacheDirectives) -> axum::response::Response {
+        if directives.no_store {
+            self.headers_mut().insert(
+                http::header::CACHE_CONTROL,
+                http::header::HeaderValue::from_static("no-store"),
+            );
+
+            return self;
+        }
+
+        let scope = if directives.public {
+            "public"
+        } else {
+            "private"
+        };
+        let mut cache_control = format!("{scope}, max-age={}", directives.max_age.as_secs());
+
+        if let Some(stale_while_revalidate) = directives.stale_while_revalidate {
+            cache_control += &format!(
+                ", stale-while-revalidate={}",
+                stale_while_revalidate.as_secs()
+            );
+        }
 
+        if let Some(stale_if_error) = directives.stale_if_error {
+            cache_control += &format!(", stale-if-error={}", stale_if_error.as_secs());
+        }
+
+        if directives.stale_while_revalidate.is_none() && directives.stale_if_error.is_none() {
+            cache_control += ", must-revalidate";
+        }
+
+        with_cache_control(self, &cache_control)
+    }
+
+    fn with_cache_part(mut self, cache_part: CachePart) -> axum::response::Response {
+        self.extensions_mut().insert(cache_part);
+        self
+    }
+
+    fn with_cache_tags<I>(mut self, tags: I) -> axum::response::Response
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.extensions_mut()
+            .get_or_insert_default::<CacheTags>()
+            .0
+            .extend(tags.into_iter().map(Into::into));
         self
     }
 
@@ -247,4 +844,437 @@ impl CachingResponseExt for axum::response::Response {
 
         Ok(self)
     }
+
+    fn with_weak_etag(
+        self,
+        opaque_tag: &str,
+    ) -> Result<axum::response::Response, axum::response::Response> {
+        if opaque_tag.contains('"') {
+            error!("Invalid weak ETag `{opaque_tag}`: it must not contain double quotes");
+
+            return Err(http::StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+
+        self.with_etag(&format!("W/\"{opaque_tag}\""))
+    }
+}
+
+/// Decorate a response with a cache control header.
+fn with_cache_control(
+    mut response: axum::response::Response,
+    cache_control: &str,
+) -> axum::response::Response {
+    let cache_control = http::header::HeaderValue::from_str(cache_control)
+        .expect("Failed to parse Cache-Control header");
+
+    response
+        .headers_mut()
+        .insert(http::header::CACHE_CONTROL, cache_control);
+    response.headers_mut().insert(
+        http::header::VARY,
+        http::header::HeaderValue::from_static("Hx-Request, Hx-Target"),
+    );
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum AppRoute {
+        Home,
+        Cart,
+        Feed,
+    }
+
+    impl std::fmt::Display for AppRoute {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "/{self:?}")
+        }
+    }
+
+    impl std::str::FromStr for AppRoute {
+        type Err = crate::ParseError;
+
+        fn from_str(_s: &str) -> Result<Self, Self::Err> {
+            Ok(Self::Home)
+        }
+    }
+
+    impl Route for AppRoute {
+        fn method(&self) -> http::Method {
+            http::Method::GET
+        }
+    }
+
+    fn cache() -> Cache<AppRoute> {
+        Cache::default().with_policy(|route, htmx| match route {
+            AppRoute::Home if matches!(htmx, crate::htmx::Request::Htmx { .. }) => {
+                CacheDirectives::private(Duration::from_secs(10))
+            }
+            AppRoute::Home => CacheDirectives::public(Duration::from_secs(300)),
+            AppRoute::Cart => CacheDirectives::no_store(),
+            AppRoute::Feed => CacheDirectives::private(Duration::from_secs(5)).with_etag(false),
+        })
+    }
+
+    /// Check the cache control of a response to a route, returning the `Cache-Control` and
+    /// `ETag` headers.
+    async fn check(route: AppRoute, htmx: crate::htmx::Request) -> (String, Option<String>) {
+        let cache = cache();
+        let directives = cache.get_cache_directives(&route, &htmx);
+        let response = cache
+            .check_cache_control(
+                CacheControl::IfNoneMatch(BTreeSet::new()),
+                &directives,
+                "Hello".into_response(),
+            )
+            .await
+            .unwrap();
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .map(|value: &http::HeaderValue| value.to_str().unwrap().to_owned())
+        };
+
+        (
+            header(http::header::CACHE_CONTROL).unwrap(),
+            header(http::header::ETAG),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_policy() {
+        let htmx = crate::htmx::Request::Htmx {
+            boosted: false,
+            current_url: String::new(),
+            history_restore_request: false,
+            prompt: String::new(),
+            target: None,
+            trigger_name: None,
+            trigger: None,
+        };
+
+        let (cache_control, etag) = check(AppRoute::Home, crate::htmx::Request::Classic).await;
+        assert_eq!(cache_control, "public, max-age=300, must-revalidate");
+        assert!(etag.is_some());
+
+        let (cache_control, _) = check(AppRoute::Home, htmx).await;
+        assert_eq!(cache_control, "private, max-age=10, must-revalidate");
+
+        let (cache_control, etag) = check(AppRoute::Cart, crate::htmx::Request::Classic).await;
+        assert_eq!(cache_control, "no-store");
+        assert_eq!(etag, None);
+
+        let (cache_control, etag) = check(AppRoute::Feed, crate::htmx::Request::Classic).await;
+        assert_eq!(cache_control, "private, max-age=5, must-revalidate");
+        assert_eq!(etag, None);
+    }
+
+    #[test]
+    fn test_default_policy() {
+        let cache = Cache::<AppRoute>::default().with_cache_duration(Duration::from_secs(30));
+
+        assert_eq!(
+            cache.get_cache_directives(&AppRoute::Cart, &crate::htmx::Request::Classic),
+            CacheDirectives::private(Duration::from_secs(30))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cache_key() {
+        let cache = Cache::<AppRoute>::default()
+            .with_cache_key(RequestCacheKey::default().with_cookie("theme"));
+        let parts = http::Request::builder()
+            .header(http::header::COOKIE, "theme=dark")
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0;
+
+        assert_eq!(
+            cache
+                .get_store_key(&AppRoute::Home, &crate::htmx::Request::Classic, &parts)
+                .unwrap()
+                .to_string(),
+            "GET /Home [cookie theme: dark]"
+        );
+
+        let response = cache
+            .check_cache_control(
+                CacheControl::IfNoneMatch(BTreeSet::new()),
+                &CacheDirectives::private(Duration::from_secs(10)),
+                "Hello".into_response(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.headers()[http::header::VARY],
+            "Hx-Request, Hx-Target, cookie"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_store_credentials() {
+        let cache = Cache::<AppRoute>::default().with_store(MemoryCacheStore::default());
+        let parts = |cookie: &'static str| {
+            http::Request::builder()
+                .header(http::header::COOKIE, cookie)
+                .body(())
+                .unwrap()
+                .into_parts()
+                .0
+        };
+
+        let mut bodies = Vec::new();
+
+        for cookie in ["session=alice", "session=bob", "session=alice"] {
+            let parts = parts(cookie);
+            let key = cache
+                .get_store_key(&AppRoute::Home, &crate::htmx::Request::Classic, &parts)
+                .unwrap();
+            let response = cache
+                .get_or_render(
+                    key,
+                    &parts.headers,
+                    &CacheControl::IfNoneMatch(BTreeSet::new()),
+                    &CacheDirectives::private(Duration::from_secs(60)),
+                    move || async move { Ok(format!("Hello {cookie}").into_response()) },
+                )
+                .await
+                .unwrap();
+
+            bodies.push(
+                axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        // Each user gets its own response, and is served it again from the store.
+        assert_eq!(
+            bodies,
+            [
+                "Hello session=alice",
+                "Hello session=bob",
+                "Hello session=alice"
+            ]
+        );
+        assert_eq!(cache.store.as_ref().unwrap().metrics().hits, 1);
+
+        // The credentials don't leak through the keys.
+        let key = cache
+            .get_store_key(
+                &AppRoute::Home,
+                &crate::htmx::Request::Classic,
+                &parts("session=alice"),
+            )
+            .unwrap()
+            .to_string();
+        assert!(key.starts_with("GET /Home [credentials: "), "{key}");
+        assert!(!key.contains("alice"), "{key}");
+    }
+
+    /// A store that never stores responses, recording the keys it is asked for.
+    #[derive(Default)]
+    struct RecordingStore(std::sync::Mutex<Vec<String>>);
+
+    impl CacheStore for RecordingStore {
+        fn get_or_render<'a>(
+            &'a self,
+            key: String,
+            _request_headers: &'a http::HeaderMap,
+            _ttl: Duration,
+            _max_body_size: CacheBodyLimit,
+            render: CacheRender,
+        ) -> std::pin::Pin<
+            Box<
+                dyn std::future::Future<
+                        Output = Result<axum::response::Response, axum::response::Response>,
+                    > + Send
+                    + 'a,
+            >,
+        > {
+            self.0.lock().unwrap().push(key);
+
+            render()
+        }
+
+        fn metrics(&self) -> CacheStoreMetrics {
+            CacheStoreMetrics::default()
+        }
+
+        fn entries(&self) -> Vec<CacheStoreEntry> {
+            Vec::new()
+        }
+
+        fn purge(&self, _key: &str) -> bool {
+            false
+        }
+
+        fn purge_all(&self) -> usize {
+            0
+        }
+
+        fn invalidate_tag(&self, _tag: &str) -> usize {
+            0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_store() {
+        let store = Arc::new(RecordingStore::default());
+        let cache = Cache::<AppRoute>::default().with_store(store.clone() as Arc<dyn CacheStore>);
+        let parts = http::Request::builder().body(()).unwrap().into_parts().0;
+        let key = cache
+            .get_store_key(&AppRoute::Home, &crate::htmx::Request::Classic, &parts)
+            .unwrap();
+
+        let response = cache
+            .get_or_render(
+                key,
+                &parts.headers,
+                &CacheControl::IfNoneMatch(BTreeSet::new()),
+                &CacheDirectives::private(Duration::from_secs(60)),
+                || async { Ok("Hello".into_response()) },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap(),
+            "Hello"
+        );
+        assert_eq!(*store.0.lock().unwrap(), ["GET /Home"]);
+    }
+
+    #[tokio::test]
+    async fn test_max_body_size() {
+        let cache = Cache::<AppRoute>::default()
+            .with_max_body_size(8)
+            .with_content_type_max_body_size("text/csv", 4)
+            .with_streaming_content_type("application/*");
+        let directives = CacheDirectives::private(Duration::from_secs(10));
+
+        let etag = async |directives: &CacheDirectives, content_type: &str, body: &'static str| {
+            let response = ([(http::header::CONTENT_TYPE, content_type)], body).into_response();
+
+            cache
+                .check_cache_control(
+                    CacheControl::IfNoneMatch(BTreeSet::new()),
+                    directives,
+                    response,
+                )
+                .await
+                .unwrap()
+                .headers()
+                .contains_key(http::header::ETAG)
+        };
+
+        assert!(etag(&directives, "text/html", "Hello").await);
+        assert!(!etag(&directives, "text/html", "Hello, world!").await);
+        assert!(!etag(&directives, "text/csv; charset=utf-8", "a,b,c").await);
+        assert!(!etag(&directives, "application/zip", "PK").await);
+        assert!(!etag(&directives, "text/event-stream", "data:").await);
+
+        let directives = directives.with_max_body_size(64);
+
+        assert!(etag(&directives, "text/html", "Hello, world!").await);
+        assert!(etag(&directives, "text/csv", "a,b,c").await);
+        assert!(!etag(&directives, "application/json", "{}").await);
+    }
+
+    struct Article {
+        version: u32,
+    }
+
+    impl IntoResponse for Article {
+        fn into_response(self) -> axum::response::Response {
+            (
+                [(http::header::CONTENT_TYPE, "text/event-stream")],
+                "data: a very long article\n\n",
+            )
+                .into_response()
+        }
+    }
+
+    impl CacheableResponse for Article {
+        fn weak_etag(&self) -> Option<String> {
+            Some(format!("article-{}", self.version))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_weak_etag() {
+        let cache = Cache::<AppRoute>::default().with_max_body_size(8);
+        let directives = CacheDirectives::private(Duration::from_secs(10));
+        let check = async |if_none_match: &[&str]| {
+            let response = Article { version: 3 }.into_cacheable_response().unwrap();
+            let response = cache
+                .check_cache_control(
+                    CacheControl::IfNoneMatch(
+                        if_none_match.iter().map(ToString::to_string).collect(),
+                    ),
+                    &directives,
+                    response,
+                )
+                .await
+                .unwrap();
+
+            (
+                response.status().as_u16(),
+                response
+                    .headers()
+                    .get(http::header::ETAG)
+                    .map(|etag| etag.to_str().unwrap().to_owned()),
+            )
+        };
+
+        // Supplied ETags are used whatever the size and the content type of the body.
+        assert_eq!(check(&[]).await, (200, Some(r#"W/"article-3""#.to_owned())));
+        assert_eq!(check(&[r#"W/"article-3""#]).await.0, 304);
+        assert_eq!(check(&[r#""article-3""#]).await.0, 304);
+        assert_eq!(check(&["*"]).await.0, 304);
+        assert_eq!(check(&[r#"W/"article-2""#]).await.0, 200);
+
+        assert!(
+            "Hello"
+                .into_response()
+                .with_weak_etag(r#"article"3"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cache_directives_header() {
+        let cache_control = |directives: CacheDirectives| {
+            "Hello"
+                .into_response()
+                .with_cache_directives(&directives)
+                .headers()[http::header::CACHE_CONTROL]
+                .to_str()
+                .unwrap()
+                .to_owned()
+        };
+
+        assert_eq!(
+            cache_control(CacheDirectives::public(Duration::from_secs(60))),
+            "public, max-age=60, must-revalidate"
+        );
+        assert_eq!(
+            cache_control(
+                CacheDirectives::private(Duration::from_secs(60))
+                    .with_stale_while_revalidate(Duration::from_secs(30))
+                    .with_stale_if_error(Duration::from_secs(600))
+            ),
+            "private, max-age=60, stale-while-revalidate=30, stale-if-error=600"
+        );
+        assert_eq!(cache_control(CacheDirectives::no_store()), "no-store");
+    }
 }
@@ -0,0 +1,190 @@
+//! Shell caching.
+//!
+//! In shell caching mode, pages are split into a shell and dynamic fragments:
+//!
+//! - The shell holds the layout of the page and the content that is the same for all visitors.
+//!   It is cached publicly, so that it can be served by shared caches and CDNs.
+//! - The dynamic fragments hold the content that depends on the visitor. They are left out of the
+//!   shell, which instead renders [`DynamicFragment`] placeholders that HTMX replaces as soon as
+//!   the page is loaded, from routes whose responses are cached privately.
+
+use std::fmt::Display;
+
+use crate::{
+    Route,
+    htmx::{HtmlId, Identity},
+};
+
+/// The part of a page held by a response, in shell caching mode.
+///
+/// Responses are marked with [`CachingResponseExt::with_cache_part`](super::CachingResponseExt),
+/// and the marker is only taken into account by caches with
+/// [`Cache::with_shell_caching`](super::Cache::with_shell_caching).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CachePart {
+    /// The response is the shell of a page, which is the same for all visitors.
+    ///
+    /// Shells are cached with a `public` cache control directive, unless they set cookies.
+    Shell,
+
+    /// The response is a dynamic fragment, which depends on the visitor.
+    ///
+    /// Dynamic fragments are cached with a `private` cache control directive, and are never kept
+    /// in a [`CacheStore`](super::CacheStore).
+    Dynamic,
+}
+
+/// A placeholder for a dynamic fragment in a page shell.
+///
+/// The placeholder renders as an element that HTMX replaces with the response of the fragment
+/// route as soon as it is loaded.
+///
+/// # Example
+///
+/// ```ignore
+/// let cart = DynamicFragment::new(HtmlId::from_static("cart")?, &AppRoute::Cart)
+///     .with_placeholder("Loading your cart...");
+///
+/// // Renders `<div id="cart" hx-get="/cart" hx-trigger="load" hx-swap="outerHTML">Loading your cart...</div>`
+/// ```
+#[derive(Clone)]
+pub struct DynamicFragment {
+    id: HtmlId,
+    route_attribute: String,
+    placeholder: String,
+}
+
+impl DynamicFragment {
+    /// Create a placeholder for the dynamic fragment rendered by the specified route.
+    ///
+    /// The route should respond with the fragment, which replaces the whole placeholder.
+    pub fn new(id: HtmlId, route: &impl Route) -> Self {
+        Self {
+            id,
+            route_attribute: route.as_htmx_attribute(),
+            placeholder: String::new(),
+        }
+    }
+
+    /// Set the content displayed until the fragment is loaded.
+    ///
+    /// The content is rendered as is, without any escaping.
+    pub fn with_placeholder(mut self, placeholder: impl Display) -> Self {
+        self.placeholder = placeholder.to_string();
+        self
+    }
+}
+
+impl Identity for DynamicFragment {
+    fn id(&self) -> HtmlId {
+        self.id.clone()
+    }
+}
+
+impl Display for DynamicFragment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            r#"<div {} {} hx-trigger="load" hx-swap="outerHTML">{}</div>"#,
+            self.id_attribute(),
+            self.route_attribute,
+            self.placeholder
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::response::IntoResponse;
+
+    use super::*;
+    use crate::{Cache, CacheControl, CachingResponseExt};
+
+    #[derive(Debug)]
+    struct CartRoute;
+
+    impl Display for CartRoute {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("/cart")
+        }
+    }
+
+    impl std::str::FromStr for CartRoute {
+        type Err = crate::ParseError;
+
+        fn from_str(_s: &str) -> Result<Self, Self::Err> {
+            Ok(Self)
+        }
+    }
+
+    impl Route for CartRoute {
+        fn method(&self) -> http::Method {
+            http::Method::GET
+        }
+    }
+
+    #[test]
+    fn test_dynamic_fragment() {
+        let fragment = DynamicFragment::new(HtmlId::from_static("cart").unwrap(), &CartRoute)
+            .with_placeholder("Loading...");
+
+        assert_eq!(
+            fragment.to_string(),
+            r#"<div id="cart" hx-get="/cart" hx-trigger="load" hx-swap="outerHTML">Loading...</div>"#
+        );
+    }
+
+    async fn cache_control(
+        cache: &Cache<CartRoute>,
+        response: axum::response::Response,
+    ) -> Option<http::HeaderValue> {
+        let directives = cache.get_cache_directives(&CartRoute, &crate::htmx::Request::Classic);
+
+        cache
+            .check_cache_control(CacheControl::NoCache, &directives, response)
+            .await
+            .unwrap()
+            .headers()
+            .get(http::header::CACHE_CONTROL)
+            .cloned()
+    }
+
+    #[tokio::test]
+    async fn test_shell_caching() {
+        let cache = Cache::default()
+            .with_cache_duration(Duration::from_secs(10))
+            .with_shell_caching(Duration::from_secs(3600));
+        let shell = || "shell".into_response().with_cache_part(CachePart::Shell);
+
+        assert_eq!(
+            cache_control(&cache, shell()).await.unwrap(),
+            "public, max-age=3600, must-revalidate"
+        );
+        assert_eq!(
+            cache_control(
+                &cache,
+                "fragment"
+                    .into_response()
+                    .with_cache_part(CachePart::Dynamic)
+            )
+            .await
+            .unwrap(),
+            "private, max-age=10, must-revalidate"
+        );
+        assert_eq!(
+            cache_control(
+                &cache,
+                ([(http::header::SET_COOKIE, "session=1")], shell()).into_response()
+            )
+            .await
+            .unwrap(),
+            "private, max-age=10, must-revalidate"
+        );
+        assert_eq!(
+            cache_control(&Cache::default(), shell()).await.unwrap(),
+            "private, max-age=60, must-revalidate"
+        );
+    }
+}
@@ -0,0 +1,1847 @@
+//! Server-side response storage.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::body::HttpBody;
+use axum::response::IntoResponse;
+use tracing::{debug, error, warn};
+
+/// The default time to wait for a concurrent render before rendering anyway.
+const DEFAULT_SINGLE_FLIGHT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The `Warning` header value of stale responses served in place of failed renders.
+const STALE_WARNING: &str = "111 - \"Revalidation Failed\"";
+
+/// A render of a response, run by a [`CacheStore`] when the response must be rendered.
+pub type CacheRender = Box<
+    dyn FnOnce() -> Pin<
+            Box<
+                dyn Future<Output = Result<axum::response::Response, axum::response::Response>>
+                    + Send,
+            >,
+        > + Send,
+>;
+
+/// A function deciding the maximum body size of a rendered response from its headers, or `None`
+/// if it must not be stored.
+pub type CacheBodyLimit = Box<dyn Fn(&http::HeaderMap) -> Option<usize> + Send>;
+
+/// A store of rendered responses, shared by all requests.
+///
+/// The [`MemoryCacheStore`] keeps the responses in memory. Other stores can be plugged in
+/// [`Cache::with_store`](super::Cache::with_store) by implementing this trait.
+///
+/// Implementations should only store successful responses, and read the cache tags of the
+/// responses from their [`CacheTags`] extension, so that they can be
+/// [invalidated](CacheStore::invalidate_tag).
+pub trait CacheStore: Send + Sync + 'static {
+    /// Get the stored response with the specified key, or render and store it.
+    ///
+    /// Stored responses with a `Vary` header must only be served to requests with the same
+    /// values for the listed headers. Rendered responses are stored for `ttl`, if they are no
+    /// larger than `max_body_size`.
+    fn get_or_render<'a>(
+        &'a self,
+        key: String,
+        request_headers: &'a http::HeaderMap,
+        ttl: Duration,
+        max_body_size: CacheBodyLimit,
+        render: CacheRender,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<axum::response::Response, axum::response::Response>>
+                + Send
+                + 'a,
+        >,
+    >;
+
+    /// Get a snapshot of the metrics of the store.
+    fn metrics(&self) -> CacheStoreMetrics;
+
+    /// Describe the stored responses, ordered by key.
+    fn entries(&self) -> Vec<CacheStoreEntry>;
+
+    /// Remove the stored response with the specified key, as described by
+    /// [`CacheStore::entries`].
+    ///
+    /// Returns whether a response was removed.
+    fn purge(&self, key: &str) -> bool;
+
+    /// Remove all the stored responses.
+    ///
+    /// Returns the number of removed responses.
+    fn purge_all(&self) -> usize;
+
+    /// Remove all the stored responses with the specified cache tag.
+    ///
+    /// Returns the number of removed responses.
+    fn invalidate_tag(&self, tag: &str) -> usize;
+}
+
+impl<S: CacheStore + ?Sized> CacheStore for Arc<S> {
+    fn get_or_render<'a>(
+        &'a self,
+        key: String,
+        request_headers: &'a http::HeaderMap,
+        ttl: Duration,
+        max_body_size: CacheBodyLimit,
+        render: CacheRender,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<axum::response::Response, axum::response::Response>>
+                + Send
+                + 'a,
+        >,
+    > {
+        (**self).get_or_render(key, request_headers, ttl, max_body_size, render)
+    }
+
+    fn metrics(&self) -> CacheStoreMetrics {
+        (**self).metrics()
+    }
+
+    fn entries(&self) -> Vec<CacheStoreEntry> {
+        (**self).entries()
+    }
+
+    fn purge(&self, key: &str) -> bool {
+        (**self).purge(key)
+    }
+
+    fn purge_all(&self) -> usize {
+        (**self).purge_all()
+    }
+
+    fn invalidate_tag(&self, tag: &str) -> usize {
+        (**self).invalidate_tag(tag)
+    }
+}
+
+/// A [`CacheStore`] keeping the rendered responses in memory.
+///
+/// When a request misses the store, it becomes responsible for rendering the response and
+/// populating the store. Concurrent requests for the same response wait for that render to
+/// complete instead of rendering it again (single-flight rendering), which protects the
+/// application against cache stampedes.
+///
+/// If the render takes longer than the single-flight timeout, or does not produce a storable
+/// response, the waiting requests fall back to rendering the response themselves.
+///
+/// Only successful (`200 OK`) responses with a known, bounded body size are stored, and responses
+/// that set cookies, have a `no-store` cache control directive, vary on `*` or are marked as
+/// [`CachePart::Dynamic`](super::CachePart::Dynamic) are never stored.
+///
+/// Responses are stored by URL, and vary on the `HX-Request` and `HX-Target` headers and on the
+/// credentials of the request, its `Cookie` and `Authorization` headers, unless a
+/// [cache key](super::Cache::with_cache_key) is configured. Stored
+/// responses with a `Vary` header, like the `Vary: Accept` of
+/// [negotiated responses](crate::Representation), are only served to requests with the same
+/// values for the listed headers: other requests render the response again, and replace it.
+///
+/// Expired responses are removed when they are looked up, or by [`MemoryCacheStore::sweep`],
+/// which can be run periodically with [`MemoryCacheStore::spawn_sweeper`]. The store can also be
+/// capped in entries or in size, in which case the least recently used responses are evicted
+/// first.
+///
+/// Responses can be tagged with
+/// [`CachingResponseExt::with_cache_tags`](super::CachingResponseExt::with_cache_tags), like
+/// `["posts", "post:3"]`, so that all the responses depending on some data can be removed at once
+/// with [`CacheStore::invalidate_tag`] when it changes. Responses with the tag that are being
+/// rendered are not stored either, as they may have been rendered from the data that changed.
+///
+/// With [`MemoryCacheStore::with_stale_if_error`], expired responses are kept for a while, and
+/// served in place of failed renders, so that read-mostly pages remain available when their
+/// handler errors or times out.
+///
+/// With [`MemoryCacheStore::with_stale_while_revalidate`], expired responses are served right
+/// away while they are rendered again in the background, so that requests never wait for renders
+/// of responses that were stored recently.
+///
+/// Cloning a store is cheap, and the clones share the same responses.
+#[derive(Debug, Clone)]
+pub struct MemoryCacheStore {
+    single_flight_timeout: Duration,
+    max_entries: Option<usize>,
+    max_size: Option<usize>,
+    stale_if_error: Option<Duration>,
+    stale_while_revalidate: Option<Duration>,
+    render_timeout: Option<Duration>,
+    state: Arc<Mutex<State>>,
+}
+
+impl Default for MemoryCacheStore {
+    fn default() -> Self {
+        Self {
+            single_flight_timeout: DEFAULT_SINGLE_FLIGHT_TIMEOUT,
+            max_entries: None,
+            max_size: None,
+            stale_if_error: None,
+            stale_while_revalidate: None,
+            render_timeout: None,
+            state: Default::default(),
+        }
+    }
+}
+
+/// A snapshot of the metrics of a [`CacheStore`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStoreMetrics {
+    /// The number of stored responses.
+    pub entries: usize,
+
+    /// The approximate size of the stored responses, in bytes.
+    pub size: usize,
+
+    /// The number of requests served from the store.
+    pub hits: u64,
+
+    /// The number of requests that missed the store.
+    pub misses: u64,
+
+    /// The number of responses removed because they expired.
+    pub expirations: u64,
+
+    /// The number of responses evicted to respect the store caps.
+    pub evictions: u64,
+
+    /// The number of stale responses served in place of failed renders, or while they were
+    /// revalidated.
+    pub stale_served: u64,
+}
+
+/// The description of a response held in a [`CacheStore`], as returned by
+/// [`CacheStore::entries`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheStoreEntry {
+    /// The key of the response, like `GET /posts (htmx)`.
+    pub key: String,
+
+    /// The status of the response.
+    pub status: http::StatusCode,
+
+    /// The approximate size of the response, in bytes.
+    pub size: usize,
+
+    /// The remaining time to live of the response, which is zero if it expired.
+    pub expires_in: Duration,
+
+    /// The cache tags of the response, in lexicographic order.
+    pub tags: Vec<String>,
+}
+
+/// The key of a stored response.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct StoreKey(String);
+
+impl StoreKey {
+    /// Create the key of the response to a request.
+    ///
+    /// Responses vary on the `HX-Request` and `HX-Target` headers.
+    pub(crate) fn new(url: String, htmx: &crate::htmx::Request) -> Self {
+        Self(match htmx {
+            crate::htmx::Request::Classic => format!("GET {url}"),
+            crate::htmx::Request::Htmx { target: None, .. } => format!("GET {url} (htmx)"),
+            crate::htmx::Request::Htmx {
+                target: Some(target),
+                ..
+            } => format!(
+                "GET {url} (htmx, target: {})",
+                target.to_str().unwrap_or("<invalid>")
+            ),
+        })
+    }
+
+    /// Key the response by the credentials of the request, if any.
+    ///
+    /// Requests with different `Cookie` or `Authorization` headers get different keys, so that
+    /// responses built from them are never served to other users. The credentials are digested,
+    /// so that they don't leak through the keys.
+    pub(crate) fn with_credentials(self, headers: &http::HeaderMap) -> Self {
+        use sha2::Digest as _;
+
+        let mut digest = sha2::Sha256::new();
+        let mut has_credentials = false;
+
+        for name in [http::header::COOKIE, http::header::AUTHORIZATION] {
+            for value in headers.get_all(&name) {
+                digest.update(name.as_str());
+                digest.update(b":");
+                digest.update(value.as_bytes());
+                digest.update(b"\n");
+                has_credentials = true;
+            }
+        }
+
+        if !has_credentials {
+            return self;
+        }
+
+        Self(format!(
+            "{} [credentials: {}]",
+            self.0,
+            hex::encode(&digest.finalize()[..16])
+        ))
+    }
+
+    /// Create a key from its string form, like one computed by a [`CacheKey`](super::CacheKey).
+    pub(crate) fn custom(key: String) -> Self {
+        Self(key)
+    }
+}
+
+impl std::fmt::Display for StoreKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<StoreKey, StoredResponse>,
+    in_flight: HashMap<StoreKey, tokio::sync::watch::Receiver<()>>,
+
+    /// The keys of the entries, by last access tick.
+    lru: BTreeMap<u64, StoreKey>,
+    tick: u64,
+
+    /// The keys of the entries, by cache tag.
+    tags: HashMap<String, HashSet<StoreKey>>,
+
+    /// The tick of the last invalidation of each tag, while renders are in flight.
+    invalidations: HashMap<String, u64>,
+    metrics: CacheStoreMetrics,
+}
+
+impl State {
+    /// Get a fresh entry as a response, marking it as recently used.
+    ///
+    /// Expired entries are removed.
+    ///
+    /// Entries varying on request headers with other values are ignored.
+    fn get(
+        &mut self,
+        key: &StoreKey,
+        request_headers: &http::HeaderMap,
+    ) -> Option<axum::response::Response> {
+        let entry = self.entries.get_mut(key)?;
+        let now = Instant::now();
+
+        if entry.expires_at <= now {
+            if entry.kept_until() <= now {
+                self.remove(key);
+                self.metrics.expirations += 1;
+            }
+
+            return None;
+        }
+
+        if !entry.matches(request_headers) {
+            return None;
+        }
+
+        self.tick += 1;
+        self.lru.remove(&entry.tick);
+        self.lru.insert(self.tick, key.clone());
+        entry.tick = self.tick;
+
+        let mut response = entry.to_response();
+        response.extensions_mut().insert(CacheHit {
+            expires_at: entry.expires_at,
+        });
+
+        Some(response)
+    }
+
+    /// Get an expired entry that can still be served in place of a failed render, as a response.
+    fn get_stale(
+        &mut self,
+        key: &StoreKey,
+        request_headers: &http::HeaderMap,
+    ) -> Option<axum::response::Response> {
+        let now = Instant::now();
+        let entry = self.entries.get(key).filter(|entry| {
+            entry.expires_at <= now && now < entry.stale_until && entry.matches(request_headers)
+        })?;
+
+        let mut response = entry.to_response();
+        let headers = response.headers_mut();
+        headers.insert(
+            http::header::WARNING,
+            http::HeaderValue::from_static(STALE_WARNING),
+        );
+        headers.insert(
+            http::header::CACHE_CONTROL,
+            http::HeaderValue::from_static("no-cache"),
+        );
+        response.extensions_mut().insert(CacheStale);
+
+        self.metrics.stale_served += 1;
+
+        Some(response)
+    }
+
+    /// Get an expired entry that can still be served while it is revalidated, as a response.
+    fn get_revalidating(
+        &mut self,
+        key: &StoreKey,
+        request_headers: &http::HeaderMap,
+    ) -> Option<axum::response::Response> {
+        let now = Instant::now();
+        let entry = self.entries.get(key).filter(|entry| {
+            entry.expires_at <= now
+                && now < entry.revalidate_until
+                && entry.matches(request_headers)
+        })?;
+
+        let mut response = entry.to_response();
+        response.headers_mut().insert(
+            http::header::CACHE_CONTROL,
+            http::HeaderValue::from_static("no-cache"),
+        );
+        response.extensions_mut().insert(CacheRevalidating);
+
+        self.metrics.stale_served += 1;
+
+        Some(response)
+    }
+
+    fn insert(&mut self, key: StoreKey, mut entry: StoredResponse) {
+        self.remove(&key);
+
+        self.tick += 1;
+        entry.tick = self.tick;
+        self.metrics.entries += 1;
+        self.metrics.size += entry.size;
+        self.lru.insert(self.tick, key.clone());
+
+        for tag in &entry.tags {
+            self.tags
+                .entry(tag.clone())
+                .or_default()
+                .insert(key.clone());
+        }
+
+        self.entries.insert(key, entry);
+    }
+
+    fn remove(&mut self, key: &StoreKey) -> Option<StoredResponse> {
+        let entry = self.entries.remove(key)?;
+
+        self.lru.remove(&entry.tick);
+        self.metrics.entries -= 1;
+        self.metrics.size -= entry.size;
+
+        for tag in &entry.tags {
+            if let Some(keys) = self.tags.get_mut(tag) {
+                keys.remove(key);
+
+                if keys.is_empty() {
+                    self.tags.remove(tag);
+                }
+            }
+        }
+
+        Some(entry)
+    }
+
+    /// Remove all the entries with the specified tag, returning their count.
+    ///
+    /// The invalidation is recorded, so that renders in flight don't store stale responses.
+    fn invalidate_tag(&mut self, tag: &str) -> usize {
+        let keys = self.tags.remove(tag).unwrap_or_default();
+
+        for key in &keys {
+            self.remove(key);
+        }
+
+        if !self.in_flight.is_empty() {
+            self.tick += 1;
+            self.invalidations.insert(tag.to_owned(), self.tick);
+        }
+
+        keys.len()
+    }
+
+    /// Check whether any of the tags was invalidated after the specified tick.
+    fn invalidated_since(&self, tags: &BTreeSet<String>, tick: u64) -> bool {
+        tags.iter().any(|tag| {
+            self.invalidations
+                .get(tag)
+                .is_some_and(|invalidated_at| *invalidated_at > tick)
+        })
+    }
+
+    /// Remove all the expired entries that can't be served stale anymore, returning their count.
+    fn sweep(&mut self) -> usize {
+        let now = Instant::now();
+        let expired: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.kept_until() <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in &expired {
+            self.remove(key);
+        }
+
+        self.metrics.expirations += expired.len() as u64;
+
+        expired.len()
+    }
+
+    /// Evict the least recently used entries until the caps are respected.
+    fn evict(&mut self, max_entries: Option<usize>, max_size: Option<usize>) {
+        while max_entries.is_some_and(|max| self.metrics.entries > max)
+            || max_size.is_some_and(|max| self.metrics.size > max)
+        {
+            let Some((_, key)) = self.lru.pop_first() else {
+                break;
+            };
+
+            if self.remove(&key).is_some() {
+                self.metrics.evictions += 1;
+
+                debug!("Evicted `{key}` from the cache store");
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct StoredResponse {
+    status: http::StatusCode,
+    headers: http::HeaderMap,
+    body: axum::body::Bytes,
+    expires_at: Instant,
+
+    /// Until when the entry can be served in place of failed renders, once expired.
+    stale_until: Instant,
+
+    /// Until when the entry can be served while it is revalidated, once expired.
+    revalidate_until: Instant,
+
+    /// The shell caching marker of the response, which is not a header.
+    cache_part: Option<super::CachePart>,
+
+    /// The cache tags of the response.
+    tags: BTreeSet<String>,
+
+    /// The request headers listed in the `Vary` header of the response, with the values they
+    /// had in the request the response was rendered for.
+    vary: Vec<(http::HeaderName, Option<http::HeaderValue>)>,
+
+    /// The approximate size of the entry, in bytes.
+    size: usize,
+
+    /// The last access tick of the entry.
+    tick: u64,
+}
+
+impl StoredResponse {
+    /// Get until when the entry is kept in the store.
+    fn kept_until(&self) -> Instant {
+        self.stale_until.max(self.revalidate_until)
+    }
+
+    /// Check whether the response can be served to a request, given its headers.
+    fn matches(&self, request_headers: &http::HeaderMap) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| request_headers.get(name) == value.as_ref())
+    }
+
+    fn to_response(&self) -> axum::response::Response {
+        let mut response = axum::response::Response::new(self.body.clone().into());
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers.clone();
+
+        if let Some(cache_part) = self.cache_part {
+            response.extensions_mut().insert(cache_part);
+        }
+
+        if !self.tags.is_empty() {
+            response
+                .extensions_mut()
+                .insert(CacheTags(self.tags.clone()));
+        }
+
+        response
+    }
+}
+
+/// A response extension holding the cache tags of a response, as set with
+/// [`CachingResponseExt::with_cache_tags`](super::CachingResponseExt::with_cache_tags).
+#[derive(Debug, Clone, Default)]
+pub struct CacheTags(pub(crate) BTreeSet<String>);
+
+impl CacheTags {
+    /// Iterate over the tags, in lexicographic order.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
+
+/// A response extension marking responses served from a [`CacheStore`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CacheHit {
+    /// When the stored response expires.
+    pub(crate) expires_at: Instant,
+}
+
+/// A response extension marking responses that were just rendered and stored in a
+/// [`CacheStore`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CacheStored {
+    /// When the stored response expires.
+    pub(crate) expires_at: Instant,
+}
+
+/// A response extension marking stale responses served in place of failed renders.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CacheStale;
+
+/// A response extension marking stale responses served while they are revalidated.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CacheRevalidating;
+
+/// The outcome of a store lookup.
+enum Lookup {
+    /// A fresh response was found.
+    Hit(axum::response::Response),
+
+    /// An expired response was found, and is being revalidated.
+    Stale(axum::response::Response),
+
+    /// An expired response was found: the caller must revalidate it.
+    ///
+    /// Holds the tick at which the render started.
+    Revalidate(
+        axum::response::Response,
+        tokio::sync::watch::Sender<()>,
+        u64,
+    ),
+
+    /// No render is in progress: the caller must render the response.
+    ///
+    /// Holds the tick at which the render started.
+    Render(tokio::sync::watch::Sender<()>, u64),
+
+    /// A render is in progress: the caller should wait for it.
+    Wait(tokio::sync::watch::Receiver<()>),
+}
+
+/// Removes the in-flight marker of a render when dropped, even if the render was cancelled.
+///
+/// Waiting requests are notified when the sender is dropped, after the marker was removed.
+struct InFlightGuard<'s> {
+    store: &'s MemoryCacheStore,
+    key: StoreKey,
+    _sender: tokio::sync::watch::Sender<()>,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        let mut state = self.store.state();
+        state.in_flight.remove(&self.key);
+
+        if state.in_flight.is_empty() {
+            state.invalidations.clear();
+        }
+    }
+}
+
+impl MemoryCacheStore {
+    /// Set the maximum time to wait for a concurrent render of the same response.
+    ///
+    /// Once the timeout expires, waiting requests render the response themselves.
+    pub fn with_single_flight_timeout(mut self, single_flight_timeout: Duration) -> Self {
+        self.single_flight_timeout = single_flight_timeout;
+        self
+    }
+
+    /// Set the maximum number of stored responses.
+    ///
+    /// Once reached, the least recently used responses are evicted.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Set the maximum approximate size of the stored responses, in bytes.
+    ///
+    /// Once reached, the least recently used responses are evicted.
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Serve expired responses in place of failed renders, for up to the specified duration after
+    /// they expired.
+    ///
+    /// Expired responses are kept in the store for that duration. When rendering a response fails,
+    /// with an error or a `5xx` status, or takes longer than the
+    /// [render timeout](MemoryCacheStore::with_render_timeout), the failure is logged and the stale
+    /// response is served instead, with a `Warning: 111 - "Revalidation Failed"` header and a
+    /// `no-cache` directive. Stale responses are never served to requests asking to bypass the
+    /// cache.
+    ///
+    /// Disabled by default.
+    pub fn with_stale_if_error(mut self, max_stale: Duration) -> Self {
+        self.stale_if_error = Some(max_stale);
+        self
+    }
+
+    /// Serve expired responses right away while they are rendered again in the background, for up
+    /// to the specified duration after they expired.
+    ///
+    /// Expired responses are kept in the store for that duration. The first request for an
+    /// expired response starts a render in the background, and all the requests are served the
+    /// expired response with a `no-cache` directive until the render completes and replaces it.
+    /// If the render fails, the expired response is kept until the next request. Stale responses
+    /// are never served to requests asking to bypass the cache.
+    ///
+    /// Disabled by default.
+    pub fn with_stale_while_revalidate(mut self, max_stale: Duration) -> Self {
+        self.stale_while_revalidate = Some(max_stale);
+        self
+    }
+
+    /// Set the maximum time to wait for a render when a stale response can be served instead.
+    ///
+    /// Renders are only interrupted when a stale response is available, as configured with
+    /// [`MemoryCacheStore::with_stale_if_error`]: otherwise, they complete as usual.
+    pub fn with_render_timeout(mut self, render_timeout: Duration) -> Self {
+        self.render_timeout = Some(render_timeout);
+        self
+    }
+
+    /// Remove all the expired responses from the store, except those that can still be served
+    /// stale.
+    ///
+    /// Returns the number of removed responses.
+    pub fn sweep(&self) -> usize {
+        self.state().sweep()
+    }
+
+    /// Spawn a task sweeping expired responses from the store at the specified interval.
+    ///
+    /// The task stops on its own once the store and all its clones are dropped.
+    ///
+    /// # Panics
+    ///
+    /// If called outside of a Tokio runtime, the call will panic.
+    pub fn spawn_sweeper(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let state = Arc::downgrade(&self.state);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                interval.tick().await;
+
+                let Some(state) = state.upgrade() else {
+                    break;
+                };
+
+                let swept = state
+                    .lock()
+                    .expect("cache store lock should not be poisoned")
+                    .sweep();
+
+                if swept > 0 {
+                    debug!("Swept {swept} expired response(s) from the cache store");
+                }
+            }
+        })
+    }
+
+    /// Get a stored response, or render and store it, always in the foreground.
+    ///
+    /// The maximum body size of the stored responses is decided from their headers, and responses
+    /// without one are never buffered nor stored.
+    #[cfg(test)]
+    async fn get_or_render_in_foreground<F, Fut, L>(
+        &self,
+        key: StoreKey,
+        request_headers: &http::HeaderMap,
+        ttl: Duration,
+        max_body_size: L,
+        render: F,
+    ) -> Result<axum::response::Response, axum::response::Response>
+    where
+        L: Fn(&http::HeaderMap) -> Option<usize>,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<axum::response::Response, axum::response::Response>>,
+    {
+        let lookup = self.lookup(&key, request_headers, false);
+
+        self.resolve(lookup, key, request_headers, ttl, max_body_size, render)
+            .await
+    }
+
+    /// Get a stored response, or render and store it, serving expired responses while they are
+    /// rendered again in the background if enabled.
+    ///
+    /// The maximum body size of the stored responses is decided from their headers, and responses
+    /// without one are never buffered nor stored.
+    async fn get_or_revalidate<F, Fut, L>(
+        &self,
+        key: StoreKey,
+        request_headers: &http::HeaderMap,
+        ttl: Duration,
+        max_body_size: L,
+        render: F,
+    ) -> Result<axum::response::Response, axum::response::Response>
+    where
+        L: Fn(&http::HeaderMap) -> Option<usize> + Send + 'static,
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<axum::response::Response, axum::response::Response>>
+            + Send
+            + 'static,
+    {
+        match self.lookup(&key, request_headers, true) {
+            Lookup::Stale(response) => Ok(response),
+            Lookup::Revalidate(response, sender, started_at) => {
+                let store = self.clone();
+                let request_headers = request_headers.clone();
+
+                debug!("Serving stale `{key}` while revalidating it in the background");
+
+                tokio::spawn(async move {
+                    let _guard = InFlightGuard {
+                        store: &store,
+                        key: key.clone(),
+                        _sender: sender,
+                    };
+
+                    let status = match render().await {
+                        Ok(response) if !response.status().is_server_error() => {
+                            let _ = store
+                                .store(
+                                    key,
+                                    &request_headers,
+                                    ttl,
+                                    max_body_size,
+                                    started_at,
+                                    response,
+                                )
+                                .await;
+
+                            return;
+                        }
+                        Ok(response) | Err(response) => response.status(),
+                    };
+
+                    warn!(
+                        "Revalidating `{key}` failed with status {status}, keeping the stale response"
+                    );
+                });
+
+                Ok(response)
+            }
+            lookup => {
+                self.resolve(lookup, key, request_headers, ttl, max_body_size, render)
+                    .await
+            }
+        }
+    }
+
+    /// Resolve a store lookup, rendering and storing the response if needed.
+    async fn resolve<F, Fut, L>(
+        &self,
+        lookup: Lookup,
+        key: StoreKey,
+        request_headers: &http::HeaderMap,
+        ttl: Duration,
+        max_body_size: L,
+        render: F,
+    ) -> Result<axum::response::Response, axum::response::Response>
+    where
+        L: Fn(&http::HeaderMap) -> Option<usize>,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<axum::response::Response, axum::response::Response>>,
+    {
+        match lookup {
+            Lookup::Hit(response) | Lookup::Stale(response) => Ok(response),
+            // Revalidating in the foreground is just rendering again.
+            Lookup::Render(sender, started_at) | Lookup::Revalidate(_, sender, started_at) => {
+                let _guard = InFlightGuard {
+                    store: self,
+                    key: key.clone(),
+                    _sender: sender,
+                };
+
+                let response = self.render_or_stale(&key, request_headers, render).await?;
+
+                if response.extensions().get::<CacheStale>().is_some() {
+                    return Ok(response);
+                }
+
+                self.store(
+                    key,
+                    request_headers,
+                    ttl,
+                    max_body_size,
+                    started_at,
+                    response,
+                )
+                .await
+            }
+            Lookup::Wait(mut receiver) => {
+                // The sender is never used to send values: `changed` returns an error as soon as
+                // the render completes, one way or another.
+                if tokio::time::timeout(self.single_flight_timeout, receiver.changed())
+                    .await
+                    .is_err()
+                {
+                    warn!(
+                        "Timed out waiting for a concurrent render of `{key}`, rendering it again"
+                    );
+                } else if let Some(response) = self.get(&key, request_headers) {
+                    return Ok(response);
+                }
+
+                self.render_or_stale(&key, request_headers, render).await
+            }
+        }
+    }
+
+    /// Render a response, or serve a stale one if the render fails or times out.
+    async fn render_or_stale<F, Fut>(
+        &self,
+        key: &StoreKey,
+        request_headers: &http::HeaderMap,
+        render: F,
+    ) -> Result<axum::response::Response, axum::response::Response>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<axum::response::Response, axum::response::Response>>,
+    {
+        let mut render = std::pin::pin!(render());
+
+        let result = match self.render_timeout {
+            Some(render_timeout) if self.has_stale(key, request_headers) => {
+                match tokio::time::timeout(render_timeout, &mut render).await {
+                    Ok(result) => result,
+                    Err(_) => match self.get_stale(key, request_headers) {
+                        Some(response) => {
+                            warn!("Rendering `{key}` timed out, serving a stale response");
+
+                            return Ok(response);
+                        }
+                        None => render.await,
+                    },
+                }
+            }
+            _ => render.await,
+        };
+
+        let status = match &result {
+            Ok(response) if !response.status().is_server_error() => return result,
+            Ok(response) | Err(response) => response.status(),
+        };
+
+        match self.get_stale(key, request_headers) {
+            Some(response) => {
+                warn!("Rendering `{key}` failed with status {status}, serving a stale response");
+
+                Ok(response)
+            }
+            None => result,
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("cache store lock should not be poisoned")
+    }
+
+    fn get(
+        &self,
+        key: &StoreKey,
+        request_headers: &http::HeaderMap,
+    ) -> Option<axum::response::Response> {
+        self.state().get(key, request_headers)
+    }
+
+    /// Check whether a stale response could be served in place of a failed render.
+    fn has_stale(&self, key: &StoreKey, request_headers: &http::HeaderMap) -> bool {
+        let now = Instant::now();
+
+        self.stale_if_error.is_some()
+            && self
+                .state()
+                .entries
+                .get(key)
+                .is_some_and(|entry| now < entry.stale_until && entry.matches(request_headers))
+    }
+
+    /// Get a stale response to serve in place of a failed render, if enabled and available.
+    fn get_stale(
+        &self,
+        key: &StoreKey,
+        request_headers: &http::HeaderMap,
+    ) -> Option<axum::response::Response> {
+        self.stale_if_error?;
+        self.state().get_stale(key, request_headers)
+    }
+
+    /// Look up a response, serving it stale while it is revalidated if `revalidate` is set and
+    /// enabled.
+    fn lookup(
+        &self,
+        key: &StoreKey,
+        request_headers: &http::HeaderMap,
+        revalidate: bool,
+    ) -> Lookup {
+        let mut state = self.state();
+
+        if let Some(response) = state.get(key, request_headers) {
+            state.metrics.hits += 1;
+
+            return Lookup::Hit(response);
+        }
+
+        state.metrics.misses += 1;
+
+        let stale = if revalidate && self.stale_while_revalidate.is_some() {
+            state.get_revalidating(key, request_headers)
+        } else {
+            None
+        };
+
+        if let Some(receiver) = state.in_flight.get(key) {
+            return match stale {
+                Some(response) => Lookup::Stale(response),
+                None => Lookup::Wait(receiver.clone()),
+            };
+        }
+
+        let (sender, receiver) = tokio::sync::watch::channel(());
+        state.in_flight.insert(key.clone(), receiver);
+
+        match stale {
+            Some(response) => Lookup::Revalidate(response, sender, state.tick),
+            None => Lookup::Render(sender, state.tick),
+        }
+    }
+
+    async fn store(
+        &self,
+        key: StoreKey,
+        request_headers: &http::HeaderMap,
+        ttl: Duration,
+        max_body_size: impl Fn(&http::HeaderMap) -> Option<usize>,
+        started_at: u64,
+        response: axum::response::Response,
+    ) -> Result<axum::response::Response, axum::response::Response> {
+        let Some(max_body_size) = max_body_size(response.headers()) else {
+            return Ok(response);
+        };
+
+        if !is_storable(&response, max_body_size) {
+            return Ok(response);
+        }
+
+        let (parts, body) = response.into_parts();
+        let body = axum::body::to_bytes(body, max_body_size)
+            .await
+            .map_err(|err| {
+                error!("Failed to read response body: {err}");
+
+                http::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            })?;
+        let cache_part = parts.extensions.get::<super::CachePart>().copied();
+        let tags = parts
+            .extensions
+            .get::<CacheTags>()
+            .map(|tags| tags.0.clone())
+            .unwrap_or_default();
+
+        let vary = vary(&parts.headers)
+            .map(|name| {
+                let value = request_headers.get(&name).cloned();
+
+                (name, value)
+            })
+            .collect();
+
+        let size = key.0.len()
+            + tags.iter().map(String::len).sum::<usize>()
+            + body.len()
+            + parts
+                .headers
+                .iter()
+                .map(|(name, value)| name.as_str().len() + value.len())
+                .sum::<usize>();
+        let expires_at = Instant::now() + ttl;
+        let entry = StoredResponse {
+            status: parts.status,
+            headers: parts.headers,
+            body,
+            expires_at,
+            stale_until: expires_at + self.stale_if_error.unwrap_or_default(),
+            revalidate_until: expires_at + self.stale_while_revalidate.unwrap_or_default(),
+            cache_part,
+            tags,
+            vary,
+            size,
+            tick: 0,
+        };
+        let mut response = entry.to_response();
+        response.extensions_mut().insert(CacheStored {
+            expires_at: entry.expires_at,
+        });
+
+        let mut state = self.state();
+
+        if state.invalidated_since(&entry.tags, started_at) {
+            debug!("Not storing `{key}`, which was invalidated while rendering");
+
+            return Ok(entry.to_response());
+        }
+
+        state.insert(key, entry);
+        state.evict(self.max_entries, self.max_size);
+
+        Ok(response)
+    }
+}
+
+impl CacheStore for MemoryCacheStore {
+    fn get_or_render<'a>(
+        &'a self,
+        key: String,
+        request_headers: &'a http::HeaderMap,
+        ttl: Duration,
+        max_body_size: CacheBodyLimit,
+        render: CacheRender,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<axum::response::Response, axum::response::Response>>
+                + Send
+                + 'a,
+        >,
+    > {
+        Box::pin(self.get_or_revalidate(
+            StoreKey::custom(key),
+            request_headers,
+            ttl,
+            max_body_size,
+            render,
+        ))
+    }
+
+    fn metrics(&self) -> CacheStoreMetrics {
+        self.state().metrics
+    }
+
+    fn entries(&self) -> Vec<CacheStoreEntry> {
+        let now = Instant::now();
+        let mut entries: Vec<_> = self
+            .state()
+            .entries
+            .iter()
+            .map(|(key, entry)| CacheStoreEntry {
+                key: key.to_string(),
+                status: entry.status,
+                size: entry.size,
+                expires_in: entry.expires_at.saturating_duration_since(now),
+                tags: entry.tags.iter().cloned().collect(),
+            })
+            .collect();
+
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+
+        entries
+    }
+
+    fn purge(&self, key: &str) -> bool {
+        let mut state = self.state();
+        let key = state
+            .entries
+            .keys()
+            .find(|stored_key| stored_key.to_string() == key)
+            .cloned();
+
+        key.is_some_and(|key| state.remove(&key).is_some())
+    }
+
+    fn purge_all(&self) -> usize {
+        let mut state = self.state();
+        let keys: Vec<_> = state.entries.keys().cloned().collect();
+
+        for key in &keys {
+            state.remove(key);
+        }
+
+        keys.len()
+    }
+
+    fn invalidate_tag(&self, tag: &str) -> usize {
+        let removed = self.state().invalidate_tag(tag);
+
+        if removed > 0 {
+            debug!("Invalidated {removed} response(s) tagged `{tag}` from the cache store");
+        }
+
+        removed
+    }
+}
+
+/// Get the names listed in the `Vary` headers of a response.
+///
+/// A `*` is kept as is: [`is_storable`] checks for it and refuses to store such responses.
+fn vary(headers: &http::HeaderMap) -> impl Iterator<Item = http::HeaderName> + '_ {
+    headers
+        .get_all(http::header::VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .filter_map(|name| http::HeaderName::try_from(name).ok())
+}
+
+/// Check whether a response can be stored.
+fn is_storable(response: &axum::response::Response, max_body_size: usize) -> bool {
+    let no_store = response
+        .headers()
+        .get_all(http::header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|directive| directive.trim().eq_ignore_ascii_case("no-store"));
+
+    response.status() == http::StatusCode::OK
+        && !no_store
+        && !vary(response.headers()).any(|name| name == "*")
+        && response.extensions().get::<super::CachePart>() != Some(&super::CachePart::Dynamic)
+        && !response.headers().contains_key(http::header::SET_COOKIE)
+        && response
+            .body()
+            .size_hint()
+            .upper()
+            .is_some_and(|size| size <= max_body_size as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(60);
+    const MAX_BODY_SIZE: usize = 1024;
+
+    fn max_body_size(_headers: &http::HeaderMap) -> Option<usize> {
+        Some(MAX_BODY_SIZE)
+    }
+
+    /// The headers of requests without `Vary`-ing headers.
+    static NO_HEADERS: std::sync::LazyLock<http::HeaderMap> =
+        std::sync::LazyLock::new(http::HeaderMap::new);
+
+    fn key() -> StoreKey {
+        StoreKey::new("/".to_string(), &crate::htmx::Request::Classic)
+    }
+
+    async fn render(
+        renders: &AtomicUsize,
+    ) -> Result<axum::response::Response, axum::response::Response> {
+        renders.fetch_add(1, Ordering::SeqCst);
+
+        // Give concurrent requests a chance to wait for this render.
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+
+        Ok("Hello".into_response())
+    }
+
+    async fn body(response: axum::response::Response) -> axum::body::Bytes {
+        axum::body::to_bytes(response.into_body(), MAX_BODY_SIZE)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_single_flight() {
+        let store = MemoryCacheStore::default();
+        let renders = AtomicUsize::new(0);
+
+        let (a, b, c) = tokio::join!(
+            store.get_or_render_in_foreground(key(), &NO_HEADERS, TTL, max_body_size, || {
+                render(&renders)
+            }),
+            store.get_or_render_in_foreground(key(), &NO_HEADERS, TTL, max_body_size, || {
+                render(&renders)
+            }),
+            store.get_or_render_in_foreground(key(), &NO_HEADERS, TTL, max_body_size, || {
+                render(&renders)
+            }),
+        );
+
+        assert_eq!(renders.load(Ordering::SeqCst), 1);
+
+        for response in [a, b, c] {
+            assert_eq!(body(response.unwrap()).await, "Hello");
+        }
+
+        // Subsequent requests are served from the store.
+        let response = store
+            .get_or_render_in_foreground(key(), &NO_HEADERS, TTL, max_body_size, || {
+                render(&renders)
+            })
+            .await
+            .unwrap();
+        assert_eq!(body(response).await, "Hello");
+        assert_eq!(renders.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_single_flight_timeout() {
+        let store =
+            MemoryCacheStore::default().with_single_flight_timeout(Duration::from_millis(10));
+        let renders = AtomicUsize::new(0);
+
+        tokio::select! {
+            _ = store.get_or_render_in_foreground(key(), &NO_HEADERS, TTL, max_body_size, std::future::pending) => {
+                unreachable!("the first render never completes");
+            }
+            response = async {
+                tokio::task::yield_now().await;
+                store.get_or_render_in_foreground(key(), &NO_HEADERS, TTL, max_body_size, || render(&renders)).await
+            } => {
+                assert_eq!(body(response.unwrap()).await, "Hello");
+            }
+        }
+
+        assert_eq!(renders.load(Ordering::SeqCst), 1);
+
+        // The cancelled render no longer blocks other requests.
+        assert!(store.state().in_flight.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unstorable_responses() {
+        let store = MemoryCacheStore::default();
+        let renders = AtomicUsize::new(0);
+
+        for _ in 0..2 {
+            let response = store
+                .get_or_render_in_foreground(key(), &NO_HEADERS, TTL, max_body_size, || async {
+                    renders.fetch_add(1, Ordering::SeqCst);
+
+                    Ok(([(http::header::SET_COOKIE, "session=1")], "Hello").into_response())
+                })
+                .await
+                .unwrap();
+            assert_eq!(body(response).await, "Hello");
+        }
+
+        assert_eq!(renders.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_dynamic_responses() {
+        use crate::{CachePart, CachingResponseExt};
+
+        let store = MemoryCacheStore::default();
+        let renders = AtomicUsize::new(0);
+
+        for cache_part in [
+            CachePart::Dynamic,
+            CachePart::Dynamic,
+            CachePart::Shell,
+            CachePart::Shell,
+        ] {
+            let key = key_for(&format!("/{cache_part:?}"));
+            let response = store
+                .get_or_render_in_foreground(key, &NO_HEADERS, TTL, max_body_size, || async {
+                    renders.fetch_add(1, Ordering::SeqCst);
+
+                    Ok("Hello".into_response().with_cache_part(cache_part))
+                })
+                .await
+                .unwrap();
+
+            assert_eq!(response.extensions().get::<CachePart>(), Some(&cache_part));
+        }
+
+        assert_eq!(renders.load(Ordering::SeqCst), 3);
+    }
+
+    fn key_for(url: &str) -> StoreKey {
+        StoreKey::new(url.to_string(), &crate::htmx::Request::Classic)
+    }
+
+    #[test]
+    fn test_store_key_display() {
+        let htmx = crate::htmx::Request::Htmx {
+            boosted: false,
+            current_url: "/".to_string(),
+            history_restore_request: false,
+            prompt: String::new(),
+            target: Some(http::HeaderValue::from_static("main")),
+            trigger_name: None,
+            trigger: None,
+        };
+
+        assert_eq!(key_for("/posts").to_string(), "GET /posts");
+        assert_eq!(
+            StoreKey::new("/posts".to_string(), &htmx).to_string(),
+            "GET /posts (htmx, target: main)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_metrics_and_sweep() {
+        let store = MemoryCacheStore::default();
+        let renders = AtomicUsize::new(0);
+
+        for ttl in [Duration::ZERO, TTL] {
+            store
+                .get_or_render_in_foreground(
+                    key_for(&format!("/{}", ttl.as_secs())),
+                    &NO_HEADERS,
+                    ttl,
+                    max_body_size,
+                    || render(&renders),
+                )
+                .await
+                .unwrap();
+        }
+
+        store
+            .get_or_render_in_foreground(key_for("/60"), &NO_HEADERS, TTL, max_body_size, || {
+                render(&renders)
+            })
+            .await
+            .unwrap();
+
+        let metrics = store.metrics();
+        assert_eq!(metrics.entries, 2);
+        assert_eq!(metrics.hits, 1);
+        assert_eq!(metrics.misses, 2);
+        assert!(metrics.size > 0);
+
+        assert_eq!(store.sweep(), 1);
+
+        let metrics = store.metrics();
+        assert_eq!(metrics.entries, 1);
+        assert_eq!(metrics.expirations, 1);
+    }
+
+    #[tokio::test]
+    async fn test_entries_and_purge() {
+        let store = MemoryCacheStore::default();
+        let renders = AtomicUsize::new(0);
+
+        for url in ["/b", "/a", "/c"] {
+            store
+                .get_or_render_in_foreground(key_for(url), &NO_HEADERS, TTL, max_body_size, || {
+                    render(&renders)
+                })
+                .await
+                .unwrap();
+        }
+
+        let entries = store.entries();
+        let keys: Vec<_> = entries.iter().map(|entry| entry.key.as_str()).collect();
+        assert_eq!(keys, ["GET /a", "GET /b", "GET /c"]);
+        assert_eq!(entries[0].status, http::StatusCode::OK);
+        assert!(entries[0].expires_in <= TTL);
+
+        assert!(store.purge("GET /b"));
+        assert!(!store.purge("GET /b"));
+        assert_eq!(store.metrics().entries, 2);
+
+        assert_eq!(store.purge_all(), 2);
+        assert!(store.entries().is_empty());
+        assert_eq!(store.metrics().size, 0);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_tag() {
+        use crate::CachingResponseExt;
+
+        let store = MemoryCacheStore::default();
+
+        for (url, tags) in [
+            ("/foods", &["food"][..]),
+            ("/foods/3", &["food", "food:3"]),
+            ("/drinks", &["drink"]),
+        ] {
+            store
+                .get_or_render_in_foreground(
+                    key_for(url),
+                    &NO_HEADERS,
+                    TTL,
+                    max_body_size,
+                    || async {
+                        Ok("Hello"
+                            .into_response()
+                            .with_cache_tags(tags.iter().copied()))
+                    },
+                )
+                .await
+                .unwrap();
+        }
+
+        let entries = store.entries();
+        assert_eq!(entries[2].key, "GET /foods/3");
+        assert_eq!(entries[2].tags, ["food", "food:3"]);
+
+        assert_eq!(store.invalidate_tag("food:3"), 1);
+        assert_eq!(store.invalidate_tag("food"), 1);
+        assert_eq!(store.invalidate_tag("food"), 0);
+
+        let keys: Vec<_> = store.entries().into_iter().map(|entry| entry.key).collect();
+        assert_eq!(keys, ["GET /drinks"]);
+
+        store.purge_all();
+        assert!(store.state().tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_tag_while_rendering() {
+        use crate::CachingResponseExt;
+
+        let store = MemoryCacheStore::default();
+
+        let response = store
+            .get_or_render_in_foreground(key(), &NO_HEADERS, TTL, max_body_size, || async {
+                store.invalidate_tag("food");
+
+                Ok("Hello".into_response().with_cache_tags(["food"]))
+            })
+            .await
+            .unwrap();
+
+        // The response is served, but not stored.
+        assert_eq!(body(response).await, "Hello");
+        assert_eq!(store.metrics().entries, 0);
+        assert!(store.state().invalidations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_lru_eviction() {
+        let store = MemoryCacheStore::default().with_max_entries(2);
+        let renders = AtomicUsize::new(0);
+
+        for url in ["/a", "/b", "/a", "/c"] {
+            store
+                .get_or_render_in_foreground(key_for(url), &NO_HEADERS, TTL, max_body_size, || {
+                    render(&renders)
+                })
+                .await
+                .unwrap();
+        }
+
+        // `/b` was the least recently used response when `/c` was stored.
+        assert_eq!(store.metrics().evictions, 1);
+        assert!(store.get(&key_for("/a"), &NO_HEADERS).is_some());
+        assert!(store.get(&key_for("/b"), &NO_HEADERS).is_none());
+        assert!(store.get(&key_for("/c"), &NO_HEADERS).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_size_cap() {
+        let store = MemoryCacheStore::default().with_max_size(1);
+        let renders = AtomicUsize::new(0);
+
+        store
+            .get_or_render_in_foreground(key(), &NO_HEADERS, TTL, max_body_size, || {
+                render(&renders)
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(store.metrics().entries, 0);
+        assert_eq!(store.metrics().size, 0);
+    }
+
+    #[tokio::test]
+    async fn test_sweeper() {
+        let store = MemoryCacheStore::default();
+        let renders = AtomicUsize::new(0);
+
+        store
+            .get_or_render_in_foreground(key(), &NO_HEADERS, Duration::ZERO, max_body_size, || {
+                render(&renders)
+            })
+            .await
+            .unwrap();
+
+        let sweeper = store.spawn_sweeper(Duration::from_millis(1));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(store.metrics().entries, 0);
+
+        // The sweeper stops once the store is dropped.
+        drop(store);
+        tokio::time::timeout(Duration::from_secs(1), sweeper)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expiration() {
+        let store = MemoryCacheStore::default();
+        let renders = AtomicUsize::new(0);
+
+        for _ in 0..2 {
+            store
+                .get_or_render_in_foreground(
+                    key(),
+                    &NO_HEADERS,
+                    Duration::ZERO,
+                    max_body_size,
+                    || render(&renders),
+                )
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(renders.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stale_while_revalidate() {
+        let store =
+            MemoryCacheStore::default().with_stale_while_revalidate(Duration::from_secs(60));
+        let renders = Arc::new(AtomicUsize::new(0));
+        let release = Arc::new(tokio::sync::Notify::new());
+        let revalidate = || {
+            let renders = renders.clone();
+            let release = release.clone();
+
+            move || async move {
+                renders.fetch_add(1, Ordering::SeqCst);
+                release.notified().await;
+
+                Ok("World".into_response())
+            }
+        };
+
+        store
+            .get_or_render_in_foreground(
+                key(),
+                &NO_HEADERS,
+                Duration::ZERO,
+                max_body_size,
+                || async { Ok("Hello".into_response()) },
+            )
+            .await
+            .unwrap();
+
+        // The expired response is served right away, and only revalidated once.
+        for _ in 0..2 {
+            let response = store
+                .get_or_revalidate(key(), &NO_HEADERS, TTL, max_body_size, revalidate())
+                .await
+                .unwrap();
+            assert_eq!(response.headers()[http::header::CACHE_CONTROL], "no-cache");
+            assert!(response.extensions().get::<CacheRevalidating>().is_some());
+            assert_eq!(body(response).await, "Hello");
+        }
+
+        release.notify_one();
+
+        while !store.state().in_flight.is_empty() {
+            tokio::task::yield_now().await;
+        }
+
+        // The revalidated response replaced the expired one.
+        let response = store
+            .get_or_revalidate(key(), &NO_HEADERS, TTL, max_body_size, revalidate())
+            .await
+            .unwrap();
+        assert!(response.extensions().get::<CacheHit>().is_some());
+        assert_eq!(body(response).await, "World");
+        assert_eq!(renders.load(Ordering::SeqCst), 1);
+        assert_eq!(store.metrics().stale_served, 2);
+    }
+
+    #[tokio::test]
+    async fn test_stale_if_error() {
+        let store = MemoryCacheStore::default().with_stale_if_error(Duration::from_secs(60));
+        let renders = AtomicUsize::new(0);
+        let failing = || async { Err(http::StatusCode::BAD_GATEWAY.into_response()) };
+
+        // Without a stale response, errors are returned as-is.
+        let response = store
+            .get_or_render_in_foreground(key(), &NO_HEADERS, Duration::ZERO, max_body_size, failing)
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), http::StatusCode::BAD_GATEWAY);
+
+        store
+            .get_or_render_in_foreground(key(), &NO_HEADERS, Duration::ZERO, max_body_size, || {
+                render(&renders)
+            })
+            .await
+            .unwrap();
+
+        // The expired response is kept, and served in place of failed renders.
+        assert_eq!(store.sweep(), 0);
+
+        let response = store
+            .get_or_render_in_foreground(key(), &NO_HEADERS, Duration::ZERO, max_body_size, failing)
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()[http::header::WARNING],
+            "111 - \"Revalidation Failed\""
+        );
+        assert_eq!(response.headers()[http::header::CACHE_CONTROL], "no-cache");
+        assert!(response.extensions().get::<CacheStale>().is_some());
+        assert_eq!(body(response).await, "Hello");
+
+        let response = store
+            .get_or_render_in_foreground(
+                key(),
+                &NO_HEADERS,
+                Duration::ZERO,
+                max_body_size,
+                || async { Ok(http::StatusCode::INTERNAL_SERVER_ERROR.into_response()) },
+            )
+            .await
+            .unwrap();
+        assert_eq!(body(response).await, "Hello");
+        assert_eq!(store.metrics().stale_served, 2);
+
+        // Successful renders replace the stale response.
+        let response = store
+            .get_or_render_in_foreground(
+                key(),
+                &NO_HEADERS,
+                Duration::ZERO,
+                max_body_size,
+                || async { Ok("World".into_response()) },
+            )
+            .await
+            .unwrap();
+        assert!(response.extensions().get::<CacheStale>().is_none());
+        assert_eq!(body(response).await, "World");
+        assert_eq!(store.metrics().entries, 1);
+    }
+
+    #[tokio::test]
+    async fn test_stale_on_render_timeout() {
+        let store = MemoryCacheStore::default()
+            .with_stale_if_error(Duration::from_secs(60))
+            .with_render_timeout(Duration::from_millis(10));
+        let renders = AtomicUsize::new(0);
+
+        store
+            .get_or_render_in_foreground(key(), &NO_HEADERS, Duration::ZERO, max_body_size, || {
+                render(&renders)
+            })
+            .await
+            .unwrap();
+
+        let response = store
+            .get_or_render_in_foreground(
+                key(),
+                &NO_HEADERS,
+                Duration::ZERO,
+                max_body_size,
+                std::future::pending,
+            )
+            .await
+            .unwrap();
+        assert!(response.extensions().get::<CacheStale>().is_some());
+        assert_eq!(body(response).await, "Hello");
+        assert!(store.state().in_flight.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stale_disabled() {
+        let store = MemoryCacheStore::default();
+        let renders = AtomicUsize::new(0);
+
+        store
+            .get_or_render_in_foreground(key(), &NO_HEADERS, Duration::ZERO, max_body_size, || {
+                render(&renders)
+            })
+            .await
+            .unwrap();
+
+        let response = store
+            .get_or_render_in_foreground(
+                key(),
+                &NO_HEADERS,
+                Duration::ZERO,
+                max_body_size,
+                || async { Err(http::StatusCode::BAD_GATEWAY.into_response()) },
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), http::StatusCode::BAD_GATEWAY);
+        assert_eq!(store.metrics().entries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_vary() {
+        let store = MemoryCacheStore::default();
+        let renders = AtomicUsize::new(0);
+
+        let render_for = |accept: &'static str| {
+            let renders = &renders;
+            let mut headers = http::HeaderMap::new();
+            headers.insert(http::header::ACCEPT, http::HeaderValue::from_static(accept));
+
+            (headers, move || async move {
+                renders.fetch_add(1, Ordering::SeqCst);
+
+                Ok((
+                    [
+                        (http::header::VARY, "Accept"),
+                        (http::header::CONTENT_TYPE, accept),
+                    ],
+                    "Hello",
+                )
+                    .into_response())
+            })
+        };
+
+        for (accept, expected_renders) in [
+            ("text/html", 1),
+            ("text/html", 1),
+            ("application/json", 2),
+            ("text/html", 3),
+        ] {
+            let (headers, render) = render_for(accept);
+            let response = store
+                .get_or_render_in_foreground(key(), &headers, TTL, max_body_size, render)
+                .await
+                .unwrap();
+
+            assert_eq!(response.headers()[http::header::CONTENT_TYPE], accept);
+            assert_eq!(renders.load(Ordering::SeqCst), expected_renders);
+        }
+
+        // Responses varying on `*` can't be served to other requests, so they are not stored.
+        store
+            .get_or_render_in_foreground(
+                key_for("/any"),
+                &NO_HEADERS,
+                TTL,
+                max_body_size,
+                || async { Ok(([(http::header::VARY, "Accept, *")], "Hello").into_response()) },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(store.metrics().entries, 1);
+    }
+}
@@ -0,0 +1,77 @@
+use std::{future::Future, sync::Arc};
+
+use crate::Route;
+
+/// A controller that coalesces concurrent identical requests to another controller.
+///
+/// Only `GET` routes whose [`Route::coalesce_requests`] method returns `true` are coalesced.
+/// Requests to other routes are handled directly by the inner controller.
+pub struct Controller<C: crate::Controller> {
+    pub controller: C,
+    pub coalescing: Arc<super::Coalescing>,
+}
+
+impl<C: crate::Controller> Clone for Controller<C> {
+    fn clone(&self) -> Self {
+        Self {
+            controller: self.controller.clone(),
+            coalescing: self.coalescing.clone(),
+        }
+    }
+}
+
+impl<C> crate::Controller for Controller<C>
+where
+    C: crate::Controller<Response = Result<axum::response::Response, axum::response::Response>>,
+    C::Route: crate::Route + Send + Sync + axum::extract::FromRequest<Self>,
+{
+    type Route = C::Route;
+    type Args = C::Args;
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    fn handle_request(
+        &self,
+        route: Self::Route,
+        htmx: crate::htmx::Request,
+        parts: http::request::Parts,
+        server_info: &crate::ServerInfo,
+        args: Self::Args,
+    ) -> impl Future<Output = Self::Response> + Send {
+        let key = (route.coalesce_requests() && route.method() == http::Method::GET)
+            .then(|| self.coalescing.key(route.to_string(), &htmx, &parts));
+
+        async move {
+            match key {
+                Some(key) => {
+                    self.coalescing
+                        .run(key, || {
+                            self.controller
+                                .handle_request(route, htmx, parts, server_info, args)
+                        })
+                        .await
+                }
+                None => {
+                    self.controller
+                        .handle_request(route, htmx, parts, server_info, args)
+                        .await
+                }
+            }
+        }
+    }
+}
+
+/// An extension trait for controllers that adds request coalescing support.
+pub trait ControllerExt: crate::Controller {
+    fn with_request_coalescing(self, coalescing: super::Coalescing) -> Controller<Self>
+    where
+        Self: Sized;
+}
+
+impl<C: crate::Controller> ControllerExt for C {
+    fn with_request_coalescing(self, coalescing: super::Coalescing) -> Controller<Self> {
+        Controller {
+            controller: self,
+            coalescing: Arc::new(coalescing),
+        }
+    }
+}
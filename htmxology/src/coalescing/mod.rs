@@ -0,0 +1,388 @@
+//! Coalescing of concurrent identical requests.
+
+mod controller;
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use axum::body::HttpBody;
+pub use controller::{Controller, ControllerExt};
+
+/// The default maximum size of a shared response body.
+const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024; // 1 MB
+
+/// A function identifying the principal of a request, like its user.
+type PrincipalFn = dyn Fn(&http::request::Parts) -> Option<String> + Send + Sync;
+
+/// A request coalescing strategy.
+///
+/// Concurrent identical `GET` requests share a single execution of the handler: the first request
+/// runs it, and the requests arriving while it runs wait for its response instead of running the
+/// handler again. Unlike a [`CacheStore`](crate::CacheStore), responses are not kept once they
+/// have been delivered.
+///
+/// Requests are identical if they are for the same route, have the same `HX-Request` and
+/// `HX-Target` headers, the same values of the vary headers (`Cookie` and `Authorization` by
+/// default) and the same principal, if a principal function is set.
+///
+/// Only successful and error responses with a known, bounded body size are shared, and responses
+/// that set cookies never are: the waiting requests run the handler themselves instead.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[derive(Route)]
+/// enum AppRoute {
+///     #[route("reports/summary")]
+///     #[coalesce]
+///     Summary,
+/// }
+///
+/// let controller = AppController::default().with_request_coalescing(
+///     Coalescing::default().with_principal(|parts| user_id(parts).map(|id| id.to_string())),
+/// );
+/// ```
+pub struct Coalescing {
+    vary_headers: Vec<http::HeaderName>,
+    principal: Option<Box<PrincipalFn>>,
+    max_body_size: usize,
+    in_flight: Mutex<HashMap<CoalescingKey, tokio::sync::watch::Receiver<Option<SharedResult>>>>,
+}
+
+impl Default for Coalescing {
+    fn default() -> Self {
+        Self {
+            vary_headers: vec![http::header::COOKIE, http::header::AUTHORIZATION],
+            principal: None,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            in_flight: Default::default(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Coalescing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Coalescing")
+            .field("vary_headers", &self.vary_headers)
+            .field("max_body_size", &self.max_body_size)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The key identifying identical requests.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct CoalescingKey {
+    url: String,
+    htmx: bool,
+    target: Option<http::HeaderValue>,
+    vary: Vec<Option<http::HeaderValue>>,
+    principal: Option<String>,
+}
+
+/// A response shared by coalesced requests.
+#[derive(Debug)]
+struct SharedResponse {
+    status: http::StatusCode,
+    headers: http::HeaderMap,
+    body: axum::body::Bytes,
+}
+
+type SharedResult = Arc<Result<SharedResponse, SharedResponse>>;
+
+impl SharedResponse {
+    /// Buffer a response, if it can be shared.
+    ///
+    /// Returns the response untouched if it can't be shared.
+    async fn buffer(
+        response: axum::response::Response,
+        max_body_size: usize,
+    ) -> Result<Self, axum::response::Response> {
+        let shareable = !response.headers().contains_key(http::header::SET_COOKIE)
+            && response
+                .body()
+                .size_hint()
+                .upper()
+                .is_some_and(|size| size <= max_body_size as u64);
+
+        if !shareable {
+            return Err(response);
+        }
+
+        let (parts, body) = response.into_parts();
+
+        match axum::body::to_bytes(body, max_body_size).await {
+            Ok(body) => Ok(Self {
+                status: parts.status,
+                headers: parts.headers,
+                body,
+            }),
+            Err(err) => {
+                tracing::error!("Failed to read response body: {err}");
+
+                Err(http::Response::from_parts(
+                    parts,
+                    axum::body::Body::from("failed to read response body"),
+                ))
+            }
+        }
+    }
+
+    fn to_response(&self) -> axum::response::Response {
+        let mut response = axum::response::Response::new(self.body.clone().into());
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers.clone();
+
+        response
+    }
+}
+
+/// Removes the in-flight marker of a handler execution when dropped, even if it was cancelled.
+struct InFlightGuard<'c> {
+    coalescing: &'c Coalescing,
+    key: CoalescingKey,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.coalescing.in_flight().remove(&self.key);
+    }
+}
+
+impl Coalescing {
+    /// Set the request headers whose values identify identical requests, in addition to the
+    /// route.
+    ///
+    /// Defaults to `Cookie` and `Authorization`, so that requests from different users are never
+    /// coalesced. Only replace them if the principal function identifies users.
+    pub fn with_vary_headers(
+        mut self,
+        vary_headers: impl IntoIterator<Item = http::HeaderName>,
+    ) -> Self {
+        self.vary_headers = vary_headers.into_iter().collect();
+        self
+    }
+
+    /// Set a function identifying the principal of a request, like its authenticated user.
+    ///
+    /// Requests are only coalesced with requests of the same principal.
+    pub fn with_principal(
+        mut self,
+        principal: impl Fn(&http::request::Parts) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.principal = Some(Box::new(principal));
+        self
+    }
+
+    /// Set the maximum size of a shared response body.
+    ///
+    /// Larger responses are not shared.
+    pub fn with_max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Get the number of handler executions currently shared by coalesced requests.
+    pub fn in_flight_count(&self) -> usize {
+        self.in_flight().len()
+    }
+
+    fn in_flight(
+        &self,
+    ) -> std::sync::MutexGuard<
+        '_,
+        HashMap<CoalescingKey, tokio::sync::watch::Receiver<Option<SharedResult>>>,
+    > {
+        self.in_flight
+            .lock()
+            .expect("in-flight lock should not be poisoned")
+    }
+
+    /// Get the key identifying a request.
+    pub(crate) fn key(
+        &self,
+        url: String,
+        htmx: &crate::htmx::Request,
+        parts: &http::request::Parts,
+    ) -> CoalescingKey {
+        let (htmx, target) = match htmx {
+            crate::htmx::Request::Classic => (false, None),
+            crate::htmx::Request::Htmx { target, .. } => (true, target.clone()),
+        };
+
+        CoalescingKey {
+            url,
+            htmx,
+            target,
+            vary: self
+                .vary_headers
+                .iter()
+                .map(|name| parts.headers.get(name).cloned())
+                .collect(),
+            principal: self
+                .principal
+                .as_ref()
+                .and_then(|principal| principal(parts)),
+        }
+    }
+
+    /// Run a handler, or wait for a concurrent execution of the same handler.
+    pub(crate) async fn run<F, Fut>(
+        &self,
+        key: CoalescingKey,
+        handle: F,
+    ) -> Result<axum::response::Response, axum::response::Response>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<axum::response::Response, axum::response::Response>>,
+    {
+        let lookup = {
+            let mut in_flight = self.in_flight();
+
+            match in_flight.get(&key) {
+                Some(receiver) => Err(receiver.clone()),
+                None => {
+                    let (sender, receiver) = tokio::sync::watch::channel(None);
+                    in_flight.insert(key.clone(), receiver);
+
+                    Ok(sender)
+                }
+            }
+        };
+
+        let mut receiver = match lookup {
+            Ok(sender) => return self.lead(key, sender, handle).await,
+            Err(receiver) => receiver,
+        };
+
+        if let Ok(shared) = receiver.wait_for(Option::is_some).await
+            && let Some(shared) = shared.as_ref()
+        {
+            let shared = shared.clone();
+
+            return match shared.as_ref() {
+                Ok(response) => Ok(response.to_response()),
+                Err(response) => Err(response.to_response()),
+            };
+        }
+
+        // The concurrent execution did not produce a shareable response, or was cancelled.
+        handle().await
+    }
+
+    /// Run a handler, and share its response with the requests waiting for it.
+    async fn lead<F, Fut>(
+        &self,
+        key: CoalescingKey,
+        sender: tokio::sync::watch::Sender<Option<SharedResult>>,
+        handle: F,
+    ) -> Result<axum::response::Response, axum::response::Response>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<axum::response::Response, axum::response::Response>>,
+    {
+        let _guard = InFlightGuard {
+            coalescing: self,
+            key,
+        };
+
+        let (response, is_ok) = match handle().await {
+            Ok(response) => (response, true),
+            Err(response) => (response, false),
+        };
+
+        let shared = match SharedResponse::buffer(response, self.max_body_size).await {
+            Ok(shared) => shared,
+            // Dropping the sender lets the waiting requests run the handler themselves.
+            Err(response) if is_ok => return Ok(response),
+            Err(response) => return Err(response),
+        };
+
+        let response = shared.to_response();
+        let result = if is_ok { Ok(shared) } else { Err(shared) };
+
+        sender.send_replace(Some(Arc::new(result)));
+
+        if is_ok { Ok(response) } else { Err(response) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use axum::response::IntoResponse;
+
+    use super::*;
+
+    fn parts(cookie: &str) -> http::request::Parts {
+        http::Request::builder()
+            .header(http::header::COOKIE, cookie)
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0
+    }
+
+    async fn handle(
+        handled: &AtomicUsize,
+    ) -> Result<axum::response::Response, axum::response::Response> {
+        handled.fetch_add(1, Ordering::SeqCst);
+
+        // Give concurrent requests a chance to wait for this execution.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        Ok("Hello".into_response())
+    }
+
+    #[tokio::test]
+    async fn test_coalescing() {
+        let coalescing = Coalescing::default();
+        let handled = AtomicUsize::new(0);
+        let key = |cookie| {
+            coalescing.key(
+                "/".to_owned(),
+                &crate::htmx::Request::Classic,
+                &parts(cookie),
+            )
+        };
+
+        let (a, b, c) = tokio::join!(
+            coalescing.run(key("user=a"), || handle(&handled)),
+            coalescing.run(key("user=a"), || handle(&handled)),
+            coalescing.run(key("user=b"), || handle(&handled)),
+        );
+
+        for response in [a, b, c] {
+            let body = axum::body::to_bytes(response.unwrap().into_body(), usize::MAX)
+                .await
+                .unwrap();
+
+            assert_eq!(body, "Hello");
+        }
+
+        assert_eq!(handled.load(Ordering::SeqCst), 2);
+        assert_eq!(coalescing.in_flight_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unshareable_responses() {
+        let coalescing = Coalescing::default();
+        let handled = AtomicUsize::new(0);
+        let key = || coalescing.key("/".to_owned(), &crate::htmx::Request::Classic, &parts(""));
+        let handle = || async {
+            handled.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+
+            Ok(([(http::header::SET_COOKIE, "session=1")], "Hello").into_response())
+        };
+
+        let (a, b) = tokio::join!(coalescing.run(key(), handle), coalescing.run(key(), handle));
+
+        assert!(a.is_ok() && b.is_ok());
+        assert_eq!(handled.load(Ordering::SeqCst), 2);
+    }
+}
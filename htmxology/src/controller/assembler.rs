@@ -0,0 +1,609 @@
+//! Assembly of pages from several routes of a controller.
+
+use std::{future::Future, pin::Pin, task::Poll};
+
+use axum::response::IntoResponse;
+
+use super::{Controller, ControllerExt};
+use crate::htmx::{HtmlId, InsertStrategy};
+
+/// The maximum size of the body of a rendered layout or fragment.
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024; // 16 MB
+
+/// A boxed render of a route of an assembled page.
+type Render<'a> = Pin<Box<dyn Future<Output = RenderOutput> + Send + 'a>>;
+
+/// The response to a route of an assembled page, with the index of its fragment, or `None` for
+/// the layout.
+type RenderOutput = (
+    Option<usize>,
+    Result<axum::response::Response, axum::response::Response>,
+);
+
+/// A fragment of an assembled page.
+struct PageFragment<R> {
+    /// The `id` of the element the fragment fills.
+    target: String,
+
+    /// The route rendering the fragment.
+    route: R,
+
+    /// The targets of the fragments rendered before this one.
+    dependencies: Vec<String>,
+}
+
+/// Assembles a page from the responses to several routes of a controller: a layout, whose
+/// response is the main body of the page, and fragments filling the elements of the layout with
+/// the declared `id`s.
+///
+/// Routes are rendered inline (see [`ControllerExt::render_inline`]), concurrently, except for
+/// fragments declared with dependencies, which are rendered once the fragments they depend on
+/// are. For HTMX requests, the fragments are out-of-band swaps appended to the layout, while they
+/// are swapped into the layout on the server for classic requests, as HTMX ignores out-of-band
+/// swaps in full page loads.
+///
+/// The status and headers of the page are the ones of the layout response, of which the
+/// fragments only contribute their body. The first route failing to render fails the whole
+/// page with its error response.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// // In `Controller::handle_request`:
+/// AppRoute::Dashboard => {
+///     Box::pin(
+///         PageAssembler::new(AppRoute::Layout)
+///             .with_fragment(HtmlId::from_static("sidebar")?, AppRoute::Sidebar)
+///             .with_fragment(HtmlId::from_static("cart")?, AppRoute::Cart)
+///             // The recommendations are rendered once the cart is.
+///             .with_fragment_after(
+///                 HtmlId::from_static("recommendations")?,
+///                 AppRoute::Recommendations,
+///                 [HtmlId::from_static("cart")?],
+///             )
+///             .assemble(self, htmx, &parts, server_info, args),
+///     )
+///     .await
+/// }
+/// ```
+pub struct PageAssembler<R> {
+    /// The route rendering the layout.
+    layout: R,
+
+    /// The fragments filling the layout, in declaration order.
+    fragments: Vec<PageFragment<R>>,
+
+    /// How the fragments fill their target.
+    insert_strategy: InsertStrategy,
+}
+
+impl<R: std::fmt::Debug> std::fmt::Debug for PageAssembler<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PageAssembler")
+            .field("layout", &self.layout)
+            .field(
+                "fragments",
+                &self
+                    .fragments
+                    .iter()
+                    .map(|fragment| (&fragment.target, &fragment.route))
+                    .collect::<Vec<_>>(),
+            )
+            .field("insert_strategy", &self.insert_strategy)
+            .finish()
+    }
+}
+
+impl<R: crate::Route + Send> PageAssembler<R> {
+    /// Create a new assembler for a page with the specified layout route.
+    pub fn new(layout: R) -> Self {
+        Self {
+            layout,
+            fragments: Vec::new(),
+            insert_strategy: InsertStrategy::InnerHtml,
+        }
+    }
+
+    /// Fill the element of the layout with the specified `id` with the response to a route.
+    pub fn with_fragment(self, target: HtmlId, route: R) -> Self {
+        self.with_fragment_after(target, route, [])
+    }
+
+    /// Fill the element of the layout with the specified `id` with the response to a route,
+    /// rendered once the fragments filling the specified targets are.
+    ///
+    /// Assembling the page fails if a dependency is not the target of another fragment, or if
+    /// the dependencies are cyclic.
+    pub fn with_fragment_after<I>(mut self, target: HtmlId, route: R, dependencies: I) -> Self
+    where
+        I: IntoIterator<Item = HtmlId>,
+    {
+        self.fragments.push(PageFragment {
+            target: target.to_string(),
+            route,
+            dependencies: dependencies
+                .into_iter()
+                .map(|dependency| dependency.to_string())
+                .collect(),
+        });
+        self
+    }
+
+    /// Set how the fragments fill their target.
+    ///
+    /// Defaults to [`InsertStrategy::InnerHtml`], which replaces the content of the targets.
+    pub fn with_insert_strategy(mut self, insert_strategy: InsertStrategy) -> Self {
+        self.insert_strategy = insert_strategy;
+        self
+    }
+
+    /// Check that the dependencies of the fragments are targets of other fragments.
+    fn check_dependencies(&self) -> Result<(), String> {
+        for fragment in &self.fragments {
+            for dependency in &fragment.dependencies {
+                if !self
+                    .fragments
+                    .iter()
+                    .any(|other| other.target == *dependency && other.target != fragment.target)
+                {
+                    return Err(format!(
+                        "the fragment of `#{}` depends on `#{dependency}`, which is not the target of another fragment",
+                        fragment.target
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Render the routes of the page, and assemble them.
+    ///
+    /// As `handle_request` awaits itself when a controller assembles a page from its own routes,
+    /// the future must be boxed.
+    pub async fn assemble<'a, C>(
+        self,
+        controller: &'a C,
+        htmx: crate::htmx::Request,
+        parts: &'a http::request::Parts,
+        server_info: &'a crate::ServerInfo,
+        args: C::Args,
+    ) -> Result<axum::response::Response, axum::response::Response>
+    where
+        R: 'a,
+        C: Controller<
+                Route = R,
+                Response = Result<axum::response::Response, axum::response::Response>,
+            >,
+        C::Args: Clone,
+    {
+        self.check_dependencies().map_err(|err| {
+            tracing::error!("Failed to assemble page: {err}.");
+
+            http::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })?;
+
+        let render = |index: Option<usize>, route: R| -> Render<'a> {
+            let htmx = htmx.clone();
+            let args = args.clone();
+
+            Box::pin(async move {
+                match controller
+                    .render_inline(route, htmx, parts, server_info, args)
+                    .await
+                {
+                    Ok(response) => (index, response),
+                    Err(err) => (index, Err(err.into_response())),
+                }
+            })
+        };
+
+        let mut targets = Vec::with_capacity(self.fragments.len());
+        let mut pending = Vec::with_capacity(self.fragments.len());
+
+        for (index, fragment) in self.fragments.into_iter().enumerate() {
+            targets.push(fragment.target);
+            pending.push((index, fragment.route, fragment.dependencies));
+        }
+
+        let mut bodies: Vec<Option<String>> = vec![None; targets.len()];
+        let mut layout = None;
+        let mut renders = vec![render(None, self.layout)];
+
+        loop {
+            // Render the fragments whose dependencies are rendered, along with the layout on
+            // the first round.
+            let (ready, blocked) = pending.into_iter().partition::<Vec<_>, _>(
+                |(_, _, dependencies): &(usize, R, Vec<String>)| {
+                    dependencies.iter().all(|dependency| {
+                        targets
+                            .iter()
+                            .zip(&bodies)
+                            .any(|(target, body)| target == dependency && body.is_some())
+                    })
+                },
+            );
+
+            if renders.is_empty() && ready.is_empty() {
+                if !blocked.is_empty() {
+                    tracing::error!(
+                        "Failed to assemble page: the dependencies of the fragments of {} are cyclic.",
+                        blocked
+                            .iter()
+                            .map(|(index, _, _)| format!("`#{}`", targets[*index]))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+
+                    return Err(http::StatusCode::INTERNAL_SERVER_ERROR.into_response());
+                }
+
+                break;
+            }
+
+            renders.extend(
+                ready
+                    .into_iter()
+                    .map(|(index, route, _)| render(Some(index), route)),
+            );
+            pending = blocked;
+
+            for (index, response) in join_all(std::mem::take(&mut renders)).await {
+                let response = response?;
+
+                match index {
+                    Some(index) => bodies[index] = Some(read_body(response).await?.1),
+                    None => layout = Some(read_body(response).await?),
+                }
+            }
+        }
+
+        let (mut parts, mut html) = layout.expect("the layout should be rendered");
+        let is_htmx = matches!(htmx, crate::htmx::Request::Htmx { .. });
+
+        for (target, body) in targets.iter().zip(bodies) {
+            let body = body.expect("the fragments should be rendered");
+
+            if is_htmx {
+                html.push_str(&oob_swap(&body, &self.insert_strategy, target));
+            } else {
+                match crate::htmx::swap_into_element(&html, target, &self.insert_strategy, &body) {
+                    Some(swapped) => html = swapped,
+                    None => tracing::warn!(
+                        "The layout has no element with the `id` `{target}`: its fragment is discarded."
+                    ),
+                }
+            }
+        }
+
+        parts.headers.remove(http::header::CONTENT_LENGTH);
+
+        Ok(axum::response::Response::from_parts(
+            parts,
+            axum::body::Body::from(html),
+        ))
+    }
+}
+
+/// Render a fragment as an out-of-band swap into its target.
+///
+/// Fragments are wrapped in a `<template>` tag, so that their whole HTML fills the target,
+/// except when they replace it, where the attribute is injected in their root element instead.
+fn oob_swap(html: &str, insert_strategy: &InsertStrategy, target: &str) -> String {
+    let target = format!("#{target}");
+
+    if matches!(insert_strategy, InsertStrategy::OuterHtml) {
+        return crate::htmx::inject_oob_attribute(html, insert_strategy, &target);
+    }
+
+    format!(
+        r#"<template hx-swap-oob="{}">{html}</template>"#,
+        crate::htmx::escape_attribute_value(&format!("{insert_strategy}:{target}"))
+    )
+}
+
+/// Read the body of a response, as HTML.
+async fn read_body(
+    response: axum::response::Response,
+) -> Result<(http::response::Parts, String), axum::response::Response> {
+    let (parts, body) = response.into_parts();
+    let body = axum::body::to_bytes(body, MAX_BODY_SIZE)
+        .await
+        .map_err(|err| {
+            tracing::error!("Failed to read the body of a page part: {err}");
+
+            http::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })?;
+
+    Ok((parts, String::from_utf8_lossy(&body).into_owned()))
+}
+
+/// Wait for the renders of a page, polling them concurrently.
+async fn join_all(mut renders: Vec<Render<'_>>) -> Vec<RenderOutput> {
+    let mut outputs: Vec<Option<RenderOutput>> = renders.iter().map(|_| None).collect();
+
+    std::future::poll_fn(|cx| {
+        let mut done = true;
+
+        for (render, output) in renders.iter_mut().zip(&mut outputs) {
+            if output.is_none() {
+                match render.as_mut().poll(cx) {
+                    Poll::Ready(result) => *output = Some(result),
+                    Poll::Pending => done = false,
+                }
+            }
+        }
+
+        if done { Poll::Ready(()) } else { Poll::Pending }
+    })
+    .await;
+
+    outputs.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{Route, ServerInfo};
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum TestRoute {
+        Page,
+        Layout,
+        Cart,
+        Recommendations,
+        Broken,
+    }
+
+    impl std::str::FromStr for TestRoute {
+        type Err = crate::ParseError;
+
+        fn from_str(_s: &str) -> Result<Self, Self::Err> {
+            Ok(Self::Layout)
+        }
+    }
+
+    impl Route for TestRoute {
+        fn method(&self) -> http::Method {
+            http::Method::GET
+        }
+    }
+
+    impl std::fmt::Display for TestRoute {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "/{self:?}")
+        }
+    }
+
+    impl<S: Send + Sync> axum::extract::FromRequest<S> for TestRoute {
+        type Rejection = axum::response::Response;
+
+        async fn from_request(
+            _req: http::Request<axum::body::Body>,
+            _state: &S,
+        ) -> Result<Self, Self::Rejection> {
+            Ok(Self::Layout)
+        }
+    }
+
+    /// A controller recording the order in which its routes are rendered.
+    #[derive(Clone, Default)]
+    struct TestController {
+        rendered: Arc<Mutex<Vec<TestRoute>>>,
+    }
+
+    impl Controller for TestController {
+        type Route = TestRoute;
+        type Args = ();
+        type Response = Result<axum::response::Response, axum::response::Response>;
+
+        async fn handle_request(
+            &self,
+            route: Self::Route,
+            htmx: crate::htmx::Request,
+            parts: http::request::Parts,
+            server_info: &ServerInfo,
+            _args: Self::Args,
+        ) -> Self::Response {
+            self.rendered.lock().unwrap().push(route.clone());
+
+            match route {
+                TestRoute::Page => {
+                    Box::pin(assembler().assemble(self, htmx, &parts, server_info, ())).await
+                }
+                TestRoute::Layout => Ok((
+                    [(http::header::CONTENT_TYPE, "text/html")],
+                    r#"<main><p id="cart">Loading</p><aside id="recommendations"></aside></main>"#,
+                )
+                    .into_response()),
+                TestRoute::Cart => Ok("<b>3</b>".into_response()),
+                TestRoute::Recommendations => Ok("<i>Socks</i>".into_response()),
+                TestRoute::Broken => Err(http::StatusCode::BAD_GATEWAY.into_response()),
+            }
+        }
+    }
+
+    async fn assemble(
+        controller: &TestController,
+        assembler: PageAssembler<TestRoute>,
+        htmx: crate::htmx::Request,
+    ) -> (u16, String) {
+        let (parts, _) = http::Request::get("/page").body(()).unwrap().into_parts();
+        let server_info = ServerInfo {
+            base_url: "http://localhost:3000".parse().unwrap(),
+        };
+
+        let response = match assembler
+            .assemble(controller, htmx, &parts, &server_info, ())
+            .await
+        {
+            Ok(response) | Err(response) => response,
+        };
+        let status = response.status().as_u16();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn assembler() -> PageAssembler<TestRoute> {
+        PageAssembler::new(TestRoute::Layout)
+            .with_fragment_after(
+                HtmlId::from_static("recommendations").unwrap(),
+                TestRoute::Recommendations,
+                [HtmlId::from_static("cart").unwrap()],
+            )
+            .with_fragment(HtmlId::from_static("cart").unwrap(), TestRoute::Cart)
+    }
+
+    #[tokio::test]
+    async fn test_classic_request() {
+        let controller = TestController::default();
+
+        assert_eq!(
+            assemble(&controller, assembler(), crate::htmx::Request::Classic).await,
+            (
+                200,
+                r#"<main><p id="cart"><b>3</b></p><aside id="recommendations"><i>Socks</i></aside></main>"#
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            *controller.rendered.lock().unwrap(),
+            [
+                TestRoute::Layout,
+                TestRoute::Cart,
+                TestRoute::Recommendations
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_own_routes() {
+        let controller = TestController::default();
+        let (parts, _) = http::Request::get("/page").body(()).unwrap().into_parts();
+        let server_info = ServerInfo {
+            base_url: "http://localhost:3000".parse().unwrap(),
+        };
+
+        let response = controller
+            .handle_request(
+                TestRoute::Page,
+                crate::htmx::Request::Classic,
+                parts,
+                &server_info,
+                (),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            body,
+            r#"<main><p id="cart"><b>3</b></p><aside id="recommendations"><i>Socks</i></aside></main>"#
+        );
+        assert_eq!(
+            *controller.rendered.lock().unwrap(),
+            [
+                TestRoute::Page,
+                TestRoute::Layout,
+                TestRoute::Cart,
+                TestRoute::Recommendations
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_htmx_request() {
+        let controller = TestController::default();
+        let htmx = crate::htmx::Request::Htmx {
+            boosted: true,
+            current_url: "http://localhost:3000/".to_owned(),
+            history_restore_request: false,
+            prompt: String::new(),
+            target: None,
+            trigger_name: None,
+            trigger: None,
+        };
+
+        assert_eq!(
+            assemble(&controller, assembler(), htmx.clone()).await.1,
+            concat!(
+                r#"<main><p id="cart">Loading</p><aside id="recommendations"></aside></main>"#,
+                r##"<template hx-swap-oob="innerHTML:#recommendations"><i>Socks</i></template>"##,
+                r##"<template hx-swap-oob="innerHTML:#cart"><b>3</b></template>"##,
+            )
+        );
+        assert_eq!(
+            assemble(
+                &controller,
+                assembler().with_insert_strategy(InsertStrategy::OuterHtml),
+                htmx,
+            )
+            .await
+            .1,
+            concat!(
+                r#"<main><p id="cart">Loading</p><aside id="recommendations"></aside></main>"#,
+                r##"<i hx-swap-oob="outerHTML:#recommendations">Socks</i>"##,
+                r##"<b hx-swap-oob="outerHTML:#cart">3</b>"##,
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failures() {
+        let controller = TestController::default();
+
+        assert_eq!(
+            assemble(
+                &controller,
+                assembler().with_fragment(HtmlId::from_static("ads").unwrap(), TestRoute::Broken),
+                crate::htmx::Request::Classic,
+            )
+            .await
+            .0,
+            502
+        );
+
+        // Unknown and cyclic dependencies.
+        assert_eq!(
+            assemble(
+                &controller,
+                PageAssembler::new(TestRoute::Layout).with_fragment_after(
+                    HtmlId::from_static("cart").unwrap(),
+                    TestRoute::Cart,
+                    [HtmlId::from_static("sidebar").unwrap()],
+                ),
+                crate::htmx::Request::Classic,
+            )
+            .await
+            .0,
+            500
+        );
+        assert_eq!(
+            assemble(
+                &controller,
+                PageAssembler::new(TestRoute::Layout)
+                    .with_fragment_after(
+                        HtmlId::from_static("cart").unwrap(),
+                        TestRoute::Cart,
+                        [HtmlId::from_static("recommendations").unwrap()],
+                    )
+                    .with_fragment_after(
+                        HtmlId::from_static("recommendations").unwrap(),
+                        TestRoute::Recommendations,
+                        [HtmlId::from_static("cart").unwrap()],
+                    ),
+                crate::htmx::Request::Classic,
+            )
+            .await
+            .0,
+            500
+        );
+    }
+}
@@ -0,0 +1,514 @@
+//! Controller combinators.
+
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use super::Controller;
+
+/// An extension trait for controllers that provides response shaping combinators.
+///
+/// Each combinator wraps the controller into a new controller, so that responses can be shaped
+/// without editing the implementation of the wrapped controller.
+pub trait ControllerExt: Controller {
+    /// Map the responses of the controller with the specified function.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let controller = MyController.map_response(|response| {
+    ///     response.map_err(|err| render_error_page(err))
+    /// });
+    /// ```
+    fn map_response<F, R>(self, f: F) -> MapResponse<Self, F>
+    where
+        Self: Sized,
+        F: Fn(Self::Response) -> R + Send + Sync + Clone,
+        R: Send + 'static,
+    {
+        MapResponse {
+            controller: self,
+            f,
+        }
+    }
+
+    /// Add the specified headers to all the responses of the controller.
+    ///
+    /// Headers are added to both successful and error responses, unless the controller already
+    /// set them.
+    fn with_headers(self, headers: http::HeaderMap) -> WithHeaders<Self>
+    where
+        Self: Sized,
+    {
+        WithHeaders {
+            controller: self,
+            headers: Arc::new(headers),
+        }
+    }
+
+    /// Choose the representation of the responses of the controller from the `Accept` header of
+    /// the requests.
+    ///
+    /// Responses with a text alternative, set with
+    /// [`NegotiationResponseExt::with_text_alternative`](crate::NegotiationResponseExt::with_text_alternative),
+    /// are replaced by it for clients preferring plain text, and get a `Vary: Accept` header.
+    /// Other responses are left untouched.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // In the controller:
+    /// Ok(Html(counter.render()?).into_response().with_text_alternative(counter.value.to_string()))
+    ///
+    /// // When building the router:
+    /// let router = ControllerRouter::new(MyController.negotiate(), args_factory);
+    /// ```
+    fn negotiate(self) -> Negotiate<Self>
+    where
+        Self: Sized,
+    {
+        Negotiate { controller: self }
+    }
+
+    /// Render a route of the controller inline, as part of the response to a request.
+    ///
+    /// The route is handled with the parts of the request, with the method and URI of the route.
+    /// As routes rendered inline can render other routes inline, the routes being rendered are
+    /// tracked in a [`RenderStack`](super::RenderStack): rendering a route that is already being
+    /// rendered, or exceeding the depth limit of the router (see
+    /// [`ControllerRouter::with_render_depth_limit`](crate::ControllerRouter::with_render_depth_limit)),
+    /// fails with a [`RenderError`](super::RenderError), which responds with
+    /// `508 Loop Detected`.
+    ///
+    /// As `handle_request` awaits itself when a controller renders its own routes, the future must
+    /// be boxed.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let sidebar = Box::pin(self.render_inline(
+    ///     AppRoute::Sidebar,
+    ///     htmx.clone(),
+    ///     &parts,
+    ///     server_info,
+    ///     args.clone(),
+    /// ))
+    /// .await
+    /// .map_err(IntoResponse::into_response)??;
+    /// ```
+    fn render_inline(
+        &self,
+        route: Self::Route,
+        htmx: crate::htmx::Request,
+        parts: &http::request::Parts,
+        server_info: &crate::ServerInfo,
+        args: Self::Args,
+    ) -> impl Future<Output = Result<Self::Response, super::RenderError>> + Send {
+        let parts = super::inline::inline_parts(&route, parts);
+
+        async move {
+            Ok(self
+                .handle_request(route, htmx, parts?, server_info, args)
+                .await)
+        }
+    }
+
+    /// Erase the type of the controller.
+    ///
+    /// This is useful to store controllers with different types but the same route, args and
+    /// response types together, or to shorten deeply nested combinator types.
+    fn boxed(self) -> BoxController<Self::Route, Self::Args, Self::Response>
+    where
+        Self: Sized + 'static,
+    {
+        BoxController {
+            controller: Arc::new(self),
+        }
+    }
+}
+
+impl<C: Controller> ControllerExt for C {}
+
+/// A controller that maps the responses of another controller.
+///
+/// Typically created with [`ControllerExt::map_response`].
+#[derive(Debug, Clone)]
+pub struct MapResponse<C, F> {
+    controller: C,
+    f: F,
+}
+
+impl<C, F, R> Controller for MapResponse<C, F>
+where
+    C: Controller,
+    C::Route: axum::extract::FromRequest<Self>,
+    F: Fn(C::Response) -> R + Send + Sync + Clone,
+    R: Send + 'static,
+{
+    type Route = C::Route;
+    type Args = C::Args;
+    type Response = R;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        htmx: crate::htmx::Request,
+        parts: http::request::Parts,
+        server_info: &crate::ServerInfo,
+        args: Self::Args,
+    ) -> Self::Response {
+        let response = self
+            .controller
+            .handle_request(route, htmx, parts, server_info, args)
+            .await;
+
+        (self.f)(response)
+    }
+}
+
+/// A controller that adds headers to the responses of another controller.
+///
+/// Typically created with [`ControllerExt::with_headers`].
+#[derive(Debug, Clone)]
+pub struct WithHeaders<C> {
+    controller: C,
+    headers: Arc<http::HeaderMap>,
+}
+
+impl<C> Controller for WithHeaders<C>
+where
+    C: Controller<Response = Result<axum::response::Response, axum::response::Response>>,
+    C::Route: axum::extract::FromRequest<Self>,
+{
+    type Route = C::Route;
+    type Args = C::Args;
+    type Response = C::Response;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        htmx: crate::htmx::Request,
+        parts: http::request::Parts,
+        server_info: &crate::ServerInfo,
+        args: Self::Args,
+    ) -> Self::Response {
+        let add_headers = |mut response: axum::response::Response| {
+            let headers = response.headers_mut();
+
+            for name in self.headers.keys() {
+                if !headers.contains_key(name) {
+                    for value in self.headers.get_all(name) {
+                        headers.append(name, value.clone());
+                    }
+                }
+            }
+
+            response
+        };
+
+        self.controller
+            .handle_request(route, htmx, parts, server_info, args)
+            .await
+            .map(add_headers)
+            .map_err(add_headers)
+    }
+}
+
+/// A controller that chooses the representation of the responses of another controller.
+///
+/// Typically created with [`ControllerExt::negotiate`].
+#[derive(Debug, Clone)]
+pub struct Negotiate<C> {
+    controller: C,
+}
+
+impl<C> Controller for Negotiate<C>
+where
+    C: Controller<Response = Result<axum::response::Response, axum::response::Response>>,
+    C::Route: axum::extract::FromRequest<Self>,
+{
+    type Route = C::Route;
+    type Args = C::Args;
+    type Response = C::Response;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        htmx: crate::htmx::Request,
+        parts: http::request::Parts,
+        server_info: &crate::ServerInfo,
+        args: Self::Args,
+    ) -> Self::Response {
+        let representation = crate::Representation::from_headers(&parts.headers);
+        let negotiate = |response| crate::negotiation::negotiate(response, representation);
+
+        self.controller
+            .handle_request(route, htmx, parts, server_info, args)
+            .await
+            .map(negotiate)
+            .map_err(negotiate)
+    }
+}
+
+/// The object-safe counterpart of the [`Controller`] trait, used by [`BoxController`].
+trait DynController<Route, Args, Response>: Send + Sync {
+    fn handle_request<'a>(
+        &'a self,
+        route: Route,
+        htmx: crate::htmx::Request,
+        parts: http::request::Parts,
+        server_info: &'a crate::ServerInfo,
+        args: Args,
+    ) -> Pin<Box<dyn Future<Output = Response> + Send + 'a>>;
+}
+
+impl<C: Controller + 'static> DynController<C::Route, C::Args, C::Response> for C {
+    fn handle_request<'a>(
+        &'a self,
+        route: C::Route,
+        htmx: crate::htmx::Request,
+        parts: http::request::Parts,
+        server_info: &'a crate::ServerInfo,
+        args: C::Args,
+    ) -> Pin<Box<dyn Future<Output = C::Response> + Send + 'a>> {
+        Box::pin(Controller::handle_request(
+            self,
+            route,
+            htmx,
+            parts,
+            server_info,
+            args,
+        ))
+    }
+}
+
+/// A type-erased controller.
+///
+/// Typically created with [`ControllerExt::boxed`].
+pub struct BoxController<Route, Args, Response> {
+    controller: Arc<dyn DynController<Route, Args, Response>>,
+}
+
+impl<Route, Args, Response> Clone for BoxController<Route, Args, Response> {
+    fn clone(&self) -> Self {
+        Self {
+            controller: self.controller.clone(),
+        }
+    }
+}
+
+impl<Route, Args, Response> std::fmt::Debug for BoxController<Route, Args, Response> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BoxController").finish_non_exhaustive()
+    }
+}
+
+impl<Route, Args, Response> Controller for BoxController<Route, Args, Response>
+where
+    Route: crate::Route + Send + axum::extract::FromRequest<Self> + 'static,
+    Args: Send + Sync + 'static,
+    Response: Send + 'static,
+{
+    type Route = Route;
+    type Args = Args;
+    type Response = Response;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        htmx: crate::htmx::Request,
+        parts: http::request::Parts,
+        server_info: &crate::ServerInfo,
+        args: Self::Args,
+    ) -> Self::Response {
+        self.controller
+            .handle_request(route, htmx, parts, server_info, args)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NegotiationResponseExt, Route, ServerInfo};
+    use axum::response::IntoResponse;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct TestRoute;
+
+    impl std::str::FromStr for TestRoute {
+        type Err = crate::ParseError;
+
+        fn from_str(_s: &str) -> Result<Self, Self::Err> {
+            Ok(TestRoute)
+        }
+    }
+
+    impl Route for TestRoute {
+        fn method(&self) -> http::Method {
+            http::Method::GET
+        }
+    }
+
+    impl std::fmt::Display for TestRoute {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "/")
+        }
+    }
+
+    impl<S: Send + Sync> axum::extract::FromRequest<S> for TestRoute {
+        type Rejection = axum::response::Response;
+
+        async fn from_request(
+            _req: http::Request<axum::body::Body>,
+            _state: &S,
+        ) -> Result<Self, Self::Rejection> {
+            Ok(Self)
+        }
+    }
+
+    #[derive(Clone)]
+    struct TestController {
+        fail: bool,
+    }
+
+    impl Controller for TestController {
+        type Route = TestRoute;
+        type Args = ();
+        type Response = Result<axum::response::Response, axum::response::Response>;
+
+        async fn handle_request(
+            &self,
+            _route: Self::Route,
+            _htmx: crate::htmx::Request,
+            _parts: http::request::Parts,
+            _server_info: &ServerInfo,
+            _args: Self::Args,
+        ) -> Self::Response {
+            if self.fail {
+                Err((
+                    http::StatusCode::NOT_FOUND,
+                    [("x-frame-options", "SAMEORIGIN")],
+                )
+                    .into_response())
+            } else {
+                Ok("Hello".into_response())
+            }
+        }
+    }
+
+    async fn call<C: Controller<Route = TestRoute, Args = ()>>(controller: &C) -> C::Response {
+        let (parts, _) = http::Request::builder().body(()).unwrap().into_parts();
+        let server_info = ServerInfo {
+            base_url: "http://localhost:3000".parse().unwrap(),
+        };
+
+        controller
+            .handle_request(
+                TestRoute,
+                crate::htmx::Request::Classic,
+                parts,
+                &server_info,
+                (),
+            )
+            .await
+    }
+
+    fn test_headers() -> http::HeaderMap {
+        let mut headers = http::HeaderMap::new();
+        headers.insert("x-frame-options", "DENY".parse().unwrap());
+        headers.insert("x-content-type-options", "nosniff".parse().unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_map_response() {
+        let controller = TestController { fail: true }
+            .map_response(|response| response.map_err(|err| err.status().as_u16()));
+
+        assert_eq!(call(&controller).await.unwrap_err(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_with_headers() {
+        let controller = TestController { fail: false }.with_headers(test_headers());
+        let response = call(&controller).await.unwrap();
+
+        assert_eq!(response.headers()["x-frame-options"], "DENY");
+        assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+
+        // Headers set by the controller are preserved.
+        let controller = TestController { fail: true }.with_headers(test_headers());
+        let response = call(&controller).await.unwrap_err();
+
+        assert_eq!(response.headers()["x-frame-options"], "SAMEORIGIN");
+        assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+    }
+
+    #[tokio::test]
+    async fn test_negotiate() {
+        #[derive(Clone)]
+        struct CounterController;
+
+        impl Controller for CounterController {
+            type Route = TestRoute;
+            type Args = ();
+            type Response = Result<axum::response::Response, axum::response::Response>;
+
+            async fn handle_request(
+                &self,
+                _route: Self::Route,
+                _htmx: crate::htmx::Request,
+                _parts: http::request::Parts,
+                _server_info: &ServerInfo,
+                _args: Self::Args,
+            ) -> Self::Response {
+                Ok(axum::response::Html("<b>42</b>")
+                    .into_response()
+                    .with_text_alternative("42"))
+            }
+        }
+
+        let controller = CounterController.negotiate();
+        let server_info = ServerInfo {
+            base_url: "http://localhost:3000".parse().unwrap(),
+        };
+
+        for (accept, content_type) in [
+            ("text/html", "text/html; charset=utf-8"),
+            ("text/plain", "text/plain; charset=utf-8"),
+        ] {
+            let (parts, _) = http::Request::builder()
+                .header(http::header::ACCEPT, accept)
+                .body(())
+                .unwrap()
+                .into_parts();
+            let response = Controller::handle_request(
+                &controller,
+                TestRoute,
+                crate::htmx::Request::Classic,
+                parts,
+                &server_info,
+                (),
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(response.headers()[http::header::CONTENT_TYPE], content_type);
+            assert_eq!(response.headers()[http::header::VARY], "Accept");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_boxed() {
+        let controllers = [
+            TestController { fail: false }.boxed(),
+            TestController { fail: false }
+                .with_headers(test_headers())
+                .boxed(),
+        ];
+
+        for controller in &controllers {
+            let response = call(controller).await.unwrap();
+            assert_eq!(response.status(), http::StatusCode::OK);
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! Adapters from plain axum handlers to controllers.
+
+use std::{fmt::Display, marker::PhantomData, str::FromStr};
+
+use axum::response::IntoResponse;
+
+use super::Controller;
+use crate::{ParseError, Route, ServerInfo, htmx};
+
+/// A controller serving requests with plain axum handlers, to migrate an axum application to
+/// typed controllers one endpoint at a time.
+///
+/// The adapter is a leaf of the controller tree, whose route is a [`HandlerRoute`] matching any
+/// request. Mounted as a subcontroller without a path, it catches the requests that no other
+/// subcontroller matches, so the handlers not migrated yet keep serving them under the same
+/// server:
+///
+/// ```rust,ignore
+/// #[derive(Clone, RoutingController)]
+/// #[controller(AppRoute)]
+/// #[subcontroller(BlogController, route = Blog, path = "blog/")]
+/// #[subcontroller(HandlerController, route = Legacy, convert_with = "Self::legacy")]
+/// struct AppController {
+///     legacy: HandlerController,
+/// }
+///
+/// impl AppController {
+///     fn legacy(&self) -> HandlerController {
+///         self.legacy.clone()
+///     }
+/// }
+///
+/// let legacy = axum::Router::new()
+///     .route("/users/{id}", get(get_user))
+///     .route("/users", post(create_user));
+/// let controller = AppController {
+///     legacy: HandlerController::from_router(legacy),
+/// };
+/// ```
+///
+/// Handlers get the original request, with its full path, so that their routes don't change.
+/// As routes are parsed before they are handled, the request body is buffered, up to the
+/// default body limit of axum. The `Args` of the parent controller are ignored, and the routes
+/// of the handlers don't appear in the route table.
+pub struct HandlerController<A = ()> {
+    /// The router dispatching requests to the handlers.
+    router: axum::Router,
+
+    /// The arguments of the parent controller, which the handlers don't take.
+    args: PhantomData<fn(A)>,
+}
+
+impl<A> Clone for HandlerController<A> {
+    fn clone(&self) -> Self {
+        Self {
+            router: self.router.clone(),
+            args: PhantomData,
+        }
+    }
+}
+
+impl<A> std::fmt::Debug for HandlerController<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HandlerController")
+            .field("router", &self.router)
+            .finish()
+    }
+}
+
+impl<A> HandlerController<A> {
+    /// Serve all the requests with a handler.
+    pub fn new<H, T>(handler: H) -> Self
+    where
+        H: axum::handler::Handler<T, ()>,
+        T: 'static,
+    {
+        Self::from_router(axum::Router::new().fallback(handler))
+    }
+
+    /// Serve all the requests with a handler taking a state, as with
+    /// [`axum::Router::with_state`].
+    pub fn with_state<H, T, S>(handler: H, state: S) -> Self
+    where
+        H: axum::handler::Handler<T, S>,
+        T: 'static,
+        S: Clone + Send + Sync + 'static,
+    {
+        Self::from_router(axum::Router::new().fallback(handler).with_state(state))
+    }
+
+    /// Serve the requests with the routes of an existing axum router.
+    ///
+    /// Requests that match none of its routes get its fallback response, `404 Not Found` by
+    /// default.
+    pub fn from_router(router: axum::Router) -> Self {
+        Self {
+            router,
+            args: PhantomData,
+        }
+    }
+}
+
+impl<A: Send + Sync + 'static> Controller for HandlerController<A> {
+    type Route = HandlerRoute;
+    type Args = A;
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    fn handle_request(
+        &self,
+        route: Self::Route,
+        _htmx: htmx::Request,
+        parts: http::request::Parts,
+        _server_info: &ServerInfo,
+        _args: Self::Args,
+    ) -> impl Future<Output = Self::Response> + Send {
+        let request = axum::extract::Request::from_parts(parts, axum::body::Body::from(route.body));
+        let response = tower_service::Service::call(&mut self.router.clone(), request);
+
+        async move {
+            match response.await {
+                Ok(response) => Ok(response),
+                Err(err) => match err {},
+            }
+        }
+    }
+}
+
+/// The route of a [`HandlerController`], matching any request.
+///
+/// It holds the method and URL of the request, relative to the mount point of the controller,
+/// and its buffered body.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HandlerRoute {
+    method: http::Method,
+    url: String,
+    body: axum::body::Bytes,
+}
+
+impl HandlerRoute {
+    /// Create a route, without a body.
+    ///
+    /// The URL is a path, with an optional query string.
+    pub fn new(method: http::Method, url: impl Into<String>) -> Self {
+        Self {
+            method,
+            url: url.into(),
+            body: axum::body::Bytes::new(),
+        }
+    }
+
+    /// Get the body of the request.
+    pub fn body(&self) -> &axum::body::Bytes {
+        &self.body
+    }
+}
+
+impl Display for HandlerRoute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.url)
+    }
+}
+
+impl FromStr for HandlerRoute {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.starts_with('/') {
+            return Err(ParseError::NoMatchingRoute {
+                url: s.to_owned(),
+                method: http::Method::GET.to_string(),
+                suggestions: Vec::new(),
+            });
+        }
+
+        Ok(Self::new(http::Method::GET, s))
+    }
+}
+
+impl Route for HandlerRoute {
+    fn method(&self) -> http::Method {
+        self.method.clone()
+    }
+
+    fn from_uri(uri: &http::Uri, method: &http::Method) -> Result<Self, ParseError> {
+        let url = uri
+            .path_and_query()
+            .map_or("/", http::uri::PathAndQuery::as_str);
+
+        Ok(Self::new(method.clone(), url))
+    }
+}
+
+impl<S: Send + Sync> axum::extract::FromRequest<S> for HandlerRoute {
+    type Rejection = axum::response::Response;
+
+    async fn from_request(req: axum::extract::Request, state: &S) -> Result<Self, Self::Rejection> {
+        let method = req.method().clone();
+        let url = req
+            .uri()
+            .path_and_query()
+            .map_or("/", http::uri::PathAndQuery::as_str)
+            .to_owned();
+        let body = <axum::body::Bytes as axum::extract::FromRequest<S>>::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        Ok(Self { method, url, body })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_service::Service as _;
+
+    use super::*;
+
+    async fn call(
+        controller: HandlerController,
+        request: http::request::Builder,
+        body: &'static str,
+    ) -> (http::StatusCode, String) {
+        let mut service =
+            crate::ControllerRouter::new(controller, |_| async {}).into_service(ServerInfo {
+                base_url: http::Uri::from_static("http://localhost"),
+            });
+        let response = service
+            .call(request.body(axum::body::Body::from(body)).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn test_route() {
+        let route: HandlerRoute = "/users?page=2".parse().unwrap();
+
+        assert_eq!(route.method(), http::Method::GET);
+        assert_eq!(route.to_string(), "/users?page=2");
+        assert_eq!(route.path(), "/users");
+        assert!("users".parse::<HandlerRoute>().is_err());
+
+        let route =
+            HandlerRoute::from_uri(&http::Uri::from_static("/users"), &http::Method::DELETE)
+                .unwrap();
+
+        assert_eq!(route.method(), http::Method::DELETE);
+    }
+
+    #[tokio::test]
+    async fn test_handler() {
+        let controller = HandlerController::new(|uri: http::Uri, body: String| async move {
+            format!("{uri} {body}")
+        });
+
+        assert_eq!(
+            call(controller, http::Request::post("/users?page=2"), "hello").await,
+            (http::StatusCode::OK, "/users?page=2 hello".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_with_state() {
+        let controller = HandlerController::with_state(
+            |axum::extract::State(name): axum::extract::State<&'static str>| async move { name },
+            "legacy",
+        );
+
+        assert_eq!(
+            call(controller, http::Request::get("/"), "").await,
+            (http::StatusCode::OK, "legacy".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_from_router() {
+        let controller =
+            HandlerController::from_router(axum::Router::new().route(
+                "/users/{id}",
+                axum::routing::get(
+                    |axum::extract::Path(id): axum::extract::Path<u32>| async move {
+                        format!("user {id}")
+                    },
+                ),
+            ));
+
+        assert_eq!(
+            call(controller.clone(), http::Request::get("/users/3"), "").await,
+            (http::StatusCode::OK, "user 3".to_owned())
+        );
+        assert_eq!(
+            call(controller.clone(), http::Request::post("/users/3"), "")
+                .await
+                .0,
+            http::StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert_eq!(
+            call(controller, http::Request::get("/posts"), "").await.0,
+            http::StatusCode::NOT_FOUND
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Inline rendering of routes, with cycle detection and a depth limit.
+
+use axum::response::IntoResponse;
+
+use crate::Route;
+
+/// The default maximum number of nested inline renders of a request.
+pub const DEFAULT_RENDER_DEPTH_LIMIT: usize = 8;
+
+/// The routes rendered inline by a request, from the request itself to the innermost inline
+/// render.
+///
+/// The stack is stored in the extensions of the request parts passed to controllers by
+/// [`ControllerExt::render_inline`](super::ControllerExt::render_inline), so that a route that
+/// renders itself, directly or through other routes, is detected instead of recursing forever.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderStack {
+    /// The routes being rendered, as `METHOD /path?query`.
+    routes: Vec<String>,
+
+    /// The maximum number of nested inline renders.
+    depth_limit: usize,
+}
+
+/// The maximum number of nested inline renders of the requests, set by
+/// [`ControllerRouter::with_render_depth_limit`](crate::ControllerRouter::with_render_depth_limit).
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RenderDepthLimit(pub(crate) usize);
+
+/// An error that can occur when rendering a route inline.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RenderError {
+    /// The route is already being rendered by the request.
+    #[error("render cycle detected: {}", .routes.join(" -> "))]
+    Cycle {
+        /// The routes being rendered, ending with the route rendered again.
+        routes: Vec<String>,
+    },
+
+    /// The route would exceed the maximum number of nested inline renders.
+    #[error("render depth limit of {limit} exceeded: {}", .routes.join(" -> "))]
+    DepthLimitExceeded {
+        /// The maximum number of nested inline renders.
+        limit: usize,
+
+        /// The routes being rendered, ending with the route that exceeded the limit.
+        routes: Vec<String>,
+    },
+}
+
+impl IntoResponse for RenderError {
+    /// Respond with `508 Loop Detected`, describing the routes being rendered.
+    fn into_response(self) -> axum::response::Response {
+        (http::StatusCode::LOOP_DETECTED, self.to_string()).into_response()
+    }
+}
+
+impl RenderStack {
+    /// Get the render stack of a request, starting a new one if the request is not rendered
+    /// inline.
+    pub fn of(parts: &http::request::Parts) -> Self {
+        if let Some(stack) = parts.extensions.get::<Self>() {
+            return stack.clone();
+        }
+
+        let path_and_query = parts
+            .uri
+            .path_and_query()
+            .map(|path_and_query| path_and_query.as_str())
+            .unwrap_or("/");
+        let depth_limit = parts
+            .extensions
+            .get::<RenderDepthLimit>()
+            .map_or(DEFAULT_RENDER_DEPTH_LIMIT, |limit| limit.0);
+
+        Self {
+            routes: vec![format!("{} {path_and_query}", parts.method)],
+            depth_limit,
+        }
+    }
+
+    /// Get the routes being rendered, as `METHOD /path?query`, from the request to the innermost
+    /// inline render.
+    pub fn routes(&self) -> &[String] {
+        &self.routes
+    }
+
+    /// Get the number of nested inline renders.
+    pub fn depth(&self) -> usize {
+        self.routes.len() - 1
+    }
+
+    /// Get the maximum number of nested inline renders.
+    pub fn depth_limit(&self) -> usize {
+        self.depth_limit
+    }
+
+    /// Push a route on the stack, unless it is already being rendered or the depth limit is
+    /// reached.
+    fn push(mut self, route: String) -> Result<Self, RenderError> {
+        let is_cycle = self.routes.contains(&route);
+        let depth_limit = self.depth_limit;
+
+        self.routes.push(route);
+
+        if is_cycle {
+            Err(RenderError::Cycle {
+                routes: self.routes,
+            })
+        } else if self.depth() > depth_limit {
+            Err(RenderError::DepthLimitExceeded {
+                limit: depth_limit,
+                routes: self.routes,
+            })
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+/// Build the request parts of a route rendered inline in a request.
+///
+/// The parts are the ones of the request, with the method and URI of the route, and the render
+/// stack of the request with the route pushed on it.
+pub(crate) fn inline_parts(
+    route: &impl Route,
+    parts: &http::request::Parts,
+) -> Result<http::request::Parts, RenderError> {
+    let path_and_query = match route.query() {
+        Some(query) => format!("{}?{query}", route.path()),
+        None => route.path(),
+    };
+    let method = route.method();
+    let stack = RenderStack::of(parts)
+        .push(format!("{method} {path_and_query}"))
+        .inspect_err(|err| tracing::error!("Failed to render route inline: {err}."))?;
+
+    let mut parts = parts.clone();
+
+    if let Ok(uri) = path_and_query.parse() {
+        parts.uri = uri;
+    }
+
+    parts.method = method;
+    parts.extensions.insert(stack);
+
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(uri: &str) -> http::request::Parts {
+        http::Request::get(uri).body(()).unwrap().into_parts().0
+    }
+
+    #[test]
+    fn test_push() {
+        let stack = RenderStack::of(&parts("/page?tab=1"))
+            .push("GET /sidebar".to_owned())
+            .unwrap()
+            .push("GET /widget".to_owned())
+            .unwrap();
+
+        assert_eq!(
+            stack.routes(),
+            ["GET /page?tab=1", "GET /sidebar", "GET /widget"]
+        );
+        assert_eq!(stack.depth(), 2);
+
+        assert_eq!(
+            stack.clone().push("GET /sidebar".to_owned()),
+            Err(RenderError::Cycle {
+                routes: vec![
+                    "GET /page?tab=1".to_owned(),
+                    "GET /sidebar".to_owned(),
+                    "GET /widget".to_owned(),
+                    "GET /sidebar".to_owned(),
+                ],
+            })
+        );
+        assert_eq!(
+            stack
+                .push("GET /page?tab=1".to_owned())
+                .unwrap_err()
+                .to_string(),
+            "render cycle detected: GET /page?tab=1 -> GET /sidebar -> GET /widget -> GET /page?tab=1"
+        );
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let mut parts = parts("/");
+        parts.extensions.insert(RenderDepthLimit(1));
+
+        let stack = RenderStack::of(&parts).push("GET /a".to_owned()).unwrap();
+
+        assert_eq!(stack.depth_limit(), 1);
+        assert_eq!(
+            stack.push("GET /b".to_owned()).unwrap_err().to_string(),
+            "render depth limit of 1 exceeded: GET / -> GET /a -> GET /b"
+        );
+    }
+}
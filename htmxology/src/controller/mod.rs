@@ -2,6 +2,17 @@
 
 use std::future::Future;
 
+mod assembler;
+mod combinators;
+mod handler;
+mod inline;
+
+pub use assembler::PageAssembler;
+pub use combinators::{BoxController, ControllerExt, MapResponse, Negotiate, WithHeaders};
+pub use handler::{HandlerController, HandlerRoute};
+pub(crate) use inline::RenderDepthLimit;
+pub use inline::{DEFAULT_RENDER_DEPTH_LIMIT, RenderError, RenderStack};
+
 /// The controller trait is responsible for rendering views in an application, based on a given
 /// route and any associated model.
 ///
@@ -70,8 +81,9 @@ pub trait Controller: Send + Sync + Clone {
     ///
     /// This type represents transient data that flows through the controller hierarchy,
     /// such as user sessions, database connections, or other request-scoped state.
-    /// Args are created fresh for each request via the `args_factory` function and
-    /// passed by value to `handle_request`.
+    /// Args are created fresh for each request via the `args_factory` function, which can
+    /// receive the request parts with [`ControllerRouter::new_with_parts`](crate::ControllerRouter::new_with_parts),
+    /// and passed by value to `handle_request`.
     ///
     /// For controllers that don't require such data, set this to `()`.
     /// For controllers needing shared context, use a struct type like `AppContext`.
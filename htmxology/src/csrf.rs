@@ -0,0 +1,583 @@
+//! Cross-site request forgery (CSRF) protection.
+//!
+//! CSRF protection is enabled on a router with
+//! [`ControllerRouter::with_csrf_protection`](crate::ControllerRouter::with_csrf_protection),
+//! which gives each client a random secret in a cookie, and rejects requests with an unsafe
+//! method (like `POST` or `DELETE`) that don't prove they know it with a [`CsrfToken`].
+//!
+//! Tokens are signed with a secret key, and are sent either as a field of a URL-encoded or
+//! multipart form, which
+//! [`HtmlForm::with_csrf_token`](crate::htmx::HtmlForm::with_csrf_token) renders as a hidden
+//! input, or as a header, which [`CsrfToken::hx_headers_attribute`] sets on all the HTMX requests
+//! of an element and its children:
+//!
+//! ```rust,ignore
+//! let router = ControllerRouter::from(controller)
+//!     .with_csrf_protection(Csrf::new(secret_key));
+//!
+//! // In `Controller::handle_request`:
+//! let token = CsrfToken::from_parts(&parts).expect("CSRF protection should be enabled");
+//!
+//! let body = format!(
+//!     "<body {}>{}</body>",
+//!     token.hx_headers_attribute(),
+//!     form.with_csrf_token(&token),
+//! );
+//! ```
+
+use std::{fmt::Display, sync::Arc};
+
+use axum::response::IntoResponse;
+use base64::Engine;
+use hmac::Mac;
+
+use crate::htmx::{FormInput, HtmlName};
+
+/// The default name of the CSRF cookie.
+pub const DEFAULT_COOKIE_NAME: &str = "htmxology_csrf";
+
+/// The default name of the form field holding the CSRF token.
+pub const DEFAULT_FIELD_NAME: &str = "csrf_token";
+
+/// The default name of the header holding the CSRF token.
+pub const DEFAULT_HEADER_NAME: &str = "x-csrf-token";
+
+/// The default maximum size of a form body inspected for a CSRF token.
+const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024; // 1 MB
+
+type HmacSha256 = hmac::Hmac<sha2::Sha256>;
+
+/// The CSRF protection configuration of a router.
+#[derive(Clone)]
+pub struct Csrf {
+    key: Vec<u8>,
+    cookie_name: String,
+    field_name: HtmlName,
+    header_name: http::HeaderName,
+    secure: bool,
+    max_body_size: usize,
+}
+
+impl std::fmt::Debug for Csrf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Csrf")
+            .field("cookie_name", &self.cookie_name)
+            .field("field_name", &self.field_name.to_string())
+            .field("header_name", &self.header_name)
+            .field("secure", &self.secure)
+            .field("max_body_size", &self.max_body_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Csrf {
+    /// Create a CSRF protection signing tokens with the specified secret key.
+    ///
+    /// The CSRF cookie is named [`DEFAULT_COOKIE_NAME`], and is `HttpOnly`, `Secure` and
+    /// `SameSite=Lax`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is shorter than 32 bytes.
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        let key = key.into();
+
+        assert!(
+            key.len() >= 32,
+            "CSRF signing keys should be at least 32 bytes long"
+        );
+
+        Self {
+            key,
+            cookie_name: DEFAULT_COOKIE_NAME.to_owned(),
+            field_name: HtmlName::from_static(DEFAULT_FIELD_NAME)
+                .expect("default CSRF field name should be valid"),
+            header_name: http::HeaderName::from_static(DEFAULT_HEADER_NAME),
+            secure: true,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    /// Set the name of the CSRF cookie.
+    pub fn with_cookie_name(mut self, cookie_name: impl Into<String>) -> Self {
+        self.cookie_name = cookie_name.into();
+        self
+    }
+
+    /// Set the name of the form field holding the CSRF token.
+    pub fn with_field_name(mut self, field_name: HtmlName) -> Self {
+        self.field_name = field_name;
+        self
+    }
+
+    /// Set the name of the header holding the CSRF token.
+    pub fn with_header_name(mut self, header_name: http::HeaderName) -> Self {
+        self.header_name = header_name;
+        self
+    }
+
+    /// Set whether the CSRF cookie is only sent over HTTPS.
+    ///
+    /// Defaults to `true`.
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Apply the cookie settings of a profile.
+    pub fn with_profile(self, settings: &crate::ProfileSettings) -> Self {
+        self.with_secure(settings.secure_cookies)
+    }
+
+    /// Set the maximum size of the form bodies inspected for a CSRF token.
+    ///
+    /// Requests with larger form bodies must send their token as a header, which is best for
+    /// multipart forms uploading large files.
+    pub fn with_max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Apply the CSRF protection middleware to a router.
+    pub(crate) fn apply(self, router: axum::Router) -> axum::Router {
+        router.layer(axum::middleware::from_fn_with_state(
+            Arc::new(self),
+            middleware,
+        ))
+    }
+
+    fn mac(&self, secret: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC keys can have any size");
+        mac.update(secret.as_bytes());
+        mac
+    }
+
+    /// Get the token matching a secret.
+    fn sign(&self, secret: &str) -> String {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .encode(self.mac(secret).finalize().into_bytes())
+    }
+
+    /// Check whether a token matches a secret, in constant time.
+    fn verify(&self, secret: &str, token: &str) -> bool {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(token)
+            .is_ok_and(|signature| self.mac(secret).verify_slice(&signature).is_ok())
+    }
+
+    /// Get the secret of the CSRF cookie of a request.
+    fn request_secret<'h>(&self, headers: &'h http::HeaderMap) -> Option<&'h str> {
+        headers
+            .get_all(http::header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .filter_map(|cookie| cookie.trim().split_once('='))
+            .find(|(name, _)| *name == self.cookie_name)
+            .map(|(_, value)| value)
+            .filter(|value| !value.is_empty() && value.bytes().all(|b| b.is_ascii_hexdigit()))
+    }
+
+    /// Build a `Set-Cookie` header value for the CSRF cookie.
+    fn set_cookie(&self, secret: &str) -> Option<http::HeaderValue> {
+        let mut cookie = format!(
+            "{}={secret}; Path=/; HttpOnly; SameSite=Lax",
+            self.cookie_name
+        );
+
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+
+        http::HeaderValue::from_str(&cookie)
+            .inspect_err(|err| tracing::error!("Invalid CSRF cookie: {err}"))
+            .ok()
+    }
+
+    /// Find the token of a request, in its headers or its form body, either URL-encoded or
+    /// multipart.
+    ///
+    /// Returns the request, with its body restored, and the token.
+    async fn request_token(
+        &self,
+        request: axum::extract::Request,
+    ) -> Result<(axum::extract::Request, Option<String>), axum::response::Response> {
+        if let Some(token) = request
+            .headers()
+            .get(&self.header_name)
+            .and_then(|value| value.to_str().ok())
+        {
+            let token = token.to_owned();
+
+            return Ok((request, Some(token)));
+        }
+
+        let Some(media_type) = crate::MediaType::from_content_type(request.headers()) else {
+            return Ok((request, None));
+        };
+        let boundary = match (media_type.type_(), media_type.subtype()) {
+            ("application", "x-www-form-urlencoded") => None,
+            ("multipart", "form-data") => match media_type.param("boundary") {
+                Some(boundary) => Some(boundary.to_owned()),
+                None => return Ok((request, None)),
+            },
+            _ => return Ok((request, None)),
+        };
+
+        let (parts, body) = request.into_parts();
+        let body = axum::body::to_bytes(body, self.max_body_size)
+            .await
+            .map_err(|err| {
+                tracing::warn!("Failed to read form body for CSRF verification: {err}");
+
+                http::StatusCode::PAYLOAD_TOO_LARGE.into_response()
+            })?;
+
+        let field_name = self.field_name.to_string();
+        let token = match boundary {
+            Some(boundary) => crate::route::find_text_field(&body, &boundary, &field_name),
+            None => body
+                .split(|b| *b == b'&')
+                .filter_map(|pair| {
+                    let pair = std::str::from_utf8(pair).ok()?;
+                    let (name, value) = pair.split_once('=')?;
+
+                    (decode_form_component(name) == field_name)
+                        .then(|| decode_form_component(value))
+                })
+                .next(),
+        };
+
+        Ok((
+            axum::extract::Request::from_parts(parts, axum::body::Body::from(body)),
+            token,
+        ))
+    }
+}
+
+/// Decode a component of an `application/x-www-form-urlencoded` body.
+fn decode_form_component(component: &str) -> String {
+    percent_encoding::percent_decode_str(&component.replace('+', " "))
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+/// Check whether a method is safe, and doesn't need CSRF protection.
+fn is_safe_method(method: &http::Method) -> bool {
+    matches!(
+        *method,
+        http::Method::GET | http::Method::HEAD | http::Method::OPTIONS | http::Method::TRACE
+    )
+}
+
+/// The CSRF token of a request.
+///
+/// Requests with an unsafe method must send it, either as a form field or as a header.
+#[derive(Clone)]
+pub struct CsrfToken {
+    value: String,
+    field_name: HtmlName,
+    header_name: http::HeaderName,
+}
+
+impl std::fmt::Debug for CsrfToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CsrfToken")
+            .field("value", &self.value)
+            .field("field_name", &self.field_name.to_string())
+            .field("header_name", &self.header_name)
+            .finish()
+    }
+}
+
+impl CsrfToken {
+    /// Get the CSRF token of a request from its parts.
+    ///
+    /// Returns `None` if CSRF protection is not enabled on the router.
+    pub fn from_parts(parts: &http::request::Parts) -> Option<Self> {
+        parts.extensions.get().cloned()
+    }
+
+    /// Get the value of the token.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Get a hidden `<input>` element holding the token, for forms.
+    pub fn input(&self) -> FormInput {
+        FormInput::new(self.field_name.clone())
+            .with_input_type("hidden")
+            .with_value(self.value.clone())
+    }
+
+    /// Get a `hx-headers` attribute declaration sending the token as a header.
+    ///
+    /// Set on the `<body>` element, it applies to all the HTMX requests of the page, including
+    /// the ones without a form, like `hx-delete` buttons.
+    pub fn hx_headers_attribute(&self) -> String {
+        // Tokens are base64url-encoded, and need no escaping.
+        format!(
+            r#"hx-headers='{{"{}":"{}"}}'"#,
+            self.header_name, self.value
+        )
+    }
+}
+
+impl Display for CsrfToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.value)
+    }
+}
+
+impl<S: Send + Sync> axum::extract::FromRequestParts<S> for CsrfToken {
+    type Rejection = (http::StatusCode, &'static str);
+
+    async fn from_request_parts(
+        parts: &mut http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        Self::from_parts(parts).ok_or((
+            http::StatusCode::INTERNAL_SERVER_ERROR,
+            "CSRF protection is not enabled on the router",
+        ))
+    }
+}
+
+/// A form rendered with a hidden CSRF token input.
+///
+/// See [`HtmlForm::with_csrf_token`](crate::htmx::HtmlForm::with_csrf_token).
+#[derive(Debug, Clone, Copy)]
+pub struct CsrfForm<'f, F> {
+    pub(crate) form: &'f F,
+    pub(crate) token: &'f CsrfToken,
+}
+
+impl<F: Display> Display for CsrfForm<'_, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let html = self.form.to_string();
+
+        // Insert the input right after the opening `<form>` tag, if any.
+        let offset = html
+            .find("<form")
+            .and_then(|start| html[start..].find('>').map(|end| start + end + 1));
+
+        match offset {
+            Some(offset) => write!(
+                f,
+                "{}{}{}",
+                &html[..offset],
+                self.token.input(),
+                &html[offset..]
+            ),
+            None => write!(f, "{html}{}", self.token.input()),
+        }
+    }
+}
+
+/// Verify the CSRF token of a request, and give new clients a CSRF cookie.
+async fn middleware(
+    axum::extract::State(csrf): axum::extract::State<Arc<Csrf>>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let secret = csrf
+        .request_secret(request.headers())
+        .map(ToOwned::to_owned);
+    let is_new = secret.is_none();
+    let secret = secret.unwrap_or_else(|| {
+        format!(
+            "{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        )
+    });
+
+    let mut request = if is_safe_method(request.method()) {
+        request
+    } else {
+        let (request, token) = match csrf.request_token(request).await {
+            Ok(result) => result,
+            Err(response) => return response,
+        };
+
+        // Clients without a CSRF cookie can't have a valid token.
+        if is_new || !token.is_some_and(|token| csrf.verify(&secret, &token)) {
+            tracing::warn!(
+                "Rejected a `{}` request to `{}` without a valid CSRF token",
+                request.method(),
+                request.uri().path()
+            );
+
+            return (http::StatusCode::FORBIDDEN, "invalid CSRF token").into_response();
+        }
+
+        request
+    };
+
+    request.extensions_mut().insert(CsrfToken {
+        value: csrf.sign(&secret),
+        field_name: csrf.field_name.clone(),
+        header_name: csrf.header_name.clone(),
+    });
+
+    let mut response = next.run(request).await;
+
+    if is_new && let Some(set_cookie) = csrf.set_cookie(&secret) {
+        response
+            .headers_mut()
+            .append(http::header::SET_COOKIE, set_cookie);
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_service::Service;
+
+    use super::*;
+
+    fn router() -> axum::Router {
+        let router = axum::Router::new().route(
+            "/",
+            axum::routing::get(|token: CsrfToken| async move { token.to_string() })
+                .post(|| async { "Saved" }),
+        );
+
+        Csrf::new([7; 32]).apply(router)
+    }
+
+    async fn call(
+        router: &mut axum::Router,
+        request: http::request::Builder,
+        body: impl Into<axum::body::Body>,
+    ) -> (http::StatusCode, Option<String>, String) {
+        let response = router
+            .call(request.uri("/").body(body.into()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let set_cookie = response
+            .headers()
+            .get(http::header::SET_COOKIE)
+            .map(|value| value.to_str().unwrap().to_owned());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        (
+            status,
+            set_cookie,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_verification() {
+        let mut router = router();
+
+        let (_, set_cookie, token) = call(&mut router, http::Request::get("/"), "").await;
+        let set_cookie = set_cookie.unwrap();
+        let cookie = set_cookie.split(';').next().unwrap();
+
+        // Unsafe requests without a valid token are rejected.
+        for (request, body) in [
+            (http::Request::post("/"), ""),
+            (
+                http::Request::post("/").header(http::header::COOKIE, cookie),
+                "",
+            ),
+            (
+                http::Request::post("/")
+                    .header(http::header::COOKIE, cookie)
+                    .header(DEFAULT_HEADER_NAME, "invalid"),
+                "",
+            ),
+        ] {
+            assert_eq!(
+                call(&mut router, request, body).await.0,
+                http::StatusCode::FORBIDDEN
+            );
+        }
+
+        // The token is accepted as a header or as a form field.
+        let (status, _, body) = call(
+            &mut router,
+            http::Request::post("/")
+                .header(http::header::COOKIE, cookie)
+                .header(DEFAULT_HEADER_NAME, &token),
+            "",
+        )
+        .await;
+
+        assert_eq!((status, body.as_str()), (http::StatusCode::OK, "Saved"));
+
+        let body = format!("name=alice&csrf_token={token}");
+        let (status, set_cookie, _) = call(
+            &mut router,
+            http::Request::post("/")
+                .header(http::header::COOKIE, cookie)
+                .header(
+                    http::header::CONTENT_TYPE,
+                    "application/x-www-form-urlencoded",
+                ),
+            body,
+        )
+        .await;
+
+        assert_eq!((status, set_cookie), (http::StatusCode::OK, None));
+
+        // The token is also accepted as a text field of a multipart form, but not as a file.
+        let multipart = |token: &str| {
+            format!(
+                "--XyZ\r\n\
+                Content-Disposition: form-data; name=\"csrf_token\"; filename=\"token.txt\"\r\n\
+                Content-Type: text/plain\r\n\r\n\
+                invalid\r\n--XyZ\r\n\
+                Content-Disposition: form-data; name=\"csrf_token\"\r\n\r\n\
+                {token}\r\n--XyZ--\r\n"
+            )
+        };
+
+        for (body, expected) in [
+            (multipart(&token), http::StatusCode::OK),
+            (multipart("invalid"), http::StatusCode::FORBIDDEN),
+        ] {
+            let (status, _, _) = call(
+                &mut router,
+                http::Request::post("/")
+                    .header(http::header::COOKIE, cookie)
+                    .header(
+                        http::header::CONTENT_TYPE,
+                        "multipart/form-data; boundary=XyZ",
+                    ),
+                body,
+            )
+            .await;
+
+            assert_eq!(status, expected);
+        }
+    }
+
+    #[test]
+    fn test_csrf_form() {
+        let token = CsrfToken {
+            value: "abc".to_owned(),
+            field_name: HtmlName::from_static(DEFAULT_FIELD_NAME).unwrap(),
+            header_name: http::HeaderName::from_static(DEFAULT_HEADER_NAME),
+        };
+
+        assert_eq!(
+            CsrfForm {
+                form: &r#"<form hx-post="/"><input type="text" name="name"></form>"#,
+                token: &token,
+            }
+            .to_string(),
+            r#"<form hx-post="/"><input type="hidden" name="csrf_token" value="abc"><input type="text" name="name"></form>"#
+        );
+        assert_eq!(
+            token.hx_headers_attribute(),
+            r#"hx-headers='{"x-csrf-token":"abc"}'"#
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Rendering of error responses as HTML pages and fragments.
+
+use std::future::Future;
+
+use axum::extract::FromRequestParts as _;
+
+/// A renderer of the error responses of controllers, as full HTML pages for classic requests and
+/// as HTML fragments for htmx requests.
+///
+/// Renderers are used by [`ControllerRouter::with_error_renderer`](crate::ControllerRouter::with_error_renderer).
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[derive(Clone)]
+/// struct Errors;
+///
+/// impl ErrorRenderer for Errors {
+///     async fn render_page(
+///         &self,
+///         status: http::StatusCode,
+///         _parts: &http::request::Parts,
+///     ) -> axum::response::Response {
+///         ErrorPage::new(status).render_into_response()
+///     }
+///
+///     async fn render_fragment(
+///         &self,
+///         status: http::StatusCode,
+///         _htmx: &htmx::Request,
+///         _parts: &http::request::Parts,
+///     ) -> axum::response::Response {
+///         htmx::Response::new(ErrorBanner::new(status))
+///             .with_retarget("#errors")
+///             .into_response()
+///     }
+/// }
+/// ```
+pub trait ErrorRenderer: Send + Sync + 'static {
+    /// Render the full HTML page of an error, for a classic request.
+    fn render_page(
+        &self,
+        status: http::StatusCode,
+        parts: &http::request::Parts,
+    ) -> impl Future<Output = axum::response::Response> + Send;
+
+    /// Render the HTML fragment of an error, for an htmx request.
+    ///
+    /// Note that htmx doesn't swap error responses by default: the fragment should be swapped
+    /// out-of-band, or the `responseHandling` configuration of htmx changed.
+    fn render_fragment(
+        &self,
+        status: http::StatusCode,
+        htmx: &crate::htmx::Request,
+        parts: &http::request::Parts,
+    ) -> impl Future<Output = axum::response::Response> + Send;
+}
+
+/// The marker of the error responses of controllers, including the rejections of requests that
+/// don't parse as routes.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ControllerError;
+
+/// Render an error response, if it is a client or server error returned by a controller.
+///
+/// The rendered response gets the status of the error, unless it sets another one, and the
+/// headers of the error it doesn't set, like `Allow` or `Retry-After`.
+pub(crate) async fn render_error<R: ErrorRenderer>(
+    renderer: &R,
+    mut parts: http::request::Parts,
+    mut error: axum::response::Response,
+) -> axum::response::Response {
+    let status = error.status();
+
+    if error.extensions_mut().remove::<ControllerError>().is_none()
+        || !(status.is_client_error() || status.is_server_error())
+    {
+        return error;
+    }
+
+    let htmx = match crate::htmx::Request::from_request_parts(&mut parts, &()).await {
+        Ok(htmx) => htmx,
+        Err(err) => match err {},
+    };
+
+    let mut rendered = match &htmx {
+        crate::htmx::Request::Htmx { .. } => renderer.render_fragment(status, &htmx, &parts).await,
+        crate::htmx::Request::Classic => renderer.render_page(status, &parts).await,
+    };
+
+    if rendered.status() == http::StatusCode::OK {
+        *rendered.status_mut() = status;
+    }
+
+    let (error_parts, _) = error.into_parts();
+
+    for name in error_parts.headers.keys() {
+        if *name == http::header::CONTENT_TYPE
+            || *name == http::header::CONTENT_LENGTH
+            || rendered.headers().contains_key(name)
+        {
+            continue;
+        }
+
+        for value in error_parts.headers.get_all(name) {
+            rendered.headers_mut().append(name.clone(), value.clone());
+        }
+    }
+
+    // Keep the other markers, like the one of unmatched requests.
+    rendered.extensions_mut().extend(error_parts.extensions);
+
+    rendered
+}
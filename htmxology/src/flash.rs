@@ -0,0 +1,515 @@
+//! Flash messages, carried over redirects by a short-lived cookie.
+
+use std::{
+    fmt::Display,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::response::IntoResponse;
+use base64::Engine;
+
+use crate::htmx::{Fragment, HtmlId, Identity, InsertStrategy};
+
+/// The default name of the flash messages cookie.
+pub const DEFAULT_FLASH_COOKIE_NAME: &str = "htmxology_flash";
+
+/// The default `id` of the element flash messages are appended to.
+pub const DEFAULT_FLASH_CONTAINER_ID: &str = "flash-messages";
+
+/// The default lifetime of the flash messages cookie.
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(60);
+
+/// The maximum size of a response body flash messages are appended to.
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024; // 16 MB
+
+/// The level of a flash message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashLevel {
+    /// An informational message.
+    Info,
+
+    /// A message confirming a successful operation.
+    Success,
+
+    /// A warning.
+    Warning,
+
+    /// An error message.
+    Error,
+}
+
+impl FlashLevel {
+    /// Get the name of the level, as used in the CSS classes of rendered messages.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Success => "success",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// A flash message.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FlashMessage {
+    /// The level of the message.
+    pub level: FlashLevel,
+
+    /// The text of the message.
+    pub text: String,
+}
+
+impl Display for FlashMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let role = match self.level {
+            FlashLevel::Warning | FlashLevel::Error => "alert",
+            FlashLevel::Info | FlashLevel::Success => "status",
+        };
+
+        write!(
+            f,
+            r#"<div class="flash flash-{}" role="{role}">{}</div>"#,
+            self.level.as_str(),
+            crate::htmx::escape_text(&self.text)
+        )
+    }
+}
+
+/// The flash messages of a request.
+///
+/// Messages pushed while handling a request are shown on the next HTML response to an HTMX
+/// request: on the response itself, or on the response to the request following a redirect. They
+/// are appended out-of-band to the element with the container `id`
+/// ([`DEFAULT_FLASH_CONTAINER_ID`] by default), which layouts should render:
+///
+/// ```html
+/// <div id="flash-messages">{% for message in flash.take() %}{{ message|safe }}{% endfor %}</div>
+/// ```
+///
+/// As full page loads don't process out-of-band swaps, layouts should render the pending messages
+/// themselves with [`FlashMessages::take`]. Messages that are not taken are kept for the next
+/// response.
+///
+/// Cloning flash messages is cheap, and the clones share the same messages.
+#[derive(Debug, Clone, Default)]
+pub struct FlashMessages {
+    messages: Arc<Mutex<Vec<FlashMessage>>>,
+}
+
+impl FlashMessages {
+    /// Get the flash messages of a request from its parts.
+    ///
+    /// Returns `None` if flash messages are not enabled on the server.
+    pub fn from_parts(parts: &http::request::Parts) -> Option<Self> {
+        parts.extensions.get().cloned()
+    }
+
+    fn messages(&self) -> std::sync::MutexGuard<'_, Vec<FlashMessage>> {
+        self.messages
+            .lock()
+            .expect("flash messages lock should not be poisoned")
+    }
+
+    /// Push a message.
+    pub fn push(&self, level: FlashLevel, text: impl Into<String>) {
+        self.messages().push(FlashMessage {
+            level,
+            text: text.into(),
+        });
+    }
+
+    /// Push an informational message.
+    pub fn push_info(&self, text: impl Into<String>) {
+        self.push(FlashLevel::Info, text);
+    }
+
+    /// Push a message confirming a successful operation.
+    pub fn push_success(&self, text: impl Into<String>) {
+        self.push(FlashLevel::Success, text);
+    }
+
+    /// Push a warning.
+    pub fn push_warning(&self, text: impl Into<String>) {
+        self.push(FlashLevel::Warning, text);
+    }
+
+    /// Push an error message.
+    pub fn push_error(&self, text: impl Into<String>) {
+        self.push(FlashLevel::Error, text);
+    }
+
+    /// Take the pending messages, to render them.
+    ///
+    /// Taken messages are not shown again.
+    pub fn take(&self) -> Vec<FlashMessage> {
+        std::mem::take(&mut *self.messages())
+    }
+
+    /// Check whether there are no pending messages.
+    pub fn is_empty(&self) -> bool {
+        self.messages().is_empty()
+    }
+}
+
+impl<S: Send + Sync> axum::extract::FromRequestParts<S> for FlashMessages {
+    type Rejection = (http::StatusCode, &'static str);
+
+    async fn from_request_parts(
+        parts: &mut http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        Self::from_parts(parts).ok_or((
+            http::StatusCode::INTERNAL_SERVER_ERROR,
+            "flash messages are not enabled on the server",
+        ))
+    }
+}
+
+/// Flash messages, rendered as an out-of-band fragment.
+struct FlashFragment<'m> {
+    container_id: &'m HtmlId,
+    messages: &'m [FlashMessage],
+}
+
+impl Identity for FlashFragment<'_> {
+    fn id(&self) -> HtmlId {
+        self.container_id.clone()
+    }
+}
+
+impl Fragment for FlashFragment<'_> {
+    fn insert_strategy(&self) -> InsertStrategy {
+        InsertStrategy::BeforeEnd
+    }
+}
+
+impl Display for FlashFragment<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<div {}>", self.id_attribute())?;
+
+        for message in self.messages {
+            message.fmt(f)?;
+        }
+
+        f.write_str("</div>")
+    }
+}
+
+/// The flash messages configuration of a server.
+#[derive(Clone)]
+pub struct Flash {
+    cookie_name: String,
+    container_id: HtmlId,
+    max_age: Duration,
+    secure: bool,
+}
+
+impl Default for Flash {
+    fn default() -> Self {
+        Self {
+            cookie_name: DEFAULT_FLASH_COOKIE_NAME.to_owned(),
+            container_id: HtmlId::from_static(DEFAULT_FLASH_CONTAINER_ID)
+                .expect("default flash container id should be valid"),
+            max_age: DEFAULT_MAX_AGE,
+            secure: true,
+        }
+    }
+}
+
+impl std::fmt::Debug for Flash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Flash")
+            .field("cookie_name", &self.cookie_name)
+            .field("container_id", &self.container_id.to_string())
+            .field("max_age", &self.max_age)
+            .field("secure", &self.secure)
+            .finish()
+    }
+}
+
+impl Flash {
+    /// Set the name of the flash messages cookie.
+    pub fn with_cookie_name(mut self, cookie_name: impl Into<String>) -> Self {
+        self.cookie_name = cookie_name.into();
+        self
+    }
+
+    /// Set the `id` of the element flash messages are appended to.
+    pub fn with_container_id(mut self, container_id: HtmlId) -> Self {
+        self.container_id = container_id;
+        self
+    }
+
+    /// Set how long messages are kept, waiting for a response to show them.
+    ///
+    /// Defaults to one minute.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Set whether the flash messages cookie is only sent over HTTPS.
+    ///
+    /// Defaults to `true`.
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Apply the cookie settings of a profile.
+    pub fn with_profile(self, settings: &crate::ProfileSettings) -> Self {
+        self.with_secure(settings.secure_cookies)
+    }
+
+    /// Apply the flash messages middleware to a router.
+    pub(crate) fn apply(self, router: axum::Router) -> axum::Router {
+        router.layer(axum::middleware::from_fn_with_state(
+            Arc::new(self),
+            middleware,
+        ))
+    }
+
+    /// Read the messages of the flash messages cookie of a request.
+    fn read_cookie(&self, headers: &http::HeaderMap) -> Option<Vec<FlashMessage>> {
+        let value = headers
+            .get_all(http::header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .filter_map(|cookie| cookie.trim().split_once('='))
+            .find(|(name, _)| *name == self.cookie_name)
+            .map(|(_, value)| value)?;
+
+        let json = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(value)
+            .ok()?;
+
+        serde_json::from_slice(&json)
+            .inspect_err(|err| tracing::warn!("Ignoring invalid flash messages cookie: {err}"))
+            .ok()
+    }
+
+    /// Build a `Set-Cookie` header value storing messages, or clearing the cookie.
+    fn set_cookie(&self, messages: &[FlashMessage]) -> Option<http::HeaderValue> {
+        let (value, max_age) = if messages.is_empty() {
+            (String::new(), 0)
+        } else {
+            let json = serde_json::to_vec(messages).ok()?;
+
+            (
+                base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json),
+                self.max_age.as_secs(),
+            )
+        };
+
+        let mut cookie = format!(
+            "{}={value}; Path=/; HttpOnly; SameSite=Lax; Max-Age={max_age}",
+            self.cookie_name
+        );
+
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+
+        http::HeaderValue::from_str(&cookie)
+            .inspect_err(|err| tracing::error!("Invalid flash messages cookie: {err}"))
+            .ok()
+    }
+}
+
+/// Check whether a response redirects the client, in which case flash messages are kept for the
+/// next response.
+fn is_redirect(response: &axum::response::Response) -> bool {
+    response.status().is_redirection()
+        || ["hx-redirect", "hx-location", "hx-refresh"]
+            .iter()
+            .any(|name| response.headers().contains_key(*name))
+}
+
+/// Check whether flash messages can be appended to a response.
+fn accepts_messages(response: &axum::response::Response) -> bool {
+    response.status().is_success()
+        && response.status() != http::StatusCode::NO_CONTENT
+        && response
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/html"))
+}
+
+/// Load the flash messages of a request, and show or store them once the request has been
+/// handled.
+async fn middleware(
+    axum::extract::State(flash): axum::extract::State<Arc<Flash>>,
+    mut request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let incoming = flash.read_cookie(request.headers());
+    let had_cookie = incoming.is_some();
+    let is_htmx = request.headers().contains_key("hx-request");
+
+    let messages = FlashMessages {
+        messages: Arc::new(Mutex::new(incoming.clone().unwrap_or_default())),
+    };
+    request.extensions_mut().insert(messages.clone());
+
+    let response = next.run(request).await;
+    let pending = messages.take();
+
+    let (response, pending) =
+        if is_htmx && !pending.is_empty() && !is_redirect(&response) && accepts_messages(&response)
+        {
+            let fragment = FlashFragment {
+                container_id: &flash.container_id,
+                messages: &pending,
+            };
+            let target = format!("#{}", flash.container_id);
+            let html = crate::htmx::inject_oob_attribute(
+                &fragment.to_string(),
+                &fragment.insert_strategy(),
+                &target,
+            );
+
+            (append_to_body(response, &html).await, Vec::new())
+        } else {
+            (response, pending)
+        };
+
+    // Only touch the cookie if the pending messages changed.
+    if incoming.as_deref().unwrap_or_default() == pending.as_slice()
+        || (!had_cookie && pending.is_empty())
+    {
+        return response;
+    }
+
+    let mut response = response;
+
+    if let Some(set_cookie) = flash.set_cookie(&pending) {
+        response
+            .headers_mut()
+            .append(http::header::SET_COOKIE, set_cookie);
+    }
+
+    response
+}
+
+/// Append HTML to the body of a response.
+async fn append_to_body(
+    response: axum::response::Response,
+    html: &str,
+) -> axum::response::Response {
+    let (mut parts, body) = response.into_parts();
+
+    let body = match axum::body::to_bytes(body, MAX_BODY_SIZE).await {
+        Ok(body) => body,
+        Err(err) => {
+            tracing::error!("Failed to read response body: {err}");
+
+            return http::StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let mut body = body.to_vec();
+    body.extend_from_slice(html.as_bytes());
+    parts.headers.remove(http::header::CONTENT_LENGTH);
+
+    axum::response::Response::from_parts(parts, axum::body::Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_service::Service;
+
+    use super::*;
+
+    fn router() -> axum::Router {
+        let router = axum::Router::new()
+            .route(
+                "/save",
+                axum::routing::post(|flash: FlashMessages| async move {
+                    flash.push_success("Saved <b>1</b> item");
+
+                    axum::response::Redirect::to("/items")
+                }),
+            )
+            .route(
+                "/items",
+                axum::routing::get(|| async { axum::response::Html("<ul></ul>") }),
+            );
+
+        Flash::default().apply(router)
+    }
+
+    async fn call(
+        router: &mut axum::Router,
+        method: &str,
+        uri: &str,
+        cookie: Option<&str>,
+    ) -> (Option<String>, String) {
+        let mut request = http::Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("hx-request", "true");
+
+        if let Some(cookie) = cookie {
+            request = request.header(http::header::COOKIE, cookie);
+        }
+
+        let response = router
+            .call(request.body(axum::body::Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let set_cookie = response
+            .headers()
+            .get(http::header::SET_COOKIE)
+            .map(|value| value.to_str().unwrap().to_owned());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        (set_cookie, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_post_redirect_get() {
+        let mut router = router();
+
+        let (set_cookie, _) = call(&mut router, "POST", "/save", None).await;
+        let set_cookie = set_cookie.unwrap();
+        let cookie = set_cookie.split(';').next().unwrap();
+
+        assert!(set_cookie.contains("Max-Age=60"));
+
+        let (set_cookie, body) = call(&mut router, "GET", "/items", Some(cookie)).await;
+
+        assert!(set_cookie.unwrap().contains("Max-Age=0"));
+        assert_eq!(
+            body,
+            r#"<ul></ul><div id="flash-messages" hx-swap-oob="beforeend:#flash-messages"><div class="flash flash-success" role="status">Saved &lt;b&gt;1&lt;/b&gt; item</div></div>"#
+        );
+
+        // Without messages, responses are left untouched.
+        assert_eq!(
+            call(&mut router, "GET", "/items", None).await,
+            (None, "<ul></ul>".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_take() {
+        let flash = FlashMessages::default();
+        flash.push_error("Oops");
+
+        assert_eq!(
+            flash.take(),
+            vec![FlashMessage {
+                level: FlashLevel::Error,
+                text: "Oops".to_owned()
+            }]
+        );
+        assert!(flash.is_empty());
+    }
+}
@@ -0,0 +1,347 @@
+//! Fragments published at content-addressed URLs, for embedding in external sites.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    sync::{Arc, RwLock},
+};
+
+use axum::response::IntoResponse;
+use sha2::Digest;
+
+use crate::CachingResponseExt;
+
+/// The duration published fragments are cached for: they never change.
+const IMMUTABLE_MAX_AGE: std::time::Duration = std::time::Duration::from_secs(365 * 24 * 60 * 60);
+
+/// The default number of versions of each fragment that remain served.
+const DEFAULT_RETAINED_VERSIONS: usize = 3;
+
+/// The name of the manifest, under the mount path.
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// A set of fragments published at content-addressed URLs, like embeddable widgets.
+///
+/// Each published fragment is served at a URL derived from the hash of its rendered HTML, with a
+/// `Cache-Control: immutable` directive, so that clients and CDNs can cache it forever. A manifest
+/// maps the names of the fragments to their current URLs, and is revalidated on each request.
+///
+/// Fragments are served with `Access-Control-Allow-Origin: *`, so that external sites can load
+/// them with htmx, and must therefore not contain any visitor-specific content.
+///
+/// Publishers are mounted with
+/// [`ControllerRouter::with_fragment_publisher`](crate::ControllerRouter::with_fragment_publisher):
+///
+/// ```rust,ignore
+/// let publisher = FragmentPublisher::new("/widgets");
+/// publisher.publish("weather", WeatherWidget::new(&forecast));
+///
+/// let router = ControllerRouter::new(controller, |_| async {})
+///     .with_fragment_publisher(publisher.clone());
+/// ```
+///
+/// External sites then load the manifest at `/widgets/manifest.json`, like
+/// `{"weather": "/widgets/4b1f0c2a9d3e8f75"}`, and embed the fragment from its URL.
+///
+/// Cloning a publisher is cheap, and the clones share the same fragments.
+#[derive(Debug, Clone)]
+pub struct FragmentPublisher {
+    path: String,
+    retained_versions: usize,
+    state: Arc<RwLock<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The hashes of the versions of each fragment, from oldest to newest.
+    versions: HashMap<String, VecDeque<String>>,
+
+    /// The rendered fragments, by hash.
+    fragments: HashMap<String, axum::body::Bytes>,
+}
+
+impl FragmentPublisher {
+    /// Create a publisher serving its fragments under the specified path.
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into().trim_end_matches('/').to_owned(),
+            retained_versions: DEFAULT_RETAINED_VERSIONS,
+            state: Default::default(),
+        }
+    }
+
+    /// Set the number of versions of each fragment that remain served after new ones are
+    /// published, for the pages embedding them from an outdated manifest.
+    ///
+    /// Defaults to 3, including the current version.
+    ///
+    /// # Panics
+    ///
+    /// If `retained_versions` is zero, the call will panic.
+    pub fn with_retained_versions(mut self, retained_versions: usize) -> Self {
+        assert!(
+            retained_versions > 0,
+            "retained versions must be greater than zero"
+        );
+
+        self.retained_versions = retained_versions;
+        self
+    }
+
+    /// Get the path the fragments are served under.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Publish a new version of a fragment, returning its URL.
+    ///
+    /// Publishing the same content again keeps the same URL.
+    pub fn publish(&self, name: &str, fragment: impl Display) -> String {
+        let html = fragment.to_string();
+        let hash = hex::encode(&sha2::Sha256::digest(html.as_bytes())[..8]);
+        let mut state = self.state();
+
+        state
+            .fragments
+            .entry(hash.clone())
+            .or_insert_with(|| html.into());
+
+        let versions = state.versions.entry(name.to_owned()).or_default();
+        versions.retain(|version| *version != hash);
+        versions.push_back(hash.clone());
+
+        let expired: Vec<_> = versions
+            .drain(..versions.len().saturating_sub(self.retained_versions))
+            .collect();
+
+        for hash in expired {
+            let in_use = state
+                .versions
+                .values()
+                .any(|versions| versions.contains(&hash));
+
+            if !in_use {
+                state.fragments.remove(&hash);
+            }
+        }
+
+        self.url_of(&hash)
+    }
+
+    /// Get the URL of the current version of a fragment, if it was published.
+    pub fn url(&self, name: &str) -> Option<String> {
+        self.read_state()
+            .versions
+            .get(name)
+            .and_then(VecDeque::back)
+            .map(|hash| self.url_of(hash))
+    }
+
+    /// Get the URL of the manifest.
+    pub fn manifest_url(&self) -> String {
+        self.url_of(MANIFEST_NAME)
+    }
+
+    /// Get the manifest, mapping the names of the fragments to the URLs of their current
+    /// versions.
+    pub fn manifest(&self) -> std::collections::BTreeMap<String, String> {
+        self.read_state()
+            .versions
+            .iter()
+            .filter_map(|(name, versions)| Some((name.clone(), self.url_of(versions.back()?))))
+            .collect()
+    }
+
+    /// Serve a published fragment, by hash.
+    pub fn serve(&self, hash: &str, headers: &http::HeaderMap) -> axum::response::Response {
+        let Some(html) = self.read_state().fragments.get(hash).cloned() else {
+            return http::StatusCode::NOT_FOUND.into_response();
+        };
+
+        let response =
+            if crate::caching::etag_matches(&crate::caching::if_none_match(headers), hash) {
+                http::StatusCode::NOT_MODIFIED.into_response()
+            } else {
+                axum::response::Html(html).into_response()
+            };
+
+        let mut response = response
+            .with_public_caching(IMMUTABLE_MAX_AGE)
+            .with_etag(hash)
+            .unwrap_or_else(|response| response);
+
+        response.headers_mut().insert(
+            http::header::CACHE_CONTROL,
+            http::HeaderValue::from_str(&format!(
+                "public, max-age={}, immutable",
+                IMMUTABLE_MAX_AGE.as_secs()
+            ))
+            .expect("immutable cache control should be a valid header value"),
+        );
+
+        with_cors(response)
+    }
+
+    /// Serve the manifest.
+    pub fn serve_manifest(&self) -> axum::response::Response {
+        let response = axum::Json(self.manifest())
+            .into_response()
+            .with_caching_disabled();
+
+        with_cors(response)
+    }
+
+    /// Build the router of the fragments and the manifest.
+    pub(crate) fn router(self) -> axum::Router {
+        let manifest = {
+            let publisher = self.clone();
+
+            move || std::future::ready(publisher.serve_manifest())
+        };
+
+        axum::Router::new()
+            .route(&self.manifest_url(), axum::routing::get(manifest))
+            .route(
+                &self.url_of("{hash}"),
+                axum::routing::get(
+                    move |axum::extract::Path(hash): axum::extract::Path<String>,
+                          headers: http::HeaderMap| {
+                        std::future::ready(self.serve(&hash, &headers))
+                    },
+                ),
+            )
+    }
+
+    fn url_of(&self, name: &str) -> String {
+        format!("{}/{name}", self.path)
+    }
+
+    fn state(&self) -> std::sync::RwLockWriteGuard<'_, State> {
+        self.state
+            .write()
+            .expect("fragments lock should not be poisoned")
+    }
+
+    fn read_state(&self) -> std::sync::RwLockReadGuard<'_, State> {
+        self.state
+            .read()
+            .expect("fragments lock should not be poisoned")
+    }
+}
+
+/// Allow any origin to load a response.
+fn with_cors(mut response: axum::response::Response) -> axum::response::Response {
+    response.headers_mut().insert(
+        http::header::ACCESS_CONTROL_ALLOW_ORIGIN,
+        http::HeaderValue::from_static("*"),
+    );
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_service::Service as _;
+
+    use super::*;
+
+    async fn get(
+        router: &mut axum::Router,
+        uri: &str,
+        etag: Option<&str>,
+    ) -> axum::response::Response {
+        let mut request = http::Request::get(uri);
+
+        if let Some(etag) = etag {
+            request = request.header(http::header::IF_NONE_MATCH, etag);
+        }
+
+        router
+            .call(request.body(axum::body::Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn body(response: axum::response::Response) -> String {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_publish() {
+        let publisher = FragmentPublisher::new("/widgets/").with_retained_versions(2);
+
+        let v1 = publisher.publish("weather", "<p>Sunny</p>");
+        assert!(v1.starts_with("/widgets/"));
+        assert_eq!(v1.len(), "/widgets/".len() + 16);
+        assert_eq!(publisher.publish("weather", "<p>Sunny</p>"), v1);
+        assert_eq!(publisher.url("weather"), Some(v1.clone()));
+        assert_eq!(publisher.url("news"), None);
+
+        let v2 = publisher.publish("weather", "<p>Rainy</p>");
+        let v3 = publisher.publish("weather", "<p>Snowy</p>");
+        assert_eq!(publisher.url("weather"), Some(v3.clone()));
+
+        // Only the retained versions are kept.
+        let state = publisher.read_state();
+        assert!(!state.fragments.contains_key(&v1["/widgets/".len()..]));
+        assert!(state.fragments.contains_key(&v2["/widgets/".len()..]));
+        drop(state);
+
+        // Versions shared with other fragments are kept.
+        publisher.publish("forecast", "<p>Snowy</p>");
+        publisher.publish("weather", "<p>Foggy</p>");
+        publisher.publish("weather", "<p>Windy</p>");
+        assert!(
+            publisher
+                .read_state()
+                .fragments
+                .contains_key(&v3["/widgets/".len()..])
+        );
+
+        assert_eq!(publisher.manifest_url(), "/widgets/manifest.json");
+        assert_eq!(
+            publisher.manifest().into_iter().collect::<Vec<_>>(),
+            [
+                ("forecast".to_owned(), v3),
+                ("weather".to_owned(), publisher.url("weather").unwrap()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_router() {
+        let publisher = FragmentPublisher::new("/widgets");
+        let url = publisher.publish("weather", "<p>Sunny</p>");
+        let mut router = publisher.clone().router();
+
+        let response = get(&mut router, &url, None).await;
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(
+            response.headers()[http::header::CACHE_CONTROL],
+            "public, max-age=31536000, immutable"
+        );
+        assert_eq!(
+            response.headers()[http::header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "*"
+        );
+        let etag = response.headers()[http::header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_owned();
+        assert_eq!(body(response).await, "<p>Sunny</p>");
+
+        let response = get(&mut router, &url, Some(&etag)).await;
+        assert_eq!(response.status(), http::StatusCode::NOT_MODIFIED);
+
+        let response = get(&mut router, "/widgets/0123456789abcdef", None).await;
+        assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+
+        let response = get(&mut router, "/widgets/manifest.json", None).await;
+        assert_eq!(response.headers()[http::header::CACHE_CONTROL], "no-cache");
+        assert_eq!(body(response).await, format!(r#"{{"weather":"{url}"}}"#));
+    }
+}
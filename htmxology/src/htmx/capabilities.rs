@@ -0,0 +1,268 @@
+//! HTMX version negotiation and capability detection.
+
+use std::{convert::Infallible, fmt::Display, str::FromStr};
+
+use http::request::Parts;
+
+/// The default request header carrying the HTMX version of the client.
+pub const DEFAULT_VERSION_HEADER: http::HeaderName = http::HeaderName::from_static("hx-version");
+
+/// A HTMX version, as reported by `htmx.version` on the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HtmxVersion {
+    /// The major version.
+    pub major: u32,
+
+    /// The minor version.
+    pub minor: u32,
+
+    /// The patch version.
+    pub patch: u32,
+}
+
+/// An error that occurs when trying to parse an invalid HTMX version.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid HTMX version: {0}")]
+pub struct InvalidHtmxVersion(String);
+
+impl HtmxVersion {
+    /// Create a new version.
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl FromStr for HtmxVersion {
+    type Err = InvalidHtmxVersion;
+
+    /// Parse a version like `1.9.12` or `2.0.0-beta1`.
+    ///
+    /// Pre-release suffixes are ignored, and missing minor or patch numbers default to zero.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidHtmxVersion(s.to_owned());
+        let version = s.trim();
+        let version = version.strip_prefix('v').unwrap_or(version);
+        let version = version.split(['-', '+']).next().unwrap_or_default();
+
+        let mut numbers = version.split('.').map(|number| number.parse::<u32>());
+        let major = numbers.next().ok_or_else(invalid)?.map_err(|_| invalid())?;
+        let minor = numbers.next().transpose().map_err(|_| invalid())?;
+        let patch = numbers.next().transpose().map_err(|_| invalid())?;
+
+        if numbers.next().is_some() {
+            return Err(invalid());
+        }
+
+        Ok(Self::new(
+            major,
+            minor.unwrap_or_default(),
+            patch.unwrap_or_default(),
+        ))
+    }
+}
+
+impl Display for HtmxVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// The name of the request header carrying the HTMX version of the client.
+///
+/// Insert this type in the request extensions (for instance with an `axum::Extension` layer) to
+/// override [`DEFAULT_VERSION_HEADER`].
+#[derive(Debug, Clone)]
+pub struct HtmxVersionHeader(pub http::HeaderName);
+
+impl Default for HtmxVersionHeader {
+    fn default() -> Self {
+        Self(DEFAULT_VERSION_HEADER)
+    }
+}
+
+impl HtmxVersionHeader {
+    /// Get a script reporting the HTMX version of the client in this header, on every request.
+    ///
+    /// HTMX does not report its version by itself: include this script in the page, after HTMX
+    /// is loaded, to enable capability detection.
+    pub fn boot_script(&self) -> String {
+        format!(
+            r#"document.addEventListener("htmx:configRequest",function(e){{e.detail.headers["{}"]=htmx.version}});"#,
+            self.0
+        )
+    }
+}
+
+/// The capabilities of the HTMX client that issued a request.
+///
+/// Capabilities are detected from the version reported by the client (see
+/// [`HtmxVersionHeader::boot_script`]). Clients that don't report their version are assumed to
+/// support everything, so that responses are not degraded when the version is unknown.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let capabilities = HtmxCapabilities::from_parts(&parts);
+///
+/// let response = if capabilities.supports_hx_reswap() {
+///     response.with_header(HX_RESWAP, "outerHTML".parse()?)
+/// } else {
+///     response
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HtmxCapabilities {
+    version: Option<HtmxVersion>,
+}
+
+impl HtmxCapabilities {
+    /// Get the capabilities of a specific HTMX version.
+    pub fn new(version: Option<HtmxVersion>) -> Self {
+        Self { version }
+    }
+
+    /// Get the capabilities of the client from the request headers, using the specified header.
+    ///
+    /// An absent or invalid header results in an unknown version.
+    pub fn from_headers(headers: &http::HeaderMap, header: &http::HeaderName) -> Self {
+        Self::new(
+            headers
+                .get(header)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok()),
+        )
+    }
+
+    /// Get the capabilities of the client from the request parts.
+    ///
+    /// The version is read from the header specified by the [`HtmxVersionHeader`] request
+    /// extension, or from [`DEFAULT_VERSION_HEADER`] if there is none.
+    pub fn from_parts(parts: &Parts) -> Self {
+        match parts.extensions.get::<HtmxVersionHeader>() {
+            Some(header) => Self::from_headers(&parts.headers, &header.0),
+            None => Self::from_headers(&parts.headers, &DEFAULT_VERSION_HEADER),
+        }
+    }
+
+    /// The HTMX version of the client, if known.
+    pub fn version(&self) -> Option<HtmxVersion> {
+        self.version
+    }
+
+    /// Check whether the client runs at least the specified version.
+    ///
+    /// Always true if the version is unknown.
+    pub fn at_least(&self, version: HtmxVersion) -> bool {
+        self.version.is_none_or(|v| v >= version)
+    }
+
+    /// Whether the client supports the `HX-Location` response header (htmx 1.6.1).
+    pub fn supports_hx_location(&self) -> bool {
+        self.at_least(HtmxVersion::new(1, 6, 1))
+    }
+
+    /// Whether the client supports the `HX-Retarget` response header (htmx 1.6.1).
+    pub fn supports_hx_retarget(&self) -> bool {
+        self.at_least(HtmxVersion::new(1, 6, 1))
+    }
+
+    /// Whether the client supports the `HX-Reswap` response header (htmx 1.8.0).
+    pub fn supports_hx_reswap(&self) -> bool {
+        self.at_least(HtmxVersion::new(1, 8, 0))
+    }
+
+    /// Whether the client supports the `HX-Push-Url` and `HX-Replace-Url` response headers (htmx
+    /// 1.8.0).
+    ///
+    /// Older versions only support the deprecated `HX-Push` header.
+    pub fn supports_hx_push_url(&self) -> bool {
+        self.at_least(HtmxVersion::new(1, 8, 0))
+    }
+
+    /// Whether the client is htmx 2 or later, where some attributes changed (like `hx-on:*`
+    /// replacing `hx-on`) and some extensions were moved out of the core.
+    pub fn is_htmx2(&self) -> bool {
+        self.at_least(HtmxVersion::new(2, 0, 0))
+    }
+}
+
+impl<S: Send + Sync> axum::extract::FromRequestParts<S> for HtmxCapabilities {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_parts(parts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            "1.9.12".parse::<HtmxVersion>().unwrap(),
+            HtmxVersion::new(1, 9, 12)
+        );
+        assert_eq!(
+            "v2.0.0-beta1".parse::<HtmxVersion>().unwrap(),
+            HtmxVersion::new(2, 0, 0)
+        );
+        assert_eq!(
+            "1.8".parse::<HtmxVersion>().unwrap(),
+            HtmxVersion::new(1, 8, 0)
+        );
+        assert!("".parse::<HtmxVersion>().is_err());
+        assert!("1.x".parse::<HtmxVersion>().is_err());
+        assert!("1.2.3.4".parse::<HtmxVersion>().is_err());
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let old = HtmxCapabilities::new(Some(HtmxVersion::new(1, 7, 0)));
+        assert!(old.supports_hx_location());
+        assert!(!old.supports_hx_reswap());
+        assert!(!old.supports_hx_push_url());
+        assert!(!old.is_htmx2());
+
+        let unknown = HtmxCapabilities::default();
+        assert!(unknown.supports_hx_reswap());
+        assert!(unknown.is_htmx2());
+    }
+
+    #[test]
+    fn test_from_parts() {
+        let (mut parts, _) = http::Request::builder()
+            .header("hx-version", "1.9.10")
+            .header("x-htmx", "1.5.0")
+            .body(())
+            .unwrap()
+            .into_parts();
+
+        assert_eq!(
+            HtmxCapabilities::from_parts(&parts).version(),
+            Some(HtmxVersion::new(1, 9, 10))
+        );
+
+        parts
+            .extensions
+            .insert(HtmxVersionHeader(http::HeaderName::from_static("x-htmx")));
+
+        assert_eq!(
+            HtmxCapabilities::from_parts(&parts).version(),
+            Some(HtmxVersion::new(1, 5, 0))
+        );
+    }
+
+    #[test]
+    fn test_boot_script() {
+        assert_eq!(
+            HtmxVersionHeader::default().boot_script(),
+            r#"document.addEventListener("htmx:configRequest",function(e){e.detail.headers["hx-version"]=htmx.version});"#
+        );
+    }
+}
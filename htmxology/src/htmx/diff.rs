@@ -0,0 +1,148 @@
+//! Skipping unchanged out-of-band updates.
+
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{Arc, Mutex},
+};
+
+use super::{Fragment, oob::render_fragment};
+
+/// The hashes of the fragments last sent, by scope and fragment `id`.
+type Hashes = HashMap<String, HashMap<String, u64>>;
+
+/// A record of the fragments last sent to clients, to skip the out-of-band updates whose HTML
+/// is unchanged.
+///
+/// Only a hash of the HTML of each fragment is kept, per scope and fragment `id`. Scopes are
+/// free-form: a session identifier, a client identifier, or a constant for broadcasts.
+/// Cloning the record returns a handle to the same hashes.
+///
+/// This is useful for high-frequency live updates, where most of the pushed fragments are
+/// identical to the previous ones. The SSE and WebSocket hubs use it when configured with
+/// `with_fragment_diff`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let diff = FragmentDiff::new();
+///
+/// if let Some(html) = diff.render_if_changed(&session_id, &Counter::new(count)) {
+///     send(html);
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FragmentDiff {
+    hashes: Arc<Mutex<Hashes>>,
+}
+
+impl FragmentDiff {
+    /// Create an empty record.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render a fragment for an out-of-band swap, unless its HTML is unchanged since it was last
+    /// rendered in the same scope.
+    ///
+    /// Returns `None` if the update can be skipped.
+    pub fn render_if_changed(&self, scope: &str, fragment: &impl Fragment) -> Option<String> {
+        let html = render_fragment(fragment);
+
+        self.update(scope, &fragment.id().to_string(), &html)
+            .then_some(html)
+    }
+
+    /// Record the HTML of an element in a scope, and check whether it changed.
+    ///
+    /// Returns `true` if the element was never recorded in the scope, or if its HTML is
+    /// different.
+    pub fn update(&self, scope: &str, id: &str, html: &str) -> bool {
+        let mut hasher = DefaultHasher::new();
+        html.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let mut hashes = self
+            .hashes
+            .lock()
+            .expect("hashes lock should not be poisoned");
+
+        let previous = hashes
+            .entry(scope.to_owned())
+            .or_default()
+            .insert(id.to_owned(), hash);
+
+        previous != Some(hash)
+    }
+
+    /// Forget the elements recorded in a scope, for instance when a session ends or a client
+    /// disconnects.
+    ///
+    /// The next updates in the scope are always sent.
+    pub fn forget(&self, scope: &str) {
+        self.hashes
+            .lock()
+            .expect("hashes lock should not be poisoned")
+            .remove(scope);
+    }
+
+    /// Forget all the recorded elements.
+    pub fn clear(&self) {
+        self.hashes
+            .lock()
+            .expect("hashes lock should not be poisoned")
+            .clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Display;
+
+    use super::*;
+    use crate::htmx::{HtmlId, Identity, InsertStrategy};
+
+    struct Counter(u32);
+
+    impl Display for Counter {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, r#"<span id="counter">{}</span>"#, self.0)
+        }
+    }
+
+    impl Identity for Counter {
+        fn id(&self) -> HtmlId {
+            HtmlId::from_static("counter").unwrap()
+        }
+    }
+
+    impl Fragment for Counter {
+        fn insert_strategy(&self) -> InsertStrategy {
+            InsertStrategy::OuterHtml
+        }
+    }
+
+    #[test]
+    fn test_render_if_changed() {
+        let diff = FragmentDiff::new();
+
+        assert_eq!(
+            diff.render_if_changed("a", &Counter(1)).as_deref(),
+            Some(r#"<span id="counter" hx-swap-oob="true">1</span>"#)
+        );
+        assert_eq!(diff.render_if_changed("a", &Counter(1)), None);
+
+        // Scopes are independent.
+        assert!(diff.render_if_changed("b", &Counter(1)).is_some());
+
+        assert!(diff.render_if_changed("a", &Counter(2)).is_some());
+        assert!(diff.render_if_changed("a", &Counter(1)).is_some());
+
+        diff.forget("a");
+        assert!(diff.render_if_changed("a", &Counter(1)).is_some());
+        assert_eq!(diff.render_if_changed("b", &Counter(1)), None);
+
+        diff.clear();
+        assert!(diff.render_if_changed("b", &Counter(1)).is_some());
+    }
+}
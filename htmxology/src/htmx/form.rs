@@ -0,0 +1,348 @@
+//! Form inputs.
+
+use std::{borrow::Cow, fmt::Display};
+
+use crate::Route;
+
+use super::{HtmlName, Named, escape_attribute_value, escape_text};
+
+/// An `<input>` element of a form.
+///
+/// Typically generated by the `HtmlForm` derive macro, for each field of a form. Renders as an
+/// `<input>` element, wrapped in a `<label>` if the input has a label.
+///
+/// # Example
+///
+/// ```ignore
+/// let input = FormInput::new(HtmlName::from_static("email")?)
+///     .with_input_type("email")
+///     .with_label("E-mail")
+///     .with_required(true);
+///
+/// assert_eq!(
+///     input.to_string(),
+///     r#"<label>E-mail <input type="email" name="email" required></label>"#,
+/// );
+/// ```
+///
+/// Inputs with a validation route or a validation result are wrapped in a `<div class="form-field">`
+/// element, which posts the form to the validation route when the input changes, and is replaced
+/// by the response: typically the input rendered again with its validation result.
+#[derive(Clone)]
+pub struct FormInput {
+    /// The name of the input.
+    name: HtmlName,
+
+    /// The type of the input.
+    input_type: Cow<'static, str>,
+
+    /// The value of the input, if any.
+    value: Option<String>,
+
+    /// Whether the input is checked, for checkboxes.
+    checked: bool,
+
+    /// The label of the input, if any.
+    label: Option<Cow<'static, str>>,
+
+    /// Whether the input is required.
+    required: bool,
+
+    /// The URL of the route validating the input when it changes, if any.
+    validation_url: Option<String>,
+
+    /// The result of the validation of the input, if it was validated.
+    validation: Option<Result<(), String>>,
+}
+
+impl FormInput {
+    /// Create a new text input.
+    pub fn new(name: HtmlName) -> Self {
+        Self {
+            name,
+            input_type: Cow::Borrowed("text"),
+            value: None,
+            checked: false,
+            label: None,
+            required: false,
+            validation_url: None,
+            validation: None,
+        }
+    }
+
+    /// Set the type of the input, like `email`, `number` or `checkbox`.
+    pub fn with_input_type(mut self, input_type: impl Into<Cow<'static, str>>) -> Self {
+        self.input_type = input_type.into();
+        self
+    }
+
+    /// Set the value of the input.
+    pub fn with_value(mut self, value: impl Into<String>) -> Self {
+        self.value = Some(value.into());
+        self
+    }
+
+    /// Set whether the input is checked, for checkboxes.
+    pub fn with_checked(mut self, checked: bool) -> Self {
+        self.checked = checked;
+        self
+    }
+
+    /// Set the label of the input.
+    pub fn with_label(mut self, label: impl Into<Cow<'static, str>>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Set whether the input is required.
+    pub fn with_required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Validate the input with the specified route when it changes.
+    ///
+    /// The route is typically a variant of the validation route type generated by the `HtmlForm`
+    /// derive macro for fields with a `validate` function.
+    pub fn with_validation_route(mut self, route: &impl Route) -> Self {
+        self.validation_url = Some(route.to_string());
+        self
+    }
+
+    /// Set the result of the validation of the input, rendering its error message if it is
+    /// invalid.
+    pub fn with_validation(mut self, result: Result<(), String>) -> Self {
+        self.validation = Some(result);
+        self
+    }
+
+    /// The type of the input.
+    pub fn input_type(&self) -> &str {
+        &self.input_type
+    }
+
+    /// The value of the input, if any.
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+
+    /// The label of the input, if any.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// The result of the validation of the input, if it was validated.
+    pub fn validation(&self) -> Option<Result<(), &str>> {
+        self.validation
+            .as_ref()
+            .map(|result| result.as_ref().map(|_| ()).map_err(String::as_str))
+    }
+}
+
+impl Named for FormInput {
+    fn name(&self) -> HtmlName {
+        self.name.clone()
+    }
+}
+
+impl Display for FormInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let is_field = self.validation_url.is_some() || self.validation.is_some();
+
+        if is_field {
+            f.write_str(match &self.validation {
+                Some(Ok(())) => r#"<div class="form-field form-field-valid""#,
+                Some(Err(_)) => r#"<div class="form-field form-field-invalid""#,
+                None => r#"<div class="form-field""#,
+            })?;
+
+            if let Some(validation_url) = &self.validation_url {
+                write!(
+                    f,
+                    r#" hx-post="{}" hx-trigger="change" hx-include="this" hx-swap="outerHTML""#,
+                    escape_attribute_value(validation_url)
+                )?;
+            }
+
+            f.write_str(">")?;
+        }
+
+        if let Some(label) = &self.label {
+            write!(f, "<label>{} ", escape_text(label))?;
+        }
+
+        write!(
+            f,
+            r#"<input type="{}" {}"#,
+            escape_attribute_value(&self.input_type),
+            self.name_attribute()
+        )?;
+
+        if let Some(value) = &self.value {
+            write!(f, r#" value="{}""#, escape_attribute_value(value))?;
+        }
+
+        if self.checked {
+            f.write_str(" checked")?;
+        }
+
+        if self.required {
+            f.write_str(" required")?;
+        }
+
+        match &self.validation {
+            Some(Ok(())) => f.write_str(r#" aria-invalid="false""#)?,
+            Some(Err(_)) => f.write_str(r#" aria-invalid="true""#)?,
+            None => {}
+        }
+
+        f.write_str(">")?;
+
+        if self.label.is_some() {
+            f.write_str("</label>")?;
+        }
+
+        if let Some(Err(error)) = &self.validation {
+            write!(
+                f,
+                r#"<span class="form-field-error">{}</span>"#,
+                escape_text(error)
+            )?;
+        }
+
+        if is_field {
+            f.write_str("</div>")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The values of a submitted form, in submission order.
+///
+/// Typically the body of the validation routes generated by the `HtmlForm` derive macro, which
+/// validate a single field of forms whose other fields may not be valid yet.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(transparent)]
+pub struct FormValues(Vec<(String, String)>);
+
+impl FormValues {
+    /// Create form values from name-value pairs.
+    pub fn new(values: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>) -> Self {
+        Self(
+            values
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.into()))
+                .collect(),
+        )
+    }
+
+    /// Get the first value of a field, if any.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Parse the first value of a field, returning `None` if it is missing.
+    ///
+    /// # Errors
+    ///
+    /// If the value can't be parsed, the error message is returned.
+    pub fn parse<T: serde::de::DeserializeOwned>(&self, name: &str) -> Result<Option<T>, String> {
+        self.get(name)
+            .map(crate::route::parse_form_value)
+            .transpose()
+    }
+
+    /// Parse the first value of an optional field, returning `None` if it is missing or empty.
+    ///
+    /// # Errors
+    ///
+    /// If the value can't be parsed, the error message is returned.
+    pub fn parse_optional<T: serde::de::DeserializeOwned>(
+        &self,
+        name: &str,
+    ) -> Result<Option<T>, String> {
+        self.get(name)
+            .filter(|value| !value.is_empty())
+            .map(crate::route::parse_form_value)
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let name = HtmlName::from_static("email").unwrap();
+
+        assert_eq!(
+            FormInput::new(name.clone()).to_string(),
+            r#"<input type="text" name="email">"#
+        );
+        assert_eq!(
+            FormInput::new(name.clone())
+                .with_input_type("email")
+                .with_value(r#"a"b@example.com"#)
+                .with_label("E-mail <required>")
+                .with_required(true)
+                .to_string(),
+            r#"<label>E-mail &lt;required&gt; <input type="email" name="email" value="a&quot;b@example.com" required></label>"#
+        );
+        assert_eq!(
+            FormInput::new(name)
+                .with_input_type("checkbox")
+                .with_value("true")
+                .with_checked(true)
+                .to_string(),
+            r#"<input type="checkbox" name="email" value="true" checked>"#
+        );
+    }
+
+    #[test]
+    fn test_display_validation() {
+        let input = FormInput::new(HtmlName::from_static("email").unwrap())
+            .with_label("E-mail")
+            .with_value("alice");
+
+        assert_eq!(
+            input
+                .clone()
+                .with_validation(Err("Invalid <e-mail>".to_owned()))
+                .to_string(),
+            concat!(
+                r#"<div class="form-field form-field-invalid">"#,
+                r#"<label>E-mail <input type="text" name="email" value="alice" aria-invalid="true"></label>"#,
+                r#"<span class="form-field-error">Invalid &lt;e-mail&gt;</span>"#,
+                "</div>",
+            )
+        );
+        assert_eq!(
+            input.with_validation(Ok(())).to_string(),
+            concat!(
+                r#"<div class="form-field form-field-valid">"#,
+                r#"<label>E-mail <input type="text" name="email" value="alice" aria-invalid="false"></label>"#,
+                "</div>",
+            )
+        );
+    }
+
+    #[test]
+    fn test_form_values() {
+        let values = FormValues::new([("age", "42"), ("nickname", ""), ("age", "43")]);
+
+        assert_eq!(values.get("age"), Some("42"));
+        assert_eq!(values.parse::<u8>("age"), Ok(Some(42)));
+        assert_eq!(values.parse::<u8>("missing"), Ok(None));
+        assert_eq!(
+            values.parse::<u8>("nickname"),
+            Err("failed to parse value `` as u8".to_owned())
+        );
+        assert_eq!(values.parse::<String>("nickname"), Ok(Some(String::new())));
+        assert_eq!(values.parse_optional::<String>("nickname"), Ok(None));
+    }
+}
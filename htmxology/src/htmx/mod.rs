@@ -4,29 +4,54 @@ use std::{borrow::Cow, convert::Infallible, fmt::Display, str::FromStr};
 
 use axum::response::IntoResponse;
 use http::request::Parts;
-use scraper::Html;
 
 use crate::Route;
 
-mod header {
+mod capabilities;
+mod diff;
+mod form;
+mod oob;
+pub mod sanity;
+mod streaming;
+
+pub use capabilities::{
+    DEFAULT_VERSION_HEADER, HtmxCapabilities, HtmxVersion, HtmxVersionHeader, InvalidHtmxVersion,
+};
+pub use diff::FragmentDiff;
+pub use form::{FormInput, FormValues};
+pub(crate) use oob::inject_oob_attribute;
+pub use oob::oob_render_with_id_attribute;
+#[cfg(any(feature = "sse", feature = "ws"))]
+pub(crate) use oob::render_fragment;
+pub(crate) use oob::swap_into_element;
+pub use streaming::StreamingBody;
+
+pub(crate) mod header {
     /// Request headers.
-    pub(super) const HX_BOOSTED: http::HeaderName = http::HeaderName::from_static("hx-boosted");
-    pub(super) const HX_CURRENT_URL: http::HeaderName =
+    pub(crate) const HX_BOOSTED: http::HeaderName = http::HeaderName::from_static("hx-boosted");
+    pub(crate) const HX_CURRENT_URL: http::HeaderName =
         http::HeaderName::from_static("hx-current-url");
-    pub(super) const HX_HISTORY_RESTORE_REQUEST: http::HeaderName =
+    pub(crate) const HX_HISTORY_RESTORE_REQUEST: http::HeaderName =
         http::HeaderName::from_static("hx-history-restore-request");
-    pub(super) const HX_PROMPT: http::HeaderName = http::HeaderName::from_static("hx-prompt");
-    pub(super) const HX_REQUEST: http::HeaderName = http::HeaderName::from_static("hx-request");
-    pub(super) const HX_TARGET: http::HeaderName = http::HeaderName::from_static("hx-target");
-    pub(super) const HX_TRIGGER_NAME: http::HeaderName =
+    pub(crate) const HX_PROMPT: http::HeaderName = http::HeaderName::from_static("hx-prompt");
+    pub(crate) const HX_REQUEST: http::HeaderName = http::HeaderName::from_static("hx-request");
+    pub(crate) const HX_TARGET: http::HeaderName = http::HeaderName::from_static("hx-target");
+    pub(crate) const HX_TRIGGER_NAME: http::HeaderName =
         http::HeaderName::from_static("hx-trigger-name");
-    pub(super) const HX_TRIGGER: http::HeaderName = http::HeaderName::from_static("hx-trigger");
+    pub(crate) const HX_TRIGGER: http::HeaderName = http::HeaderName::from_static("hx-trigger");
 
     // Response headers.
-    pub(super) const HX_LOCATION: http::HeaderName = http::HeaderName::from_static("hx-location");
-    pub(super) const HX_PUSH_URL: http::HeaderName = http::HeaderName::from_static("hx-push-url");
-    pub(super) const HX_REDIRECT: http::HeaderName = http::HeaderName::from_static("hx-redirect");
-    pub(super) const HX_RETARGET: http::HeaderName = http::HeaderName::from_static("hx-retarget");
+    pub(crate) const HX_LOCATION: http::HeaderName = http::HeaderName::from_static("hx-location");
+    pub(crate) const HX_PUSH_URL: http::HeaderName = http::HeaderName::from_static("hx-push-url");
+    pub(crate) const HX_REDIRECT: http::HeaderName = http::HeaderName::from_static("hx-redirect");
+    pub(crate) const HX_REPLACE_URL: http::HeaderName =
+        http::HeaderName::from_static("hx-replace-url");
+    pub(crate) const HX_RESWAP: http::HeaderName = http::HeaderName::from_static("hx-reswap");
+    pub(crate) const HX_RETARGET: http::HeaderName = http::HeaderName::from_static("hx-retarget");
+    pub(crate) const HX_TRIGGER_AFTER_SETTLE: http::HeaderName =
+        http::HeaderName::from_static("hx-trigger-after-settle");
+    pub(crate) const HX_TRIGGER_AFTER_SWAP: http::HeaderName =
+        http::HeaderName::from_static("hx-trigger-after-swap");
 }
 
 /// An HTMX request header extractor.
@@ -60,6 +85,57 @@ pub enum Request {
     },
 }
 
+impl Request {
+    /// Get the route of the page the request was issued from.
+    ///
+    /// The `HX-Current-URL` header is parsed as a `GET` route with [`Route::from_uri`], without
+    /// the [path prefix](crate::path_prefix) of the request, if any.
+    ///
+    /// Returns `None` for classic requests, or if the current URL is missing or invalid.
+    pub fn current_route<R: Route>(&self) -> Option<Result<R, crate::ParseError>> {
+        match self {
+            Self::Classic => None,
+            Self::Htmx { current_url, .. } => {
+                let uri: http::Uri = current_url.parse().ok()?;
+                let path_and_query = uri
+                    .path_and_query()
+                    .map_or("/", http::uri::PathAndQuery::as_str);
+                let uri: http::Uri = crate::route::strip_path_prefix(path_and_query)
+                    .parse()
+                    .ok()?;
+
+                Some(R::from_uri(&uri, &http::Method::GET))
+            }
+        }
+    }
+
+    /// Check whether the request targets the element a route is meant to be swapped into.
+    ///
+    /// The `HX-Target` header, which holds the `id` of the target element, is compared to the
+    /// [expected target](Route::expected_target) of the route.
+    ///
+    /// Classic, boosted and history restore requests, which render whole pages, and routes without
+    /// an expected target always match.
+    pub fn matches_target<R: Route>(&self, route: &R) -> bool {
+        match (self, route.expected_target()) {
+            (
+                Self::Htmx {
+                    target,
+                    boosted: false,
+                    history_restore_request: false,
+                    ..
+                },
+                Some(expected),
+            ) => {
+                let id = expected.strip_prefix('#').unwrap_or(expected);
+
+                target.as_ref().is_some_and(|target| target == id)
+            }
+            _ => true,
+        }
+    }
+}
+
 impl<S: Send + Sync> axum::extract::FromRequestParts<S> for Request {
     type Rejection = Infallible;
 
@@ -99,6 +175,9 @@ pub struct Response<T> {
     /// The main insert that constitutes the body of the response.
     body: T,
 
+    /// The status code of the response.
+    status: http::StatusCode,
+
     /// The content-type of the response.
     content_type: http::HeaderValue,
 
@@ -112,7 +191,159 @@ pub struct Response<T> {
     /// out-of-band by HTMX.
     ///
     /// The `hx-swap-oob` attribute is injected directly into the root element of each OOB fragment.
-    oob_elements: Vec<(InsertStrategy, Cow<'static, str>, Box<dyn Display + Send>)>,
+    oob_elements: Vec<(
+        InsertStrategy,
+        Cow<'static, str>,
+        Box<dyn oob::OobElement + Send>,
+    )>,
+
+    /// The events triggered on the client, with their timing and payload.
+    triggers: Vec<(TriggerTiming, String, serde_json::Value)>,
+
+    /// The inline assets injected in the body of the response, once rendered.
+    inline_assets: Option<crate::InlineAssets>,
+
+    /// The layout slots whose placeholders are replaced in the body of the response, once
+    /// rendered.
+    layout_slots: Option<crate::LayoutSlots>,
+}
+
+/// When the events of a `HX-Trigger*` response header are triggered on the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TriggerTiming {
+    /// As soon as the response is received (`HX-Trigger`).
+    Received,
+
+    /// After the swap step (`HX-Trigger-After-Swap`).
+    AfterSwap,
+
+    /// After the settle step (`HX-Trigger-After-Settle`).
+    AfterSettle,
+}
+
+impl TriggerTiming {
+    /// The response header for the timing.
+    fn header(self) -> http::HeaderName {
+        match self {
+            Self::Received => header::HX_TRIGGER,
+            Self::AfterSwap => header::HX_TRIGGER_AFTER_SWAP,
+            Self::AfterSettle => header::HX_TRIGGER_AFTER_SETTLE,
+        }
+    }
+}
+
+/// An event triggered on the client through a `HX-Trigger*` response header.
+///
+/// This is implemented for bare event names (`&str` and `String`), and for `(name, payload)`
+/// tuples where the payload implements `serde::Serialize`.
+pub trait TriggerEvent {
+    /// Get the name and payload of the event.
+    ///
+    /// Bare events have a `null` payload.
+    fn into_event(self) -> Result<(String, serde_json::Value), serde_json::Error>;
+}
+
+impl TriggerEvent for &str {
+    fn into_event(self) -> Result<(String, serde_json::Value), serde_json::Error> {
+        Ok((self.to_owned(), serde_json::Value::Null))
+    }
+}
+
+impl TriggerEvent for String {
+    fn into_event(self) -> Result<(String, serde_json::Value), serde_json::Error> {
+        Ok((self, serde_json::Value::Null))
+    }
+}
+
+impl<N: Into<String>, P: serde::Serialize> TriggerEvent for (N, P) {
+    fn into_event(self) -> Result<(String, serde_json::Value), serde_json::Error> {
+        Ok((self.0.into(), serde_json::to_value(self.1)?))
+    }
+}
+
+/// Get the value of a `HX-Trigger*` header for the specified events.
+///
+/// Bare events with simple names are rendered as a comma-separated list, and a JSON object mapping
+/// names to payloads is used otherwise. Events triggered several times keep their last payload.
+fn trigger_header_value<'e>(
+    events: impl IntoIterator<Item = (&'e str, &'e serde_json::Value)>,
+) -> http::HeaderValue {
+    let mut names = Vec::new();
+    let mut payloads = serde_json::Map::new();
+
+    for (name, payload) in events {
+        if !payloads.contains_key(name) {
+            names.push(name);
+        }
+
+        payloads.insert(name.to_owned(), payload.clone());
+    }
+
+    let is_simple = payloads.values().all(serde_json::Value::is_null)
+        && names.iter().all(|name| {
+            name.chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+        });
+
+    let value = if is_simple {
+        names.join(", ")
+    } else {
+        serde_json::Value::Object(payloads).to_string()
+    };
+
+    http::HeaderValue::from_bytes(value.as_bytes())
+        .expect("JSON-encoded trigger events should be a valid header value")
+}
+
+/// The empty body of a [`Response`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Empty;
+
+impl Display for Empty {
+    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Ok(())
+    }
+}
+
+impl Response<Empty> {
+    /// Create a `200 OK` response with an empty body.
+    ///
+    /// The main swap empties the target, or removes it with an `outerHTML` swap, and the
+    /// out-of-band inserts of the response are processed as usual.
+    pub fn empty() -> Self {
+        Self::new(Empty)
+    }
+
+    /// Create a `204 No Content` response.
+    ///
+    /// HTMX doesn't swap anything for `204` responses, including the out-of-band inserts, but
+    /// still processes their headers, like the triggered events.
+    pub fn no_content() -> Self {
+        Self::new(Empty).with_status(http::StatusCode::NO_CONTENT)
+    }
+
+    /// Create the response of a delete action, removing the deleted element from the page.
+    ///
+    /// The response has an empty body and doesn't swap its target, while the element is removed
+    /// through an out-of-band `delete` swap, so that the action works regardless of the element
+    /// that issued it.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// Route::DeleteTodo { id } => {
+    ///     todos.remove(id);
+    ///
+    ///     let todo_id = HtmlId::try_from(format!("todo-{id}"))?;
+    ///
+    ///     Response::deleted(&todo_id).with_count_update("todos-changed", todos.len())
+    /// }
+    /// ```
+    pub fn deleted(element: &impl Identity) -> Self {
+        Self::empty()
+            .with_reswap(InsertStrategy::None)
+            .with_deleted(element)
+    }
 }
 
 impl<T: Default> Default for Response<T> {
@@ -167,87 +398,167 @@ impl Display for InsertStrategy {
     }
 }
 
-/// Inject the `hx-swap-oob` attribute into an HTML fragment.
+/// An HTMX swap specification: an insert strategy with optional modifiers.
 ///
-/// This function parses the HTML, finds the root element, and adds the `hx-swap-oob` attribute.
-/// If there are multiple root elements or if the fragment cannot be parsed, it wraps the content
-/// in a `<template>` tag with the attribute.
+/// Rendered as the value of `hx-swap` attributes or `HX-Reswap` headers, like
+/// `outerHTML swap:1s scroll:top`.
 ///
-/// # Arguments
-///
-/// * `html` - The HTML fragment to modify
-/// * `strategy` - The swap strategy to use
-/// * `target` - The CSS selector target for the swap
+/// # Example
 ///
-/// # Returns
+/// ```ignore
+/// let swap = Swap::new(InsertStrategy::OuterHtml)
+///     .with_modifier(SwapModifier::SwapDelay(Duration::from_secs(1)))
+///     .with_modifier(SwapModifier::Scroll(None, ScrollPosition::Top));
 ///
-/// The modified HTML with the `hx-swap-oob` attribute injected.
-fn inject_oob_attribute(html: &str, strategy: &InsertStrategy, target: &str) -> String {
-    // Parse the HTML fragment
-    let fragment = Html::parse_fragment(html);
-
-    // Try to find root elements - scraper puts fragment children directly under the root
-    // We need to collect all element nodes at the root level
-    let root_elements: Vec<_> = fragment
-        .root_element()
-        .children()
-        .filter_map(scraper::ElementRef::wrap)
-        .collect();
-
-    // If we have exactly one root element, inject the attribute
-    if root_elements.len() == 1 {
-        let root = root_elements[0];
-        let tag_name = root.value().name();
-
-        // Build the hx-swap-oob attribute value
-        let oob_value = if target.starts_with('#') && strategy.to_string() == "outerHTML" {
-            // Simple case: if it's an ID selector and outerHTML, we can use "true"
-            // But only if the element has a matching id attribute
-            if let Some(id_attr) = root.value().attr("id") {
-                if format!("#{}", id_attr) == target {
-                    "true".to_string()
-                } else {
-                    format!("{}:{}", strategy, target)
-                }
-            } else {
-                format!("{}:{}", strategy, target)
-            }
-        } else {
-            format!("{}:{}", strategy, target)
-        };
+/// assert_eq!(swap.to_string(), "outerHTML swap:1s scroll:top");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Swap {
+    strategy: InsertStrategy,
+    modifiers: Vec<SwapModifier>,
+}
 
-        // Get all attributes
-        let mut attrs = Vec::new();
-        for (name, value) in root.value().attrs() {
-            // Skip existing hx-swap-oob attributes
-            if name != "hx-swap-oob" {
-                attrs.push(format!("{}=\"{}\"", name, value));
-            }
+impl Swap {
+    /// Create a new swap specification with no modifiers.
+    pub fn new(strategy: InsertStrategy) -> Self {
+        Self {
+            strategy,
+            modifiers: Vec::new(),
         }
+    }
 
-        // Add the hx-swap-oob attribute
-        attrs.push(format!("hx-swap-oob=\"{}\"", oob_value));
+    /// Add a modifier to the swap specification.
+    pub fn with_modifier(mut self, modifier: SwapModifier) -> Self {
+        self.modifiers.push(modifier);
+        self
+    }
+}
 
-        // Reconstruct the element with the new attribute
-        let attrs_str = attrs.join(" ");
-        let inner_html = root.inner_html();
+impl From<InsertStrategy> for Swap {
+    fn from(strategy: InsertStrategy) -> Self {
+        Self::new(strategy)
+    }
+}
 
-        // Handle self-closing tags
-        if inner_html.is_empty() && is_void_element(tag_name) {
-            format!("<{} {} />", tag_name, attrs_str)
-        } else {
-            format!("<{} {}>{}</{}>", tag_name, attrs_str, inner_html, tag_name)
+impl Display for Swap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.strategy)?;
+
+        for modifier in &self.modifiers {
+            write!(f, " {modifier}")?;
         }
+
+        Ok(())
+    }
+}
+
+/// A modifier of a HTMX swap specification.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum SwapModifier {
+    /// Wait before swapping the new content (`swap:1s`).
+    SwapDelay(std::time::Duration),
+
+    /// Wait between the swap and settle steps (`settle:100ms`).
+    SettleDelay(std::time::Duration),
+
+    /// Scroll the target, or the element matching a selector, to the top or bottom
+    /// (`scroll:top`, `scroll:#list:bottom`).
+    Scroll(Option<String>, ScrollPosition),
+
+    /// Scroll the viewport to show the top or bottom of the target, or of the element matching a
+    /// selector (`show:top`, `show:window:bottom`).
+    Show(Option<String>, ScrollPosition),
+
+    /// Whether to use the View Transitions API (`transition:true`).
+    Transition(bool),
+
+    /// Whether to ignore any `<title>` tag in the new content (`ignoreTitle:true`).
+    IgnoreTitle(bool),
+
+    /// Whether to scroll to the focused element after the swap (`focus-scroll:true`).
+    FocusScroll(bool),
+
+    /// Custom modifier.
+    ///
+    /// This is a catch-all variant for modifiers that are not (yet) covered by the other
+    /// variants.
+    Custom(String),
+}
+
+/// A scroll position, for the `scroll` and `show` swap modifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollPosition {
+    /// The top of the element.
+    Top,
+
+    /// The bottom of the element.
+    Bottom,
+}
+
+impl Display for ScrollPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Top => write!(f, "top"),
+            Self::Bottom => write!(f, "bottom"),
+        }
+    }
+}
+
+/// Format a duration as a HTMX time interval, in seconds if possible.
+fn fmt_interval(
+    f: &mut std::fmt::Formatter<'_>,
+    duration: std::time::Duration,
+) -> std::fmt::Result {
+    let millis = duration.as_millis();
+
+    if millis.is_multiple_of(1000) {
+        write!(f, "{}s", millis / 1000)
     } else {
-        // Multiple root elements or no root elements - wrap in template
-        let oob_value = format!("{}:{}", strategy, target);
-        format!(
-            "<template hx-swap-oob=\"{}\">{}</template>",
-            oob_value, html
-        )
+        write!(f, "{millis}ms")
+    }
+}
+
+impl Display for SwapModifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SwapDelay(duration) => {
+                write!(f, "swap:")?;
+                fmt_interval(f, *duration)
+            }
+            Self::SettleDelay(duration) => {
+                write!(f, "settle:")?;
+                fmt_interval(f, *duration)
+            }
+            Self::Scroll(None, position) => write!(f, "scroll:{position}"),
+            Self::Scroll(Some(selector), position) => write!(f, "scroll:{selector}:{position}"),
+            Self::Show(None, position) => write!(f, "show:{position}"),
+            Self::Show(Some(selector), position) => write!(f, "show:{selector}:{position}"),
+            Self::Transition(value) => write!(f, "transition:{value}"),
+            Self::IgnoreTitle(value) => write!(f, "ignoreTitle:{value}"),
+            Self::FocusScroll(value) => write!(f, "focus-scroll:{value}"),
+            Self::Custom(custom) => write!(f, "{custom}"),
+        }
     }
 }
 
+/// Escape a value for a double-quoted HTML attribute.
+pub(crate) fn escape_attribute_value(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Escape a value for use as HTML text content.
+pub(crate) fn escape_text(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
 /// Check if an HTML tag is a void element (self-closing).
 fn is_void_element(tag: &str) -> bool {
     matches!(
@@ -274,13 +585,25 @@ impl<T> Response<T> {
     pub fn new(body: T) -> Self {
         Self {
             body,
+            status: http::StatusCode::OK,
             content_type: http::HeaderValue::from_static("text/html"),
             htmx_retarget: None,
             extra_headers: http::HeaderMap::new(),
             oob_elements: vec![],
+            triggers: vec![],
+            inline_assets: None,
+            layout_slots: None,
         }
     }
 
+    /// Set the status code of the response.
+    ///
+    /// Note that HTMX doesn't swap the content of error responses by default.
+    pub fn with_status(mut self, status: http::StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
     /// Set the content type of the response.
     pub fn with_content_type(mut self, content_type: http::HeaderValue) -> Self {
         self.content_type = content_type;
@@ -294,21 +617,65 @@ impl<T> Response<T> {
     }
 
     /// Retarget the response to the given target.
-    ///
-    /// # Panics
-    ///
-    /// If the target is not a valid HTTP header value, the call will panic.
     pub fn with_retarget(mut self, htmx_retarget: http::HeaderValue) -> Self {
         self.htmx_retarget = Some(htmx_retarget);
         self
     }
 
+    /// Retarget the response to the given CSS selector.
+    ///
+    /// # Errors
+    ///
+    /// If the target is not a valid HTTP header value, an error is returned.
+    pub fn try_with_retarget(self, target: impl AsRef<str>) -> Result<Self, ResponseBuildError> {
+        let htmx_retarget = header_value(header::HX_RETARGET, target.as_ref())?;
+
+        Ok(self.with_retarget(htmx_retarget))
+    }
+
+    /// Change the swap strategy of the response, through the `HX-Reswap` header.
+    ///
+    /// Accepts either a bare [`InsertStrategy`] or a [`Swap`] with modifiers. Calling this method
+    /// again replaces the previous swap strategy.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// response.with_reswap(
+    ///     Swap::new(InsertStrategy::BeforeEnd)
+    ///         .with_modifier(SwapModifier::Scroll(None, ScrollPosition::Bottom)),
+    /// )
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the swap specification is not a valid HTTP header value (which can only happen with
+    /// custom strategies or modifiers), the call will panic.
+    pub fn with_reswap(self, swap: impl Into<Swap>) -> Self {
+        Self::or_panic(self.try_with_reswap(swap))
+    }
+
+    /// Change the swap strategy of the response, through the `HX-Reswap` header.
+    ///
+    /// This is the non-panicking variant of [`Response::with_reswap`].
+    ///
+    /// # Errors
+    ///
+    /// If the swap specification is not a valid HTTP header value, an error is returned.
+    pub fn try_with_reswap(mut self, swap: impl Into<Swap>) -> Result<Self, ResponseBuildError> {
+        let header_value = header_value(header::HX_RESWAP, &swap.into().to_string())?;
+
+        self.extra_headers.insert(header::HX_RESWAP, header_value);
+        Ok(self)
+    }
+
     /// Add an out-of-band insert to the response using the fragment's specified swap strategy.
     ///
     /// This method uses the element's ID (from the `Identity` trait) as the target selector
     /// and the swap strategy from the `Fragment` trait's `insert_strategy()` method.
     /// The `hx-swap-oob` attribute will be injected directly into the root element of the
-    /// rendered HTML fragment.
+    /// rendered HTML fragment, unless the fragment renders it itself with
+    /// [`Fragment::oob_render`].
     ///
     /// # Example
     ///
@@ -319,10 +686,16 @@ impl<T> Response<T> {
     /// // The strategy comes from notification.insert_strategy()
     /// response.with_oob(notification)
     /// ```
-    pub fn with_oob(self, oob_element: impl Fragment + Send + 'static) -> Self {
+    pub fn with_oob(mut self, oob_element: impl Fragment + Send + 'static) -> Self {
         let target = format!("#{}", oob_element.id());
         let strategy = oob_element.insert_strategy();
-        self.with_raw_oob(strategy, target, oob_element)
+        self.oob_elements.push((
+            strategy,
+            target.into(),
+            Box::new(oob::FragmentOob(oob_element)),
+        ));
+
+        self
     }
 
     /// Add an out-of-band insert to the response using the specified insert strategy.
@@ -339,12 +712,62 @@ impl<T> Response<T> {
         target: impl Into<Cow<'static, str>>,
         oob_element: impl Display + Send + 'static,
     ) -> Self {
-        self.oob_elements
-            .push((insert_strategy, target.into(), Box::new(oob_element)));
+        self.oob_elements.push((
+            insert_strategy,
+            target.into(),
+            Box::new(oob::RawOob(oob_element)),
+        ));
 
         self
     }
 
+    /// Remove an element from the page, through an out-of-band `delete` swap.
+    ///
+    /// Accepts either the fragment that was deleted, or its [`HtmlId`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// Response::new(Counter { count: todos.len() }).with_deleted(&todo)
+    /// ```
+    pub fn with_deleted(self, element: &impl Identity) -> Self {
+        self.with_raw_oob(
+            InsertStrategy::Delete,
+            format!("#{}", element.id()),
+            "<div></div>",
+        )
+    }
+
+    /// Trigger an event on the client carrying an updated count, as `{"count": count}`.
+    ///
+    /// This is typically used after deleting or adding an item, for the elements displaying the
+    /// number of items to update themselves:
+    ///
+    /// ```html
+    /// <span hx-on:todos-changed="this.textContent = event.detail.count"></span>
+    /// ```
+    pub fn with_count_update(self, event: impl Into<String>, count: usize) -> Self {
+        self.with_trigger((event.into(), serde_json::json!({ "count": count })))
+    }
+
+    /// Inject the inline assets of a registry in the response.
+    ///
+    /// The assets are injected once the body and the out-of-band inserts have been rendered, so
+    /// that the assets registered while rendering them are included. They are inserted at the
+    /// end of the `<head>` element of documents, or at the start of fragments.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let assets = InlineAssets::new().with_nonce(&nonce);
+    ///
+    /// Response::new(PageTemplate { assets: assets.clone(), .. }).with_inline_assets(assets)
+    /// ```
+    pub fn with_inline_assets(mut self, inline_assets: crate::InlineAssets) -> Self {
+        self.inline_assets = Some(inline_assets);
+        self
+    }
+
     /// Add an extra HTTP header to the response.
     pub fn with_header(mut self, name: http::HeaderName, value: http::HeaderValue) -> Self {
         self.extra_headers.append(name, value);
@@ -356,15 +779,19 @@ impl<T> Response<T> {
     /// # Panics
     ///
     /// If another `hx-push-url` header is already present, the call will panic.
-    pub fn without_push_url(mut self) -> Self {
-        assert!(
-            self.extra_headers
-                .insert(header::HX_PUSH_URL, http::HeaderValue::from_static("false"))
-                .is_none(),
-            "hx-push-url header already present"
-        );
+    pub fn without_push_url(self) -> Self {
+        Self::or_panic(self.try_without_push_url())
+    }
 
-        self
+    /// Indicate that the response should not push the URL to the browser history.
+    ///
+    /// This is the non-panicking variant of [`Response::without_push_url`].
+    ///
+    /// # Errors
+    ///
+    /// If another `hx-push-url` header is already present, an error is returned.
+    pub fn try_without_push_url(self) -> Result<Self, ResponseBuildError> {
+        self.try_insert_header(header::HX_PUSH_URL, http::HeaderValue::from_static("false"))
     }
 
     /// Indicate that the response should push the URL to the browser history.
@@ -374,17 +801,87 @@ impl<T> Response<T> {
     /// If another `hx-push-url` header is already present, the call will panic.
     ///
     /// The URL to push must be a valid HTTP header value or the call will panic.
-    pub fn with_push_url(mut self, url: &http::Uri) -> Self {
-        let header_value = http::HeaderValue::from_str(&url.to_string()).expect("invalid URL");
+    pub fn with_push_url(self, url: &http::Uri) -> Self {
+        Self::or_panic(self.try_with_push_url(url))
+    }
 
-        assert!(
-            self.extra_headers
-                .insert(header::HX_PUSH_URL, header_value,)
-                .is_none(),
-            "hx-push-url header already present"
-        );
+    /// Indicate that the response should push the URL to the browser history.
+    ///
+    /// This is the non-panicking variant of [`Response::with_push_url`].
+    ///
+    /// # Errors
+    ///
+    /// If another `hx-push-url` header is already present, or if the URL is not a valid HTTP
+    /// header value, an error is returned.
+    pub fn try_with_push_url(self, url: &http::Uri) -> Result<Self, ResponseBuildError> {
+        let header_value = header_value(header::HX_PUSH_URL, &url.to_string())?;
 
-        self
+        self.try_insert_header(header::HX_PUSH_URL, header_value)
+    }
+
+    /// Indicate that the response should push the URL of a route to the browser history.
+    ///
+    /// The URL is the [`Display`] representation of the route.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// Response::new(html)
+    ///     .with_push_route(&AppRoute::Blog(BlogRoute::Post { id: 42 }))
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If another `hx-push-url` header is already present, the call will panic.
+    pub fn with_push_route(self, route: &impl Route) -> Self {
+        Self::or_panic(self.try_with_push_route(route))
+    }
+
+    /// Indicate that the response should push the URL of a route to the browser history.
+    ///
+    /// This is the non-panicking variant of [`Response::with_push_route`].
+    ///
+    /// # Errors
+    ///
+    /// If another `hx-push-url` header is already present, or if the URL of the route is not a
+    /// valid HTTP header value, an error is returned.
+    pub fn try_with_push_route(self, route: &impl Route) -> Result<Self, ResponseBuildError> {
+        let header_value = header_value(header::HX_PUSH_URL, &route.to_string())?;
+
+        self.try_insert_header(header::HX_PUSH_URL, header_value)
+    }
+
+    /// Indicate that the response should replace the current URL in the browser location bar
+    /// with the URL of a route, without creating a new history entry.
+    ///
+    /// The URL is the [`Display`] representation of the route.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// Response::new(html)
+    ///     .with_replace_route(&AppRoute::Search { query: Some("htmx".to_owned()) })
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If another `hx-replace-url` header is already present, the call will panic.
+    pub fn with_replace_route(self, route: &impl Route) -> Self {
+        Self::or_panic(self.try_with_replace_route(route))
+    }
+
+    /// Indicate that the response should replace the current URL with the URL of a route.
+    ///
+    /// This is the non-panicking variant of [`Response::with_replace_route`].
+    ///
+    /// # Errors
+    ///
+    /// If another `hx-replace-url` header is already present, or if the URL of the route is not a
+    /// valid HTTP header value, an error is returned.
+    pub fn try_with_replace_route(self, route: &impl Route) -> Result<Self, ResponseBuildError> {
+        let header_value = header_value(header::HX_REPLACE_URL, &route.to_string())?;
+
+        self.try_insert_header(header::HX_REPLACE_URL, header_value)
     }
 
     /// Trigger a client-side redirect to a new URL that does a full page reload.
@@ -404,17 +901,23 @@ impl<T> Response<T> {
     /// If another `hx-redirect` header is already present, the call will panic.
     ///
     /// The URL must be a valid HTTP header value or the call will panic.
-    pub fn with_redirect(mut self, url: impl AsRef<str>) -> Self {
-        let header_value = http::HeaderValue::from_str(url.as_ref()).expect("invalid redirect URL");
+    pub fn with_redirect(self, url: impl AsRef<str>) -> Self {
+        Self::or_panic(self.try_with_redirect(url))
+    }
 
-        assert!(
-            self.extra_headers
-                .insert(header::HX_REDIRECT, header_value)
-                .is_none(),
-            "hx-redirect header already present"
-        );
+    /// Trigger a client-side redirect to a new URL that does a full page reload.
+    ///
+    /// This is the non-panicking variant of [`Response::with_redirect`], suitable for URLs that
+    /// come from user input.
+    ///
+    /// # Errors
+    ///
+    /// If another `hx-redirect` header is already present, or if the URL is not a valid HTTP
+    /// header value, an error is returned.
+    pub fn try_with_redirect(self, url: impl AsRef<str>) -> Result<Self, ResponseBuildError> {
+        let header_value = header_value(header::HX_REDIRECT, url.as_ref())?;
 
-        self
+        self.try_insert_header(header::HX_REDIRECT, header_value)
     }
 
     /// Trigger a client-side redirect without a full page reload (AJAX-based navigation).
@@ -436,18 +939,23 @@ impl<T> Response<T> {
     /// If another `hx-location` header is already present, the call will panic.
     ///
     /// The URL must be a valid HTTP header value or the call will panic.
-    pub fn with_location(mut self, path: impl AsRef<str>) -> Self {
-        let header_value =
-            http::HeaderValue::from_str(path.as_ref()).expect("invalid location path");
+    pub fn with_location(self, path: impl AsRef<str>) -> Self {
+        Self::or_panic(self.try_with_location(path))
+    }
 
-        assert!(
-            self.extra_headers
-                .insert(header::HX_LOCATION, header_value)
-                .is_none(),
-            "hx-location header already present"
-        );
+    /// Trigger a client-side redirect without a full page reload (AJAX-based navigation).
+    ///
+    /// This is the non-panicking variant of [`Response::with_location`], suitable for paths
+    /// that come from user input.
+    ///
+    /// # Errors
+    ///
+    /// If another `hx-location` header is already present, or if the path is not a valid HTTP
+    /// header value, an error is returned.
+    pub fn try_with_location(self, path: impl AsRef<str>) -> Result<Self, ResponseBuildError> {
+        let header_value = header_value(header::HX_LOCATION, path.as_ref())?;
 
-        self
+        self.try_insert_header(header::HX_LOCATION, header_value)
     }
 
     /// Trigger a client-side redirect with detailed configuration using JSON.
@@ -478,10 +986,26 @@ impl<T> Response<T> {
     ///
     /// The generated JSON must be a valid HTTP header value or the call will panic.
     pub fn with_location_details(
-        mut self,
+        self,
         path: impl AsRef<str>,
         target: Option<impl AsRef<str>>,
     ) -> Self {
+        Self::or_panic(self.try_with_location_details(path, target))
+    }
+
+    /// Trigger a client-side redirect with detailed configuration using JSON.
+    ///
+    /// This is the non-panicking variant of [`Response::with_location_details`].
+    ///
+    /// # Errors
+    ///
+    /// If another `hx-location` header is already present, or if the generated JSON is not a
+    /// valid HTTP header value, an error is returned.
+    pub fn try_with_location_details(
+        self,
+        path: impl AsRef<str>,
+        target: Option<impl AsRef<str>>,
+    ) -> Result<Self, ResponseBuildError> {
         let location_value = if let Some(target) = target {
             // Use JSON format for advanced configuration
             serde_json::json!({
@@ -494,44 +1018,284 @@ impl<T> Response<T> {
             path.as_ref().to_string()
         };
 
-        let header_value =
-            http::HeaderValue::from_str(&location_value).expect("invalid location value");
+        let header_value = header_value(header::HX_LOCATION, &location_value)?;
 
-        assert!(
-            self.extra_headers
-                .insert(header::HX_LOCATION, header_value)
-                .is_none(),
-            "hx-location header already present"
+        self.try_insert_header(header::HX_LOCATION, header_value)
+    }
+
+    /// Trigger an event on the client as soon as the response is received, through the
+    /// `HX-Trigger` header.
+    ///
+    /// The event can be a bare event name, or a `(name, payload)` tuple where the payload is
+    /// serialized to JSON. Multiple events are merged into a single header.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// response
+    ///     .with_trigger("itemSaved")
+    ///     .with_trigger(("showMessage", json!({"level": "info", "text": "Saved!"})))
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the payload fails to serialize, the call will panic.
+    pub fn with_trigger(self, event: impl TriggerEvent) -> Self {
+        Self::or_panic(self.try_with_trigger(event))
+    }
+
+    /// Trigger an event on the client as soon as the response is received.
+    ///
+    /// This is the non-panicking variant of [`Response::with_trigger`].
+    ///
+    /// # Errors
+    ///
+    /// If the payload fails to serialize, an error is returned.
+    pub fn try_with_trigger(self, event: impl TriggerEvent) -> Result<Self, ResponseBuildError> {
+        self.try_with_trigger_at(TriggerTiming::Received, event)
+    }
+
+    /// Trigger an event on the client after the swap step, through the `HX-Trigger-After-Swap`
+    /// header.
+    ///
+    /// See [`Response::with_trigger`] for details.
+    ///
+    /// # Panics
+    ///
+    /// If the payload fails to serialize, the call will panic.
+    pub fn with_trigger_after_swap(self, event: impl TriggerEvent) -> Self {
+        Self::or_panic(self.try_with_trigger_after_swap(event))
+    }
+
+    /// Trigger an event on the client after the swap step.
+    ///
+    /// This is the non-panicking variant of [`Response::with_trigger_after_swap`].
+    ///
+    /// # Errors
+    ///
+    /// If the payload fails to serialize, an error is returned.
+    pub fn try_with_trigger_after_swap(
+        self,
+        event: impl TriggerEvent,
+    ) -> Result<Self, ResponseBuildError> {
+        self.try_with_trigger_at(TriggerTiming::AfterSwap, event)
+    }
+
+    /// Trigger an event on the client after the settle step, through the
+    /// `HX-Trigger-After-Settle` header.
+    ///
+    /// See [`Response::with_trigger`] for details.
+    ///
+    /// # Panics
+    ///
+    /// If the payload fails to serialize, the call will panic.
+    pub fn with_trigger_after_settle(self, event: impl TriggerEvent) -> Self {
+        Self::or_panic(self.try_with_trigger_after_settle(event))
+    }
+
+    /// Trigger an event on the client after the settle step.
+    ///
+    /// This is the non-panicking variant of [`Response::with_trigger_after_settle`].
+    ///
+    /// # Errors
+    ///
+    /// If the payload fails to serialize, an error is returned.
+    pub fn try_with_trigger_after_settle(
+        self,
+        event: impl TriggerEvent,
+    ) -> Result<Self, ResponseBuildError> {
+        self.try_with_trigger_at(TriggerTiming::AfterSettle, event)
+    }
+
+    fn try_with_trigger_at(
+        mut self,
+        timing: TriggerTiming,
+        event: impl TriggerEvent,
+    ) -> Result<Self, ResponseBuildError> {
+        let (name, payload) = event
+            .into_event()
+            .map_err(ResponseBuildError::TriggerPayload)?;
+
+        self.triggers.push((timing, name, payload));
+        Ok(self)
+    }
+
+    /// Insert a header that can only be set once.
+    fn try_insert_header(
+        mut self,
+        name: http::HeaderName,
+        value: http::HeaderValue,
+    ) -> Result<Self, ResponseBuildError> {
+        if self.extra_headers.contains_key(&name) {
+            return Err(ResponseBuildError::DuplicateHeader(name));
+        }
+
+        self.extra_headers.insert(name, value);
+        Ok(self)
+    }
+
+    /// Unwrap the result of a fallible builder method, for its panicking variant.
+    #[track_caller]
+    fn or_panic(result: Result<Self, ResponseBuildError>) -> Self {
+        result.unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+/// An error that occurs when building a [`Response`] with one of its fallible builder methods.
+#[derive(Debug, thiserror::Error)]
+pub enum ResponseBuildError {
+    /// A header value is not a valid HTTP header value.
+    #[error("invalid value for the {name} header")]
+    InvalidHeaderValue {
+        /// The name of the header.
+        name: http::HeaderName,
+
+        /// The underlying error.
+        #[source]
+        source: http::header::InvalidHeaderValue,
+    },
+
+    /// A header that can only be set once is already present.
+    #[error("{0} header already present")]
+    DuplicateHeader(http::HeaderName),
+
+    /// The payload of a trigger event failed to serialize.
+    #[error("failed to serialize the trigger event payload")]
+    TriggerPayload(#[source] serde_json::Error),
+}
+
+/// Convert a string to the value of a header.
+fn header_value(
+    name: http::HeaderName,
+    value: &str,
+) -> Result<http::HeaderValue, ResponseBuildError> {
+    http::HeaderValue::from_str(value)
+        .map_err(|source| ResponseBuildError::InvalidHeaderValue { name, source })
+}
+
+impl<T> Response<T> {
+    /// Take the headers of the response, including its `HX-Retarget` and `HX-Trigger*` headers.
+    fn take_headers(&mut self) -> http::HeaderMap {
+        let mut headers: http::HeaderMap =
+            [(http::header::CONTENT_TYPE, self.content_type.clone())]
+                .into_iter()
+                .chain(
+                    self.htmx_retarget
+                        .take()
+                        .map(|htmx_target| (header::HX_RETARGET, htmx_target)),
+                )
+                .chain(
+                    std::mem::take(&mut self.extra_headers)
+                        .into_iter()
+                        .filter_map(|(name, value)| name.map(|name| (name, value))),
+                )
+                .collect();
+
+        for timing in [
+            TriggerTiming::Received,
+            TriggerTiming::AfterSwap,
+            TriggerTiming::AfterSettle,
+        ] {
+            let mut events = self
+                .triggers
+                .iter()
+                .filter(|(t, _, _)| *t == timing)
+                .map(|(_, name, payload)| (name.as_str(), payload))
+                .peekable();
+
+            if events.peek().is_some() {
+                headers.insert(timing.header(), trigger_header_value(events));
+            }
+        }
+
+        #[cfg(debug_assertions)]
+        sanity::report(
+            headers
+                .iter()
+                .flat_map(|(name, value)| sanity::check_header(name, value)),
         );
 
+        headers
+    }
+
+    /// Render the out-of-band inserts of the response, with their `hx-swap-oob` attribute.
+    fn render_oob_elements(&mut self) -> String {
+        std::mem::take(&mut self.oob_elements)
+            .into_iter()
+            .map(|(strategy, target, oob_element)| oob_element.render_oob(&strategy, &target))
+            .collect()
+    }
+}
+
+/// Check whether response headers declare an HTML body.
+#[cfg(debug_assertions)]
+fn is_html(headers: &http::HeaderMap) -> bool {
+    headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"))
+}
+
+impl<T: Display> Response<T> {
+    /// Replace the slot placeholders of the response by the contents of a registry.
+    ///
+    /// The placeholders are replaced once the body and the out-of-band inserts have been
+    /// rendered, so that the slots filled while rendering them are included. Streaming responses
+    /// don't support slots, as their placeholders are sent before the slots are filled.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let slots = LayoutSlots::new();
+    ///
+    /// Response::new(PageTemplate { slots: slots.clone(), .. }).with_layout_slots(slots)
+    /// ```
+    pub fn with_layout_slots(mut self, layout_slots: crate::LayoutSlots) -> Self {
+        self.layout_slots = Some(layout_slots);
         self
     }
 }
 
 impl<T: Display> axum::response::IntoResponse for Response<T> {
-    fn into_response(self) -> axum::response::Response {
-        let headers: http::HeaderMap = [(http::header::CONTENT_TYPE, self.content_type)]
-            .into_iter()
-            .chain(
-                self.htmx_retarget
-                    .map(|htmx_target| (header::HX_RETARGET, htmx_target)),
-            )
-            .chain(
-                self.extra_headers
-                    .into_iter()
-                    .filter_map(|(name, value)| name.map(|name| (name, value))),
-            )
-            .collect();
-
+    fn into_response(mut self) -> axum::response::Response {
+        let headers = self.take_headers();
         let mut body = self.body.to_string();
 
-        for (strategy, target, oob_element) in self.oob_elements {
-            let oob_html = oob_element.to_string();
-            let injected_html = inject_oob_attribute(&oob_html, &strategy, &target);
-            body.push_str(&injected_html);
+        #[cfg(debug_assertions)]
+        if is_html(&headers) {
+            sanity::report(sanity::check_html(&body));
+        }
+
+        body.push_str(&self.render_oob_elements());
+
+        if let Some(layout_slots) = self.layout_slots {
+            body = layout_slots.inject(&body);
         }
 
-        (headers, body).into_response()
+        if let Some(inline_assets) = self.inline_assets {
+            body = inline_assets.inject(&body);
+        }
+
+        #[cfg(debug_assertions)]
+        if is_html(&headers) {
+            sanity::report_html_validation(&body);
+        }
+
+        (self.status, headers, body).into_response()
+    }
+}
+
+impl axum::response::IntoResponse for Response<StreamingBody> {
+    fn into_response(mut self) -> axum::response::Response {
+        let headers = self.take_headers();
+        let oob = self.render_oob_elements();
+
+        (
+            self.status,
+            headers,
+            self.body.with_tail(oob, self.inline_assets).into_body(),
+        )
+            .into_response()
     }
 }
 
@@ -778,9 +1542,23 @@ pub trait Identity: Display {
     ///
     /// This is a convenience method that formats the `id` attribute for use in HTML.
     ///
+    /// When the element is a fragment rendered by [`oob_render_with_id_attribute`], this also
+    /// declares its `hx-swap-oob` attribute.
+    ///
     /// In most cases, this method should not be overridden.
     fn id_attribute(&self) -> String {
-        format!(r#"id="{}""#, self.id())
+        let id = self.id().to_string();
+
+        match oob::take_scoped_oob_attribute(&id) {
+            Some(oob_attribute) => format!(r#"id="{id}" {oob_attribute}"#),
+            None => format!(r#"id="{id}""#),
+        }
+    }
+}
+
+impl Identity for HtmlId {
+    fn id(&self) -> HtmlId {
+        self.clone()
     }
 }
 
@@ -826,6 +1604,67 @@ pub trait Fragment: Identity {
     /// This determines how the fragment will be swapped into the page when used as an
     /// out-of-band element.
     fn insert_strategy(&self) -> InsertStrategy;
+
+    /// Render the fragment for an out-of-band swap, with the `hx-swap-oob` attribute on its root
+    /// element.
+    ///
+    /// By default, this returns `None`: the fragment is rendered with its [`Display`]
+    /// implementation, and the attribute is injected in the start tag of its root element.
+    /// Fragments that can emit the attribute themselves, typically with
+    /// [`Fragment::oob_attribute`], can override this method to skip that rewrite.
+    ///
+    /// The `Fragment` derive macro implements it with [`oob_render_with_id_attribute`] when
+    /// given the `oob_render` option, for templates that use [`Identity::id_attribute`].
+    fn oob_render(&self) -> Option<String> {
+        None
+    }
+
+    /// Get the `hx-swap-oob` attribute declaration for an out-of-band swap of the fragment.
+    ///
+    /// This is a convenience method that formats the `hx-swap-oob` attribute for use in HTML, in
+    /// [`Fragment::oob_render`] implementations.
+    ///
+    /// In most cases, this method should not be overridden.
+    fn oob_attribute(&self) -> String {
+        let id = self.id().to_string();
+        let value = oob::oob_value(&self.insert_strategy(), &format!("#{id}"), Some(&id));
+
+        format!(r#"hx-swap-oob="{}""#, escape_attribute_value(&value))
+    }
+}
+
+/// A trait for HTML elements that must survive swaps, like video players or text areas being
+/// edited.
+///
+/// When swapping content, HTMX keeps the elements of the page with an `hx-preserve` attribute in
+/// place of the elements of the new content with the same `id`. Types that implement this trait
+/// MUST render an element with the attributes declared by [`Preserved::preserve_attributes`],
+/// and their `id` must be unique in the page and stable across renders, or the element is
+/// replaced.
+///
+/// The `Preserved` derive macro implements this trait for types implementing [`Identity`]:
+///
+/// ```ignore
+/// #[derive(Identity, Preserved)]
+/// #[identity(id = "intro-video")]
+/// struct IntroVideo;
+/// ```
+///
+/// ```ignore
+/// <video {{ preserve_attributes()|safe }} src="/intro.mp4" controls></video>
+/// ```
+pub trait Preserved: Identity {
+    /// Get the `id` and `hx-preserve` attributes declaration for the element.
+    ///
+    /// In debug builds, this checks that the `id` of the element is stable, by getting it twice.
+    ///
+    /// In most cases, this method should not be overridden.
+    fn preserve_attributes(&self) -> String {
+        #[cfg(debug_assertions)]
+        sanity::report(sanity::check_preserved_id(self));
+
+        format!(r#"{} hx-preserve="true""#, self.id_attribute())
+    }
 }
 
 /// A trait for HTML elements that have a form attribute name.
@@ -861,6 +1700,20 @@ pub trait HtmlForm: Display {
     fn action_attribute(&self) -> String {
         self.action_route().as_htmx_attribute()
     }
+
+    /// Render the form with a hidden input holding a CSRF token.
+    ///
+    /// The input is inserted right after the opening `<form>` tag.
+    #[cfg(feature = "csrf")]
+    fn with_csrf_token<'f>(
+        &'f self,
+        token: &'f crate::csrf::CsrfToken,
+    ) -> crate::csrf::CsrfForm<'f, Self>
+    where
+        Self: Sized,
+    {
+        crate::csrf::CsrfForm { form: self, token }
+    }
 }
 
 /// An extension trait for providing convenience methods on `Result<T, E>`.
@@ -1314,6 +2167,250 @@ mod tests {
             .with_location("/settings");
     }
 
+    #[test]
+    fn test_try_with_invalid_header_values() {
+        let invalid = "/search?q=\n";
+
+        assert!(matches!(
+            Response::new("test body").try_with_redirect(invalid),
+            Err(ResponseBuildError::InvalidHeaderValue { name, .. }) if name == "hx-redirect"
+        ));
+        assert!(matches!(
+            Response::new("test body").try_with_location(invalid),
+            Err(ResponseBuildError::InvalidHeaderValue { name, .. }) if name == "hx-location"
+        ));
+        assert!(matches!(
+            Response::new("test body").try_with_retarget(invalid),
+            Err(ResponseBuildError::InvalidHeaderValue { name, .. }) if name == "hx-retarget"
+        ));
+    }
+
+    #[test]
+    fn test_try_with_duplicate_headers() {
+        let response = Response::new("test body")
+            .try_with_redirect("/login")
+            .unwrap();
+
+        assert!(matches!(
+            response.try_with_redirect("/logout"),
+            Err(ResponseBuildError::DuplicateHeader(name)) if name == "hx-redirect"
+        ));
+
+        let response = Response::new("test body")
+            .try_with_push_url(&http::Uri::from_static("/posts"))
+            .unwrap();
+
+        assert!(matches!(
+            response.try_without_push_url(),
+            Err(ResponseBuildError::DuplicateHeader(name)) if name == "hx-push-url"
+        ));
+    }
+
+    #[test]
+    fn test_try_with_retarget() {
+        use axum::response::IntoResponse;
+
+        let response = Response::new("test body")
+            .try_with_retarget("#main")
+            .unwrap()
+            .into_response();
+
+        assert_eq!(response.headers().get("hx-retarget").unwrap(), "#main");
+    }
+
+    #[tokio::test]
+    async fn test_with_inline_assets() {
+        use axum::response::IntoResponse;
+
+        struct Card(crate::InlineAssets);
+
+        impl Display for Card {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, r#"{}<div class="card"></div>"#, self.0.style(".card{}"))
+            }
+        }
+
+        let assets = crate::InlineAssets::new();
+        let axum_response = Response::new(Card(assets.clone()))
+            .with_raw_oob(InsertStrategy::BeforeEnd, "#cards", Card(assets.clone()))
+            .with_inline_assets(assets)
+            .into_response();
+
+        let body = axum::body::to_bytes(axum_response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            body,
+            r##"<style>.card{}</style><div class="card"></div><div class="card" hx-swap-oob="beforeend:#cards"></div>"##
+        );
+    }
+
+    #[tokio::test]
+    async fn test_with_layout_slots() {
+        use axum::response::IntoResponse;
+
+        struct Page(crate::LayoutSlots);
+
+        impl Display for Page {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(
+                    f,
+                    "<head>{}</head><body>{}<p>Content</p></body>",
+                    self.0.placeholder("scripts"),
+                    self.0.fill("scripts", "<script>a()</script>"),
+                )
+            }
+        }
+
+        let slots = crate::LayoutSlots::new();
+        let axum_response = Response::new(Page(slots.clone()))
+            .with_layout_slots(slots)
+            .into_response();
+
+        let body = axum::body::to_bytes(axum_response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            body,
+            "<head><script>a()</script></head><body><p>Content</p></body>"
+        );
+    }
+
+    #[test]
+    fn test_with_reswap() {
+        use axum::response::IntoResponse;
+
+        let axum_response = Response::new("test body")
+            .with_reswap(InsertStrategy::InnerHtml)
+            .with_reswap(
+                Swap::new(InsertStrategy::OuterHtml)
+                    .with_modifier(SwapModifier::SwapDelay(std::time::Duration::from_secs(1)))
+                    .with_modifier(SwapModifier::SettleDelay(std::time::Duration::from_millis(
+                        100,
+                    )))
+                    .with_modifier(SwapModifier::Scroll(None, ScrollPosition::Top))
+                    .with_modifier(SwapModifier::Show(
+                        Some("#list".to_string()),
+                        ScrollPosition::Bottom,
+                    ))
+                    .with_modifier(SwapModifier::Transition(true)),
+            )
+            .into_response();
+
+        assert_eq!(
+            axum_response.headers().get("hx-reswap").unwrap(),
+            "outerHTML swap:1s settle:100ms scroll:top show:#list:bottom transition:true"
+        );
+    }
+
+    #[test]
+    fn test_with_trigger_bare_events() {
+        use axum::response::IntoResponse;
+
+        let axum_response = Response::new("test body")
+            .with_trigger("itemSaved")
+            .with_trigger("listChanged".to_string())
+            .with_trigger("itemSaved")
+            .into_response();
+
+        assert_eq!(
+            axum_response.headers().get("hx-trigger").unwrap(),
+            "itemSaved, listChanged"
+        );
+        assert!(
+            axum_response
+                .headers()
+                .get("hx-trigger-after-swap")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_with_trigger_payloads() {
+        use axum::response::IntoResponse;
+
+        #[derive(serde::Serialize)]
+        struct Message {
+            level: &'static str,
+            text: &'static str,
+        }
+
+        let axum_response = Response::new("test body")
+            .with_trigger("itemSaved")
+            .with_trigger((
+                "showMessage",
+                Message {
+                    level: "info",
+                    text: "Saved!",
+                },
+            ))
+            .with_trigger_after_swap(("focus", "#name"))
+            .with_trigger_after_settle("settled")
+            .into_response();
+
+        let headers = axum_response.headers();
+        let trigger: serde_json::Value =
+            serde_json::from_str(headers.get("hx-trigger").unwrap().to_str().unwrap()).unwrap();
+
+        assert_eq!(
+            trigger,
+            serde_json::json!({
+                "itemSaved": null,
+                "showMessage": {"level": "info", "text": "Saved!"},
+            })
+        );
+        assert_eq!(
+            headers.get("hx-trigger-after-swap").unwrap(),
+            r##"{"focus":"#name"}"##
+        );
+        assert_eq!(headers.get("hx-trigger-after-settle").unwrap(), "settled");
+    }
+
+    #[tokio::test]
+    async fn test_deleted() {
+        use axum::response::IntoResponse;
+
+        let axum_response = Response::deleted(&HtmlId::from_static("todo-3").unwrap())
+            .with_count_update("todos-changed", 2)
+            .into_response();
+
+        assert_eq!(axum_response.status(), http::StatusCode::OK);
+        assert_eq!(axum_response.headers().get("hx-reswap").unwrap(), "none");
+        assert_eq!(
+            axum_response.headers().get("hx-trigger").unwrap(),
+            r#"{"todos-changed":{"count":2}}"#
+        );
+
+        let body = axum::body::to_bytes(axum_response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        assert_eq!(body, r##"<div hx-swap-oob="delete:#todo-3"></div>"##);
+    }
+
+    #[tokio::test]
+    async fn test_no_content() {
+        use axum::response::IntoResponse;
+
+        let axum_response = Response::no_content()
+            .with_trigger("todos-changed")
+            .into_response();
+
+        assert_eq!(axum_response.status(), http::StatusCode::NO_CONTENT);
+        assert_eq!(
+            axum_response.headers().get("hx-trigger").unwrap(),
+            "todos-changed"
+        );
+
+        let body = axum::body::to_bytes(axum_response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        assert!(body.is_empty());
+    }
+
     // Test that htmx::Response can be used as a Controller response type
     #[cfg(test)]
     mod controller_response_tests {
@@ -1379,6 +2476,37 @@ mod tests {
             }
         }
 
+        #[test]
+        fn test_with_push_route() {
+            use axum::response::IntoResponse;
+
+            let response = Response::new("test body")
+                .with_push_route(&TestRoute::Home)
+                .into_response();
+
+            assert_eq!(response.headers().get("hx-push-url").unwrap(), "/");
+        }
+
+        #[test]
+        fn test_with_replace_route() {
+            use axum::response::IntoResponse;
+
+            let response = Response::new("test body")
+                .with_replace_route(&TestRoute::Home)
+                .into_response();
+
+            assert_eq!(response.headers().get("hx-replace-url").unwrap(), "/");
+            assert!(response.headers().get("hx-push-url").is_none());
+        }
+
+        #[test]
+        #[should_panic(expected = "hx-push-url header already present")]
+        fn test_with_push_route_duplicate_panics() {
+            Response::new("test body")
+                .without_push_url()
+                .with_push_route(&TestRoute::Home);
+        }
+
         #[tokio::test]
         async fn test_controller_with_htmx_response() {
             let controller = TestController;
@@ -0,0 +1,694 @@
+//! Out-of-band swaps.
+
+use std::{cell::RefCell, fmt::Display};
+
+use super::{Fragment, InsertStrategy, escape_attribute_value, is_void_element, sanity};
+use sanity::{RAW_TEXT_TAGS, tag_end};
+
+/// An out-of-band insert, rendered with its `hx-swap-oob` attribute.
+pub(super) trait OobElement {
+    /// Render the insert, for the specified strategy and target.
+    fn render_oob(&self, strategy: &InsertStrategy, target: &str) -> String;
+}
+
+/// A raw out-of-band insert, whose attribute is injected in its rendered HTML.
+pub(super) struct RawOob<D>(pub(super) D);
+
+impl<D: Display> OobElement for RawOob<D> {
+    fn render_oob(&self, strategy: &InsertStrategy, target: &str) -> String {
+        inject_oob_attribute(&self.0.to_string(), strategy, target)
+    }
+}
+
+/// A fragment out-of-band insert, rendered with [`render_fragment`].
+pub(super) struct FragmentOob<F>(pub(super) F);
+
+impl<F: Fragment> OobElement for FragmentOob<F> {
+    fn render_oob(&self, _strategy: &InsertStrategy, _target: &str) -> String {
+        render_fragment(&self.0)
+    }
+}
+
+/// Render a fragment for an out-of-band swap.
+///
+/// Fragments that render their own `hx-swap-oob` attribute with [`Fragment::oob_render`] are
+/// used as-is, while the attribute is injected in the others.
+pub(crate) fn render_fragment(fragment: &impl Fragment) -> String {
+    let (html, rendered_oob) = match fragment.oob_render() {
+        Some(html) => (html, true),
+        None => (fragment.to_string(), false),
+    };
+
+    #[cfg(debug_assertions)]
+    sanity::report(sanity::check_identity(&html, &fragment.id()));
+
+    if rendered_oob {
+        return html;
+    }
+
+    inject_oob_attribute(
+        &html,
+        &fragment.insert_strategy(),
+        &format!("#{}", fragment.id()),
+    )
+}
+
+thread_local! {
+    /// The `id` and `hx-swap-oob` attribute of the fragment being rendered by
+    /// [`oob_render_with_id_attribute`], until its `id` attribute is rendered.
+    static SCOPED_OOB_ATTRIBUTE: RefCell<Option<(String, String)>> = const { RefCell::new(None) };
+}
+
+/// Render a fragment for an out-of-band swap, emitting its `hx-swap-oob` attribute along with
+/// its `id` attribute.
+///
+/// While the fragment is rendered, the first call to [`Identity::id_attribute`] for its `id`
+/// also declares the [`Fragment::oob_attribute`], so that templates using `id_attribute()` render
+/// the attribute themselves, without their HTML being rewritten.
+///
+/// This is what the `oob_render` option of the `Fragment` derive macro generates:
+///
+/// ```ignore
+/// #[derive(Identity, Fragment)]
+/// #[identity(id = "counter")]
+/// #[fragment(strategy = "innerHTML", oob_render)]
+/// struct Counter {
+///     value: u32,
+/// }
+/// ```
+///
+/// Returns `None` if the fragment did not render its `id_attribute()`, in which case the
+/// attribute is injected in its HTML instead.
+///
+/// [`Identity::id_attribute`]: super::Identity::id_attribute
+pub fn oob_render_with_id_attribute(fragment: &impl Fragment) -> Option<String> {
+    /// Restores the previous scope, even if rendering panics.
+    struct ScopeGuard(Option<Option<(String, String)>>);
+
+    impl Drop for ScopeGuard {
+        fn drop(&mut self) {
+            if let Some(previous) = self.0.take() {
+                SCOPED_OOB_ATTRIBUTE.set(previous);
+            }
+        }
+    }
+
+    let scope = (fragment.id().to_string(), fragment.oob_attribute());
+    let mut guard = ScopeGuard(Some(SCOPED_OOB_ATTRIBUTE.replace(Some(scope))));
+    let html = fragment.to_string();
+    let unused = SCOPED_OOB_ATTRIBUTE.replace(guard.0.take().flatten());
+
+    unused.is_none().then_some(html)
+}
+
+/// Take the `hx-swap-oob` attribute to declare along with an `id` attribute, if the fragment
+/// with that `id` is being rendered by [`oob_render_with_id_attribute`].
+pub(super) fn take_scoped_oob_attribute(id: &str) -> Option<String> {
+    SCOPED_OOB_ATTRIBUTE.with_borrow_mut(|scope| {
+        if scope.as_ref().is_some_and(|(scoped_id, _)| scoped_id == id) {
+            scope.take().map(|(_, attribute)| attribute)
+        } else {
+            None
+        }
+    })
+}
+
+/// Get the value of the `hx-swap-oob` attribute of an element.
+///
+/// The value is `true` when the element replaces the one with its own `id`, which is the HTMX
+/// default.
+pub(super) fn oob_value(strategy: &InsertStrategy, target: &str, id: Option<&str>) -> String {
+    match (strategy, target.strip_prefix('#')) {
+        (InsertStrategy::OuterHtml, Some(target_id)) if id == Some(target_id) => "true".to_owned(),
+        _ => format!("{strategy}:{target}"),
+    }
+}
+
+/// Inject the `hx-swap-oob` attribute into an HTML fragment.
+///
+/// When the fragment has a single root element, the attribute is added to its start tag, which
+/// is the only part of the HTML that is rewritten: any existing `hx-swap-oob` attribute is
+/// removed, and the rest of the fragment is kept verbatim. Otherwise, the fragment is wrapped
+/// in a `<template>` tag with the attribute.
+///
+/// # Arguments
+///
+/// * `html` - The HTML fragment to modify
+/// * `strategy` - The swap strategy to use
+/// * `target` - The CSS selector target for the swap
+///
+/// # Returns
+///
+/// The modified HTML with the `hx-swap-oob` attribute injected.
+pub(crate) fn inject_oob_attribute(html: &str, strategy: &InsertStrategy, target: &str) -> String {
+    #[cfg(debug_assertions)]
+    sanity::report(sanity::check_oob(html, strategy, target));
+
+    let Some(root) = RootTag::find(html) else {
+        return format!(
+            r#"<template hx-swap-oob="{}">{html}</template>"#,
+            escape_attribute_value(&oob_value(strategy, target, None))
+        );
+    };
+
+    let value = oob_value(strategy, target, root.attribute("id"));
+    let mut result = String::with_capacity(html.len() + value.len() + 14);
+    let mut position = 0;
+
+    for attribute in root.attributes.iter().filter(|a| a.name == "hx-swap-oob") {
+        result.push_str(&html[position..attribute.start]);
+        position = attribute.end;
+    }
+
+    result.push_str(&html[position..root.insert_at]);
+    result.push_str(r#" hx-swap-oob=""#);
+    result.push_str(&escape_attribute_value(&value));
+    result.push('"');
+    result.push_str(&html[root.insert_at..]);
+    result
+}
+
+/// Swap some HTML into the element with an `id` of an HTML document, as an out-of-band swap with
+/// the strategy would on the client.
+///
+/// Returns `None` if the document has no element with the `id`, or the strategy is a custom one.
+pub(crate) fn swap_into_element(
+    html: &str,
+    id: &str,
+    strategy: &InsertStrategy,
+    content: &str,
+) -> Option<String> {
+    let (start, tag) = find_element(html, id)?;
+    let end = element_end(html, &tag)?;
+    let content_end = if tag.self_closing {
+        tag.end
+    } else {
+        html[..end].rfind("</")?
+    };
+    let text;
+
+    let (from, to, content) = match strategy {
+        InsertStrategy::OuterHtml => (start, end, content),
+        InsertStrategy::InnerHtml => (tag.end, content_end, content),
+        InsertStrategy::TextContent => {
+            text = super::escape_text(content);
+
+            (tag.end, content_end, text.as_str())
+        }
+        InsertStrategy::BeforeBegin => (start, start, content),
+        InsertStrategy::AfterBegin => (tag.end, tag.end, content),
+        InsertStrategy::BeforeEnd => (content_end, content_end, content),
+        InsertStrategy::AfterEnd => (end, end, content),
+        InsertStrategy::Delete => (start, end, ""),
+        InsertStrategy::None => (start, start, ""),
+        InsertStrategy::Custom(_) => return None,
+    };
+
+    Some(format!("{}{content}{}", &html[..from], &html[to..]))
+}
+
+/// Find the start tag of the element with an `id` in an HTML document, with its offset.
+fn find_element(html: &str, id: &str) -> Option<(usize, StartTag)> {
+    let mut position = 0;
+
+    while let Some(index) = html[position..].find('<') {
+        position += index;
+        let rest = &html[position..];
+
+        if rest.starts_with("<!--") {
+            position = rest
+                .find("-->")
+                .map_or(html.len(), |end| position + end + 3);
+
+            continue;
+        }
+
+        let Some(tag) = start_tag(html, position) else {
+            position += 1;
+
+            continue;
+        };
+
+        let attributes_end = if html[..tag.end].ends_with("/>") {
+            tag.end - 2
+        } else {
+            tag.end - 1
+        };
+
+        if parse_attributes(html, position + 1 + tag.name.len(), attributes_end)
+            .iter()
+            .any(|attribute| attribute.name == "id" && attribute.value == Some(id))
+        {
+            return Some((position, tag));
+        }
+
+        position = tag.end;
+
+        if RAW_TEXT_TAGS.contains(&tag.name.as_str()) {
+            let closing = format!("</{}", tag.name);
+
+            position += html[position..]
+                .to_ascii_lowercase()
+                .find(&closing)
+                .unwrap_or(html.len() - position);
+        }
+    }
+
+    None
+}
+
+/// An attribute of a start tag.
+struct Attribute<'a> {
+    /// The lowercase attribute name.
+    name: String,
+
+    /// The raw attribute value, if any.
+    value: Option<&'a str>,
+
+    /// The offset of the attribute in the HTML, including its leading whitespace.
+    start: usize,
+
+    /// The offset of the end of the attribute in the HTML.
+    end: usize,
+}
+
+/// The start tag of the single root element of an HTML fragment.
+struct RootTag<'a> {
+    /// The attributes of the tag.
+    attributes: Vec<Attribute<'a>>,
+
+    /// The offset where new attributes can be inserted, before the closing `>` or `/>`.
+    insert_at: usize,
+}
+
+impl<'a> RootTag<'a> {
+    /// Find the start tag of the single root element of an HTML fragment.
+    ///
+    /// Only the root element is scanned, to find its end: its content is not parsed. Returns
+    /// `None` if the fragment has no root element, several ones, or text around it.
+    fn find(html: &'a str) -> Option<Self> {
+        let start = skip_insignificant(html, 0);
+        let tag = start_tag(html, start)?;
+        let end = element_end(html, &tag)?;
+
+        if skip_insignificant(html, end) != html.len() {
+            return None;
+        }
+
+        let content_end = if html[..tag.end].ends_with("/>") {
+            tag.end - 2
+        } else {
+            tag.end - 1
+        };
+        let content = &html[..content_end];
+        let insert_at = content.trim_end().len();
+
+        Some(Self {
+            attributes: parse_attributes(html, start + 1 + tag.name.len(), insert_at),
+            insert_at,
+        })
+    }
+
+    /// Get the raw value of an attribute.
+    fn attribute(&self, name: &str) -> Option<&'a str> {
+        self.attributes
+            .iter()
+            .find(|a| a.name == name)
+            .and_then(|a| a.value)
+    }
+}
+
+/// A start tag, as found in some HTML.
+struct StartTag {
+    /// The lowercase tag name.
+    name: String,
+
+    /// The offset of the end of the tag, after its closing `>`.
+    end: usize,
+
+    /// Whether the tag is self-closing.
+    self_closing: bool,
+}
+
+/// Parse the start tag at some offset of the HTML, if any.
+fn start_tag(html: &str, offset: usize) -> Option<StartTag> {
+    let rest = &html[offset..];
+
+    if !rest.starts_with('<') || !rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let len = tag_end(rest);
+
+    if !rest[..len].ends_with('>') {
+        return None;
+    }
+
+    let name = rest[1..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let self_closing = is_void_element(&name) || rest[..len].ends_with("/>");
+
+    Some(StartTag {
+        name,
+        end: offset + len,
+        self_closing,
+    })
+}
+
+/// Find the offset of the end of an element, after its closing tag.
+///
+/// Only the tags with the same name as the element are tracked, to find its matching closing tag.
+fn element_end(html: &str, tag: &StartTag) -> Option<usize> {
+    if tag.self_closing {
+        return Some(tag.end);
+    }
+
+    let closing = format!("</{}", tag.name);
+    let mut depth = 1;
+    let mut position = tag.end;
+
+    while let Some(index) = html[position..].find('<') {
+        position += index;
+        let rest = &html[position..];
+
+        if rest.starts_with("<!--") {
+            position = rest
+                .find("-->")
+                .map_or(html.len(), |end| position + end + 3);
+        } else if starts_with_tag(rest, &closing) {
+            position += tag_end(rest);
+            depth -= 1;
+
+            if depth == 0 {
+                return Some(position);
+            }
+        } else if let Some(nested) = start_tag(html, position) {
+            position = nested.end;
+
+            if RAW_TEXT_TAGS.contains(&nested.name.as_str()) {
+                let nested_closing = format!("</{}", nested.name);
+
+                position += html[position..]
+                    .to_ascii_lowercase()
+                    .find(&nested_closing)
+                    .unwrap_or(html.len() - position);
+            } else if nested.name == tag.name && !nested.self_closing {
+                depth += 1;
+            }
+        } else {
+            position += 1;
+        }
+    }
+
+    None
+}
+
+/// Check whether some HTML starts with a tag, matched case-insensitively and followed by the end
+/// of its name.
+fn starts_with_tag(html: &str, tag: &str) -> bool {
+    html.get(..tag.len())
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(tag))
+        && html[tag.len()..].starts_with(|c: char| c.is_ascii_whitespace() || c == '>' || c == '/')
+}
+
+/// Skip the whitespace and comments from some offset of the HTML.
+fn skip_insignificant(html: &str, mut offset: usize) -> usize {
+    loop {
+        let rest = &html[offset..];
+        let trimmed = rest.trim_start();
+        offset += rest.len() - trimmed.len();
+
+        match trimmed.strip_prefix("<!--") {
+            Some(comment) => match comment.find("-->") {
+                Some(end) => offset += 4 + end + 3,
+                None => return offset,
+            },
+            None => return offset,
+        }
+    }
+}
+
+/// Parse the attributes of a start tag, between two offsets of the HTML.
+fn parse_attributes(html: &str, mut position: usize, end: usize) -> Vec<Attribute<'_>> {
+    let mut attributes = Vec::new();
+
+    while position < end {
+        let start = position;
+        let rest = &html[position..end];
+        let trimmed = rest.trim_start();
+        position += rest.len() - trimmed.len();
+
+        let name_len = trimmed
+            .find(|c: char| c.is_ascii_whitespace() || c == '=' || c == '/')
+            .unwrap_or(trimmed.len());
+
+        if name_len == 0 {
+            // A stray `/`, or trailing whitespace.
+            position += trimmed.len().min(1);
+            continue;
+        }
+
+        let name = trimmed[..name_len].to_ascii_lowercase();
+        position += name_len;
+
+        let after_name = &html[position..end];
+        let after_whitespace = after_name.trim_start();
+        let mut value = None;
+
+        if let Some(assigned) = after_whitespace.strip_prefix('=') {
+            let value_html = assigned.trim_start();
+            let value_start = end - value_html.len();
+
+            let (raw, len) = match value_html.chars().next() {
+                Some(quote @ ('"' | '\'')) => match value_html[1..].find(quote) {
+                    Some(close) => (&value_html[1..=close], close + 2),
+                    None => (&value_html[1..], value_html.len()),
+                },
+                _ => {
+                    let len = value_html
+                        .find(|c: char| c.is_ascii_whitespace())
+                        .unwrap_or(value_html.len());
+                    (&value_html[..len], len)
+                }
+            };
+
+            value = Some(raw);
+            position = value_start + len;
+        }
+
+        attributes.push(Attribute {
+            name,
+            value,
+            start,
+            end: position,
+        });
+    }
+
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::htmx::{HtmlId, Identity};
+
+    fn inject(html: &str, strategy: InsertStrategy, target: &str) -> String {
+        inject_oob_attribute(html, &strategy, target)
+    }
+
+    #[test]
+    fn test_swap_into_element() {
+        let html = r#"<main><!-- <p id="cart"> --><script>"<p id='cart'>"</script><p id=cart class="x">Old</p><br id="hr"></main>"#;
+        let swap =
+            |strategy: InsertStrategy| swap_into_element(html, "cart", &strategy, "<b>3</b>");
+
+        assert_eq!(
+            swap(InsertStrategy::InnerHtml).unwrap(),
+            r#"<main><!-- <p id="cart"> --><script>"<p id='cart'>"</script><p id=cart class="x"><b>3</b></p><br id="hr"></main>"#
+        );
+        assert_eq!(
+            swap(InsertStrategy::OuterHtml).unwrap(),
+            r#"<main><!-- <p id="cart"> --><script>"<p id='cart'>"</script><b>3</b><br id="hr"></main>"#
+        );
+        assert_eq!(
+            swap(InsertStrategy::BeforeEnd).unwrap(),
+            r#"<main><!-- <p id="cart"> --><script>"<p id='cart'>"</script><p id=cart class="x">Old<b>3</b></p><br id="hr"></main>"#
+        );
+        assert_eq!(
+            swap(InsertStrategy::TextContent).unwrap(),
+            r#"<main><!-- <p id="cart"> --><script>"<p id='cart'>"</script><p id=cart class="x">&lt;b&gt;3&lt;/b&gt;</p><br id="hr"></main>"#
+        );
+        assert_eq!(
+            swap_into_element(html, "hr", &InsertStrategy::AfterEnd, "<hr>").unwrap(),
+            r#"<main><!-- <p id="cart"> --><script>"<p id='cart'>"</script><p id=cart class="x">Old</p><br id="hr"><hr></main>"#
+        );
+        assert_eq!(
+            swap_into_element(html, "missing", &InsertStrategy::InnerHtml, ""),
+            None
+        );
+        assert_eq!(swap(InsertStrategy::Custom("morph".to_owned())), None);
+    }
+
+    #[test]
+    fn test_inject_preserves_formatting() {
+        assert_eq!(
+            inject(
+                "\n  <div  id='test'\n class=\"a\"><p>One<br>Two</p></div>\n",
+                InsertStrategy::OuterHtml,
+                "#test"
+            ),
+            "\n  <div  id='test'\n class=\"a\" hx-swap-oob=\"true\"><p>One<br>Two</p></div>\n"
+        );
+    }
+
+    #[test]
+    fn test_inject_replaces_existing_attribute() {
+        assert_eq!(
+            inject(
+                r#"<div hx-swap-oob="true" id="test">Content</div>"#,
+                InsertStrategy::InnerHtml,
+                "#test"
+            ),
+            r#"<div id="test" hx-swap-oob="innerHTML:#test">Content</div>"#
+        );
+    }
+
+    #[test]
+    fn test_inject_self_closing() {
+        assert_eq!(
+            inject(
+                r#"<img id="logo" src="/a.png" />"#,
+                InsertStrategy::OuterHtml,
+                "#logo"
+            ),
+            r#"<img id="logo" src="/a.png" hx-swap-oob="true" />"#
+        );
+        assert_eq!(
+            inject(
+                r#"<input id=name value=x>"#,
+                InsertStrategy::OuterHtml,
+                "#name"
+            ),
+            r#"<input id=name value=x hx-swap-oob="true">"#
+        );
+    }
+
+    #[test]
+    fn test_inject_nested_elements() {
+        let html = r#"<div id="a"><div>One</div><!-- </div> --><script>"</div>"</script></div>"#;
+
+        assert_eq!(
+            inject(html, InsertStrategy::OuterHtml, "#a"),
+            r#"<div id="a" hx-swap-oob="true"><div>One</div><!-- </div> --><script>"</div>"</script></div>"#
+        );
+    }
+
+    #[test]
+    fn test_inject_quoted_greater_than() {
+        assert_eq!(
+            inject(
+                r#"<div id="a" data-x="1 > 0">Content</div>"#,
+                InsertStrategy::OuterHtml,
+                "#a"
+            ),
+            r#"<div id="a" data-x="1 > 0" hx-swap-oob="true">Content</div>"#
+        );
+    }
+
+    #[test]
+    fn test_inject_escapes_target() {
+        assert_eq!(
+            inject(
+                "<li>Item</li>",
+                InsertStrategy::BeforeEnd,
+                r#"[data-list="todo"]"#
+            ),
+            r#"<li hx-swap-oob="beforeend:[data-list=&quot;todo&quot;]">Item</li>"#
+        );
+    }
+
+    #[test]
+    fn test_inject_wraps_fragments_without_single_root() {
+        for html in [
+            "Text",
+            "<div>A</div> text",
+            "<div>Unclosed",
+            "<p>A</p><p>B</p>",
+        ] {
+            assert_eq!(
+                inject(html, InsertStrategy::BeforeEnd, "#list"),
+                format!(r##"<template hx-swap-oob="beforeend:#list">{html}</template>"##)
+            );
+        }
+    }
+
+    struct SelfRendered;
+
+    impl Display for SelfRendered {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(r#"<div id="counter">1</div>"#)
+        }
+    }
+
+    impl Identity for SelfRendered {
+        fn id(&self) -> HtmlId {
+            HtmlId::from_static("counter").unwrap()
+        }
+    }
+
+    impl Fragment for SelfRendered {
+        fn insert_strategy(&self) -> InsertStrategy {
+            InsertStrategy::InnerHtml
+        }
+
+        fn oob_render(&self) -> Option<String> {
+            Some(format!(
+                r#"<div id="counter" {}>1</div>"#,
+                self.oob_attribute()
+            ))
+        }
+    }
+
+    #[test]
+    fn test_render_fragment_with_own_attribute() {
+        assert_eq!(
+            render_fragment(&SelfRendered),
+            r##"<div id="counter" hx-swap-oob="innerHTML:#counter">1</div>"##
+        );
+    }
+
+    struct Templated(&'static str);
+
+    impl Display for Templated {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "<p {}>{}</p>", self.id_attribute(), self.0)
+        }
+    }
+
+    impl Identity for Templated {
+        fn id(&self) -> HtmlId {
+            HtmlId::from_static("status").unwrap()
+        }
+    }
+
+    impl Fragment for Templated {
+        fn insert_strategy(&self) -> InsertStrategy {
+            InsertStrategy::OuterHtml
+        }
+    }
+
+    #[test]
+    fn test_oob_render_with_id_attribute() {
+        let fragment = Templated("Ready");
+
+        assert_eq!(
+            oob_render_with_id_attribute(&fragment).as_deref(),
+            Some(r#"<p id="status" hx-swap-oob="true">Ready</p>"#)
+        );
+        assert_eq!(fragment.to_string(), r#"<p id="status">Ready</p>"#);
+        assert_eq!(oob_render_with_id_attribute(&SelfRendered), None);
+    }
+}
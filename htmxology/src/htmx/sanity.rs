@@ -0,0 +1,859 @@
+//! Sanity checks of HTMX invariants, for development.
+//!
+//! In debug builds, responses and pushed fragments are checked as they are rendered, and any
+//! violated invariant is logged as a warning:
+//!
+//! - Fragments swapped out-of-band through their `Identity` must render a root element carrying
+//!   the promised `id`.
+//! - Out-of-band targets, `hx-target`-like attributes and `HX-Retarget` headers must be valid CSS
+//!   selectors, or valid HTMX extended selectors.
+//! - `hx-*` attributes must be known HTMX attributes, and their values must parse as HTMX would
+//!   expect them to.
+//! - Requests to routes with an [expected target](crate::Route::expected_target) must target that
+//!   element, as reported by their `HX-Target` header.
+//! - Elements with an `hx-preserve` attribute must have an `id`, unique among the preserved
+//!   elements, and [`Preserved`](super::Preserved) elements must get the same `id` on every
+//!   render.
+//!
+//! The checks are compiled out of release builds. They can also be run explicitly, for instance
+//! in tests, with [`check_html`] and [`check_oob`].
+//!
+//! The complete HTML of responses, including their out-of-band fragments, can additionally be
+//! validated with [`set_html_validation`]: malformed HTML is silently fixed by browsers, but
+//! often not the way HTMX swaps expect it. The validation reports:
+//!
+//! - Elements that are not closed, or closed out of order.
+//! - Closing tags that don't match any open element.
+//! - Duplicate `id` attributes, across the main content and the out-of-band fragments.
+
+use std::{
+    collections::HashSet,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use scraper::{ElementRef, Html, Selector};
+
+use super::{HtmlId, InsertStrategy};
+
+/// The known HTMX attributes, without their `hx-` prefix.
+const KNOWN_ATTRIBUTES: &[&str] = &[
+    "boost",
+    "confirm",
+    "delete",
+    "disable",
+    "disabled-elt",
+    "disinherit",
+    "encoding",
+    "ext",
+    "get",
+    "headers",
+    "history",
+    "history-elt",
+    "include",
+    "indicator",
+    "inherit",
+    "params",
+    "patch",
+    "post",
+    "preserve",
+    "prompt",
+    "push-url",
+    "put",
+    "replace-url",
+    "request",
+    "select",
+    "select-oob",
+    "swap",
+    "swap-oob",
+    "sync",
+    "target",
+    "trigger",
+    "validate",
+    "vals",
+    "vars",
+];
+
+/// The swap styles understood by HTMX.
+const SWAP_STYLES: &[&str] = &[
+    "innerHTML",
+    "outerHTML",
+    "textContent",
+    "beforebegin",
+    "afterbegin",
+    "beforeend",
+    "afterend",
+    "delete",
+    "none",
+];
+
+/// The swap modifiers understood by HTMX.
+const SWAP_MODIFIERS: &[&str] = &[
+    "swap",
+    "settle",
+    "scroll",
+    "show",
+    "transition",
+    "ignoreTitle",
+    "focus-scroll",
+];
+
+/// The elements whose closing tag can be omitted.
+const OPTIONAL_CLOSING_TAGS: &[&str] = &[
+    "body", "colgroup", "dd", "dt", "head", "html", "li", "optgroup", "option", "p", "rp", "rt",
+    "tbody", "td", "tfoot", "th", "thead", "tr",
+];
+
+/// The elements whose content is raw text, which can't contain other elements.
+pub(super) const RAW_TEXT_TAGS: &[&str] = &["script", "style", "textarea", "title"];
+
+/// The configured HTML validation, as an [`HtmlValidation`] discriminant.
+static HTML_VALIDATION: AtomicU8 = AtomicU8::new(HtmlValidation::Disabled as u8);
+
+/// What to do with the HTML validation warnings of responses.
+///
+/// See [`set_html_validation`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum HtmlValidation {
+    /// Responses are not validated.
+    #[default]
+    Disabled,
+
+    /// Warnings are logged.
+    Log,
+
+    /// Warnings cause a panic, which is useful to fail tests.
+    Panic,
+}
+
+/// Set how the HTML of responses is validated, in debug builds.
+///
+/// Validation is disabled by default, and never runs in release builds.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[cfg(debug_assertions)]
+/// htmx::sanity::set_html_validation(HtmlValidation::Log);
+/// ```
+pub fn set_html_validation(validation: HtmlValidation) {
+    HTML_VALIDATION.store(validation as u8, Ordering::Relaxed);
+}
+
+/// Get how the HTML of responses is validated.
+pub fn html_validation() -> HtmlValidation {
+    match HTML_VALIDATION.load(Ordering::Relaxed) {
+        1 => HtmlValidation::Log,
+        2 => HtmlValidation::Panic,
+        _ => HtmlValidation::Disabled,
+    }
+}
+
+/// A violated HTMX invariant.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum SanityWarning {
+    /// A fragment does not render a root element with the `id` promised by its `Identity`.
+    #[error(
+        "fragment `{id}` does not render a single root element with `id=\"{id}\"`: render `id_attribute()` on its root element, or subsequent swaps targeting `#{id}` will fail"
+    )]
+    MissingId {
+        /// The promised identifier.
+        id: String,
+    },
+
+    /// A selector is not valid.
+    #[error("invalid selector `{selector}` in {context}: {reason}")]
+    InvalidSelector {
+        /// The invalid selector.
+        selector: String,
+
+        /// Where the selector was found.
+        context: String,
+
+        /// Why the selector is invalid.
+        reason: String,
+    },
+
+    /// An `hx-*` attribute is not a known HTMX attribute.
+    #[error("unknown HTMX attribute `{name}`: check its spelling, or use `data-` attributes")]
+    UnknownAttribute {
+        /// The name of the attribute.
+        name: String,
+    },
+
+    /// An `hx-*` attribute or `HX-*` header has a value HTMX can't parse.
+    #[error("invalid `{name}` value `{value}`: {reason}")]
+    InvalidValue {
+        /// The name of the attribute or header.
+        name: String,
+
+        /// The invalid value.
+        value: String,
+
+        /// Why the value is invalid.
+        reason: String,
+    },
+
+    /// An element is not closed, or is closed after its parent.
+    #[error(
+        "unclosed `<{tag}>` element: browsers close it implicitly, possibly not where intended"
+    )]
+    UnclosedTag {
+        /// The name of the element.
+        tag: String,
+    },
+
+    /// A closing tag doesn't match any open element.
+    #[error("unexpected `</{tag}>` closing tag, which browsers ignore")]
+    UnexpectedClosingTag {
+        /// The name of the element.
+        tag: String,
+    },
+
+    /// A request doesn't target the element its route is meant to be swapped into.
+    #[error(
+        "request to `{route}` targets {}, but the route expects `{expected}`: check the `hx-target` of the element issuing it",
+        .target.as_ref().map_or_else(|| "an element without `id`".to_owned(), |target| format!("`#{target}`"))
+    )]
+    UnexpectedTarget {
+        /// The name of the variant of the route.
+        route: String,
+
+        /// The expected target selector.
+        expected: String,
+
+        /// The `id` of the actual target, from the `HX-Target` header, if any.
+        target: Option<String>,
+    },
+
+    /// An element with an `hx-preserve` attribute has no `id`.
+    #[error(
+        "preserved `<{tag}>` element has no `id`: HTMX can only preserve elements with an `id`"
+    )]
+    MissingPreservedId {
+        /// The name of the element.
+        tag: String,
+    },
+
+    /// Several elements with an `hx-preserve` attribute have the same `id`.
+    #[error(
+        "duplicate preserved `id=\"{id}\"`: HTMX only preserves one element with the same `id`"
+    )]
+    DuplicatePreservedId {
+        /// The duplicate identifier.
+        id: String,
+    },
+
+    /// A preserved element gets a different `id` when rendered again.
+    #[error(
+        "preserved element `#{id}` gets `id=\"{other}\"` when rendered again: preserved elements need stable ids, or swaps replace them"
+    )]
+    UnstablePreservedId {
+        /// The identifier of the first render.
+        id: String,
+
+        /// The identifier of the next render.
+        other: String,
+    },
+
+    /// Several elements have the same `id`.
+    #[error(
+        "duplicate `id=\"{id}\"`: swaps targeting `#{id}` only affect the first matching element"
+    )]
+    DuplicateId {
+        /// The duplicate identifier.
+        id: String,
+    },
+}
+
+/// Check the `hx-*` attributes of an HTML document or fragment.
+pub fn check_html(html: &str) -> Vec<SanityWarning> {
+    let fragment = Html::parse_fragment(html);
+    let mut warnings = Vec::new();
+    let mut preserved_ids = HashSet::new();
+
+    for element in fragment
+        .root_element()
+        .descendants()
+        .filter_map(ElementRef::wrap)
+    {
+        let mut preserved = false;
+
+        for (name, value) in element.value().attrs() {
+            let htmx_name = name.strip_prefix("data-").unwrap_or(name);
+
+            if let Some(suffix) = htmx_name.strip_prefix("hx-") {
+                check_attribute(htmx_name, suffix, value, &mut warnings);
+
+                preserved |= suffix == "preserve" && value != "false";
+            }
+        }
+
+        if preserved {
+            match element.value().id() {
+                Some(id) if !preserved_ids.insert(id) => {
+                    warnings.push(SanityWarning::DuplicatePreservedId { id: id.to_owned() });
+                }
+                Some(_) => {}
+                None => warnings.push(SanityWarning::MissingPreservedId {
+                    tag: element.value().name().to_owned(),
+                }),
+            }
+        }
+    }
+
+    warnings
+}
+
+/// Check that a preserved element gets the same `id` when rendered again.
+pub fn check_preserved_id<P: super::Preserved + ?Sized>(preserved: &P) -> Option<SanityWarning> {
+    let id = preserved.id().to_string();
+    let other = preserved.id().to_string();
+
+    (id != other).then_some(SanityWarning::UnstablePreservedId { id, other })
+}
+
+/// Validate the structure of an HTML document or fragment.
+///
+/// Unlike the other checks, which rely on the lenient parsing of browsers, this detects
+/// unclosed elements, unexpected closing tags and duplicate `id` attributes.
+pub fn validate_html(html: &str) -> Vec<SanityWarning> {
+    let mut warnings = Vec::new();
+    let mut open_tags: Vec<String> = Vec::new();
+    let mut rest = html;
+
+    let unclosed = |tag: String, warnings: &mut Vec<SanityWarning>| {
+        if !OPTIONAL_CLOSING_TAGS.contains(&tag.as_str()) {
+            warnings.push(SanityWarning::UnclosedTag { tag });
+        }
+    };
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+        } else if let Some(closing) = rest.strip_prefix("</") {
+            let end = closing.find('>').unwrap_or(closing.len());
+            let tag = closing[..end].trim().to_ascii_lowercase();
+            rest = closing.get(end + 1..).unwrap_or_default();
+
+            match open_tags.iter().rposition(|open| *open == tag) {
+                Some(index) => {
+                    for tag in open_tags.drain(index..).skip(1) {
+                        unclosed(tag, &mut warnings);
+                    }
+                }
+                None => warnings.push(SanityWarning::UnexpectedClosingTag { tag }),
+            }
+        } else if rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+            let end = tag_end(rest);
+            let tag_html = &rest[..end];
+            let tag: String = tag_html[1..]
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+                .collect::<String>()
+                .to_ascii_lowercase();
+            rest = &rest[end..];
+
+            if super::is_void_element(&tag) || tag_html.ends_with("/>") {
+                continue;
+            }
+
+            if RAW_TEXT_TAGS.contains(&tag.as_str()) {
+                let closing = format!("</{tag}");
+                rest = rest
+                    .to_ascii_lowercase()
+                    .find(&closing)
+                    .map_or("", |index| &rest[index..]);
+            }
+
+            open_tags.push(tag);
+        } else {
+            // A `<!DOCTYPE>` declaration, or a `<` in text.
+            rest = &rest[1..];
+        }
+    }
+
+    for tag in open_tags {
+        unclosed(tag, &mut warnings);
+    }
+
+    let fragment = Html::parse_fragment(html);
+    let mut ids = HashSet::new();
+    let mut duplicates = HashSet::new();
+
+    for element in fragment
+        .root_element()
+        .descendants()
+        .filter_map(ElementRef::wrap)
+    {
+        if let Some(id) = element.value().id()
+            && !ids.insert(id)
+            && duplicates.insert(id)
+        {
+            warnings.push(SanityWarning::DuplicateId { id: id.to_owned() });
+        }
+    }
+
+    warnings
+}
+
+/// Get the length of the opening tag at the start of some HTML, up to its closing `>`.
+pub(super) fn tag_end(html: &str) -> usize {
+    let mut quote = None;
+
+    for (index, c) in html.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return index + 1,
+            (Some(q), c) if q == c => quote = None,
+            _ => {}
+        }
+    }
+
+    html.len()
+}
+
+/// Check an out-of-band swap of an HTML fragment.
+pub fn check_oob(html: &str, strategy: &InsertStrategy, target: &str) -> Vec<SanityWarning> {
+    let mut warnings = Vec::new();
+
+    if let Err(reason) = check_swap_spec(&strategy.to_string()) {
+        warnings.push(SanityWarning::InvalidValue {
+            name: "hx-swap-oob".to_owned(),
+            value: strategy.to_string(),
+            reason,
+        });
+    }
+
+    if let Err(reason) = check_selector(target) {
+        warnings.push(SanityWarning::InvalidSelector {
+            selector: target.to_owned(),
+            context: "an out-of-band swap target".to_owned(),
+            reason,
+        });
+    }
+
+    warnings.extend(check_html(html));
+    warnings
+}
+
+/// Check that an HTML fragment renders a single root element with the specified `id`.
+pub fn check_identity(html: &str, id: &HtmlId) -> Option<SanityWarning> {
+    let fragment = Html::parse_fragment(html);
+    let mut root_elements = fragment
+        .root_element()
+        .children()
+        .filter_map(ElementRef::wrap);
+
+    let id = id.to_string();
+    let has_id = match (root_elements.next(), root_elements.next()) {
+        (Some(root), None) => root.value().id() == Some(id.as_str()),
+        _ => false,
+    };
+
+    (!has_id).then_some(SanityWarning::MissingId { id })
+}
+
+/// Check that a request targets the element its route is meant to be swapped into.
+pub fn check_target<R: crate::Route>(route: &R, request: &super::Request) -> Option<SanityWarning> {
+    if request.matches_target(route) {
+        return None;
+    }
+
+    let super::Request::Htmx { target, .. } = request else {
+        return None;
+    };
+
+    Some(SanityWarning::UnexpectedTarget {
+        route: route.variant_name().into_owned(),
+        expected: route.expected_target()?.to_owned(),
+        target: target
+            .as_ref()
+            .map(|target| String::from_utf8_lossy(target.as_bytes()).into_owned()),
+    })
+}
+
+/// Check a `HX-Reswap` or `HX-Retarget` response header.
+#[cfg(any(debug_assertions, test))]
+pub(crate) fn check_header(
+    name: &http::HeaderName,
+    value: &http::HeaderValue,
+) -> Vec<SanityWarning> {
+    let Ok(value) = value.to_str() else {
+        return Vec::new();
+    };
+
+    let result = match name.as_str() {
+        "hx-reswap" => check_swap_spec(value).map_err(|reason| SanityWarning::InvalidValue {
+            name: name.to_string(),
+            value: value.to_owned(),
+            reason,
+        }),
+        "hx-retarget" => {
+            check_extended_selector(value).map_err(|reason| SanityWarning::InvalidSelector {
+                selector: value.to_owned(),
+                context: format!("the `{name}` header"),
+                reason,
+            })
+        }
+        _ => Ok(()),
+    };
+
+    result.err().into_iter().collect()
+}
+
+/// Log sanity warnings.
+#[cfg(debug_assertions)]
+pub(crate) fn report(warnings: impl IntoIterator<Item = SanityWarning>) {
+    for warning in warnings {
+        tracing::warn!("HTMX sanity check failed: {warning}");
+    }
+}
+
+/// Validate the HTML of a response, as configured by [`set_html_validation`].
+#[cfg(debug_assertions)]
+pub(crate) fn report_html_validation(html: &str) {
+    match html_validation() {
+        HtmlValidation::Disabled => {}
+        HtmlValidation::Log => {
+            for warning in validate_html(html) {
+                tracing::warn!("HTML validation failed: {warning}");
+            }
+        }
+        HtmlValidation::Panic => {
+            let warnings: Vec<_> = validate_html(html)
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+
+            assert!(
+                warnings.is_empty(),
+                "HTML validation failed: {}",
+                warnings.join("; ")
+            );
+        }
+    }
+}
+
+/// Check an `hx-*` attribute, given its name and the name without its `hx-` prefix.
+fn check_attribute(name: &str, suffix: &str, value: &str, warnings: &mut Vec<SanityWarning>) {
+    // `hx-on:click`, `hx-on::after-request` and `hx-on-click` are event handlers.
+    if suffix.starts_with("on:") || suffix.starts_with("on-") {
+        return;
+    }
+
+    if !KNOWN_ATTRIBUTES.contains(&suffix) {
+        warnings.push(SanityWarning::UnknownAttribute {
+            name: name.to_owned(),
+        });
+
+        return;
+    }
+
+    let invalid_value = |reason: String| SanityWarning::InvalidValue {
+        name: name.to_owned(),
+        value: value.to_owned(),
+        reason,
+    };
+
+    match suffix {
+        "swap" => {
+            if let Err(reason) = check_swap_spec(value) {
+                warnings.push(invalid_value(reason));
+            }
+        }
+        "swap-oob" => {
+            if let Err(reason) = check_oob_spec(value) {
+                warnings.push(invalid_value(reason));
+            }
+        }
+        "target" | "indicator" | "disabled-elt" | "include" | "select" => {
+            if let Err(reason) = check_extended_selector(value) {
+                warnings.push(SanityWarning::InvalidSelector {
+                    selector: value.to_owned(),
+                    context: format!("a `{name}` attribute"),
+                    reason,
+                });
+            }
+        }
+        "vals" | "headers" => {
+            if let Err(reason) = check_json_object(value) {
+                warnings.push(invalid_value(reason));
+            }
+        }
+        "boost" | "preserve" | "validate" if !matches!(value, "" | "true" | "false") => {
+            warnings.push(invalid_value("expected `true` or `false`".to_owned()));
+        }
+        _ => {}
+    }
+}
+
+/// Check a swap specification, like `outerHTML swap:1s`.
+fn check_swap_spec(value: &str) -> Result<(), String> {
+    for (i, token) in value.split_whitespace().enumerate() {
+        match token.split_once(':') {
+            Some((modifier, _)) => {
+                if !SWAP_MODIFIERS.contains(&modifier) {
+                    return Err(format!("unknown swap modifier `{modifier}`"));
+                }
+            }
+            None if i == 0 => {
+                if !SWAP_STYLES.contains(&token) {
+                    return Err(format!("unknown swap style `{token}`"));
+                }
+            }
+            None => return Err(format!("unexpected token `{token}`")),
+        }
+    }
+
+    Ok(())
+}
+
+/// Check an `hx-swap-oob` value, like `true` or `beforeend:#log`.
+fn check_oob_spec(value: &str) -> Result<(), String> {
+    if value.is_empty() || value == "true" {
+        return Ok(());
+    }
+
+    match value.split_once(':') {
+        Some((style, selector)) => {
+            check_swap_spec(style)?;
+            check_selector(selector)
+        }
+        None => check_swap_spec(value),
+    }
+}
+
+/// Check a CSS selector, or an HTMX extended selector like `closest tr` or `next .item`.
+fn check_extended_selector(value: &str) -> Result<(), String> {
+    let value = value.trim();
+
+    if matches!(
+        value,
+        "this" | "next" | "previous" | "document" | "window" | "body" | "inherit"
+    ) {
+        return Ok(());
+    }
+
+    for prefix in ["closest ", "find ", "findAll ", "next ", "previous "] {
+        if let Some(selector) = value.strip_prefix(prefix) {
+            return check_selector(selector);
+        }
+    }
+
+    check_selector(value)
+}
+
+/// Check a CSS selector.
+fn check_selector(value: &str) -> Result<(), String> {
+    Selector::parse(value.trim())
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+/// Check an `hx-vals`-like JSON object, whose braces are optional.
+fn check_json_object(value: &str) -> Result<(), String> {
+    let value = value.trim();
+
+    if value.starts_with("js:") || value.starts_with("javascript:") {
+        return Ok(());
+    }
+
+    let json = if value.starts_with('{') {
+        value.to_owned()
+    } else {
+        format!("{{{value}}}")
+    };
+
+    serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&json)
+        .map(|_| ())
+        .map_err(|err| format!("expected a JSON object: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::htmx::{Identity, Preserved};
+
+    #[test]
+    fn test_check_html() {
+        assert!(
+            check_html(
+                r##"<div hx-get="/items" hx-target="closest tr" hx-swap="outerHTML swap:1s" hx-vals='{"a": 1}' hx-on::after-request="x()"></div>"##
+            )
+            .is_empty()
+        );
+
+        assert_eq!(
+            check_html(
+                r#"<div hx-gte="/items"><p data-hx-swap="outer"></p><p hx-target="li["></p></div>"#
+            ),
+            vec![
+                SanityWarning::UnknownAttribute {
+                    name: "hx-gte".to_owned()
+                },
+                SanityWarning::InvalidValue {
+                    name: "hx-swap".to_owned(),
+                    value: "outer".to_owned(),
+                    reason: "unknown swap style `outer`".to_owned(),
+                },
+                SanityWarning::InvalidSelector {
+                    selector: "li[".to_owned(),
+                    context: "a `hx-target` attribute".to_owned(),
+                    reason: check_selector("li[").unwrap_err(),
+                },
+            ]
+        );
+
+        assert_eq!(check_html(r#"<p hx-vals="a: 1">"#).len(), 1);
+    }
+
+    #[test]
+    fn test_check_oob() {
+        assert!(check_oob("<li>a</li>", &InsertStrategy::BeforeEnd, "#log").is_empty());
+        assert!(
+            check_oob(
+                r#"<p hx-swap-oob="beforeend:#log"></p>"#,
+                &InsertStrategy::None,
+                "#x"
+            )
+            .is_empty()
+        );
+        assert_eq!(
+            check_oob(
+                "<li>a</li>",
+                &InsertStrategy::Custom("sideways".to_owned()),
+                "#log["
+            )
+            .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_check_identity() {
+        let id = HtmlId::from_static("counter").unwrap();
+
+        assert_eq!(check_identity(r#"<span id="counter">1</span>"#, &id), None);
+        assert_eq!(
+            check_identity("<span>1</span>", &id),
+            Some(SanityWarning::MissingId {
+                id: "counter".to_owned()
+            })
+        );
+        assert!(check_identity(r#"<span id="counter"></span><span></span>"#, &id).is_some());
+    }
+
+    #[test]
+    fn test_check_preserved() {
+        assert!(
+            check_html(
+                r#"<video id="player" hx-preserve="true"></video><p hx-preserve="false"></p>"#
+            )
+            .is_empty()
+        );
+        assert_eq!(
+            check_html(
+                r#"<video id="player" hx-preserve></video><textarea hx-preserve></textarea><audio id="player" data-hx-preserve></audio>"#
+            ),
+            vec![
+                SanityWarning::MissingPreservedId {
+                    tag: "textarea".to_owned()
+                },
+                SanityWarning::DuplicatePreservedId {
+                    id: "player".to_owned()
+                },
+            ]
+        );
+    }
+
+    /// A preserved video player, whose `id` changes on every render if it is not `fixed`.
+    struct Player {
+        fixed: bool,
+        renders: AtomicU8,
+    }
+
+    impl std::fmt::Display for Player {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "<video {}></video>", self.preserve_attributes())
+        }
+    }
+
+    impl Identity for Player {
+        fn id(&self) -> HtmlId {
+            match self.fixed {
+                true => HtmlId::from_static("player").unwrap(),
+                false => HtmlId::try_from(format!(
+                    "player-{}",
+                    self.renders.fetch_add(1, Ordering::Relaxed)
+                ))
+                .unwrap(),
+            }
+        }
+    }
+
+    impl Preserved for Player {}
+
+    #[test]
+    fn test_check_preserved_id() {
+        let player = |fixed| Player {
+            fixed,
+            renders: AtomicU8::new(0),
+        };
+
+        assert_eq!(check_preserved_id(&player(true)), None);
+        assert_eq!(
+            player(true).to_string(),
+            r#"<video id="player" hx-preserve="true"></video>"#
+        );
+        assert_eq!(
+            check_preserved_id(&player(false)),
+            Some(SanityWarning::UnstablePreservedId {
+                id: "player-0".to_owned(),
+                other: "player-1".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_html() {
+        assert!(
+            validate_html(
+                r#"<!DOCTYPE html><ul><li>a<li>b</ul><p>text<br><img src="a>b"/><!-- <div> --><script>if (a < b) { x("</div>"); }</script>"#
+            )
+            .is_empty()
+        );
+
+        assert_eq!(
+            validate_html(r#"<div id="a"><span id="a"></div></em><section>"#),
+            vec![
+                SanityWarning::UnclosedTag {
+                    tag: "span".to_owned()
+                },
+                SanityWarning::UnexpectedClosingTag {
+                    tag: "em".to_owned()
+                },
+                SanityWarning::UnclosedTag {
+                    tag: "section".to_owned()
+                },
+                SanityWarning::DuplicateId { id: "a".to_owned() },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_header() {
+        let name = http::HeaderName::from_static("hx-reswap");
+
+        assert!(
+            check_header(&name, &http::HeaderValue::from_static("innerHTML show:top")).is_empty()
+        );
+        assert_eq!(
+            check_header(&name, &http::HeaderValue::from_static("inner")).len(),
+            1
+        );
+    }
+}
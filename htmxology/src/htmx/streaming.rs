@@ -0,0 +1,158 @@
+//! Streamed response bodies.
+
+use std::{
+    convert::Infallible,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::body::{Body, BodyDataStream, Bytes};
+use futures_core::Stream;
+
+/// A streamed body for an HTMX [`Response`](super::Response).
+///
+/// Large pages can be sent as they are rendered, instead of being buffered in a `String`. The
+/// out-of-band inserts of the response are sent once the body stream completes, followed by its
+/// inline assets, if any: as the body was already sent, the assets are appended rather than
+/// injected in its `<head>`.
+///
+/// The HTML of streamed bodies is not checked by the [sanity checks](super::sanity).
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let rows = futures_util::stream::iter(items).map(|item| Bytes::from(format!("<tr>{item}</tr>")));
+///
+/// Response::new(StreamingBody::from_stream(rows)).with_oob(ItemCount::new(count))
+/// ```
+pub struct StreamingBody(Body);
+
+impl StreamingBody {
+    /// Create a streamed body from a stream of chunks.
+    pub fn from_stream<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Bytes> + Send + 'static,
+    {
+        Self(Body::from_stream(Chunks(Box::pin(stream))))
+    }
+
+    /// Append a tail to the body, sent once the body stream completes.
+    pub(super) fn with_tail(self, oob: String, inline_assets: Option<crate::InlineAssets>) -> Self {
+        if oob.is_empty() && inline_assets.is_none() {
+            return self;
+        }
+
+        Self(Body::from_stream(WithTail {
+            body: Some(self.0.into_data_stream()),
+            oob,
+            inline_assets,
+        }))
+    }
+
+    /// Get the underlying body.
+    pub fn into_body(self) -> Body {
+        self.0
+    }
+}
+
+impl From<Body> for StreamingBody {
+    fn from(body: Body) -> Self {
+        Self(body)
+    }
+}
+
+/// A stream of infallible chunks.
+struct Chunks(Pin<Box<dyn Stream<Item = Bytes> + Send>>);
+
+impl Stream for Chunks {
+    type Item = Result<Bytes, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.as_mut().poll_next(cx).map(|chunk| chunk.map(Ok))
+    }
+}
+
+/// A body stream followed by a tail, rendered once the body stream completes.
+struct WithTail {
+    body: Option<BodyDataStream>,
+    oob: String,
+    inline_assets: Option<crate::InlineAssets>,
+}
+
+impl Stream for WithTail {
+    type Item = Result<Bytes, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(body) = &mut self.body {
+            match Pin::new(body).poll_next(cx) {
+                Poll::Ready(None) => self.body = None,
+                poll => return poll,
+            }
+        }
+
+        let mut tail = std::mem::take(&mut self.oob);
+
+        // Assets are rendered last, to include the ones registered while streaming the body.
+        if let Some(inline_assets) = self.inline_assets.take() {
+            tail.push_str(&inline_assets.render());
+        }
+
+        Poll::Ready((!tail.is_empty()).then(|| Ok(Bytes::from(tail))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::response::IntoResponse;
+
+    use super::*;
+    use crate::htmx::{InsertStrategy, Response};
+
+    struct Iter(std::vec::IntoIter<Bytes>);
+
+    impl Stream for Iter {
+        type Item = Bytes;
+
+        fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Bytes>> {
+            Poll::Ready(self.0.next())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streaming_body_with_oob() {
+        let chunks = vec![
+            Bytes::from("<ul><li>a</li>"),
+            Bytes::from("<li>b</li></ul>"),
+        ];
+        let response = Response::new(StreamingBody::from_stream(Iter(chunks.into_iter())))
+            .with_raw_oob(InsertStrategy::InnerHtml, "#count", "<span>2</span>")
+            .with_header(
+                http::header::CACHE_CONTROL,
+                http::HeaderValue::from_static("no-cache"),
+            )
+            .into_response();
+
+        assert_eq!(response.headers()[http::header::CONTENT_TYPE], "text/html");
+        assert_eq!(response.headers()[http::header::CACHE_CONTROL], "no-cache");
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            body,
+            r##"<ul><li>a</li><li>b</li></ul><span hx-swap-oob="innerHTML:#count">2</span>"##
+        );
+    }
+
+    #[tokio::test]
+    async fn test_streaming_body_from_body() {
+        let response =
+            Response::new(StreamingBody::from(Body::from("<p>Hello</p>"))).into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        assert_eq!(body, "<p>Hello</p>");
+    }
+}
@@ -0,0 +1,327 @@
+//! Typed HTMX boot configuration, rendered in the `<head>` of layouts.
+
+use std::{fmt::Display, time::Duration};
+
+use crate::htmx::{InsertStrategy, escape_attribute_value};
+
+/// The HTMX version loaded from the CDN by default.
+pub const DEFAULT_HTMX_VERSION: &str = "2.0.3";
+
+/// The SRI hash of the default HTMX version.
+const DEFAULT_HTMX_INTEGRITY: &str =
+    "sha384-0895/pl2MU10Hqc6jd4RvrthNlDiE9U1tWmX7WRESftEDRosgxNsQG/Ze9YMRzHq";
+
+/// The base URL of the CDN HTMX and its extensions are loaded from.
+const CDN_BASE_URL: &str = "https://unpkg.com";
+
+/// Where the HTMX scripts are loaded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HtmxScriptSource {
+    /// The `unpkg` CDN, with the SRI hashes of the scripts, if known.
+    Cdn,
+
+    /// A URL prefix the scripts are served under, typically with
+    /// [`StaticAssets`](crate::StaticAssets).
+    ///
+    /// HTMX is loaded from `<prefix>/htmx.min.js`, and extensions from `<prefix>/ext/<name>.js`.
+    SelfHosted(String),
+}
+
+/// An HTMX extension, loaded after HTMX.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmxExtension {
+    /// The name of the extension, like `preload`.
+    name: String,
+
+    /// The version of the extension package on the CDN.
+    version: String,
+
+    /// The SRI hash of the extension script on the CDN.
+    integrity: Option<String>,
+}
+
+impl HtmxExtension {
+    /// Create an extension, with the version of its `htmx-ext-<name>` package on the CDN.
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            integrity: None,
+        }
+    }
+
+    /// Set the SRI hash of the extension script on the CDN, like `sha384-...`.
+    ///
+    /// Without it, the script is loaded from the CDN without integrity check.
+    pub fn with_integrity(mut self, integrity: impl Into<String>) -> Self {
+        self.integrity = Some(integrity.into());
+        self
+    }
+
+    /// Get the name of the extension.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// The boot configuration of HTMX: its `htmx-config` options, and the scripts of HTMX and of the
+/// extensions to load.
+///
+/// The configuration renders the `<meta name="htmx-config">` tag followed by the `<script>` tags,
+/// meant for the `<head>` of layouts, and the names of the extensions, for the `hx-ext`
+/// attribute activating them:
+///
+/// ```rust,ignore
+/// let boot = HtmxBootConfig::new()
+///     .with_default_swap_style(InsertStrategy::OuterHtml)
+///     .with_timeout(Duration::from_secs(10))
+///     .with_extension(HtmxExtension::new("preload", "2.0.1").with_integrity("sha384-..."));
+/// ```
+///
+/// ```html
+/// <head>{{ boot|safe }}</head>
+/// <body hx-ext="{{ boot.hx_ext() }}">...</body>
+/// ```
+///
+/// Scripts are loaded from the `unpkg` CDN by default, pinned to [`DEFAULT_HTMX_VERSION`] with its
+/// SRI hash.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmxBootConfig {
+    /// The `htmx-config` options.
+    options: serde_json::Map<String, serde_json::Value>,
+
+    /// The extensions to load, in order.
+    extensions: Vec<HtmxExtension>,
+
+    /// Where the scripts are loaded from.
+    source: HtmxScriptSource,
+
+    /// The HTMX version loaded from the CDN.
+    version: String,
+
+    /// The SRI hash of the HTMX script on the CDN.
+    integrity: Option<String>,
+
+    /// The CSP nonce of the scripts, if any.
+    nonce: Option<String>,
+}
+
+impl Default for HtmxBootConfig {
+    fn default() -> Self {
+        Self {
+            options: serde_json::Map::new(),
+            extensions: Vec::new(),
+            source: HtmxScriptSource::Cdn,
+            version: DEFAULT_HTMX_VERSION.to_owned(),
+            integrity: Some(DEFAULT_HTMX_INTEGRITY.to_owned()),
+            nonce: None,
+        }
+    }
+}
+
+impl HtmxBootConfig {
+    /// Create a configuration loading the default HTMX version from the CDN, without options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the default swap style (`defaultSwapStyle`).
+    pub fn with_default_swap_style(self, strategy: InsertStrategy) -> Self {
+        self.with_option("defaultSwapStyle", strategy.to_string())
+    }
+
+    /// Set the timeout of requests (`timeout`).
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_option(
+            "timeout",
+            u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX),
+        )
+    }
+
+    /// Set the number of pages kept in the history cache (`historyCacheSize`).
+    ///
+    /// `0` disables the history cache.
+    pub fn with_history_cache_size(self, history_cache_size: usize) -> Self {
+        self.with_option("historyCacheSize", history_cache_size)
+    }
+
+    /// Set any other `htmx-config` option, like `("scrollBehavior", "smooth")`.
+    pub fn with_option(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        self.options.insert(name.into(), value.into());
+        self
+    }
+
+    /// Load an extension after HTMX.
+    pub fn with_extension(mut self, extension: HtmxExtension) -> Self {
+        self.extensions.push(extension);
+        self
+    }
+
+    /// Set where the scripts are loaded from.
+    pub fn with_source(mut self, source: HtmxScriptSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Load the scripts from a URL prefix, like `/static/htmx`, instead of the CDN.
+    pub fn self_hosted(self, prefix: impl Into<String>) -> Self {
+        self.with_source(HtmxScriptSource::SelfHosted(prefix.into()))
+    }
+
+    /// Load another HTMX version from the CDN, with the SRI hash of its script.
+    pub fn with_htmx_version(
+        mut self,
+        version: impl Into<String>,
+        integrity: impl Into<String>,
+    ) -> Self {
+        self.version = version.into();
+        self.integrity = Some(integrity.into());
+        self
+    }
+
+    /// Set the CSP nonce added to the scripts.
+    pub fn with_nonce(mut self, nonce: impl Into<String>) -> Self {
+        self.nonce = Some(nonce.into());
+        self
+    }
+
+    /// Get the names of the extensions, separated by commas, for the `hx-ext` attribute.
+    pub fn hx_ext(&self) -> String {
+        self.extensions
+            .iter()
+            .map(HtmxExtension::name)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Render the `<meta name="htmx-config">` tag, if there are options, and the `<script>` tags.
+    pub fn render(&self) -> String {
+        self.to_string()
+    }
+
+    /// Write a `<script>` tag.
+    fn fmt_script(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        src: &str,
+        integrity: Option<&str>,
+    ) -> std::fmt::Result {
+        write!(f, r#"<script src="{}""#, escape_attribute_value(src))?;
+
+        if let Some(integrity) = integrity {
+            write!(
+                f,
+                r#" integrity="{}" crossorigin="anonymous""#,
+                escape_attribute_value(integrity)
+            )?;
+        }
+
+        if let Some(nonce) = &self.nonce {
+            write!(f, r#" nonce="{}""#, escape_attribute_value(nonce))?;
+        }
+
+        write!(f, "></script>")
+    }
+}
+
+impl Display for HtmxBootConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.options.is_empty() {
+            let options = serde_json::to_string(&self.options).map_err(|_| std::fmt::Error)?;
+
+            write!(
+                f,
+                r#"<meta name="htmx-config" content="{}">"#,
+                escape_attribute_value(&options)
+            )?;
+        }
+
+        match &self.source {
+            HtmxScriptSource::Cdn => {
+                self.fmt_script(
+                    f,
+                    &format!("{CDN_BASE_URL}/htmx.org@{}/dist/htmx.min.js", self.version),
+                    self.integrity.as_deref(),
+                )?;
+
+                for extension in &self.extensions {
+                    self.fmt_script(
+                        f,
+                        &format!(
+                            "{CDN_BASE_URL}/htmx-ext-{name}@{}/{name}.js",
+                            extension.version,
+                            name = extension.name
+                        ),
+                        extension.integrity.as_deref(),
+                    )?;
+                }
+            }
+            HtmxScriptSource::SelfHosted(prefix) => {
+                let prefix = prefix.trim_end_matches('/');
+
+                self.fmt_script(f, &format!("{prefix}/htmx.min.js"), None)?;
+
+                for extension in &self.extensions {
+                    self.fmt_script(f, &format!("{prefix}/ext/{}.js", extension.name), None)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default() {
+        assert_eq!(
+            HtmxBootConfig::new().render(),
+            r#"<script src="https://unpkg.com/htmx.org@2.0.3/dist/htmx.min.js" integrity="sha384-0895/pl2MU10Hqc6jd4RvrthNlDiE9U1tWmX7WRESftEDRosgxNsQG/Ze9YMRzHq" crossorigin="anonymous"></script>"#
+        );
+    }
+
+    #[test]
+    fn test_options_and_extensions() {
+        let boot = HtmxBootConfig::new()
+            .with_default_swap_style(InsertStrategy::OuterHtml)
+            .with_timeout(Duration::from_secs(10))
+            .with_history_cache_size(0)
+            .with_option("scrollBehavior", "<smooth>")
+            .with_extension(HtmxExtension::new("preload", "2.0.1").with_integrity("sha384-abc"))
+            .with_extension(HtmxExtension::new("sse", "2.2.2"))
+            .with_nonce("n0nce");
+
+        assert_eq!(boot.hx_ext(), "preload,sse");
+        assert_eq!(
+            boot.render(),
+            concat!(
+                r#"<meta name="htmx-config" content="{&quot;defaultSwapStyle&quot;:&quot;outerHTML&quot;,&quot;historyCacheSize&quot;:0,&quot;scrollBehavior&quot;:&quot;&lt;smooth&gt;&quot;,&quot;timeout&quot;:10000}">"#,
+                r#"<script src="https://unpkg.com/htmx.org@2.0.3/dist/htmx.min.js" integrity="sha384-0895/pl2MU10Hqc6jd4RvrthNlDiE9U1tWmX7WRESftEDRosgxNsQG/Ze9YMRzHq" crossorigin="anonymous" nonce="n0nce"></script>"#,
+                r#"<script src="https://unpkg.com/htmx-ext-preload@2.0.1/preload.js" integrity="sha384-abc" crossorigin="anonymous" nonce="n0nce"></script>"#,
+                r#"<script src="https://unpkg.com/htmx-ext-sse@2.2.2/sse.js" nonce="n0nce"></script>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_self_hosted() {
+        let boot = HtmxBootConfig::new()
+            .self_hosted("/static/htmx/")
+            .with_extension(HtmxExtension::new("preload", "2.0.1").with_integrity("sha384-abc"));
+
+        assert_eq!(
+            boot.render(),
+            concat!(
+                r#"<script src="/static/htmx/htmx.min.js"></script>"#,
+                r#"<script src="/static/htmx/ext/preload.js"></script>"#,
+            )
+        );
+    }
+}
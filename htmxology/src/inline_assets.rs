@@ -0,0 +1,237 @@
+//! Inline critical CSS and JavaScript, deduplicated per response.
+
+use std::{
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
+
+use base64::Engine;
+use sha2::Digest;
+
+/// The kind of an inline asset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InlineAssetKind {
+    /// A stylesheet, emitted in a `<style>` element.
+    Style,
+
+    /// A script, emitted in a `<script>` element.
+    Script,
+}
+
+impl InlineAssetKind {
+    /// The HTML tag of the asset.
+    fn tag(self) -> &'static str {
+        match self {
+            Self::Style => "style",
+            Self::Script => "script",
+        }
+    }
+}
+
+/// An inline asset.
+#[derive(Debug, Clone)]
+struct InlineAsset {
+    /// The kind of the asset.
+    kind: InlineAssetKind,
+
+    /// The content of the asset.
+    content: String,
+
+    /// The CSP hash source of the content, like `'sha256-...'`.
+    hash: String,
+}
+
+/// A registry of small inline CSS and JavaScript snippets, emitted once per response.
+///
+/// Fragments and layouts register the snippets they depend on while they render, and the
+/// registry emits each distinct snippet once, in registration order. Snippets can carry a CSP
+/// nonce, and their hashes are available for `style-src` and `script-src` directives.
+///
+/// A registry is meant to be created for each response, and shared (cheaply cloned) with the
+/// templates rendering it. The [`InlineAssets::style`] and [`InlineAssets::script`] methods render
+/// nothing, so that they can be called from templates directly:
+///
+/// ```html
+/// <div class="card">{{ assets.style(".card { border: 1px solid }") }}...</div>
+/// ```
+///
+/// The registered snippets are typically emitted with
+/// [`htmx::Response::with_inline_assets`](crate::htmx::Response::with_inline_assets), which
+/// injects them after the whole response has been rendered.
+///
+/// The content of the snippets is emitted verbatim: it must be trusted, and must not contain
+/// closing `</style>` or `</script>` tags.
+#[derive(Debug, Clone, Default)]
+pub struct InlineAssets {
+    /// The registered assets, in registration order.
+    assets: Arc<Mutex<Vec<InlineAsset>>>,
+
+    /// The CSP nonce of the emitted elements, if any.
+    nonce: Option<String>,
+}
+
+impl InlineAssets {
+    /// Create a new, empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the CSP nonce added to the emitted elements.
+    pub fn with_nonce(mut self, nonce: impl Into<String>) -> Self {
+        self.nonce = Some(nonce.into());
+        self
+    }
+
+    /// Register an inline stylesheet.
+    ///
+    /// Renders nothing, so that it can be called from templates.
+    pub fn style(&self, css: impl Display) -> &'static str {
+        self.register(InlineAssetKind::Style, css.to_string());
+        ""
+    }
+
+    /// Register an inline script.
+    ///
+    /// Renders nothing, so that it can be called from templates.
+    pub fn script(&self, js: impl Display) -> &'static str {
+        self.register(InlineAssetKind::Script, js.to_string());
+        ""
+    }
+
+    /// Register an inline asset, unless the same asset was already registered.
+    fn register(&self, kind: InlineAssetKind, content: String) {
+        let hash = format!(
+            "'sha256-{}'",
+            base64::engine::general_purpose::STANDARD.encode(sha2::Sha256::digest(&content))
+        );
+        let mut assets = self
+            .assets
+            .lock()
+            .expect("assets lock should not be poisoned");
+
+        if !assets
+            .iter()
+            .any(|asset| asset.kind == kind && asset.hash == hash)
+        {
+            assets.push(InlineAsset {
+                kind,
+                content,
+                hash,
+            });
+        }
+    }
+
+    /// Check whether no asset was registered.
+    pub fn is_empty(&self) -> bool {
+        self.assets
+            .lock()
+            .expect("assets lock should not be poisoned")
+            .is_empty()
+    }
+
+    /// Get the CSP hash sources of the registered assets of a kind, like `'sha256-...'`.
+    ///
+    /// The sources can be listed in the `style-src` or `script-src` directive of a
+    /// `Content-Security-Policy` header, as an alternative to nonces.
+    pub fn csp_hashes(&self, kind: InlineAssetKind) -> Vec<String> {
+        self.assets
+            .lock()
+            .expect("assets lock should not be poisoned")
+            .iter()
+            .filter(|asset| asset.kind == kind)
+            .map(|asset| asset.hash.clone())
+            .collect()
+    }
+
+    /// Render the registered assets as HTML elements.
+    pub fn render(&self) -> String {
+        let nonce = match &self.nonce {
+            Some(nonce) => format!(r#" nonce="{}""#, crate::htmx::escape_attribute_value(nonce)),
+            None => String::new(),
+        };
+
+        self.assets
+            .lock()
+            .expect("assets lock should not be poisoned")
+            .iter()
+            .map(|asset| {
+                let tag = asset.kind.tag();
+
+                format!("<{tag}{nonce}>{}</{tag}>", asset.content)
+            })
+            .collect()
+    }
+
+    /// Inject the registered assets in an HTML document or fragment.
+    ///
+    /// The assets are inserted at the end of the `<head>` element of documents, or at the start of
+    /// fragments.
+    pub fn inject(&self, html: &str) -> String {
+        let assets = self.render();
+
+        if assets.is_empty() {
+            return html.to_owned();
+        }
+
+        match find_ascii_case_insensitive(html, "</head>") {
+            Some(index) => format!("{}{assets}{}", &html[..index], &html[index..]),
+            None => format!("{assets}{html}"),
+        }
+    }
+}
+
+/// Find the first occurrence of an ASCII needle, ignoring case.
+fn find_ascii_case_insensitive(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deduplication() {
+        let assets = InlineAssets::new().with_nonce("abc");
+
+        assert_eq!(assets.style(".a{}"), "");
+        assets.script("init()");
+        assets.style(".a{}");
+        assets.script(".a{}");
+
+        assert_eq!(
+            assets.render(),
+            r#"<style nonce="abc">.a{}</style><script nonce="abc">init()</script><script nonce="abc">.a{}</script>"#
+        );
+    }
+
+    #[test]
+    fn test_csp_hashes() {
+        let assets = InlineAssets::new();
+
+        assets.script("alert('Hello, world.');");
+
+        assert_eq!(
+            assets.csp_hashes(InlineAssetKind::Script),
+            vec!["'sha256-qznLcsROx4GACP2dm0UCKCzCG+HiZ1guq6ZZDob/Tng='"]
+        );
+        assert!(assets.csp_hashes(InlineAssetKind::Style).is_empty());
+    }
+
+    #[test]
+    fn test_inject() {
+        let assets = InlineAssets::new();
+
+        assert_eq!(assets.inject("<p>Hi</p>"), "<p>Hi</p>");
+
+        assets.style("p{}");
+
+        assert_eq!(
+            assets.inject("<html><head><title>T</title></HEAD><body></body></html>"),
+            "<html><head><title>T</title><style>p{}</style></HEAD><body></body></html>"
+        );
+        assert_eq!(assets.inject("<p>Hi</p>"), "<style>p{}</style><p>Hi</p>");
+    }
+}
@@ -0,0 +1,174 @@
+//! Named slots of layouts, filled by the fragments rendered in them.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
+
+/// The start of the placeholder of a slot.
+const PLACEHOLDER_START: &str = "<!--htmxology-slot:";
+
+/// The end of the placeholder of a slot.
+const PLACEHOLDER_END: &str = "-->";
+
+/// A registry of the content of named layout slots, like `sidebar`, `modals` or `scripts`.
+///
+/// Layouts mark where each slot goes with [`LayoutSlots::placeholder`], and the fragments they
+/// render, possibly from different subcontrollers, fill the slots with [`LayoutSlots::fill`]. As a
+/// fragment can fill a slot after its placeholder was rendered, like a script going in the
+/// `<head>` of the page, the placeholders are replaced once the whole response has been
+/// rendered, typically with
+/// [`htmx::Response::with_layout_slots`](crate::htmx::Response::with_layout_slots).
+///
+/// Each slot emits its distinct contents once, in fill order, so that fragments rendered several
+/// times don't duplicate their modals or scripts.
+///
+/// A registry is meant to be created for each response, and shared (cheaply cloned) with the
+/// templates rendering it, for instance through the controller `Args`:
+///
+/// ```html
+/// <html>
+///   <head>{{ slots.placeholder("scripts") }}</head>
+///   <body>
+///     <aside>{{ slots.placeholder("sidebar") }}</aside>
+///     <main>{{ content }}</main>
+///   </body>
+/// </html>
+/// ```
+///
+/// With a fragment of the page filling the slots:
+///
+/// ```html
+/// {{ slots.fill("scripts", "<script src=\"/chart.js\"></script>") }}
+/// <canvas id="chart"></canvas>
+/// ```
+///
+/// The content of the slots is emitted verbatim: it must be trusted or escaped. Contents filling
+/// a slot without placeholder, like in fragments rendered for htmx requests, are discarded.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutSlots {
+    /// The contents of each slot, in fill order.
+    slots: Arc<Mutex<HashMap<String, Vec<String>>>>,
+}
+
+impl LayoutSlots {
+    /// Create a new, empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render the placeholder of a slot, replaced by its contents on injection.
+    ///
+    /// Slot names should only contain alphanumeric characters, dashes and underscores.
+    pub fn placeholder(&self, slot: &str) -> String {
+        format!("{PLACEHOLDER_START}{slot}{PLACEHOLDER_END}")
+    }
+
+    /// Fill a slot with some content, unless the slot already has the same content.
+    ///
+    /// Renders nothing, so that it can be called from templates.
+    pub fn fill(&self, slot: &str, content: impl Display) -> &'static str {
+        let content = content.to_string();
+        let mut slots = self
+            .slots
+            .lock()
+            .expect("slots lock should not be poisoned");
+        let contents = slots.entry(slot.to_owned()).or_default();
+
+        if !contents.contains(&content) {
+            contents.push(content);
+        }
+
+        ""
+    }
+
+    /// Check whether no slot was filled.
+    pub fn is_empty(&self) -> bool {
+        self.slots
+            .lock()
+            .expect("slots lock should not be poisoned")
+            .is_empty()
+    }
+
+    /// Render the contents of a slot.
+    pub fn render(&self, slot: &str) -> String {
+        self.slots
+            .lock()
+            .expect("slots lock should not be poisoned")
+            .get(slot)
+            .map(|contents| contents.concat())
+            .unwrap_or_default()
+    }
+
+    /// Replace the slot placeholders of an HTML document or fragment by their contents.
+    ///
+    /// The placeholders of empty slots are removed.
+    pub fn inject(&self, html: &str) -> String {
+        let mut result = String::with_capacity(html.len());
+        let mut rest = html;
+
+        while let Some(start) = rest.find(PLACEHOLDER_START) {
+            let after_start = &rest[start + PLACEHOLDER_START.len()..];
+
+            let Some(end) = after_start.find(PLACEHOLDER_END) else {
+                break;
+            };
+
+            result.push_str(&rest[..start]);
+            result.push_str(&self.render(&after_start[..end]));
+            rest = &after_start[end + PLACEHOLDER_END.len()..];
+        }
+
+        result.push_str(rest);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_and_render() {
+        let slots = LayoutSlots::new();
+
+        assert!(slots.is_empty());
+        assert_eq!(slots.fill("modals", "<dialog>A</dialog>"), "");
+        slots.fill("scripts", "<script>a()</script>");
+        slots.fill("modals", "<dialog>B</dialog>");
+        slots.fill("modals", "<dialog>A</dialog>");
+
+        assert_eq!(
+            slots.render("modals"),
+            "<dialog>A</dialog><dialog>B</dialog>"
+        );
+        assert_eq!(slots.render("scripts"), "<script>a()</script>");
+        assert_eq!(slots.render("sidebar"), "");
+    }
+
+    #[test]
+    fn test_inject() {
+        let slots = LayoutSlots::new();
+        let layout = format!(
+            "<head>{}</head><body><aside>{}</aside><main>{}</main></body>",
+            slots.placeholder("scripts"),
+            slots.placeholder("sidebar"),
+            slots.placeholder("scripts"),
+        );
+
+        slots.fill("scripts", "<script>a()</script>");
+        slots.fill("footer", "<p>Discarded</p>");
+
+        assert_eq!(
+            slots.inject(&layout),
+            "<head><script>a()</script></head><body><aside></aside><main><script>a()</script></main></body>"
+        );
+
+        // Unterminated placeholders are left as-is.
+        assert_eq!(
+            slots.inject("<p>Hi</p><!--htmxology-slot:scripts"),
+            "<p>Hi</p><!--htmxology-slot:scripts"
+        );
+    }
+}
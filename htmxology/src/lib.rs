@@ -5,38 +5,153 @@
 //! # Features
 //!
 //! - `auto-reload`: Automatically reload the server when the source code changes. Useful for
-//!   development. **Not enabled by default.**
+//!   development. Also enables zero-downtime restarts on Unix, through [`Handoff`]. **Not enabled
+//!   by default.**
 //! - `interfaces`: Enrich the local base URL guessing logic with the ability to inspect the
 //!   workstation's network interfaces. Useful for development. **Not enabled by default.**
+//! - `session`: Per-user sessions stored in memory or in signed cookies, with the [`session`]
+//!   module. **Not enabled by default.**
+//! - `sse`: Push fragments to connected clients through Server-Sent Events, with the [`sse`]
+//!   module. **Not enabled by default.**
+//! - `csrf`: Cross-site request forgery protection for forms and HTMX requests, with the
+//!   [`csrf`] module. **Not enabled by default.**
+//! - `admin`: Endpoints to inspect and purge the cache and session stores, with the [`admin`]
+//!   module. **Not enabled by default.**
+//! - `openapi`: Generate OpenAPI 3 documents from the route descriptions, with the [`openapi`]
+//!   module. **Not enabled by default.**
+//! - `redirects`: Redirects and rewrites loaded from TOML or CSV files, with the [`redirects`]
+//!   module. **Not enabled by default.**
+//! - `compression`: Compress the responses with gzip, brotli or zstd, with
+//!   [`ControllerRouter::with_compression`]. **Not enabled by default.**
+//! - `scaffold`: Generate starter projects with the [`scaffold`] module, or the
+//!   `htmxology-scaffold` binary. **Not enabled by default.**
+//! - `include-dir`: Serve static assets embedded with the `include_dir` crate, through
+//!   [`StaticAssets`]. **Not enabled by default.**
+//! - `tls`: Terminate TLS connections in the server, with
+//!   [`ServerBuilder::with_tls`]. **Not enabled by default.**
+//! - `lambda`: Run controller routers on AWS Lambda, with
+//!   [`ControllerService::run_on_lambda`]. **Not enabled by default.**
+//! - `testing`: Test multi-step HTMX navigations end-to-end without a browser, with the
+//!   [`testing`] module. **Not enabled by default.**
+//! - `ws`: Push fragments to connected clients through WebSockets, with the [`ws`] module. **Not
+//!   enabled by default.**
 
 pub mod htmx;
 
 mod caching;
+mod coalescing;
 mod controller;
+mod error_pages;
+mod flash;
+mod fragment_publisher;
+mod htmx_boot;
+mod inline_assets;
+mod layout_slots;
+mod metrics;
+mod negotiation;
+mod prerender;
+mod profile;
 mod route;
 mod server;
+mod static_assets;
+mod trace_context;
+
+#[cfg(feature = "admin")]
+pub mod admin;
+
+#[cfg(feature = "csrf")]
+pub mod csrf;
+
+#[cfg(feature = "openapi")]
+pub mod openapi;
+
+#[cfg(feature = "redirects")]
+pub mod redirects;
+
+#[cfg(feature = "sse")]
+pub mod sse;
+
+#[cfg(feature = "scaffold")]
+pub mod scaffold;
+
+#[cfg(feature = "session")]
+pub mod session;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+#[cfg(feature = "ws")]
+pub mod ws;
 
 #[cfg(feature = "templating")]
 mod templating;
 
 pub use caching::{
-    Cache, CacheControl, CachingResponseExt, Controller as CachingController,
-    ControllerExt as CachingControllerExt,
+    Cache, CacheBodyLimit, CacheControl, CacheDirectives, CacheKey, CachePart, CacheRender,
+    CacheStore, CacheStoreEntry, CacheStoreMetrics, CacheTags, CacheableResponse,
+    CachingResponseExt, Controller as CachingController, ControllerExt as CachingControllerExt,
+    DynamicFragment, MemoryCacheStore, RequestCacheKey,
 };
-pub use controller::{Controller, HasSubcontroller, SubcontrollerExt};
-pub use route::{ParseError, Route, RouteExt, decode_path_argument, replace_request_path};
+pub use coalescing::{
+    Coalescing, Controller as CoalescingController, ControllerExt as CoalescingControllerExt,
+};
+pub use controller::{
+    BoxController, Controller, ControllerExt, DEFAULT_RENDER_DEPTH_LIMIT, HandlerController,
+    HandlerRoute, HasSubcontroller, MapResponse, Negotiate, PageAssembler, RenderError,
+    RenderStack, SubcontrollerExt, WithHeaders,
+};
+pub use error_pages::ErrorRenderer;
+pub use flash::{
+    DEFAULT_FLASH_CONTAINER_ID, DEFAULT_FLASH_COOKIE_NAME, Flash, FlashLevel, FlashMessage,
+    FlashMessages,
+};
+pub use fragment_publisher::FragmentPublisher;
+pub use htmx_boot::{DEFAULT_HTMX_VERSION, HtmxBootConfig, HtmxExtension, HtmxScriptSource};
+pub use inline_assets::{InlineAssetKind, InlineAssets};
+pub use layout_slots::LayoutSlots;
+pub use metrics::{
+    Controller as MetricsController, ControllerExt as MetricsControllerExt, RouteMetrics,
+    RouteMetricsEntry, RouteMetricsFragment,
+};
+pub use negotiation::{NegotiationResponseExt, Representation};
+pub use prerender::{PrerenderError, Prerenderer};
+pub use profile::{ParseProfileError, Profile, ProfileFromEnvError, ProfileSettings};
+pub use route::{
+    BASE_HOST_PLACEHOLDER, InvalidMediaType, METHOD_OVERRIDE_HEADER, MediaType, ParameterLocation,
+    ParseError, RestPath, Route, RouteDescription, RouteEntry, RouteExt, RouteParameter,
+    RoutePattern, RoutePatternConstraint, RoutePatternSegment, RouteSuggestion,
+    decode_encoded_path_argument, decode_path_argument, decode_rest_path_argument,
+    fmt_path_segment, fmt_with_path_prefix, format_fragment, host_matches, is_htmx_method,
+    is_valid_path_argument, normalize_path_prefix, parse_encoded_path_argument,
+    parse_path_argument, path_prefix, replace_request_path, request_has_method, request_host,
+    request_matches_host, strip_path_prefix, with_path_prefix,
+};
+#[cfg(feature = "derive")]
+pub use route::{
+    MultipartForm, RouteMatch, RouteOrder, UploadedFile, check_route_patterns, decode_form_body,
+    decode_json_body, decode_multipart_body, decode_negotiated_body,
+};
+#[cfg(feature = "tls")]
+pub use server::TlsError;
 #[cfg(feature = "auto-reload")]
 pub use server::auto_reload::get_or_bind_tcp_listener;
+#[cfg(all(feature = "auto-reload", unix))]
+pub use server::handoff::{HANDOFF_FD_ENV, Handoff, HandoffError, StateHandoff};
 pub use server::{
-    ControllerRouter, ServeError, Server, ServerBuilder, ServerInfo, ServerOptions,
-    ServerOptionsFromEnvError,
+    ControllerRouter, ControllerRouterDescription, ControllerService, ListenerAddr,
+    NestedRouterDescription, RequestInfo, ServeError, Server, ServerBuilder, ServerHandle,
+    ServerInfo, ServerListener, ServerOptions, ServerOptionsFromEnvError, ShutdownHandle,
 };
+pub use static_assets::StaticAssets;
+pub use trace_context::{TRACEPARENT_HEADER, TRACESTATE_HEADER, TraceContext};
 
 #[cfg(feature = "templating")]
-pub use templating::RenderIntoResponse;
+pub use templating::{AssetUrls, RenderContext, RenderContextConfig, RenderIntoResponse};
 
 #[cfg(feature = "derive")]
-pub use htmxology_macros::{DisplayDelegate, Fragment, Identity, Named, Route, RoutingController};
+pub use htmxology_macros::{
+    DisplayDelegate, Fragment, HtmlForm, Identity, Named, Preserved, Route, RoutingController,
+};
 
 // Re-export traits at root level for convenience (Issue #7)
-pub use htmx::{Fragment, Identity, Named};
+pub use htmx::{Fragment, Identity, Named, Preserved};
@@ -0,0 +1,76 @@
+use std::{future::Future, time::Instant};
+
+use crate::Route;
+
+/// A controller that records the [metrics](super::RouteMetrics) of the requests to another
+/// controller.
+///
+/// Wrap cached controllers, rather than the other way around, to record cache hits.
+pub struct Controller<C: crate::Controller> {
+    pub controller: C,
+    pub metrics: super::RouteMetrics,
+}
+
+impl<C: crate::Controller> Clone for Controller<C> {
+    fn clone(&self) -> Self {
+        Self {
+            controller: self.controller.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+impl<C> crate::Controller for Controller<C>
+where
+    C: crate::Controller<Response = Result<axum::response::Response, axum::response::Response>>,
+    C::Route: crate::Route + Send + Sync + axum::extract::FromRequest<Self>,
+{
+    type Route = C::Route;
+    type Args = C::Args;
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    fn handle_request(
+        &self,
+        route: Self::Route,
+        htmx: crate::htmx::Request,
+        parts: http::request::Parts,
+        server_info: &crate::ServerInfo,
+        args: Self::Args,
+    ) -> impl Future<Output = Self::Response> + Send {
+        let variant = route.variant_name();
+        let method = route.method();
+
+        async move {
+            let start = Instant::now();
+            let result = self
+                .controller
+                .handle_request(route, htmx, parts, server_info, args)
+                .await;
+
+            let response = match &result {
+                Ok(response) | Err(response) => response,
+            };
+
+            self.metrics
+                .record(variant, method, start.elapsed(), response);
+
+            result
+        }
+    }
+}
+
+/// An extension trait for controllers that adds route metrics support.
+pub trait ControllerExt: crate::Controller {
+    fn with_route_metrics(self, metrics: super::RouteMetrics) -> Controller<Self>
+    where
+        Self: Sized;
+}
+
+impl<C: crate::Controller> ControllerExt for C {
+    fn with_route_metrics(self, metrics: super::RouteMetrics) -> Controller<Self> {
+        Controller {
+            controller: self,
+            metrics,
+        }
+    }
+}
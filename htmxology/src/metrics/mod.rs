@@ -0,0 +1,345 @@
+//! In-process route metrics, for development.
+
+mod controller;
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt::Display,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+pub use controller::{Controller, ControllerExt};
+
+use crate::htmx::{HtmlId, Identity, escape_attribute_value, escape_text};
+
+/// The `id` of the route metrics fragment.
+const FRAGMENT_ID: &str = "htmxology-route-metrics";
+
+/// The default refresh interval of the route metrics fragment.
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The default number of routes listed by the route metrics fragment.
+const DEFAULT_FRAGMENT_LIMIT: usize = 20;
+
+/// The statistics of the routes, by variant name and method.
+type Routes = HashMap<(Cow<'static, str>, http::Method), RouteStats>;
+
+/// In-process metrics of the routes of a controller.
+///
+/// Controllers wrapped with [`ControllerExt::with_route_metrics`] record the duration of each
+/// request, whether it failed, and whether it was served from a cache, by route variant (see
+/// [`Route::variant_name`](crate::Route::variant_name)).
+///
+/// This is meant to give immediate feedback during development, without an external monitoring
+/// system: mount the metrics with [`ControllerRouter::with_route_metrics_endpoint`] to get an
+/// HTML fragment listing the slowest routes, which refreshes itself.
+///
+/// Cloning metrics is cheap, and the clones share the same records.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let metrics = RouteMetrics::default();
+/// let controller = AppController::default()
+///     .with_cache(Cache::default().with_store(MemoryCacheStore::default()))
+///     .with_route_metrics(metrics.clone());
+///
+/// let router = ControllerRouter::from(controller)
+///     .with_route_metrics_endpoint("/_dev/metrics", metrics);
+/// ```
+///
+/// [`ControllerRouter::with_route_metrics_endpoint`]: crate::ControllerRouter::with_route_metrics_endpoint
+#[derive(Debug, Clone)]
+pub struct RouteMetrics {
+    refresh_interval: Duration,
+    fragment_limit: usize,
+    routes: Arc<Mutex<Routes>>,
+}
+
+impl Default for RouteMetrics {
+    fn default() -> Self {
+        Self {
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            fragment_limit: DEFAULT_FRAGMENT_LIMIT,
+            routes: Default::default(),
+        }
+    }
+}
+
+/// The accumulated statistics of a route.
+#[derive(Debug, Clone, Copy, Default)]
+struct RouteStats {
+    requests: u64,
+    errors: u64,
+    cache_hits: u64,
+    total_duration: Duration,
+    max_duration: Duration,
+}
+
+/// A snapshot of the metrics of a route variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteMetricsEntry {
+    /// The name of the variant, like `Blog::Post`.
+    pub variant: String,
+
+    /// The HTTP method of the requests.
+    pub method: http::Method,
+
+    /// The number of handled requests.
+    pub requests: u64,
+
+    /// The number of requests that resulted in a client or server error status.
+    pub errors: u64,
+
+    /// The number of requests served from a cache, either a [`CacheStore`](crate::CacheStore) or
+    /// the cache of the client (`304 Not Modified`).
+    pub cache_hits: u64,
+
+    /// The total duration of the requests.
+    pub total_duration: Duration,
+
+    /// The duration of the slowest request.
+    pub max_duration: Duration,
+}
+
+impl RouteMetricsEntry {
+    /// Get the mean duration of the requests.
+    pub fn mean_duration(&self) -> Duration {
+        match u32::try_from(self.requests) {
+            Ok(0) => Duration::ZERO,
+            Ok(requests) => self.total_duration / requests,
+            Err(_) => {
+                Duration::from_secs_f64(self.total_duration.as_secs_f64() / self.requests as f64)
+            }
+        }
+    }
+
+    /// Get the ratio of requests that resulted in an error, between 0 and 1.
+    pub fn error_rate(&self) -> f64 {
+        ratio(self.errors, self.requests)
+    }
+
+    /// Get the ratio of requests served from a cache, between 0 and 1.
+    pub fn cache_hit_ratio(&self) -> f64 {
+        ratio(self.cache_hits, self.requests)
+    }
+}
+
+fn ratio(count: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+impl RouteMetrics {
+    /// Set how often the route metrics fragment refreshes itself.
+    ///
+    /// Defaults to 2 seconds.
+    pub fn with_refresh_interval(mut self, refresh_interval: Duration) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+
+    /// Set the maximum number of routes listed by the route metrics fragment.
+    ///
+    /// Defaults to 20.
+    pub fn with_fragment_limit(mut self, fragment_limit: usize) -> Self {
+        self.fragment_limit = fragment_limit;
+        self
+    }
+
+    fn routes(&self) -> std::sync::MutexGuard<'_, Routes> {
+        self.routes
+            .lock()
+            .expect("route metrics lock should not be poisoned")
+    }
+
+    /// Record a handled request.
+    pub(crate) fn record(
+        &self,
+        variant: Cow<'static, str>,
+        method: http::Method,
+        duration: Duration,
+        response: &axum::response::Response,
+    ) {
+        let status = response.status();
+        let is_error = status.is_client_error() || status.is_server_error();
+        let is_cache_hit = status == http::StatusCode::NOT_MODIFIED
+            || response
+                .extensions()
+                .get::<crate::caching::CacheHit>()
+                .is_some();
+
+        let mut routes = self.routes();
+        let stats = routes.entry((variant, method)).or_default();
+
+        stats.requests += 1;
+        stats.errors += u64::from(is_error);
+        stats.cache_hits += u64::from(is_cache_hit);
+        stats.total_duration += duration;
+        stats.max_duration = stats.max_duration.max(duration);
+    }
+
+    /// Get the metrics of all the routes, the slowest on average first.
+    pub fn entries(&self) -> Vec<RouteMetricsEntry> {
+        let mut entries: Vec<_> = self
+            .routes()
+            .iter()
+            .map(|((variant, method), stats)| RouteMetricsEntry {
+                variant: variant.to_string(),
+                method: method.clone(),
+                requests: stats.requests,
+                errors: stats.errors,
+                cache_hits: stats.cache_hits,
+                total_duration: stats.total_duration,
+                max_duration: stats.max_duration,
+            })
+            .collect();
+
+        entries.sort_by(|a, b| {
+            b.mean_duration()
+                .cmp(&a.mean_duration())
+                .then_with(|| a.variant.cmp(&b.variant))
+                .then_with(|| a.method.as_str().cmp(b.method.as_str()))
+        });
+
+        entries
+    }
+
+    /// Forget all the recorded requests.
+    pub fn reset(&self) {
+        self.routes().clear();
+    }
+
+    /// Get an HTML fragment listing the slowest routes.
+    ///
+    /// If a refresh URL is specified, the fragment polls it to replace itself periodically.
+    pub fn fragment(&self, refresh_url: Option<&str>) -> RouteMetricsFragment {
+        let mut entries = self.entries();
+        entries.truncate(self.fragment_limit);
+
+        RouteMetricsFragment {
+            entries,
+            refresh: refresh_url.map(|url| (url.to_owned(), self.refresh_interval)),
+        }
+    }
+}
+
+/// An HTML fragment listing the slowest routes, as returned by [`RouteMetrics::fragment`].
+#[derive(Debug, Clone)]
+pub struct RouteMetricsFragment {
+    entries: Vec<RouteMetricsEntry>,
+    refresh: Option<(String, Duration)>,
+}
+
+impl Identity for RouteMetricsFragment {
+    fn id(&self) -> HtmlId {
+        HtmlId::from_static(FRAGMENT_ID).expect("route metrics fragment id should be valid")
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+impl Display for RouteMetricsFragment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<div {}", self.id_attribute())?;
+
+        if let Some((url, interval)) = &self.refresh {
+            write!(
+                f,
+                r#" hx-get="{}" hx-trigger="every {}ms" hx-swap="outerHTML""#,
+                escape_attribute_value(url),
+                interval.as_millis()
+            )?;
+        }
+
+        f.write_str("><table><thead><tr><th>Route</th><th>Method</th><th>Requests</th><th>Mean</th><th>Max</th><th>Errors</th><th>Cache hits</th></tr></thead><tbody>")?;
+
+        for entry in &self.entries {
+            write!(
+                f,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.1}%</td><td>{:.1}%</td></tr>",
+                escape_text(&entry.variant),
+                entry.method,
+                entry.requests,
+                format_duration(entry.mean_duration()),
+                format_duration(entry.max_duration),
+                entry.error_rate() * 100.0,
+                entry.cache_hit_ratio() * 100.0,
+            )?;
+        }
+
+        f.write_str("</tbody></table></div>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::response::IntoResponse;
+
+    use super::*;
+
+    #[test]
+    fn test_entries() {
+        let metrics = RouteMetrics::default();
+        let ok = "Hello".into_response();
+        let error = http::StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        let not_modified = http::StatusCode::NOT_MODIFIED.into_response();
+
+        for (duration, response) in [(10, &ok), (30, &error), (20, &not_modified), (20, &ok)] {
+            metrics.record(
+                "Home".into(),
+                http::Method::GET,
+                Duration::from_millis(duration),
+                response,
+            );
+        }
+
+        metrics.record(
+            "Blog::Post".into(),
+            http::Method::GET,
+            Duration::from_millis(50),
+            &ok,
+        );
+
+        let entries = metrics.entries();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].variant, "Blog::Post");
+        assert_eq!(entries[1].variant, "Home");
+        assert_eq!(entries[1].requests, 4);
+        assert_eq!(entries[1].mean_duration(), Duration::from_millis(20));
+        assert_eq!(entries[1].max_duration, Duration::from_millis(30));
+        assert_eq!(entries[1].error_rate(), 0.25);
+        assert_eq!(entries[1].cache_hit_ratio(), 0.25);
+
+        metrics.reset();
+
+        assert!(metrics.entries().is_empty());
+    }
+
+    #[test]
+    fn test_fragment() {
+        let metrics = RouteMetrics::default().with_fragment_limit(1);
+
+        for variant in ["Home", "About"] {
+            metrics.record(
+                variant.into(),
+                http::Method::GET,
+                Duration::from_millis(5),
+                &"Hello".into_response(),
+            );
+        }
+
+        assert_eq!(
+            metrics.fragment(Some("/_dev/metrics")).to_string(),
+            r#"<div id="htmxology-route-metrics" hx-get="/_dev/metrics" hx-trigger="every 2000ms" hx-swap="outerHTML"><table><thead><tr><th>Route</th><th>Method</th><th>Requests</th><th>Mean</th><th>Max</th><th>Errors</th><th>Cache hits</th></tr></thead><tbody><tr><td>About</td><td>GET</td><td>1</td><td>5.0 ms</td><td>5.0 ms</td><td>0.0%</td><td>0.0%</td></tr></tbody></table></div>"#
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! Content negotiation between HTML fragments and plain text.
+
+use crate::MediaType;
+
+/// The representation of a response, as chosen from the `Accept` header of a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Representation {
+    /// An HTML document or fragment.
+    #[default]
+    Html,
+
+    /// Plain text.
+    Text,
+}
+
+impl Representation {
+    /// Choose the representation preferred by the `Accept` header of the specified headers.
+    ///
+    /// Each representation gets the quality of the most specific media range matching it, so that
+    /// `text/plain, */*;q=0.8` prefers plain text. HTML is chosen when the header is absent or
+    /// invalid, when both representations are equally acceptable, and when neither is.
+    pub fn from_headers(headers: &http::HeaderMap) -> Self {
+        let ranges: Vec<(MediaType, f32)> = headers
+            .get_all(http::header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|range| range.parse::<MediaType>().ok())
+            .map(|range| {
+                let quality = range.param("q").and_then(|q| q.parse().ok()).unwrap_or(1.0);
+
+                (range, quality)
+            })
+            .collect();
+
+        if quality(&ranges, "text", "plain") > quality(&ranges, "text", "html") {
+            Self::Text
+        } else {
+            Self::Html
+        }
+    }
+
+    /// The content type of the representation.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Html => "text/html; charset=utf-8",
+            Self::Text => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+/// Get the quality of the most specific media range matching a media type.
+///
+/// Without an `Accept` header, every media type is acceptable.
+fn quality(ranges: &[(MediaType, f32)], type_: &str, subtype: &str) -> f32 {
+    if ranges.is_empty() {
+        return 1.0;
+    }
+
+    ranges
+        .iter()
+        .filter_map(|(range, quality)| {
+            let specificity = match (range.type_(), range.subtype()) {
+                (t, s) if t == type_ && s == subtype => 2,
+                (t, "*") if t == type_ => 1,
+                ("*", "*") => 0,
+                _ => return None,
+            };
+
+            Some((specificity, *quality))
+        })
+        .max_by_key(|(specificity, _)| *specificity)
+        .map_or(0.0, |(_, quality)| quality)
+}
+
+/// The plain text alternative of a response.
+#[derive(Debug, Clone)]
+struct TextAlternative(String);
+
+/// An extension trait for responses with alternative representations.
+///
+/// The alternatives are used by the [`ControllerExt::negotiate`](crate::ControllerExt::negotiate)
+/// combinator.
+pub trait NegotiationResponseExt {
+    /// Set the plain text alternative of the response.
+    ///
+    /// The response is sent as-is to clients preferring HTML, and replaced by the text for
+    /// clients preferring plain text, like `curl -H 'Accept: text/plain'` or scripts.
+    fn with_text_alternative(self, text: impl Into<String>) -> axum::response::Response;
+}
+
+impl NegotiationResponseExt for axum::response::Response {
+    fn with_text_alternative(mut self, text: impl Into<String>) -> axum::response::Response {
+        self.extensions_mut().insert(TextAlternative(text.into()));
+        self
+    }
+}
+
+/// Choose the representation of a response, if it has alternatives.
+///
+/// Responses with alternatives get a `Vary: Accept` header, whichever representation is chosen.
+pub(crate) fn negotiate(
+    mut response: axum::response::Response,
+    representation: Representation,
+) -> axum::response::Response {
+    let Some(TextAlternative(text)) = response.extensions_mut().remove() else {
+        return response;
+    };
+
+    let headers = response.headers_mut();
+
+    headers.append(http::header::VARY, http::HeaderValue::from_static("Accept"));
+
+    if representation == Representation::Text {
+        headers.insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static(representation.content_type()),
+        );
+        headers.remove(http::header::CONTENT_LENGTH);
+        *response.body_mut() = axum::body::Body::from(text);
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::response::IntoResponse;
+
+    use super::*;
+
+    fn representation(accept: &[&str]) -> Representation {
+        let mut headers = http::HeaderMap::new();
+
+        for value in accept {
+            headers.append(http::header::ACCEPT, value.parse().unwrap());
+        }
+
+        Representation::from_headers(&headers)
+    }
+
+    #[test]
+    fn test_from_headers() {
+        assert_eq!(representation(&[]), Representation::Html);
+        assert_eq!(representation(&["*/*"]), Representation::Html);
+        assert_eq!(representation(&["text/html"]), Representation::Html);
+        assert_eq!(representation(&["text/plain"]), Representation::Text);
+        assert_eq!(representation(&["text/*"]), Representation::Html);
+        assert_eq!(
+            representation(&["text/plain, */*;q=0.8"]),
+            Representation::Text
+        );
+        assert_eq!(
+            representation(&["text/html;q=0.5", "text/plain;q=0.9"]),
+            Representation::Text
+        );
+        assert_eq!(
+            representation(&["TEXT/Plain, text/html;q=0"]),
+            Representation::Text
+        );
+        assert_eq!(representation(&["application/json"]), Representation::Html);
+        assert_eq!(representation(&["not a media type"]), Representation::Html);
+    }
+
+    #[tokio::test]
+    async fn test_negotiate() {
+        let response = || {
+            axum::response::Html("<p>42</p>")
+                .into_response()
+                .with_text_alternative("42")
+        };
+
+        let html = negotiate(response(), Representation::Html);
+        assert_eq!(html.headers()[http::header::VARY], "Accept");
+        assert_eq!(
+            html.headers()[http::header::CONTENT_TYPE],
+            "text/html; charset=utf-8"
+        );
+
+        let text = negotiate(response(), Representation::Text);
+        assert_eq!(text.headers()[http::header::VARY], "Accept");
+        assert_eq!(
+            text.headers()[http::header::CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+        let body = axum::body::to_bytes(text.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, "42");
+
+        // Responses without alternatives are left untouched.
+        let plain = negotiate("<p>42</p>".into_response(), Representation::Text);
+        assert!(!plain.headers().contains_key(http::header::VARY));
+    }
+}
@@ -0,0 +1,524 @@
+//! OpenAPI documents generated from route descriptions.
+//!
+//! Routes deriving [`Route`] describe themselves with [`Route::describe`], and [`OpenApi`]
+//! converts these descriptions into an [OpenAPI 3](https://spec.openapis.org/oas/v3.0.3) document,
+//! for documentation and client generation:
+//!
+//! ```rust,ignore
+//! let document = OpenApi::new("Blog", env!("CARGO_PKG_VERSION"))
+//!     .with_description("The routes of the blog.")
+//!     .with_routes::<AppRoute>()
+//!     .to_json();
+//!
+//! let router = axum::Router::new().route(
+//!     "/openapi.json",
+//!     axum::routing::get(move || std::future::ready(axum::Json(document))),
+//! );
+//! ```
+//!
+//! The documents are as precise as the routes allow: path parameters are typed after their
+//! constraints or their Rust types, while query and body parameters, deserialized as a whole, are
+//! described as objects with their Rust type in an `x-htmxology-type` extension.
+
+use serde_json::{Map, Value, json};
+
+use crate::{ParameterLocation, Route, RouteDescription, RouteParameter};
+
+/// The version of the OpenAPI specification of the generated documents.
+const OPENAPI_VERSION: &str = "3.0.3";
+
+/// The HTTP methods supported by OpenAPI operations.
+const OPERATION_METHODS: &[&str] = &[
+    "GET", "PUT", "POST", "DELETE", "OPTIONS", "HEAD", "PATCH", "TRACE",
+];
+
+/// An OpenAPI document builder.
+#[derive(Debug, Clone)]
+pub struct OpenApi {
+    title: String,
+    version: String,
+    description: Option<String>,
+    routes: Vec<RouteDescription>,
+}
+
+impl OpenApi {
+    /// Create a new document, with the title and the version of the API.
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            version: version.into(),
+            description: None,
+            routes: Vec::new(),
+        }
+    }
+
+    /// Set the description of the API.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Add the routes of a route type to the document.
+    pub fn with_routes<R: Route>(self) -> Self {
+        self.with_descriptions(R::describe())
+    }
+
+    /// Add route descriptions to the document.
+    pub fn with_descriptions(
+        mut self,
+        descriptions: impl IntoIterator<Item = RouteDescription>,
+    ) -> Self {
+        self.routes.extend(descriptions);
+        self
+    }
+
+    /// Get the OpenAPI document, as JSON.
+    ///
+    /// Routes with methods unknown to OpenAPI are skipped, and when several routes share the same
+    /// method and path, only the first one is documented, as it is the one that matches.
+    pub fn to_json(&self) -> Value {
+        let mut paths = Map::new();
+
+        for route in &self.routes {
+            let method = route.method.to_ascii_uppercase();
+
+            if !OPERATION_METHODS.contains(&method.as_str()) {
+                continue;
+            }
+
+            let Value::Object(path_item) = paths
+                .entry(openapi_path(&route.pattern))
+                .or_insert_with(|| Value::Object(Map::new()))
+            else {
+                unreachable!("path items should be objects");
+            };
+
+            path_item
+                .entry(method.to_ascii_lowercase())
+                .or_insert_with(|| self.operation(route));
+        }
+
+        let mut info = json!({
+            "title": self.title,
+            "version": self.version,
+        });
+
+        if let Some(description) = &self.description {
+            info["description"] = description.as_str().into();
+        }
+
+        json!({
+            "openapi": OPENAPI_VERSION,
+            "info": info,
+            "paths": paths,
+        })
+    }
+
+    /// Get the operation of a route.
+    fn operation(&self, route: &RouteDescription) -> Value {
+        let mut operation = json!({
+            "operationId": self.operation_id(route),
+            "responses": {
+                "200": {
+                    "description": "The rendered HTML.",
+                    "content": {"text/html": {}},
+                },
+            },
+        });
+
+        if let Some(doc) = &route.doc {
+            operation["summary"] = doc.lines().next().unwrap_or_default().into();
+            operation["description"] = doc.as_str().into();
+        }
+
+        let constraints = path_constraints(&route.pattern);
+        let parameters: Vec<_> = route
+            .parameters
+            .iter()
+            .filter_map(|parameter| match parameter.location {
+                ParameterLocation::Path => {
+                    let schema = constraints
+                        .iter()
+                        .find(|(name, _)| *name == parameter.name)
+                        .and_then(|(_, constraint)| constraint_schema(constraint))
+                        .unwrap_or_else(|| type_schema(&parameter.ty));
+
+                    Some(json!({
+                        "name": parameter.name,
+                        "in": "path",
+                        "required": true,
+                        "schema": schema,
+                    }))
+                }
+                ParameterLocation::Query => Some(json!({
+                    "name": parameter.name,
+                    "in": "query",
+                    "required": !is_optional(&parameter.ty),
+                    "style": "form",
+                    "explode": true,
+                    "schema": object_schema(parameter),
+                })),
+                ParameterLocation::Body => None,
+            })
+            .collect();
+
+        if !parameters.is_empty() {
+            operation["parameters"] = parameters.into();
+        }
+
+        if let Some(body) = route
+            .parameters
+            .iter()
+            .find(|parameter| parameter.location == ParameterLocation::Body)
+        {
+            let content: Map<_, _> = body
+                .content_types
+                .iter()
+                .map(|content_type| (content_type.clone(), json!({"schema": object_schema(body)})))
+                .collect();
+
+            operation["requestBody"] = json!({
+                "required": !is_optional(&body.ty),
+                "content": content,
+            });
+        }
+
+        if let Some(host) = &route.host {
+            operation["x-htmxology-host"] = host.as_str().into();
+        }
+
+        if let Some(target) = &route.target {
+            operation["x-htmxology-target"] = target.as_str().into();
+        }
+
+        operation
+    }
+
+    /// Get the unique operation identifier of a route.
+    ///
+    /// Variants with several routes, like form variants, get the method as a suffix.
+    fn operation_id(&self, route: &RouteDescription) -> String {
+        let id = route.variant.replace("::", "_");
+
+        if self
+            .routes
+            .iter()
+            .filter(|other| other.variant == route.variant)
+            .count()
+            > 1
+        {
+            format!("{id}_{}", route.method.to_ascii_lowercase())
+        } else {
+            id
+        }
+    }
+}
+
+/// Convert a route pattern to an OpenAPI path, without the parameter constraints.
+///
+/// For instance `/items/{id:u32:1..}/{*rest}` becomes `/items/{id}/{rest}`.
+fn openapi_path(pattern: &str) -> String {
+    let mut path = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+
+    while let Some(start) = rest.find('{') {
+        path.push_str(&rest[..start]);
+
+        let (name, _, after) = split_parameter(&rest[start + 1..]);
+
+        path.push('{');
+        path.push_str(name);
+        path.push('}');
+        rest = after;
+    }
+
+    path.push_str(rest);
+    path
+}
+
+/// Get the constraints of the path parameters of a route pattern, by name.
+fn path_constraints(pattern: &str) -> Vec<(&str, &str)> {
+    let mut constraints = Vec::new();
+    let mut rest = pattern;
+
+    while let Some(start) = rest.find('{') {
+        let (name, constraint, after) = split_parameter(&rest[start + 1..]);
+
+        if let Some(constraint) = constraint {
+            constraints.push((name, constraint));
+        }
+
+        rest = after;
+    }
+
+    constraints
+}
+
+/// Split a path parameter, starting after its opening brace, into its name, its constraint and
+/// the rest of the pattern.
+///
+/// Regex constraints, like `/[0-9]{4}/`, may contain braces and end with a slash.
+fn split_parameter(s: &str) -> (&str, Option<&str>, &str) {
+    let s = s.strip_prefix('*').unwrap_or(s);
+
+    let Some(end) = s.find([':', '}']) else {
+        return (s, None, "");
+    };
+
+    let name = &s[..end];
+
+    if s[end..].starts_with('}') {
+        return (name, None, &s[end + 1..]);
+    }
+
+    let constraint = &s[end + 1..];
+    let close = if constraint.starts_with('/') {
+        constraint.find("/}").map(|position| position + 1)
+    } else {
+        constraint.find('}')
+    };
+
+    match close {
+        Some(close) => (name, Some(&constraint[..close]), &constraint[close + 1..]),
+        None => (name, Some(constraint), ""),
+    }
+}
+
+/// Get the schema of a path parameter constraint, like `u32:1..=10` or `/[0-9a-f]+/`.
+fn constraint_schema(constraint: &str) -> Option<Value> {
+    if let Some(regex) = constraint
+        .strip_prefix('/')
+        .and_then(|regex| regex.strip_suffix('/'))
+    {
+        return Some(json!({"type": "string", "pattern": format!("^(?:{regex})$")}));
+    }
+
+    let (ty, range) = match constraint.split_once(':') {
+        Some((ty, range)) => (ty, Some(range)),
+        None => (constraint, None),
+    };
+
+    let mut schema = type_schema(ty);
+
+    if let Some((min, max)) = range.and_then(|range| range.split_once("..")) {
+        if let Ok(min) = min.parse::<i64>() {
+            schema["minimum"] = min.into();
+        }
+
+        if let Some(Ok(max)) = max.strip_prefix('=').map(str::parse::<i64>) {
+            schema["maximum"] = max.into();
+        }
+    }
+
+    Some(schema)
+}
+
+/// Get the schema of a Rust type, as written in the route.
+fn type_schema(ty: &str) -> Value {
+    if let Some(inner) = generic_argument(ty, "Option") {
+        return type_schema(inner);
+    }
+
+    if let Some(inner) = generic_argument(ty, "Vec") {
+        return json!({"type": "array", "items": type_schema(inner)});
+    }
+
+    match ty {
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => {
+            json!({"type": "integer", "minimum": 0})
+        }
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => json!({"type": "integer"}),
+        "f32" | "f64" => json!({"type": "number"}),
+        "bool" => json!({"type": "boolean"}),
+        _ => json!({"type": "string"}),
+    }
+}
+
+/// Get the schema of a query or body parameter, deserialized as a whole.
+fn object_schema(parameter: &RouteParameter) -> Value {
+    let ty = generic_argument(&parameter.ty, "Option").unwrap_or(&parameter.ty);
+
+    json!({
+        "type": "object",
+        "x-htmxology-type": ty,
+    })
+}
+
+/// Check whether a Rust type is optional.
+fn is_optional(ty: &str) -> bool {
+    generic_argument(ty, "Option").is_some()
+}
+
+/// Get the argument of a generic Rust type, like `u32` for `Option<u32>` or
+/// `std::option::Option<u32>`.
+fn generic_argument<'a>(ty: &'a str, name: &str) -> Option<&'a str> {
+    let ty = ty.trim_start_matches("::");
+    let ty = ty.strip_prefix("std::").unwrap_or(ty);
+    let ty = ty
+        .strip_prefix(&format!("{}::", name.to_ascii_lowercase()))
+        .unwrap_or(ty);
+
+    ty.strip_prefix(name)?.strip_prefix('<')?.strip_suffix('>')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openapi_path() {
+        assert_eq!(openapi_path("/"), "/");
+        assert_eq!(
+            openapi_path("/items/{id:u32:1..}/{*rest}"),
+            "/items/{id}/{rest}"
+        );
+        assert_eq!(
+            openapi_path("/years/{year:/[0-9]{4}/}/posts"),
+            "/years/{year}/posts"
+        );
+        assert_eq!(
+            path_constraints("/items/{id:u32:1..=10}/{slug:/[a-z]{2,}/}/{page}"),
+            [("id", "u32:1..=10"), ("slug", "/[a-z]{2,}/")]
+        );
+    }
+
+    #[test]
+    fn test_schemas() {
+        assert_eq!(
+            constraint_schema("u32:1..=10"),
+            Some(json!({"type": "integer", "minimum": 1, "maximum": 10}))
+        );
+        assert_eq!(
+            constraint_schema("i8:..=0"),
+            Some(json!({"type": "integer", "maximum": 0}))
+        );
+        assert_eq!(
+            constraint_schema("/[0-9a-f]+/"),
+            Some(json!({"type": "string", "pattern": "^(?:[0-9a-f]+)$"}))
+        );
+        assert_eq!(type_schema("Option<bool>"), json!({"type": "boolean"}));
+        assert_eq!(
+            type_schema("Vec<f64>"),
+            json!({"type": "array", "items": {"type": "number"}})
+        );
+        assert_eq!(type_schema("String"), json!({"type": "string"}));
+        assert!(is_optional("std::option::Option<Query>"));
+        assert!(!is_optional("Query"));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let routes = vec![
+            RouteDescription::new("Home", "GET", "/").with_doc("The home page.\n\nWith news."),
+            RouteDescription::new("Settings", "GET", "/settings"),
+            RouteDescription::new("Settings", "POST", "/settings").with_parameter(
+                RouteParameter::new("form", ParameterLocation::Body, "Option<SettingsForm>")
+                    .with_content_types(&["application/x-www-form-urlencoded", "application/json"]),
+            ),
+            RouteDescription::new("Blog::Post", "GET", "/blog/{id:u32:1..}")
+                .with_parameter(RouteParameter::new("id", ParameterLocation::Path, "u32"))
+                .with_parameter(RouteParameter::new(
+                    "query",
+                    ParameterLocation::Query,
+                    "PostQuery",
+                )),
+            RouteDescription::new("Purge", "PURGE", "/cache"),
+            RouteDescription::new("Shadowed", "GET", "/"),
+        ];
+
+        let document = OpenApi::new("Test", "1.0.0")
+            .with_description("A test API.")
+            .with_descriptions(routes)
+            .to_json();
+
+        assert_eq!(
+            document,
+            json!({
+                "openapi": "3.0.3",
+                "info": {"title": "Test", "version": "1.0.0", "description": "A test API."},
+                "paths": {
+                    "/": {
+                        "get": {
+                            "operationId": "Home",
+                            "summary": "The home page.",
+                            "description": "The home page.\n\nWith news.",
+                            "responses": {
+                                "200": {
+                                    "description": "The rendered HTML.",
+                                    "content": {"text/html": {}},
+                                },
+                            },
+                        },
+                    },
+                    "/settings": {
+                        "get": {
+                            "operationId": "Settings_get",
+                            "responses": {
+                                "200": {
+                                    "description": "The rendered HTML.",
+                                    "content": {"text/html": {}},
+                                },
+                            },
+                        },
+                        "post": {
+                            "operationId": "Settings_post",
+                            "requestBody": {
+                                "required": false,
+                                "content": {
+                                    "application/x-www-form-urlencoded": {
+                                        "schema": {
+                                            "type": "object",
+                                            "x-htmxology-type": "SettingsForm",
+                                        },
+                                    },
+                                    "application/json": {
+                                        "schema": {
+                                            "type": "object",
+                                            "x-htmxology-type": "SettingsForm",
+                                        },
+                                    },
+                                },
+                            },
+                            "responses": {
+                                "200": {
+                                    "description": "The rendered HTML.",
+                                    "content": {"text/html": {}},
+                                },
+                            },
+                        },
+                    },
+                    "/blog/{id}": {
+                        "get": {
+                            "operationId": "Blog_Post",
+                            "parameters": [
+                                {
+                                    "name": "id",
+                                    "in": "path",
+                                    "required": true,
+                                    "schema": {"type": "integer", "minimum": 1},
+                                },
+                                {
+                                    "name": "query",
+                                    "in": "query",
+                                    "required": true,
+                                    "style": "form",
+                                    "explode": true,
+                                    "schema": {
+                                        "type": "object",
+                                        "x-htmxology-type": "PostQuery",
+                                    },
+                                },
+                            ],
+                            "responses": {
+                                "200": {
+                                    "description": "The rendered HTML.",
+                                    "content": {"text/html": {}},
+                                },
+                            },
+                        },
+                    },
+                },
+            })
+        );
+    }
+}
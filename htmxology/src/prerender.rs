@@ -0,0 +1,290 @@
+//! Static export of routes, for CDN or edge prerendering.
+
+use std::path::{Path, PathBuf};
+
+use crate::{Controller, Route, ServerInfo};
+
+/// Renders `GET` routes through a controller into static HTML files.
+///
+/// This enables hybrid deployments, where content-heavy sections are served as static files by a
+/// CDN while the rest of the application stays dynamic.
+///
+/// Routes are rendered as classic (non-HTMX) requests. Each route is exported to an `index.html`
+/// file in a directory matching its path, so that `/blog/first-post` is written to
+/// `blog/first-post/index.html`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let prerenderer = Prerenderer::new(controller, |_| async {})
+///     .with_base_url("https://example.com".parse()?)
+///     .with_asset_url_rewrite("/assets/", "https://cdn.example.com/assets/");
+///
+/// let files = prerenderer
+///     .export([AppRoute::Home, AppRoute::About], "dist/")
+///     .await?;
+/// ```
+pub struct Prerenderer<C, F> {
+    /// The controller used to render the routes.
+    controller: C,
+
+    /// The factory for the controller arguments.
+    args_factory: F,
+
+    /// The server information passed to the controller.
+    server_info: ServerInfo,
+
+    /// The asset URL prefixes to rewrite, and their replacements.
+    asset_url_rewrites: Vec<(String, String)>,
+}
+
+/// An error that can occur when prerendering a route.
+#[derive(Debug, thiserror::Error)]
+pub enum PrerenderError {
+    /// The route is not a `GET` route.
+    #[error("route `{route}` is a {method} route, only GET routes can be prerendered")]
+    NotAGetRoute {
+        /// The route.
+        route: String,
+
+        /// The method of the route.
+        method: http::Method,
+    },
+
+    /// The route can't be mapped to a static file.
+    #[error("route `{route}` can't be mapped to a static file")]
+    UnsupportedRoute {
+        /// The route.
+        route: String,
+    },
+
+    /// The controller returned a non-successful response.
+    #[error("route `{route}` responded with status {status}")]
+    Status {
+        /// The route.
+        route: String,
+
+        /// The status of the response.
+        status: http::StatusCode,
+    },
+
+    /// The response body could not be read.
+    #[error("failed to read the response body of route `{route}`: {err}")]
+    Body {
+        /// The route.
+        route: String,
+
+        /// The error.
+        err: axum::Error,
+    },
+
+    /// The response body is not valid UTF-8.
+    #[error("the response body of route `{route}` is not valid UTF-8")]
+    NotUtf8 {
+        /// The route.
+        route: String,
+    },
+
+    /// The static file could not be written.
+    #[error("failed to write `{}`: {err}", path.display())]
+    Io {
+        /// The path of the file.
+        path: PathBuf,
+
+        /// The error.
+        err: std::io::Error,
+    },
+}
+
+impl<C, F, Fut> Prerenderer<C, F>
+where
+    C: Controller<Response = Result<axum::response::Response, axum::response::Response>>,
+    F: Fn(&C) -> Fut,
+    Fut: std::future::Future<Output = C::Args>,
+{
+    /// Create a new prerenderer from a controller with an args factory.
+    ///
+    /// The factory function is called for each rendered route, as for
+    /// [`ControllerRouter::new`](crate::ControllerRouter::new).
+    pub fn new(controller: C, args_factory: F) -> Self {
+        Self {
+            controller,
+            args_factory,
+            server_info: ServerInfo {
+                base_url: http::Uri::from_static("http://localhost"),
+            },
+            asset_url_rewrites: Vec::new(),
+        }
+    }
+
+    /// Set the base URL passed to the controller through the server information.
+    ///
+    /// Defaults to `http://localhost`.
+    pub fn with_base_url(mut self, base_url: http::Uri) -> Self {
+        self.server_info.base_url = base_url;
+        self
+    }
+
+    /// Rewrite asset URLs starting with `prefix` to start with `replacement` instead.
+    ///
+    /// Only URLs found at the start of a `src`, `href` or `srcset` attribute value are rewritten.
+    /// This is typically used to point assets to a CDN.
+    pub fn with_asset_url_rewrite(
+        mut self,
+        prefix: impl Into<String>,
+        replacement: impl Into<String>,
+    ) -> Self {
+        self.asset_url_rewrites
+            .push((prefix.into(), replacement.into()));
+        self
+    }
+
+    /// Render a route to HTML.
+    pub async fn render(&self, route: C::Route) -> Result<String, PrerenderError> {
+        let url = route.to_string();
+        let method = route.method();
+
+        if method != http::Method::GET {
+            return Err(PrerenderError::NotAGetRoute { route: url, method });
+        }
+
+        let (parts, _) = http::Request::builder()
+            .method(http::Method::GET)
+            .uri(&url)
+            .body(())
+            .map_err(|_| PrerenderError::UnsupportedRoute { route: url.clone() })?
+            .into_parts();
+
+        let args = (self.args_factory)(&self.controller).await;
+        let response = match self
+            .controller
+            .handle_request(
+                route,
+                crate::htmx::Request::Classic,
+                parts,
+                &self.server_info,
+                args,
+            )
+            .await
+        {
+            Ok(response) | Err(response) => response,
+        };
+
+        if !response.status().is_success() {
+            return Err(PrerenderError::Status {
+                route: url,
+                status: response.status(),
+            });
+        }
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .map_err(|err| PrerenderError::Body {
+                route: url.clone(),
+                err,
+            })?;
+        let body =
+            String::from_utf8(body.into()).map_err(|_| PrerenderError::NotUtf8 { route: url })?;
+
+        Ok(self.rewrite_asset_urls(body))
+    }
+
+    /// Render routes and write them as static files in the specified directory.
+    ///
+    /// Returns the paths of the written files, in the order of the routes.
+    pub async fn export(
+        &self,
+        routes: impl IntoIterator<Item = C::Route>,
+        output_dir: impl AsRef<Path>,
+    ) -> Result<Vec<PathBuf>, PrerenderError> {
+        let output_dir = output_dir.as_ref();
+        let mut paths = Vec::new();
+
+        for route in routes {
+            let path = output_dir.join(route_file_path(&route.to_string())?);
+            let html = self.render(route).await?;
+
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|err| PrerenderError::Io {
+                    path: parent.to_owned(),
+                    err,
+                })?;
+            }
+
+            std::fs::write(&path, html).map_err(|err| PrerenderError::Io {
+                path: path.clone(),
+                err,
+            })?;
+
+            paths.push(path);
+        }
+
+        Ok(paths)
+    }
+
+    /// Rewrite the asset URLs of a HTML document.
+    fn rewrite_asset_urls(&self, mut html: String) -> String {
+        for (prefix, replacement) in &self.asset_url_rewrites {
+            for attribute in ["src", "href", "srcset"] {
+                for quote in ['"', '\''] {
+                    html = html.replace(
+                        &format!("{attribute}={quote}{prefix}"),
+                        &format!("{attribute}={quote}{replacement}"),
+                    );
+                }
+            }
+        }
+
+        html
+    }
+}
+
+/// Get the relative path of the static file for a route URL.
+fn route_file_path(url: &str) -> Result<PathBuf, PrerenderError> {
+    let unsupported = || PrerenderError::UnsupportedRoute {
+        route: url.to_owned(),
+    };
+
+    if url.contains(['?', '#']) {
+        return Err(unsupported());
+    }
+
+    let mut path = PathBuf::new();
+
+    for segment in url.split('/').filter(|segment| !segment.is_empty()) {
+        let segment = percent_encoding::percent_decode_str(segment)
+            .decode_utf8()
+            .map_err(|_| unsupported())?;
+
+        if segment == "." || segment == ".." || segment.contains(['/', '\\']) {
+            return Err(unsupported());
+        }
+
+        path.push(segment.as_ref());
+    }
+
+    path.push("index.html");
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_file_path() {
+        assert_eq!(route_file_path("/").unwrap(), PathBuf::from("index.html"));
+        assert_eq!(
+            route_file_path("/blog/first-post").unwrap(),
+            PathBuf::from("blog/first-post/index.html")
+        );
+        assert_eq!(
+            route_file_path("/blog/hello%20world/").unwrap(),
+            PathBuf::from("blog/hello world/index.html")
+        );
+        assert!(route_file_path("/search?q=rust").is_err());
+        assert!(route_file_path("/blog/..").is_err());
+        assert!(route_file_path("/blog/a%2Fb").is_err());
+    }
+}
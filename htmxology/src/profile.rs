@@ -0,0 +1,291 @@
+//! Per-environment configuration profiles.
+
+use std::time::Duration;
+
+/// The environment an application runs in, selecting the defaults of its subsystems.
+///
+/// The settings of a profile are bundled in [`ProfileSettings`], which subsystems accept through
+/// their `with_profile` methods, so that production defaults don't require configuring each
+/// subsystem individually:
+///
+/// ```rust,ignore
+/// let settings = Profile::from_env()?
+///     .settings()
+///     .with_cache_duration(Duration::from_secs(30));
+///
+/// let controller = AppController::new(Cache::default().with_profile(&settings));
+/// let router = ControllerRouter::new(controller, |_| async {})
+///     .with_static_assets(
+///         "/static",
+///         StaticAssets::from_dir("static").with_profile(&settings),
+///     )
+///     .with_profile(&settings);
+///
+/// Server::builder_with_profile("127.0.0.1:3000", &settings)
+///     .await?
+///     .with_sessions(Sessions::new(MemorySessionStore::default()).with_profile(&settings))
+///     .build()
+///     .serve(router)
+///     .await?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Profile {
+    /// Local development: nothing is cached, and the internals of the application are exposed.
+    Development,
+
+    /// A production-like environment, keeping the cache debug headers.
+    Staging,
+
+    /// Production: strict defaults, and nothing exposed.
+    Production,
+}
+
+/// An error that can occur when trying to get the profile from the environment.
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileFromEnvError {
+    /// The environment variable was not unicode.
+    #[error("environment variable {name} was not unicode")]
+    NotUnicode {
+        /// The name of the environment variable.
+        name: &'static str,
+    },
+
+    /// The environment variable is not a known profile.
+    #[error("failed to parse the profile from environment variable {name}: {err}")]
+    Profile {
+        /// The name of the environment variable.
+        name: &'static str,
+
+        /// The error that occurred.
+        #[source]
+        err: ParseProfileError,
+    },
+}
+
+/// An error that can occur when parsing a profile.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("unknown profile `{0}`: expected `development`, `staging` or `production`")]
+pub struct ParseProfileError(String);
+
+impl Profile {
+    /// The environment variable name for the profile.
+    pub const HTMXOLOGY_PROFILE: &'static str = "HTMXOLOGY_PROFILE";
+
+    /// Get the profile from the `HTMXOLOGY_PROFILE` environment variable.
+    ///
+    /// Falls back to [`Profile::default`] if the variable is not set.
+    pub fn from_env() -> Result<Self, ProfileFromEnvError> {
+        let name = Self::HTMXOLOGY_PROFILE;
+
+        let profile = match std::env::var(name) {
+            Ok(value) if !value.is_empty() => value
+                .parse()
+                .map_err(|err| ProfileFromEnvError::Profile { name, err })?,
+            Ok(_) | Err(std::env::VarError::NotPresent) => {
+                let profile = Self::default();
+
+                tracing::info!("{name} was not set: using the {profile} profile.");
+
+                return Ok(profile);
+            }
+            Err(std::env::VarError::NotUnicode(_)) => {
+                return Err(ProfileFromEnvError::NotUnicode { name });
+            }
+        };
+
+        tracing::info!("{name} was set: using the {profile} profile.");
+
+        Ok(profile)
+    }
+
+    /// Get the default settings of the profile.
+    pub fn settings(self) -> ProfileSettings {
+        match self {
+            Self::Development => ProfileSettings {
+                profile: self,
+                secure_cookies: false,
+                cache_duration: Duration::ZERO,
+                cache_debug_headers: true,
+                static_max_age: Duration::ZERO,
+                route_debug_endpoint: Some("/_routes".to_owned()),
+                auto_reload: true,
+            },
+            Self::Staging => ProfileSettings {
+                profile: self,
+                secure_cookies: true,
+                cache_duration: Duration::from_secs(60),
+                cache_debug_headers: true,
+                static_max_age: Duration::from_secs(60 * 60),
+                route_debug_endpoint: None,
+                auto_reload: false,
+            },
+            Self::Production => ProfileSettings {
+                profile: self,
+                secure_cookies: true,
+                cache_duration: Duration::from_secs(60),
+                cache_debug_headers: false,
+                static_max_age: Duration::from_secs(60 * 60),
+                route_debug_endpoint: None,
+                auto_reload: false,
+            },
+        }
+    }
+}
+
+impl Default for Profile {
+    /// [`Profile::Development`] in debug builds, and [`Profile::Production`] in release builds.
+    fn default() -> Self {
+        if cfg!(debug_assertions) {
+            Self::Development
+        } else {
+            Self::Production
+        }
+    }
+}
+
+impl std::fmt::Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Development => "development",
+            Self::Staging => "staging",
+            Self::Production => "production",
+        })
+    }
+}
+
+impl std::str::FromStr for Profile {
+    type Err = ParseProfileError;
+
+    /// Parse a profile, case-insensitively, accepting `dev` and `prod` as shorthands.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "development" | "dev" => Ok(Self::Development),
+            "staging" => Ok(Self::Staging),
+            "production" | "prod" => Ok(Self::Production),
+            _ => Err(ParseProfileError(s.to_owned())),
+        }
+    }
+}
+
+/// The settings of a [`Profile`], applied to the subsystems through their `with_profile`
+/// methods.
+///
+/// Each setting can be overridden with its `with_*` method, or by setting its field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileSettings {
+    /// The profile the settings derive from.
+    pub profile: Profile,
+
+    /// Whether the cookies of the sessions, CSRF protection and flash messages are only sent over
+    /// HTTPS.
+    ///
+    /// Disabled in development, to allow testing the application from other devices of the local
+    /// network over HTTP.
+    pub secure_cookies: bool,
+
+    /// The duration responses are cached for, by clients and by the server-side cache store.
+    ///
+    /// Zero in development, so that changes show up immediately.
+    pub cache_duration: Duration,
+
+    /// Whether responses describe the cache decisions in debug headers.
+    pub cache_debug_headers: bool,
+
+    /// The duration clients may cache static assets for.
+    pub static_max_age: Duration,
+
+    /// The path of the route debug endpoint, if it should be mounted.
+    ///
+    /// See [`ControllerRouter::with_route_debug_endpoint`](crate::ControllerRouter::with_route_debug_endpoint).
+    pub route_debug_endpoint: Option<String>,
+
+    /// Whether the server takes its listener from `listenfd`, to keep connections open while the
+    /// application is rebuilt.
+    pub auto_reload: bool,
+}
+
+impl Default for ProfileSettings {
+    /// The settings of the default profile.
+    fn default() -> Self {
+        Profile::default().settings()
+    }
+}
+
+impl ProfileSettings {
+    /// Set whether cookies are only sent over HTTPS.
+    pub fn with_secure_cookies(mut self, secure_cookies: bool) -> Self {
+        self.secure_cookies = secure_cookies;
+        self
+    }
+
+    /// Set the duration responses are cached for.
+    pub fn with_cache_duration(mut self, cache_duration: Duration) -> Self {
+        self.cache_duration = cache_duration;
+        self
+    }
+
+    /// Set whether responses describe the cache decisions in debug headers.
+    pub fn with_cache_debug_headers(mut self, cache_debug_headers: bool) -> Self {
+        self.cache_debug_headers = cache_debug_headers;
+        self
+    }
+
+    /// Set the duration clients may cache static assets for.
+    pub fn with_static_max_age(mut self, static_max_age: Duration) -> Self {
+        self.static_max_age = static_max_age;
+        self
+    }
+
+    /// Set the path of the route debug endpoint, or `None` not to mount it.
+    pub fn with_route_debug_endpoint(mut self, path: Option<impl Into<String>>) -> Self {
+        self.route_debug_endpoint = path.map(Into::into);
+        self
+    }
+
+    /// Set whether the server takes its listener from `listenfd`.
+    pub fn with_auto_reload(mut self, auto_reload: bool) -> Self {
+        self.auto_reload = auto_reload;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("dev".parse(), Ok(Profile::Development));
+        assert_eq!("Staging".parse(), Ok(Profile::Staging));
+        assert_eq!("PRODUCTION".parse(), Ok(Profile::Production));
+        assert_eq!(
+            "qa".parse::<Profile>().unwrap_err().to_string(),
+            "unknown profile `qa`: expected `development`, `staging` or `production`"
+        );
+
+        for profile in [Profile::Development, Profile::Staging, Profile::Production] {
+            assert_eq!(profile.to_string().parse(), Ok(profile));
+        }
+    }
+
+    #[test]
+    fn test_settings() {
+        let production = Profile::Production.settings();
+
+        assert!(production.secure_cookies);
+        assert!(!production.cache_debug_headers);
+        assert_eq!(production.route_debug_endpoint, None);
+
+        let development = Profile::Development
+            .settings()
+            .with_cache_duration(Duration::from_secs(5))
+            .with_route_debug_endpoint(Some("/_debug/routes"));
+
+        assert!(!development.secure_cookies);
+        assert_eq!(development.cache_duration, Duration::from_secs(5));
+        assert_eq!(
+            development.route_debug_endpoint.as_deref(),
+            Some("/_debug/routes")
+        );
+    }
+}
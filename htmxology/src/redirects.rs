@@ -0,0 +1,642 @@
+//! Declarative redirects and rewrites, loaded from a configuration file.
+//!
+//! Redirect tables let marketing URL changes and legacy migrations be handled without changing
+//! the route types. They are matched before the routes of a [`ControllerRouter`], with
+//! [`ControllerRouter::with_redirects`]:
+//!
+//! ```rust,ignore
+//! let redirects = Redirects::from_file("redirects.toml")?;
+//! let router = ControllerRouter::new(controller, |_| async {}).with_redirects(redirects);
+//! ```
+//!
+//! Tables can be written in TOML, with `[[redirect]]` and `[[rewrite]]` entries:
+//!
+//! ```toml
+//! [[redirect]]
+//! from = "/old-blog/{slug}"
+//! to = "/blog/{slug}"
+//! status = 301
+//!
+//! [[redirect]]
+//! host = "docs.{base}"
+//! from = "/{*path}"
+//! to = "https://docs.example.com/{path}"
+//!
+//! [[rewrite]]
+//! from = "/feed"
+//! to = "/blog/feed.xml"
+//! ```
+//!
+//! Or in CSV, with one `from,to[,status[,host]]` rule per line, where the status is either a
+//! redirection status code or `rewrite`. Empty lines and lines starting with `#` are ignored, and
+//! fields can't be quoted:
+//!
+//! ```csv
+//! # from,to,status,host
+//! /old-blog/{slug},/blog/{slug},301
+//! /feed,/blog/feed.xml,rewrite
+//! ```
+//!
+//! `from` patterns are paths whose segments can be `{name}` placeholders, matching any segment,
+//! and whose last segment can be a `{*name}` placeholder, matching the remaining path. `to` targets
+//! reference the placeholders of their pattern, and are either paths or, for redirects, absolute
+//! URLs. The query string of the request is kept, unless the target has its own. Host patterns
+//! restrict rules to a host, with `{base}` standing for the host of the server base URL, as for
+//! `#[host]` routes.
+//!
+//! Rules are matched in order, and the first matching rule applies: redirects respond with their
+//! status and a `Location` header, and rewrites change the URL of the request before it is
+//! routed. Redirects default to `301 Moved Permanently`.
+
+use std::{path::Path, sync::Arc};
+
+use axum::response::IntoResponse as _;
+
+use crate::{ServerInfo, host_matches, request_host};
+
+/// A table of redirects and rewrites, matched before the routes of a router.
+///
+/// See the [module documentation](self) for the configuration formats.
+#[derive(Debug, Clone, Default)]
+pub struct Redirects {
+    rules: Arc<Vec<Rule>>,
+}
+
+/// An error that can occur when loading a redirect table.
+#[derive(Debug, thiserror::Error)]
+pub enum RedirectsError {
+    /// The file could not be read.
+    #[error("failed to read the redirect table `{path}`: {err}")]
+    Io {
+        /// The path of the file.
+        path: String,
+
+        /// The error that occurred.
+        #[source]
+        err: std::io::Error,
+    },
+
+    /// The format of the file could not be determined from its extension.
+    #[error("unknown redirect table format for `{path}`: expected a `.toml` or `.csv` file")]
+    UnknownFormat {
+        /// The path of the file.
+        path: String,
+    },
+
+    /// The TOML table could not be parsed.
+    #[error("failed to parse the TOML redirect table: {0}")]
+    Toml(#[from] basic_toml::Error),
+
+    /// A line of the CSV table could not be parsed.
+    #[error("failed to parse line {line} of the CSV redirect table: {reason}")]
+    Csv {
+        /// The line number, starting at 1.
+        line: usize,
+
+        /// The reason why the line is invalid.
+        reason: String,
+    },
+
+    /// A rule is invalid.
+    #[error("invalid redirect rule from `{from}`: {reason}")]
+    InvalidRule {
+        /// The pattern of the rule.
+        from: String,
+
+        /// The reason why the rule is invalid.
+        reason: String,
+    },
+}
+
+/// A redirect or rewrite rule.
+#[derive(Debug, Clone)]
+struct Rule {
+    /// The host pattern the rule is restricted to, if any.
+    host: Option<String>,
+
+    /// The segments of the path pattern.
+    from: Vec<Segment>,
+
+    /// The target, with placeholders.
+    to: String,
+
+    /// What to do with matching requests.
+    action: Action,
+}
+
+/// A segment of a path pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// A static segment.
+    Literal(String),
+
+    /// A placeholder matching any non-empty segment.
+    Parameter(String),
+
+    /// A placeholder matching the remaining path.
+    Rest(String),
+}
+
+/// What to do with the requests matching a rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    /// Respond with a redirection.
+    Redirect(http::StatusCode),
+
+    /// Change the URL of the request.
+    Rewrite,
+}
+
+/// The TOML representation of a redirect table.
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlTable {
+    #[serde(default)]
+    redirect: Vec<TomlRule>,
+
+    #[serde(default)]
+    rewrite: Vec<TomlRule>,
+}
+
+/// The TOML representation of a rule.
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlRule {
+    from: String,
+    to: String,
+    host: Option<String>,
+    status: Option<u16>,
+}
+
+impl Redirects {
+    /// Create an empty redirect table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a redirect table from a `.toml` or `.csv` file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, RedirectsError> {
+        let path = path.as_ref();
+        let display = || path.display().to_string();
+        let content = std::fs::read_to_string(path).map_err(|err| RedirectsError::Io {
+            path: display(),
+            err,
+        })?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("toml") => Self::from_toml(&content),
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => Self::from_csv(&content),
+            _ => Err(RedirectsError::UnknownFormat { path: display() }),
+        }
+    }
+
+    /// Parse a redirect table in TOML.
+    ///
+    /// Redirects are listed before rewrites, each in their order in the table.
+    pub fn from_toml(s: &str) -> Result<Self, RedirectsError> {
+        let table: TomlTable = basic_toml::from_str(s)?;
+        let mut rules = Vec::with_capacity(table.redirect.len() + table.rewrite.len());
+
+        for rule in table.redirect {
+            let status = rule
+                .status
+                .map(|status| {
+                    http::StatusCode::from_u16(status).map_err(|_| RedirectsError::InvalidRule {
+                        from: rule.from.clone(),
+                        reason: format!("invalid status code `{status}`"),
+                    })
+                })
+                .transpose()?;
+
+            rules.push(Rule::new(
+                &rule.from,
+                rule.to,
+                rule.host,
+                Action::Redirect(status.unwrap_or(http::StatusCode::MOVED_PERMANENTLY)),
+            )?);
+        }
+
+        for rule in table.rewrite {
+            if rule.status.is_some() {
+                return Err(RedirectsError::InvalidRule {
+                    from: rule.from,
+                    reason: "rewrites have no status".to_owned(),
+                });
+            }
+
+            rules.push(Rule::new(&rule.from, rule.to, rule.host, Action::Rewrite)?);
+        }
+
+        Ok(Self {
+            rules: Arc::new(rules),
+        })
+    }
+
+    /// Parse a redirect table in CSV.
+    pub fn from_csv(s: &str) -> Result<Self, RedirectsError> {
+        let mut rules = Vec::new();
+
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let csv_error = |reason: String| RedirectsError::Csv {
+                line: index + 1,
+                reason,
+            };
+            let fields: Vec<_> = line.split(',').map(str::trim).collect();
+
+            let (from, to, status, host) = match fields[..] {
+                [from, to] => (from, to, None, None),
+                [from, to, status] => (from, to, Some(status), None),
+                [from, to, status, host] => (from, to, Some(status), Some(host)),
+                _ => {
+                    return Err(csv_error(format!(
+                        "expected 2 to 4 fields, found {}",
+                        fields.len()
+                    )));
+                }
+            };
+
+            let action = match status {
+                None | Some("") => Action::Redirect(http::StatusCode::MOVED_PERMANENTLY),
+                Some(status) if status.eq_ignore_ascii_case("rewrite") => Action::Rewrite,
+                Some(status) => Action::Redirect(
+                    status
+                        .parse()
+                        .map_err(|_| csv_error(format!("invalid status `{status}`")))?,
+                ),
+            };
+            let host = host.filter(|host| !host.is_empty()).map(ToOwned::to_owned);
+
+            rules.push(Rule::new(from, to.to_owned(), host, action)?);
+        }
+
+        Ok(Self {
+            rules: Arc::new(rules),
+        })
+    }
+
+    /// Add a redirect rule, responding with the specified status.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rule is invalid, as the files loaded by [`Redirects::from_file`] would fail
+    /// to load.
+    pub fn with_redirect(
+        self,
+        from: &str,
+        to: impl Into<String>,
+        status: http::StatusCode,
+    ) -> Self {
+        self.with_rule(from, to.into(), None, Action::Redirect(status))
+    }
+
+    /// Add a rewrite rule, changing the URL of the matching requests before they are routed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rule is invalid, as the files loaded by [`Redirects::from_file`] would fail
+    /// to load.
+    pub fn with_rewrite(self, from: &str, to: impl Into<String>) -> Self {
+        self.with_rule(from, to.into(), None, Action::Rewrite)
+    }
+
+    /// Add a rule.
+    fn with_rule(mut self, from: &str, to: String, host: Option<String>, action: Action) -> Self {
+        let rule = Rule::new(from, to, host, action).unwrap_or_else(|err| panic!("{err}"));
+
+        Arc::make_mut(&mut self.rules).push(rule);
+        self
+    }
+
+    /// Get the number of rules in the table.
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Check whether the table has no rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Apply the first matching rule to a request.
+    ///
+    /// Returns the redirection response of matching redirects, and changes the URL of the
+    /// request for matching rewrites.
+    pub(crate) fn apply(
+        &self,
+        request: &mut axum::extract::Request,
+    ) -> Option<axum::response::Response> {
+        let base_host = request
+            .extensions()
+            .get::<Arc<ServerInfo>>()
+            .and_then(|server_info| server_info.base_url.host());
+
+        let (rule, target) = self.rules.iter().find_map(|rule| {
+            if let Some(pattern) = &rule.host
+                && !request_host(request).is_some_and(|host| host_matches(pattern, host, base_host))
+            {
+                return None;
+            }
+
+            rule.target(request.uri()).map(|target| (rule, target))
+        })?;
+
+        if let Action::Redirect(status) = rule.action {
+            return Some((status, [(http::header::LOCATION, target)]).into_response());
+        }
+
+        let mut parts = request.uri().clone().into_parts();
+
+        match target
+            .parse()
+            .map_err(http::Error::from)
+            .and_then(|path_and_query| {
+                parts.path_and_query = Some(path_and_query);
+
+                Ok(http::Uri::from_parts(parts)?)
+            }) {
+            Ok(uri) => *request.uri_mut() = uri,
+            Err(err) => {
+                tracing::error!("Failed to rewrite `{}` to `{target}`: {err}", request.uri());
+            }
+        }
+
+        None
+    }
+}
+
+impl Rule {
+    /// Parse and validate a rule.
+    fn new(
+        from: &str,
+        to: String,
+        host: Option<String>,
+        action: Action,
+    ) -> Result<Self, RedirectsError> {
+        let invalid = |reason: String| RedirectsError::InvalidRule {
+            from: from.to_owned(),
+            reason,
+        };
+
+        let Some(path) = from.strip_prefix('/') else {
+            return Err(invalid("the pattern must start with a slash".to_owned()));
+        };
+
+        let segments: Vec<_> = path.split('/').collect();
+        let mut pattern = Vec::with_capacity(segments.len());
+
+        for (index, segment) in segments.iter().enumerate() {
+            let segment = match segment
+                .strip_prefix('{')
+                .and_then(|segment| segment.strip_suffix('}'))
+            {
+                Some(name) => match name.strip_prefix('*') {
+                    Some(_) if index + 1 != segments.len() => {
+                        return Err(invalid(format!(
+                            "the rest placeholder `{{{name}}}` must be the last segment"
+                        )));
+                    }
+                    Some(name) => Segment::Rest(name.to_owned()),
+                    None => Segment::Parameter(name.to_owned()),
+                },
+                None if segment.contains(['{', '}']) => {
+                    return Err(invalid(format!(
+                        "placeholders must span whole segments, found `{segment}`"
+                    )));
+                }
+                None => Segment::Literal((*segment).to_owned()),
+            };
+
+            if let Segment::Parameter(name) | Segment::Rest(name) = &segment
+                && (name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_'))
+            {
+                return Err(invalid(format!("invalid placeholder name `{name}`")));
+            }
+
+            pattern.push(segment);
+        }
+
+        match action {
+            Action::Redirect(status) if !status.is_redirection() => {
+                return Err(invalid(format!("`{status}` is not a redirection status")));
+            }
+            Action::Rewrite if !to.starts_with('/') => {
+                return Err(invalid(format!(
+                    "the rewrite target `{to}` must be a path starting with a slash"
+                )));
+            }
+            _ => {}
+        }
+
+        let rule = Self {
+            host,
+            from: pattern,
+            to,
+            action,
+        };
+
+        // Check that the target only references placeholders of the pattern.
+        let mut rest = rule.to.as_str();
+
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}') else {
+                return Err(invalid(format!(
+                    "unclosed placeholder in target `{}`",
+                    rule.to
+                )));
+            };
+            let name = &rest[start + 1..start + end];
+
+            if !rule.from.iter().any(
+                |segment| matches!(segment, Segment::Parameter(n) | Segment::Rest(n) if n == name),
+            ) {
+                return Err(invalid(format!(
+                    "the target references the unknown placeholder `{{{name}}}`"
+                )));
+            }
+
+            rest = &rest[start + end + 1..];
+        }
+
+        Ok(rule)
+    }
+
+    /// Get the target of a URI, if it matches the pattern of the rule.
+    fn target(&self, uri: &http::Uri) -> Option<String> {
+        // The remaining path to match, or `None` once all its segments are matched.
+        let mut remaining = Some(uri.path().strip_prefix('/')?);
+        let mut captures = Vec::new();
+
+        for segment in &self.from {
+            if let Segment::Rest(name) = segment {
+                captures.push((name.as_str(), remaining.take().unwrap_or_default()));
+
+                break;
+            }
+
+            let path = remaining?;
+            let (value, rest) = match path.split_once('/') {
+                Some((value, rest)) => (value, Some(rest)),
+                None => (path, None),
+            };
+
+            match segment {
+                Segment::Literal(literal) if literal == value => {}
+                Segment::Parameter(name) if !value.is_empty() => captures.push((name, value)),
+                _ => return None,
+            }
+
+            remaining = rest;
+        }
+
+        remaining
+            .is_none()
+            .then(|| self.substitute(&captures, uri.query()))
+    }
+
+    /// Substitute the captured placeholders in the target, keeping the query of the request if
+    /// the target has none.
+    fn substitute(&self, captures: &[(&str, &str)], query: Option<&str>) -> String {
+        let mut target = self.to.clone();
+
+        for (name, value) in captures {
+            target = target.replace(&format!("{{{name}}}"), value);
+        }
+
+        if let Some(query) = query
+            && !target.contains('?')
+        {
+            target.push('?');
+            target.push_str(query);
+        }
+
+        target
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(redirects: &Redirects, uri: &str) -> Option<String> {
+        let uri: http::Uri = uri.parse().unwrap();
+
+        redirects.rules.iter().find_map(|rule| rule.target(&uri))
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let redirects = Redirects::from_toml(
+            r#"
+            [[redirect]]
+            from = "/old-blog/{slug}"
+            to = "/blog/{slug}"
+            status = 308
+
+            [[redirect]]
+            from = "/docs/{*path}"
+            to = "https://docs.example.com/{path}"
+
+            [[rewrite]]
+            from = "/feed"
+            to = "/blog/feed.xml"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(redirects.len(), 3);
+        assert_eq!(
+            redirects.rules[0].action,
+            Action::Redirect(http::StatusCode::PERMANENT_REDIRECT)
+        );
+        assert_eq!(
+            redirects.rules[1].action,
+            Action::Redirect(http::StatusCode::MOVED_PERMANENTLY)
+        );
+        assert_eq!(redirects.rules[2].action, Action::Rewrite);
+
+        assert_eq!(
+            target(&redirects, "/old-blog/hello?page=2").as_deref(),
+            Some("/blog/hello?page=2")
+        );
+        assert_eq!(
+            target(&redirects, "/docs/guide/intro").as_deref(),
+            Some("https://docs.example.com/guide/intro")
+        );
+        assert_eq!(
+            target(&redirects, "/docs").as_deref(),
+            Some("https://docs.example.com/")
+        );
+        assert_eq!(target(&redirects, "/old-blog").as_deref(), None);
+        assert_eq!(target(&redirects, "/old-blog/hello/world").as_deref(), None);
+        assert_eq!(
+            target(&redirects, "/feed").as_deref(),
+            Some("/blog/feed.xml")
+        );
+        assert_eq!(target(&redirects, "/feed/").as_deref(), None);
+    }
+
+    #[test]
+    fn test_from_csv() {
+        let redirects = Redirects::from_csv(
+            "# from,to,status,host\n\
+             /promo,/offers?utm_source=promo,302\n\
+             \n\
+             /feed,/blog/feed.xml,rewrite\n\
+             /,/fr/,,fr.{base}\n",
+        )
+        .unwrap();
+
+        assert_eq!(redirects.len(), 3);
+        assert_eq!(
+            target(&redirects, "/promo?ref=x").as_deref(),
+            Some("/offers?utm_source=promo")
+        );
+        assert_eq!(redirects.rules[2].host.as_deref(), Some("fr.{base}"));
+
+        assert_eq!(
+            Redirects::from_csv("/a,/b\n/c").unwrap_err().to_string(),
+            "failed to parse line 2 of the CSV redirect table: expected 2 to 4 fields, found 1"
+        );
+        assert_eq!(
+            Redirects::from_csv("/a,/b,abc").unwrap_err().to_string(),
+            "failed to parse line 1 of the CSV redirect table: invalid status `abc`"
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        for (csv, expected) in [
+            ("a,/b", "the pattern must start with a slash"),
+            (
+                "/a/{id},/b/{slug}",
+                "the target references the unknown placeholder `{slug}`",
+            ),
+            (
+                "/{*path}/a,/b",
+                "the rest placeholder `{*path}` must be the last segment",
+            ),
+            (
+                "/a-{id},/b",
+                "placeholders must span whole segments, found `a-{id}`",
+            ),
+            ("/a,/b,200", "`200 OK` is not a redirection status"),
+            (
+                "/a,https://example.com,rewrite",
+                "the rewrite target `https://example.com` must be a path starting with a slash",
+            ),
+        ] {
+            let err = Redirects::from_csv(csv).unwrap_err();
+
+            assert!(
+                matches!(&err, RedirectsError::InvalidRule { reason, .. } if reason == expected),
+                "unexpected error for `{csv}`: {err}"
+            );
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! Request body decoding.
+
+use std::borrow::Cow;
+
+use axum::extract::FromRequest;
+use axum::response::IntoResponse;
+
+use super::MediaType;
+
+/// Decode a form-encoded request body into a value.
+///
+/// The request `Content-Type` must have the same type and subtype as `content_type`, in any case
+/// and with any parameters. If the request specifies a non-UTF-8 `charset` known to the WHATWG
+/// Encoding Standard (like `ISO-8859-1` or `Shift_JIS`), the body is transcoded before being
+/// deserialized.
+///
+/// As for regular HTML forms, `GET` and `HEAD` requests are decoded from the query string instead.
+///
+/// Typically used by the code generated by the `Route` derive macro for `#[body]` fields.
+pub async fn decode_form_body<T, S>(
+    req: axum::extract::Request,
+    state: &S,
+    content_type: &'static str,
+) -> Result<T, axum::response::Response>
+where
+    T: serde::de::DeserializeOwned,
+    S: Send + Sync,
+{
+    if req.method() == http::Method::GET || req.method() == http::Method::HEAD {
+        let query = req.uri().query().unwrap_or_default();
+
+        return serde_html_form::from_str(query).map_err(|err| {
+            (
+                http::StatusCode::BAD_REQUEST,
+                format!("Failed to deserialize form: {err}"),
+            )
+                .into_response()
+        });
+    }
+
+    let expected: MediaType = content_type
+        .parse()
+        .expect("body content type should be a valid media type");
+
+    let encoding = match MediaType::from_content_type(req.headers()) {
+        Some(media_type) if media_type.essence_eq(&expected) => match media_type.charset() {
+            Some(charset) => {
+                encoding_rs::Encoding::for_label(charset.as_bytes()).ok_or_else(|| {
+                    (
+                        http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                        format!("Unsupported charset `{charset}`"),
+                    )
+                        .into_response()
+                })?
+            }
+            None => encoding_rs::UTF_8,
+        },
+        _ => {
+            return Err((
+                http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("Expected request with `Content-Type: {content_type}`"),
+            )
+                .into_response());
+        }
+    };
+
+    let body = axum::body::Bytes::from_request(req, state)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let body = if encoding == encoding_rs::UTF_8 {
+        Cow::Borrowed(body.as_ref())
+    } else {
+        Cow::Owned(transcode_form(&body, encoding).into_bytes())
+    };
+
+    serde_html_form::from_bytes(&body).map_err(|err| {
+        (
+            http::StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Failed to deserialize form body: {err}"),
+        )
+            .into_response()
+    })
+}
+
+/// Decode a JSON request body into a value.
+///
+/// The request must have a JSON `Content-Type`, like `application/json`. The rejections of
+/// [`axum::Json`] are returned as responses.
+///
+/// Typically used by the code generated by the `Route` derive macro for
+/// `#[body("application/json")]` fields.
+pub async fn decode_json_body<T, S>(
+    req: axum::extract::Request,
+    state: &S,
+) -> Result<T, axum::response::Response>
+where
+    T: serde::de::DeserializeOwned,
+    S: Send + Sync,
+{
+    axum::Json::from_request(req, state)
+        .await
+        .map(|axum::Json(value)| value)
+        .map_err(IntoResponse::into_response)
+}
+
+/// Decode a request body into a value, according to the `Content-Type` of the request.
+///
+/// The body is decoded as JSON for `application/json`, and as a form for the form-encoded content
+/// types, if the request content type is one of `content_types`. Requests with another content
+/// type are rejected with `415 Unsupported Media Type`, except for `GET` and `HEAD` requests which
+/// are decoded from the query string if a form-encoded content type is accepted.
+///
+/// Typically used by the code generated by the `Route` derive macro for `#[body]` fields with
+/// several content types, like `#[body(any("application/json", "application/x-www-form-urlencoded"))]`.
+pub async fn decode_negotiated_body<T, S>(
+    req: axum::extract::Request,
+    state: &S,
+    content_types: &'static [&'static str],
+) -> Result<T, axum::response::Response>
+where
+    T: serde::de::DeserializeOwned,
+    S: Send + Sync,
+{
+    const JSON_CONTENT_TYPE: &str = "application/json";
+
+    let request_media_type = MediaType::from_content_type(req.headers());
+    let content_type = content_types.iter().copied().find(|content_type| {
+        let content_type: MediaType = content_type
+            .parse()
+            .expect("body content type should be a valid media type");
+
+        request_media_type
+            .as_ref()
+            .is_some_and(|media_type| media_type.essence_eq(&content_type))
+    });
+    let is_query = req.method() == http::Method::GET || req.method() == http::Method::HEAD;
+
+    match content_type {
+        _ if is_query => {
+            match content_types
+                .iter()
+                .find(|content_type| **content_type != JSON_CONTENT_TYPE)
+            {
+                Some(content_type) => decode_form_body(req, state, content_type).await,
+                None => decode_json_body(req, state).await,
+            }
+        }
+        Some(JSON_CONTENT_TYPE) => decode_json_body(req, state).await,
+        Some(content_type) => decode_form_body(req, state, content_type).await,
+        None => Err((
+            http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!(
+                "Expected request with `Content-Type: {}`",
+                content_types.join("` or `")
+            ),
+        )
+            .into_response()),
+    }
+}
+
+/// Transcode a form-encoded body from the specified encoding to UTF-8.
+///
+/// Percent-encoded sequences are decoded in the source encoding, and re-encoded in UTF-8.
+fn transcode_form(body: &[u8], encoding: &'static encoding_rs::Encoding) -> String {
+    let decode = |input: &[u8]| {
+        let input: Vec<u8> = input
+            .iter()
+            .map(|&b| if b == b'+' { b' ' } else { b })
+            .collect();
+        let input: Vec<u8> = percent_encoding::percent_decode(&input).collect();
+
+        encoding.decode_without_bom_handling(&input).0.into_owned()
+    };
+
+    let pairs: Vec<(String, String)> = body
+        .split(|&b| b == b'&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.iter().position(|&b| b == b'=') {
+            Some(idx) => (decode(&pair[..idx]), decode(&pair[idx + 1..])),
+            None => (decode(pair), String::new()),
+        })
+        .collect();
+
+    serde_html_form::to_string(&pairs).expect("string pairs should always serialize")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Form {
+        name: String,
+    }
+
+    fn make_request(content_type: &str, body: &'static [u8]) -> axum::extract::Request {
+        http::Request::builder()
+            .method(http::Method::POST)
+            .header(http::header::CONTENT_TYPE, content_type)
+            .body(axum::body::Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_decode_form_body_with_charset() {
+        let req = make_request(
+            "Application/X-WWW-Form-Urlencoded; charset=UTF-8",
+            b"name=J%C3%A9r%C3%B4me",
+        );
+        let form: Form = decode_form_body(req, &(), "application/x-www-form-urlencoded")
+            .await
+            .unwrap();
+        assert_eq!(form.name, "Jérôme");
+    }
+
+    #[tokio::test]
+    async fn test_decode_form_body_non_utf8_charset() {
+        let req = make_request(
+            "application/x-www-form-urlencoded; charset=ISO-8859-1",
+            b"name=J%E9r%F4me+D",
+        );
+        let form: Form = decode_form_body(req, &(), "application/x-www-form-urlencoded")
+            .await
+            .unwrap();
+        assert_eq!(form.name, "Jérôme D");
+    }
+
+    #[tokio::test]
+    async fn test_decode_form_body_unsupported() {
+        let req = make_request("application/x-www-form-urlencoded; charset=nope", b"");
+        let err = decode_form_body::<Form, _>(req, &(), "application/x-www-form-urlencoded")
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), http::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let req = make_request("application/json", b"{}");
+        let err = decode_form_body::<Form, _>(req, &(), "application/x-www-form-urlencoded")
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), http::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+}
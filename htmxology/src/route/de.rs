@@ -68,8 +68,7 @@ macro_rules! impl_simple {
         where
             V: Visitor<'de>,
         {
-            let value = percent_encoding::percent_decode(self.url_encoded_value.as_bytes())
-                .decode_utf8()?;
+            let value = self.value()?;
 
             let value =
                 value
@@ -86,12 +85,36 @@ macro_rules! impl_simple {
 
 pub(crate) struct PathArgumentDeserializer<'de> {
     url_encoded_value: &'de str,
+
+    /// Whether the value is percent-decoded before being deserialized.
+    percent_decode: bool,
 }
 
 impl<'de> PathArgumentDeserializer<'de> {
     #[inline]
     pub(crate) fn new(url_encoded_value: &'de str) -> Self {
-        PathArgumentDeserializer { url_encoded_value }
+        PathArgumentDeserializer {
+            url_encoded_value,
+            percent_decode: true,
+        }
+    }
+
+    /// Create a deserializer for a value that is deserialized as-is, without percent-decoding.
+    #[inline]
+    pub(crate) fn new_encoded(url_encoded_value: &'de str) -> Self {
+        PathArgumentDeserializer {
+            url_encoded_value,
+            percent_decode: false,
+        }
+    }
+
+    /// Get the value to deserialize, percent-decoded if required.
+    fn value(&self) -> Result<Cow<'de, str>, std::str::Utf8Error> {
+        if self.percent_decode {
+            percent_encoding::percent_decode(self.url_encoded_value.as_bytes()).decode_utf8()
+        } else {
+            Ok(Cow::Borrowed(self.url_encoded_value))
+        }
     }
 }
 
@@ -133,7 +156,7 @@ impl<'de> Deserializer<'de> for PathArgumentDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        match percent_encoding::percent_decode(self.url_encoded_value.as_bytes()).decode_utf8()? {
+        match self.value()? {
             Cow::Borrowed(value) => visitor.visit_borrowed_str(value),
             Cow::Owned(value) => {
                 Err(PathArgumentDeserializationError::PercentDecodingRequired { value })
@@ -399,4 +422,13 @@ mod tests {
             PathArgumentDeserializationError::ParseError{value, expected_type} if value == "foo" && expected_type == "u16"
         );
     }
+
+    #[test]
+    fn test_parse_encoded_value() {
+        let deserializer = PathArgumentDeserializer::new_encoded("alpha%20beta");
+        assert_eq!(String::deserialize(deserializer).unwrap(), "alpha%20beta");
+
+        let deserializer = PathArgumentDeserializer::new_encoded("alpha%20beta");
+        assert_eq!(<&str>::deserialize(deserializer).unwrap(), "alpha%20beta");
+    }
 }
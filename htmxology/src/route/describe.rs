@@ -0,0 +1,246 @@
+//! Machine-readable route descriptions.
+
+use super::RouteEntry;
+
+/// The description of a route, with its parameters and documentation.
+///
+/// See [`Route::describe`](crate::Route::describe).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct RouteDescription {
+    /// The path of the variant, like `Blog::Post` for a variant of a subroute.
+    pub variant: String,
+
+    /// The HTTP method of the route.
+    pub method: String,
+
+    /// The URL pattern of the route, like `/blog/{id}`.
+    pub pattern: String,
+
+    /// The host pattern the route is scoped to, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+
+    /// The documentation of the variant, from its doc comments.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+
+    /// The id selector of the element the route is swapped into, if any.
+    ///
+    /// See [`Route::expected_target`](crate::Route::expected_target).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+
+    /// The parameters of the route, in URL order, followed by the query and body parameters.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub parameters: Vec<RouteParameter>,
+}
+
+/// A parameter of a route.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct RouteParameter {
+    /// The name of the parameter, which is the name of the field of the variant, or of its
+    /// placeholder in the URL pattern for unnamed fields.
+    pub name: String,
+
+    /// Where the parameter is taken from in the request.
+    pub location: ParameterLocation,
+
+    /// The Rust type of the parameter, like `u32` or `Option<SearchQuery>`.
+    #[serde(rename = "type")]
+    pub ty: String,
+
+    /// The media types accepted for body parameters.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub content_types: Vec<String>,
+}
+
+/// Where a route parameter is taken from in the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterLocation {
+    /// A placeholder of the URL path.
+    Path,
+
+    /// The query string, deserialized as a whole.
+    Query,
+
+    /// The request body.
+    Body,
+}
+
+impl RouteParameter {
+    /// Create a new route parameter.
+    pub fn new(
+        name: impl Into<String>,
+        location: ParameterLocation,
+        ty: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            location,
+            ty: ty.into(),
+            content_types: Vec::new(),
+        }
+    }
+
+    /// Set the media types accepted for a body parameter.
+    pub fn with_content_types(mut self, content_types: &[&str]) -> Self {
+        self.content_types = content_types.iter().map(|&ct| ct.to_owned()).collect();
+        self
+    }
+}
+
+impl RouteDescription {
+    /// Create a new route description, without parameters nor documentation.
+    pub fn new(
+        variant: impl Into<String>,
+        method: impl Into<String>,
+        pattern: impl Into<String>,
+    ) -> Self {
+        Self::from(RouteEntry::new(variant, method, pattern))
+    }
+
+    /// Set the documentation of the route.
+    pub fn with_doc(mut self, doc: impl Into<String>) -> Self {
+        self.doc = Some(doc.into());
+        self
+    }
+
+    /// Set the id selector of the element the route is swapped into.
+    pub fn with_target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    /// Add a parameter to the route.
+    pub fn with_parameter(mut self, parameter: RouteParameter) -> Self {
+        self.parameters.push(parameter);
+        self
+    }
+
+    /// Nest the descriptions of a subroute or catch-all route type under a variant.
+    ///
+    /// As with [`RouteEntry::nested`], the variant name and the URL prefix are prepended to each
+    /// description. The parameters of the prefix come before the parameters of each description,
+    /// and the documentation and target of the variant apply to the descriptions without their
+    /// own.
+    ///
+    /// Typically used by the code generated by the `Route` derive macro.
+    pub fn nested(
+        descriptions: Vec<Self>,
+        parent: Self,
+        prefix: &str,
+        host: Option<&str>,
+    ) -> Vec<Self> {
+        let prefix = prefix.trim_end_matches('/');
+
+        descriptions
+            .into_iter()
+            .map(|description| Self {
+                variant: format!("{}::{}", parent.variant, description.variant),
+                method: description.method,
+                pattern: format!("{prefix}{}", description.pattern),
+                host: description.host.or_else(|| host.map(ToOwned::to_owned)),
+                doc: description.doc.or_else(|| parent.doc.clone()),
+                target: description.target.or_else(|| parent.target.clone()),
+                parameters: parent
+                    .parameters
+                    .iter()
+                    .cloned()
+                    .chain(description.parameters)
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Get the route table entry of the route.
+    pub fn to_entry(&self) -> RouteEntry {
+        RouteEntry {
+            variant: self.variant.clone(),
+            method: self.method.clone(),
+            pattern: self.pattern.clone(),
+            host: self.host.clone(),
+        }
+    }
+}
+
+impl From<RouteEntry> for RouteDescription {
+    fn from(entry: RouteEntry) -> Self {
+        Self {
+            variant: entry.variant,
+            method: entry.method,
+            pattern: entry.pattern,
+            host: entry.host,
+            doc: None,
+            target: None,
+            parameters: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested() {
+        let descriptions = vec![
+            RouteDescription::new("Home", "GET", "/").with_doc("The home page."),
+            RouteDescription::new("Post", "POST", "/posts/{id}")
+                .with_parameter(RouteParameter::new("id", ParameterLocation::Path, "u32")),
+        ];
+        let parent = RouteDescription::new("Blog", "GET", "/blog/{lang}/")
+            .with_doc("The blog.")
+            .with_target("#blog")
+            .with_parameter(RouteParameter::new(
+                "lang",
+                ParameterLocation::Path,
+                "String",
+            ));
+
+        let nested = RouteDescription::nested(descriptions, parent, "/blog/{lang}/", None);
+
+        assert_eq!(nested[0].variant, "Blog::Home");
+        assert_eq!(nested[0].pattern, "/blog/{lang}/");
+        assert_eq!(nested[0].doc.as_deref(), Some("The home page."));
+        assert_eq!(nested[0].parameters.len(), 1);
+        assert_eq!(nested[1].pattern, "/blog/{lang}/posts/{id}");
+        assert_eq!(nested[1].doc.as_deref(), Some("The blog."));
+        assert_eq!(nested[1].target.as_deref(), Some("#blog"));
+        assert_eq!(
+            nested[1]
+                .parameters
+                .iter()
+                .map(|parameter| parameter.name.as_str())
+                .collect::<Vec<_>>(),
+            ["lang", "id"]
+        );
+        assert_eq!(
+            nested[1].to_entry(),
+            RouteEntry::new("Blog::Post", "POST", "/blog/{lang}/posts/{id}")
+        );
+    }
+
+    #[test]
+    fn test_serialize() {
+        let description = RouteDescription::new("Search", "POST", "/search").with_parameter(
+            RouteParameter::new("form", ParameterLocation::Body, "SearchForm")
+                .with_content_types(&["application/json"]),
+        );
+
+        assert_eq!(
+            serde_json::to_value(&description).unwrap(),
+            serde_json::json!({
+                "variant": "Search",
+                "method": "POST",
+                "pattern": "/search",
+                "parameters": [{
+                    "name": "form",
+                    "location": "body",
+                    "type": "SearchForm",
+                    "content_types": ["application/json"],
+                }],
+            })
+        );
+    }
+}
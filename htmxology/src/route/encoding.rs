@@ -0,0 +1,198 @@
+//! Percent-encoding of path parameters.
+
+use std::fmt::{Display, Write};
+use std::str::FromStr;
+
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
+
+/// The characters percent-encoded in a path segment.
+///
+/// This is the path percent-encode set of the URL standard, plus `/` so that values are formatted
+/// as a single segment, and `%` so that values round-trip through percent-decoding.
+pub(crate) const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// The characters percent-encoded in a rest path, which keeps its slashes.
+pub(crate) const REST_PATH: &AsciiSet = &PATH_SEGMENT.remove(b'/');
+
+/// The characters percent-encoded in a URL fragment, as per the fragment percent-encode set of the
+/// URL standard.
+const FRAGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'<')
+    .add(b'>')
+    .add(b'`');
+
+/// A writer percent-encoding everything written to it.
+struct PercentEncoder<'a, W> {
+    writer: &'a mut W,
+    ascii_set: &'static AsciiSet,
+}
+
+impl<W: Write> Write for PercentEncoder<'_, W> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        for chunk in utf8_percent_encode(s, self.ascii_set) {
+            self.writer.write_str(chunk)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Write a value percent-encoded with the specified set.
+pub(crate) fn write_encoded(
+    writer: &mut impl Write,
+    value: &(impl Display + ?Sized),
+    ascii_set: &'static AsciiSet,
+) -> std::fmt::Result {
+    write!(PercentEncoder { writer, ascii_set }, "{value}")
+}
+
+/// Format a path parameter as a single percent-encoded path segment.
+///
+/// Typically used by the `Display` implementations generated by the `Route` derive macro, so that
+/// values containing `/`, spaces or `#` don't break the URL of the route.
+///
+/// Empty values format as empty segments, which path parameters don't match when parsing.
+pub fn fmt_path_segment(value: &impl Display, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write_encoded(f, value, PATH_SEGMENT)
+}
+
+/// Format a value as a percent-encoded URL fragment, without its leading `#`.
+///
+/// Returns `None` if the value formats as an empty string, so that empty anchors are omitted from
+/// URLs. Typically used by the `Display` implementations generated by the `Route` derive macro for
+/// `#[fragment_anchor]` fields.
+pub fn format_fragment(value: &(impl Display + ?Sized)) -> Option<String> {
+    let mut fragment = String::new();
+
+    write_encoded(&mut fragment, value, FRAGMENT).ok()?;
+
+    (!fragment.is_empty()).then_some(fragment)
+}
+
+/// Check whether a path argument is validly percent-encoded: every `%` must start an escape of
+/// two hexadecimal digits, and the escapes must decode to UTF-8.
+///
+/// Typically used by the code generated by the `Route` derive macro, so that paths with invalid
+/// escapes, like `/users/%ZZ` or `/users/%FF`, don't match any route.
+pub fn is_valid_path_argument(value: &str) -> bool {
+    value.split('%').skip(1).all(|escape| {
+        escape
+            .as_bytes()
+            .get(..2)
+            .is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+    }) && percent_encoding::percent_decode_str(value)
+        .decode_utf8()
+        .is_ok()
+}
+
+/// Parse a percent-encoded path argument into a value, returning the error message on failure.
+///
+/// Typically used by the `FromStr` implementations generated by the `Route` derive macro.
+pub fn parse_path_argument<T>(value: &str) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if !is_valid_path_argument(value) {
+        return Err("invalid percent-encoding".to_owned());
+    }
+
+    let value = percent_encoding::percent_decode_str(value).decode_utf8_lossy();
+
+    parse_encoded_path_argument(&value)
+}
+
+/// Parse a path argument into a value, without percent-decoding it, returning the error message on
+/// failure.
+///
+/// Typically used by the `FromStr` implementations generated by the `Route` derive macro, for
+/// path parameters declared as already encoded with `#[param(name, encoded)]`.
+pub fn parse_encoded_path_argument<T>(value: &str) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value.parse().map_err(|err: T::Err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Segment<T>(T);
+
+    impl<T: Display> Display for Segment<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            fmt_path_segment(&self.0, f)
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for value in ["hello", "a/b", "my file#1", "100%", "été?x=1", "{id}"] {
+            let encoded = Segment(value).to_string();
+
+            assert!(!encoded.contains(['/', ' ', '#', '?']), "{encoded}");
+            assert_eq!(parse_path_argument::<String>(&encoded).unwrap(), value);
+        }
+
+        assert_eq!(Segment("a/b c").to_string(), "a%2Fb%20c");
+        assert_eq!(Segment(42).to_string(), "42");
+    }
+
+    #[test]
+    fn test_format_fragment() {
+        assert_eq!(format_fragment("section-2"), Some("section-2".to_owned()));
+        assert_eq!(
+            format_fragment("a b#c/d?e"),
+            Some("a%20b%23c/d?e".to_owned())
+        );
+        assert_eq!(format_fragment(""), None);
+    }
+
+    #[test]
+    fn test_is_valid_path_argument() {
+        for value in ["", "hello", "a%2Fb", "%C3%A9t%C3%A9", "100%25"] {
+            assert!(is_valid_path_argument(value), "{value}");
+        }
+
+        for value in ["%ZZ", "%", "100%", "%2", "%%41", "%FF", "%C3"] {
+            assert!(!is_valid_path_argument(value), "{value}");
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_path_argument::<u32>("42"), Ok(42));
+        assert_eq!(
+            parse_path_argument::<u32>("4x"),
+            Err("invalid digit found in string".to_owned())
+        );
+        assert_eq!(
+            parse_path_argument::<String>("%FF"),
+            Err("invalid percent-encoding".to_owned())
+        );
+        assert_eq!(
+            parse_path_argument::<String>("%ZZ"),
+            Err("invalid percent-encoding".to_owned())
+        );
+        assert_eq!(
+            parse_encoded_path_argument::<String>("a%2Fb"),
+            Ok("a%2Fb".to_owned())
+        );
+    }
+}
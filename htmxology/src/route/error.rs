@@ -2,14 +2,29 @@
 
 use std::fmt;
 
-/// Error that can occur when parsing a route from a string.
+use crate::Route;
+
+/// The maximum number of route suggestions of a [`ParseError::NoMatchingRoute`] error.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Error that can occur when parsing a route from a string or a URI.
+///
+/// Only GET routes can be parsed from URL strings, while `Route::from_uri` supports any method
+/// for routes that don't have request bodies.
 ///
-/// This error is only relevant for GET routes, as only GET routes can be
-/// parsed from URL strings (since they don't have request bodies).
+/// New variants may be added in minor releases, so matches on it need a wildcard arm.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum ParseError {
-    /// The URL doesn't match any known GET route pattern.
-    NoMatchingRoute { url: String },
+    /// The URL doesn't match any known route pattern.
+    ///
+    /// The suggestions are empty until filled by [`ParseError::with_suggestions`], as computing
+    /// them requires the route table of the top-level route type.
+    NoMatchingRoute {
+        url: String,
+        method: String,
+        suggestions: Vec<RouteSuggestion>,
+    },
 
     /// The URL matches a route, but that route is not a GET route.
     /// FromStr only works for GET routes since other methods may have request bodies.
@@ -27,13 +42,39 @@ pub enum ParseError {
 
     /// A path parameter was missing from the URL.
     MissingPathParam { param_name: String },
+
+    /// The URL matches a route, but not for the requested method.
+    MethodNotAllowed { url: String, method: String },
+
+    /// The URL and method match a route that requires a request body, which can't be parsed
+    /// from a URI alone.
+    BodyRequired { url: String, method: String },
+
+    /// The URL and method match a route with fields extracted from the request, which can't be
+    /// parsed from a URI alone.
+    ExtractionRequired { url: String, method: String },
 }
 
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::NoMatchingRoute { url } => {
-                write!(f, "No matching GET route found for URL: {}", url)
+            Self::NoMatchingRoute {
+                url,
+                method,
+                suggestions,
+            } => {
+                write!(f, "No matching {} route found for URL: {}", method, url)?;
+
+                if !suggestions.is_empty() {
+                    let suggestions: Vec<_> = suggestions
+                        .iter()
+                        .map(|suggestion| format!("{} {}", suggestion.method, suggestion.pattern))
+                        .collect();
+
+                    write!(f, " (did you mean: {}?)", suggestions.join(", "))?;
+                }
+
+                Ok(())
             }
             Self::NotAGetRoute { url, method } => {
                 write!(
@@ -59,8 +100,232 @@ impl fmt::Display for ParseError {
             Self::MissingPathParam { param_name } => {
                 write!(f, "Missing required path parameter: {}", param_name)
             }
+            Self::MethodNotAllowed { url, method } => {
+                write!(f, "URL '{}' has no matching {} route", url, method)
+            }
+            Self::BodyRequired { url, method } => {
+                write!(
+                    f,
+                    "URL '{}' matches a {} route that requires a request body",
+                    url, method
+                )
+            }
+            Self::ExtractionRequired { url, method } => {
+                write!(
+                    f,
+                    "URL '{}' matches a {} route with fields extracted from the request",
+                    url, method
+                )
+            }
         }
     }
 }
 
 impl std::error::Error for ParseError {}
+
+impl ParseError {
+    /// Suggest the nearest routes of a route type for a [`ParseError::NoMatchingRoute`] error.
+    ///
+    /// Routes are ranked by how many path segments they share with the URL, from its start,
+    /// preferring routes with the same method. Other errors are returned unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// if let Err(err) = AppRoute::from_uri(&uri, &method) {
+    ///     for suggestion in err.with_suggestions::<AppRoute>().suggestions() {
+    ///         println!("did you mean {} {}?", suggestion.method, suggestion.pattern);
+    ///     }
+    /// }
+    /// ```
+    pub fn with_suggestions<R: Route>(self) -> Self {
+        match self {
+            Self::NoMatchingRoute { url, method, .. } => {
+                let suggestions = RouteSuggestion::rank(R::route_table(), &url, &method);
+
+                Self::NoMatchingRoute {
+                    url,
+                    method,
+                    suggestions,
+                }
+            }
+            err => err,
+        }
+    }
+
+    /// Replace the URL of a [`ParseError::NoMatchingRoute`] error.
+    ///
+    /// Typically used by the code generated by the `Route` derive macro, to report the full URL
+    /// rather than the path of a subroute. Other errors are returned unchanged.
+    pub fn with_url(self, url: &str) -> Self {
+        match self {
+            Self::NoMatchingRoute {
+                method,
+                suggestions,
+                ..
+            } => Self::NoMatchingRoute {
+                url: url.to_owned(),
+                method,
+                suggestions,
+            },
+            err => err,
+        }
+    }
+
+    /// Get the route suggestions of the error, nearest first.
+    ///
+    /// Only [`ParseError::NoMatchingRoute`] errors filled by [`ParseError::with_suggestions`]
+    /// have suggestions.
+    pub fn suggestions(&self) -> &[RouteSuggestion] {
+        match self {
+            Self::NoMatchingRoute { suggestions, .. } => suggestions,
+            _ => &[],
+        }
+    }
+}
+
+impl ParseError {
+    /// Render a development page describing a request that doesn't match any route.
+    pub(crate) fn to_debug_page(&self) -> String {
+        use crate::htmx::escape_text;
+
+        let mut page = format!(
+            "<!DOCTYPE html><html><head><title>404 Not Found</title></head><body><h1>No matching route</h1><p>{}</p>",
+            escape_text(&self.to_string())
+        );
+
+        if !self.suggestions().is_empty() {
+            page.push_str("<h2>Did you mean</h2><ul>");
+
+            for suggestion in self.suggestions() {
+                page.push_str(&format!(
+                    "<li><code>{} {}</code> ({})</li>",
+                    suggestion.method,
+                    escape_text(&suggestion.pattern),
+                    escape_text(&suggestion.variant),
+                ));
+            }
+
+            page.push_str("</ul>");
+        }
+
+        page.push_str(
+            "<p><small>This page is only shown in debug builds.</small></p></body></html>",
+        );
+        page
+    }
+}
+
+/// A route suggested for a URL that doesn't match any route.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct RouteSuggestion {
+    /// The path of the variant, like `Blog::Post` for a variant of a subroute.
+    pub variant: String,
+
+    /// The HTTP method of the route.
+    pub method: String,
+
+    /// The URL pattern of the route, like `/blog/{id}`.
+    pub pattern: String,
+
+    /// The number of path segments that differ between the URL and the pattern, after their
+    /// common prefix.
+    pub distance: usize,
+}
+
+impl RouteSuggestion {
+    /// Rank the entries of a route table by distance to a URL, keeping the nearest ones.
+    fn rank(entries: Vec<super::RouteEntry>, url: &str, method: &str) -> Vec<Self> {
+        let path = url.split(['?', '#']).next().unwrap_or_default();
+        let segments = path_segments(path);
+
+        let mut suggestions: Vec<_> = entries
+            .into_iter()
+            .filter(|entry| entry.host.is_none())
+            .filter_map(|entry| {
+                let pattern = path_segments(&entry.pattern);
+                let common = segments
+                    .iter()
+                    .zip(&pattern)
+                    .take_while(|(segment, pattern)| pattern.starts_with('{') || segment == pattern)
+                    .count();
+
+                // Routes that share nothing with the URL are not worth suggesting.
+                if common == 0 && !segments.is_empty() {
+                    return None;
+                }
+
+                Some(Self {
+                    distance: segments.len() + pattern.len() - 2 * common,
+                    variant: entry.variant,
+                    method: entry.method,
+                    pattern: entry.pattern,
+                })
+            })
+            .collect();
+
+        suggestions.sort_by_key(|suggestion| (suggestion.distance, suggestion.method != method));
+        suggestions.truncate(MAX_SUGGESTIONS);
+        suggestions
+    }
+}
+
+/// Split a path in its non-empty segments.
+fn path_segments(path: &str) -> Vec<&str> {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RouteEntry;
+
+    #[test]
+    fn test_rank_suggestions() {
+        let entries = vec![
+            RouteEntry::new("Home", "GET", "/"),
+            RouteEntry::new("Blog::Index", "GET", "/blog/"),
+            RouteEntry::new("Blog::Post", "GET", "/blog/{id:u32}"),
+            RouteEntry::new("Blog::Delete", "DELETE", "/blog/{id:u32}"),
+            RouteEntry::new("Blog::Comments", "GET", "/blog/{id:u32}/comments"),
+            RouteEntry::new("About", "GET", "/about"),
+        ];
+
+        let suggestions = RouteSuggestion::rank(entries, "/blog/42/comment?page=2", "DELETE");
+        let suggestions: Vec<_> = suggestions
+            .iter()
+            .map(|suggestion| (suggestion.variant.as_str(), suggestion.distance))
+            .collect();
+
+        assert_eq!(
+            suggestions,
+            [
+                ("Blog::Delete", 1),
+                ("Blog::Post", 1),
+                ("Blog::Index", 2),
+                ("Blog::Comments", 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_display_suggestions() {
+        let err = ParseError::NoMatchingRoute {
+            url: "/blog/42/comment".to_owned(),
+            method: "GET".to_owned(),
+            suggestions: vec![RouteSuggestion {
+                variant: "Blog::Comments".to_owned(),
+                method: "GET".to_owned(),
+                pattern: "/blog/{id:u32}/comments".to_owned(),
+                distance: 2,
+            }],
+        };
+
+        assert_eq!(
+            err.to_string(),
+            "No matching GET route found for URL: /blog/42/comment (did you mean: GET /blog/{id:u32}/comments?)"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Parsing of `multipart/form-data` bodies.
+
+/// A part of a multipart body.
+#[derive(Debug)]
+pub(crate) struct Part {
+    pub(crate) name: String,
+    pub(crate) file_name: Option<String>,
+    pub(crate) content_type: Option<String>,
+    pub(crate) content: axum::body::Bytes,
+}
+
+/// Split a multipart body into its parts.
+pub(crate) fn parse_parts(
+    body: &axum::body::Bytes,
+    boundary: &str,
+) -> Result<Vec<Part>, &'static str> {
+    let delimiter = format!("--{boundary}");
+    let separator = format!("\r\n--{boundary}");
+
+    let mut position =
+        find(body, delimiter.as_bytes(), 0).ok_or("missing first boundary")? + delimiter.len();
+    let mut parts = Vec::new();
+
+    loop {
+        let rest = &body[position..];
+
+        if rest.starts_with(b"--") {
+            return Ok(parts);
+        }
+
+        if !rest.starts_with(b"\r\n") {
+            return Err("invalid boundary line");
+        }
+
+        let headers_start = position + 2;
+        let headers_end =
+            find(body, b"\r\n\r\n", headers_start).ok_or("unterminated part headers")?;
+        let content_start = headers_end + 4;
+        let content_end =
+            find(body, separator.as_bytes(), headers_end + 2).ok_or("unterminated part")?;
+
+        if content_end < content_start {
+            return Err("unterminated part headers");
+        }
+
+        let headers = std::str::from_utf8(&body[headers_start..headers_end])
+            .map_err(|_| "part headers must be valid UTF-8")?;
+
+        let mut part = Part {
+            name: String::new(),
+            file_name: None,
+            content_type: None,
+            content: body.slice(content_start..content_end),
+        };
+        let mut has_name = false;
+
+        for header in headers.split("\r\n") {
+            let Some((name, value)) = header.split_once(':') else {
+                return Err("invalid part header");
+            };
+
+            if name.trim().eq_ignore_ascii_case("content-disposition") {
+                for (key, value) in disposition_params(value) {
+                    match key.as_str() {
+                        "name" => {
+                            part.name = value;
+                            has_name = true;
+                        }
+                        "filename" => part.file_name = Some(value),
+                        _ => {}
+                    }
+                }
+            } else if name.trim().eq_ignore_ascii_case("content-type") {
+                part.content_type = Some(value.trim().to_owned());
+            }
+        }
+
+        if !has_name {
+            return Err("part without a field name");
+        }
+
+        parts.push(part);
+        position = content_end + separator.len();
+    }
+}
+
+/// Find the position of a needle in a haystack, from a starting position.
+fn find(haystack: &[u8], needle: &[u8], start: usize) -> Option<usize> {
+    haystack
+        .get(start..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|position| start + position)
+}
+
+/// Parse the parameters of a `Content-Disposition` header value, like
+/// `form-data; name="avatar"; filename="me.png"`.
+///
+/// Parameter names are normalized to lowercase, and quoted values unescaped.
+fn disposition_params(value: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut chars = value.chars().peekable();
+
+    // Skip the disposition type.
+    for c in chars.by_ref() {
+        if c == ';' {
+            break;
+        }
+    }
+
+    loop {
+        let key: String = chars
+            .by_ref()
+            .take_while(|&c| c != '=')
+            .collect::<String>()
+            .trim()
+            .to_ascii_lowercase();
+
+        if key.is_empty() {
+            return params;
+        }
+
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut value = String::new();
+
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => value.extend(chars.next()),
+                    c => value.push(c),
+                }
+            }
+
+            for c in chars.by_ref() {
+                if c == ';' {
+                    break;
+                }
+            }
+        } else {
+            value = chars.by_ref().take_while(|&c| c != ';').collect();
+            value = value.trim().to_owned();
+        }
+
+        params.push((key, value));
+    }
+}
+
+/// Find the value of a text field in a multipart body.
+///
+/// Returns `None` if the body is malformed, or if it has no such text field: file parts and parts
+/// with another content type than `text/plain` are ignored.
+#[cfg(feature = "csrf")]
+pub(crate) fn find_text_field(
+    body: &axum::body::Bytes,
+    boundary: &str,
+    name: &str,
+) -> Option<String> {
+    parse_parts(body, boundary)
+        .ok()?
+        .into_iter()
+        .find(|part| {
+            part.name == name
+                && part.file_name.is_none()
+                && part.content_type.as_deref().is_none_or(|content_type| {
+                    content_type
+                        .split(';')
+                        .next()
+                        .is_some_and(|essence| essence.trim().eq_ignore_ascii_case("text/plain"))
+                })
+        })
+        .and_then(|part| String::from_utf8(part.content.to_vec()).ok())
+}
@@ -0,0 +1,139 @@
+//! Host matching, for routes scoped to a host.
+
+use std::sync::Arc;
+
+use crate::ServerInfo;
+
+/// The placeholder for the host of the server base URL, in host patterns.
+pub const BASE_HOST_PLACEHOLDER: &str = "{base}";
+
+/// Get the host a request was sent to, without its port.
+///
+/// The host is taken from the request URI for HTTP/2 requests, or from the `Host` header.
+pub fn request_host<B>(req: &http::Request<B>) -> Option<&str> {
+    if let Some(host) = req.uri().host() {
+        return Some(host);
+    }
+
+    let host = req.headers().get(http::header::HOST)?.to_str().ok()?;
+
+    Some(strip_port(host))
+}
+
+/// Check whether a host matches a host pattern.
+///
+/// Host patterns are host names, compared case-insensitively, in which `{base}` stands for the
+/// host of the server base URL (e.g. `admin.{base}`). Patterns using `{base}` never match if
+/// `base_host` is `None`.
+pub fn host_matches(pattern: &str, host: &str, base_host: Option<&str>) -> bool {
+    if pattern.contains(BASE_HOST_PLACEHOLDER) {
+        match base_host {
+            Some(base_host) => pattern
+                .replace(BASE_HOST_PLACEHOLDER, base_host)
+                .eq_ignore_ascii_case(host),
+            None => false,
+        }
+    } else {
+        pattern.eq_ignore_ascii_case(host)
+    }
+}
+
+/// Check whether a request was sent to a host matching the specified pattern.
+///
+/// The base host for `{base}` placeholders is taken from the [`ServerInfo`] in the request
+/// extensions, as set by the server.
+///
+/// Typically used by the code generated by the `Route` derive macro for `#[host]` variants.
+pub fn request_matches_host<B>(req: &http::Request<B>, pattern: &str) -> bool {
+    let Some(host) = request_host(req) else {
+        return false;
+    };
+
+    let base_host = req
+        .extensions()
+        .get::<Arc<ServerInfo>>()
+        .and_then(|server_info| server_info.base_url.host());
+
+    host_matches(pattern, host, base_host)
+}
+
+/// Format a URL with another host, keeping its scheme, port and path.
+pub(crate) fn with_host(url: &http::Uri, host: &str) -> String {
+    let scheme = url.scheme_str().unwrap_or("http");
+    let port = url
+        .port()
+        .map(|port| format!(":{port}"))
+        .unwrap_or_default();
+    let path = url.path_and_query().map_or("/", |path| path.as_str());
+
+    format!("{scheme}://{host}{port}{path}")
+}
+
+/// Strip the port from a `Host` header value.
+fn strip_port(host: &str) -> &str {
+    if host.starts_with('[') {
+        // IPv6 literal, like `[::1]:3000`.
+        match host.find(']') {
+            Some(idx) => &host[..=idx],
+            None => host,
+        }
+    } else {
+        host.split_once(':').map_or(host, |(host, _)| host)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_matches() {
+        assert!(host_matches(
+            "admin.{base}",
+            "admin.example.com",
+            Some("example.com")
+        ));
+        assert!(host_matches(
+            "admin.{base}",
+            "Admin.Example.com",
+            Some("example.com")
+        ));
+        assert!(!host_matches(
+            "admin.{base}",
+            "example.com",
+            Some("example.com")
+        ));
+        assert!(!host_matches("admin.{base}", "admin.example.com", None));
+        assert!(host_matches(
+            "static.example.com",
+            "static.example.com",
+            None
+        ));
+    }
+
+    #[test]
+    fn test_request_matches_host() {
+        let mut req = http::Request::builder()
+            .uri("/")
+            .header(http::header::HOST, "admin.localhost:3000")
+            .body(())
+            .unwrap();
+
+        assert_eq!(request_host(&req), Some("admin.localhost"));
+        assert!(!request_matches_host(&req, "admin.{base}"));
+
+        req.extensions_mut().insert(Arc::new(ServerInfo {
+            base_url: "http://localhost:3000".parse().unwrap(),
+        }));
+
+        assert!(request_matches_host(&req, "admin.{base}"));
+        assert!(!request_matches_host(&req, "api.{base}"));
+    }
+
+    #[test]
+    fn test_strip_port() {
+        assert_eq!(strip_port("example.com"), "example.com");
+        assert_eq!(strip_port("example.com:80"), "example.com");
+        assert_eq!(strip_port("[::1]:3000"), "[::1]");
+    }
+}
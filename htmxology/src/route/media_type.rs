@@ -0,0 +1,250 @@
+//! Media type parsing.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// A parsed media type, as found in `Content-Type` headers.
+///
+/// The type, subtype and parameter names are case-insensitive and normalized to lowercase.
+/// Parameter values are kept as-is, with quoted strings unescaped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaType {
+    type_: String,
+    subtype: String,
+    params: Vec<(String, String)>,
+}
+
+/// An error that occurs when trying to parse an invalid media type.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid media type: {0}")]
+pub struct InvalidMediaType(String);
+
+impl MediaType {
+    /// Get the media type from the `Content-Type` header of the specified headers.
+    ///
+    /// Returns `None` if the header is absent, not valid UTF-8 or not a valid media type.
+    pub fn from_content_type(headers: &http::HeaderMap) -> Option<Self> {
+        headers
+            .get(http::header::CONTENT_TYPE)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    /// The top-level type (e.g. `application`).
+    pub fn type_(&self) -> &str {
+        &self.type_
+    }
+
+    /// The subtype (e.g. `x-www-form-urlencoded`).
+    pub fn subtype(&self) -> &str {
+        &self.subtype
+    }
+
+    /// Get the value of a parameter, by case-insensitive name.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Get the `charset` parameter, if any.
+    pub fn charset(&self) -> Option<&str> {
+        self.param("charset")
+    }
+
+    /// Check whether the media type has the same type and subtype as another, ignoring any
+    /// parameters.
+    pub fn essence_eq(&self, other: &Self) -> bool {
+        self.type_ == other.type_ && self.subtype == other.subtype
+    }
+}
+
+impl FromStr for MediaType {
+    type Err = InvalidMediaType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidMediaType(s.to_owned());
+
+        let (essence, mut rest) = match s.split_once(';') {
+            Some((essence, rest)) => (essence, Some(rest)),
+            None => (s, None),
+        };
+
+        let (type_, subtype) = essence.trim().split_once('/').ok_or_else(invalid)?;
+
+        if !is_token(type_) || !is_token(subtype) {
+            return Err(invalid());
+        }
+
+        let mut params = Vec::new();
+
+        while let Some(input) = rest {
+            let input = input.trim_start();
+
+            if input.is_empty() {
+                break;
+            }
+
+            let (name, input) = input.split_once('=').ok_or_else(invalid)?;
+            let name = name.trim();
+
+            if !is_token(name) {
+                return Err(invalid());
+            }
+
+            let value = if let Some(input) = input.strip_prefix('"') {
+                let mut value = String::new();
+                let mut chars = input.char_indices();
+
+                loop {
+                    match chars.next() {
+                        Some((_, '\\')) => value.push(chars.next().ok_or_else(invalid)?.1),
+                        Some((idx, '"')) => {
+                            let input = input[idx + 1..].trim_start();
+
+                            rest = match input.strip_prefix(';') {
+                                Some(input) => Some(input),
+                                None if input.is_empty() => None,
+                                None => return Err(invalid()),
+                            };
+
+                            break;
+                        }
+                        Some((_, c)) => value.push(c),
+                        None => return Err(invalid()),
+                    }
+                }
+
+                value
+            } else {
+                let (value, input) = match input.split_once(';') {
+                    Some((value, input)) => (value, Some(input)),
+                    None => (input, None),
+                };
+                rest = input;
+                let value = value.trim();
+
+                if !is_token(value) {
+                    return Err(invalid());
+                }
+
+                value.to_owned()
+            };
+
+            params.push((name.to_ascii_lowercase(), value));
+        }
+
+        Ok(Self {
+            type_: type_.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            params,
+        })
+    }
+}
+
+impl Display for MediaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.type_, self.subtype)?;
+
+        for (name, value) in &self.params {
+            if is_token(value) {
+                write!(f, "; {name}={value}")?;
+            } else {
+                write!(
+                    f,
+                    "; {name}=\"{}\"",
+                    value.replace('\\', "\\\\").replace('"', "\\\"")
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Check whether a string is a valid RFC 7230 token.
+fn is_token(s: &str) -> bool {
+    !s.is_empty()
+        && s.bytes().all(|b| {
+            b.is_ascii_alphanumeric()
+                || matches!(
+                    b,
+                    b'!' | b'#'
+                        | b'$'
+                        | b'%'
+                        | b'&'
+                        | b'\''
+                        | b'*'
+                        | b'+'
+                        | b'-'
+                        | b'.'
+                        | b'^'
+                        | b'_'
+                        | b'`'
+                        | b'|'
+                        | b'~'
+                )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_simple() {
+        let media_type: MediaType = "application/x-www-form-urlencoded".parse().unwrap();
+        assert_eq!(media_type.type_(), "application");
+        assert_eq!(media_type.subtype(), "x-www-form-urlencoded");
+        assert_eq!(media_type.charset(), None);
+    }
+
+    #[test]
+    fn test_parse_case_and_params() {
+        let media_type: MediaType = "Application/X-WWW-Form-Urlencoded ; Charset=UTF-8"
+            .parse()
+            .unwrap();
+        assert_eq!(media_type.type_(), "application");
+        assert_eq!(media_type.subtype(), "x-www-form-urlencoded");
+        assert_eq!(media_type.charset(), Some("UTF-8"));
+        assert!(media_type.essence_eq(&"application/x-www-form-urlencoded".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_parse_quoted_params() {
+        let media_type: MediaType = r#"multipart/form-data; boundary="a \"b\"; c"; charset=latin1"#
+            .parse()
+            .unwrap();
+        assert_eq!(media_type.param("boundary"), Some(r#"a "b"; c"#));
+        assert_eq!(media_type.charset(), Some("latin1"));
+        assert_eq!(
+            media_type.to_string(),
+            r#"multipart/form-data; boundary="a \"b\"; c"; charset=latin1"#
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for input in [
+            "",
+            "text",
+            "text/",
+            "/html",
+            "text/html; charset",
+            "text/html; charset=\"utf-8",
+            "text/html; charset=\"utf-8\" x",
+            "te xt/html",
+        ] {
+            assert!(input.parse::<MediaType>().is_err(), "{input:?} should fail");
+        }
+    }
+
+    #[test]
+    fn test_parse_trailing_semicolon() {
+        let media_type: MediaType = "text/html;".parse().unwrap();
+        assert_eq!(media_type.to_string(), "text/html");
+    }
+}
@@ -1,5 +1,6 @@
 //! The route trait.
 
+use std::borrow::Cow;
 use std::fmt::Display;
 use std::str::FromStr;
 
@@ -7,10 +8,46 @@ use axum::response::IntoResponse;
 use de::PathArgumentDeserializer;
 use http::uri::PathAndQuery;
 
+#[cfg(feature = "derive")]
+mod body;
 mod de;
+mod describe;
+mod encoding;
 mod error;
+#[cfg(any(feature = "derive", feature = "csrf"))]
+mod form_data;
+mod host;
+mod media_type;
+#[cfg(feature = "derive")]
+mod multipart;
+mod pattern;
+mod prefix;
+mod rest;
+mod table;
 
-pub use error::ParseError;
+#[cfg(feature = "derive")]
+pub use body::{decode_form_body, decode_json_body, decode_negotiated_body};
+pub use describe::{ParameterLocation, RouteDescription, RouteParameter};
+pub use encoding::{
+    fmt_path_segment, format_fragment, is_valid_path_argument, parse_encoded_path_argument,
+    parse_path_argument,
+};
+pub use error::{ParseError, RouteSuggestion};
+#[cfg(feature = "csrf")]
+pub(crate) use form_data::find_text_field;
+pub use host::{BASE_HOST_PLACEHOLDER, host_matches, request_host, request_matches_host};
+pub use media_type::{InvalidMediaType, MediaType};
+#[cfg(feature = "derive")]
+pub use multipart::{MultipartForm, UploadedFile, decode_multipart_body};
+#[cfg(feature = "derive")]
+pub use pattern::{RouteMatch, RouteOrder, check_route_patterns};
+pub use pattern::{RoutePattern, RoutePatternConstraint, RoutePatternSegment};
+pub(crate) use prefix::without_path_prefix;
+pub use prefix::{
+    fmt_with_path_prefix, normalize_path_prefix, path_prefix, strip_path_prefix, with_path_prefix,
+};
+pub use rest::{RestPath, decode_rest_path_argument};
+pub use table::RouteEntry;
 
 /// The route trait can be implemented for types that represent a possible set of routes in an
 /// application.
@@ -24,17 +61,316 @@ pub trait Route: Display + FromStr<Err = ParseError> {
     /// Get the method for the route.
     fn method(&self) -> http::Method;
 
+    /// Check whether concurrent identical requests to the route can share a single handler
+    /// execution.
+    ///
+    /// This is only honored by controllers wrapped in a
+    /// [`CoalescingController`](crate::CoalescingController), and should only be enabled for
+    /// expensive idempotent routes. The `Route` derive macro enables it for `#[coalesce]`
+    /// variants.
+    fn coalesce_requests(&self) -> bool {
+        false
+    }
+
+    /// Serialize the JSON body of the route, if it has one.
+    ///
+    /// This is useful to send a route to a JSON endpoint from a test or from the server, or to
+    /// render it in `hx-vals` for the `json-enc` htmx extension. The `Route` derive macro
+    /// serializes the `#[body("application/json")]` fields with `serde_json`, which requires their
+    /// types to implement `serde::Serialize`. Form routes without a body return `None`.
+    ///
+    /// The default implementation returns `None`.
+    fn json_body(&self) -> Option<String> {
+        None
+    }
+
+    /// Get the id selector of the element the route is meant to be swapped into, like
+    /// `#food-list`, if any.
+    ///
+    /// Routes that render a specific fragment are declared with
+    /// `#[route("foods", target = "#food-list")]` with the `Route` derive macro. In debug builds,
+    /// htmx requests whose `HX-Target` doesn't match are reported by the
+    /// [sanity checks](crate::htmx::sanity), and controllers can check it explicitly with
+    /// [`htmx::Request::matches_target`](crate::htmx::Request::matches_target).
+    ///
+    /// The default implementation returns `None`.
+    fn expected_target(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Get the host pattern the route is scoped to, like `admin.{base}`, if any.
+    ///
+    /// Routes are scoped to a host with `#[host("admin.{base}")]` on a catch-all variant with the
+    /// `Route` derive macro. Their `Display` implementation only formats their path, so links to
+    /// them are only valid on their host: use [`Route::to_absolute_url`] to link to them from
+    /// another host.
+    ///
+    /// The default implementation returns `None`.
+    fn host(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Get the name of the variant of the route, like `Blog::Post` for a variant of a subroute.
+    ///
+    /// Names match the `variant` of the entries of the [route table](Route::route_table), and
+    /// identify routes regardless of their parameters, which is useful to aggregate metrics.
+    ///
+    /// The default implementation returns the name of the route type. The `Route` derive macro
+    /// generates a complete one.
+    fn variant_name(&self) -> Cow<'static, str> {
+        Cow::Borrowed(std::any::type_name::<Self>())
+    }
+
+    /// Get the path of the route, without its query string and fragment.
+    ///
+    /// This is useful to compare routes regardless of their query, for instance to highlight
+    /// the active links of a navigation menu, or to build breadcrumbs.
+    ///
+    /// The default implementation strips the query string and the fragment from the `Display`
+    /// implementation. The `Route` derive macro generates one formatting the path only.
+    fn path(&self) -> String {
+        let mut url = self.to_string();
+
+        if let Some(index) = url.find(['?', '#']) {
+            url.truncate(index);
+        }
+
+        url
+    }
+
+    /// Get the query string of the route, without the leading `?`, if it isn't empty.
+    ///
+    /// The default implementation extracts it from the `Display` implementation. The `Route`
+    /// derive macro generates one formatting the query only.
+    fn query(&self) -> Option<String> {
+        let url = self.to_string();
+        let url = url.split_once('#').map_or(url.as_str(), |(url, _)| url);
+
+        url.split_once('?')
+            .map(|(_, query)| query.to_owned())
+            .filter(|query| !query.is_empty())
+    }
+
+    /// Get the fragment of the route, without the leading `#`, if it isn't empty.
+    ///
+    /// Fragments link to in-page anchors: they are appended to the URL of the route, but as
+    /// browsers don't send them to servers, they are ignored when parsing routes. They are
+    /// declared with `#[route("docs/{page}#install")]` or with a `#[fragment_anchor]` field with
+    /// the `Route` derive macro.
+    ///
+    /// The default implementation extracts it from the `Display` implementation. The `Route`
+    /// derive macro generates one formatting the fragment only.
+    fn fragment(&self) -> Option<String> {
+        self.to_string()
+            .split_once('#')
+            .map(|(_, fragment)| fragment.to_owned())
+            .filter(|fragment| !fragment.is_empty())
+    }
+
+    /// Check whether two routes are the same, regardless of their query and body.
+    ///
+    /// This is useful to mark the link to the current route as active in navigation menus:
+    /// unlike comparing their URLs, it ignores the query parameters like the current page.
+    fn is_same_route(&self, other: &Self) -> bool {
+        self.method() == other.method() && self.path() == other.path()
+    }
+
+    /// Check whether the route is an ancestor of another route, that is whether its path is a
+    /// strict prefix of the other path, segment-wise.
+    ///
+    /// For instance, a `/blog/` route is an ancestor of the `/blog/posts/3` route of one of its
+    /// subroutes, but not of `/blogroll` or of itself. Methods and queries are ignored.
+    ///
+    /// This is useful to mark the links of sections as active in navigation menus:
+    ///
+    /// ```rust,ignore
+    /// let active = link.is_same_route(&current) || link.is_ancestor_of(&current);
+    /// ```
+    fn is_ancestor_of(&self, other: &Self) -> bool {
+        let path = self.path();
+        let other_path = other.path();
+        let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+        let mut other_segments = other_path.split('/').filter(|segment| !segment.is_empty());
+
+        segments.all(|segment| other_segments.next() == Some(segment))
+            && other_segments.next().is_some()
+    }
+
     /// Get a HTMX attribute for the route.
+    ///
+    /// HTMX can only issue `GET`, `POST`, `PUT`, `PATCH` and `DELETE` requests. For any other
+    /// method (like the WebDAV `PROPFIND` or `REPORT` verbs), the attribute falls back to a
+    /// `hx-post` request carrying the actual method in the `X-HTTP-Method-Override` header,
+    /// which the `Route` derive macro honors when parsing requests.
     fn as_htmx_attribute(&self) -> String {
-        format!(r#"hx-{}="{self}""#, self.method().as_str().to_lowercase())
+        let method = self.method();
+
+        if is_htmx_method(&method) {
+            format!(r#"hx-{}="{self}""#, method.as_str().to_lowercase())
+        } else {
+            format!(r#"hx-post="{self}" hx-headers='{{"{METHOD_OVERRIDE_HEADER}": "{method}"}}'"#)
+        }
+    }
+
+    /// Get the query parameters of the route as a JSON object, for a `hx-vals` attribute.
+    ///
+    /// This allows attaching the parameters of a route to an existing element, like a form
+    /// input, without building its full URL. Repeated parameters are collected in arrays, and
+    /// routes without a query give an empty object.
+    ///
+    /// The JSON must be escaped in the attribute, which templates do by default:
+    ///
+    /// ```html
+    /// <input name="q" hx-get="/search" hx-vals="{{ route.as_hx_vals() }}">
+    /// ```
+    fn as_hx_vals(&self) -> String {
+        let mut vals = serde_json::Map::new();
+
+        for pair in self.query().iter().flat_map(|query| query.split('&')) {
+            if pair.is_empty() {
+                continue;
+            }
+
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = serde_json::Value::String(decode_query_component(value));
+
+            match vals.entry(decode_query_component(name)) {
+                serde_json::map::Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+                serde_json::map::Entry::Occupied(mut entry) => match entry.get_mut() {
+                    serde_json::Value::Array(values) => values.push(value),
+                    previous => *previous = serde_json::Value::Array(vec![previous.take(), value]),
+                },
+            }
+        }
+
+        serde_json::Value::Object(vals).to_string()
+    }
+
+    /// Parse a route from a URI and a method, without a request body.
+    ///
+    /// This is useful to identify routes outside of request extraction, for instance in
+    /// middleware or analytics hooks. Routes that require a request body can't be parsed this
+    /// way.
+    ///
+    /// The default implementation only supports `GET` routes, through the `FromStr`
+    /// implementation. The `Route` derive macro generates an implementation supporting all
+    /// methods.
+    fn from_uri(uri: &http::Uri, method: &http::Method) -> Result<Self, ParseError> {
+        let url = uri
+            .path_and_query()
+            .map(PathAndQuery::as_str)
+            .unwrap_or("/");
+
+        if method == http::Method::GET {
+            url.parse()
+        } else {
+            Err(ParseError::MethodNotAllowed {
+                url: url.to_owned(),
+                method: method.to_string(),
+            })
+        }
+    }
+
+    /// Parse a route from the parts of a request, without its body.
+    ///
+    /// See [`Route::from_uri`] for details.
+    fn from_parts(parts: &http::request::Parts) -> Result<Self, ParseError> {
+        Self::from_uri(&parts.uri, &parts.method)
+    }
+
+    /// Get the table of all the routes of the route type.
+    ///
+    /// Entries are listed in declaration order, with the routes of subroutes and catch-all
+    /// variants flattened in place. This is useful to snapshot the routing configuration in tests.
+    ///
+    /// The default implementation returns an empty table. The `Route` derive macro generates a
+    /// complete one.
+    fn route_table() -> Vec<RouteEntry> {
+        Vec::new()
+    }
+
+    /// Get the URL patterns of the route type, as matched when parsing requests.
+    ///
+    /// Route types including this one with `#[route_include]` merge these patterns with their
+    /// own, so that its routes are matched as if they were declared in place.
+    ///
+    /// The default implementation returns no patterns, in which case including route types try
+    /// this one after their own routes. The `Route` derive macro generates all of them.
+    fn route_patterns() -> Vec<RoutePattern> {
+        Vec::new()
+    }
+
+    /// Describe all the routes of the route type, with their parameters and documentation.
+    ///
+    /// Descriptions are listed in the same order as the [route table](Route::route_table), and
+    /// are suitable to generate documentation or API clients, for instance as an OpenAPI document
+    /// with the `openapi` feature.
+    ///
+    /// The default implementation describes the entries of the route table, without parameters
+    /// nor documentation. The `Route` derive macro generates complete descriptions, with the doc
+    /// comments of the variants.
+    fn describe() -> Vec<RouteDescription> {
+        Self::route_table()
+            .into_iter()
+            .map(RouteDescription::from)
+            .collect()
     }
 
     /// Get an absolute URL for the route.
+    ///
+    /// The base URL includes the [path prefix](crate::ServerOptions::path_prefix) of the server,
+    /// if any, as the base URL of the [`ServerInfo`](crate::ServerInfo) does.
+    ///
+    /// Routes scoped to a [host](Route::host) get that host instead of the one of the base URL,
+    /// with `{base}` replaced by the latter.
     fn to_absolute_url(&self, base_url: &http::Uri) -> String {
-        format!("{}/{}", base_url, self)
+        let base_url = match (self.host(), base_url.host()) {
+            (Some(pattern), Some(base_host)) => {
+                host::with_host(base_url, &pattern.replace(BASE_HOST_PLACEHOLDER, base_host))
+            }
+            _ => base_url.to_string(),
+        };
+        let route = without_path_prefix(|| self.to_string());
+
+        format!("{}{route}", base_url.trim_end_matches('/'))
     }
 }
 
+/// The header used to tunnel HTTP methods that HTMX cannot issue natively.
+pub const METHOD_OVERRIDE_HEADER: &str = "X-HTTP-Method-Override";
+
+/// Check whether the specified method can be issued natively by HTMX.
+pub fn is_htmx_method(method: &http::Method) -> bool {
+    matches!(
+        *method,
+        http::Method::GET
+            | http::Method::POST
+            | http::Method::PUT
+            | http::Method::PATCH
+            | http::Method::DELETE
+    )
+}
+
+/// Check whether a request targets the specified method.
+///
+/// This matches the request method directly, or a `POST` request carrying the method in its
+/// `X-HTTP-Method-Override` header.
+///
+/// Typically used by the code generated by the `Route` derive macro for non-standard methods.
+pub fn request_has_method<B>(req: &http::Request<B>, method: &str) -> bool {
+    if req.method().as_str() == method {
+        return true;
+    }
+
+    req.method() == http::Method::POST
+        && req
+            .headers()
+            .get(METHOD_OVERRIDE_HEADER)
+            .is_some_and(|value| value.as_bytes() == method.as_bytes())
+}
+
 /// An extension trait for routes.
 pub trait RouteExt: Route {
     /// Turn the route into a redirect response.
@@ -45,6 +381,25 @@ pub trait RouteExt: Route {
             .body(axum::body::Body::empty())
             .expect("failed to create redirect response")
     }
+
+    /// Turn the route into a permanent redirect response, for a request with the specified
+    /// method.
+    ///
+    /// `GET` and `HEAD` requests are redirected with `301 Moved Permanently`, and other requests
+    /// with `308 Permanent Redirect`, which preserves their method and body.
+    fn as_permanent_redirect_response(&self, method: &http::Method) -> axum::response::Response {
+        let status = if method == http::Method::GET || method == http::Method::HEAD {
+            http::StatusCode::MOVED_PERMANENTLY
+        } else {
+            http::StatusCode::PERMANENT_REDIRECT
+        };
+
+        http::Response::builder()
+            .status(status)
+            .header(http::header::LOCATION, self.to_string())
+            .body(axum::body::Body::empty())
+            .expect("failed to create redirect response")
+    }
 }
 
 impl<T: Route> RouteExt for T {}
@@ -66,6 +421,29 @@ pub fn decode_path_argument<T: serde::de::DeserializeOwned>(
     Ok(value)
 }
 
+/// Decode a path argument into a value, without percent-decoding it.
+///
+/// Typically used for path parameters declared as already encoded with
+/// `#[param(name, encoded)]`.
+#[expect(clippy::result_large_err)]
+pub fn decode_encoded_path_argument<T: serde::de::DeserializeOwned>(
+    key: &'static str,
+    value: &str,
+) -> Result<T, axum::response::Response> {
+    T::deserialize(PathArgumentDeserializer::new_encoded(value)).map_err(|err| {
+        (
+            http::StatusCode::BAD_REQUEST,
+            format!("error while deserializing argument `{key}`: {err}"),
+        )
+            .into_response()
+    })
+}
+
+/// Parse a decoded form value into a value, returning the error message on failure.
+pub(crate) fn parse_form_value<T: serde::de::DeserializeOwned>(value: &str) -> Result<T, String> {
+    T::deserialize(PathArgumentDeserializer::new_encoded(value)).map_err(|err| err.to_string())
+}
+
 /// Replace the path in a request.
 pub fn replace_request_path<B>(req: http::Request<B>, path: String) -> http::Request<B> {
     let (mut parts, body) = req.into_parts();
@@ -85,6 +463,13 @@ pub fn replace_request_path<B>(req: http::Request<B>, path: String) -> http::Req
     http::Request::from_parts(parts, body)
 }
 
+/// Decode a component of a URL-encoded query string.
+fn decode_query_component(component: &str) -> String {
+    percent_encoding::percent_decode_str(&component.replace('+', " "))
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -116,5 +501,100 @@ mod tests {
 
         let route = TestRoute;
         assert_eq!(route.as_htmx_attribute(), r#"hx-get="/test/route""#);
+
+        let uri = http::Uri::from_static("/test/route");
+        assert!(TestRoute::from_uri(&uri, &http::Method::GET).is_ok());
+        assert_eq!(
+            TestRoute::from_uri(&uri, &http::Method::POST).unwrap_err(),
+            ParseError::MethodNotAllowed {
+                url: "/test/route".to_string(),
+                method: "POST".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_route_as_htmx_attribute_custom_method() {
+        #[derive(Debug, Clone, Copy)]
+        struct TestRoute;
+
+        impl Display for TestRoute {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "/calendar")
+            }
+        }
+
+        impl FromStr for TestRoute {
+            type Err = ParseError;
+
+            fn from_str(_s: &str) -> Result<Self, Self::Err> {
+                Ok(TestRoute)
+            }
+        }
+
+        impl Route for TestRoute {
+            fn method(&self) -> http::Method {
+                http::Method::from_bytes(b"REPORT").unwrap()
+            }
+        }
+
+        let route = TestRoute;
+        assert_eq!(
+            route.as_htmx_attribute(),
+            r#"hx-post="/calendar" hx-headers='{"X-HTTP-Method-Override": "REPORT"}'"#
+        );
+    }
+
+    #[test]
+    fn test_route_as_hx_vals_with_repeated_parameters() {
+        #[derive(Debug, Clone, Copy)]
+        struct TestRoute;
+
+        impl Display for TestRoute {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "/items?tag=a&tag=b+c&tag=%C3%A9&&flag")
+            }
+        }
+
+        impl FromStr for TestRoute {
+            type Err = ParseError;
+
+            fn from_str(_s: &str) -> Result<Self, Self::Err> {
+                Ok(TestRoute)
+            }
+        }
+
+        impl Route for TestRoute {
+            fn method(&self) -> http::Method {
+                http::Method::GET
+            }
+        }
+
+        assert_eq!(
+            TestRoute.as_hx_vals(),
+            r#"{"flag":"","tag":["a","b c","é"]}"#
+        );
+    }
+
+    #[test]
+    fn test_request_has_method() {
+        let req = http::Request::builder().method("REPORT").body(()).unwrap();
+        assert!(request_has_method(&req, "REPORT"));
+        assert!(!request_has_method(&req, "PROPFIND"));
+
+        let req = http::Request::builder()
+            .method(http::Method::POST)
+            .header(METHOD_OVERRIDE_HEADER, "REPORT")
+            .body(())
+            .unwrap();
+        assert!(request_has_method(&req, "REPORT"));
+        assert!(!request_has_method(&req, "PROPFIND"));
+
+        let req = http::Request::builder()
+            .method(http::Method::PUT)
+            .header(METHOD_OVERRIDE_HEADER, "REPORT")
+            .body(())
+            .unwrap();
+        assert!(!request_has_method(&req, "REPORT"));
     }
 }
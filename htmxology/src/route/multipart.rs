@@ -0,0 +1,240 @@
+//! Multipart request body decoding.
+
+use axum::extract::FromRequest;
+use axum::response::IntoResponse;
+
+use super::MediaType;
+use super::form_data::parse_parts;
+
+/// The media type of multipart bodies.
+const MULTIPART_FORM_DATA: &str = "multipart/form-data";
+
+/// A file uploaded in a `multipart/form-data` request body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadedFile {
+    /// The name of the form field of the file.
+    pub field_name: String,
+
+    /// The name of the file on the client, as sent by the browser.
+    ///
+    /// It must not be trusted as a path on the server.
+    pub file_name: String,
+
+    /// The content type of the file, if specified.
+    pub content_type: Option<String>,
+
+    /// The content of the file.
+    pub bytes: axum::body::Bytes,
+}
+
+/// A decoded `multipart/form-data` request body, with its text fields and its uploaded files.
+///
+/// Used as the type of `#[body("multipart/form-data")]` route fields:
+///
+/// ```rust,ignore
+/// #[derive(Route)]
+/// enum AppRoute {
+///     #[route("avatar", method = "POST")]
+///     UploadAvatar {
+///         #[body("multipart/form-data")]
+///         upload: MultipartForm<AvatarForm>,
+///     },
+/// }
+/// ```
+///
+/// The whole body is buffered in memory, within the limit of the
+/// [`DefaultBodyLimit`](axum::extract::DefaultBodyLimit) of the router.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultipartForm<T> {
+    /// The text fields of the form, deserialized as a form-encoded body would be.
+    pub fields: T,
+
+    /// The uploaded files, in body order.
+    pub files: Vec<UploadedFile>,
+}
+
+impl<T> MultipartForm<T> {
+    /// Get the first file uploaded for a form field, if any.
+    pub fn file(&self, field_name: &str) -> Option<&UploadedFile> {
+        self.files.iter().find(|file| file.field_name == field_name)
+    }
+}
+
+/// Decode a `multipart/form-data` request body.
+///
+/// Parts with a `filename` are collected as files, and the other parts are deserialized into the
+/// text fields. As for regular HTML forms, `GET` and `HEAD` requests are decoded from the query
+/// string instead, without files.
+///
+/// Typically used by the code generated by the `Route` derive macro for
+/// `#[body("multipart/form-data")]` fields.
+pub async fn decode_multipart_body<T, S>(
+    req: axum::extract::Request,
+    state: &S,
+) -> Result<MultipartForm<T>, axum::response::Response>
+where
+    T: serde::de::DeserializeOwned,
+    S: Send + Sync,
+{
+    if req.method() == http::Method::GET || req.method() == http::Method::HEAD {
+        let fields = super::decode_form_body(req, state, MULTIPART_FORM_DATA).await?;
+
+        return Ok(MultipartForm {
+            fields,
+            files: Vec::new(),
+        });
+    }
+
+    let expected: MediaType = MULTIPART_FORM_DATA
+        .parse()
+        .expect("multipart media type should be valid");
+
+    let boundary = match MediaType::from_content_type(req.headers()) {
+        Some(media_type) if media_type.essence_eq(&expected) => media_type
+            .param("boundary")
+            .filter(|boundary| !boundary.is_empty())
+            .map(str::to_owned)
+            .ok_or_else(|| {
+                (http::StatusCode::BAD_REQUEST, "Missing multipart boundary").into_response()
+            })?,
+        _ => {
+            return Err((
+                http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("Expected request with `Content-Type: {MULTIPART_FORM_DATA}`"),
+            )
+                .into_response());
+        }
+    };
+
+    let body = axum::body::Bytes::from_request(req, state)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let parts = parse_parts(&body, &boundary).map_err(|err| {
+        (
+            http::StatusCode::BAD_REQUEST,
+            format!("Malformed multipart body: {err}"),
+        )
+            .into_response()
+    })?;
+
+    let invalid = |err: &dyn std::fmt::Display| {
+        (
+            http::StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Failed to deserialize multipart body: {err}"),
+        )
+            .into_response()
+    };
+
+    let mut pairs = Vec::new();
+    let mut files = Vec::new();
+
+    for part in parts {
+        match part.file_name {
+            Some(file_name) => files.push(UploadedFile {
+                field_name: part.name,
+                file_name,
+                content_type: part.content_type,
+                bytes: part.content,
+            }),
+            None => {
+                let value =
+                    String::from_utf8(part.content.to_vec()).map_err(|err| invalid(&err))?;
+
+                pairs.push((part.name, value));
+            }
+        }
+    }
+
+    let form = serde_html_form::to_string(&pairs).map_err(|err| invalid(&err))?;
+    let fields = serde_html_form::from_str(&form).map_err(|err| invalid(&err))?;
+
+    Ok(MultipartForm { fields, files })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Form {
+        title: String,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+
+    const BODY: &str = "preamble\r\n--XyZ\r\n\
+        Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+        Hello; world\r\n--XyZ\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"a \\\"b\\\".txt\"\r\n\
+        Content-Type: text/plain\r\n\r\n\
+        line 1\r\nline 2\r\n--XyZ\r\n\
+        Content-Disposition: form-data; name=\"tags\"\r\n\r\n\
+        a\r\n--XyZ\r\n\
+        content-disposition: form-data; name=tags\r\n\r\n\
+        b\r\n--XyZ--\r\n";
+
+    fn make_request(content_type: &str, body: &'static str) -> axum::extract::Request {
+        http::Request::builder()
+            .method(http::Method::POST)
+            .header(http::header::CONTENT_TYPE, content_type)
+            .body(axum::body::Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_decode_multipart_body() {
+        let req = make_request("multipart/form-data; boundary=\"XyZ\"", BODY);
+        let form: MultipartForm<Form> = decode_multipart_body(req, &()).await.unwrap();
+
+        assert_eq!(
+            form.fields,
+            Form {
+                title: "Hello; world".to_owned(),
+                tags: vec!["a".to_owned(), "b".to_owned()],
+            }
+        );
+        assert_eq!(
+            form.files,
+            [UploadedFile {
+                field_name: "file".to_owned(),
+                file_name: "a \"b\".txt".to_owned(),
+                content_type: Some("text/plain".to_owned()),
+                bytes: "line 1\r\nline 2".into(),
+            }]
+        );
+        assert_eq!(form.file("file").unwrap().file_name, "a \"b\".txt");
+        assert!(form.file("other").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_decode_multipart_body_errors() {
+        let req = make_request("multipart/form-data", BODY);
+        let err = decode_multipart_body::<Form, _>(req, &())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), http::StatusCode::BAD_REQUEST);
+
+        let req = make_request("application/x-www-form-urlencoded", "title=a");
+        let err = decode_multipart_body::<Form, _>(req, &())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), http::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let req = make_request(
+            "multipart/form-data; boundary=XyZ",
+            "--XyZ\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nunterminated",
+        );
+        let err = decode_multipart_body::<Form, _>(req, &())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), http::StatusCode::BAD_REQUEST);
+
+        // Missing text fields.
+        let req = make_request("multipart/form-data; boundary=XyZ", "--XyZ--\r\n");
+        let err = decode_multipart_body::<Form, _>(req, &())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
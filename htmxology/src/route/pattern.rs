@@ -0,0 +1,408 @@
+//! Route patterns, to match the routes of included route types in place.
+
+/// A route URL pattern of a route type, as matched when parsing requests.
+///
+/// See [`Route::route_patterns`](crate::Route::route_patterns).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoutePattern {
+    /// The variant declaring the pattern, with its route type, like `BlogRoute::Post`.
+    pub variant: &'static str,
+
+    /// The URL pattern, like `/blog/{id}`.
+    pub pattern: &'static str,
+
+    /// The segments of the URL pattern, which order the patterns from the least to the most
+    /// specific.
+    pub segments: &'static [RoutePatternSegment],
+
+    /// The regex matching the paths of the pattern.
+    pub regex: &'static str,
+
+    /// Whether the pattern is a subroute prefix, tried before the other patterns when parsing
+    /// requests.
+    pub subroute: bool,
+}
+
+/// A segment of a [`RoutePattern`].
+///
+/// Segments are ordered like the route URLs of the `Route` derive macro, from the least to the
+/// most specific.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RoutePatternSegment {
+    /// A slash separator, which ends subroute prefixes.
+    Separator,
+
+    /// A rest path parameter, capturing the remaining path.
+    Rest(&'static str),
+
+    /// A path parameter, with its constraint, its name and whether it is already percent-encoded.
+    Parameter(Option<RoutePatternConstraint>, &'static str, bool),
+
+    /// A static segment.
+    Literal(&'static str),
+}
+
+/// A constraint on the values of a path parameter of a [`RoutePattern`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RoutePatternConstraint {
+    /// An integer type, optionally within inclusive bounds.
+    Integer(&'static str, Option<i128>, Option<i128>),
+
+    /// A regular expression the whole value must match.
+    Regex(&'static str),
+}
+
+/// The outcome of matching a path against a [`RouteOrder`].
+#[cfg(feature = "derive")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteMatch {
+    /// A pattern of the route type matched, by its index.
+    Local(usize),
+
+    /// A pattern of an included route type matched, by the index of the included route type.
+    Include(usize),
+}
+
+/// The order in which the patterns of a route type and of its included route types are tried.
+///
+/// The patterns of included route types are merged with the patterns of the route type, as if
+/// they were declared in place.
+///
+/// Typically used by the code generated by the `Route` derive macro.
+#[cfg(feature = "derive")]
+#[derive(Debug)]
+pub struct RouteOrder {
+    steps: Vec<RouteMatch>,
+    set: regex::RegexSet,
+}
+
+#[cfg(feature = "derive")]
+impl RouteOrder {
+    /// Order the patterns of a route type and of its included route types.
+    ///
+    /// Patterns are tried from the most specific to the least specific, after the subroute
+    /// prefixes if `subroutes_first` is set. Patterns of the route type are tried before
+    /// identical patterns of the included route types.
+    ///
+    /// # Panics
+    ///
+    /// If a pattern regex is invalid, the call will panic.
+    pub fn new(
+        local: &[RoutePattern],
+        includes: &[Vec<RoutePattern>],
+        subroutes_first: bool,
+    ) -> Self {
+        let mut patterns: Vec<_> = local
+            .iter()
+            .enumerate()
+            .map(|(index, pattern)| (pattern, RouteMatch::Local(index)))
+            .chain(includes.iter().enumerate().flat_map(|(index, patterns)| {
+                patterns
+                    .iter()
+                    .map(move |pattern| (pattern, RouteMatch::Include(index)))
+            }))
+            .collect();
+
+        patterns.sort_by(|(a, _), (b, _)| order(a, b, subroutes_first));
+
+        let set = regex::RegexSet::new(patterns.iter().map(|(pattern, _)| pattern.regex))
+            .expect("route pattern regexes should be valid");
+
+        Self {
+            steps: patterns.into_iter().map(|(_, step)| step).collect(),
+            set,
+        }
+    }
+
+    /// Get the patterns matching a path, in the order they must be tried.
+    ///
+    /// Each included route type is listed once, at the position of its first matching pattern.
+    pub fn matches(&self, path: &str) -> Vec<RouteMatch> {
+        let mut matches = Vec::new();
+
+        for index in self.set.matches(path) {
+            let step = self.steps[index];
+
+            if !matches.contains(&step) {
+                matches.push(step);
+            }
+        }
+
+        matches
+    }
+}
+
+/// Compare two patterns by the order they are tried in.
+#[cfg(feature = "derive")]
+fn order(a: &RoutePattern, b: &RoutePattern, subroutes_first: bool) -> std::cmp::Ordering {
+    let group = |pattern: &RoutePattern| subroutes_first && pattern.subroute;
+
+    group(b)
+        .cmp(&group(a))
+        .then_with(|| b.segments.cmp(a.segments))
+}
+
+/// Check that no route of an included route type is shadowed by another route of the route type
+/// or of another included route type, panicking with all the conflicts otherwise.
+///
+/// Routes declared in the same route type are checked at compile time by the `Route` derive
+/// macro, but the routes of the included route types are only known at runtime. The code
+/// generated by the macro runs this check when building the route table, in debug builds.
+///
+/// Typically used by the code generated by the `Route` derive macro.
+///
+/// # Panics
+///
+/// If a route is shadowed, the call will panic.
+#[cfg(feature = "derive")]
+pub fn check_route_patterns(local: &[RoutePattern], includes: &[Vec<RoutePattern>]) {
+    let patterns: Vec<_> = local
+        .iter()
+        .map(|pattern| (pattern, None))
+        .chain(includes.iter().enumerate().flat_map(|(index, patterns)| {
+            patterns.iter().map(move |pattern| (pattern, Some(index)))
+        }))
+        .collect();
+
+    let conflicts: Vec<_> = patterns
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (pattern, source))| {
+            let (_, (shadowing, _)) =
+                patterns
+                    .iter()
+                    .enumerate()
+                    .find(|(other_index, (other, other_source))| {
+                        // Routes from the same route type were checked at compile time, and
+                        // identical patterns are tried in declaration order.
+                        other_source != source
+                            && order(other, pattern, true)
+                                .then(other_index.cmp(&index))
+                                .is_lt()
+                            && covers(other.segments, pattern.segments)
+                    })?;
+
+            Some(format!(
+                "the route `{}` of `{}` is unreachable, as all its paths are matched first by the route `{}` of `{}`",
+                pattern.pattern, pattern.variant, shadowing.pattern, shadowing.variant,
+            ))
+        })
+        .collect();
+
+    assert!(
+        conflicts.is_empty(),
+        "conflicting included routes: {}",
+        conflicts.join("; ")
+    );
+}
+
+/// An element of the path regex of a route pattern, to compare the paths matched by patterns.
+#[cfg(feature = "derive")]
+#[derive(Debug, Clone, Copy)]
+enum PathAtom {
+    Slash,
+    Literal(&'static str),
+    Parameter(Option<RoutePatternConstraint>),
+    Rest,
+}
+
+/// Get the path atoms of the segments of a route pattern.
+#[cfg(feature = "derive")]
+fn path_atoms(segments: &[RoutePatternSegment]) -> Vec<PathAtom> {
+    let mut atoms = Vec::with_capacity(segments.len() + 1);
+
+    for (index, segment) in segments.iter().enumerate() {
+        match segment {
+            RoutePatternSegment::Separator if index + 1 == segments.len() => {
+                atoms.extend([PathAtom::Slash, PathAtom::Rest]);
+            }
+            RoutePatternSegment::Separator => atoms.push(PathAtom::Slash),
+            RoutePatternSegment::Literal(literal) => atoms.push(PathAtom::Literal(literal)),
+            RoutePatternSegment::Parameter(constraint, ..) => {
+                atoms.push(PathAtom::Parameter(*constraint));
+            }
+            RoutePatternSegment::Rest(_) => atoms.push(PathAtom::Rest),
+        }
+    }
+
+    atoms
+}
+
+/// Check whether a route pattern matches all the paths matched by another one.
+///
+/// The check is conservative, like the one of the `Route` derive macro: it can miss that a pattern
+/// matches all the paths of another, but never reports it wrongly.
+#[cfg(feature = "derive")]
+fn covers(segments: &[RoutePatternSegment], other: &[RoutePatternSegment]) -> bool {
+    let atoms = path_atoms(segments);
+    let other_atoms = path_atoms(other);
+    let mut atoms = atoms.iter();
+    let mut other_atoms = other_atoms.iter();
+
+    loop {
+        match (atoms.next(), other_atoms.next()) {
+            (None, None) | (Some(PathAtom::Rest), _) => return true,
+            (Some(atom), Some(other)) if atom_covers(atom, other) => {}
+            _ => return false,
+        }
+    }
+}
+
+/// Check whether a path atom matches all the strings matched by another one.
+#[cfg(feature = "derive")]
+fn atom_covers(atom: &PathAtom, other: &PathAtom) -> bool {
+    match (atom, other) {
+        (PathAtom::Rest, _) | (PathAtom::Slash, PathAtom::Slash) => true,
+        (PathAtom::Literal(literal), PathAtom::Literal(other)) => literal == other,
+        (PathAtom::Parameter(None), PathAtom::Literal(_)) => true,
+        (PathAtom::Parameter(Some(constraint)), PathAtom::Literal(value)) => {
+            constraint_matches(constraint, value)
+        }
+        // Regex constraints may match slashes, so only integers are known to be segments.
+        (
+            PathAtom::Parameter(None),
+            PathAtom::Parameter(None | Some(RoutePatternConstraint::Integer(..))),
+        ) => true,
+        (PathAtom::Parameter(Some(constraint)), PathAtom::Parameter(Some(other))) => {
+            constraint_covers(constraint, other)
+        }
+        _ => false,
+    }
+}
+
+/// Get the inclusive bounds of the values accepted by an integer constraint.
+#[cfg(feature = "derive")]
+fn integer_range(constraint: &RoutePatternConstraint) -> Option<(i128, i128)> {
+    let RoutePatternConstraint::Integer(ty, min, max) = constraint else {
+        return None;
+    };
+
+    let (type_min, type_max): (i128, i128) = match *ty {
+        "u8" => (0, u8::MAX.into()),
+        "u16" => (0, u16::MAX.into()),
+        "u32" => (0, u32::MAX.into()),
+        "u64" | "usize" => (0, u64::MAX.into()),
+        "i8" => (i8::MIN.into(), i8::MAX.into()),
+        "i16" => (i16::MIN.into(), i16::MAX.into()),
+        "i32" => (i32::MIN.into(), i32::MAX.into()),
+        "i64" | "isize" => (i64::MIN.into(), i64::MAX.into()),
+        _ => return None,
+    };
+
+    Some((min.unwrap_or(type_min), max.unwrap_or(type_max)))
+}
+
+/// Check whether a path segment value satisfies a constraint.
+#[cfg(feature = "derive")]
+fn constraint_matches(constraint: &RoutePatternConstraint, value: &str) -> bool {
+    match constraint {
+        RoutePatternConstraint::Integer(ty, ..) => {
+            let digits = match value.strip_prefix('-') {
+                Some(digits) if !ty.starts_with('u') => digits,
+                _ => value,
+            };
+
+            !digits.is_empty()
+                && digits.bytes().all(|b| b.is_ascii_digit())
+                && integer_range(constraint).is_some_and(|(min, max)| {
+                    value
+                        .parse::<i128>()
+                        .is_ok_and(|value| (min..=max).contains(&value))
+                })
+        }
+        RoutePatternConstraint::Regex(regex) => {
+            regex::Regex::new(&format!("^(?:{regex})$")).is_ok_and(|regex| regex.is_match(value))
+        }
+    }
+}
+
+/// Check whether a constraint accepts all the values accepted by another one.
+#[cfg(feature = "derive")]
+fn constraint_covers(constraint: &RoutePatternConstraint, other: &RoutePatternConstraint) -> bool {
+    match (constraint, other) {
+        // Signed integers also match `-0`, which unsigned integers don't.
+        (
+            RoutePatternConstraint::Integer(ty, ..),
+            RoutePatternConstraint::Integer(other_ty, ..),
+        ) if ty.starts_with('u') && !other_ty.starts_with('u') => false,
+        (RoutePatternConstraint::Integer(..), RoutePatternConstraint::Integer(..)) => {
+            match (integer_range(constraint), integer_range(other)) {
+                (Some((min, max)), Some((other_min, other_max))) => {
+                    min <= other_min && other_max <= max
+                }
+                _ => false,
+            }
+        }
+        _ => constraint == other,
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+
+    fn pattern(
+        pattern: &'static str,
+        segments: &'static [RoutePatternSegment],
+        regex: &'static str,
+    ) -> RoutePattern {
+        RoutePattern {
+            variant: pattern,
+            pattern,
+            segments,
+            regex,
+            subroute: false,
+        }
+    }
+
+    const SLUG: RoutePattern = RoutePattern {
+        variant: "Page",
+        pattern: "/{slug}",
+        segments: &[RoutePatternSegment::Parameter(None, "slug", false)],
+        regex: "^/(?P<slug>[^/]+)$",
+        subroute: false,
+    };
+
+    #[test]
+    fn test_order() {
+        let about = pattern(
+            "/about",
+            &[RoutePatternSegment::Literal("about")],
+            "^/about$",
+        );
+        let order = RouteOrder::new(&[SLUG], &[vec![about]], true);
+
+        assert_eq!(
+            order.matches("/about"),
+            [RouteMatch::Include(0), RouteMatch::Local(0)]
+        );
+        assert_eq!(order.matches("/contact"), [RouteMatch::Local(0)]);
+        assert_eq!(order.matches("/a/b"), []);
+    }
+
+    #[test]
+    fn test_check_route_patterns() {
+        let about = pattern(
+            "/about",
+            &[RoutePatternSegment::Literal("about")],
+            "^/about$",
+        );
+        check_route_patterns(&[SLUG], &[vec![about]]);
+
+        let page = RoutePattern {
+            variant: "Blog::Page",
+            ..SLUG
+        };
+        let result = std::panic::catch_unwind(|| check_route_patterns(&[SLUG], &[vec![page]]));
+
+        assert_eq!(
+            result
+                .unwrap_err()
+                .downcast_ref::<String>()
+                .map(String::as_str),
+            Some(
+                "conflicting included routes: the route `/{slug}` of `Blog::Page` is unreachable, as all its paths are matched first by the route `/{slug}` of `Page`"
+            )
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Path prefixes, for applications mounted under a sub-path.
+
+use std::{borrow::Cow, cell::Cell, future::Future, sync::Arc};
+
+tokio::task_local! {
+    /// The path prefix of the request being served.
+    static PATH_PREFIX: Arc<str>;
+}
+
+thread_local! {
+    /// Whether a route is being formatted, in which case nested routes don't get the prefix.
+    static FORMATTING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Normalize a path prefix, like `myapp/` into `/myapp`.
+///
+/// Returns `None` for empty prefixes, like `/`.
+pub fn normalize_path_prefix(prefix: &str) -> Option<String> {
+    let prefix = prefix.trim().trim_matches('/');
+
+    (!prefix.is_empty()).then(|| format!("/{prefix}"))
+}
+
+/// Get the path prefix of the request being served, if any.
+///
+/// Set by the server for the duration of each request when
+/// [`ServerOptions::path_prefix`](crate::ServerOptions::path_prefix) is set, and by
+/// [`with_path_prefix`]. Tasks spawned by the requests don't inherit it.
+pub fn path_prefix() -> Option<Arc<str>> {
+    PATH_PREFIX.try_with(Arc::clone).ok()
+}
+
+/// Run a future with a path prefix, so that the routes formatted by the future include it.
+///
+/// The prefix must be normalized, with [`normalize_path_prefix`].
+pub async fn with_path_prefix<F: Future>(prefix: Arc<str>, future: F) -> F::Output {
+    PATH_PREFIX.scope(prefix, future).await
+}
+
+/// Strip the path prefix of the request being served from a URL, if it starts with it.
+pub fn strip_path_prefix(url: &str) -> Cow<'_, str> {
+    let Some(prefix) = path_prefix() else {
+        return Cow::Borrowed(url);
+    };
+
+    match url.strip_prefix(&*prefix) {
+        Some(rest) if rest.starts_with('/') => Cow::Borrowed(rest),
+        Some(rest) if rest.is_empty() || rest.starts_with(['?', '#']) => {
+            Cow::Owned(format!("/{rest}"))
+        }
+        _ => Cow::Borrowed(url),
+    }
+}
+
+/// Format a route, prefixed with the path prefix of the request being served, if any.
+///
+/// Only the outermost route is prefixed, so that subroutes formatted by their parent are not.
+///
+/// Typically used by the `Display` implementations generated by the `Route` derive macro.
+#[doc(hidden)]
+pub fn fmt_with_path_prefix(
+    f: &mut std::fmt::Formatter<'_>,
+    fmt: impl FnOnce(&mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+) -> std::fmt::Result {
+    if FORMATTING.get() {
+        return fmt(f);
+    }
+
+    if let Some(prefix) = path_prefix() {
+        f.write_str(&prefix)?;
+    }
+
+    let _guard = FormattingGuard::enter();
+
+    fmt(f)
+}
+
+/// Format a value without the path prefix of the request being served.
+pub(crate) fn without_path_prefix<T>(f: impl FnOnce() -> T) -> T {
+    let _guard = FormattingGuard::enter();
+
+    f()
+}
+
+/// Marks a route as being formatted, until dropped.
+struct FormattingGuard {
+    /// Whether a route was already being formatted.
+    previous: bool,
+}
+
+impl FormattingGuard {
+    fn enter() -> Self {
+        Self {
+            previous: FORMATTING.replace(true),
+        }
+    }
+}
+
+impl Drop for FormattingGuard {
+    fn drop(&mut self) {
+        FORMATTING.set(self.previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_path_prefix() {
+        assert_eq!(normalize_path_prefix("myapp"), Some("/myapp".to_owned()));
+        assert_eq!(normalize_path_prefix("/myapp/"), Some("/myapp".to_owned()));
+        assert_eq!(normalize_path_prefix("/a/b/"), Some("/a/b".to_owned()));
+        assert_eq!(normalize_path_prefix("/"), None);
+        assert_eq!(normalize_path_prefix(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_strip_path_prefix() {
+        assert_eq!(strip_path_prefix("/myapp/posts"), "/myapp/posts");
+
+        with_path_prefix("/myapp".into(), async {
+            assert_eq!(strip_path_prefix("/myapp/posts"), "/posts");
+            assert_eq!(strip_path_prefix("/myapp"), "/");
+            assert_eq!(strip_path_prefix("/myapp?page=2"), "/?page=2");
+            assert_eq!(strip_path_prefix("/myapps"), "/myapps");
+            assert_eq!(strip_path_prefix("/posts"), "/posts");
+        })
+        .await;
+    }
+}
@@ -0,0 +1,101 @@
+//! Rest path parameters, capturing the remaining path of a route.
+
+use axum::response::IntoResponse;
+
+use super::encoding::{PATH_SEGMENT, REST_PATH, write_encoded};
+
+/// A type that can hold the value of a `{*rest}` path parameter.
+///
+/// Rest path parameters capture the remaining path of a route, slashes included, like in
+/// `#[route("files/{*path}")]`. The path can be captured as a single `String`, or as a
+/// `Vec<String>` of its non-empty segments.
+pub trait RestPath: Sized {
+    /// Parse the value from the remaining path, still percent-encoded.
+    fn from_rest_path(path: &str) -> Result<Self, std::str::Utf8Error>;
+
+    /// Format the value as a percent-encoded path.
+    fn fmt_rest_path(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result;
+}
+
+impl RestPath for String {
+    fn from_rest_path(path: &str) -> Result<Self, std::str::Utf8Error> {
+        percent_encoding::percent_decode_str(path)
+            .decode_utf8()
+            .map(Into::into)
+    }
+
+    fn fmt_rest_path(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_encoded(f, self, REST_PATH)
+    }
+}
+
+impl RestPath for Vec<String> {
+    fn from_rest_path(path: &str) -> Result<Self, std::str::Utf8Error> {
+        path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(String::from_rest_path)
+            .collect()
+    }
+
+    fn fmt_rest_path(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str("/")?;
+            }
+
+            write_encoded(f, segment, PATH_SEGMENT)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Decode a rest path argument into a value.
+///
+/// Typically used by the code generated by the `Route` derive macro for `{*rest}` path
+/// parameters.
+#[expect(clippy::result_large_err)]
+pub fn decode_rest_path_argument<T: RestPath>(
+    key: &'static str,
+    value: &str,
+) -> Result<T, axum::response::Response> {
+    T::from_rest_path(value).map_err(|err| {
+        (
+            http::StatusCode::BAD_REQUEST,
+            format!("error while decoding argument `{key}`: {err}"),
+        )
+            .into_response()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Display;
+
+    use super::*;
+
+    struct Formatted<T>(T);
+
+    impl<T: RestPath> Display for Formatted<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            self.0.fmt_rest_path(f)
+        }
+    }
+
+    #[test]
+    fn test_string() {
+        let path = String::from_rest_path("docs/my%20file.txt").unwrap();
+
+        assert_eq!(path, "docs/my file.txt");
+        assert_eq!(Formatted(path).to_string(), "docs/my%20file.txt");
+    }
+
+    #[test]
+    fn test_segments() {
+        let path = Vec::<String>::from_rest_path("docs//a%2Fb/c/").unwrap();
+
+        assert_eq!(path, vec!["docs", "a/b", "c"]);
+        assert_eq!(Formatted(path).to_string(), "docs/a%2Fb/c");
+        assert!(Vec::<String>::from_rest_path("").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+//! Route tables, describing all the routes of a route type.
+
+/// An entry in the route table of a route type.
+///
+/// See [`Route::route_table`](crate::Route::route_table).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct RouteEntry {
+    /// The path of the variant, like `Blog::Post` for a variant of a subroute.
+    pub variant: String,
+
+    /// The HTTP method of the route.
+    pub method: String,
+
+    /// The URL pattern of the route, like `/blog/{id}`.
+    pub pattern: String,
+
+    /// The host pattern the route is scoped to, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+}
+
+impl RouteEntry {
+    /// Create a new route entry.
+    pub fn new(
+        variant: impl Into<String>,
+        method: impl Into<String>,
+        pattern: impl Into<String>,
+    ) -> Self {
+        Self {
+            variant: variant.into(),
+            method: method.into(),
+            pattern: pattern.into(),
+            host: None,
+        }
+    }
+
+    /// Nest the entries of a subroute or catch-all route table under a variant.
+    ///
+    /// The variant name is prepended to the variant path of each entry, and the URL prefix to its
+    /// pattern. The host pattern, if specified, applies to entries that are not scoped yet.
+    ///
+    /// Typically used by the code generated by the `Route` derive macro.
+    pub fn nested(
+        entries: Vec<Self>,
+        variant: &str,
+        prefix: &str,
+        host: Option<&str>,
+    ) -> Vec<Self> {
+        let prefix = prefix.trim_end_matches('/');
+
+        entries
+            .into_iter()
+            .map(|entry| Self {
+                variant: format!("{variant}::{}", entry.variant),
+                method: entry.method,
+                pattern: format!("{prefix}{}", entry.pattern),
+                host: entry.host.or_else(|| host.map(ToOwned::to_owned)),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested() {
+        let entries = vec![
+            RouteEntry::new("Home", "GET", "/"),
+            RouteEntry::new("Post", "POST", "/posts/{id}"),
+        ];
+
+        assert_eq!(
+            RouteEntry::nested(entries, "Blog", "/blog", Some("blog.{base}")),
+            vec![
+                RouteEntry {
+                    variant: "Blog::Home".to_owned(),
+                    method: "GET".to_owned(),
+                    pattern: "/blog/".to_owned(),
+                    host: Some("blog.{base}".to_owned()),
+                },
+                RouteEntry {
+                    variant: "Blog::Post".to_owned(),
+                    method: "POST".to_owned(),
+                    pattern: "/blog/posts/{id}".to_owned(),
+                    host: Some("blog.{base}".to_owned()),
+                },
+            ]
+        );
+    }
+}
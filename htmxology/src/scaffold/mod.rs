@@ -0,0 +1,379 @@
+//! Generation of starter projects.
+//!
+//! A [`Scaffold`] generates a new application wiring the subsystems of the crate together: a
+//! controller tree with its route enums, `askama` templates sharing a layout, and optionally
+//! sessions, a login flow built on them, and static assets:
+//!
+//! ```rust,ignore
+//! Scaffold::new("my-app")?
+//!     .with_auth(false)
+//!     .write_to("my-app")?;
+//! ```
+//!
+//! The same generator is available on the command line through the `htmxology-scaffold` binary.
+//!
+//! The templates of the generated files live in this crate, so that they are updated along with
+//! its API.
+
+use std::path::{Path, PathBuf};
+
+/// The templates of the generated files.
+const TEMPLATES: &[Template] = &[
+    Template {
+        path: "Cargo.toml",
+        source: include_str!("template/Cargo.toml.in"),
+        condition: None,
+    },
+    Template {
+        path: ".gitignore",
+        source: include_str!("template/gitignore"),
+        condition: None,
+    },
+    Template {
+        path: "README.md",
+        source: include_str!("template/README.md"),
+        condition: None,
+    },
+    Template {
+        path: "src/main.rs",
+        source: include_str!("template/src/main.rs"),
+        condition: None,
+    },
+    Template {
+        path: "src/controller/mod.rs",
+        source: include_str!("template/src/controller/mod.rs"),
+        condition: None,
+    },
+    Template {
+        path: "src/controller/pages.rs",
+        source: include_str!("template/src/controller/pages.rs"),
+        condition: None,
+    },
+    Template {
+        path: "src/controller/account.rs",
+        source: include_str!("template/src/controller/account.rs"),
+        condition: Some(Condition::Auth),
+    },
+    Template {
+        path: "src/views.rs",
+        source: include_str!("template/src/views.rs"),
+        condition: None,
+    },
+    Template {
+        path: "templates/layout.html.jinja",
+        source: include_str!("template/templates/layout.html.jinja"),
+        condition: None,
+    },
+    Template {
+        path: "templates/home.html.jinja",
+        source: include_str!("template/templates/home.html.jinja"),
+        condition: None,
+    },
+    Template {
+        path: "templates/login.html.jinja",
+        source: include_str!("template/templates/login.html.jinja"),
+        condition: Some(Condition::Auth),
+    },
+    Template {
+        path: "static/app.css",
+        source: include_str!("template/static/app.css"),
+        condition: Some(Condition::StaticAssets),
+    },
+];
+
+/// An error that can occur when generating a project.
+#[derive(Debug, thiserror::Error)]
+pub enum ScaffoldError {
+    /// The project name is not a valid package name.
+    #[error(
+        "invalid project name `{0}`: expected ASCII letters, digits, `-` or `_`, starting with a letter"
+    )]
+    InvalidName(String),
+
+    /// A generated file already exists.
+    #[error("`{}` already exists", .0.display())]
+    AlreadyExists(PathBuf),
+
+    /// A generated file could not be written.
+    #[error("failed to write the project: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A generated file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScaffoldFile {
+    /// The path of the file, relative to the project directory.
+    pub path: PathBuf,
+
+    /// The contents of the file.
+    pub contents: String,
+}
+
+/// A starter project generator.
+///
+/// All the optional subsystems are enabled by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scaffold {
+    /// The name of the project, used as its package name.
+    name: String,
+
+    /// Whether to enable sessions.
+    sessions: bool,
+
+    /// Whether to generate a login flow.
+    auth: bool,
+
+    /// Whether to serve static assets.
+    static_assets: bool,
+
+    /// The path of the `htmxology` crate, instead of its published version.
+    htmxology_path: Option<PathBuf>,
+}
+
+/// A condition on the options of a [`Scaffold`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Condition {
+    Sessions,
+    Auth,
+    StaticAssets,
+}
+
+/// The template of a generated file.
+#[derive(Debug)]
+struct Template {
+    /// The path of the generated file.
+    path: &'static str,
+
+    /// The source of the template.
+    source: &'static str,
+
+    /// The condition for the file to be generated.
+    condition: Option<Condition>,
+}
+
+impl Scaffold {
+    /// Create a new generator for a project.
+    ///
+    /// The name must be a valid package name.
+    pub fn new(name: impl Into<String>) -> Result<Self, ScaffoldError> {
+        let name = name.into();
+
+        let is_valid = name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+        if !is_valid {
+            return Err(ScaffoldError::InvalidName(name));
+        }
+
+        Ok(Self {
+            name,
+            sessions: true,
+            auth: true,
+            static_assets: true,
+            htmxology_path: None,
+        })
+    }
+
+    /// Get the name of the project.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Set whether to enable sessions, stored in memory.
+    ///
+    /// The login flow requires sessions, and enables them regardless.
+    pub fn with_sessions(mut self, sessions: bool) -> Self {
+        self.sessions = sessions;
+        self
+    }
+
+    /// Set whether to generate a login flow, storing the logged in user in the session.
+    pub fn with_auth(mut self, auth: bool) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Set whether to serve the static assets of the `static` directory under `/static`.
+    pub fn with_static_assets(mut self, static_assets: bool) -> Self {
+        self.static_assets = static_assets;
+        self
+    }
+
+    /// Depend on the `htmxology` crate at a path, instead of its published version.
+    ///
+    /// Useful to try the generated project against a local checkout.
+    pub fn with_htmxology_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.htmxology_path = Some(path.into());
+        self
+    }
+
+    /// Check whether a condition holds.
+    fn holds(&self, condition: Condition) -> bool {
+        match condition {
+            Condition::Sessions => self.sessions || self.auth,
+            Condition::Auth => self.auth,
+            Condition::StaticAssets => self.static_assets,
+        }
+    }
+
+    /// Generate the files of the project.
+    pub fn files(&self) -> Vec<ScaffoldFile> {
+        TEMPLATES
+            .iter()
+            .filter(|template| template.condition.is_none_or(|c| self.holds(c)))
+            .map(|template| ScaffoldFile {
+                path: PathBuf::from(template.path),
+                contents: self.render(template.source),
+            })
+            .collect()
+    }
+
+    /// Write the files of the project to a directory, creating it if needed.
+    ///
+    /// Fails without writing anything if any of the files already exists.
+    pub fn write_to(&self, dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, ScaffoldError> {
+        let dir = dir.as_ref();
+        let files = self.files();
+
+        if let Some(path) = files
+            .iter()
+            .map(|file| dir.join(&file.path))
+            .find(|path| path.exists())
+        {
+            return Err(ScaffoldError::AlreadyExists(path));
+        }
+
+        files
+            .into_iter()
+            .map(|file| {
+                let path = dir.join(file.path);
+
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+
+                std::fs::write(&path, file.contents)?;
+
+                Ok(path)
+            })
+            .collect()
+    }
+
+    /// Get the `htmxology` dependency line of the generated manifest.
+    fn htmxology_dependency(&self) -> String {
+        let mut features = vec!["auto-reload", "derive", "templating"];
+
+        if self.holds(Condition::Sessions) {
+            features.push("session");
+        }
+
+        let features = features
+            .iter()
+            .map(|feature| format!("{feature:?}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        match &self.htmxology_path {
+            Some(path) => format!(
+                "htmxology = {{ path = {:?}, features = [{features}] }}",
+                path.display().to_string()
+            ),
+            None => format!(
+                "htmxology = {{ version = \"{}\", features = [{features}] }}",
+                env!("CARGO_PKG_VERSION")
+            ),
+        }
+    }
+
+    /// Render a template.
+    ///
+    /// Templates substitute `${name}` and `${htmxology}`, and keep the lines between `%if
+    /// <condition>` and `%endif` lines only if the condition holds. Conditions can be negated with
+    /// `!`, and nested.
+    fn render(&self, source: &str) -> String {
+        let mut result = String::with_capacity(source.len());
+        let mut conditions = Vec::new();
+
+        for line in source.lines() {
+            let trimmed = line.trim();
+
+            if let Some(condition) = trimmed.strip_prefix("%if ") {
+                let (negated, condition) = match condition.strip_prefix('!') {
+                    Some(condition) => (true, condition),
+                    None => (false, condition),
+                };
+                let condition = match condition {
+                    "sessions" => Condition::Sessions,
+                    "auth" => Condition::Auth,
+                    "static_assets" => Condition::StaticAssets,
+                    condition => panic!("unknown scaffold template condition `{condition}`"),
+                };
+
+                conditions.push(self.holds(condition) != negated);
+            } else if trimmed == "%endif" {
+                conditions
+                    .pop()
+                    .expect("scaffold template `%endif` should match an `%if`");
+            } else if conditions.iter().all(|holds| *holds) {
+                result.push_str(
+                    &line
+                        .replace("${name}", &self.name)
+                        .replace("${htmxology}", &self.htmxology_dependency()),
+                );
+                result.push('\n');
+            }
+        }
+
+        assert!(
+            conditions.is_empty(),
+            "scaffold template `%if` should be closed by an `%endif`"
+        );
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_names() {
+        for name in ["", "1app", "my app", "app/..", "-app"] {
+            assert!(
+                matches!(Scaffold::new(name), Err(ScaffoldError::InvalidName(_))),
+                "{name}"
+            );
+        }
+
+        assert_eq!(Scaffold::new("my_app-2").unwrap().name(), "my_app-2");
+    }
+
+    #[test]
+    fn test_render() {
+        let source = "a ${name}\n%if auth\nb\n%if !static_assets\nc\n%endif\n%endif\nd\n";
+        let scaffold = Scaffold::new("app").unwrap();
+
+        assert_eq!(scaffold.render(source), "a app\nb\nd\n");
+        assert_eq!(
+            scaffold.clone().with_static_assets(false).render(source),
+            "a app\nb\nc\nd\n"
+        );
+        assert_eq!(scaffold.with_auth(false).render(source), "a app\nd\n");
+    }
+
+    #[test]
+    fn test_auth_enables_sessions() {
+        let scaffold = Scaffold::new("app").unwrap().with_sessions(false);
+
+        assert!(scaffold.htmxology_dependency().contains("\"session\""));
+        assert!(
+            !scaffold
+                .with_auth(false)
+                .htmxology_dependency()
+                .contains("\"session\"")
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! The login flow, storing the logged in user in the session.
+
+use axum::response::IntoResponse;
+use htmxology::{Controller, RenderIntoResponse, Route, ServerInfo, session::Session};
+use serde::{Deserialize, Serialize};
+
+use super::{AppController, AppRoute, PagesRoute, Response};
+use crate::views;
+
+/// The session key of the logged in user.
+pub const USER_KEY: &str = "user";
+
+/// The account routes.
+#[derive(Debug, Clone, Route)]
+pub enum AccountRoute {
+    /// The login page.
+    #[route("login")]
+    Login,
+
+    /// Log in.
+    #[route("login", method = "POST")]
+    SubmitLogin {
+        #[body]
+        form: LoginForm,
+    },
+
+    /// Log out.
+    #[route("logout", method = "POST")]
+    Logout,
+}
+
+/// The login form.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct LoginForm {
+    /// The name of the user.
+    pub username: String,
+
+    /// The password of the user.
+    pub password: String,
+}
+
+impl LoginForm {
+    /// Check the credentials of the form.
+    ///
+    /// Replace this with a lookup of the users of the application.
+    fn authenticate(&self) -> bool {
+        !self.username.is_empty() && self.password == "htmxology"
+    }
+}
+
+/// The controller of the login flow.
+#[derive(Debug, Clone)]
+pub struct AccountController;
+
+impl From<&AppController> for AccountController {
+    fn from(_: &AppController) -> Self {
+        Self
+    }
+}
+
+impl Controller for AccountController {
+    type Route = AccountRoute;
+    type Args = ();
+    type Response = Response;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        _htmx: htmxology::htmx::Request,
+        parts: http::request::Parts,
+        _server_info: &ServerInfo,
+        _args: Self::Args,
+    ) -> Self::Response {
+        let session =
+            Session::from_parts(&parts).expect("sessions should be enabled by the server");
+        let home_url = AppRoute::Pages(PagesRoute::Home).to_string();
+
+        match route {
+            AccountRoute::Login => Ok(views::Login {
+                nav: super::nav(&parts),
+                submit_url: AppRoute::Account(AccountRoute::Login),
+                error: None,
+            }
+            .render_into_response()),
+            AccountRoute::SubmitLogin { form } => {
+                if !form.authenticate() {
+                    return Ok(views::Login {
+                        nav: super::nav(&parts),
+                        submit_url: AppRoute::Account(AccountRoute::Login),
+                        error: Some("Invalid username or password.".to_owned()),
+                    }
+                    .render_into_response());
+                }
+
+                // Give the session a new identifier as the privileges of the user change.
+                session.renew();
+                session.insert(USER_KEY, &form.username).map_err(|err| {
+                    tracing::error!("Failed to save the session: {err}");
+
+                    http::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                })?;
+
+                Ok(axum::response::Redirect::to(&home_url).into_response())
+            }
+            AccountRoute::Logout => {
+                session.destroy();
+
+                Ok(axum::response::Redirect::to(&home_url).into_response())
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! The controllers of the application.
+//!
+//! The `AppController` routes the requests to its subcontrollers, each handling its own route
+//! enum.
+
+%if auth
+mod account;
+%endif
+mod pages;
+
+use htmxology::RoutingController;
+
+%if auth
+pub use account::{AccountController, AccountRoute};
+%endif
+pub use pages::{PagesController, PagesRoute};
+
+/// The response of the controllers.
+pub type Response = Result<axum::response::Response, axum::response::Response>;
+
+/// The root controller.
+#[derive(Debug, Clone, Default, RoutingController)]
+#[controller(AppRoute)]
+#[subcontroller(PagesController, route = Pages)]
+%if auth
+#[subcontroller(AccountController, route = Account, path = "account/")]
+%endif
+pub struct AppController;
+
+/// Render the navigation bar of a request.
+%if auth
+pub fn nav(parts: &http::request::Parts) -> crate::views::Nav {
+%endif
+%if !auth
+pub fn nav(_parts: &http::request::Parts) -> crate::views::Nav {
+%endif
+%if auth
+    let user = htmxology::session::Session::from_parts(parts)
+        .and_then(|session| session.get::<String>(account::USER_KEY));
+
+%endif
+    crate::views::Nav {
+        home_url: AppRoute::Pages(PagesRoute::Home),
+%if auth
+        login_url: AppRoute::Account(AccountRoute::Login),
+        logout_url: AppRoute::Account(AccountRoute::Logout),
+        user,
+%endif
+    }
+}
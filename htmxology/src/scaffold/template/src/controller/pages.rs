@@ -0,0 +1,69 @@
+//! The pages of the application.
+
+%if sessions
+use axum::response::IntoResponse;
+%endif
+use htmxology::{Controller, RenderIntoResponse, Route, ServerInfo};
+
+use super::{AppController, Response};
+use crate::views;
+
+/// The page routes.
+#[derive(Debug, Clone, Route)]
+pub enum PagesRoute {
+    /// The home page.
+    #[route("")]
+    Home,
+}
+
+/// The controller of the pages.
+#[derive(Debug, Clone)]
+pub struct PagesController;
+
+impl From<&AppController> for PagesController {
+    fn from(_: &AppController) -> Self {
+        Self
+    }
+}
+
+impl Controller for PagesController {
+    type Route = PagesRoute;
+    type Args = ();
+    type Response = Response;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        _htmx: htmxology::htmx::Request,
+        parts: http::request::Parts,
+        _server_info: &ServerInfo,
+        _args: Self::Args,
+    ) -> Self::Response {
+        match route {
+            PagesRoute::Home => Ok(views::Home {
+                nav: super::nav(&parts),
+%if sessions
+                visits: record_visit(&parts)?,
+%endif
+            }
+            .render_into_response()),
+        }
+    }
+}
+%if sessions
+
+/// Count a visit of the home page in the session, returning the number of visits.
+fn record_visit(parts: &http::request::Parts) -> Result<u64, axum::response::Response> {
+    let session = htmxology::session::Session::from_parts(parts)
+        .expect("sessions should be enabled by the server");
+    let visits = session.get::<u64>("visits").unwrap_or_default() + 1;
+
+    session.insert("visits", visits).map_err(|err| {
+        tracing::error!("Failed to save the session: {err}");
+
+        http::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    Ok(visits)
+}
+%endif
@@ -0,0 +1,33 @@
+//! The `${name}` web application.
+
+mod controller;
+mod views;
+
+use tracing::info;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt().init();
+
+    info!("Starting `{}`...", env!("CARGO_PKG_NAME"));
+
+    // Take the TCP listener from `listenfd` when run through `systemfd`, so that the server can be
+    // reloaded without dropping connections, and bind to a local address otherwise.
+    let server = htmxology::Server::builder_with_auto_reload("127.0.0.1:3000")
+        .await?
+        .with_options_from_env()?
+%if sessions
+        .with_sessions(htmxology::session::Sessions::new(
+            htmxology::session::MemorySessionStore::default(),
+        ))
+%endif
+        .build();
+
+    let router =
+        htmxology::ControllerRouter::new(controller::AppController::default(), |_| async {});
+%if static_assets
+    let router = router.with_static_assets("/static", htmxology::StaticAssets::from_dir("static"));
+%endif
+
+    server.serve(router).await.map_err(Into::into)
+}
@@ -0,0 +1,53 @@
+//! The views of the application, rendered with the `askama` templates of the `templates`
+//! directory.
+
+use askama::Template;
+
+use crate::controller::AppRoute;
+
+/// The navigation bar, shared by all the pages.
+#[derive(Debug)]
+pub struct Nav {
+    /// The home page.
+    pub home_url: AppRoute,
+%if auth
+
+    /// The login page.
+    pub login_url: AppRoute,
+
+    /// The logout route.
+    pub logout_url: AppRoute,
+
+    /// The name of the logged in user, if any.
+    pub user: Option<String>,
+%endif
+}
+
+/// The home page.
+#[derive(Debug, Template)]
+#[template(path = "home.html.jinja")]
+pub struct Home {
+    /// The navigation bar.
+    pub nav: Nav,
+%if sessions
+
+    /// The number of visits of the home page in the session.
+    pub visits: u64,
+%endif
+}
+%if auth
+
+/// The login page.
+#[derive(Debug, Template)]
+#[template(path = "login.html.jinja")]
+pub struct Login {
+    /// The navigation bar.
+    pub nav: Nav,
+
+    /// The route the form is submitted to.
+    pub submit_url: AppRoute,
+
+    /// The error of the previous submission, if any.
+    pub error: Option<String>,
+}
+%endif
@@ -0,0 +1,440 @@
+//! Canonical host and HTTPS redirect enforcement.
+
+use std::sync::Arc;
+
+use axum::{Router, response::IntoResponse};
+
+use crate::htmx::header;
+
+use super::ServerOptions;
+use super::forwarded::ForwardedOrigin;
+
+/// Redirects requests to the canonical base URL of the server.
+#[derive(Debug, Clone)]
+pub(crate) struct CanonicalRedirect {
+    /// The scheme of the canonical base URL.
+    scheme: http::uri::Scheme,
+
+    /// The authority of the canonical base URL.
+    authority: http::uri::Authority,
+
+    /// Whether to redirect requests arriving on other hosts.
+    host: bool,
+
+    /// Whether to redirect plain HTTP requests, when the base URL uses HTTPS.
+    https: bool,
+}
+
+impl CanonicalRedirect {
+    /// Create the redirects enabled by the server options, if any.
+    pub(crate) fn from_options(options: &ServerOptions) -> Option<Self> {
+        if !options.canonical_host_redirect && !options.https_redirect {
+            return None;
+        }
+
+        let Some(base_url) = &options.base_url else {
+            tracing::warn!(
+                "Canonical redirects are enabled but no base URL is set: requests won't be redirected."
+            );
+
+            return None;
+        };
+
+        let (Some(scheme), Some(authority)) = (base_url.scheme(), base_url.authority()) else {
+            tracing::warn!(
+                "Canonical redirects are enabled but the base URL `{base_url}` is not absolute: requests won't be redirected."
+            );
+
+            return None;
+        };
+
+        Some(Self {
+            scheme: scheme.clone(),
+            authority: authority.clone(),
+            host: options.canonical_host_redirect,
+            https: options.https_redirect && *scheme == http::uri::Scheme::HTTPS,
+        })
+    }
+
+    /// Apply the redirects to a router.
+    pub(crate) fn apply(self, router: Router) -> Router {
+        router.layer(axum::middleware::from_fn_with_state(
+            Arc::new(self),
+            middleware,
+        ))
+    }
+
+    /// Get the redirect response of a request, if it must be redirected.
+    fn redirect(&self, request: &axum::extract::Request) -> Option<axum::response::Response> {
+        let headers = request.headers();
+        let wrong_host = self.host
+            && request_authority(request).is_some_and(|authority| {
+                !same_authority(&authority, &self.authority, &self.scheme)
+            });
+        let plain_http = self.https
+            && request_scheme(request).is_some_and(|scheme| *scheme == http::uri::Scheme::HTTP);
+
+        if !wrong_host && !plain_http {
+            return None;
+        }
+
+        // Fragment requests redirect the whole page, so they are redirected to the canonical URL
+        // of the page they were issued from.
+        let current_url = headers
+            .get(header::HX_CURRENT_URL)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<http::Uri>().ok());
+        let htmx = headers.contains_key(header::HX_REQUEST);
+        let path_and_query = match &current_url {
+            Some(current_url) if htmx => current_url.path_and_query(),
+            _ => request.uri().path_and_query(),
+        }
+        .map_or("/", http::uri::PathAndQuery::as_str);
+
+        let location = format!("{}://{}{path_and_query}", self.scheme, self.authority);
+        let location = http::HeaderValue::from_str(&location).ok()?;
+
+        tracing::debug!(
+            "Redirecting request for `{}` to `{location:?}`",
+            request.uri()
+        );
+
+        Some(if htmx {
+            [(header::HX_REDIRECT, location)].into_response()
+        } else {
+            (
+                http::StatusCode::PERMANENT_REDIRECT,
+                [(http::header::LOCATION, location)],
+            )
+                .into_response()
+        })
+    }
+}
+
+/// Get the scheme a request was sent with, as forwarded by trusted proxies, or from its URI.
+fn request_scheme(request: &axum::extract::Request) -> Option<&http::uri::Scheme> {
+    request
+        .extensions()
+        .get::<ForwardedOrigin>()
+        .and_then(|origin| origin.scheme.as_ref())
+        .or(request.uri().scheme())
+}
+
+/// Get the authority a request was sent to, as forwarded by trusted proxies, or from its URI or
+/// its `Host` header.
+fn request_authority(request: &axum::extract::Request) -> Option<http::uri::Authority> {
+    request
+        .extensions()
+        .get::<ForwardedOrigin>()
+        .and_then(|origin| origin.authority.clone())
+        .or_else(|| request.uri().authority().cloned())
+        .or_else(|| {
+            request
+                .headers()
+                .get(http::header::HOST)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+        })
+}
+
+/// Check whether two authorities designate the same host and port, for a scheme.
+fn same_authority(
+    authority: &http::uri::Authority,
+    canonical: &http::uri::Authority,
+    scheme: &http::uri::Scheme,
+) -> bool {
+    let default_port = if *scheme == http::uri::Scheme::HTTPS {
+        443
+    } else {
+        80
+    };
+
+    authority.host().eq_ignore_ascii_case(canonical.host())
+        && authority.port_u16().unwrap_or(default_port)
+            == canonical.port_u16().unwrap_or(default_port)
+}
+
+/// Redirect the requests not sent to the canonical base URL.
+async fn middleware(
+    axum::extract::State(redirect): axum::extract::State<Arc<CanonicalRedirect>>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    match redirect.redirect(&request) {
+        Some(response) => response,
+        None => next.run(request).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redirect(host: bool, https: bool) -> CanonicalRedirect {
+        CanonicalRedirect::from_options(&ServerOptions {
+            base_url: Some("https://example.com".parse().unwrap()),
+            canonical_host_redirect: host,
+            https_redirect: https,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn request(host: &str, headers: &[(&str, &str)]) -> axum::extract::Request {
+        let mut request = http::Request::builder()
+            .uri("/posts?page=2")
+            .header(http::header::HOST, host);
+
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        request.body(axum::body::Body::empty()).unwrap()
+    }
+
+    fn forwarded_request(host: &str, scheme: http::uri::Scheme) -> axum::extract::Request {
+        let mut request = request(host, &[]);
+        request.extensions_mut().insert(ForwardedOrigin {
+            scheme: Some(scheme),
+            authority: None,
+        });
+
+        request
+    }
+
+    fn location(response: Option<axum::response::Response>) -> Option<(u16, String)> {
+        response.map(|response| {
+            let location = response
+                .headers()
+                .get(http::header::LOCATION)
+                .or(response.headers().get(header::HX_REDIRECT))
+                .unwrap();
+
+            (
+                response.status().as_u16(),
+                location.to_str().unwrap().to_owned(),
+            )
+        })
+    }
+
+    #[test]
+    fn test_from_options() {
+        assert!(CanonicalRedirect::from_options(&ServerOptions::default()).is_none());
+        assert!(
+            CanonicalRedirect::from_options(&ServerOptions {
+                base_url: None,
+                canonical_host_redirect: true,
+                https_redirect: true,
+                ..Default::default()
+            })
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_canonical_host() {
+        let redirect = redirect(true, false);
+
+        assert_eq!(
+            redirect.redirect(&request("example.com", &[])).map(|_| ()),
+            None
+        );
+        assert_eq!(
+            redirect
+                .redirect(&request("EXAMPLE.com:443", &[]))
+                .map(|_| ()),
+            None
+        );
+        assert_eq!(
+            location(redirect.redirect(&request("www.example.com", &[]))),
+            Some((308, "https://example.com/posts?page=2".to_owned()))
+        );
+
+        // Plain HTTP requests on the canonical host are left alone.
+        assert_eq!(
+            redirect
+                .redirect(&forwarded_request("example.com", http::uri::Scheme::HTTP))
+                .map(|_| ()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_https() {
+        let redirect = redirect(false, true);
+
+        assert_eq!(
+            redirect
+                .redirect(&forwarded_request("example.com", http::uri::Scheme::HTTPS))
+                .map(|_| ()),
+            None
+        );
+        assert_eq!(
+            location(redirect.redirect(&forwarded_request("example.com", http::uri::Scheme::HTTP))),
+            Some((308, "https://example.com/posts?page=2".to_owned()))
+        );
+
+        // The scheme is only known from trusted proxies, or from the URI.
+        assert_eq!(
+            redirect
+                .redirect(&request("example.com", &[("x-forwarded-proto", "http")]))
+                .map(|_| ()),
+            None
+        );
+        assert_eq!(
+            location(
+                redirect.redirect(
+                    &http::Request::get("http://example.com/posts")
+                        .body(axum::body::Body::empty())
+                        .unwrap()
+                )
+            ),
+            Some((308, "https://example.com/posts".to_owned()))
+        );
+    }
+
+    async fn call_forwarded(peer: &str, uri: &str, headers: &[(&str, &str)]) -> Option<String> {
+        use tower_service::Service as _;
+
+        let options = ServerOptions {
+            base_url: Some("https://example.com".parse().unwrap()),
+            canonical_host_redirect: true,
+            https_redirect: true,
+            trusted_proxies: vec!["10.0.0.0/8".parse().unwrap()],
+            ..Default::default()
+        };
+        let router = axum::Router::new().route("/posts", axum::routing::get(|| async { "" }));
+        let router = CanonicalRedirect::from_options(&options)
+            .unwrap()
+            .apply(router);
+        let mut router = super::super::forwarded::ForwardedHeaders::from_options(&options)
+            .unwrap()
+            .apply(router);
+
+        let mut request = http::Request::get(uri).header(http::header::HOST, "backend:8000");
+
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        let mut request = request.body(axum::body::Body::empty()).unwrap();
+        request.extensions_mut().insert(axum::extract::ConnectInfo(
+            super::super::forwarded::PeerAddr::Tcp(peer.parse().unwrap()),
+        ));
+
+        router
+            .call(request)
+            .await
+            .unwrap()
+            .headers()
+            .get(http::header::LOCATION)
+            .map(|location| location.to_str().unwrap().to_owned())
+    }
+
+    #[tokio::test]
+    async fn test_forwarded() {
+        // The forwarded host is matched, even when the URI has an authority, as with HTTP/2.
+        for uri in ["/posts", "http://backend:8000/posts"] {
+            assert_eq!(
+                call_forwarded(
+                    "10.0.0.1:4000",
+                    uri,
+                    &[
+                        ("x-forwarded-host", "example.com"),
+                        ("x-forwarded-proto", "https")
+                    ]
+                )
+                .await,
+                None
+            );
+            assert_eq!(
+                call_forwarded(
+                    "10.0.0.1:4000",
+                    uri,
+                    &[("forwarded", "proto=https;host=www.example.com")]
+                )
+                .await
+                .as_deref(),
+                Some("https://example.com/posts")
+            );
+        }
+
+        // The last scheme, added by the trusted proxy, is used.
+        assert_eq!(
+            call_forwarded(
+                "10.0.0.1:4000",
+                "/posts",
+                &[
+                    ("x-forwarded-host", "example.com"),
+                    ("x-forwarded-proto", "https, http")
+                ]
+            )
+            .await
+            .as_deref(),
+            Some("https://example.com/posts")
+        );
+        assert_eq!(
+            call_forwarded(
+                "10.0.0.1:4000",
+                "/posts",
+                &[
+                    ("x-forwarded-host", "example.com"),
+                    ("x-forwarded-proto", "http, https")
+                ]
+            )
+            .await,
+            None
+        );
+        assert_eq!(
+            call_forwarded(
+                "10.0.0.1:4000",
+                "/posts",
+                &[("forwarded", "proto=http;host=example.com")]
+            )
+            .await
+            .as_deref(),
+            Some("https://example.com/posts")
+        );
+
+        // The forwarding headers of untrusted peers are ignored.
+        assert_eq!(
+            call_forwarded(
+                "192.0.2.60:4000",
+                "/posts",
+                &[
+                    ("x-forwarded-host", "example.com"),
+                    ("x-forwarded-proto", "http")
+                ]
+            )
+            .await
+            .as_deref(),
+            Some("https://example.com/posts")
+        );
+        assert_eq!(
+            call_forwarded(
+                "192.0.2.60:4000",
+                "/posts",
+                &[("x-forwarded-host", "example.com")]
+            )
+            .await
+            .as_deref(),
+            Some("https://example.com/posts")
+        );
+    }
+
+    #[test]
+    fn test_htmx() {
+        let redirect = redirect(true, true);
+
+        assert_eq!(
+            location(redirect.redirect(&request(
+                "www.example.com",
+                &[
+                    ("hx-request", "true"),
+                    ("hx-current-url", "http://www.example.com/posts/3#comments"),
+                ],
+            ))),
+            Some((200, "https://example.com/posts/3".to_owned()))
+        );
+    }
+}
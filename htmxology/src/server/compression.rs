@@ -0,0 +1,135 @@
+//! Response compression.
+
+use axum::Router;
+
+/// Compresses the responses with gzip, brotli or zstd, as negotiated with `Accept-Encoding`.
+///
+/// The compression applies after the caching middlewares and controllers, which compute the
+/// ETags from the uncompressed bodies. As the compressed bodies differ from them, the ETags of
+/// the compressed responses are weakened: `If-None-Match` compares ETags weakly, so revalidating
+/// them still yields `304 Not Modified`, whatever the encoding.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Compression;
+
+impl Compression {
+    /// Apply the compression to a router.
+    pub(crate) fn apply(self, router: Router) -> Router {
+        router
+            .layer(tower_http::compression::CompressionLayer::new())
+            .layer(axum::middleware::map_response(weaken_etag))
+    }
+}
+
+/// Weaken the strong ETag of a compressed response.
+async fn weaken_etag(mut response: axum::response::Response) -> axum::response::Response {
+    if !response
+        .headers()
+        .contains_key(http::header::CONTENT_ENCODING)
+    {
+        return response;
+    }
+
+    let weak_etag = response
+        .headers()
+        .get(http::header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .filter(|etag| !etag.starts_with("W/"))
+        .and_then(|etag| http::HeaderValue::from_str(&format!("W/{etag}")).ok());
+
+    if let Some(weak_etag) = weak_etag {
+        response.headers_mut().insert(http::header::ETAG, weak_etag);
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::response::IntoResponse;
+    use tower_service::Service as _;
+
+    use super::*;
+
+    fn router() -> Router {
+        let router = Router::new().route(
+            "/",
+            axum::routing::get(|headers: http::HeaderMap| async move {
+                let etag = "\"abc\"";
+
+                if crate::caching::etag_matches(&crate::caching::if_none_match(&headers), etag) {
+                    return http::StatusCode::NOT_MODIFIED.into_response();
+                }
+
+                (
+                    [
+                        (http::header::ETAG, etag),
+                        (http::header::CONTENT_TYPE, "text/html"),
+                    ],
+                    "<p>Hello, world!</p>".repeat(16),
+                )
+                    .into_response()
+            }),
+        );
+
+        Compression.apply(router)
+    }
+
+    async fn get(headers: &[(http::HeaderName, &str)]) -> axum::response::Response {
+        let mut request = http::Request::get("/");
+
+        for (name, value) in headers {
+            request = request.header(name, *value);
+        }
+
+        router()
+            .call(request.body(axum::body::Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn header(response: &axum::response::Response, name: http::HeaderName) -> Option<&str> {
+        response
+            .headers()
+            .get(name)
+            .map(|value| value.to_str().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_uncompressed() {
+        let response = get(&[]).await;
+
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(header(&response, http::header::CONTENT_ENCODING), None);
+        assert_eq!(header(&response, http::header::ETAG), Some("\"abc\""));
+    }
+
+    #[tokio::test]
+    async fn test_compressed() {
+        for encoding in ["gzip", "br", "zstd"] {
+            let response = get(&[(http::header::ACCEPT_ENCODING, encoding)]).await;
+
+            assert_eq!(response.status(), http::StatusCode::OK);
+            assert_eq!(
+                header(&response, http::header::CONTENT_ENCODING),
+                Some(encoding)
+            );
+            assert_eq!(header(&response, http::header::ETAG), Some("W/\"abc\""));
+            assert_eq!(
+                header(&response, http::header::VARY),
+                Some("accept-encoding")
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_not_modified() {
+        let response = get(&[
+            (http::header::ACCEPT_ENCODING, "gzip"),
+            (http::header::IF_NONE_MATCH, "W/\"abc\""),
+        ])
+        .await;
+
+        assert_eq!(response.status(), http::StatusCode::NOT_MODIFIED);
+        assert_eq!(header(&response, http::header::CONTENT_ENCODING), None);
+    }
+}
@@ -1,14 +1,69 @@
-use std::sync::Arc;
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    task::{Context, Poll},
+};
 
-use axum::Router;
+use axum::{Router, body::HttpBody, extract::FromRequestParts as _, response::IntoResponse};
 
-use crate::Controller;
+use crate::{
+    Controller, ErrorRenderer, Route, RouteEntry, RouteMetrics, StaticAssets,
+    error_pages::{ControllerError, render_error},
+};
 
 use super::ServerInfo;
+use super::route_debug::{RouteDebugPage, RouteDebugQuery, RouteMatcher};
 
 /// A router that is associated to a controller.
 #[derive(Debug, Clone, Default)]
-pub struct ControllerRouter(Router);
+pub struct ControllerRouter {
+    /// The underlying router.
+    router: Router,
+
+    /// The description of the router configuration.
+    description: ControllerRouterDescription,
+
+    /// The parsers of the routes of the controllers, for the route debug endpoint.
+    matchers: Vec<RouteMatcher>,
+}
+
+/// A structured summary of the configuration of a [`ControllerRouter`].
+///
+/// The description is serializable and deterministic, which makes it suitable for snapshot
+/// tests that detect accidental routing changes across refactors.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let router = ControllerRouter::new(controller, |_| async {})
+///     .layer(CompressionLayer::new());
+///
+/// insta::assert_json_snapshot!(router.describe());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct ControllerRouterDescription {
+    /// The route table of the controller, if any.
+    pub routes: Vec<RouteEntry>,
+
+    /// The type names of the layers, from innermost to outermost.
+    pub layers: Vec<String>,
+
+    /// The paths of the additional endpoints, like Server-Sent Events or WebSocket endpoints.
+    pub endpoints: Vec<String>,
+
+    /// The routers nested under a path prefix, in mounting order.
+    pub nested: Vec<NestedRouterDescription>,
+}
+
+/// The description of a router nested in a [`ControllerRouter`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct NestedRouterDescription {
+    /// The path prefix the router is mounted at.
+    pub prefix: String,
+
+    /// The description of the nested router.
+    pub router: ControllerRouterDescription,
+}
 
 impl ControllerRouter {
     /// Create a new controller router from an existing router.
@@ -19,48 +74,644 @@ impl ControllerRouter {
     /// controller, likely through the `ControllerRouter::new` constructor.
     ///
     /// The router may contain layers or additional routes that are not controller-related.
+    ///
+    /// The resulting router has an empty description.
     pub unsafe fn from_router(router: Router) -> Self {
-        Self(router)
+        Self {
+            router,
+            description: ControllerRouterDescription::default(),
+            matchers: Vec::new(),
+        }
     }
 
     /// Create a new controller router from a controller with an args factory.
     ///
     /// The factory function is called on each request with a reference to the controller
     /// to create the Args for that request.
+    ///
+    /// To create the Args from the request, like its session or authenticated user, use
+    /// [`ControllerRouter::new_with_parts`].
     pub fn new<C, F, Fut>(controller: C, args_factory: F) -> Self
     where
         C: Controller<Response = Result<axum::response::Response, axum::response::Response>>
             + 'static,
         F: Fn(&C) -> Fut + Send + Sync + Clone + 'static,
         Fut: std::future::Future<Output = C::Args> + Send,
+    {
+        Self::new_with_parts(
+            controller,
+            move |controller: &C, _: &http::request::Parts| args_factory(controller),
+        )
+    }
+
+    /// Create a new controller router from a controller with an args factory receiving the
+    /// request parts.
+    ///
+    /// The factory function is called on each request whose route parses, with a reference to
+    /// the controller and the parts of the request, so that the Args can be built from its
+    /// headers, cookies or extensions set by middleware. As the returned future can't borrow the
+    /// parts, the factory should extract what it needs before the `async` block.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let router = ControllerRouter::new_with_parts(controller, |controller: &AppController, parts| {
+    ///     let db = controller.db.clone();
+    ///     let user = parts.extensions.get::<AuthenticatedUser>().cloned();
+    ///
+    ///     async move { AppArgs { db, user } }
+    /// });
+    /// ```
+    pub fn new_with_parts<C, F, Fut>(controller: C, args_factory: F) -> Self
+    where
+        C: Controller<Response = Result<axum::response::Response, axum::response::Response>>
+            + 'static,
+        F: Fn(&C, &http::request::Parts) -> Fut + Send + Sync + Clone + 'static,
+        Fut: std::future::Future<Output = C::Args> + Send,
     {
         let router = Router::new()
             .fallback(
                 move |axum::extract::State(controller): axum::extract::State<C>,
                       htmx: crate::htmx::Request,
-                      parts: http::request::Parts,
-                      route: C::Route| {
+                      request: axum::extract::Request| {
                     let args_factory = args_factory.clone();
                     async move {
+                        let (parts, body) = request.into_parts();
+                        let request = axum::extract::Request::from_parts(parts.clone(), body);
+                        let route = <C::Route as axum::extract::FromRequest<C>>::from_request(
+                            request,
+                            &controller,
+                        )
+                        .await
+                        .map_err(|rejection| {
+                            route_rejection::<C::Route>(&parts, rejection.into_response())
+                        })?;
+
+                        #[cfg(debug_assertions)]
+                        crate::htmx::sanity::report(crate::htmx::sanity::check_target(
+                            &route, &htmx,
+                        ));
+
                         let server_info: Arc<ServerInfo> = parts.extensions.get().cloned().expect(
                             "server info was not found in request extensions: this is not expected",
                         );
 
                         // Call the factory to create args for this request
-                        let args = args_factory(&controller).await;
-                        C::handle_request(&controller, route, htmx, parts, &server_info, args).await
+                        let args = args_factory(&controller, &parts).await;
+                        C::handle_request(&controller, route, htmx, parts, &server_info, args)
+                            .await
+                            .map_err(|mut response| {
+                                response.extensions_mut().insert(ControllerError);
+                                response
+                            })
                     }
                 },
             )
             .with_state(controller);
 
-        Self(router)
+        Self {
+            router,
+            description: ControllerRouterDescription {
+                routes: C::Route::route_table(),
+                ..Default::default()
+            },
+            matchers: vec![RouteMatcher::new::<C::Route>()],
+        }
+    }
+
+    /// Nest another controller router under a path prefix.
+    ///
+    /// Requests whose path starts with the prefix are forwarded to the nested router, with the
+    /// prefix stripped.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`axum::Router::nest`], for instance if the prefix is
+    /// empty or doesn't start with a slash.
+    pub fn nest(mut self, prefix: &str, router: ControllerRouter) -> Self {
+        self.router = self.router.nest(prefix, router.router);
+        self.description.nested.push(NestedRouterDescription {
+            prefix: prefix.to_owned(),
+            router: router.description,
+        });
+        self.matchers.extend(
+            router
+                .matchers
+                .into_iter()
+                .map(|matcher| matcher.nested(prefix)),
+        );
+        self
+    }
+
+    /// Mount a Server-Sent Events endpoint streaming the fragments of a stream.
+    ///
+    /// Each `GET` request to the path subscribes a new client to the stream, replaying the events
+    /// it missed if it resumes with a `Last-Event-ID` header (see
+    /// [`SseFragmentStream::resume`](crate::sse::SseFragmentStream::resume)).
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`axum::Router::route`], for instance if the path is
+    /// already routed.
+    #[cfg(feature = "sse")]
+    pub fn with_sse(mut self, path: &str, stream: crate::sse::SseFragmentStream) -> Self {
+        self.router = self.router.route(
+            path,
+            axum::routing::get(move |headers: http::HeaderMap| {
+                std::future::ready(stream.resume(&headers))
+            }),
+        );
+        self.description.endpoints.push(path.to_owned());
+        self
+    }
+
+    /// Mount a WebSocket endpoint connecting clients to a fragment hub.
+    ///
+    /// Each `GET` request to the path is upgraded to a WebSocket connection to the hub.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`axum::Router::route`], for instance if the path is
+    /// already routed.
+    #[cfg(feature = "ws")]
+    pub fn with_ws_endpoint(mut self, path: &str, hub: crate::ws::WsFragmentHub) -> Self {
+        self.router = self.router.route(
+            path,
+            axum::routing::get(move |upgrade: axum::extract::ws::WebSocketUpgrade| {
+                std::future::ready(hub.upgrade(upgrade))
+            }),
+        );
+        self.description.endpoints.push(path.to_owned());
+        self
+    }
+
+    /// Serve static assets under a path prefix.
+    ///
+    /// The assets can be a directory, as a `PathBuf` or a `&Path`, or any [`StaticAssets`],
+    /// including embedded ones. They are served with their content type, an ETag and a `public`
+    /// cache control directive.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`axum::Router::route`], for instance if the prefix
+    /// doesn't start with a slash.
+    pub fn with_static_assets(mut self, prefix: &str, assets: impl Into<StaticAssets>) -> Self {
+        let assets = assets.into();
+        let path = format!("{}/{{*path}}", prefix.trim_end_matches('/'));
+
+        self.router = self.router.route(
+            &path,
+            axum::routing::get(
+                move |axum::extract::Path(path): axum::extract::Path<String>,
+                      headers: http::HeaderMap| async move {
+                    assets.serve(&path, &headers).await
+                },
+            ),
+        );
+        self.description.endpoints.push(path);
+        self
+    }
+
+    /// Serve the fragments of a publisher at their content-addressed URLs, with their manifest.
+    ///
+    /// The manifest and the fragments are served under the path of the publisher, and recorded in
+    /// the router description. See [`FragmentPublisher`](crate::FragmentPublisher) for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`axum::Router::route`], for instance if the path is
+    /// already routed.
+    pub fn with_fragment_publisher(mut self, publisher: crate::FragmentPublisher) -> Self {
+        self.description.endpoints.extend([
+            publisher.manifest_url(),
+            format!("{}/{{hash}}", publisher.path()),
+        ]);
+        self.router = self.router.merge(publisher.router());
+        self
+    }
+
+    /// Mount an endpoint serving an HTML fragment listing the slowest routes.
+    ///
+    /// The fragment polls the endpoint to refresh itself, so it can be embedded in a development
+    /// page with `hx-get` and `hx-trigger="load"`. As it exposes the internals of the
+    /// application, it should not be mounted in production.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`axum::Router::route`], for instance if the path is
+    /// already routed.
+    pub fn with_route_metrics_endpoint(mut self, path: &str, metrics: RouteMetrics) -> Self {
+        let url = path.to_owned();
+
+        self.router = self.router.route(
+            path,
+            axum::routing::get(move || {
+                std::future::ready(axum::response::Html(
+                    metrics.fragment(Some(&url)).to_string(),
+                ))
+            }),
+        );
+        self.description.endpoints.push(path.to_owned());
+        self
+    }
+
+    /// Mount a liveness endpoint, like `/healthz`, answering `200 OK` while the server runs.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`axum::Router::route`], for instance if the path is
+    /// already routed.
+    pub fn with_health_endpoint(mut self, path: &str) -> Self {
+        self.router = self.router.route(
+            path,
+            axum::routing::get(|| std::future::ready((http::StatusCode::OK, "ok"))),
+        );
+        self.description.endpoints.push(path.to_owned());
+        self
+    }
+
+    /// Mount a readiness endpoint, like `/readyz`, answering `503 Service Unavailable` once the
+    /// graceful shutdown of the server is triggered, and `200 OK` before.
+    ///
+    /// Combined with [`ServerBuilder::with_shutdown_delay`](crate::ServerBuilder::with_shutdown_delay),
+    /// this lets load balancers stop routing requests to the server before it stops listening:
+    ///
+    /// ```rust,ignore
+    /// let router = ControllerRouter::new(controller, |_| async {})
+    ///     .with_health_endpoint("/healthz")
+    ///     .with_readiness_endpoint("/readyz");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`axum::Router::route`], for instance if the path is
+    /// already routed.
+    pub fn with_readiness_endpoint(mut self, path: &str) -> Self {
+        self.router = self
+            .router
+            .route(path, axum::routing::get(super::shutdown::readiness));
+        self.description.endpoints.push(path.to_owned());
+        self
+    }
+
+    /// Mount a development page listing the routes of the router, with a form testing which route
+    /// a URL matches.
+    ///
+    /// The page lists the routes, endpoints and layers mounted so far, including those of the
+    /// nested routers, so it should be mounted last. URLs that don't match any route are reported
+    /// with the nearest routes. As it exposes the internals of the application, it should not be
+    /// mounted in production:
+    ///
+    /// ```rust,ignore
+    /// let mut router = ControllerRouter::new(controller, |_| async {});
+    ///
+    /// if cfg!(debug_assertions) {
+    ///     router = router.with_route_debug_endpoint("/_routes");
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`axum::Router::route`], for instance if the path is
+    /// already routed.
+    pub fn with_route_debug_endpoint(mut self, path: &str) -> Self {
+        self.description.endpoints.push(path.to_owned());
+
+        let description = Arc::new(self.description.clone());
+        let matchers: Arc<[RouteMatcher]> = self.matchers.clone().into();
+
+        self.router = self.router.route(
+            path,
+            axum::routing::get(
+                move |axum::extract::Query(query): axum::extract::Query<RouteDebugQuery>| {
+                    let page = RouteDebugPage {
+                        description: &description,
+                        matchers: &matchers,
+                        query,
+                    };
+
+                    std::future::ready(axum::response::Html(page.to_string()))
+                },
+            ),
+        );
+        self
+    }
+
+    /// Apply the settings of a profile, mounting the route debug endpoint if the profile has one.
+    ///
+    /// As the route debug endpoint lists the routes mounted so far, this should be called last.
+    pub fn with_profile(self, settings: &crate::ProfileSettings) -> Self {
+        match &settings.route_debug_endpoint {
+            Some(path) => self.with_route_debug_endpoint(path),
+            None => self,
+        }
+    }
+
+    /// Mount the administrative endpoints, to inspect and purge the cache and session stores.
+    ///
+    /// The endpoints are served under the path, and recorded in the router description. See the
+    /// [`admin`](crate::admin) module for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`axum::Router::route`], for instance if the path is
+    /// already routed.
+    #[cfg(feature = "admin")]
+    pub fn with_admin(mut self, path: &str, admin: crate::admin::Admin) -> Self {
+        self.description.endpoints.extend(admin.paths(path));
+        self.router = self.router.merge(admin.router(path));
+        self
+    }
+
+    /// Protect the routes mounted so far against cross-site request forgery.
+    ///
+    /// Requests with an unsafe method, like `POST` or `DELETE`, are rejected with `403 Forbidden`
+    /// unless they carry a valid [`CsrfToken`](crate::csrf::CsrfToken). The protection is recorded
+    /// as a layer in the router description.
+    #[cfg(feature = "csrf")]
+    pub fn with_csrf_protection(mut self, csrf: crate::csrf::Csrf) -> Self {
+        self.router = csrf.apply(self.router);
+        self.description
+            .layers
+            .push(std::any::type_name::<crate::csrf::Csrf>().to_owned());
+        self
+    }
+
+    /// Match the requests against a table of redirects and rewrites before routing them.
+    ///
+    /// Redirects respond directly, and rewrites change the URL of the requests before they are
+    /// routed, so that they can target any route or endpoint. See the
+    /// [`redirects`](crate::redirects) module for the configuration formats.
+    ///
+    /// As with [`ControllerRouter::layer`], the table only applies to the routes mounted before it
+    /// is set.
+    #[cfg(feature = "redirects")]
+    pub fn with_redirects(mut self, redirects: crate::redirects::Redirects) -> Self {
+        // Wrapping the router in a fallback lets the rewritten requests be routed afterwards.
+        self.router = Router::new()
+            .fallback_service(std::mem::take(&mut self.router))
+            .layer(axum::middleware::from_fn(
+                move |mut request: axum::extract::Request, next: axum::middleware::Next| {
+                    let redirect = redirects.apply(&mut request);
+
+                    async move {
+                        match redirect {
+                            Some(response) => response,
+                            None => next.run(request).await,
+                        }
+                    }
+                },
+            ));
+        self.description
+            .layers
+            .push(std::any::type_name::<crate::redirects::Redirects>().to_owned());
+        self
+    }
+
+    /// Redirect the requests not sent to the canonical base URL, as enabled by the
+    /// [`ServerOptions::canonical_host_redirect`](crate::ServerOptions::canonical_host_redirect)
+    /// and [`ServerOptions::https_redirect`](crate::ServerOptions::https_redirect) options.
+    ///
+    /// Requests are redirected with `308 Permanent Redirect`, and HTMX requests with an
+    /// `HX-Redirect` header to the canonical URL of the page they were issued from.
+    ///
+    /// A [`Server`](crate::Server) already applies the redirects of its options before its other
+    /// middlewares: this is meant for routers served with [`ControllerRouter::into_service`]. As
+    /// with [`ControllerRouter::layer`], the redirects only apply to the routes mounted before
+    /// they are set.
+    pub fn with_canonical_redirects(mut self, options: &super::ServerOptions) -> Self {
+        if let Some(redirect) = super::canonical::CanonicalRedirect::from_options(options) {
+            self.router = redirect.apply(self.router);
+            self.description
+                .layers
+                .push(std::any::type_name::<super::canonical::CanonicalRedirect>().to_owned());
+        }
+
+        self
+    }
+
+    /// Compress the responses with gzip, brotli or zstd, as negotiated with the
+    /// `Accept-Encoding` header of the requests.
+    ///
+    /// The compression applies after the controllers and their [caching](crate::caching), so
+    /// ETags are computed from the uncompressed bodies, and the ETags of the compressed responses
+    /// are weakened: revalidations with `If-None-Match` keep yielding `304 Not Modified`. Small
+    /// responses, images and Server-Sent Events are never compressed.
+    ///
+    /// As with [`ControllerRouter::layer`], the compression only applies to the routes mounted
+    /// before it is set.
+    #[cfg(feature = "compression")]
+    pub fn with_compression(mut self) -> Self {
+        self.router = super::compression::Compression.apply(self.router);
+        self.description
+            .layers
+            .push(std::any::type_name::<super::compression::Compression>().to_owned());
+        self
+    }
+
+    /// Set the maximum number of nested inline renders of the requests, which defaults to
+    /// [`DEFAULT_RENDER_DEPTH_LIMIT`](crate::DEFAULT_RENDER_DEPTH_LIMIT).
+    ///
+    /// Rendering a route inline with
+    /// [`ControllerExt::render_inline`](crate::ControllerExt::render_inline) beyond the limit fails
+    /// with a [`RenderError`](crate::RenderError). As with [`ControllerRouter::layer`], the limit
+    /// only applies to the routes mounted before it is set.
+    pub fn with_render_depth_limit(mut self, limit: usize) -> Self {
+        self.router = self.router.layer(axum::extract::Extension(
+            crate::controller::RenderDepthLimit(limit),
+        ));
+        self
+    }
+
+    /// Render the requests that match no route with a fallback handler, instead of an empty
+    /// `404 Not Found` response.
+    ///
+    /// The handler receives the htmx request and the parts of the request, and typically renders
+    /// an HTML not-found page through a controller. Responses returned by the controllers, even
+    /// with a `404 Not Found` status, are left untouched.
+    ///
+    /// As with [`ControllerRouter::layer`], the handler only applies to the routers nested
+    /// before it is set.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let router = ControllerRouter::new(controller.clone(), |_| async {}).with_fallback(
+    ///     move |htmx, parts| {
+    ///         let controller = controller.clone();
+    ///
+    ///         async move { controller.render_not_found(&htmx, &parts).await }
+    ///     },
+    /// );
+    /// ```
+    pub fn with_fallback<F, Fut>(mut self, fallback: F) -> Self
+    where
+        F: Fn(crate::htmx::Request, http::request::Parts) -> Fut + Clone + Send + Sync + 'static,
+        Fut: std::future::Future<Output = axum::response::Response> + Send + 'static,
+    {
+        self.router = self.router.layer(axum::middleware::map_response(
+            move |mut response: axum::response::Response| {
+                let fallback = fallback.clone();
+
+                async move {
+                    let Some(RouteNotFound(mut parts)) = response.extensions_mut().remove() else {
+                        return response;
+                    };
+
+                    let htmx = match crate::htmx::Request::from_request_parts(&mut parts, &()).await
+                    {
+                        Ok(htmx) => htmx,
+                        Err(err) => match err {},
+                    };
+
+                    fallback(htmx, parts).await
+                }
+            },
+        ));
+        self
+    }
+
+    /// Render the client and server errors returned by the controllers with an error renderer.
+    ///
+    /// Classic requests get the page of the renderer, and htmx requests its fragment. The
+    /// requests that don't parse as routes are rendered too, except the ones handled by a
+    /// [fallback handler](ControllerRouter::with_fallback).
+    ///
+    /// As with [`ControllerRouter::layer`], the renderer only applies to the routers nested
+    /// before it is set.
+    pub fn with_error_renderer(mut self, renderer: impl ErrorRenderer) -> Self {
+        let renderer = Arc::new(renderer);
+
+        self.router = self.router.layer(axum::middleware::from_fn(
+            move |request: axum::extract::Request, next: axum::middleware::Next| {
+                let renderer = renderer.clone();
+
+                async move {
+                    let (parts, body) = request.into_parts();
+                    let response = next
+                        .run(axum::extract::Request::from_parts(parts.clone(), body))
+                        .await;
+
+                    render_error(renderer.as_ref(), parts, response).await
+                }
+            },
+        ));
+        self
+    }
+
+    /// Apply a layer to the router, as with [`axum::Router::layer`].
+    ///
+    /// The type name of the layer is recorded in the router description.
+    pub fn layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower_layer::Layer<axum::routing::Route> + Clone + Send + Sync + 'static,
+        L::Service: tower_service::Service<axum::extract::Request, Error = Infallible>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        <L::Service as tower_service::Service<axum::extract::Request>>::Response:
+            axum::response::IntoResponse + 'static,
+        <L::Service as tower_service::Service<axum::extract::Request>>::Future: Send + 'static,
+    {
+        self.router = self.router.layer(layer);
+        self.description
+            .layers
+            .push(std::any::type_name::<L>().to_owned());
+        self
+    }
+
+    /// Get a structured description of the router configuration.
+    ///
+    /// The description lists the route table of the controller (see [`Route::route_table`]), the
+    /// layers applied through [`ControllerRouter::layer`] and the routers mounted through
+    /// [`ControllerRouter::nest`].
+    pub fn describe(&self) -> &ControllerRouterDescription {
+        &self.description
+    }
+
+    /// Turn the router into a standalone service, without a [`Server`](crate::Server).
+    ///
+    /// The server information is made available to the controllers as when serving the router,
+    /// and the middlewares set on the router itself, like CSRF protection, compression or the ones
+    /// applied with [`ControllerRouter::layer`], apply as usual.
+    ///
+    /// The middlewares a [`Server`](crate::Server) adds around the router are not applied:
+    ///
+    /// - the sessions, flash messages, trace context and render context configuration of the
+    ///   [`ServerBuilder`](crate::ServerBuilder),
+    /// - the path prefix, the forwarding headers of trusted proxies and the canonical redirects
+    ///   of the [`ServerOptions`](crate::ServerOptions). Canonical redirects can be applied with
+    ///   [`ControllerRouter::with_canonical_redirects`].
+    pub fn into_service(self, server_info: ServerInfo) -> ControllerService {
+        ControllerService {
+            router: self
+                .router
+                .layer(axum::extract::Extension(Arc::new(server_info))),
+        }
+    }
+}
+
+/// A [`ControllerRouter`] as a standalone [`tower_service::Service`].
+///
+/// The service handles `http::Request`s with any body, which makes it suitable for serverless
+/// runtimes and for embedding in other hyper-based servers.
+///
+/// # Example
+///
+/// On AWS Lambda, with the `lambda` feature:
+///
+/// ```rust,ignore
+/// let server_info = ServerInfo {
+///     base_url: "https://example.com".parse()?,
+/// };
+/// let service = ControllerRouter::new(controller, |_| async {}).into_service(server_info);
+///
+/// service.run_on_lambda().await
+/// ```
+#[derive(Debug, Clone)]
+pub struct ControllerService {
+    /// The underlying router, with the server information.
+    router: Router,
+}
+
+impl<B> tower_service::Service<http::Request<B>> for ControllerService
+where
+    B: HttpBody<Data = axum::body::Bytes> + Send + 'static,
+    B::Error: Into<axum::BoxError>,
+{
+    type Response = axum::response::Response;
+    type Error = Infallible;
+    type Future = axum::routing::future::RouteFuture<Infallible>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        tower_service::Service::<http::Request<B>>::poll_ready(&mut self.router, cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        self.router.call(request)
+    }
+}
+
+#[cfg(feature = "lambda")]
+impl ControllerService {
+    /// Serve the requests of the AWS Lambda runtime, until it stops the function.
+    ///
+    /// The requests can come from API Gateway REST and HTTP APIs, Application Load Balancers,
+    /// VPC Lattice or function URLs, as supported by [`lambda_http`]. Set the base URL of the
+    /// server information to the public URL of the function, as it can't be guessed.
+    ///
+    /// The middlewares of the [`ServerBuilder`](crate::ServerBuilder) and of the
+    /// [`ServerOptions`](crate::ServerOptions) are not applied: see
+    /// [`ControllerRouter::into_service`].
+    pub async fn run_on_lambda(self) -> Result<(), lambda_http::Error> {
+        lambda_http::run(self).await
     }
 }
 
 impl From<ControllerRouter> for Router {
     fn from(controller_router: ControllerRouter) -> Self {
-        controller_router.0
+        controller_router.router
     }
 }
 
@@ -76,3 +727,88 @@ where
         ControllerRouter::new(controller, |_| async { C::Args::default() })
     }
 }
+
+impl<C> From<crate::coalescing::Controller<C>> for ControllerRouter
+where
+    C: crate::Controller<Response = Result<axum::response::Response, axum::response::Response>>
+        + 'static,
+    C::Route:
+        crate::Route + Send + Sync + axum::extract::FromRequest<crate::coalescing::Controller<C>>,
+    C::Args: Default,
+{
+    fn from(controller: crate::coalescing::Controller<C>) -> Self {
+        ControllerRouter::new(controller, |_| async { C::Args::default() })
+    }
+}
+
+impl<C> From<crate::metrics::Controller<C>> for ControllerRouter
+where
+    C: crate::Controller<Response = Result<axum::response::Response, axum::response::Response>>
+        + 'static,
+    C::Route:
+        crate::Route + Send + Sync + axum::extract::FromRequest<crate::metrics::Controller<C>>,
+    C::Args: Default,
+{
+    fn from(controller: crate::metrics::Controller<C>) -> Self {
+        ControllerRouter::new(controller, |_| async { C::Args::default() })
+    }
+}
+
+/// The marker of the responses to requests that match no route, holding the parts of the request
+/// for the fallback handler.
+#[derive(Clone)]
+struct RouteNotFound(http::request::Parts);
+
+/// Convert the rejection of a request that doesn't parse as a route, marking it as an error and
+/// marking the requests that match no route.
+fn route_rejection<R: Route>(
+    parts: &http::request::Parts,
+    rejection: axum::response::Response,
+) -> axum::response::Response {
+    let not_found = rejection.status() == http::StatusCode::NOT_FOUND
+        && rejection.body().size_hint().exact() == Some(0);
+
+    let mut response = not_found_diagnostics::<R>(parts, rejection);
+
+    response.extensions_mut().insert(ControllerError);
+
+    if not_found {
+        response
+            .extensions_mut()
+            .insert(RouteNotFound(parts.clone()));
+    }
+
+    response
+}
+
+/// Replace the empty `404 Not Found` rejection of a request that doesn't match any route with a
+/// page suggesting the nearest routes, in debug builds.
+fn not_found_diagnostics<R: Route>(
+    parts: &http::request::Parts,
+    rejection: axum::response::Response,
+) -> axum::response::Response {
+    if !cfg!(debug_assertions)
+        || rejection.status() != http::StatusCode::NOT_FOUND
+        || rejection.body().size_hint().exact() != Some(0)
+    {
+        return rejection;
+    }
+
+    let err = crate::ParseError::NoMatchingRoute {
+        url: parts
+            .uri
+            .path_and_query()
+            .map(|path_and_query| path_and_query.as_str())
+            .unwrap_or("/")
+            .to_owned(),
+        method: parts.method.to_string(),
+        suggestions: Vec::new(),
+    }
+    .with_suggestions::<R>();
+
+    (
+        http::StatusCode::NOT_FOUND,
+        axum::response::Html(err.to_debug_page()),
+    )
+        .into_response()
+}
@@ -0,0 +1,555 @@
+//! Reverse-proxy awareness, from the `Forwarded` and `X-Forwarded-*` headers.
+
+use std::{
+    fmt::Display,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::Arc,
+};
+
+use axum::{Router, extract::ConnectInfo};
+
+use super::ServerInfo;
+
+/// The header set by reverse proxies to the scheme of the original request.
+const X_FORWARDED_PROTO: http::HeaderName = http::HeaderName::from_static("x-forwarded-proto");
+
+/// The header set by reverse proxies to the host of the original request.
+const X_FORWARDED_HOST: http::HeaderName = http::HeaderName::from_static("x-forwarded-host");
+
+/// A network of reverse proxies whose forwarding headers are trusted, like `10.0.0.0/8` or
+/// `127.0.0.1`.
+///
+/// See [`ServerOptions::trusted_proxies`](crate::ServerOptions::trusted_proxies).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TrustedProxy {
+    /// The address of the network.
+    addr: IpAddr,
+
+    /// The length of the network prefix, in bits.
+    prefix_len: u8,
+}
+
+/// An error that occurs when trying to parse an invalid trusted proxy.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid trusted proxy `{0}`: expected an IP address or network, like `10.0.0.0/8`")]
+pub struct InvalidTrustedProxy(String);
+
+impl TrustedProxy {
+    /// Trust a network of proxies, with the specified prefix length.
+    ///
+    /// Returns `None` if the prefix is longer than the address.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Option<Self> {
+        let max_len = if addr.is_ipv4() { 32 } else { 128 };
+
+        (prefix_len <= max_len).then_some(Self { addr, prefix_len })
+    }
+
+    /// Check whether an address belongs to the network.
+    ///
+    /// IPv4-mapped IPv6 addresses are matched as IPv4 addresses.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        match (self.addr, addr.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+
+                u32::from(network) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+
+                u128::from(network) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<IpAddr> for TrustedProxy {
+    fn from(addr: IpAddr) -> Self {
+        let prefix_len = if addr.is_ipv4() { 32 } else { 128 };
+
+        Self { addr, prefix_len }
+    }
+}
+
+impl FromStr for TrustedProxy {
+    type Err = InvalidTrustedProxy;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidTrustedProxy(s.to_owned());
+
+        match s.trim().split_once('/') {
+            Some((addr, prefix_len)) => Self::new(
+                addr.parse().map_err(|_| invalid())?,
+                prefix_len.parse().map_err(|_| invalid())?,
+            )
+            .ok_or_else(invalid),
+            None => s.trim().parse().map(IpAddr::into).map_err(|_| invalid()),
+        }
+    }
+}
+
+impl Display for TrustedProxy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+/// The address of the peer of a connection, as recorded by the listeners of the server.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum PeerAddr {
+    /// A peer connected over TCP.
+    Tcp(SocketAddr),
+
+    /// A local process connected over a Unix domain socket.
+    #[cfg(unix)]
+    Unix,
+}
+
+impl
+    axum::extract::connect_info::Connected<axum::serve::IncomingStream<'_, tokio::net::TcpListener>>
+    for PeerAddr
+{
+    fn connect_info(stream: axum::serve::IncomingStream<'_, tokio::net::TcpListener>) -> Self {
+        Self::Tcp(*stream.remote_addr())
+    }
+}
+
+#[cfg(feature = "tls")]
+impl
+    axum::extract::connect_info::Connected<axum::serve::IncomingStream<'_, super::tls::TlsListener>>
+    for PeerAddr
+{
+    fn connect_info(stream: axum::serve::IncomingStream<'_, super::tls::TlsListener>) -> Self {
+        Self::Tcp(*stream.remote_addr())
+    }
+}
+
+#[cfg(unix)]
+impl
+    axum::extract::connect_info::Connected<
+        axum::serve::IncomingStream<'_, tokio::net::UnixListener>,
+    > for PeerAddr
+{
+    fn connect_info(_stream: axum::serve::IncomingStream<'_, tokio::net::UnixListener>) -> Self {
+        Self::Unix
+    }
+}
+
+/// Derives the base URL and host of requests from the forwarding headers of trusted proxies.
+#[derive(Debug, Clone)]
+pub(crate) struct ForwardedHeaders {
+    /// The trusted proxies.
+    trusted_proxies: Vec<TrustedProxy>,
+}
+
+impl ForwardedHeaders {
+    /// Create the middleware enabled by the server options, if any.
+    pub(crate) fn from_options(options: &super::ServerOptions) -> Option<Self> {
+        if options.trusted_proxies.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            trusted_proxies: options.trusted_proxies.clone(),
+        })
+    }
+
+    /// Apply the middleware to a router.
+    ///
+    /// The router must get the [`ServerInfo`] of the server in its extensions, outside of the
+    /// middleware.
+    pub(crate) fn apply(self, router: Router) -> Router {
+        router.layer(axum::middleware::from_fn_with_state(
+            Arc::new(self),
+            middleware,
+        ))
+    }
+
+    /// Check whether an address is a trusted proxy.
+    fn trusts(&self, addr: IpAddr) -> bool {
+        self.trusted_proxies
+            .iter()
+            .any(|proxy| proxy.contains(addr))
+    }
+
+    /// Get the scheme and host of the original request, as forwarded by trusted proxies.
+    fn forwarded(&self, request: &axum::extract::Request) -> Forwarded {
+        let trusted = match request.extensions().get::<ConnectInfo<PeerAddr>>() {
+            Some(ConnectInfo(PeerAddr::Tcp(addr))) => self.trusts(addr.ip()),
+            #[cfg(unix)]
+            Some(ConnectInfo(PeerAddr::Unix)) => true,
+            None => false,
+        };
+
+        if !trusted {
+            return Forwarded::default();
+        }
+
+        let headers = request.headers();
+        let elements: Vec<_> = headers
+            .get_all(http::header::FORWARDED)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(Forwarded::parse)
+            .collect();
+
+        // The last element was added by the peer, which is trusted: the previous ones are only
+        // trusted as long as they were added by trusted proxies too.
+        let mut index = elements.len();
+
+        while index > 1
+            && elements[index - 1]
+                .for_
+                .is_some_and(|addr| self.trusts(addr))
+        {
+            index -= 1;
+        }
+
+        if let Some(element) = index.checked_sub(1).map(|index| &elements[index])
+            && (element.proto.is_some() || element.host.is_some())
+        {
+            return element.clone();
+        }
+
+        // Proxies append their values to the `X-Forwarded-*` headers, so only the last one is
+        // trusted.
+        let last_value = |name| {
+            headers
+                .get_all(name)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .map(str::trim)
+                .rfind(|value| !value.is_empty())
+                .map(ToOwned::to_owned)
+        };
+
+        Forwarded {
+            for_: None,
+            proto: last_value(X_FORWARDED_PROTO),
+            host: last_value(X_FORWARDED_HOST),
+        }
+    }
+}
+
+/// The scheme and authority of the original request, as forwarded by trusted proxies.
+///
+/// Inserted in the extensions of the requests whose forwarding headers are trusted, for the
+/// middlewares that depend on the origin of requests, like canonical redirects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ForwardedOrigin {
+    /// The scheme of the original request, if forwarded.
+    pub(crate) scheme: Option<http::uri::Scheme>,
+
+    /// The authority of the original request, if forwarded.
+    pub(crate) authority: Option<http::uri::Authority>,
+}
+
+impl ForwardedOrigin {
+    /// Get the base URL of the original request, from the base URL of the server.
+    fn base_url(&self, base_url: &http::Uri) -> Option<http::Uri> {
+        let scheme = self.scheme.clone().or_else(|| base_url.scheme().cloned())?;
+        let authority = self
+            .authority
+            .clone()
+            .or_else(|| base_url.authority().cloned())?;
+
+        http::Uri::builder()
+            .scheme(scheme)
+            .authority(authority)
+            .path_and_query(
+                base_url
+                    .path_and_query()
+                    .map_or("/", http::uri::PathAndQuery::as_str),
+            )
+            .build()
+            .ok()
+    }
+}
+
+/// The scheme and host of an original request, as forwarded by a proxy.
+#[derive(Debug, Clone, Default)]
+struct Forwarded {
+    /// The address of the client of the proxy.
+    for_: Option<IpAddr>,
+
+    /// The scheme of the original request.
+    proto: Option<String>,
+
+    /// The host of the original request, with its port, if any.
+    host: Option<String>,
+}
+
+impl Forwarded {
+    /// Parse an element of a `Forwarded` header, like `for=192.0.2.60;proto=https`.
+    fn parse(element: &str) -> Self {
+        let mut forwarded = Self::default();
+
+        for pair in element.split(';') {
+            let Some((name, value)) = pair.split_once('=') else {
+                continue;
+            };
+
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+
+            match name.trim().to_ascii_lowercase().as_str() {
+                "for" => forwarded.for_ = parse_node(value),
+                "proto" => forwarded.proto = Some(value.to_owned()),
+                "host" => forwarded.host = Some(value.to_owned()),
+                _ => {}
+            }
+        }
+
+        forwarded
+    }
+
+    /// Get the origin of the original request.
+    ///
+    /// Returns `None` if nothing was forwarded, or if the forwarded scheme or host is invalid.
+    fn origin(&self) -> Option<ForwardedOrigin> {
+        if self.proto.is_none() && self.host.is_none() {
+            return None;
+        }
+
+        let scheme = match self.proto.as_deref() {
+            Some(proto) if proto.eq_ignore_ascii_case("https") => Some(http::uri::Scheme::HTTPS),
+            Some(proto) if proto.eq_ignore_ascii_case("http") => Some(http::uri::Scheme::HTTP),
+            Some(_) => return None,
+            None => None,
+        };
+        let authority = match self.host.as_deref() {
+            Some(host) => Some(host.parse().ok()?),
+            None => None,
+        };
+
+        Some(ForwardedOrigin { scheme, authority })
+    }
+}
+
+/// Parse the IP address of a node of a `Forwarded` header, like `192.0.2.43:47011` or
+/// `[2001:db8::17]`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    if let Some(node) = node.strip_prefix('[') {
+        return node.split_once(']')?.0.parse().ok();
+    }
+
+    node.split_once(':')
+        .map_or(node, |(addr, _)| addr)
+        .parse()
+        .ok()
+}
+
+/// The middleware deriving the base URL and host of requests from the forwarding headers.
+async fn middleware(
+    axum::extract::State(headers): axum::extract::State<Arc<ForwardedHeaders>>,
+    mut request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let Some(origin) = headers.forwarded(&request).origin() else {
+        return next.run(request).await;
+    };
+
+    if let Some(server_info) = request.extensions().get::<Arc<ServerInfo>>()
+        && let Some(base_url) = origin.base_url(&server_info.base_url)
+    {
+        tracing::trace!("Using forwarded base URL `{base_url}`");
+
+        let mut server_info = ServerInfo::clone(server_info);
+        server_info.base_url = base_url;
+
+        request.extensions_mut().insert(Arc::new(server_info));
+    }
+
+    // Host-scoped routes match the forwarded host.
+    if let Some(authority) = &origin.authority
+        && let Ok(host) = http::HeaderValue::from_str(authority.as_str())
+    {
+        request.headers_mut().insert(http::header::HOST, host);
+    }
+
+    request.extensions_mut().insert(origin);
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_service::Service as _;
+
+    use super::*;
+
+    #[test]
+    fn test_trusted_proxy() {
+        let proxy: TrustedProxy = "10.0.0.0/8".parse().unwrap();
+
+        assert_eq!(proxy.to_string(), "10.0.0.0/8");
+        assert!(proxy.contains("10.1.2.3".parse().unwrap()));
+        assert!(proxy.contains("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(!proxy.contains("11.1.2.3".parse().unwrap()));
+
+        let proxy: TrustedProxy = "::1".parse().unwrap();
+
+        assert_eq!(proxy.to_string(), "::1/128");
+        assert!(proxy.contains("::1".parse().unwrap()));
+        assert!(!proxy.contains("127.0.0.1".parse().unwrap()));
+
+        assert!(
+            "0.0.0.0/0"
+                .parse::<TrustedProxy>()
+                .unwrap()
+                .contains("1.2.3.4".parse().unwrap())
+        );
+        assert!("10.0.0.0/33".parse::<TrustedProxy>().is_err());
+        assert!("localhost".parse::<TrustedProxy>().is_err());
+    }
+
+    async fn call(peer: &str, headers: &[(&str, &str)]) -> (String, String) {
+        let options = super::super::ServerOptions {
+            trusted_proxies: vec!["10.0.0.0/8".parse().unwrap()],
+            ..Default::default()
+        };
+        let router = axum::Router::new().route(
+            "/",
+            axum::routing::get(
+                |axum::extract::Extension(server_info): axum::extract::Extension<
+                    Arc<ServerInfo>,
+                >,
+                 headers: http::HeaderMap| async move {
+                    format!(
+                        "{} {}",
+                        server_info.base_url,
+                        headers[http::header::HOST].to_str().unwrap()
+                    )
+                },
+            ),
+        );
+        let mut router = ForwardedHeaders::from_options(&options)
+            .unwrap()
+            .apply(router)
+            .layer(axum::extract::Extension(Arc::new(ServerInfo {
+                base_url: http::Uri::from_static("http://backend:8000/app/"),
+            })));
+
+        let mut request = http::Request::get("/").header(http::header::HOST, "backend:8000");
+
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        let mut request = request.body(axum::body::Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(PeerAddr::Tcp(peer.parse().unwrap())));
+
+        let response = router.call(request).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let (base_url, host) = body.split_once(' ').unwrap();
+
+        (base_url.to_owned(), host.to_owned())
+    }
+
+    #[tokio::test]
+    async fn test_x_forwarded() {
+        assert_eq!(
+            call(
+                "10.0.0.1:4000",
+                &[
+                    ("x-forwarded-proto", "https"),
+                    ("x-forwarded-host", "example.com")
+                ]
+            )
+            .await,
+            (
+                "https://example.com/app/".to_owned(),
+                "example.com".to_owned()
+            )
+        );
+
+        // Only the value appended by the trusted proxy is used.
+        assert_eq!(
+            call(
+                "10.0.0.1:4000",
+                &[("x-forwarded-host", "evil.com, example.com")]
+            )
+            .await
+            .0,
+            "http://example.com/app/"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forwarded() {
+        assert_eq!(
+            call(
+                "10.0.0.1:4000",
+                &[
+                    ("forwarded", "for=192.0.2.60;proto=https;host=example.com"),
+                    ("x-forwarded-host", "ignored.com")
+                ]
+            )
+            .await
+            .0,
+            "https://example.com/app/"
+        );
+
+        // Elements added by untrusted clients are ignored.
+        assert_eq!(
+            call(
+                "10.0.0.1:4000",
+                &[(
+                    "forwarded",
+                    "host=evil.com, for=\"192.0.2.60:4711\";host=example.com"
+                )]
+            )
+            .await
+            .0,
+            "http://example.com/app/"
+        );
+
+        // Elements added by trusted proxies are used.
+        assert_eq!(
+            call(
+                "10.0.0.1:4000",
+                &[(
+                    "forwarded",
+                    "for=192.0.2.60;proto=https;host=example.com, for=10.0.0.2;host=internal"
+                )]
+            )
+            .await
+            .0,
+            "https://example.com/app/"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_untrusted_peer() {
+        assert_eq!(
+            call(
+                "192.0.2.60:4000",
+                &[
+                    ("x-forwarded-proto", "https"),
+                    ("x-forwarded-host", "evil.com")
+                ]
+            )
+            .await,
+            (
+                "http://backend:8000/app/".to_owned(),
+                "backend:8000".to_owned()
+            )
+        );
+    }
+}
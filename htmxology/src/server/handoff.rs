@@ -0,0 +1,471 @@
+//! Zero-downtime restarts, handing off the listener and the state of a server to its successor.
+
+use std::{
+    io,
+    os::fd::{AsRawFd, FromRawFd, RawFd},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::UnixStream,
+    signal::unix::SignalKind,
+};
+
+/// The environment variable holding the file descriptor of the handoff socket of a successor.
+pub const HANDOFF_FD_ENV: &str = "HTMXOLOGY_HANDOFF_FD";
+
+/// The default time a successor has to start.
+const DEFAULT_START_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The message of a successor that started, and waits for the state.
+const STARTED: u8 = b'S';
+
+/// The message of a successor that imported the state, and serves requests.
+const READY: u8 = b'R';
+
+/// A hook exporting state to hand off to the successor of a server, and importing it.
+///
+/// Implemented by [`MemorySessionStore`](crate::session::MemorySessionStore), so that sessions
+/// survive restarts.
+pub trait StateHandoff: Send + Sync + 'static {
+    /// Export the state, to hand off to the successor.
+    fn export_state(&self) -> Vec<u8>;
+
+    /// Import the state exported by the predecessor.
+    fn import_state(&self, state: &[u8]) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// An error that can occur when handing off a server to its successor.
+#[derive(Debug, thiserror::Error)]
+pub enum HandoffError {
+    /// The successor could not be spawned.
+    #[error("failed to spawn the successor: {0}")]
+    Spawn(#[source] io::Error),
+
+    /// The successor did not start in time.
+    #[error("the successor did not start within {0:?}")]
+    StartTimeout(Duration),
+
+    /// The handoff socket failed, or the other process exited.
+    #[error("the handoff socket failed: {0}")]
+    Io(#[from] io::Error),
+
+    /// The handed off state is malformed.
+    #[error("malformed handoff state")]
+    MalformedState,
+}
+
+/// The configuration of the zero-downtime restarts of a server.
+///
+/// Enabled with [`ServerBuilder::with_handoff`](crate::ServerBuilder::with_handoff), on both the
+/// predecessor and the successor, which are usually the same program.
+///
+/// On its restart signal (`SIGHUP` by default), a server with a handoff spawns a new instance of
+/// its program, passing it its TCP listener with the `listenfd` protocol, so that
+/// [`get_or_bind_tcp_listener`](crate::get_or_bind_tcp_listener) picks it up, and a socket to
+/// hand off its state through:
+///
+/// 1. The successor reports that it started, once its server is built with a handoff too.
+/// 2. The predecessor stops accepting connections, which queue in the shared listener meanwhile,
+///    and sends the state exported by its [`StateHandoff`] hooks.
+/// 3. The successor imports the state and starts serving, while the predecessor drains its
+///    connections, until the [drain timeout](crate::ServerBuilder::with_drain_timeout) of the
+///    server, if any.
+///
+/// If the successor fails to start, it is killed and the predecessor keeps serving.
+///
+/// The state changed by the requests drained by the predecessor is not handed off.
+///
+/// ```rust,ignore
+/// let store = MemorySessionStore::default();
+/// let server = Server::builder_with_auto_reload("127.0.0.1:3000")
+///     .await?
+///     .with_sessions(Sessions::new(store.clone()))
+///     .with_handoff(Handoff::new().with_state("sessions", store))
+///     .with_drain_timeout(Duration::from_secs(30))
+///     .build();
+/// ```
+#[derive(Clone)]
+pub struct Handoff {
+    /// The state hooks, by name.
+    hooks: Vec<(String, Arc<dyn StateHandoff>)>,
+
+    /// The signal triggering the restart.
+    signal: SignalKind,
+
+    /// The program of the successor, instead of the current executable.
+    program: Option<PathBuf>,
+
+    /// The time the successor has to start.
+    start_timeout: Duration,
+}
+
+impl std::fmt::Debug for Handoff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Handoff")
+            .field(
+                "hooks",
+                &self.hooks.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            )
+            .field("signal", &self.signal)
+            .field("program", &self.program)
+            .field("start_timeout", &self.start_timeout)
+            .finish()
+    }
+}
+
+impl Default for Handoff {
+    fn default() -> Self {
+        Self {
+            hooks: Vec::new(),
+            signal: SignalKind::hangup(),
+            program: None,
+            start_timeout: DEFAULT_START_TIMEOUT,
+        }
+    }
+}
+
+impl Handoff {
+    /// Create a handoff restarting on `SIGHUP`, without state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hand off the state of a hook, under a name identifying it in the successor.
+    ///
+    /// The state of hooks missing from the successor is dropped.
+    pub fn with_state(mut self, name: impl Into<String>, hook: impl StateHandoff) -> Self {
+        self.hooks.push((name.into(), Arc::new(hook)));
+        self
+    }
+
+    /// Set the signal triggering the restart.
+    pub fn with_signal(mut self, signal: SignalKind) -> Self {
+        self.signal = signal;
+        self
+    }
+
+    /// Set the program of the successor, started with the arguments of the current process.
+    ///
+    /// Defaults to the current executable, which is the previous version of the program if it
+    /// was replaced on disk on Linux: deployments replacing the program should set its path.
+    pub fn with_program(mut self, program: impl Into<PathBuf>) -> Self {
+        self.program = Some(program.into());
+        self
+    }
+
+    /// Set the time the successor has to start, after which it is killed.
+    pub fn with_start_timeout(mut self, start_timeout: Duration) -> Self {
+        self.start_timeout = start_timeout;
+        self
+    }
+
+    /// Resume the state of the predecessor of the process, if it was started by one, and hand off
+    /// the server to a successor on the restart signal.
+    ///
+    /// `stop_accepting` is notified once a successor started. The returned task listens for the
+    /// restart signal, and must be aborted once the server stops.
+    pub(crate) async fn start(
+        &self,
+        listener: &tokio::net::TcpListener,
+        stop_accepting: Arc<tokio::sync::Notify>,
+    ) -> io::Result<tokio::task::JoinHandle<()>> {
+        match Self::take_predecessor() {
+            Some(Ok(mut predecessor)) => {
+                tracing::info!("Resuming the state of the predecessor process...");
+
+                if let Err(err) = self.resume(&mut predecessor).await {
+                    tracing::error!("Failed to resume the state of the predecessor: {err}");
+                }
+
+                if let Err(err) = Self::ready(&mut predecessor).await {
+                    tracing::error!("Failed to report to the predecessor that we serve: {err}");
+                }
+            }
+            Some(Err(err)) => tracing::error!("Failed to take the handoff socket: {err}"),
+            None => {}
+        }
+
+        let mut signal = tokio::signal::unix::signal(self.signal)?;
+        let listener = listener.as_raw_fd();
+        let handoff = self.clone();
+
+        Ok(tokio::spawn(async move {
+            while signal.recv().await.is_some() {
+                tracing::info!("Received the restart signal, starting a successor process...");
+
+                let stop_accepting = || stop_accepting.notify_one();
+
+                match handoff.hand_off(listener, stop_accepting).await {
+                    Ok(()) => break,
+                    Err(err) => tracing::error!("Failed to hand off the server, keeping on: {err}"),
+                }
+            }
+        }))
+    }
+
+    /// Take the handoff socket of the predecessor of the process, if it was started by one.
+    fn take_predecessor() -> Option<io::Result<UnixStream>> {
+        let fd = std::env::var(HANDOFF_FD_ENV).ok()?;
+
+        // SAFETY: Removing the variable is unsound if other threads read the environment
+        // concurrently, which they don't while the server is being prepared.
+        unsafe { std::env::remove_var(HANDOFF_FD_ENV) };
+
+        let stream = fd
+            .parse::<RawFd>()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+            .and_then(|fd| {
+                // SAFETY: The predecessor passed the socket at this file descriptor, which
+                // nothing else owns.
+                let stream = unsafe { std::os::unix::net::UnixStream::from_raw_fd(fd) };
+
+                stream.set_nonblocking(true)?;
+
+                UnixStream::from_std(stream)
+            });
+
+        Some(stream)
+    }
+
+    /// Resume the state of the predecessor, from its handoff socket.
+    async fn resume(&self, predecessor: &mut UnixStream) -> Result<(), HandoffError> {
+        predecessor.write_u8(STARTED).await?;
+        self.receive_state(predecessor).await
+    }
+
+    /// Report to the predecessor that the successor serves requests.
+    async fn ready(predecessor: &mut UnixStream) -> Result<(), HandoffError> {
+        predecessor.write_u8(READY).await?;
+
+        Ok(())
+    }
+
+    /// Hand off the listener and the state of the server to a successor.
+    ///
+    /// `stop_accepting` is called once the successor started, before exporting the state.
+    pub(crate) async fn hand_off(
+        &self,
+        listener: RawFd,
+        stop_accepting: impl FnOnce(),
+    ) -> Result<(), HandoffError> {
+        let (mut successor, mut child) = self.spawn_successor(listener)?;
+
+        let started = tokio::time::timeout(self.start_timeout, successor.read_u8()).await;
+
+        match started {
+            Ok(Ok(STARTED)) => {}
+            started => {
+                if let Err(err) = child.kill().and_then(|()| child.wait()) {
+                    tracing::warn!("Failed to kill the successor: {err}");
+                }
+
+                return Err(match started {
+                    Ok(Ok(_)) => HandoffError::MalformedState,
+                    Ok(Err(err)) => err.into(),
+                    Err(_) => HandoffError::StartTimeout(self.start_timeout),
+                });
+            }
+        }
+
+        tracing::info!(
+            "Successor process {} started, handing off the server.",
+            child.id()
+        );
+
+        stop_accepting();
+        self.send_state(&mut successor).await?;
+
+        match tokio::time::timeout(self.start_timeout, successor.read_u8()).await {
+            Ok(Ok(READY)) => tracing::info!("Successor process {} is serving.", child.id()),
+            _ => tracing::error!(
+                "Successor process {} did not report that it is serving.",
+                child.id()
+            ),
+        }
+
+        Ok(())
+    }
+
+    /// Spawn the successor, passing it the listener and one end of a handoff socket.
+    fn spawn_successor(
+        &self,
+        listener: RawFd,
+    ) -> Result<(UnixStream, std::process::Child), HandoffError> {
+        let program = match &self.program {
+            Some(program) => program.clone(),
+            None => std::env::current_exe().map_err(HandoffError::Spawn)?,
+        };
+        let (ours, theirs) = std::os::unix::net::UnixStream::pair()?;
+
+        ours.set_nonblocking(true)?;
+
+        let ours = UnixStream::from_std(ours)?;
+
+        // Duplicates of file descriptors are inherited by child processes, unlike the originals.
+        let listener_fd = Inherited::dup(listener)?;
+        let theirs_fd = Inherited::dup(theirs.as_raw_fd())?;
+
+        let child = std::process::Command::new(program)
+            .args(std::env::args_os().skip(1))
+            .env("LISTEN_FDS", "1")
+            .env("LISTEN_FDS_FIRST_FD", listener_fd.0.to_string())
+            .env_remove("LISTEN_PID")
+            .env(HANDOFF_FD_ENV, theirs_fd.0.to_string())
+            .spawn()
+            .map_err(HandoffError::Spawn)?;
+
+        Ok((ours, child))
+    }
+
+    /// Send the state of the hooks, as the name and the state of each hook prefixed by their
+    /// length, and close the socket for writing.
+    async fn send_state(&self, successor: &mut UnixStream) -> Result<(), HandoffError> {
+        for (name, hook) in &self.hooks {
+            let state = hook.export_state();
+
+            for bytes in [name.as_bytes(), &state] {
+                let len = u32::try_from(bytes.len()).map_err(|_| HandoffError::MalformedState)?;
+
+                successor.write_u32(len).await?;
+                successor.write_all(bytes).await?;
+            }
+        }
+
+        successor.shutdown().await?;
+
+        Ok(())
+    }
+
+    /// Receive the state sent by [`Handoff::send_state`], and import it in the hooks.
+    async fn receive_state(&self, predecessor: &mut UnixStream) -> Result<(), HandoffError> {
+        let mut buf = Vec::new();
+
+        predecessor.read_to_end(&mut buf).await?;
+
+        let mut rest = buf.as_slice();
+
+        while !rest.is_empty() {
+            let name = take_frame(&mut rest)?;
+            let state = take_frame(&mut rest)?;
+            let name = std::str::from_utf8(name).map_err(|_| HandoffError::MalformedState)?;
+
+            match self.hooks.iter().find(|(hook_name, _)| hook_name == name) {
+                Some((_, hook)) => {
+                    if let Err(err) = hook.import_state(state) {
+                        tracing::error!("Failed to import the `{name}` handoff state: {err}");
+                    }
+                }
+                None => tracing::warn!("Dropping the `{name}` handoff state, with no hook."),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Take a frame prefixed by its length from the beginning of a buffer.
+fn take_frame<'a>(buf: &mut &'a [u8]) -> Result<&'a [u8], HandoffError> {
+    let (len, rest) = buf
+        .split_first_chunk::<4>()
+        .ok_or(HandoffError::MalformedState)?;
+    let len = u32::from_be_bytes(*len) as usize;
+
+    if rest.len() < len {
+        return Err(HandoffError::MalformedState);
+    }
+
+    let (frame, rest) = rest.split_at(len);
+
+    *buf = rest;
+
+    Ok(frame)
+}
+
+/// A duplicate of a file descriptor, inherited by child processes and closed when dropped.
+struct Inherited(RawFd);
+
+impl Inherited {
+    fn dup(fd: RawFd) -> io::Result<Self> {
+        // SAFETY: `dup` has no memory safety requirements, and the duplicate is owned by the
+        // result.
+        match unsafe { libc::dup(fd) } {
+            -1 => Err(io::Error::last_os_error()),
+            fd => Ok(Self(fd)),
+        }
+    }
+}
+
+impl Drop for Inherited {
+    fn drop(&mut self) {
+        // SAFETY: The file descriptor is owned, and not used after this.
+        unsafe { libc::close(self.0) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct TestState(Mutex<Vec<u8>>);
+
+    impl StateHandoff for Arc<TestState> {
+        fn export_state(&self) -> Vec<u8> {
+            self.0.lock().unwrap().clone()
+        }
+
+        fn import_state(
+            &self,
+            state: &[u8],
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            *self.0.lock().unwrap() = state.to_vec();
+
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_state_handoff() {
+        let (mut predecessor, mut successor) = UnixStream::pair().unwrap();
+        let old = Arc::new(TestState(Mutex::new(b"counter=3".to_vec())));
+        let new = Arc::new(TestState::default());
+        let unknown = Arc::new(TestState(Mutex::new(b"dropped".to_vec())));
+
+        let sending = Handoff::new()
+            .with_state("unknown", unknown)
+            .with_state("counter", old);
+        let receiving = Handoff::new().with_state("counter", new.clone());
+
+        let (sent, ()) = tokio::join!(
+            async {
+                assert_eq!(predecessor.read_u8().await.unwrap(), STARTED);
+                sending.send_state(&mut predecessor).await.unwrap();
+                predecessor.read_u8().await.unwrap()
+            },
+            async {
+                receiving.resume(&mut successor).await.unwrap();
+                Handoff::ready(&mut successor).await.unwrap();
+            }
+        );
+
+        assert_eq!(sent, READY);
+        assert_eq!(new.export_state(), b"counter=3");
+    }
+
+    #[test]
+    fn test_take_frame() {
+        let mut buf: &[u8] = &[0, 0, 0, 2, b'a', b'b', 0, 0, 0, 3, b'c'];
+
+        assert_eq!(take_frame(&mut buf).unwrap(), b"ab");
+        assert!(matches!(
+            take_frame(&mut buf),
+            Err(HandoffError::MalformedState)
+        ));
+    }
+}
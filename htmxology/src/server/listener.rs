@@ -0,0 +1,152 @@
+//! The listeners a server accepts connections on.
+
+use std::{fmt::Display, future::Future, net::SocketAddr};
+
+use axum::Router;
+
+use super::forwarded::PeerAddr;
+
+/// A listener a [`Server`](crate::Server) accepts connections on.
+///
+/// Servers are built from a TCP listener or, on Unix, from a Unix domain socket listener, as
+/// bound by deployments behind a reverse proxy or activated by systemd. Both convert into a
+/// `ServerListener`:
+///
+/// ```rust,ignore
+/// let listener = tokio::net::UnixListener::bind("/run/app.sock")?;
+/// let server = Server::builder(listener).build();
+/// ```
+#[derive(Debug)]
+pub enum ServerListener {
+    /// A TCP listener.
+    Tcp(tokio::net::TcpListener),
+
+    /// A Unix domain socket listener.
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+}
+
+/// The local address of a [`ServerListener`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenerAddr {
+    /// The address of a TCP listener.
+    Tcp(SocketAddr),
+
+    /// The path of a Unix domain socket listener, unless it is unnamed.
+    #[cfg(unix)]
+    Unix(Option<std::path::PathBuf>),
+}
+
+impl ListenerAddr {
+    /// Get the address of a TCP listener.
+    pub fn as_tcp(&self) -> Option<SocketAddr> {
+        match self {
+            Self::Tcp(addr) => Some(*addr),
+            #[cfg(unix)]
+            Self::Unix(_) => None,
+        }
+    }
+}
+
+impl Display for ListenerAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "TCP/{addr}"),
+            #[cfg(unix)]
+            Self::Unix(Some(path)) => write!(f, "UNIX/{}", path.display()),
+            #[cfg(unix)]
+            Self::Unix(None) => f.write_str("UNIX/(unnamed)"),
+        }
+    }
+}
+
+impl From<tokio::net::TcpListener> for ServerListener {
+    fn from(listener: tokio::net::TcpListener) -> Self {
+        Self::Tcp(listener)
+    }
+}
+
+#[cfg(unix)]
+impl From<tokio::net::UnixListener> for ServerListener {
+    fn from(listener: tokio::net::UnixListener) -> Self {
+        Self::Unix(listener)
+    }
+}
+
+impl ServerListener {
+    /// Get the local address of the listener.
+    pub fn local_addr(&self) -> std::io::Result<ListenerAddr> {
+        match self {
+            Self::Tcp(listener) => listener.local_addr().map(ListenerAddr::Tcp),
+            #[cfg(unix)]
+            Self::Unix(listener) => listener
+                .local_addr()
+                .map(|addr| ListenerAddr::Unix(addr.as_pathname().map(ToOwned::to_owned))),
+        }
+    }
+
+    /// Serve a router on the listener, until the graceful shutdown signal.
+    ///
+    /// The address of the peer of each connection is recorded in the request extensions, for
+    /// the trusted proxies.
+    ///
+    /// TLS, if configured, is only terminated on TCP listeners.
+    pub(crate) async fn serve(
+        self,
+        router: Router,
+        #[cfg(feature = "tls")] tls: Option<std::sync::Arc<tokio_rustls::rustls::ServerConfig>>,
+        signal: impl Future<Output = ()> + Send + 'static,
+    ) -> std::io::Result<()> {
+        match self {
+            #[cfg(feature = "tls")]
+            Self::Tcp(listener) if tls.is_some() => {
+                let config = tls.expect("TLS configuration is set");
+
+                axum::serve(
+                    super::tls::TlsListener::new(listener, config)?,
+                    router.into_make_service_with_connect_info::<PeerAddr>(),
+                )
+                .with_graceful_shutdown(signal)
+                .await
+            }
+            Self::Tcp(listener) => {
+                axum::serve(
+                    listener,
+                    router.into_make_service_with_connect_info::<PeerAddr>(),
+                )
+                .with_graceful_shutdown(signal)
+                .await
+            }
+            #[cfg(unix)]
+            Self::Unix(listener) => {
+                axum::serve(
+                    listener,
+                    router.into_make_service_with_connect_info::<PeerAddr>(),
+                )
+                .with_graceful_shutdown(signal)
+                .await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listener_addr() {
+        let addr = ListenerAddr::Tcp("127.0.0.1:8000".parse().unwrap());
+
+        assert_eq!(addr.to_string(), "TCP/127.0.0.1:8000");
+        assert_eq!(addr.as_tcp(), Some("127.0.0.1:8000".parse().unwrap()));
+
+        #[cfg(unix)]
+        {
+            let addr = ListenerAddr::Unix(Some("/run/app.sock".into()));
+
+            assert_eq!(addr.to_string(), "UNIX//run/app.sock");
+            assert_eq!(addr.as_tcp(), None);
+        }
+    }
+}
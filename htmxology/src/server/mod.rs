@@ -1,48 +1,116 @@
-use std::{future::Future, net::SocketAddr, pin::Pin, sync::Arc};
+use std::{future::Future, net::SocketAddr, pin::Pin, sync::Arc, time::Duration};
 
 #[cfg(feature = "auto-reload")]
 pub mod auto_reload;
 
+#[cfg(all(feature = "auto-reload", unix))]
+pub mod handoff;
+
+mod canonical;
+#[cfg(feature = "compression")]
+mod compression;
 mod controller_router;
+mod forwarded;
+mod listener;
 mod options;
+mod path_prefix;
+mod request_info;
+mod route_debug;
+mod shutdown;
+#[cfg(feature = "tls")]
+mod tls;
 
 use axum::Router;
-pub use controller_router::ControllerRouter;
+pub use controller_router::{
+    ControllerRouter, ControllerRouterDescription, ControllerService, NestedRouterDescription,
+};
+pub use forwarded::{InvalidTrustedProxy, TrustedProxy};
+pub use listener::{ListenerAddr, ServerListener};
 pub use options::{ServerOptions, ServerOptionsFromEnvError};
+pub use request_info::RequestInfo;
+pub use shutdown::ShutdownHandle;
+#[cfg(feature = "tls")]
+pub use tls::TlsError;
 
 /// The server information.
 ///
 /// This information is made available in the controller through request extensions.
+///
+/// Its base URL is the same for all the requests, unless forwarded by trusted proxies: see
+/// [`RequestInfo`] for the host of each request.
 #[derive(Debug, Clone)]
 pub struct ServerInfo {
     /// The base URL of the server.
     pub base_url: http::Uri,
 }
 
+/// A function applying a middleware to a router.
+type RouterLayer = Box<dyn FnOnce(Router) -> Router + Send>;
+
 /// A server builder.
 pub struct ServerBuilder {
-    /// The TCP listener that the server is using.
-    listener: tokio::net::TcpListener,
+    /// The listeners that the server is using, served concurrently.
+    listeners: Vec<ServerListener>,
 
     /// The graceful shutdown signal.
     graceful_shutdown: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
 
     /// The options for the server.
     options: ServerOptions,
+
+    /// The middlewares to apply to the router, like sessions or flash messages.
+    layers: Vec<RouterLayer>,
+
+    /// The time the connections have to drain after the graceful shutdown signal.
+    drain_timeout: Option<Duration>,
+
+    /// The handle triggering the graceful shutdown, and reporting it to readiness endpoints.
+    shutdown: ShutdownHandle,
+
+    /// The time new connections are still accepted after the graceful shutdown signal.
+    shutdown_delay: Option<Duration>,
+
+    /// The handoff to successor processes.
+    #[cfg(all(feature = "auto-reload", unix))]
+    handoff: Option<handoff::Handoff>,
+
+    /// The TLS configuration, to terminate TLS connections.
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<tokio_rustls::rustls::ServerConfig>>,
 }
 
 /// The main struct for the HTMX-SSR framework.
 ///
 /// Represents a running HTMX-SSR server.
 pub struct Server {
-    /// The TCP listener that the server is using.
-    listener: tokio::net::TcpListener,
+    /// The listeners that the server is using, served concurrently.
+    listeners: Vec<ServerListener>,
 
     /// The graceful shutdown signal.
     graceful_shutdown: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
 
     /// The options for the server.
     options: ServerOptions,
+
+    /// The middlewares to apply to the router, like sessions or flash messages.
+    layers: Vec<RouterLayer>,
+
+    /// The time the connections have to drain after the graceful shutdown signal.
+    drain_timeout: Option<Duration>,
+
+    /// The handle triggering the graceful shutdown, and reporting it to readiness endpoints.
+    shutdown: ShutdownHandle,
+
+    /// The time new connections are still accepted after the graceful shutdown signal.
+    shutdown_delay: Option<Duration>,
+
+    /// The handoff to successor processes.
+    #[cfg(all(feature = "auto-reload", unix))]
+    handoff: Option<handoff::Handoff>,
+
+    /// The TLS configuration, to terminate TLS connections.
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<tokio_rustls::rustls::ServerConfig>>,
 }
 
 /// An error that can occur when instantiating a new HTMX-SSR server with auto-reload features.
@@ -64,6 +132,250 @@ pub enum ServeError {
     /// An error occurred while trying to get the local address of the listener.
     #[error("failed to get the local address of the listener: {0}")]
     LocalAddr(std::io::Error),
+
+    /// The task of a spawned server panicked or was cancelled.
+    #[error("the server task failed: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+/// A handle to a server running in the background, returned by [`Server::spawn`].
+///
+/// Dropping the handle does not stop the server: use [`ServerHandle::shutdown`] for that.
+pub struct ServerHandle {
+    /// The local addresses the server is listening on, in the order of its listeners.
+    local_addrs: Vec<ListenerAddr>,
+
+    /// The server information.
+    server_info: Arc<ServerInfo>,
+
+    /// The trigger of the graceful shutdown.
+    shutdown: ShutdownHandle,
+
+    /// Whether the server is serving requests.
+    ready: tokio::sync::watch::Receiver<bool>,
+
+    /// The task running the server.
+    join_handle: tokio::task::JoinHandle<Result<(), ServeError>>,
+}
+
+impl ServerHandle {
+    /// Get the local address of the first TCP listener of the server.
+    ///
+    /// This is useful when the listener was bound to port `0`, to get the actual port.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the server has no TCP listener: use [`ServerHandle::local_addrs`] instead.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addrs
+            .iter()
+            .find_map(ListenerAddr::as_tcp)
+            .expect("the server has no TCP listener")
+    }
+
+    /// Get the local addresses the server is listening on, in the order of its listeners.
+    pub fn local_addrs(&self) -> &[ListenerAddr] {
+        &self.local_addrs
+    }
+
+    /// Get the server information, as made available to controllers.
+    pub fn server_info(&self) -> &ServerInfo {
+        &self.server_info
+    }
+
+    /// Wait until the server is serving requests.
+    ///
+    /// Returns immediately if the server already stopped.
+    pub async fn ready(&self) {
+        // An error means the server stopped, so it won't get any more ready.
+        let _ = self.ready.clone().wait_for(|ready| *ready).await;
+    }
+
+    /// Trigger the graceful shutdown of the server.
+    ///
+    /// The server stops accepting new connections, and stops once the pending requests are
+    /// served. Use [`ServerHandle::join`] to wait for it.
+    pub fn shutdown(&self) {
+        self.shutdown.shutdown();
+    }
+
+    /// Get the handle triggering the graceful shutdown of the server, to share with other tasks.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Check whether the server stopped.
+    pub fn is_finished(&self) -> bool {
+        self.join_handle.is_finished()
+    }
+
+    /// Wait for the server to stop.
+    pub async fn join(self) -> Result<(), ServeError> {
+        self.join_handle.await?
+    }
+
+    /// Trigger the graceful shutdown of the server, and wait for it to stop.
+    pub async fn shutdown_and_join(self) -> Result<(), ServeError> {
+        self.shutdown();
+        self.join().await
+    }
+
+    /// Get the handle of the task running the server.
+    pub fn into_join_handle(self) -> tokio::task::JoinHandle<Result<(), ServeError>> {
+        self.join_handle
+    }
+}
+
+/// A server whose router is ready to be served.
+struct PreparedServer {
+    listeners: Vec<ServerListener>,
+    router: Router,
+    graceful_shutdown: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+    drain_timeout: Option<Duration>,
+    shutdown: ShutdownHandle,
+    shutdown_delay: Option<Duration>,
+    #[cfg(all(feature = "auto-reload", unix))]
+    handoff: Option<handoff::Handoff>,
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<tokio_rustls::rustls::ServerConfig>>,
+    local_addrs: Vec<ListenerAddr>,
+    server_info: Arc<ServerInfo>,
+}
+
+impl PreparedServer {
+    /// Serve the router until the graceful shutdown signal, if any, the shutdown handle, or the
+    /// handoff to a successor.
+    async fn serve(self) -> Result<(), ServeError> {
+        let stop_accepting = Arc::new(tokio::sync::Notify::new());
+
+        #[cfg(all(feature = "auto-reload", unix))]
+        let restarts = match (&self.handoff, self.listeners.as_slice()) {
+            (Some(handoff), [ServerListener::Tcp(listener)]) => {
+                Some(handoff.start(listener, stop_accepting.clone()).await?)
+            }
+            (Some(_), _) => {
+                tracing::warn!(
+                    "Handoff is only supported for servers with a single TCP listener, disabling it."
+                );
+
+                None
+            }
+            (None, _) => None,
+        };
+
+        let draining = Arc::new(tokio::sync::Notify::new());
+        let handed_off = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let signal = self.graceful_shutdown;
+        let shutdown_handle = self.shutdown;
+        let shutdown_delay = self.shutdown_delay;
+        let draining_signal = draining.clone();
+        let handed_off_signal = handed_off.clone();
+
+        let shutdown = async move {
+            let stop_accepting = async {
+                stop_accepting.notified().await;
+                handed_off_signal.store(true, std::sync::atomic::Ordering::Relaxed);
+            };
+
+            let shutting_down = async {
+                match signal {
+                    Some(signal) => tokio::select! {
+                        () = signal => {}
+                        () = shutdown_handle.wait() => {}
+                    },
+                    None => shutdown_handle.wait().await,
+                }
+
+                // Report the shutdown to the readiness endpoints, whatever triggered it.
+                shutdown_handle.shutdown();
+
+                if let Some(shutdown_delay) = shutdown_delay {
+                    tracing::info!(
+                        "Shutting down, still accepting connections for {shutdown_delay:?}..."
+                    );
+
+                    tokio::time::sleep(shutdown_delay).await;
+                }
+            };
+
+            tokio::select! {
+                () = shutting_down => {}
+                () = stop_accepting => {}
+            }
+
+            draining_signal.notify_one();
+        };
+
+        // Each listener is served in its own task, and stops on the shared shutdown signal.
+        let (stop, stopped) = tokio::sync::watch::channel(false);
+        let mut servers = tokio::task::JoinSet::new();
+
+        for listener in self.listeners {
+            let mut stopped = stopped.clone();
+
+            servers.spawn(listener.serve(
+                self.router.clone(),
+                #[cfg(feature = "tls")]
+                self.tls.clone(),
+                async move {
+                    // An error means the shutdown future was dropped, so stop too.
+                    let _ = stopped.wait_for(|stopped| *stopped).await;
+                },
+            ));
+        }
+
+        let serve = async move {
+            let shutdown = async move {
+                shutdown.await;
+                stop.send_replace(true);
+            };
+
+            tokio::pin!(shutdown);
+
+            let mut shutting_down = false;
+
+            loop {
+                tokio::select! {
+                    () = &mut shutdown, if !shutting_down => shutting_down = true,
+                    result = servers.join_next() => match result {
+                        Some(Ok(Ok(()))) => {}
+                        Some(Ok(Err(err))) => return Err(ServeError::Io(err)),
+                        Some(Err(err)) => return Err(ServeError::Join(err)),
+                        None => return Ok(()),
+                    },
+                }
+            }
+        };
+
+        let result = match self.drain_timeout {
+            Some(drain_timeout) => tokio::select! {
+                result = serve => result,
+                () = async {
+                    draining.notified().await;
+                    tokio::time::sleep(drain_timeout).await;
+                } => {
+                    tracing::warn!(
+                        "Connections still open after the {drain_timeout:?} drain timeout, stopping anyway."
+                    );
+
+                    Ok(())
+                }
+            },
+            None => serve.await,
+        };
+
+        // Let the successor report that it serves, if the server was handed off to it.
+        #[cfg(all(feature = "auto-reload", unix))]
+        if let Some(restarts) = restarts {
+            if handed_off.load(std::sync::atomic::Ordering::Relaxed) {
+                let _ = restarts.await;
+            } else {
+                restarts.abort();
+            }
+        }
+
+        result
+    }
 }
 
 impl ServerBuilder {
@@ -103,23 +415,164 @@ impl ServerBuilder {
         })
     }
 
+    /// Set the time the connections have to drain after the graceful shutdown signal.
+    ///
+    /// Once it elapses, the server stops even if requests are still being served. Without it,
+    /// the server waits for all the connections to close.
+    pub fn with_drain_timeout(mut self, drain_timeout: Duration) -> Self {
+        self.drain_timeout = Some(drain_timeout);
+        self
+    }
+
+    /// Keep accepting new connections for some time after the graceful shutdown signal.
+    ///
+    /// Readiness endpoints report the server as unavailable meanwhile, which gives load balancers,
+    /// like Kubernetes services, the time to stop routing new requests to it before it stops
+    /// listening. The drain timeout, if any, starts after it.
+    pub fn with_shutdown_delay(mut self, shutdown_delay: Duration) -> Self {
+        self.shutdown_delay = Some(shutdown_delay);
+        self
+    }
+
+    /// Get the handle triggering the graceful shutdown of the server, from other tasks.
+    ///
+    /// The server shuts down gracefully on the first of its graceful shutdown signal, if any, and
+    /// [`ShutdownHandle::shutdown`].
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Enable zero-downtime restarts, handing off the listener and the state of the server to a
+    /// successor process on the restart signal.
+    ///
+    /// The successor must enable the handoff too, to resume the state and take over. See
+    /// [`Handoff`](crate::Handoff) for details.
+    #[cfg(all(feature = "auto-reload", unix))]
+    pub fn with_handoff(mut self, handoff: handoff::Handoff) -> Self {
+        self.handoff = Some(handoff);
+        self
+    }
+
+    /// Terminate TLS connections, with PEM-encoded certificate chain and private key files.
+    ///
+    /// The server then serves HTTPS directly, without a TLS-terminating reverse proxy, and its
+    /// guessed base URL uses the `https` scheme. Certificates are not reloaded: restart the server
+    /// to renew them.
+    ///
+    /// TLS is only terminated on TCP listeners: Unix domain socket listeners keep serving plain
+    /// HTTP, to the local reverse proxy.
+    #[cfg(feature = "tls")]
+    pub fn with_tls(
+        self,
+        cert: impl AsRef<std::path::Path>,
+        key: impl AsRef<std::path::Path>,
+    ) -> Result<Self, TlsError> {
+        let config = tls::load_config(cert.as_ref(), key.as_ref())?;
+
+        Ok(self.with_tls_config(config))
+    }
+
+    /// Terminate TLS connections, with a custom `rustls` configuration.
+    ///
+    /// Use it for client certificates or certificate resolvers. The ALPN protocols of the
+    /// configuration should not include `h2`, as the server only speaks HTTP/1.1.
+    #[cfg(feature = "tls")]
+    pub fn with_tls_config(mut self, config: Arc<tokio_rustls::rustls::ServerConfig>) -> Self {
+        self.tls = Some(config);
+        self
+    }
+
+    /// Enable flash messages.
+    ///
+    /// The flash messages of each request are available to controllers through
+    /// [`FlashMessages::from_parts`](crate::FlashMessages::from_parts).
+    pub fn with_flash_messages(mut self, flash: crate::Flash) -> Self {
+        self.layers
+            .push(Box::new(move |router| flash.apply(router)));
+        self
+    }
+
+    /// Enable W3C trace context propagation.
+    ///
+    /// Each request is handled in a span continuing the trace of its `traceparent` header, if
+    /// any, and its trace context is available to controllers through
+    /// [`TraceContext::from_parts`](crate::TraceContext::from_parts), to be embedded in the
+    /// rendered fragments.
+    pub fn with_trace_context(mut self) -> Self {
+        self.layers.push(Box::new(crate::TraceContext::apply));
+        self
+    }
+
+    /// Configure the [`RenderContext`](crate::RenderContext) of the requests, like how their
+    /// locale or principal are found.
+    ///
+    /// The render context is available to controllers without it, with its default
+    /// configuration.
+    #[cfg(feature = "templating")]
+    pub fn with_render_context(mut self, config: crate::RenderContextConfig) -> Self {
+        self.layers
+            .push(Box::new(move |router| config.apply(router)));
+        self
+    }
+
+    /// Enable sessions, with the specified store and cookie configuration.
+    ///
+    /// The session of each request is available to controllers through
+    /// [`Session::from_parts`](crate::session::Session::from_parts).
+    #[cfg(feature = "session")]
+    pub fn with_sessions<S: crate::session::SessionStore>(
+        mut self,
+        sessions: crate::session::Sessions<S>,
+    ) -> Self {
+        self.layers
+            .push(Box::new(move |router| sessions.apply(router)));
+        self
+    }
+
+    /// Serve on an additional listener, like a Unix domain socket next to a TCP port.
+    ///
+    /// The listeners are served concurrently, with the same router, and stop on the same
+    /// graceful shutdown signal.
+    pub fn with_listener(mut self, listener: impl Into<ServerListener>) -> Self {
+        self.listeners.push(listener.into());
+        self
+    }
+
     /// Build the server.
     pub fn build(self) -> Server {
         Server {
-            listener: self.listener,
+            listeners: self.listeners,
             graceful_shutdown: self.graceful_shutdown,
             options: self.options,
+            layers: self.layers,
+            drain_timeout: self.drain_timeout,
+            shutdown: self.shutdown,
+            shutdown_delay: self.shutdown_delay,
+            #[cfg(all(feature = "auto-reload", unix))]
+            handoff: self.handoff,
+            #[cfg(feature = "tls")]
+            tls: self.tls,
         }
     }
 }
 
 impl Server {
-    /// Get a builder for the server.
-    pub fn builder(listener: tokio::net::TcpListener) -> ServerBuilder {
+    /// Get a builder for the server, listening on a TCP or a Unix domain socket listener.
+    ///
+    /// Use [`ServerBuilder::with_listener`] to listen on more.
+    pub fn builder(listener: impl Into<ServerListener>) -> ServerBuilder {
         ServerBuilder {
-            listener,
+            listeners: vec![listener.into()],
             graceful_shutdown: None,
             options: Default::default(),
+            layers: Vec::new(),
+            drain_timeout: None,
+            shutdown: ShutdownHandle::default(),
+            shutdown_delay: None,
+            #[cfg(all(feature = "auto-reload", unix))]
+            handoff: None,
+            #[cfg(feature = "tls")]
+            tls: None,
         }
     }
 
@@ -138,6 +591,28 @@ impl Server {
         Ok(Self::builder(listener).with_ctrl_c_graceful_shutdown())
     }
 
+    /// Instantiate a new HTMX-SSR server for a profile.
+    ///
+    /// Takes the TCP listener from `listenfd` if the profile enables auto-reload, as
+    /// [`Server::builder_with_auto_reload`] does, and binds to the address otherwise.
+    ///
+    /// Also sets the graceful shutdown signal to `ctrl-c`.
+    #[cfg(feature = "auto-reload")]
+    pub async fn builder_with_profile(
+        addr: impl tokio::net::ToSocketAddrs,
+        settings: &crate::ProfileSettings,
+    ) -> Result<ServerBuilder, NewWithAutoReloadError> {
+        if settings.auto_reload {
+            return Self::builder_with_auto_reload(addr).await;
+        }
+
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .map_err(auto_reload::GetTcpListenerError::Bind)?;
+
+        Ok(Self::builder(listener).with_ctrl_c_graceful_shutdown())
+    }
+
     /// Serve the specified controller router.
     ///
     /// You must manually create a `ControllerRouter` using `ControllerRouter::new()`
@@ -164,13 +639,95 @@ impl Server {
     ///
     /// Use this method to add custom routes to the server before serving it.
     pub async fn serve_with_router(self, router: ControllerRouter) -> Result<(), ServeError> {
-        let local_addr = self.listener.local_addr().map_err(ServeError::LocalAddr)?;
+        self.prepare(router)?.serve().await
+    }
+
+    /// Spawn a task serving the specified controller router, and return a handle to control it.
+    ///
+    /// Unlike [`Server::serve`], this returns as soon as the server is spawned, which makes it
+    /// easy to run servers in tests, or several servers in the same process. The server stops
+    /// on the graceful shutdown signal, if any, or when [`ServerHandle::shutdown`] is called.
+    ///
+    /// Must be called from a Tokio runtime.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    /// let handle = Server::builder(listener).build().spawn(router)?;
+    ///
+    /// handle.ready().await;
+    /// let url = format!("http://{}/", handle.local_addr());
+    /// // ...
+    /// handle.shutdown_and_join().await?;
+    /// ```
+    pub fn spawn(self, router: impl Into<ControllerRouter>) -> Result<ServerHandle, ServeError> {
+        let server = self.prepare(router.into())?;
+        let shutdown = server.shutdown.clone();
+        let (ready_sender, ready) = tokio::sync::watch::channel(false);
+
+        let local_addrs = server.local_addrs.clone();
+        let server_info = server.server_info.clone();
+
+        let join_handle = tokio::spawn(async move {
+            // The listener is already bound, so connections are accepted as soon as the server
+            // is polled.
+            ready_sender.send_replace(true);
+
+            server.serve().await
+        });
+
+        Ok(ServerHandle {
+            local_addrs,
+            server_info,
+            shutdown,
+            ready,
+            join_handle,
+        })
+    }
 
-        tracing::info!("HTMX SSR server listening on TCP/{local_addr}.");
+    /// Prepare the router to serve, with the server information and middlewares.
+    fn prepare(self, router: ControllerRouter) -> Result<PreparedServer, ServeError> {
+        let local_addrs = self
+            .listeners
+            .iter()
+            .map(ServerListener::local_addr)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ServeError::LocalAddr)?;
+
+        #[cfg(feature = "tls")]
+        let secure = self.tls.is_some();
+        #[cfg(not(feature = "tls"))]
+        let secure = false;
+
+        for local_addr in &local_addrs {
+            if secure && local_addr.as_tcp().is_some() {
+                tracing::info!("HTMX SSR server listening on {local_addr}, with TLS.");
+            } else {
+                tracing::info!("HTMX SSR server listening on {local_addr}.");
+            }
+        }
 
-        let base_url = match self.options.base_url {
-            Some(base_url) => base_url,
-            None => Self::guess_base_url(local_addr),
+        let canonical_redirect = canonical::CanonicalRedirect::from_options(&self.options);
+        let forwarded_headers = forwarded::ForwardedHeaders::from_options(&self.options);
+        let path_prefix = path_prefix::PathPrefix::from_options(&self.options);
+        let base_url = match (self.options.base_url, local_addrs.first()) {
+            (Some(base_url), _) => base_url,
+            (None, Some(ListenerAddr::Tcp(local_addr))) => {
+                Self::guess_base_url(*local_addr, secure)
+            }
+            (None, _) => {
+                tracing::warn!(
+                    "No base URL set, and the server does not listen on TCP: using `http://localhost`. Set the base URL to the public URL of the server."
+                );
+
+                http::Uri::from_static("http://localhost")
+            }
+        };
+
+        let base_url = match &path_prefix {
+            Some(path_prefix) => path_prefix.base_url(base_url),
+            None => base_url,
         };
 
         let server_info = Arc::new(ServerInfo { base_url });
@@ -181,21 +738,58 @@ impl Server {
         );
 
         let router: Router = router.into();
-        let router = router.layer(axum::extract::Extension(server_info));
 
-        let serve = axum::serve(self.listener, router);
+        let router = self
+            .layers
+            .into_iter()
+            .fold(router, |router, layer| layer(router));
 
-        match self.graceful_shutdown {
-            Some(signal) => serve.with_graceful_shutdown(signal).await,
-            None => serve.await,
-        }
-        .map_err(Into::into)
+        // The path prefix is stripped before the other middlewares, so that they see the same
+        // paths as the routes.
+        let router = match path_prefix {
+            Some(path_prefix) => path_prefix.apply(router),
+            None => router,
+        };
+
+        // Canonical redirects run before the other middlewares, like sessions.
+        let router = match canonical_redirect {
+            Some(redirect) => redirect.apply(router),
+            None => router,
+        };
+
+        // Forwarding headers are honored before canonical redirects, which match the forwarded
+        // host.
+        let router = match forwarded_headers {
+            Some(forwarded_headers) => forwarded_headers.apply(router),
+            None => router,
+        };
+
+        let router = router
+            .layer(axum::extract::Extension(server_info.clone()))
+            .layer(axum::extract::Extension(self.shutdown.clone()));
+
+        Ok(PreparedServer {
+            listeners: self.listeners,
+            router,
+            graceful_shutdown: self.graceful_shutdown,
+            drain_timeout: self.drain_timeout,
+            shutdown: self.shutdown,
+            shutdown_delay: self.shutdown_delay,
+            #[cfg(all(feature = "auto-reload", unix))]
+            handoff: self.handoff,
+            #[cfg(feature = "tls")]
+            tls: self.tls,
+            local_addrs,
+            server_info,
+        })
     }
 
-    /// Guess the base URL from the local address.
-    fn guess_base_url(local_addr: SocketAddr) -> http::Uri {
+    /// Guess the base URL from the local address, with the `https` scheme if TLS is terminated.
+    fn guess_base_url(local_addr: SocketAddr, secure: bool) -> http::Uri {
         tracing::info!("No base URL set, guessing from local address `{local_addr}`...");
 
+        let scheme = if secure { "https" } else { "http" };
+
         if local_addr.ip().is_unspecified() {
             // If the local address is unspecified, we have to enumerate the network
             // interfaces and take an address from one of them.
@@ -203,7 +797,7 @@ impl Server {
                 "Local address is unspecified, guessing from network interfaces... This is likely not what you want."
             );
 
-            let localhost_base_url = format!("http://localhost:{}", local_addr.port())
+            let localhost_base_url = format!("{scheme}://localhost:{}", local_addr.port())
                 .parse()
                 .expect("hardcoded URL is valid");
 
@@ -228,7 +822,7 @@ impl Server {
                         .next()
                     {
                         Some(ip) => {
-                            return format!("http://{}:{}", ip, local_addr.port())
+                            return format!("{scheme}://{}:{}", ip, local_addr.port())
                                 .parse()
                                 .expect("hardcoded URL is valid");
                         }
@@ -246,7 +840,7 @@ impl Server {
 
             localhost_base_url
         } else {
-            format!("http://{}:{}", local_addr.ip(), local_addr.port())
+            format!("{scheme}://{}:{}", local_addr.ip(), local_addr.port())
                 .parse()
                 .expect("hardcoded URL is valid")
         }
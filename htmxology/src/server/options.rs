@@ -14,6 +14,49 @@ pub struct ServerOptions {
     /// If `HTMXOLOGY_BASE_URL` is set in the environment, it will be read and used as the base URL
     /// when calling `ServerOptions::from_env`.
     pub base_url: Option<http::Uri>,
+
+    /// Whether to redirect requests arriving on another host than the one of the base URL to the
+    /// base URL.
+    ///
+    /// If `HTMXOLOGY_CANONICAL_HOST_REDIRECT` is set to `true` or `1` in the environment, it will
+    /// be enabled when calling `ServerOptions::from_env`.
+    pub canonical_host_redirect: bool,
+
+    /// Whether to redirect plain HTTP requests to the base URL, when it uses HTTPS.
+    ///
+    /// Requests are considered plain HTTP if their scheme, as forwarded by the TLS-terminating
+    /// reverse proxies listed in [`Self::trusted_proxies`], or as sent with HTTP/2, is `http`.
+    /// The forwarding headers of the other peers are ignored.
+    ///
+    /// If `HTMXOLOGY_HTTPS_REDIRECT` is set to `true` or `1` in the environment, it will be
+    /// enabled when calling `ServerOptions::from_env`.
+    pub https_redirect: bool,
+
+    /// The reverse proxies whose forwarding headers are trusted.
+    ///
+    /// If not empty, the base URL of the [`ServerInfo`](crate::ServerInfo) of the requests
+    /// connected from trusted proxies, and their host, are derived from their `Forwarded`
+    /// header, or from their `X-Forwarded-Proto` and `X-Forwarded-Host` headers, so that
+    /// absolute URLs point to the proxy and canonical redirects check the original scheme and
+    /// host. The forwarding headers of the other requests are
+    /// ignored. Connections on Unix domain sockets, which only local processes open, are always
+    /// trusted.
+    ///
+    /// If `HTMXOLOGY_TRUSTED_PROXIES` is set in the environment to a comma-separated list of IP
+    /// addresses or networks (e.g. `127.0.0.1,10.0.0.0/8`), it will be read and used when
+    /// calling `ServerOptions::from_env`.
+    pub trusted_proxies: Vec<super::TrustedProxy>,
+
+    /// The path prefix the application is mounted at behind a reverse proxy, like `/myapp`.
+    ///
+    /// The prefix is stripped from the requests before their route is matched, and added to the
+    /// base URL of the server, and to the routes formatted while serving requests, so that links,
+    /// `HX-Push-Url` headers and redirects include it. Requests outside of the prefix are served
+    /// as-is, for reverse proxies that strip it themselves.
+    ///
+    /// If `HTMXOLOGY_PATH_PREFIX` (or `HTMX_SSR_PATH_PREFIX`) is set in the environment, it will
+    /// be read and used as the path prefix when calling `ServerOptions::from_env`.
+    pub path_prefix: Option<String>,
 }
 
 /// An error that can occur when trying to get the server options from the environment.
@@ -39,12 +82,48 @@ pub enum ServerOptionsFromEnvError {
         #[source]
         err: http::uri::InvalidUri,
     },
+
+    /// An error occurred while trying to get the trusted proxies from the environment.
+    #[error("failed to parse the trusted proxies from environment variable {name}: {err}")]
+    TrustedProxies {
+        /// The name of the environment variable.
+        name: &'static str,
+
+        /// The error that occurred.
+        #[source]
+        err: super::InvalidTrustedProxy,
+    },
+
+    /// A flag environment variable was neither `true`, `false`, `1` nor `0`.
+    #[error("failed to parse the flag from environment variable {name} (was `{value}`)")]
+    Flag {
+        /// The name of the environment variable.
+        name: &'static str,
+
+        /// The value that was attempted to be parsed.
+        value: String,
+    },
 }
 
 impl ServerOptions {
     /// The environment variable name for the base URL.
     pub const HTMXOLOGY_BASE_URL: &'static str = "HTMXOLOGY_BASE_URL";
 
+    /// The environment variable name for the canonical host redirect.
+    pub const HTMXOLOGY_CANONICAL_HOST_REDIRECT: &'static str = "HTMXOLOGY_CANONICAL_HOST_REDIRECT";
+
+    /// The environment variable name for the HTTPS redirect.
+    pub const HTMXOLOGY_HTTPS_REDIRECT: &'static str = "HTMXOLOGY_HTTPS_REDIRECT";
+
+    /// The environment variable name for the trusted proxies.
+    pub const HTMXOLOGY_TRUSTED_PROXIES: &'static str = "HTMXOLOGY_TRUSTED_PROXIES";
+
+    /// The environment variable name for the path prefix.
+    pub const HTMXOLOGY_PATH_PREFIX: &'static str = "HTMXOLOGY_PATH_PREFIX";
+
+    /// The alternative environment variable name for the path prefix.
+    pub const HTMX_SSR_PATH_PREFIX: &'static str = "HTMX_SSR_PATH_PREFIX";
+
     fn env_var(name: &'static str) -> Result<Option<String>, ServerOptionsFromEnvError> {
         match std::env::var(name) {
             Ok(value) => Ok(if value.is_empty() { None } else { Some(value) }),
@@ -55,6 +134,17 @@ impl ServerOptions {
         }
     }
 
+    fn env_flag(name: &'static str) -> Result<bool, ServerOptionsFromEnvError> {
+        match Self::env_var(name)?.as_deref() {
+            None | Some("false" | "0") => Ok(false),
+            Some("true" | "1") => Ok(true),
+            Some(value) => Err(ServerOptionsFromEnvError::Flag {
+                name,
+                value: value.to_owned(),
+            }),
+        }
+    }
+
     /// Get the server options from the environment.
     pub fn from_env() -> Result<Self, ServerOptionsFromEnvError> {
         tracing::info!("Reading HTMX SSR server options from the environment...");
@@ -85,6 +175,37 @@ impl ServerOptions {
             }
         };
 
-        Ok(Self { base_url })
+        let canonical_host_redirect = Self::env_flag(Self::HTMXOLOGY_CANONICAL_HOST_REDIRECT)?;
+        let https_redirect = Self::env_flag(Self::HTMXOLOGY_HTTPS_REDIRECT)?;
+        let trusted_proxies = Self::env_var(Self::HTMXOLOGY_TRUSTED_PROXIES)?
+            .map(|proxies| {
+                proxies
+                    .split(',')
+                    .filter(|proxy| !proxy.trim().is_empty())
+                    .map(str::parse)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|err| ServerOptionsFromEnvError::TrustedProxies {
+                        name: Self::HTMXOLOGY_TRUSTED_PROXIES,
+                        err,
+                    })
+            })
+            .transpose()?
+            .unwrap_or_default();
+        let path_prefix = match Self::env_var(Self::HTMXOLOGY_PATH_PREFIX)? {
+            Some(path_prefix) => Some(path_prefix),
+            None => Self::env_var(Self::HTMX_SSR_PATH_PREFIX)?,
+        };
+
+        if let Some(path_prefix) = &path_prefix {
+            tracing::info!("Mounting the server under the `{path_prefix}` path prefix.");
+        }
+
+        Ok(Self {
+            base_url,
+            canonical_host_redirect,
+            https_redirect,
+            trusted_proxies,
+            path_prefix,
+        })
     }
 }
@@ -0,0 +1,146 @@
+//! Mounting applications under a path prefix.
+
+use std::sync::Arc;
+
+use axum::Router;
+
+use super::ServerOptions;
+
+/// Strips the path prefix from requests, and sets it for the routes they format.
+#[derive(Debug, Clone)]
+pub(crate) struct PathPrefix {
+    /// The normalized path prefix, like `/myapp`.
+    prefix: Arc<str>,
+}
+
+impl PathPrefix {
+    /// Create the path prefix set by the server options, if any.
+    pub(crate) fn from_options(options: &ServerOptions) -> Option<Self> {
+        let prefix = crate::normalize_path_prefix(options.path_prefix.as_deref()?)?;
+
+        Some(Self {
+            prefix: prefix.into(),
+        })
+    }
+
+    /// Add the path prefix to a base URL, unless its path already starts with it.
+    pub(crate) fn base_url(&self, base_url: http::Uri) -> http::Uri {
+        if strip(base_url.path(), &self.prefix).is_some() {
+            return base_url;
+        }
+
+        let mut parts = base_url.into_parts();
+        parts.path_and_query = Some(
+            format!("{}/", self.prefix)
+                .parse()
+                .expect("normalized path prefixes are valid paths"),
+        );
+
+        http::Uri::from_parts(parts).expect("the base URL is made of valid parts")
+    }
+
+    /// Apply the path prefix to a router.
+    pub(crate) fn apply(self, router: Router) -> Router {
+        router.layer(axum::middleware::from_fn_with_state(
+            Arc::new(self),
+            middleware,
+        ))
+    }
+}
+
+/// Strip a path prefix from a path, if it starts with it.
+fn strip<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    match path.strip_prefix(prefix)? {
+        "" => Some("/"),
+        rest if rest.starts_with('/') => Some(rest),
+        _ => None,
+    }
+}
+
+/// The middleware stripping the path prefix from requests.
+///
+/// Requests outside of the path prefix are left untouched, for reverse proxies that strip it
+/// themselves.
+async fn middleware(
+    axum::extract::State(path_prefix): axum::extract::State<Arc<PathPrefix>>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let request = match strip(request.uri().path(), &path_prefix.prefix) {
+        Some(path) => {
+            let path = path.to_owned();
+
+            crate::replace_request_path(request, path)
+        }
+        None => request,
+    };
+
+    crate::with_path_prefix(path_prefix.prefix.clone(), next.run(request)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_service::Service as _;
+
+    use super::*;
+
+    fn path_prefix(prefix: &str) -> Option<PathPrefix> {
+        PathPrefix::from_options(&ServerOptions {
+            path_prefix: Some(prefix.to_owned()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_from_options() {
+        assert!(PathPrefix::from_options(&ServerOptions::default()).is_none());
+        assert!(path_prefix("/").is_none());
+        assert_eq!(&*path_prefix("myapp/").unwrap().prefix, "/myapp");
+    }
+
+    #[test]
+    fn test_base_url() {
+        let path_prefix = path_prefix("/myapp").unwrap();
+
+        assert_eq!(
+            path_prefix.base_url(http::Uri::from_static("https://example.com")),
+            http::Uri::from_static("https://example.com/myapp/")
+        );
+        assert_eq!(
+            path_prefix.base_url(http::Uri::from_static("https://example.com/myapp/")),
+            http::Uri::from_static("https://example.com/myapp/")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_middleware() {
+        let router = axum::Router::new().fallback(|uri: http::Uri| async move {
+            format!(
+                "{uri} {}",
+                crate::path_prefix().as_deref().unwrap_or_default()
+            )
+        });
+        let mut router = path_prefix("/myapp").unwrap().apply(router);
+
+        for (uri, expected) in [
+            ("/myapp/posts?page=2", "/posts?page=2 /myapp"),
+            ("/myapp", "/ /myapp"),
+            ("/myapps", "/myapps /myapp"),
+            ("/posts", "/posts /myapp"),
+        ] {
+            let response = router
+                .call(
+                    http::Request::get(uri)
+                        .body(axum::body::Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+
+            assert_eq!(body, expected);
+        }
+    }
+}
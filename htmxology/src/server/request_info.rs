@@ -0,0 +1,211 @@
+//! Per-request server information.
+
+use std::{convert::Infallible, sync::Arc};
+
+use super::ServerInfo;
+use super::forwarded::ForwardedOrigin;
+use crate::Route;
+
+/// The scheme, host and path prefix a request was sent to.
+///
+/// Unlike the base URL of the [`ServerInfo`], which is the same for all the requests, it reflects
+/// the host of each request, so that applications serving several domains from one process
+/// generate absolute URLs on the domain of the request:
+///
+/// ```rust,ignore
+/// async fn handle_request(&self, route: Self::Route, _htmx: htmx::Request, parts: Parts, ...) {
+///     let info = RequestInfo::from_parts(&parts);
+///     let url = info.absolute_url(&AppRoute::Home);
+///     // ...
+/// }
+/// ```
+///
+/// The host is taken from the request URI for HTTP/2 requests, or from the `Host` header, as
+/// forwarded by [trusted proxies](crate::ServerOptions::trusted_proxies). The scheme and the
+/// path prefix are those of the base URL of the server, which trusted proxies forward too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestInfo {
+    /// The scheme of the request.
+    scheme: http::uri::Scheme,
+
+    /// The host of the request, with its port, if any.
+    authority: http::uri::Authority,
+
+    /// The path prefix the server is mounted at.
+    path_prefix: String,
+}
+
+impl RequestInfo {
+    /// Get the information of a request from its parts.
+    ///
+    /// The scheme and host forwarded by trusted proxies take precedence over the ones of the
+    /// request.
+    ///
+    /// Falls back to the base URL of the server for the requests without a valid host, and to
+    /// `http://localhost/` outside of a server.
+    pub fn from_parts(parts: &http::request::Parts) -> Self {
+        let base_url = parts
+            .extensions
+            .get::<Arc<ServerInfo>>()
+            .map(|server_info| &server_info.base_url);
+
+        let origin = parts.extensions.get::<ForwardedOrigin>();
+
+        let scheme = origin
+            .and_then(|origin| origin.scheme.as_ref())
+            .or(parts.uri.scheme())
+            .or(base_url.and_then(http::Uri::scheme))
+            .cloned()
+            .unwrap_or(http::uri::Scheme::HTTP);
+        let authority = origin
+            .and_then(|origin| origin.authority.clone())
+            .or_else(|| parts.uri.authority().cloned())
+            .or_else(|| {
+                parts
+                    .headers
+                    .get(http::header::HOST)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse().ok())
+            })
+            .or_else(|| base_url.and_then(http::Uri::authority).cloned())
+            .unwrap_or_else(|| http::uri::Authority::from_static("localhost"));
+        let path_prefix = base_url.map_or("/", http::Uri::path).to_owned();
+
+        Self {
+            scheme,
+            authority,
+            path_prefix,
+        }
+    }
+
+    /// Get the scheme of the request.
+    pub fn scheme(&self) -> &http::uri::Scheme {
+        &self.scheme
+    }
+
+    /// Get the host of the request, without its port.
+    pub fn host(&self) -> &str {
+        self.authority.host()
+    }
+
+    /// Get the host of the request, with its port, if any.
+    pub fn authority(&self) -> &http::uri::Authority {
+        &self.authority
+    }
+
+    /// Get the path prefix the server is mounted at, like `/` or `/app/`.
+    pub fn path_prefix(&self) -> &str {
+        &self.path_prefix
+    }
+
+    /// Get the base URL of the server, on the host of the request.
+    pub fn base_url(&self) -> http::Uri {
+        http::Uri::builder()
+            .scheme(self.scheme.clone())
+            .authority(self.authority.clone())
+            .path_and_query(self.path_prefix.as_str())
+            .build()
+            .expect("the base URL is made of valid parts")
+    }
+
+    /// Get an absolute URL for a route, on the host of the request.
+    pub fn absolute_url(&self, route: &impl Route) -> String {
+        route.to_absolute_url(&self.base_url())
+    }
+}
+
+impl<S: Send + Sync> axum::extract::FromRequestParts<S> for RequestInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_parts(parts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(
+        request: http::request::Builder,
+        base_url: Option<&'static str>,
+    ) -> http::request::Parts {
+        let (mut parts, ()) = request.body(()).unwrap().into_parts();
+
+        if let Some(base_url) = base_url {
+            parts.extensions.insert(Arc::new(ServerInfo {
+                base_url: http::Uri::from_static(base_url),
+            }));
+        }
+
+        parts
+    }
+
+    #[test]
+    fn test_from_host_header() {
+        let info = RequestInfo::from_parts(&parts(
+            http::Request::get("/posts").header(http::header::HOST, "tenant.example.com:8443"),
+            Some("https://example.com/app/"),
+        ));
+
+        assert_eq!(info.scheme(), &http::uri::Scheme::HTTPS);
+        assert_eq!(info.host(), "tenant.example.com");
+        assert_eq!(info.authority().as_str(), "tenant.example.com:8443");
+        assert_eq!(info.path_prefix(), "/app/");
+        assert_eq!(
+            info.base_url(),
+            http::Uri::from_static("https://tenant.example.com:8443/app/")
+        );
+    }
+
+    #[test]
+    fn test_from_absolute_uri() {
+        let info = RequestInfo::from_parts(&parts(
+            http::Request::get("http://tenant.example.com/posts")
+                .header(http::header::HOST, "ignored.example.com"),
+            Some("https://example.com"),
+        ));
+
+        assert_eq!(
+            info.base_url(),
+            http::Uri::from_static("http://tenant.example.com/")
+        );
+    }
+
+    #[test]
+    fn test_from_forwarded_origin() {
+        let mut parts = parts(
+            http::Request::get("http://backend:8000/posts"),
+            Some("http://backend:8000/app/"),
+        );
+        parts.extensions.insert(ForwardedOrigin {
+            scheme: Some(http::uri::Scheme::HTTPS),
+            authority: Some(http::uri::Authority::from_static("tenant.example.com")),
+        });
+
+        assert_eq!(
+            RequestInfo::from_parts(&parts).base_url(),
+            http::Uri::from_static("https://tenant.example.com/app/")
+        );
+    }
+
+    #[test]
+    fn test_fallbacks() {
+        let info = RequestInfo::from_parts(&parts(
+            http::Request::get("/posts"),
+            Some("https://example.com/app/"),
+        ));
+
+        assert_eq!(
+            info.base_url(),
+            http::Uri::from_static("https://example.com/app/")
+        );
+
+        let info = RequestInfo::from_parts(&parts(http::Request::get("/posts"), None));
+
+        assert_eq!(info.base_url(), http::Uri::from_static("http://localhost/"));
+    }
+}
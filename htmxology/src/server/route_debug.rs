@@ -0,0 +1,274 @@
+//! A development page listing the routes of a controller router.
+
+use std::sync::Arc;
+
+use crate::{
+    ParseError, Route, RouteEntry,
+    htmx::{escape_attribute_value, escape_text},
+};
+
+use super::ControllerRouterDescription;
+
+/// A function parsing a URL into the name of the matching variant.
+type ParseFn = dyn Fn(&http::Uri, &http::Method) -> Result<String, ParseError> + Send + Sync;
+
+/// A type-erased parser of the routes of a controller, mounted under a path prefix.
+#[derive(Clone)]
+pub(crate) struct RouteMatcher {
+    /// The path prefix the controller is mounted at, without a trailing slash.
+    prefix: String,
+
+    /// Parse a URL into the name of the matching variant.
+    parse: Arc<ParseFn>,
+}
+
+impl std::fmt::Debug for RouteMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RouteMatcher")
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RouteMatcher {
+    /// Create the matcher of a route type.
+    pub(crate) fn new<R: Route>() -> Self {
+        Self {
+            prefix: String::new(),
+            parse: Arc::new(|uri, method| {
+                R::from_uri(uri, method)
+                    .map(|route| route.variant_name().into_owned())
+                    .map_err(ParseError::with_suggestions::<R>)
+            }),
+        }
+    }
+
+    /// Mount the matcher under a path prefix.
+    pub(crate) fn nested(mut self, prefix: &str) -> Self {
+        self.prefix = format!("{}{}", prefix.trim_end_matches('/'), self.prefix);
+        self
+    }
+
+    /// Parse a URL, if it is under the prefix of the matcher.
+    fn parse(&self, uri: &http::Uri, method: &http::Method) -> Option<Result<String, ParseError>> {
+        let path = uri.path().strip_prefix(&self.prefix)?;
+
+        if !path.is_empty() && !path.starts_with('/') {
+            return None;
+        }
+
+        let path_and_query = match uri.query() {
+            Some(query) => format!("/{}?{query}", path.trim_start_matches('/')),
+            None => format!("/{}", path.trim_start_matches('/')),
+        };
+        let mut parts = uri.clone().into_parts();
+        parts.path_and_query = Some(path_and_query.parse().ok()?);
+
+        Some((self.parse)(&http::Uri::from_parts(parts).ok()?, method))
+    }
+}
+
+/// The query of the route debug page, to test a URL against the routes.
+#[derive(Debug, Default, serde::Deserialize)]
+pub(crate) struct RouteDebugQuery {
+    url: Option<String>,
+    method: Option<String>,
+}
+
+/// The route debug page, listing the routes of a controller router and testing URLs against
+/// them.
+pub(crate) struct RouteDebugPage<'a> {
+    pub(crate) description: &'a ControllerRouterDescription,
+    pub(crate) matchers: &'a [RouteMatcher],
+    pub(crate) query: RouteDebugQuery,
+}
+
+impl RouteDebugPage<'_> {
+    /// Test the URL of the query against the routes, returning an HTML description of the
+    /// outcome.
+    fn test_url(&self, url: &str, method: &str) -> String {
+        let (Ok(uri), Ok(method)) = (
+            url.parse::<http::Uri>(),
+            http::Method::from_bytes(method.as_bytes()),
+        ) else {
+            return "<p>Invalid URL or method.</p>".to_owned();
+        };
+
+        let mut errors = Vec::new();
+
+        for matcher in self.matchers {
+            match matcher.parse(&uri, &method) {
+                Some(Ok(variant)) => {
+                    let prefix = if matcher.prefix.is_empty() {
+                        String::new()
+                    } else {
+                        format!(", under <code>{}</code>", escape_text(&matcher.prefix))
+                    };
+
+                    return format!(
+                        "<p>Matches <code>{}</code>{prefix}.</p>",
+                        escape_text(&variant)
+                    );
+                }
+                Some(Err(err)) => errors.push((matcher.prefix.len(), err)),
+                None => {}
+            }
+        }
+
+        // The error of the most specific controller is the most relevant one.
+        let Some((_, err)) = errors.into_iter().max_by_key(|(prefix_len, _)| *prefix_len) else {
+            return "<p>No controller is mounted under this URL.</p>".to_owned();
+        };
+
+        let mut html = format!("<p>{}</p>", escape_text(&err.to_string()));
+
+        if !err.suggestions().is_empty() {
+            html.push_str("<p>Nearest routes:</p><ul>");
+
+            for suggestion in err.suggestions() {
+                html.push_str(&format!(
+                    "<li><code>{} {}</code> ({})</li>",
+                    suggestion.method,
+                    escape_text(&suggestion.pattern),
+                    escape_text(&suggestion.variant),
+                ));
+            }
+
+            html.push_str("</ul>");
+        }
+
+        html
+    }
+}
+
+/// List the routes of a router description and of its nested routers, with their full patterns.
+fn flatten_routes(description: &ControllerRouterDescription, prefix: &str) -> Vec<RouteEntry> {
+    let prefix = prefix.trim_end_matches('/');
+    let mut routes: Vec<_> = description
+        .routes
+        .iter()
+        .map(|entry| RouteEntry {
+            pattern: format!("{prefix}{}", entry.pattern),
+            ..entry.clone()
+        })
+        .collect();
+
+    for nested in &description.nested {
+        routes.extend(flatten_routes(
+            &nested.router,
+            &format!("{prefix}{}", nested.prefix),
+        ));
+    }
+
+    routes
+}
+
+/// List the endpoints of a router description and of its nested routers, with their full paths.
+fn flatten_endpoints(description: &ControllerRouterDescription, prefix: &str) -> Vec<String> {
+    let prefix = prefix.trim_end_matches('/');
+    let mut endpoints: Vec<_> = description
+        .endpoints
+        .iter()
+        .map(|endpoint| format!("{prefix}{endpoint}"))
+        .collect();
+
+    for nested in &description.nested {
+        endpoints.extend(flatten_endpoints(
+            &nested.router,
+            &format!("{prefix}{}", nested.prefix),
+        ));
+    }
+
+    endpoints
+}
+
+impl std::fmt::Display for RouteDebugPage<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let url = self.query.url.as_deref().unwrap_or_default();
+        let method = self.query.method.as_deref().unwrap_or("GET");
+
+        write!(
+            f,
+            r#"<!DOCTYPE html><html><head><title>Routes</title></head><body><h1>Routes</h1><form method="get"><input name="method" value="{}" size="8"> <input name="url" value="{}" placeholder="/blog/42" size="60"> <button type="submit">Test</button></form>"#,
+            escape_attribute_value(method),
+            escape_attribute_value(url),
+        )?;
+
+        if !url.is_empty() {
+            f.write_str(&self.test_url(url, method))?;
+        }
+
+        f.write_str("<table><thead><tr><th>Method</th><th>Pattern</th><th>Host</th><th>Variant</th></tr></thead><tbody>")?;
+
+        for entry in flatten_routes(self.description, "") {
+            write!(
+                f,
+                "<tr><td>{}</td><td><code>{}</code></td><td>{}</td><td>{}</td></tr>",
+                escape_text(&entry.method),
+                escape_text(&entry.pattern),
+                escape_text(entry.host.as_deref().unwrap_or_default()),
+                escape_text(&entry.variant),
+            )?;
+        }
+
+        f.write_str("</tbody></table>")?;
+
+        let endpoints = flatten_endpoints(self.description, "");
+
+        if !endpoints.is_empty() {
+            f.write_str("<h2>Endpoints</h2><ul>")?;
+
+            for endpoint in endpoints {
+                write!(f, "<li><code>{}</code></li>", escape_text(&endpoint))?;
+            }
+
+            f.write_str("</ul>")?;
+        }
+
+        if !self.description.layers.is_empty() {
+            f.write_str("<h2>Layers</h2><ol>")?;
+
+            for layer in &self.description.layers {
+                write!(f, "<li><code>{}</code></li>", escape_text(layer))?;
+            }
+
+            f.write_str("</ol>")?;
+        }
+
+        f.write_str(
+            "<p><small>This page exposes the internals of the application, and should only be mounted in development.</small></p></body></html>",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NestedRouterDescription;
+
+    #[test]
+    fn test_flatten() {
+        let description = ControllerRouterDescription {
+            routes: vec![RouteEntry::new("Home", "GET", "/")],
+            endpoints: vec!["/events".to_owned()],
+            nested: vec![NestedRouterDescription {
+                prefix: "/v2/".to_owned(),
+                router: ControllerRouterDescription {
+                    routes: vec![RouteEntry::new("Post", "GET", "/posts/{id}")],
+                    endpoints: vec!["/ws".to_owned()],
+                    ..Default::default()
+                },
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            flatten_routes(&description, ""),
+            [
+                RouteEntry::new("Home", "GET", "/"),
+                RouteEntry::new("Post", "GET", "/v2/posts/{id}"),
+            ]
+        );
+        assert_eq!(flatten_endpoints(&description, ""), ["/events", "/v2/ws"]);
+    }
+}
@@ -0,0 +1,118 @@
+//! Programmatic graceful shutdown.
+
+use std::sync::Arc;
+
+/// A handle to trigger the graceful shutdown of a server.
+///
+/// Get it from [`ServerBuilder::shutdown_handle`](crate::ServerBuilder::shutdown_handle) before
+/// serving, to stop the server from another task, like a Kubernetes `preStop` hook or an admin
+/// endpoint:
+///
+/// ```rust,ignore
+/// let builder = Server::builder(listener).with_drain_timeout(Duration::from_secs(30));
+/// let shutdown = builder.shutdown_handle();
+///
+/// tokio::spawn(async move {
+///     wait_for_termination().await;
+///     shutdown.shutdown();
+/// });
+///
+/// builder.build().serve(router).await?;
+/// ```
+///
+/// The other graceful shutdown signals of the server, like `ctrl-c`, trigger it too, so that
+/// [readiness endpoints](crate::ControllerRouter::with_readiness_endpoint) report the server as
+/// unavailable as soon as it starts shutting down.
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+    /// Whether the shutdown was triggered.
+    state: Arc<tokio::sync::watch::Sender<bool>>,
+}
+
+impl Default for ShutdownHandle {
+    fn default() -> Self {
+        Self {
+            state: Arc::new(tokio::sync::watch::Sender::new(false)),
+        }
+    }
+}
+
+impl ShutdownHandle {
+    /// Trigger the graceful shutdown.
+    ///
+    /// Triggering it several times has no further effect.
+    pub fn shutdown(&self) {
+        self.state.send_replace(true);
+    }
+
+    /// Check whether the graceful shutdown was triggered.
+    pub fn is_shutting_down(&self) -> bool {
+        *self.state.borrow()
+    }
+
+    /// Wait until the graceful shutdown is triggered.
+    ///
+    /// Returns immediately if it already was.
+    pub async fn wait(&self) {
+        // The sender is alive as long as `self`, so this can't fail.
+        let _ = self
+            .state
+            .subscribe()
+            .wait_for(|shutting_down| *shutting_down)
+            .await;
+    }
+}
+
+/// Serve the readiness of the server, as reported by its shutdown handle.
+pub(crate) async fn readiness(
+    shutdown: Option<axum::extract::Extension<ShutdownHandle>>,
+) -> (http::StatusCode, &'static str) {
+    match shutdown {
+        Some(axum::extract::Extension(shutdown)) if shutdown.is_shutting_down() => {
+            (http::StatusCode::SERVICE_UNAVAILABLE, "shutting down")
+        }
+        _ => (http::StatusCode::OK, "ready"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown_handle() {
+        let handle = ShutdownHandle::default();
+        let other = handle.clone();
+
+        assert!(!handle.is_shutting_down());
+
+        let waiting = tokio::spawn(async move { other.wait().await });
+
+        handle.shutdown();
+        waiting.await.unwrap();
+
+        assert!(handle.is_shutting_down());
+
+        // Waiting after the shutdown returns immediately.
+        handle.wait().await;
+    }
+
+    #[tokio::test]
+    async fn test_readiness() {
+        assert_eq!(readiness(None).await.0, http::StatusCode::OK);
+
+        let handle = ShutdownHandle::default();
+        assert_eq!(
+            readiness(Some(axum::extract::Extension(handle.clone())))
+                .await
+                .0,
+            http::StatusCode::OK
+        );
+
+        handle.shutdown();
+        assert_eq!(
+            readiness(Some(axum::extract::Extension(handle))).await.0,
+            http::StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+}
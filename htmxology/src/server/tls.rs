@@ -0,0 +1,197 @@
+//! TLS termination.
+
+use std::{net::SocketAddr, path::Path, sync::Arc, time::Duration};
+
+use tokio_rustls::rustls::{
+    self,
+    pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject as _},
+};
+
+/// The time clients have to complete the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An error that can occur when loading the TLS configuration of a server.
+#[derive(Debug, thiserror::Error)]
+pub enum TlsError {
+    /// The certificate chain could not be read.
+    #[error("failed to read the certificate chain: {0}")]
+    Certificates(rustls::pki_types::pem::Error),
+
+    /// The private key could not be read.
+    #[error("failed to read the private key: {0}")]
+    PrivateKey(rustls::pki_types::pem::Error),
+
+    /// The certificate chain and private key don't make a valid configuration.
+    #[error("invalid TLS configuration: {0}")]
+    Config(#[from] rustls::Error),
+}
+
+/// Load a TLS server configuration from PEM-encoded certificate chain and private key files.
+pub(crate) fn load_config(cert: &Path, key: &Path) -> Result<Arc<rustls::ServerConfig>, TlsError> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(Iterator::collect::<Result<Vec<_>, _>>)
+        .map_err(TlsError::Certificates)?;
+    let key = PrivateKeyDer::from_pem_file(key).map_err(TlsError::PrivateKey)?;
+
+    let mut config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_no_client_auth()
+    .with_single_cert(certs, key)?;
+
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(Arc::new(config))
+}
+
+/// A listener accepting TLS connections.
+///
+/// Handshakes are performed in their own tasks, so that slow clients don't hold back the other
+/// connections.
+pub(crate) struct TlsListener {
+    /// The local address of the underlying TCP listener.
+    local_addr: SocketAddr,
+
+    /// The connections whose handshake completed.
+    connections: tokio::sync::mpsc::Receiver<(
+        tokio_rustls::server::TlsStream<tokio::net::TcpStream>,
+        SocketAddr,
+    )>,
+}
+
+impl TlsListener {
+    /// Accept TLS connections on a TCP listener.
+    ///
+    /// The TCP listener is closed once the TLS listener is dropped.
+    pub(crate) fn new(
+        listener: tokio::net::TcpListener,
+        config: Arc<rustls::ServerConfig>,
+    ) -> std::io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let acceptor = tokio_rustls::TlsAcceptor::from(config);
+        let (sender, connections) = tokio::sync::mpsc::channel(64);
+
+        tokio::spawn(async move {
+            let mut listener = listener;
+
+            loop {
+                let (stream, addr) = tokio::select! {
+                    () = sender.closed() => break,
+                    result = axum::serve::Listener::accept(&mut listener) => result,
+                };
+
+                let acceptor = acceptor.clone();
+                let sender = sender.clone();
+
+                tokio::spawn(async move {
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(stream)) => {
+                            let _ = sender.send((stream, addr)).await;
+                        }
+                        Ok(Err(err)) => {
+                            tracing::debug!("TLS handshake with `{addr}` failed: {err}");
+                        }
+                        Err(_) => {
+                            tracing::debug!("TLS handshake with `{addr}` timed out");
+                        }
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            local_addr,
+            connections,
+        })
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = tokio_rustls::server::TlsStream<tokio::net::TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.connections.recv().await {
+            Some(connection) => connection,
+            // The accepting task only stops once the listener is dropped.
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    use super::*;
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn test_load_config() {
+        assert!(load_config(&fixture("localhost.crt"), &fixture("localhost.key")).is_ok());
+        assert!(matches!(
+            load_config(&fixture("missing.crt"), &fixture("localhost.key")),
+            Err(TlsError::Certificates(_))
+        ));
+        assert!(matches!(
+            load_config(&fixture("localhost.crt"), &fixture("localhost.crt")),
+            Err(TlsError::PrivateKey(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_listener() {
+        let config = load_config(&fixture("localhost.crt"), &fixture("localhost.key")).unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let listener = TlsListener::new(listener, config).unwrap();
+        let addr = axum::serve::Listener::local_addr(&listener).unwrap();
+        let router = axum::Router::new().route("/", axum::routing::get(|| async { "Hello" }));
+
+        tokio::spawn(axum::serve(listener, router).into_future());
+
+        let mut roots = rustls::RootCertStore::empty();
+
+        for cert in CertificateDer::pem_file_iter(fixture("localhost.crt")).unwrap() {
+            roots.add(cert.unwrap()).unwrap();
+        }
+
+        let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(config));
+
+        // Clients failing their handshake don't prevent the others from connecting.
+        let _plain = tokio::net::TcpStream::connect(addr).await.unwrap();
+
+        let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let mut stream = connector
+            .connect("localhost".try_into().unwrap(), stream)
+            .await
+            .unwrap();
+
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("Hello"));
+    }
+}
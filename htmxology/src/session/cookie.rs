@@ -0,0 +1,124 @@
+//! Signed cookie session store.
+
+use base64::Engine;
+use hmac::Mac;
+
+use super::{SessionData, SessionError, SessionStore};
+
+/// The maximum size of a cookie value accepted by most browsers.
+const MAX_COOKIE_SIZE: usize = 4000;
+
+type HmacSha256 = hmac::Hmac<sha2::Sha256>;
+
+/// A session store keeping the data of sessions in the session cookie itself, signed with a
+/// secret key.
+///
+/// Clients can read the session data, but can't tamper with it. As the whole session is sent
+/// with every request, sessions should only hold a few small values, like a user identifier:
+/// saving a session larger than about 4 KB fails.
+///
+/// Destroyed sessions can't be revoked: a client holding a copy of an old cookie can still use
+/// it. Use a server-side store, like [`MemorySessionStore`](super::MemorySessionStore), if that
+/// matters.
+#[derive(Clone)]
+pub struct CookieSessionStore {
+    key: Vec<u8>,
+}
+
+impl std::fmt::Debug for CookieSessionStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CookieSessionStore").finish_non_exhaustive()
+    }
+}
+
+impl CookieSessionStore {
+    /// Create a store signing cookies with the specified secret key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is shorter than 32 bytes.
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        let key = key.into();
+
+        assert!(
+            key.len() >= 32,
+            "session signing keys should be at least 32 bytes long"
+        );
+
+        Self { key }
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        HmacSha256::new_from_slice(&self.key).expect("HMAC keys can have any size")
+    }
+}
+
+impl SessionStore for CookieSessionStore {
+    async fn load(&self, cookie: &str) -> Option<SessionData> {
+        let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        let (payload, signature) = cookie.rsplit_once('.')?;
+        let signature = engine.decode(signature).ok()?;
+
+        let mut mac = self.mac();
+        mac.update(payload.as_bytes());
+
+        if mac.verify_slice(&signature).is_err() {
+            tracing::warn!("Rejected a session cookie with an invalid signature");
+
+            return None;
+        }
+
+        serde_json::from_slice(&engine.decode(payload).ok()?).ok()
+    }
+
+    async fn save(&self, _cookie: Option<&str>, data: SessionData) -> Result<String, SessionError> {
+        let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        let payload = engine.encode(serde_json::to_vec(&data)?);
+
+        let mut mac = self.mac();
+        mac.update(payload.as_bytes());
+
+        let cookie = format!("{payload}.{}", engine.encode(mac.finalize().into_bytes()));
+
+        if cookie.len() > MAX_COOKIE_SIZE {
+            return Err(SessionError::TooLarge { size: cookie.len() });
+        }
+
+        Ok(cookie)
+    }
+
+    async fn delete(&self, _cookie: &str) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_signed_cookie() {
+        let store = CookieSessionStore::new([7; 32]);
+        let mut data = SessionData::new();
+        data.insert("user".to_owned(), "alice".into());
+
+        let cookie = store.save(None, data.clone()).await.unwrap();
+
+        assert_eq!(store.load(&cookie).await, Some(data));
+
+        let tampered = cookie.replacen(&cookie[..4], "eyJ4", 1);
+
+        assert_eq!(store.load(&tampered).await, None);
+        assert_eq!(CookieSessionStore::new([8; 32]).load(&cookie).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_too_large() {
+        let store = CookieSessionStore::new([7; 32]);
+        let mut data = SessionData::new();
+        data.insert("blob".to_owned(), "x".repeat(4000).into());
+
+        assert!(matches!(
+            store.save(None, data).await,
+            Err(SessionError::TooLarge { .. })
+        ));
+    }
+}
@@ -0,0 +1,548 @@
+//! In-memory session store.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use sha2::Digest;
+use tracing::debug;
+
+use super::{SessionData, SessionError, SessionStore};
+
+/// A session store keeping sessions in memory, identified by a random cookie value.
+///
+/// Sessions are lost when the server restarts, unless handed off to its successor with the
+/// [`Handoff`](crate::Handoff) of the server, and are not shared between server instances.
+///
+/// Expired sessions are removed when they are loaded, or by [`MemorySessionStore::sweep`], which
+/// can be run periodically with [`MemorySessionStore::spawn_sweeper`]. The store can also be
+/// capped in sessions or in size, in which case the least recently used sessions are evicted
+/// first.
+///
+/// Cloning a store is cheap, and the clones share the same sessions.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySessionStore {
+    ttl: Option<Duration>,
+    max_entries: Option<usize>,
+    max_size: Option<usize>,
+    state: Arc<Mutex<State>>,
+}
+
+/// A snapshot of the metrics of a [`MemorySessionStore`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemorySessionStoreMetrics {
+    /// The number of stored sessions.
+    pub entries: usize,
+
+    /// The approximate size of the stored sessions, in bytes.
+    pub size: usize,
+
+    /// The number of sessions loaded from the store.
+    pub hits: u64,
+
+    /// The number of loads of unknown or expired sessions.
+    pub misses: u64,
+
+    /// The number of sessions removed because they expired.
+    pub expirations: u64,
+
+    /// The number of sessions evicted to respect the store caps.
+    pub evictions: u64,
+}
+
+/// The description of a session held in a [`MemorySessionStore`], as returned by
+/// [`MemorySessionStore::entries`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemorySessionEntry {
+    /// An opaque handle of the session, derived from its identifier without revealing it.
+    pub handle: String,
+
+    /// The keys of the session data.
+    pub keys: Vec<String>,
+
+    /// The remaining time to live of the session, if it expires, which is zero if it expired.
+    pub expires_in: Option<Duration>,
+}
+
+#[derive(Debug)]
+struct StoredSession {
+    data: SessionData,
+    expires_at: Option<Instant>,
+
+    /// The approximate size of the session, in bytes.
+    size: usize,
+
+    /// The tick of the last access to the session.
+    tick: u64,
+}
+
+impl StoredSession {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    sessions: HashMap<String, StoredSession>,
+
+    /// The identifiers of the sessions, by last access tick.
+    lru: BTreeMap<u64, String>,
+    tick: u64,
+    metrics: MemorySessionStoreMetrics,
+}
+
+impl State {
+    /// Load a session that did not expire, marking it as recently used.
+    ///
+    /// Expired sessions are removed.
+    fn load(&mut self, id: &str) -> Option<SessionData> {
+        let Some(session) = self.sessions.get(id) else {
+            self.metrics.misses += 1;
+
+            return None;
+        };
+
+        if session.is_expired(Instant::now()) {
+            self.remove(id);
+            self.metrics.expirations += 1;
+            self.metrics.misses += 1;
+
+            return None;
+        }
+
+        self.metrics.hits += 1;
+        self.touch(id);
+
+        self.sessions.get(id).map(|session| session.data.clone())
+    }
+
+    /// Insert or replace a session, as the most recently used one.
+    fn insert(&mut self, id: String, data: SessionData, expires_at: Option<Instant>) {
+        self.remove(&id);
+
+        // The size of a session is approximated by the size of its identifier and its JSON data.
+        let size = id.len() + serde_json::to_string(&data).map_or(0, |data| data.len());
+
+        self.tick += 1;
+        self.lru.insert(self.tick, id.clone());
+        self.metrics.entries += 1;
+        self.metrics.size += size;
+        self.sessions.insert(
+            id,
+            StoredSession {
+                data,
+                expires_at,
+                size,
+                tick: self.tick,
+            },
+        );
+    }
+
+    /// Mark a session as recently used.
+    fn touch(&mut self, id: &str) {
+        let Some(session) = self.sessions.get_mut(id) else {
+            return;
+        };
+
+        self.lru.remove(&session.tick);
+        self.tick += 1;
+        session.tick = self.tick;
+        self.lru.insert(self.tick, id.to_owned());
+    }
+
+    fn remove(&mut self, id: &str) -> Option<StoredSession> {
+        let session = self.sessions.remove(id)?;
+
+        self.lru.remove(&session.tick);
+        self.metrics.entries -= 1;
+        self.metrics.size -= session.size;
+
+        Some(session)
+    }
+
+    /// Remove the expired sessions, returning how many were removed.
+    fn sweep(&mut self) -> usize {
+        let now = Instant::now();
+        let expired: Vec<_> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.is_expired(now))
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in &expired {
+            self.remove(id);
+        }
+
+        self.metrics.expirations += expired.len() as u64;
+
+        expired.len()
+    }
+
+    /// Evict the least recently used sessions until the caps are respected.
+    fn evict(&mut self, max_entries: Option<usize>, max_size: Option<usize>) {
+        while max_entries.is_some_and(|max| self.metrics.entries > max)
+            || max_size.is_some_and(|max| self.metrics.size > max)
+        {
+            let Some((_, id)) = self.lru.pop_first() else {
+                break;
+            };
+
+            if self.remove(&id).is_some() {
+                self.metrics.evictions += 1;
+
+                debug!("Evicted session `{}` from the session store", handle(&id));
+            }
+        }
+    }
+}
+
+impl MemorySessionStore {
+    /// Expire sessions that have not been saved for the specified duration.
+    ///
+    /// By default, sessions never expire, and are only removed when they are destroyed.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Set the maximum number of stored sessions.
+    ///
+    /// Once reached, the least recently used sessions are evicted, which logs their users out.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Set the maximum approximate size of the stored sessions, in bytes.
+    ///
+    /// Once reached, the least recently used sessions are evicted, which logs their users out.
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Get the number of stored sessions, including the expired ones not removed yet.
+    pub fn len(&self) -> usize {
+        self.state().sessions.len()
+    }
+
+    /// Check whether the store holds no session.
+    pub fn is_empty(&self) -> bool {
+        self.state().sessions.is_empty()
+    }
+
+    /// Get a snapshot of the metrics of the store.
+    pub fn metrics(&self) -> MemorySessionStoreMetrics {
+        self.state().metrics
+    }
+
+    /// Remove the expired sessions, returning how many were removed.
+    pub fn sweep(&self) -> usize {
+        self.state().sweep()
+    }
+
+    /// Spawn a task sweeping expired sessions from the store at the specified interval.
+    ///
+    /// The task stops on its own once the store and all its clones are dropped.
+    ///
+    /// # Panics
+    ///
+    /// If called outside of a Tokio runtime, the call will panic.
+    pub fn spawn_sweeper(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let state = Arc::downgrade(&self.state);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                interval.tick().await;
+
+                let Some(state) = state.upgrade() else {
+                    break;
+                };
+
+                let swept = state.lock().unwrap_or_else(|err| err.into_inner()).sweep();
+
+                if swept > 0 {
+                    debug!("Swept {swept} expired session(s) from the session store");
+                }
+            }
+        })
+    }
+
+    /// Describe the stored sessions, ordered by handle.
+    pub fn entries(&self) -> Vec<MemorySessionEntry> {
+        let now = Instant::now();
+        let mut entries: Vec<_> = self
+            .state()
+            .sessions
+            .iter()
+            .map(|(id, session)| MemorySessionEntry {
+                handle: handle(id),
+                keys: session.data.keys().cloned().collect(),
+                expires_in: session
+                    .expires_at
+                    .map(|expires_at| expires_at.saturating_duration_since(now)),
+            })
+            .collect();
+
+        entries.sort_by(|a, b| a.handle.cmp(&b.handle));
+
+        entries
+    }
+
+    /// Remove the session with the specified handle, as described by
+    /// [`MemorySessionStore::entries`].
+    ///
+    /// Returns whether a session was removed.
+    pub fn purge(&self, handle_to_purge: &str) -> bool {
+        let mut state = self.state();
+        let Some(id) = state
+            .sessions
+            .keys()
+            .find(|id| handle(id) == handle_to_purge)
+            .cloned()
+        else {
+            return false;
+        };
+
+        state.remove(&id).is_some()
+    }
+
+    /// Remove all the sessions.
+    ///
+    /// Returns the number of removed sessions.
+    pub fn purge_all(&self) -> usize {
+        let mut state = self.state();
+        let len = state.sessions.len();
+
+        state.sessions.clear();
+        state.lru.clear();
+        state.metrics.entries = 0;
+        state.metrics.size = 0;
+
+        len
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("sessions lock should not be poisoned")
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    async fn load(&self, cookie: &str) -> Option<SessionData> {
+        self.state().load(cookie)
+    }
+
+    async fn save(&self, cookie: Option<&str>, data: SessionData) -> Result<String, SessionError> {
+        let id = cookie.map(str::to_owned).unwrap_or_else(|| {
+            format!(
+                "{}{}",
+                uuid::Uuid::new_v4().simple(),
+                uuid::Uuid::new_v4().simple()
+            )
+        });
+
+        let mut state = self.state();
+
+        state.insert(id.clone(), data, self.ttl.map(|ttl| Instant::now() + ttl));
+        state.evict(self.max_entries, self.max_size);
+
+        Ok(id)
+    }
+
+    async fn delete(&self, cookie: &str) {
+        self.state().remove(cookie);
+    }
+}
+
+/// A session handed off to the successor of a server.
+#[cfg(all(feature = "auto-reload", unix))]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct HandedOffSession {
+    id: String,
+    data: SessionData,
+    expires_in_ms: Option<u64>,
+}
+
+/// Hands off the sessions that did not expire, keeping their remaining time to live.
+#[cfg(all(feature = "auto-reload", unix))]
+impl crate::StateHandoff for MemorySessionStore {
+    fn export_state(&self) -> Vec<u8> {
+        let now = Instant::now();
+        let sessions: Vec<_> = self
+            .state()
+            .sessions
+            .iter()
+            .filter(|(_, session)| !session.is_expired(now))
+            .map(|(id, session)| HandedOffSession {
+                id: id.clone(),
+                data: session.data.clone(),
+                expires_in_ms: session.expires_at.map(|expires_at| {
+                    u64::try_from((expires_at - now).as_millis()).unwrap_or(u64::MAX)
+                }),
+            })
+            .collect();
+
+        serde_json::to_vec(&sessions).expect("sessions should serialize to JSON")
+    }
+
+    fn import_state(&self, state: &[u8]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let handed_off: Vec<HandedOffSession> = serde_json::from_slice(state)?;
+        let now = Instant::now();
+        let mut state = self.state();
+
+        for session in handed_off {
+            state.insert(
+                session.id,
+                session.data,
+                session
+                    .expires_in_ms
+                    .map(|expires_in_ms| now + Duration::from_millis(expires_in_ms)),
+            );
+        }
+
+        state.evict(self.max_entries, self.max_size);
+
+        Ok(())
+    }
+}
+
+/// Get the handle of a session, as the beginning of the SHA-256 digest of its identifier.
+fn handle(id: &str) -> String {
+    hex::encode(&sha2::Sha256::digest(id.as_bytes())[..8])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_entries_and_purge() {
+        let store = MemorySessionStore::default().with_ttl(Duration::from_secs(60));
+        let mut data = SessionData::new();
+        data.insert("user_id".to_owned(), 42.into());
+
+        let a = store.save(None, data.clone()).await.unwrap();
+        let b = store.save(None, SessionData::new()).await.unwrap();
+
+        let entries = store.entries();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|entry| entry.handle.len() == 16
+            && !a.contains(&entry.handle)
+            && entry.expires_in.is_some()));
+
+        let entry = entries
+            .iter()
+            .find(|entry| entry.handle == handle(&a))
+            .unwrap();
+        assert_eq!(entry.keys, ["user_id"]);
+
+        assert!(store.purge(&handle(&a)));
+        assert!(!store.purge(&handle(&a)));
+        assert_eq!(store.load(&a).await, None);
+        assert!(store.load(&b).await.is_some());
+
+        assert_eq!(store.purge_all(), 1);
+        assert!(store.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sweep_and_metrics() {
+        let store = MemorySessionStore::default().with_ttl(Duration::from_millis(10));
+
+        let id = store.save(None, SessionData::new()).await.unwrap();
+        assert!(store.load(&id).await.is_some());
+        assert_eq!(store.load("unknown").await, None);
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let fresh = store.save(None, SessionData::new()).await.unwrap();
+
+        assert_eq!(store.sweep(), 1);
+        assert_eq!(store.len(), 1);
+
+        let metrics = store.metrics();
+        assert_eq!(metrics.entries, 1);
+        assert_eq!(metrics.size, fresh.len() + "{}".len());
+        assert_eq!(metrics.hits, 1);
+        assert_eq!(metrics.misses, 1);
+        assert_eq!(metrics.expirations, 1);
+    }
+
+    #[tokio::test]
+    async fn test_sweeper() {
+        let store = MemorySessionStore::default().with_ttl(Duration::from_millis(10));
+        store.save(None, SessionData::new()).await.unwrap();
+
+        let sweeper = store.spawn_sweeper(Duration::from_millis(5));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(store.is_empty());
+
+        // The sweeper stops once the store is dropped.
+        drop(store);
+        tokio::time::timeout(Duration::from_secs(1), sweeper)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_max_entries_and_size() {
+        let store = MemorySessionStore::default().with_max_entries(2);
+
+        let a = store.save(None, SessionData::new()).await.unwrap();
+        let b = store.save(None, SessionData::new()).await.unwrap();
+
+        // Loading `a` makes `b` the least recently used session.
+        assert!(store.load(&a).await.is_some());
+
+        let c = store.save(None, SessionData::new()).await.unwrap();
+
+        assert!(store.load(&a).await.is_some());
+        assert_eq!(store.load(&b).await, None);
+        assert!(store.load(&c).await.is_some());
+        assert_eq!(store.metrics().evictions, 1);
+
+        let store = MemorySessionStore::default().with_max_size(200);
+        let mut data = SessionData::new();
+        data.insert("blob".to_owned(), "x".repeat(100).into());
+
+        let a = store.save(None, data.clone()).await.unwrap();
+        let b = store.save(None, data).await.unwrap();
+
+        assert_eq!(store.load(&a).await, None);
+        assert!(store.load(&b).await.is_some());
+        assert!(store.metrics().size <= 200);
+    }
+
+    #[cfg(all(feature = "auto-reload", unix))]
+    #[tokio::test]
+    async fn test_state_handoff() {
+        use crate::StateHandoff;
+
+        let store = MemorySessionStore::default().with_ttl(Duration::from_secs(60));
+        let mut data = SessionData::new();
+        data.insert("user_id".to_owned(), 42.into());
+
+        let id = store.save(None, data.clone()).await.unwrap();
+
+        let successor = MemorySessionStore::default();
+        successor.import_state(&store.export_state()).unwrap();
+
+        assert_eq!(successor.load(&id).await, Some(data));
+        assert!(
+            successor.entries()[0]
+                .expires_in
+                .is_some_and(|expires_in| expires_in > Duration::from_secs(50))
+        );
+        assert!(successor.import_state(b"not json").is_err());
+    }
+}
@@ -0,0 +1,520 @@
+//! Per-user sessions, identified by a cookie.
+//!
+//! Sessions are enabled on a server with
+//! [`ServerBuilder::with_sessions`](crate::ServerBuilder::with_sessions), which loads the session
+//! of each request from a [`SessionStore`] and makes it available to controllers through the
+//! request extensions:
+//!
+//! ```rust,ignore
+//! let server = Server::builder(listener)
+//!     .with_sessions(Sessions::new(MemorySessionStore::default()))
+//!     .build();
+//!
+//! // In `Controller::handle_request`:
+//! let session = Session::from_parts(&parts).expect("sessions should be enabled");
+//!
+//! session.insert("user_id", user.id)?;
+//! session.renew();
+//! ```
+//!
+//! Sessions are only saved, and their cookie only set, when they are modified: anonymous
+//! requests that never touch their session don't get a cookie.
+
+mod cookie;
+mod memory;
+mod submission;
+mod undo;
+
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::response::IntoResponse;
+use serde::{Serialize, de::DeserializeOwned};
+
+pub use cookie::CookieSessionStore;
+pub use memory::{MemorySessionEntry, MemorySessionStore, MemorySessionStoreMetrics};
+pub use submission::{AlreadySubmitted, SubmissionToken};
+pub use undo::{UndoQueue, UndoToast, UndoToken};
+
+/// The default name of the session cookie.
+pub const DEFAULT_COOKIE_NAME: &str = "htmxology_session";
+
+/// The data of a session.
+pub type SessionData = serde_json::Map<String, serde_json::Value>;
+
+/// An error that can occur when saving a session.
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    /// The session data could not be serialized.
+    #[error("failed to serialize the session data: {0}")]
+    Serialize(#[from] serde_json::Error),
+
+    /// The session data does not fit in a cookie.
+    #[error("the session cookie is too large ({size} bytes)")]
+    TooLarge {
+        /// The size of the cookie value, in bytes.
+        size: usize,
+    },
+
+    /// The session store failed.
+    #[error("the session store failed: {0}")]
+    Store(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// A backend storing the data of sessions.
+///
+/// Stores are given the value of the session cookie of requests, and return the value of the
+/// cookie to set when a session is saved: either an identifier of the session data, or the
+/// session data itself.
+pub trait SessionStore: Send + Sync + 'static {
+    /// Load the data of a session from the value of its cookie.
+    ///
+    /// Returns `None` if the session does not exist, or has expired.
+    fn load(&self, cookie: &str) -> impl Future<Output = Option<SessionData>> + Send;
+
+    /// Save the data of a session, returning the new value of its cookie.
+    ///
+    /// `cookie` is the current value of the cookie of the session, or `None` for new sessions and
+    /// renewed sessions, which must get a new identifier.
+    fn save(
+        &self,
+        cookie: Option<&str>,
+        data: SessionData,
+    ) -> impl Future<Output = Result<String, SessionError>> + Send;
+
+    /// Delete a session, from the value of its cookie.
+    fn delete(&self, cookie: &str) -> impl Future<Output = ()> + Send;
+}
+
+/// The session of a request.
+///
+/// Cloning a session is cheap, and the clones share the same data.
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    state: Arc<Mutex<State>>,
+
+    /// The submission tokens consumed recently by all the sessions of the server, if any.
+    consumed: Option<Arc<submission::ConsumedTokens>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    data: SessionData,
+    status: Status,
+}
+
+/// What must be done with a session once the request has been handled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    #[default]
+    Unchanged,
+    Modified,
+    Renewed,
+    Destroyed,
+}
+
+impl Session {
+    fn new(data: SessionData) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                data,
+                status: Status::Unchanged,
+            })),
+            consumed: None,
+        }
+    }
+
+    /// Get the session of a request from its parts.
+    ///
+    /// Returns `None` if sessions are not enabled on the server.
+    pub fn from_parts(parts: &http::request::Parts) -> Option<Self> {
+        parts.extensions.get().cloned()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("session lock should not be poisoned")
+    }
+
+    fn mark(state: &mut State, status: Status) {
+        if state.status == Status::Unchanged {
+            state.status = status;
+        }
+    }
+
+    /// Get a value from the session.
+    ///
+    /// Returns `None` if the value is missing, or can't be deserialized as a `T`.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.state().data.get(key)?.clone();
+
+        serde_json::from_value(value)
+            .inspect_err(|err| tracing::warn!("Failed to deserialize session value `{key}`: {err}"))
+            .ok()
+    }
+
+    /// Insert a value in the session, replacing any previous value.
+    pub fn insert(
+        &self,
+        key: impl Into<String>,
+        value: impl Serialize,
+    ) -> Result<(), SessionError> {
+        let value = serde_json::to_value(value)?;
+        let mut state = self.state();
+
+        state.data.insert(key.into(), value);
+        Self::mark(&mut state, Status::Modified);
+
+        Ok(())
+    }
+
+    /// Remove a value from the session, returning whether it was present.
+    pub fn remove(&self, key: &str) -> bool {
+        let mut state = self.state();
+        let removed = state.data.remove(key).is_some();
+
+        if removed {
+            Self::mark(&mut state, Status::Modified);
+        }
+
+        removed
+    }
+
+    /// Remove all the values of the session.
+    pub fn clear(&self) {
+        let mut state = self.state();
+
+        state.data.clear();
+        Self::mark(&mut state, Status::Modified);
+    }
+
+    /// Check whether the session holds no value.
+    pub fn is_empty(&self) -> bool {
+        self.state().data.is_empty()
+    }
+
+    /// Give the session a new identifier, keeping its data.
+    ///
+    /// This should be called whenever the privileges of the user change, typically when they log
+    /// in, to prevent session fixation attacks.
+    pub fn renew(&self) {
+        let mut state = self.state();
+
+        if state.status != Status::Destroyed {
+            state.status = Status::Renewed;
+        }
+    }
+
+    /// Destroy the session, deleting its data and its cookie.
+    ///
+    /// This should be called when the user logs out.
+    pub fn destroy(&self) {
+        let mut state = self.state();
+
+        state.data.clear();
+        state.status = Status::Destroyed;
+    }
+}
+
+impl<S: Send + Sync> axum::extract::FromRequestParts<S> for Session {
+    type Rejection = (http::StatusCode, &'static str);
+
+    async fn from_request_parts(
+        parts: &mut http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        Self::from_parts(parts).ok_or((
+            http::StatusCode::INTERNAL_SERVER_ERROR,
+            "sessions are not enabled on the server",
+        ))
+    }
+}
+
+/// The session configuration of a server: a store and the attributes of the session cookie.
+#[derive(Debug)]
+pub struct Sessions<S> {
+    store: Arc<S>,
+    consumed: Arc<submission::ConsumedTokens>,
+    cookie_name: String,
+    secure: bool,
+    max_age: Option<Duration>,
+}
+
+impl<S> Clone for Sessions<S> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            consumed: self.consumed.clone(),
+            cookie_name: self.cookie_name.clone(),
+            secure: self.secure,
+            max_age: self.max_age,
+        }
+    }
+}
+
+impl<S: SessionStore> Sessions<S> {
+    /// Create a session configuration using the specified store.
+    ///
+    /// The session cookie is named [`DEFAULT_COOKIE_NAME`], and is `HttpOnly`, `Secure` and
+    /// `SameSite=Lax`. It expires when the browser is closed, unless a maximum age is set.
+    pub fn new(store: S) -> Self {
+        Self {
+            store: Arc::new(store),
+            consumed: Default::default(),
+            cookie_name: DEFAULT_COOKIE_NAME.to_owned(),
+            secure: true,
+            max_age: None,
+        }
+    }
+
+    /// Set the name of the session cookie.
+    pub fn with_cookie_name(mut self, cookie_name: impl Into<String>) -> Self {
+        self.cookie_name = cookie_name.into();
+        self
+    }
+
+    /// Set whether the session cookie is only sent over HTTPS.
+    ///
+    /// Defaults to `true`. Browsers treat `localhost` as secure, so this rarely needs to be
+    /// disabled, even in development.
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Apply the cookie settings of a profile.
+    pub fn with_profile(self, settings: &crate::ProfileSettings) -> Self {
+        self.with_secure(settings.secure_cookies)
+    }
+
+    /// Set the maximum age of the session cookie.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Apply the session middleware to a router.
+    pub(crate) fn apply(self, router: axum::Router) -> axum::Router {
+        router.layer(axum::middleware::from_fn_with_state(self, middleware::<S>))
+    }
+
+    /// Get the value of the session cookie of a request.
+    fn request_cookie<'h>(&self, headers: &'h http::HeaderMap) -> Option<&'h str> {
+        headers
+            .get_all(http::header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .filter_map(|cookie| cookie.trim().split_once('='))
+            .find(|(name, _)| *name == self.cookie_name)
+            .map(|(_, value)| value)
+    }
+
+    /// Build a `Set-Cookie` header value for the session cookie.
+    fn set_cookie(&self, value: Option<&str>) -> Option<http::HeaderValue> {
+        let mut cookie = format!(
+            "{}={}; Path=/; HttpOnly; SameSite=Lax",
+            self.cookie_name,
+            value.unwrap_or_default()
+        );
+
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+
+        match (value, self.max_age) {
+            (None, _) => cookie.push_str("; Max-Age=0"),
+            (Some(_), Some(max_age)) => {
+                cookie.push_str(&format!("; Max-Age={}", max_age.as_secs()))
+            }
+            (Some(_), None) => {}
+        }
+
+        http::HeaderValue::from_str(&cookie)
+            .inspect_err(|err| tracing::error!("Invalid session cookie: {err}"))
+            .ok()
+    }
+}
+
+/// Load the session of a request, and save it once the request has been handled.
+async fn middleware<S: SessionStore>(
+    axum::extract::State(sessions): axum::extract::State<Sessions<S>>,
+    mut request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let cookie = sessions
+        .request_cookie(request.headers())
+        .map(str::to_owned);
+    let loaded = match &cookie {
+        Some(cookie) => sessions.store.load(cookie).await,
+        None => None,
+    };
+    let existing_cookie = loaded.is_some().then_some(cookie.as_deref()).flatten();
+
+    let session = Session {
+        consumed: Some(sessions.consumed.clone()),
+        ..Session::new(loaded.clone().unwrap_or_default())
+    };
+    request.extensions_mut().insert(session.clone());
+
+    let mut response = next.run(request).await;
+
+    let (data, status) = {
+        let mut state = session.state();
+
+        (std::mem::take(&mut state.data), state.status)
+    };
+
+    let new_cookie = match status {
+        Status::Unchanged => return response,
+        Status::Modified if data.is_empty() && existing_cookie.is_none() => return response,
+        Status::Modified => sessions.store.save(existing_cookie, data).await.map(Some),
+        Status::Renewed => {
+            if let Some(existing_cookie) = existing_cookie {
+                sessions.store.delete(existing_cookie).await;
+            }
+
+            sessions.store.save(None, data).await.map(Some)
+        }
+        Status::Destroyed => {
+            if let Some(existing_cookie) = existing_cookie {
+                sessions.store.delete(existing_cookie).await;
+            }
+
+            // Only expire the cookie if the client sent one.
+            if cookie.is_none() {
+                return response;
+            }
+
+            Ok(None)
+        }
+    };
+
+    match new_cookie {
+        Ok(new_cookie) => {
+            if let Some(set_cookie) = sessions.set_cookie(new_cookie.as_deref()) {
+                response
+                    .headers_mut()
+                    .append(http::header::SET_COOKIE, set_cookie);
+            }
+
+            response
+        }
+        Err(err) => {
+            tracing::error!("Failed to save the session: {err}");
+
+            http::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_service::Service;
+
+    use super::*;
+
+    fn router() -> axum::Router {
+        let router = axum::Router::new()
+            .route(
+                "/login",
+                axum::routing::post(|session: Session| async move {
+                    session.insert("user", "alice").unwrap();
+                    session.renew();
+                }),
+            )
+            .route(
+                "/whoami",
+                axum::routing::get(|session: Session| async move {
+                    session.get::<String>("user").unwrap_or_default()
+                }),
+            )
+            .route(
+                "/logout",
+                axum::routing::post(|session: Session| async move { session.destroy() }),
+            );
+
+        Sessions::new(MemorySessionStore::default()).apply(router)
+    }
+
+    async fn call(
+        router: &mut axum::Router,
+        method: &str,
+        uri: &str,
+        cookie: Option<&str>,
+    ) -> (Option<String>, String) {
+        let mut request = http::Request::builder().method(method).uri(uri);
+
+        if let Some(cookie) = cookie {
+            request = request.header(http::header::COOKIE, cookie);
+        }
+
+        let response = router
+            .call(request.body(axum::body::Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let set_cookie = response
+            .headers()
+            .get(http::header::SET_COOKIE)
+            .map(|value| value.to_str().unwrap().to_owned());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        (set_cookie, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_session_lifecycle() {
+        let mut router = router();
+
+        // Anonymous requests don't get a cookie.
+        assert_eq!(
+            call(&mut router, "GET", "/whoami", None).await,
+            (None, String::new())
+        );
+
+        let (set_cookie, _) = call(&mut router, "POST", "/login", None).await;
+        let set_cookie = set_cookie.unwrap();
+        let cookie = set_cookie.split(';').next().unwrap();
+
+        assert!(cookie.starts_with("htmxology_session="));
+        assert!(set_cookie.contains("HttpOnly; SameSite=Lax; Secure"));
+
+        assert_eq!(
+            call(&mut router, "GET", "/whoami", Some(cookie)).await,
+            (None, "alice".to_owned())
+        );
+
+        let (set_cookie, _) = call(&mut router, "POST", "/logout", Some(cookie)).await;
+
+        assert!(set_cookie.unwrap().contains("Max-Age=0"));
+        assert_eq!(
+            call(&mut router, "GET", "/whoami", Some(cookie)).await,
+            (None, String::new())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_renew_changes_cookie() {
+        let mut router = router();
+
+        let (first, _) = call(&mut router, "POST", "/login", None).await;
+        let first = first.unwrap();
+        let first = first.split(';').next().unwrap();
+        let (second, _) = call(&mut router, "POST", "/login", Some(first)).await;
+        let second = second.unwrap();
+        let second = second.split(';').next().unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(call(&mut router, "GET", "/whoami", Some(first)).await.1, "");
+        assert_eq!(
+            call(&mut router, "GET", "/whoami", Some(second)).await.1,
+            "alice"
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! One-time form submission tokens.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use super::Session;
+
+/// The session key holding the issued submission tokens.
+const SESSION_KEY: &str = "htmxology.submission_tokens";
+
+/// The maximum number of unused submission tokens kept in a session.
+///
+/// Older tokens are forgotten, and can't be used anymore.
+const MAX_ISSUED_TOKENS: usize = 32;
+
+/// How long consumed tokens are remembered, to reject concurrent duplicate submissions.
+const CONSUMED_TOKENS_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// A one-time token, rejecting duplicate submissions of a form.
+///
+/// Tokens are issued by [`Session::issue_submission_token`] when a form is rendered, and
+/// consumed by [`Session::consume_submission_token`] when it is submitted: submitting the same
+/// form twice, for instance by double-clicking its submit button or replaying the request, fails
+/// with [`AlreadySubmitted`].
+///
+/// Unlike a CSRF token, a submission token is only valid once. Fields of this type are rendered as
+/// `hidden` inputs by the `HtmlForm` derive macro:
+///
+/// ```rust,ignore
+/// #[derive(Debug, Clone, serde::Deserialize, HtmlForm)]
+/// #[html_form(route = AppRoute, submit = "Pay")]
+/// struct PaymentForm {
+///     amount: u32,
+///     token: SubmissionToken,
+/// }
+///
+/// // When rendering the form:
+/// let form = PaymentForm { amount: 10, token: session.issue_submission_token() };
+///
+/// // When handling its submission:
+/// session.consume_submission_token(&form.token)?;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct SubmissionToken(String);
+
+impl SubmissionToken {
+    /// Get the value of the token.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for SubmissionToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// The error returned when a form is submitted with a token that was already consumed, or was
+/// never issued.
+///
+/// As a response, it renders a canned "already submitted" fragment, with a `200 OK` status so that
+/// HTMX swaps it in place of the usual result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("the form has already been submitted")]
+pub struct AlreadySubmitted;
+
+impl axum::response::IntoResponse for AlreadySubmitted {
+    fn into_response(self) -> axum::response::Response {
+        axum::response::Html(
+            r#"<div class="already-submitted" role="alert">This form has already been submitted.</div>"#,
+        )
+        .into_response()
+    }
+}
+
+/// The submission tokens consumed recently, shared by all the sessions of a server.
+///
+/// Concurrent requests load their own copy of a session, so the session alone can't tell that a
+/// token is being consumed by another request.
+#[derive(Debug, Default)]
+pub(super) struct ConsumedTokens {
+    tokens: Mutex<HashMap<SubmissionToken, Instant>>,
+}
+
+impl ConsumedTokens {
+    /// Mark a token as consumed, returning `false` if it already was.
+    fn consume(&self, token: &SubmissionToken) -> bool {
+        let now = Instant::now();
+        let mut tokens = self
+            .tokens
+            .lock()
+            .expect("consumed tokens lock should not be poisoned");
+
+        tokens.retain(|_, consumed_at| now.duration_since(*consumed_at) < CONSUMED_TOKENS_TTL);
+        tokens.insert(token.clone(), now).is_none()
+    }
+}
+
+impl Session {
+    /// Issue a one-time token for a form.
+    ///
+    /// Only the most recent tokens of a session are valid, which is plenty for forms rendered in
+    /// several tabs.
+    pub fn issue_submission_token(&self) -> SubmissionToken {
+        let token = SubmissionToken(uuid::Uuid::new_v4().simple().to_string());
+        let mut tokens: Vec<SubmissionToken> = self.get(SESSION_KEY).unwrap_or_default();
+
+        tokens.push(token.clone());
+
+        if tokens.len() > MAX_ISSUED_TOKENS {
+            tokens.drain(..tokens.len() - MAX_ISSUED_TOKENS);
+        }
+
+        self.insert(SESSION_KEY, tokens)
+            .expect("submission tokens should be serializable");
+
+        token
+    }
+
+    /// Consume a one-time token, rejecting duplicate submissions of a form.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AlreadySubmitted`] if the token was already consumed, including by a concurrent
+    /// request, or was not issued to the session.
+    pub fn consume_submission_token(
+        &self,
+        token: &SubmissionToken,
+    ) -> Result<(), AlreadySubmitted> {
+        let mut tokens: Vec<SubmissionToken> = self.get(SESSION_KEY).unwrap_or_default();
+        let len = tokens.len();
+
+        tokens.retain(|issued| issued != token);
+
+        if tokens.len() == len {
+            return Err(AlreadySubmitted);
+        }
+
+        self.insert(SESSION_KEY, tokens)
+            .expect("submission tokens should be serializable");
+
+        match &self.consumed {
+            Some(consumed) if !consumed.consume(token) => Err(AlreadySubmitted),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn test_submission_tokens() {
+        let session = Session::default();
+        let token = session.issue_submission_token();
+
+        assert_eq!(session.consume_submission_token(&token), Ok(()));
+        assert_eq!(
+            session.consume_submission_token(&token),
+            Err(AlreadySubmitted)
+        );
+        assert_eq!(
+            session.consume_submission_token(&SubmissionToken("forged".to_owned())),
+            Err(AlreadySubmitted)
+        );
+    }
+
+    #[test]
+    fn test_concurrent_submissions() {
+        let consumed = Arc::new(ConsumedTokens::default());
+        let issuing = Session::default();
+        let token = issuing.issue_submission_token();
+
+        // Concurrent requests load their own copy of the session.
+        let sessions = [0, 1].map(|_| Session {
+            consumed: Some(consumed.clone()),
+            ..Session::new(issuing.state().data.clone())
+        });
+
+        assert_eq!(sessions[0].consume_submission_token(&token), Ok(()));
+        assert_eq!(
+            sessions[1].consume_submission_token(&token),
+            Err(AlreadySubmitted)
+        );
+    }
+
+    #[test]
+    fn test_max_issued_tokens() {
+        let session = Session::default();
+        let first = session.issue_submission_token();
+
+        for _ in 0..MAX_ISSUED_TOKENS {
+            session.issue_submission_token();
+        }
+
+        assert_eq!(
+            session.consume_submission_token(&first),
+            Err(AlreadySubmitted)
+        );
+    }
+}
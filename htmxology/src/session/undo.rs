@@ -0,0 +1,522 @@
+//! Session-scoped undo and redo of destructive actions.
+
+use std::{
+    fmt::Display,
+    marker::PhantomData,
+    time::{Duration, SystemTime},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::{Session, SessionError};
+use crate::{
+    Route,
+    htmx::{Fragment, HtmlId, Identity, InsertStrategy},
+};
+
+/// The prefix of the session keys holding the undo queues.
+const SESSION_KEY_PREFIX: &str = "htmxology.undo";
+
+/// The default name of an undo queue.
+const DEFAULT_NAME: &str = "default";
+
+/// How long actions can be undone by default.
+const DEFAULT_TTL: Duration = Duration::from_secs(30);
+
+/// The default maximum number of actions that can be undone, or redone.
+const DEFAULT_CAPACITY: usize = 16;
+
+/// A token identifying an action that can be undone, or redone.
+///
+/// Tokens are typically path parameters of the routes of the undo and redo endpoints.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct UndoToken(String);
+
+impl UndoToken {
+    /// Get the value of the token.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for UndoToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::str::FromStr for UndoToken {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.to_owned()))
+    }
+}
+
+/// An action kept in an undo queue.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct Entry {
+    /// The token of the action.
+    token: UndoToken,
+
+    /// The reversal payload of the action.
+    payload: serde_json::Value,
+
+    /// When the action expires, in milliseconds since the UNIX epoch.
+    expires_at: u64,
+}
+
+/// The actions of an undo queue, as stored in the session.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct Entries {
+    /// The actions that can be undone, the most recent last.
+    undo: Vec<Entry>,
+
+    /// The undone actions that can be redone, the most recently undone last.
+    redo: Vec<Entry>,
+}
+
+impl Entries {
+    /// Forget the expired actions.
+    fn prune(&mut self, now: u64) {
+        self.undo.retain(|entry| entry.expires_at > now);
+        self.redo.retain(|entry| entry.expires_at > now);
+    }
+}
+
+/// A session-scoped queue of destructive actions that can be undone, and redone.
+///
+/// Instead of asking for confirmation, handlers of destructive actions push a reversal payload -
+/// like the deleted item - and respond with an [`UndoToast`], rendered out-of-band in the flash
+/// messages container. The undo endpoint then takes the payload back from the queue to reverse
+/// the action, until it expires:
+///
+/// ```rust,ignore
+/// // When deleting an item:
+/// let item = store.delete(id).await?;
+/// let toast = session
+///     .undo_queue::<Item>()
+///     .push(format!("Deleted {}.", item.name), item, |token| AppRoute::Undo {
+///         token: token.clone(),
+///     })?;
+///
+/// Ok(htmx::Response::new(StatusCode::OK).with_oob(toast))
+///
+/// // When undoing it:
+/// match session.undo_queue::<Item>().undo(&token)? {
+///     Some(item) => store.insert(item).await?,
+///     None => flash.error("This action can't be undone anymore."),
+/// }
+/// ```
+///
+/// Undone actions can be redone with [`UndoQueue::redo`], until they expire too. Pushing a new
+/// action forgets the undone ones.
+///
+/// Payloads are kept in the session, so they should stay small: the whole session is stored in a
+/// cookie by the [`CookieSessionStore`](super::CookieSessionStore).
+#[derive(Debug)]
+pub struct UndoQueue<T> {
+    session: Session,
+    name: String,
+    ttl: Duration,
+    capacity: usize,
+    payload: PhantomData<fn() -> T>,
+}
+
+impl Session {
+    /// Get the undo queue of the session, for actions reversed by payloads of type `T`.
+    pub fn undo_queue<T>(&self) -> UndoQueue<T> {
+        UndoQueue {
+            session: self.clone(),
+            name: DEFAULT_NAME.to_owned(),
+            ttl: DEFAULT_TTL,
+            capacity: DEFAULT_CAPACITY,
+            payload: PhantomData,
+        }
+    }
+}
+
+impl<T> UndoQueue<T> {
+    /// Set the name of the queue.
+    ///
+    /// Queues with different names are independent, which is required for queues with different
+    /// payload types.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Set how long actions can be undone, or redone once undone.
+    ///
+    /// Defaults to 30 seconds.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Set the maximum number of actions that can be undone, or redone.
+    ///
+    /// Older actions are forgotten, and can't be undone anymore. Defaults to 16.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Get the session key holding the queue.
+    fn key(&self) -> String {
+        format!("{SESSION_KEY_PREFIX}.{}", self.name)
+    }
+
+    /// Get the current time, in milliseconds since the UNIX epoch.
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis()
+            .try_into()
+            .unwrap_or(u64::MAX)
+    }
+
+    /// Get the expiration time of an action pushed now.
+    fn expires_at(&self, now: u64) -> u64 {
+        now.saturating_add(self.ttl.as_millis().try_into().unwrap_or(u64::MAX))
+    }
+
+    /// Load the unexpired actions of the queue.
+    fn load(&self, now: u64) -> Entries {
+        let mut entries: Entries = self.session.get(&self.key()).unwrap_or_default();
+
+        entries.prune(now);
+        entries
+    }
+
+    /// Save the actions of the queue, removing it from the session if it is empty.
+    fn save(&self, entries: &Entries) -> Result<(), SessionError> {
+        if entries.undo.is_empty() && entries.redo.is_empty() {
+            self.session.remove(&self.key());
+
+            Ok(())
+        } else {
+            self.session.insert(self.key(), entries)
+        }
+    }
+
+    /// Move an action from one stack to the other, returning its payload.
+    fn transfer(
+        &self,
+        token: &UndoToken,
+        select: fn(&mut Entries) -> (&mut Vec<Entry>, &mut Vec<Entry>),
+    ) -> Result<Option<serde_json::Value>, SessionError> {
+        let now = Self::now();
+        let mut entries = self.load(now);
+        let (from, to) = select(&mut entries);
+
+        let Some(index) = from.iter().position(|entry| entry.token == *token) else {
+            self.save(&entries)?;
+
+            return Ok(None);
+        };
+
+        let mut entry = from.remove(index);
+        let payload = entry.payload.clone();
+
+        entry.expires_at = self.expires_at(now);
+        to.push(entry);
+
+        if to.len() > self.capacity {
+            to.drain(..to.len() - self.capacity);
+        }
+
+        self.save(&entries)?;
+
+        Ok(Some(payload))
+    }
+
+    /// Forget all the actions of the queue.
+    pub fn clear(&self) {
+        self.session.remove(&self.key());
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> UndoQueue<T> {
+    /// Push a destructive action, with the payload reversing it.
+    ///
+    /// Returns the toast offering to undo the action, with a button requesting the route built
+    /// from the token of the action.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the payload can't be serialized.
+    pub fn push<R: Route>(
+        &self,
+        label: impl Into<String>,
+        payload: T,
+        route: impl FnOnce(&UndoToken) -> R,
+    ) -> Result<UndoToast, SessionError> {
+        let token = UndoToken(uuid::Uuid::new_v4().simple().to_string());
+        let now = Self::now();
+        let mut entries = self.load(now);
+
+        entries.undo.push(Entry {
+            token: token.clone(),
+            payload: serde_json::to_value(payload)?,
+            expires_at: self.expires_at(now),
+        });
+        entries.redo.clear();
+
+        if entries.undo.len() > self.capacity {
+            entries.undo.drain(..entries.undo.len() - self.capacity);
+        }
+
+        self.save(&entries)?;
+
+        Ok(UndoToast::new(label, &route(&token)).with_ttl(self.ttl))
+    }
+
+    /// Undo an action, returning the payload reversing it.
+    ///
+    /// The action can then be redone with [`UndoQueue::redo`], until it expires. Returns `None` if
+    /// the action expired, was already undone, or was not pushed in the session.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the payload can't be deserialized as a `T`.
+    pub fn undo(&self, token: &UndoToken) -> Result<Option<T>, SessionError> {
+        self.transfer(token, |entries| (&mut entries.undo, &mut entries.redo))?
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    /// Redo an undone action, returning the payload that reversed it.
+    ///
+    /// The action can then be undone again, until it expires. Returns `None` if the action
+    /// expired, was not undone, or was not pushed in the session.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the payload can't be deserialized as a `T`.
+    pub fn redo(&self, token: &UndoToken) -> Result<Option<T>, SessionError> {
+        self.transfer(token, |entries| (&mut entries.redo, &mut entries.undo))?
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(Into::into)
+    }
+}
+
+/// A toast offering to undo an action.
+///
+/// It is appended out-of-band to the flash messages container
+/// ([`DEFAULT_FLASH_CONTAINER_ID`](crate::DEFAULT_FLASH_CONTAINER_ID) by default), with a button
+/// requesting the undo route:
+///
+/// ```html
+/// <div class="undo-toast" role="status" data-undo-ttl="30">
+///   <span>Deleted the report.</span>
+///   <button hx-post="/undo/4f6c..." hx-target="closest .undo-toast" hx-swap="outerHTML">Undo</button>
+/// </div>
+/// ```
+///
+/// The response to the undo route replaces the toast. The `data-undo-ttl` attribute holds the
+/// number of seconds the action can be undone for, for scripts dismissing expired toasts.
+#[derive(Clone)]
+pub struct UndoToast {
+    label: String,
+    action: String,
+    method: http::Method,
+    url: String,
+    ttl: Option<Duration>,
+    container_id: HtmlId,
+}
+
+impl std::fmt::Debug for UndoToast {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UndoToast")
+            .field("label", &self.label)
+            .field("action", &self.action)
+            .field("method", &self.method)
+            .field("url", &self.url)
+            .field("ttl", &self.ttl)
+            .field("container_id", &self.container_id.to_string())
+            .finish()
+    }
+}
+
+impl UndoToast {
+    /// Create a toast with a button requesting a route.
+    ///
+    /// Toasts are usually created by [`UndoQueue::push`], but this can be used to offer to redo
+    /// an undone action.
+    pub fn new(label: impl Into<String>, route: &impl Route) -> Self {
+        Self {
+            label: label.into(),
+            action: "Undo".to_owned(),
+            method: route.method(),
+            url: route.to_string(),
+            ttl: None,
+            container_id: HtmlId::from_static(crate::DEFAULT_FLASH_CONTAINER_ID)
+                .expect("the default flash container id should be valid"),
+        }
+    }
+
+    /// Set the label of the button.
+    ///
+    /// Defaults to "Undo".
+    pub fn with_action_label(mut self, action: impl Into<String>) -> Self {
+        self.action = action.into();
+        self
+    }
+
+    /// Set the number of seconds the action can be undone for, rendered in the `data-undo-ttl`
+    /// attribute.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Set the `id` of the element the toast is appended to.
+    pub fn with_container_id(mut self, container_id: HtmlId) -> Self {
+        self.container_id = container_id;
+        self
+    }
+}
+
+impl Identity for UndoToast {
+    fn id(&self) -> HtmlId {
+        self.container_id.clone()
+    }
+}
+
+impl Fragment for UndoToast {
+    fn insert_strategy(&self) -> InsertStrategy {
+        InsertStrategy::BeforeEnd
+    }
+}
+
+impl Display for UndoToast {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            r#"<div {}><div class="undo-toast" role="status""#,
+            self.id_attribute()
+        )?;
+
+        if let Some(ttl) = self.ttl {
+            write!(f, r#" data-undo-ttl="{}""#, ttl.as_secs())?;
+        }
+
+        write!(
+            f,
+            r#"><span>{}</span> <button hx-{}="{}" hx-target="closest .undo-toast" hx-swap="outerHTML">{}</button></div></div>"#,
+            crate::htmx::escape_text(&self.label),
+            self.method.as_str().to_ascii_lowercase(),
+            crate::htmx::escape_attribute_value(&self.url),
+            crate::htmx::escape_text(&self.action),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Undo(UndoToken);
+
+    impl Display for Undo {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "/undo/{}", self.0)
+        }
+    }
+
+    impl std::str::FromStr for Undo {
+        type Err = crate::ParseError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Ok(Self(UndoToken(s.trim_start_matches("/undo/").to_owned())))
+        }
+    }
+
+    impl Route for Undo {
+        fn method(&self) -> http::Method {
+            http::Method::POST
+        }
+    }
+
+    fn token(toast: &UndoToast) -> UndoToken {
+        UndoToken(toast.url.trim_start_matches("/undo/").to_owned())
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let session = Session::default();
+        let queue = session.undo_queue::<String>();
+        let toast = queue
+            .push("Deleted <b>.", "b".to_owned(), |token| Undo(token.clone()))
+            .unwrap();
+        let token = token(&toast);
+
+        assert_eq!(
+            toast.to_string(),
+            format!(
+                r#"<div id="flash-messages"><div class="undo-toast" role="status" data-undo-ttl="30"><span>Deleted &lt;b&gt;.</span> <button hx-post="/undo/{token}" hx-target="closest .undo-toast" hx-swap="outerHTML">Undo</button></div></div>"#
+            )
+        );
+
+        assert_eq!(queue.redo(&token).unwrap(), None);
+        assert_eq!(queue.undo(&token).unwrap().as_deref(), Some("b"));
+        assert_eq!(queue.undo(&token).unwrap(), None);
+        assert_eq!(queue.redo(&token).unwrap().as_deref(), Some("b"));
+        assert_eq!(queue.undo(&token).unwrap().as_deref(), Some("b"));
+
+        // Pushing a new action forgets the undone ones.
+        queue
+            .push("Deleted c.", "c".to_owned(), |token| Undo(token.clone()))
+            .unwrap();
+
+        assert_eq!(queue.redo(&token).unwrap(), None);
+        assert_eq!(queue.undo(&UndoToken("forged".to_owned())).unwrap(), None);
+    }
+
+    #[test]
+    fn test_expiration() {
+        let session = Session::default();
+        let queue = session.undo_queue::<u32>().with_ttl(Duration::ZERO);
+        let toast = queue
+            .push("Deleted.", 1, |token| Undo(token.clone()))
+            .unwrap();
+
+        assert_eq!(queue.undo(&token(&toast)).unwrap(), None);
+
+        // Expired actions are removed from the session.
+        assert_eq!(
+            session.get::<Entries>("htmxology.undo.default").map(|_| ()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_capacity_and_names() {
+        let session = Session::default();
+        let queue = session.undo_queue::<u32>().with_capacity(2);
+        let tokens = (0..3)
+            .map(|i| {
+                token(
+                    &queue
+                        .push("Deleted.", i, |token| Undo(token.clone()))
+                        .unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(queue.undo(&tokens[0]).unwrap(), None);
+        assert_eq!(queue.undo(&tokens[2]).unwrap(), Some(2));
+
+        // Queues with other names are independent.
+        let other = session.undo_queue::<String>().with_name("other");
+
+        assert_eq!(other.undo(&tokens[1]).unwrap(), None);
+        assert_eq!(queue.undo(&tokens[1]).unwrap(), Some(1));
+    }
+}
@@ -0,0 +1,599 @@
+//! Server-Sent Events, to push fragments to connected clients.
+//!
+//! The events are compatible with the [htmx SSE extension](https://htmx.org/extensions/sse/).
+
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    convert::Infallible,
+    fmt::Display,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use axum::response::{
+    IntoResponse,
+    sse::{Event, KeepAlive, Sse},
+};
+use futures_util::StreamExt;
+use tokio::sync::broadcast;
+
+use crate::htmx::{Fragment, FragmentDiff, InsertStrategy, render_fragment};
+
+/// The default name of the events carrying fragments.
+///
+/// This is the name of unnamed SSE events, which the htmx SSE extension swaps with
+/// `sse-swap="message"`.
+pub const DEFAULT_EVENT_NAME: &str = "message";
+
+/// The name of the event sent to resuming clients that missed events no longer in the replay
+/// buffer.
+///
+/// Pages can reload the affected parts on this event, for instance with
+/// `hx-trigger="sse:resync"`.
+pub const RESYNC_EVENT_NAME: &str = "resync";
+
+/// The default number of events buffered for slow clients.
+const DEFAULT_CAPACITY: usize = 64;
+
+/// The scope of the pushed fragments in the [`FragmentDiff`] of a stream.
+const BROADCAST_SCOPE: &str = "*";
+
+/// A stream of HTML fragments pushed to all connected clients as Server-Sent Events.
+///
+/// Fragments are sent as out-of-band swaps: their root element carries an `hx-swap-oob`
+/// attribute, so that a single SSE connection can update any element of the page. Cloning the
+/// stream returns a handle to the same set of clients.
+///
+/// Clients that fall behind by more than the capacity of the stream miss the oldest events.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let notifications = SseFragmentStream::default();
+///
+/// let router = ControllerRouter::new(controller, |_| async {})
+///     .with_sse("/events", notifications.clone());
+///
+/// // Anywhere in the application:
+/// notifications.push(Notification::new("Saved!"));
+/// ```
+///
+/// With the page listening for events:
+///
+/// ```html
+/// <div hx-ext="sse" sse-connect="/events" sse-swap="message" hx-swap="none"></div>
+/// ```
+///
+/// # Resuming
+///
+/// With a [replay buffer](Self::with_replay), events carry increasing IDs, and clients
+/// reconnecting with a `Last-Event-ID` header through [`resume`](Self::resume) receive the events
+/// they missed. The IDs include a token unique to the stream, so that clients of a previous
+/// server process are not mistaken for clients of this one.
+#[derive(Debug, Clone)]
+pub struct SseFragmentStream {
+    /// The sender of the events.
+    sender: broadcast::Sender<Message>,
+
+    /// The name of the events carrying fragments.
+    event_name: Cow<'static, str>,
+
+    /// The interval of the keep-alive comments, if any.
+    keep_alive: Option<Duration>,
+
+    /// The record of the fragments last pushed, to skip unchanged ones, if any.
+    diff: Option<FragmentDiff>,
+
+    /// The buffer of the last events, to replay them to resuming clients, if any.
+    replay: Option<Arc<Mutex<ReplayBuffer>>>,
+}
+
+impl Default for SseFragmentStream {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl SseFragmentStream {
+    /// Create a new stream, buffering at most `capacity` events for slow clients.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero, the call will panic.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+
+        Self {
+            sender,
+            event_name: Cow::Borrowed(DEFAULT_EVENT_NAME),
+            keep_alive: Some(Duration::from_secs(15)),
+            diff: None,
+            replay: None,
+        }
+    }
+
+    /// Set the name of the events carrying fragments.
+    ///
+    /// Defaults to [`DEFAULT_EVENT_NAME`].
+    pub fn with_event_name(mut self, event_name: impl Into<Cow<'static, str>>) -> Self {
+        self.event_name = event_name.into();
+        self
+    }
+
+    /// Set the interval of the keep-alive comments, or disable them with `None`.
+    ///
+    /// Defaults to 15 seconds, which prevents most proxies from closing idle connections.
+    pub fn with_keep_alive(mut self, keep_alive: Option<Duration>) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    /// Skip the fragments whose HTML is unchanged since they were last pushed.
+    ///
+    /// Fragments pushed with a raw target and custom events are always sent.
+    pub fn with_fragment_diff(mut self, diff: FragmentDiff) -> Self {
+        self.diff = Some(diff);
+        self
+    }
+
+    /// Keep the last events in a replay buffer, for clients resuming with a `Last-Event-ID`
+    /// header.
+    ///
+    /// The buffer holds at most `capacity` events, and events older than `max_age`, if any, are
+    /// dropped. Clients that missed events no longer in the buffer receive a
+    /// [`RESYNC_EVENT_NAME`] event instead.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero, the call will panic.
+    pub fn with_replay(mut self, capacity: usize, max_age: Option<Duration>) -> Self {
+        assert!(capacity > 0, "replay capacity must be greater than zero");
+
+        self.replay = Some(Arc::new(Mutex::new(ReplayBuffer::new(capacity, max_age))));
+        self
+    }
+
+    /// Push a fragment to all connected clients, using the fragment's swap strategy.
+    ///
+    /// Returns the number of clients the fragment was sent to, which is zero if it was skipped
+    /// as unchanged.
+    pub fn push(&self, fragment: impl Fragment) -> usize {
+        let html = match &self.diff {
+            Some(diff) => match diff.render_if_changed(BROADCAST_SCOPE, &fragment) {
+                Some(html) => html,
+                None => return 0,
+            },
+            None => render_fragment(&fragment),
+        };
+
+        self.send(self.event_name.clone(), html)
+    }
+
+    /// Push a fragment to all connected clients, with the specified swap strategy and target.
+    ///
+    /// Returns the number of clients the fragment was sent to.
+    pub fn push_raw(
+        &self,
+        insert_strategy: InsertStrategy,
+        target: &str,
+        fragment: impl Display,
+    ) -> usize {
+        let html =
+            crate::htmx::inject_oob_attribute(&fragment.to_string(), &insert_strategy, target);
+
+        self.send(self.event_name.clone(), html)
+    }
+
+    /// Send an event with a custom name and data to all connected clients.
+    ///
+    /// The data is sent as-is, for instance to elements listening with `sse-swap="<name>"` or to
+    /// `hx-trigger="sse:<name>"` triggers.
+    ///
+    /// Returns the number of clients the event was sent to.
+    pub fn send(&self, name: impl Into<Cow<'static, str>>, data: impl Display) -> usize {
+        let message = Message {
+            id: None,
+            name: name.into(),
+            data: data.to_string(),
+        };
+
+        // Sending only fails when there are no clients, which is not an error.
+        match &self.replay {
+            Some(replay) => {
+                // The lock is held while sending, so that resuming clients don't miss the event.
+                let mut replay = replay.lock().expect("replay lock should not be poisoned");
+                let message = replay.record(message);
+
+                self.sender.send(message).unwrap_or_default()
+            }
+            None => self.sender.send(message).unwrap_or_default(),
+        }
+    }
+
+    /// The number of connected clients.
+    pub fn client_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// Subscribe a new client to the stream.
+    ///
+    /// The returned response streams all the events sent from now on, until the client
+    /// disconnects.
+    pub fn subscribe(&self) -> axum::response::Response {
+        self.stream(self.sender.subscribe(), Vec::new())
+    }
+
+    /// Subscribe a client to the stream, replaying the events it missed according to the
+    /// `Last-Event-ID` header of its request.
+    ///
+    /// Without a [replay buffer](Self::with_replay) or a `Last-Event-ID` header, this is the same
+    /// as [`subscribe`](Self::subscribe).
+    pub fn resume(&self, headers: &http::HeaderMap) -> axum::response::Response {
+        let last_event_id = headers
+            .get(LAST_EVENT_ID)
+            .and_then(|value| value.to_str().ok());
+
+        match (&self.replay, last_event_id) {
+            (Some(replay), Some(last_event_id)) => {
+                let mut replay = replay.lock().expect("replay lock should not be poisoned");
+                let missed = replay.since(last_event_id);
+
+                self.stream(self.sender.subscribe(), missed)
+            }
+            _ => self.subscribe(),
+        }
+    }
+
+    /// Stream some missed events, then the events received from now on.
+    fn stream(
+        &self,
+        receiver: broadcast::Receiver<Message>,
+        missed: Vec<Message>,
+    ) -> axum::response::Response {
+        let missed = futures_util::stream::iter(missed)
+            .map(|message| Ok::<_, Infallible>(message.into_event()));
+        let received = futures_util::stream::unfold(receiver, |mut receiver| async {
+            loop {
+                match receiver.recv().await {
+                    Ok(message) => {
+                        return Some((Ok::<_, Infallible>(message.into_event()), receiver));
+                    }
+                    Err(broadcast::error::RecvError::Lagged(count)) => {
+                        tracing::warn!("SSE client lagged behind, {count} event(s) were dropped.");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+
+        let sse = Sse::new(missed.chain(received));
+
+        match self.keep_alive {
+            Some(interval) => sse
+                .keep_alive(KeepAlive::new().interval(interval))
+                .into_response(),
+            None => sse.into_response(),
+        }
+    }
+}
+
+/// The header holding the ID of the last event received by a reconnecting client.
+const LAST_EVENT_ID: http::HeaderName = http::HeaderName::from_static("last-event-id");
+
+/// An event sent to the clients of a stream.
+#[derive(Debug, Clone)]
+struct Message {
+    /// The ID of the event, if the stream has a replay buffer.
+    id: Option<String>,
+
+    /// The name of the event.
+    name: Cow<'static, str>,
+
+    /// The data of the event.
+    data: String,
+}
+
+impl Message {
+    fn into_event(self) -> Event {
+        let event = Event::default().event(self.name).data(self.data);
+
+        match self.id {
+            Some(id) => event.id(id),
+            None => event,
+        }
+    }
+}
+
+/// The last events of a stream, with their sequence numbers and send times.
+#[derive(Debug)]
+struct ReplayBuffer {
+    /// The token unique to the stream, prefixing the event IDs.
+    token: String,
+
+    /// The sequence number of the next event.
+    next_seq: u64,
+
+    /// The buffered events, oldest first.
+    events: VecDeque<(u64, Instant, Message)>,
+
+    /// The maximum number of buffered events.
+    capacity: usize,
+
+    /// The maximum age of buffered events, if any.
+    max_age: Option<Duration>,
+}
+
+impl ReplayBuffer {
+    fn new(capacity: usize, max_age: Option<Duration>) -> Self {
+        let token = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default();
+
+        Self {
+            token: format!("{token:x}"),
+            next_seq: 1,
+            events: VecDeque::with_capacity(capacity),
+            capacity,
+            max_age,
+        }
+    }
+
+    /// Assign an ID to an event, and record it.
+    fn record(&mut self, mut message: Message) -> Message {
+        let seq = self.next_seq;
+
+        self.next_seq += 1;
+        message.id = Some(self.id(seq));
+
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+
+        self.events
+            .push_back((seq, Instant::now(), message.clone()));
+        self.evict_expired();
+
+        message
+    }
+
+    /// Get the events sent after the one with the specified ID.
+    ///
+    /// If some of them are no longer buffered, or if the ID is unknown, a single resync event is
+    /// returned instead.
+    fn since(&mut self, last_event_id: &str) -> Vec<Message> {
+        self.evict_expired();
+
+        let last_seq = last_event_id
+            .split_once('-')
+            .filter(|(token, _)| *token == self.token)
+            .and_then(|(_, seq)| seq.parse::<u64>().ok())
+            .filter(|seq| *seq < self.next_seq);
+
+        let Some(last_seq) = last_seq else {
+            return vec![self.resync()];
+        };
+
+        if last_seq + 1 == self.next_seq {
+            return Vec::new();
+        }
+
+        match self.events.front() {
+            Some((first_seq, _, _)) if *first_seq <= last_seq + 1 => self
+                .events
+                .iter()
+                .filter(|(seq, _, _)| *seq > last_seq)
+                .map(|(_, _, message)| message.clone())
+                .collect(),
+            _ => vec![self.resync()],
+        }
+    }
+
+    /// The event telling a client that some events can't be replayed.
+    ///
+    /// Its ID is the one of the last event, so that the client resumes from there next time.
+    fn resync(&self) -> Message {
+        tracing::debug!("SSE client missed events that can't be replayed, resyncing.");
+
+        Message {
+            id: Some(self.id(self.next_seq - 1)),
+            name: Cow::Borrowed(RESYNC_EVENT_NAME),
+            // Browsers ignore events without data.
+            data: RESYNC_EVENT_NAME.to_owned(),
+        }
+    }
+
+    fn id(&self, seq: u64) -> String {
+        format!("{}-{seq}", self.token)
+    }
+
+    fn evict_expired(&mut self) {
+        let Some(max_age) = self.max_age else {
+            return;
+        };
+
+        while let Some((_, sent_at, _)) = self.events.front()
+            && sent_at.elapsed() > max_age
+        {
+            self.events.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::htmx::{HtmlId, Identity};
+
+    struct Counter(u32);
+
+    impl Display for Counter {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, r#"<span id="counter">{}</span>"#, self.0)
+        }
+    }
+
+    impl Identity for Counter {
+        fn id(&self) -> HtmlId {
+            HtmlId::from_static("counter").expect("valid ID")
+        }
+    }
+
+    impl Fragment for Counter {
+        fn insert_strategy(&self) -> InsertStrategy {
+            InsertStrategy::OuterHtml
+        }
+    }
+
+    async fn next_frame(body: &mut axum::body::BodyDataStream) -> String {
+        let frame = body.next().await.expect("frame").expect("valid frame");
+
+        String::from_utf8(frame.to_vec()).expect("UTF-8 frame")
+    }
+
+    #[tokio::test]
+    async fn test_push() {
+        let stream = SseFragmentStream::default().with_keep_alive(None);
+
+        assert_eq!(stream.push(Counter(0)), 0);
+
+        let response = stream.subscribe();
+        assert_eq!(stream.client_count(), 1);
+        assert_eq!(
+            response.headers().get(http::header::CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+
+        let mut body = response.into_body().into_data_stream();
+
+        assert_eq!(stream.push(Counter(1)), 1);
+        assert_eq!(
+            next_frame(&mut body).await,
+            "event: message\ndata: <span id=\"counter\" hx-swap-oob=\"true\">1</span>\n\n"
+        );
+
+        stream.send("refresh", 42);
+        assert_eq!(next_frame(&mut body).await, "event: refresh\ndata: 42\n\n");
+
+        drop(body);
+        assert_eq!(stream.client_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_push_raw() {
+        let stream = SseFragmentStream::new(4)
+            .with_keep_alive(None)
+            .with_event_name("updates");
+        let mut body = stream.subscribe().into_body().into_data_stream();
+
+        stream.push_raw(InsertStrategy::BeforeEnd, "#log", "<li>a</li>\n<li>b</li>");
+        assert_eq!(
+            next_frame(&mut body).await,
+            "event: updates\ndata: <template hx-swap-oob=\"beforeend:#log\"><li>a</li>\ndata: <li>b</li></template>\n\n"
+        );
+    }
+
+    fn last_event_id(id: &str) -> http::HeaderMap {
+        let mut headers = http::HeaderMap::new();
+        headers.insert(LAST_EVENT_ID, id.parse().unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_resume() {
+        let stream = SseFragmentStream::default()
+            .with_keep_alive(None)
+            .with_replay(2, None);
+        let token = stream
+            .replay
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .token
+            .clone();
+
+        stream.send("tick", 1);
+        stream.send("tick", 2);
+        stream.send("tick", 3);
+
+        // The client missed the last event.
+        let response = stream.resume(&last_event_id(&format!("{token}-2")));
+        let mut body = response.into_body().into_data_stream();
+
+        assert_eq!(
+            next_frame(&mut body).await,
+            format!("event: tick\ndata: 3\nid: {token}-3\n\n")
+        );
+
+        stream.send("tick", 4);
+        assert_eq!(
+            next_frame(&mut body).await,
+            format!("event: tick\ndata: 4\nid: {token}-4\n\n")
+        );
+
+        // The client is up to date.
+        let response = stream.resume(&last_event_id(&format!("{token}-4")));
+        let mut body = response.into_body().into_data_stream();
+
+        stream.send("tick", 5);
+        assert_eq!(
+            next_frame(&mut body).await,
+            format!("event: tick\ndata: 5\nid: {token}-5\n\n")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resume_with_missing_events() {
+        let stream = SseFragmentStream::default()
+            .with_keep_alive(None)
+            .with_replay(2, None);
+        let token = stream
+            .replay
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .token
+            .clone();
+
+        for i in 1..=4 {
+            stream.send("tick", i);
+        }
+
+        for id in [
+            format!("{token}-1"),
+            format!("{token}-9"),
+            "0-3".to_owned(),
+            "invalid".to_owned(),
+        ] {
+            let response = stream.resume(&last_event_id(&id));
+            let mut body = response.into_body().into_data_stream();
+
+            assert_eq!(
+                next_frame(&mut body).await,
+                format!("event: resync\ndata: resync\nid: {token}-4\n\n"),
+                "for `{id}`"
+            );
+        }
+    }
+
+    #[test]
+    fn test_replay_max_age() {
+        let mut replay = ReplayBuffer::new(8, Some(Duration::ZERO));
+        let message = |data: &str| Message {
+            id: None,
+            name: Cow::Borrowed("tick"),
+            data: data.to_owned(),
+        };
+
+        replay.record(message("1"));
+        replay.record(message("2"));
+        std::thread::sleep(Duration::from_millis(1));
+
+        let missed = replay.since(&format!("{}-1", replay.token));
+        assert_eq!(missed.len(), 1);
+        assert_eq!(missed[0].name, RESYNC_EVENT_NAME);
+    }
+}
@@ -0,0 +1,337 @@
+//! Static asset serving, like stylesheets, scripts and images.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use axum::response::IntoResponse;
+use md5::Digest;
+
+use crate::CachingResponseExt;
+
+/// The default duration static assets are cached for.
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// A set of static assets, served from a directory or embedded in the binary.
+///
+/// Assets are served with a content type guessed from their extension, an ETag computed from
+/// their content and a `public` cache control directive. Requests whose `If-None-Match` header
+/// matches the ETag of an asset get a `304 Not Modified` response.
+///
+/// Assets are typically mounted with
+/// [`ControllerRouter::with_static_assets`](crate::ControllerRouter::with_static_assets):
+///
+/// ```rust,ignore
+/// // Served from the disk, which picks up changes without restarting the server.
+/// let router = router.with_static_assets("/static", StaticAssets::from_dir("static"));
+///
+/// // Embedded in the binary.
+/// let router = router.with_static_assets(
+///     "/static",
+///     StaticAssets::embedded().with_file("app.css", include_bytes!("../static/app.css")),
+/// );
+/// ```
+///
+/// With the `include-dir` feature, a whole `include_dir::Dir` can also be embedded, as in
+/// `StaticAssets::from(&STATIC_DIR)`.
+#[derive(Debug, Clone)]
+pub struct StaticAssets {
+    source: Source,
+    max_age: Duration,
+}
+
+/// The source of static assets.
+#[derive(Debug, Clone)]
+enum Source {
+    /// Assets read from a directory on each request.
+    Dir(PathBuf),
+
+    /// Assets embedded in the binary, by path.
+    Embedded(Arc<HashMap<String, EmbeddedAsset>>),
+}
+
+/// An embedded asset.
+#[derive(Debug, Clone)]
+struct EmbeddedAsset {
+    content: &'static [u8],
+    etag: String,
+}
+
+impl StaticAssets {
+    /// Serve the files of a directory, and of its subdirectories.
+    ///
+    /// Files are read on each request, so that changes are picked up without restarting the
+    /// server. Paths containing `.` or `..` segments are rejected.
+    pub fn from_dir(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            source: Source::Dir(dir.into()),
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+
+    /// Serve assets embedded in the binary, added with [`StaticAssets::with_file`].
+    pub fn embedded() -> Self {
+        Self {
+            source: Source::Embedded(Default::default()),
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+
+    /// Add an embedded asset, at a path relative to the mount point of the assets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assets are served from a directory.
+    pub fn with_file(mut self, path: impl Into<String>, content: &'static [u8]) -> Self {
+        let Source::Embedded(assets) = &mut self.source else {
+            panic!("files can only be added to embedded assets");
+        };
+
+        Arc::make_mut(assets).insert(
+            path.into().trim_start_matches('/').to_owned(),
+            EmbeddedAsset {
+                content,
+                etag: etag(content),
+            },
+        );
+
+        self
+    }
+
+    /// Set the duration clients and shared caches may cache the assets for.
+    ///
+    /// Defaults to one hour. Clients revalidate expired assets with their ETag.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Apply the maximum age of static assets of a profile.
+    pub fn with_profile(self, settings: &crate::ProfileSettings) -> Self {
+        self.with_max_age(settings.static_max_age)
+    }
+
+    /// Serve the asset at the specified path, relative to the mount point of the assets.
+    pub async fn serve(&self, path: &str, headers: &http::HeaderMap) -> axum::response::Response {
+        let path = path.trim_start_matches('/');
+
+        let (content, etag) = match &self.source {
+            Source::Dir(dir) => {
+                let Some(file_path) = resolve(dir, path) else {
+                    return http::StatusCode::NOT_FOUND.into_response();
+                };
+
+                match tokio::fs::read(&file_path).await {
+                    Ok(content) => {
+                        let etag = etag(&content);
+
+                        (axum::body::Bytes::from(content), etag)
+                    }
+                    Err(err)
+                        if matches!(
+                            err.kind(),
+                            std::io::ErrorKind::NotFound | std::io::ErrorKind::IsADirectory
+                        ) =>
+                    {
+                        return http::StatusCode::NOT_FOUND.into_response();
+                    }
+                    Err(err) => {
+                        tracing::error!(
+                            "Failed to read static asset `{}`: {err}",
+                            file_path.display()
+                        );
+
+                        return http::StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                    }
+                }
+            }
+            Source::Embedded(assets) => match assets.get(path) {
+                Some(asset) => (
+                    axum::body::Bytes::from_static(asset.content),
+                    asset.etag.clone(),
+                ),
+                None => return http::StatusCode::NOT_FOUND.into_response(),
+            },
+        };
+
+        let response =
+            if crate::caching::etag_matches(&crate::caching::if_none_match(headers), &etag) {
+                http::StatusCode::NOT_MODIFIED.into_response()
+            } else {
+                ([(http::header::CONTENT_TYPE, content_type(path))], content).into_response()
+            };
+
+        response
+            .with_public_caching(self.max_age)
+            .with_etag(&etag)
+            .unwrap_or_else(|response| response)
+    }
+}
+
+impl From<PathBuf> for StaticAssets {
+    fn from(dir: PathBuf) -> Self {
+        Self::from_dir(dir)
+    }
+}
+
+impl From<&Path> for StaticAssets {
+    fn from(dir: &Path) -> Self {
+        Self::from_dir(dir)
+    }
+}
+
+#[cfg(feature = "include-dir")]
+impl From<&'static include_dir::Dir<'static>> for StaticAssets {
+    fn from(dir: &'static include_dir::Dir<'static>) -> Self {
+        fn add_files(
+            assets: StaticAssets,
+            dir: &'static include_dir::Dir<'static>,
+        ) -> StaticAssets {
+            dir.entries()
+                .iter()
+                .fold(assets, |assets, entry| match entry {
+                    include_dir::DirEntry::Dir(dir) => add_files(assets, dir),
+                    include_dir::DirEntry::File(file) => assets.with_file(
+                        file.path().to_string_lossy().replace('\\', "/"),
+                        file.contents(),
+                    ),
+                })
+        }
+
+        add_files(Self::embedded(), dir)
+    }
+}
+
+/// Resolve a relative asset path in a directory, rejecting any path that could escape it.
+fn resolve(dir: &Path, path: &str) -> Option<PathBuf> {
+    let mut file_path = dir.to_path_buf();
+
+    for segment in path.split('/') {
+        if segment.is_empty()
+            || segment == "."
+            || segment == ".."
+            || segment.contains(['\\', ':', '\0'])
+        {
+            return None;
+        }
+
+        file_path.push(segment);
+    }
+
+    Some(file_path)
+}
+
+/// Compute the ETag of an asset.
+fn etag(content: &[u8]) -> String {
+    let mut hasher = md5::Md5::new();
+
+    hasher.update(content);
+    hex::encode(hasher.finalize())
+}
+
+/// Guess the content type of an asset from its extension.
+fn content_type(path: &str) -> &'static str {
+    let extension = path
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "txt" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "xml" => "application/xml",
+        "json" | "map" => "application/json",
+        "webmanifest" => "application/manifest+json",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn body(response: axum::response::Response) -> axum::body::Bytes {
+        axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_embedded() {
+        let assets = StaticAssets::embedded().with_file("/css/app.css", b"body {}");
+
+        let response = assets.serve("css/app.css", &http::HeaderMap::new()).await;
+        let etag = response.headers()[http::header::ETAG].clone();
+
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(
+            response.headers()[http::header::CONTENT_TYPE],
+            "text/css; charset=utf-8"
+        );
+        assert_eq!(
+            response.headers()[http::header::CACHE_CONTROL],
+            "public, max-age=3600, must-revalidate"
+        );
+        assert_eq!(body(response).await, "body {}");
+
+        let headers = http::HeaderMap::from_iter([(http::header::IF_NONE_MATCH, etag)]);
+        let response = assets.serve("css/app.css", &headers).await;
+
+        assert_eq!(response.status(), http::StatusCode::NOT_MODIFIED);
+
+        let response = assets.serve("css/missing.css", &headers).await;
+
+        assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_dir() {
+        let dir = std::env::temp_dir().join(format!("htmxology-static-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("js")).unwrap();
+        std::fs::write(dir.join("js/app.js"), "alert(1)").unwrap();
+
+        let assets = StaticAssets::from_dir(&dir).with_max_age(Duration::from_secs(10));
+        let response = assets.serve("js/app.js", &http::HeaderMap::new()).await;
+
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(
+            response.headers()[http::header::CONTENT_TYPE],
+            "text/javascript; charset=utf-8"
+        );
+        assert_eq!(
+            response.headers()[http::header::CACHE_CONTROL],
+            "public, max-age=10, must-revalidate"
+        );
+        assert_eq!(body(response).await, "alert(1)");
+
+        for path in ["js/../js/app.js", "js//app.js", "js/missing.js", "js"] {
+            let response = assets.serve(path, &http::HeaderMap::new()).await;
+
+            assert_ne!(response.status(), http::StatusCode::OK, "{path}");
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,397 @@
+//! Request-scoped values for templates.
+
+use std::{any::Any, collections::HashMap, convert::Infallible, sync::Arc};
+
+use crate::ServerInfo;
+
+/// A function getting a value from the parts of a request.
+type PartsFn = dyn Fn(&http::request::Parts) -> Option<String> + Send + Sync;
+
+/// The configuration of the [`RenderContext`] of the requests.
+///
+/// Without it, the render context still gets the base URL, the locale from the
+/// `Accept-Language` header and the CSRF token of the requests.
+///
+/// ```rust,ignore
+/// let server = Server::builder(listener)
+///     .with_render_context(
+///         RenderContextConfig::default()
+///             .with_principal(|parts| user_name(parts))
+///             .with_default_locale("en")
+///             .with_assets_prefix("/static"),
+///     )
+///     .build();
+/// ```
+#[derive(Clone, Default)]
+pub struct RenderContextConfig {
+    locale: Option<Arc<PartsFn>>,
+    default_locale: Option<String>,
+    principal: Option<Arc<PartsFn>>,
+    assets_prefix: Option<String>,
+}
+
+impl std::fmt::Debug for RenderContextConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RenderContextConfig")
+            .field("default_locale", &self.default_locale)
+            .field("assets_prefix", &self.assets_prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RenderContextConfig {
+    /// Set a function getting the locale of a request, like from a cookie or the user settings.
+    ///
+    /// It replaces the `Accept-Language` header, and falls back to the default locale.
+    pub fn with_locale(
+        mut self,
+        locale: impl Fn(&http::request::Parts) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.locale = Some(Arc::new(locale));
+        self
+    }
+
+    /// Set the locale of the requests without one.
+    pub fn with_default_locale(mut self, default_locale: impl Into<String>) -> Self {
+        self.default_locale = Some(default_locale.into());
+        self
+    }
+
+    /// Set a function identifying the principal of a request, like its authenticated user.
+    pub fn with_principal(
+        mut self,
+        principal: impl Fn(&http::request::Parts) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.principal = Some(Arc::new(principal));
+        self
+    }
+
+    /// Set the path prefix the static assets are mounted at, to get their URLs with
+    /// [`AssetUrls`].
+    pub fn with_assets_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.assets_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Make the configuration available to the render context of the requests of a router.
+    pub(crate) fn apply(self, router: axum::Router) -> axum::Router {
+        router.layer(axum::extract::Extension(Arc::new(self)))
+    }
+}
+
+/// The URLs of the static assets, from the path prefix they are mounted at.
+///
+/// See [`RenderContextConfig::with_assets_prefix`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetUrls {
+    prefix: String,
+}
+
+impl AssetUrls {
+    /// Get the URL of an asset, from its path relative to the assets directory.
+    pub fn url(&self, path: &str) -> String {
+        format!(
+            "{}/{}",
+            self.prefix.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        )
+    }
+}
+
+/// The values shared by the templates of a request, like its locale or CSRF token.
+///
+/// The render context is built from the parts of each request, and can be extracted by handlers.
+/// It implements [`askama::Values`], so templates get its values without every view declaring
+/// them, when rendered with
+/// [`render_into_response_with_values`](crate::RenderIntoResponse::render_into_response_with_values):
+///
+/// | Key          | Type                  | Availability                                  |
+/// |--------------|-----------------------|-----------------------------------------------|
+/// | `base_url`   | [`http::Uri`]         | Served by a server or a controller service    |
+/// | `locale`     | [`String`]            | Requests with a locale                        |
+/// | `principal`  | [`String`]            | With [`RenderContextConfig::with_principal`]  |
+/// | `csrf_token` | `CsrfToken`           | With CSRF protection (`csrf` feature)         |
+/// | `assets`     | [`AssetUrls`]         | With [`RenderContextConfig::with_assets_prefix`] |
+///
+/// Other values can be added with [`RenderContext::with_value`].
+///
+/// ```rust,ignore
+/// async fn handle_request(&self, route: AppRoute, _htmx: htmx::Request, parts: Parts, ...) -> Self::Response {
+///     let context = RenderContext::from_parts(&parts).with_value("title", "Home");
+///
+///     Ok(HomePage { posts }.render_into_response_with_values(&context))
+/// }
+/// ```
+///
+/// ```html
+/// <html lang="{% if let Ok(locale) = "locale"|value::<String> %}{{ locale }}{% else %}en{% endif %}">
+/// {% if let Ok(assets) = "assets"|value::<htmxology::AssetUrls> %}
+///   <link rel="stylesheet" href="{{ assets.url("app.css") }}">
+/// {% endif %}
+/// ```
+#[derive(Clone, Default)]
+pub struct RenderContext {
+    base_url: Option<http::Uri>,
+    locale: Option<String>,
+    principal: Option<String>,
+    #[cfg(feature = "csrf")]
+    csrf_token: Option<crate::csrf::CsrfToken>,
+    assets: Option<AssetUrls>,
+    values: HashMap<String, Arc<dyn Any + Send + Sync>>,
+}
+
+impl std::fmt::Debug for RenderContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("RenderContext");
+
+        debug
+            .field("base_url", &self.base_url)
+            .field("locale", &self.locale)
+            .field("principal", &self.principal);
+
+        #[cfg(feature = "csrf")]
+        debug.field("csrf_token", &self.csrf_token);
+
+        debug
+            .field("assets", &self.assets)
+            .field("values", &self.values.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl RenderContext {
+    /// Build the render context of a request from its parts.
+    pub fn from_parts(parts: &http::request::Parts) -> Self {
+        let config = parts
+            .extensions
+            .get::<Arc<RenderContextConfig>>()
+            .cloned()
+            .unwrap_or_default();
+
+        let locale = match &config.locale {
+            Some(locale) => locale(parts),
+            None => accept_language(&parts.headers),
+        }
+        .or_else(|| config.default_locale.clone());
+
+        Self {
+            base_url: parts
+                .extensions
+                .get::<Arc<ServerInfo>>()
+                .map(|server_info| server_info.base_url.clone()),
+            locale,
+            principal: config
+                .principal
+                .as_ref()
+                .and_then(|principal| principal(parts)),
+            #[cfg(feature = "csrf")]
+            csrf_token: crate::csrf::CsrfToken::from_parts(parts),
+            assets: config.assets_prefix.as_ref().map(|prefix| AssetUrls {
+                prefix: prefix.clone(),
+            }),
+            values: HashMap::new(),
+        }
+    }
+
+    /// Add a value, available to templates under a key.
+    ///
+    /// It replaces the value of the key, if any, including the built-in ones.
+    pub fn with_value(mut self, key: impl Into<String>, value: impl Any + Send + Sync) -> Self {
+        self.values.insert(key.into(), Arc::new(value));
+        self
+    }
+
+    /// Get the base URL of the server.
+    pub fn base_url(&self) -> Option<&http::Uri> {
+        self.base_url.as_ref()
+    }
+
+    /// Get the locale of the request.
+    pub fn locale(&self) -> Option<&str> {
+        self.locale.as_deref()
+    }
+
+    /// Get the principal of the request.
+    pub fn principal(&self) -> Option<&str> {
+        self.principal.as_deref()
+    }
+
+    /// Get the CSRF token of the request.
+    #[cfg(feature = "csrf")]
+    pub fn csrf_token(&self) -> Option<&crate::csrf::CsrfToken> {
+        self.csrf_token.as_ref()
+    }
+
+    /// Get the URLs of the static assets.
+    pub fn assets(&self) -> Option<&AssetUrls> {
+        self.assets.as_ref()
+    }
+}
+
+impl askama::Values for RenderContext {
+    fn get_value<'a>(&'a self, key: &str) -> Option<&'a dyn Any> {
+        if let Some(value) = self.values.get(key) {
+            return Some(&**value);
+        }
+
+        match key {
+            "base_url" => self.base_url.as_ref().map(|value| value as &dyn Any),
+            "locale" => self.locale.as_ref().map(|value| value as &dyn Any),
+            "principal" => self.principal.as_ref().map(|value| value as &dyn Any),
+            #[cfg(feature = "csrf")]
+            "csrf_token" => self.csrf_token.as_ref().map(|value| value as &dyn Any),
+            "assets" => self.assets.as_ref().map(|value| value as &dyn Any),
+            _ => None,
+        }
+    }
+}
+
+impl<S: Send + Sync> axum::extract::FromRequestParts<S> for RenderContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_parts(parts))
+    }
+}
+
+/// Get the preferred language of the `Accept-Language` header.
+fn accept_language(headers: &http::HeaderMap) -> Option<String> {
+    headers
+        .get_all(http::header::ACCEPT_LANGUAGE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|language| {
+            let mut params = language.split(';').map(str::trim);
+            let tag = params.next().filter(|tag| !tag.is_empty() && *tag != "*")?;
+            let quality = params
+                .find_map(|param| param.strip_prefix("q="))
+                .map_or(Some(1.0), |quality| quality.parse::<f32>().ok())?;
+
+            Some((tag, quality))
+        })
+        .filter(|(_, quality)| *quality > 0.0)
+        // The first language wins among the ones with the same quality.
+        .fold(
+            None,
+            |best: Option<(&str, f32)>, (tag, quality)| match best {
+                Some((_, best_quality)) if best_quality >= quality => best,
+                _ => Some((tag, quality)),
+            },
+        )
+        .map(|(tag, _)| tag.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use askama::Template;
+
+    use super::*;
+
+    #[derive(Template)]
+    #[template(
+        source = r#"{% if let Ok(base_url) = "base_url"|value::<http::Uri> %}{{ base_url }}{% endif %} {% if let Ok(locale) = "locale"|value::<String> %}{{ locale }}{% endif %} {% if let Ok(assets) = "assets"|value::<AssetUrls> %}{{ assets.url("app.css") }}{% endif %} {% if let Ok(title) = "title"|value::<&str> %}{{ title }}{% endif %}"#,
+        ext = "html"
+    )]
+    struct Page;
+
+    fn parts(headers: &[(&str, &str)]) -> http::request::Parts {
+        let mut request = http::Request::get("/");
+
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        request.body(()).unwrap().into_parts().0
+    }
+
+    #[test]
+    fn test_accept_language() {
+        let locale = |value: &str| accept_language(&parts(&[("accept-language", value)]).headers);
+
+        assert_eq!(
+            locale("fr-CH, fr;q=0.9, en;q=0.8"),
+            Some("fr-CH".to_owned())
+        );
+        assert_eq!(locale("en;q=0.5, de;q=0.7, *"), Some("de".to_owned()));
+        assert_eq!(locale("en, de"), Some("en".to_owned()));
+        assert_eq!(locale("en;q=0, *"), None);
+        assert_eq!(accept_language(&http::HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_from_parts() {
+        let context = RenderContext::from_parts(&parts(&[("accept-language", "fr")]));
+
+        assert_eq!(context.base_url(), None);
+        assert_eq!(context.locale(), Some("fr"));
+        assert_eq!(context.principal(), None);
+        assert_eq!(context.assets(), None);
+
+        let mut parts = parts(&[("accept-language", "fr"), ("x-user", "alice")]);
+        parts.extensions.insert(Arc::new(ServerInfo {
+            base_url: http::Uri::from_static("https://example.com"),
+        }));
+        parts.extensions.insert(Arc::new(
+            RenderContextConfig::default()
+                .with_locale(|parts| {
+                    parts
+                        .headers
+                        .get("x-locale")
+                        .and_then(|value| value.to_str().ok())
+                        .map(ToOwned::to_owned)
+                })
+                .with_default_locale("en")
+                .with_principal(|parts| {
+                    parts
+                        .headers
+                        .get("x-user")
+                        .and_then(|value| value.to_str().ok())
+                        .map(ToOwned::to_owned)
+                })
+                .with_assets_prefix("/static/"),
+        ));
+
+        let context = RenderContext::from_parts(&parts);
+
+        assert_eq!(
+            context.base_url(),
+            Some(&http::Uri::from_static("https://example.com"))
+        );
+        assert_eq!(context.locale(), Some("en"));
+        assert_eq!(context.principal(), Some("alice"));
+        assert_eq!(
+            context.assets().map(|assets| assets.url("/app.css")),
+            Some("/static/app.css".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_values() {
+        let mut parts = parts(&[("accept-language", "fr")]);
+        parts.extensions.insert(Arc::new(ServerInfo {
+            base_url: http::Uri::from_static("https://example.com"),
+        }));
+        parts.extensions.insert(Arc::new(
+            RenderContextConfig::default().with_assets_prefix("/static"),
+        ));
+
+        let context = RenderContext::from_parts(&parts).with_value("title", "Home");
+
+        assert_eq!(
+            Page.render_with_values(&context).unwrap(),
+            "https://example.com/ fr /static/app.css Home"
+        );
+
+        // Added values take precedence over the built-in ones.
+        let context = context.with_value("locale", "de".to_owned());
+
+        assert_eq!(
+            Page.render_with_values(&context).unwrap(),
+            "https://example.com/ de /static/app.css Home"
+        );
+    }
+}
@@ -1,5 +1,9 @@
 //! Templating facilities.
 
+mod context;
+
+pub use context::{AssetUrls, RenderContext, RenderContextConfig};
+
 /// Render a template into an Axum response.
 pub trait RenderIntoResponse: Sized {
     /// Render the template into a response.
@@ -8,6 +12,8 @@ pub trait RenderIntoResponse: Sized {
     }
 
     /// Render the template into a response, with values.
+    ///
+    /// The values are typically the [`RenderContext`] of the request.
     fn render_into_response_with_values(
         self,
         values: &dyn askama::Values,
@@ -0,0 +1,429 @@
+//! End-to-end testing of multi-step HTMX navigations, without a browser.
+//!
+//! A [`Scenario`] sends a sequence of requests to a router, carrying forward what a browser
+//! running htmx would: the cookies set by the responses, the `HX-Current-URL` of the page, and
+//! the URLs pushed by `HX-Push-Url` or `HX-Replace-Url`. Redirects are followed, including the
+//! `HX-Redirect` and `HX-Location` ones of htmx responses.
+//!
+//! ```rust,ignore
+//! let mut scenario = Scenario::new(ControllerRouter::new(AppController, |_| async {}));
+//!
+//! let response = scenario.post_form("/login", &[("user", "alice")]).await;
+//! assert_eq!(response.url(), "/dashboard");
+//!
+//! let response = scenario.hx_get("/dashboard/projects").await;
+//! assert_eq!(response.select("li.project").len(), 2);
+//! assert_eq!(scenario.current_url(), Some("/dashboard/projects"));
+//!
+//! let response = scenario
+//!     .send(ScenarioRequest::post("/projects").htmx().with_form(&[("name", "New")]))
+//!     .await;
+//! assert_eq!(response.status(), http::StatusCode::CREATED);
+//! ```
+
+use std::collections::BTreeMap;
+
+use tower_service::Service as _;
+
+use crate::{ControllerRouter, ControllerService, ServerInfo, htmx::header};
+
+/// The maximum number of redirects followed for a single request.
+const MAX_REDIRECTS: usize = 10;
+
+/// The base URL of the server of scenarios created with [`Scenario::new`].
+const DEFAULT_BASE_URL: &str = "http://localhost";
+
+/// A request of a [`Scenario`].
+#[derive(Debug, Clone)]
+pub struct ScenarioRequest {
+    method: http::Method,
+    url: String,
+    htmx: bool,
+    target: Option<String>,
+    form: Option<String>,
+    headers: http::HeaderMap,
+
+    /// Whether the URL of the request is pushed, as for `HX-Location` redirects.
+    push_url: bool,
+}
+
+impl ScenarioRequest {
+    /// Create a request with a method and a URL, like `/items?page=2`.
+    pub fn new(method: http::Method, url: impl Into<String>) -> Self {
+        Self {
+            method,
+            url: url.into(),
+            htmx: false,
+            target: None,
+            form: None,
+            headers: http::HeaderMap::new(),
+            push_url: false,
+        }
+    }
+
+    /// Create a `GET` request.
+    pub fn get(url: impl Into<String>) -> Self {
+        Self::new(http::Method::GET, url)
+    }
+
+    /// Create a `POST` request.
+    pub fn post(url: impl Into<String>) -> Self {
+        Self::new(http::Method::POST, url)
+    }
+
+    /// Send the request as htmx would, with the `HX-Request` and `HX-Current-URL` headers.
+    pub fn htmx(mut self) -> Self {
+        self.htmx = true;
+        self
+    }
+
+    /// Set the `id` of the target element of an htmx request, sent as `HX-Target`.
+    pub fn with_target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    /// Set the fields of a form submitted as an `application/x-www-form-urlencoded` body.
+    pub fn with_form(mut self, fields: &[(&str, &str)]) -> Self {
+        self.form = Some(
+            fields
+                .iter()
+                .map(|(name, value)| format!("{}={}", encode(name), encode(value)))
+                .collect::<Vec<_>>()
+                .join("&"),
+        );
+        self
+    }
+
+    /// Add a header to the request.
+    pub fn with_header(mut self, name: http::HeaderName, value: http::HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+}
+
+/// Percent-encode a form field name or value.
+fn encode(value: &str) -> percent_encoding::PercentEncode<'_> {
+    percent_encoding::utf8_percent_encode(value, percent_encoding::NON_ALPHANUMERIC)
+}
+
+/// A response of a [`Scenario`], with its body collected.
+#[derive(Debug, Clone)]
+pub struct ScenarioResponse {
+    url: String,
+    status: http::StatusCode,
+    headers: http::HeaderMap,
+    body: String,
+}
+
+impl ScenarioResponse {
+    /// Get the URL of the request the response answers, after redirects.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Get the status of the response.
+    pub fn status(&self) -> http::StatusCode {
+        self.status
+    }
+
+    /// Get the headers of the response.
+    pub fn headers(&self) -> &http::HeaderMap {
+        &self.headers
+    }
+
+    /// Get a header of the response, if it is set and valid UTF-8.
+    pub fn header(&self, name: impl http::header::AsHeaderName) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    /// Get the body of the response.
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// Get the HTML of the elements of the body matching a CSS selector.
+    ///
+    /// # Panics
+    ///
+    /// If the selector is invalid, the call will panic.
+    pub fn select(&self, selector: &str) -> Vec<String> {
+        let selector = scraper::Selector::parse(selector)
+            .unwrap_or_else(|err| panic!("invalid selector `{selector}`: {err}"));
+
+        scraper::Html::parse_fragment(&self.body)
+            .select(&selector)
+            .map(|element| element.html())
+            .collect()
+    }
+}
+
+/// A sequence of requests to a router, simulating a browser running htmx.
+///
+/// Each request carries the cookies set by the previous responses. Classic requests navigate to
+/// their URL, following redirects, and htmx requests send the `HX-Current-URL` of the page and
+/// navigate to the URLs pushed or replaced by their response.
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    service: ControllerService,
+    base_url: String,
+    cookies: BTreeMap<String, String>,
+    current_url: Option<String>,
+}
+
+impl Scenario {
+    /// Create a scenario sending requests to a router, served at `http://localhost`.
+    pub fn new(router: ControllerRouter) -> Self {
+        let server_info = ServerInfo {
+            base_url: http::Uri::from_static(DEFAULT_BASE_URL),
+        };
+
+        Self::from_service(router.into_service(server_info), DEFAULT_BASE_URL)
+    }
+
+    /// Create a scenario sending requests to a service, served at the specified base URL.
+    pub fn from_service(service: ControllerService, base_url: impl Into<String>) -> Self {
+        Self {
+            service,
+            base_url: base_url.into().trim_end_matches('/').to_owned(),
+            cookies: BTreeMap::new(),
+            current_url: None,
+        }
+    }
+
+    /// Get the URL of the current page, if any.
+    pub fn current_url(&self) -> Option<&str> {
+        self.current_url.as_deref()
+    }
+
+    /// Get the value of a cookie, if it is set.
+    pub fn cookie(&self, name: &str) -> Option<&str> {
+        self.cookies.get(name).map(String::as_str)
+    }
+
+    /// Set a cookie, as if it was set by a previous response.
+    pub fn set_cookie(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.cookies.insert(name.into(), value.into());
+    }
+
+    /// Navigate to a URL.
+    pub async fn get(&mut self, url: &str) -> ScenarioResponse {
+        self.send(ScenarioRequest::get(url)).await
+    }
+
+    /// Submit a form, without htmx.
+    pub async fn post_form(&mut self, url: &str, fields: &[(&str, &str)]) -> ScenarioResponse {
+        self.send(ScenarioRequest::post(url).with_form(fields))
+            .await
+    }
+
+    /// Send an htmx `GET` request, like a `hx-get` attribute.
+    pub async fn hx_get(&mut self, url: &str) -> ScenarioResponse {
+        self.send(ScenarioRequest::get(url).htmx()).await
+    }
+
+    /// Submit a form with htmx, like a `hx-post` attribute.
+    pub async fn hx_post(&mut self, url: &str, fields: &[(&str, &str)]) -> ScenarioResponse {
+        self.send(ScenarioRequest::post(url).htmx().with_form(fields))
+            .await
+    }
+
+    /// Send a request, and follow its redirects.
+    ///
+    /// # Panics
+    ///
+    /// If more than 10 redirects are followed, the call will panic.
+    pub async fn send(&mut self, mut request: ScenarioRequest) -> ScenarioResponse {
+        for _ in 0..=MAX_REDIRECTS {
+            let response = self.call(&request).await;
+
+            match self.next_request(&request, &response) {
+                Some(next) => request = next,
+                None => {
+                    self.navigate(&request, &response);
+
+                    return response;
+                }
+            }
+        }
+
+        panic!("too many redirects from `{}`", request.url);
+    }
+
+    /// Send a single request.
+    async fn call(&mut self, request: &ScenarioRequest) -> ScenarioResponse {
+        let mut builder = http::Request::builder()
+            .method(request.method.clone())
+            .uri(&request.url);
+
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+
+        if !self.cookies.is_empty() {
+            let cookies = self
+                .cookies
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect::<Vec<_>>()
+                .join("; ");
+
+            builder = builder.header(http::header::COOKIE, cookies);
+        }
+
+        if request.htmx {
+            builder = builder.header(header::HX_REQUEST, "true");
+
+            if let Some(current_url) = &self.current_url {
+                builder = builder.header(
+                    header::HX_CURRENT_URL,
+                    format!("{}{current_url}", self.base_url),
+                );
+            }
+
+            if let Some(target) = &request.target {
+                builder = builder.header(header::HX_TARGET, target);
+            }
+        }
+
+        let body = match &request.form {
+            Some(form) => {
+                builder = builder.header(
+                    http::header::CONTENT_TYPE,
+                    "application/x-www-form-urlencoded",
+                );
+
+                axum::body::Body::from(form.clone())
+            }
+            None => axum::body::Body::empty(),
+        };
+
+        let request_url = request.url.clone();
+        let request = builder.body(body).expect("request should be valid");
+        let response = match self.service.call(request).await {
+            Ok(response) => response,
+            Err(err) => match err {},
+        };
+
+        let (parts, body) = response.into_parts();
+
+        for value in parts.headers.get_all(http::header::SET_COOKIE) {
+            if let Ok(value) = value.to_str() {
+                self.store_cookie(value);
+            }
+        }
+
+        let body = axum::body::to_bytes(body, usize::MAX)
+            .await
+            .expect("response body should be readable");
+
+        ScenarioResponse {
+            url: request_url,
+            status: parts.status,
+            headers: parts.headers,
+            body: String::from_utf8_lossy(&body).into_owned(),
+        }
+    }
+
+    /// Store or remove a cookie from a `Set-Cookie` header.
+    fn store_cookie(&mut self, set_cookie: &str) {
+        let mut attributes = set_cookie.split(';').map(str::trim);
+
+        let Some((name, value)) = attributes.next().and_then(|pair| pair.split_once('=')) else {
+            return;
+        };
+
+        let expired = attributes.any(|attribute| {
+            attribute
+                .split_once('=')
+                .is_some_and(|(key, value)| key.eq_ignore_ascii_case("max-age") && value == "0")
+        });
+
+        if expired {
+            self.cookies.remove(name);
+        } else {
+            self.cookies.insert(name.to_owned(), value.to_owned());
+        }
+    }
+
+    /// Get the request a response redirects to, if any.
+    fn next_request(
+        &self,
+        request: &ScenarioRequest,
+        response: &ScenarioResponse,
+    ) -> Option<ScenarioRequest> {
+        if request.htmx {
+            if let Some(url) = response.header(header::HX_REDIRECT) {
+                return Some(ScenarioRequest::get(self.relative_url(url)));
+            }
+
+            if let Some(location) = response.header(header::HX_LOCATION) {
+                let url = match serde_json::from_str::<serde_json::Value>(location) {
+                    Ok(serde_json::Value::Object(details)) => details
+                        .get("path")
+                        .and_then(serde_json::Value::as_str)?
+                        .to_owned(),
+                    _ => location.to_owned(),
+                };
+
+                return Some(ScenarioRequest {
+                    push_url: true,
+                    ..ScenarioRequest::get(self.relative_url(&url)).htmx()
+                });
+            }
+
+            return None;
+        }
+
+        if !response.status.is_redirection() {
+            return None;
+        }
+
+        let location = self.relative_url(response.header(http::header::LOCATION)?);
+
+        // Like browsers, only `307` and `308` redirects preserve the method and the body.
+        Some(match response.status {
+            http::StatusCode::TEMPORARY_REDIRECT | http::StatusCode::PERMANENT_REDIRECT => {
+                ScenarioRequest {
+                    url: location,
+                    ..request.clone()
+                }
+            }
+            _ => ScenarioRequest::get(location),
+        })
+    }
+
+    /// Update the current page after the final response of a request.
+    fn navigate(&mut self, request: &ScenarioRequest, response: &ScenarioResponse) {
+        if !request.htmx {
+            self.current_url = Some(request.url.clone());
+
+            return;
+        }
+
+        let pushed = if request.push_url {
+            Some(request.url.as_str())
+        } else {
+            response
+                .header(header::HX_PUSH_URL)
+                .or_else(|| response.header(header::HX_REPLACE_URL))
+        };
+
+        if let Some(url) = pushed.filter(|url| *url != "false") {
+            self.current_url = Some(self.relative_url(url));
+        }
+    }
+
+    /// Get the path and query of a URL on the server, leaving other URLs as-is.
+    fn relative_url(&self, url: &str) -> String {
+        url.strip_prefix(&self.base_url)
+            .map(|path| {
+                if path.is_empty() {
+                    "/".to_owned()
+                } else {
+                    path.to_owned()
+                }
+            })
+            .unwrap_or_else(|| url.to_owned())
+    }
+}
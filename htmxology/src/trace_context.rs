@@ -0,0 +1,388 @@
+//! W3C trace context propagation, across the HTMX requests of a page.
+
+use std::{
+    hash::{BuildHasher, Hasher},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use tracing::Instrument;
+
+/// The name of the header holding the trace parent.
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// The name of the header holding the vendor-specific trace state.
+pub const TRACESTATE_HEADER: &str = "tracestate";
+
+/// The maximum length of a propagated trace state, as recommended by the W3C specification.
+const MAX_TRACESTATE_LEN: usize = 512;
+
+/// The trace flag marking sampled traces.
+const SAMPLED_FLAG: u8 = 0x01;
+
+/// The W3C trace context of a request.
+///
+/// Enabled with [`ServerBuilder::with_trace_context`](crate::ServerBuilder::with_trace_context),
+/// which parses the `traceparent` and `tracestate` headers of each request, or starts a new
+/// trace if they are missing or invalid, and gives the request its own span identifier. The
+/// request is handled in a `request` tracing span recording the identifiers.
+///
+/// The context of a request is available to controllers through [`TraceContext::from_parts`],
+/// and can be embedded in the rendered pages with [`TraceContext::hx_headers_attribute`], so
+/// that the HTMX requests of the page continue the same distributed trace:
+///
+/// ```rust,ignore
+/// let trace_context = TraceContext::from_parts(&parts).expect("trace context should be enabled");
+///
+/// let body = format!("<html {}>...</html>", trace_context.hx_headers_attribute());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    /// The identifier of the whole trace.
+    trace_id: [u8; 16],
+
+    /// The identifier of the span the request was issued from, if any.
+    parent_id: Option<[u8; 8]>,
+
+    /// The identifier of the span of the request.
+    span_id: [u8; 8],
+
+    /// The trace flags.
+    flags: u8,
+
+    /// The vendor-specific trace state, propagated as is.
+    tracestate: Option<String>,
+}
+
+impl TraceContext {
+    /// Start a new, sampled trace.
+    pub fn new() -> Self {
+        Self {
+            trace_id: random_id(),
+            parent_id: None,
+            span_id: random_id(),
+            flags: SAMPLED_FLAG,
+            tracestate: None,
+        }
+    }
+
+    /// Continue the trace of the `traceparent` and `tracestate` headers of a request, in a new
+    /// span.
+    ///
+    /// Returns `None` if the `traceparent` header is missing or invalid, in which case the
+    /// `tracestate` header must be ignored too.
+    pub fn from_headers(headers: &http::HeaderMap) -> Option<Self> {
+        let mut traceparents = headers.get_all(TRACEPARENT_HEADER).iter();
+
+        let (Some(traceparent), None) = (traceparents.next(), traceparents.next()) else {
+            return None;
+        };
+
+        let (trace_id, parent_id, flags) = parse_traceparent(traceparent.to_str().ok()?)?;
+
+        let tracestate = headers
+            .get_all(TRACESTATE_HEADER)
+            .iter()
+            .map(|value| value.to_str().map(str::trim))
+            .collect::<Result<Vec<_>, _>>()
+            .ok()
+            .map(|values| values.join(","))
+            .filter(|tracestate| !tracestate.is_empty() && tracestate.len() <= MAX_TRACESTATE_LEN);
+
+        Some(Self {
+            trace_id,
+            parent_id: Some(parent_id),
+            span_id: random_id(),
+            flags,
+            tracestate,
+        })
+    }
+
+    /// Get the trace context of a request, if enabled on the server.
+    pub fn from_parts(parts: &http::request::Parts) -> Option<Self> {
+        parts.extensions.get().cloned()
+    }
+
+    /// Get the identifier of the trace, as 32 hexadecimal digits.
+    pub fn trace_id(&self) -> String {
+        hex::encode(self.trace_id)
+    }
+
+    /// Get the identifier of the span the request was issued from, as 16 hexadecimal digits.
+    ///
+    /// Returns `None` if the request started the trace.
+    pub fn parent_id(&self) -> Option<String> {
+        self.parent_id.map(hex::encode)
+    }
+
+    /// Get the identifier of the span of the request, as 16 hexadecimal digits.
+    pub fn span_id(&self) -> String {
+        hex::encode(self.span_id)
+    }
+
+    /// Check whether the trace is sampled.
+    pub fn is_sampled(&self) -> bool {
+        self.flags & SAMPLED_FLAG != 0
+    }
+
+    /// Get the vendor-specific trace state, if any.
+    pub fn tracestate(&self) -> Option<&str> {
+        self.tracestate.as_deref()
+    }
+
+    /// Get the `traceparent` header value of the requests issued while handling the request,
+    /// with its span as their parent.
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{}-{}-{:02x}",
+            self.trace_id(),
+            self.span_id(),
+            self.flags
+        )
+    }
+
+    /// Get a `hx-headers` attribute declaration sending the trace context as headers.
+    ///
+    /// Set on an element, it applies to all the HTMX requests of the element and its children.
+    /// HTMX merges the `hx-headers` of the ancestors of an element, so it can be set on the
+    /// `<html>` element while the `<body>` element sends a
+    /// CSRF token, for instance.
+    pub fn hx_headers_attribute(&self) -> String {
+        let mut headers = serde_json::Map::new();
+
+        headers.insert(TRACEPARENT_HEADER.to_owned(), self.traceparent().into());
+
+        if let Some(tracestate) = &self.tracestate {
+            headers.insert(TRACESTATE_HEADER.to_owned(), tracestate.clone().into());
+        }
+
+        let headers = serde_json::Value::Object(headers)
+            .to_string()
+            .replace('&', "&amp;")
+            .replace('\'', "&#39;");
+
+        format!("hx-headers='{headers}'")
+    }
+
+    /// Apply the trace context middleware to a router.
+    pub(crate) fn apply(router: axum::Router) -> axum::Router {
+        router.layer(axum::middleware::from_fn(middleware))
+    }
+}
+
+impl Default for TraceContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Send + Sync> axum::extract::FromRequestParts<S> for TraceContext {
+    type Rejection = (http::StatusCode, &'static str);
+
+    async fn from_request_parts(
+        parts: &mut http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        Self::from_parts(parts).ok_or((
+            http::StatusCode::INTERNAL_SERVER_ERROR,
+            "trace context is not enabled on the server",
+        ))
+    }
+}
+
+/// Parse a `traceparent` header value into its trace identifier, parent identifier and flags.
+///
+/// Values of future versions are accepted, ignoring the fields they add.
+fn parse_traceparent(traceparent: &str) -> Option<([u8; 16], [u8; 8], u8)> {
+    let mut fields = traceparent.trim().split('-');
+    let version = fields.next().and_then(parse_hex::<1>)?[0];
+    let trace_id = fields.next().and_then(parse_hex::<16>)?;
+    let parent_id = fields.next().and_then(parse_hex::<8>)?;
+    let flags = fields.next().and_then(parse_hex::<1>)?[0];
+
+    let is_valid = match version {
+        0x00 => fields.next().is_none(),
+        0xff => false,
+        _ => true,
+    };
+
+    (is_valid && trace_id != [0; 16] && parent_id != [0; 8]).then_some((trace_id, parent_id, flags))
+}
+
+/// Parse lowercase hexadecimal digits into exactly `N` bytes.
+fn parse_hex<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    if hex.len() != N * 2 || !hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+        return None;
+    }
+
+    let mut bytes = [0; N];
+
+    hex::decode_to_slice(hex, &mut bytes).ok()?;
+
+    Some(bytes)
+}
+
+/// Generate a random, non-zero identifier.
+///
+/// Identifiers need to be unique, not unpredictable: they are derived from the randomly keyed
+/// hasher of the standard library, and a counter.
+fn random_id<const N: usize>() -> [u8; N] {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let mut id = [0; N];
+
+    while id == [0; N] {
+        for chunk in id.chunks_mut(8) {
+            let mut hasher = std::hash::RandomState::new().build_hasher();
+
+            hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+            chunk.copy_from_slice(&hasher.finish().to_le_bytes()[..chunk.len()]);
+        }
+    }
+
+    id
+}
+
+/// Attach the trace context to the request, and handle it in its span.
+async fn middleware(
+    mut request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let trace_context = TraceContext::from_headers(request.headers()).unwrap_or_default();
+
+    let span = tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        trace_id = %trace_context.trace_id(),
+        span_id = %trace_context.span_id(),
+        parent_id = trace_context.parent_id(),
+    );
+
+    request.extensions_mut().insert(trace_context);
+
+    next.run(request).instrument(span).await
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_service::Service as _;
+
+    use super::*;
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    fn headers(traceparent: &str, tracestate: Option<&str>) -> http::HeaderMap {
+        let mut headers = http::HeaderMap::new();
+
+        headers.insert(TRACEPARENT_HEADER, traceparent.parse().unwrap());
+
+        if let Some(tracestate) = tracestate {
+            headers.insert(TRACESTATE_HEADER, tracestate.parse().unwrap());
+        }
+
+        headers
+    }
+
+    #[test]
+    fn test_from_headers() {
+        let context =
+            TraceContext::from_headers(&headers(TRACEPARENT, Some("congo=t61rcWkgMzE"))).unwrap();
+
+        assert_eq!(context.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(context.parent_id().as_deref(), Some("00f067aa0ba902b7"));
+        assert_ne!(context.span_id(), "00f067aa0ba902b7");
+        assert!(context.is_sampled());
+        assert_eq!(context.tracestate(), Some("congo=t61rcWkgMzE"));
+        assert_eq!(
+            context.traceparent(),
+            format!(
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-{}-01",
+                context.span_id()
+            )
+        );
+
+        // Future versions can add fields.
+        assert!(
+            TraceContext::from_headers(&headers(&format!("cc{}-extra", &TRACEPARENT[2..]), None))
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_invalid_traceparents() {
+        for traceparent in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+        ] {
+            assert_eq!(
+                TraceContext::from_headers(&headers(traceparent, Some("a=b"))),
+                None,
+                "{traceparent}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        let a = TraceContext::new();
+        let b = TraceContext::new();
+
+        assert_ne!(a.trace_id(), b.trace_id());
+        assert_ne!(a.span_id(), b.span_id());
+        assert_eq!(a.parent_id(), None);
+        assert!(TraceContext::from_headers(&headers(&a.traceparent(), None)).is_some());
+    }
+
+    #[test]
+    fn test_hx_headers_attribute() {
+        let context = TraceContext::from_headers(&headers(TRACEPARENT, Some("a='1'&b=2"))).unwrap();
+
+        assert_eq!(
+            context.hx_headers_attribute(),
+            format!(
+                r#"hx-headers='{{"traceparent":"{}","tracestate":"a=&#39;1&#39;&amp;b=2"}}'"#,
+                context.traceparent()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_middleware() {
+        let mut router = TraceContext::apply(axum::Router::new().route(
+            "/",
+            axum::routing::get(|context: TraceContext| async move { context.traceparent() }),
+        ));
+
+        let mut call = async |traceparent: Option<&str>| {
+            let mut request = http::Request::builder().uri("/");
+
+            if let Some(traceparent) = traceparent {
+                request = request.header(TRACEPARENT_HEADER, traceparent);
+            }
+
+            let response = router
+                .call(request.body(axum::body::Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let body = axum::body::to_bytes(response.into_body(), 1024)
+                .await
+                .unwrap();
+
+            String::from_utf8(body.to_vec()).unwrap()
+        };
+
+        let continued = call(Some(TRACEPARENT)).await;
+        assert!(continued.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"));
+        assert_ne!(continued, TRACEPARENT);
+
+        let started = call(None).await;
+        assert!(parse_traceparent(&started).is_some());
+        assert!(!started.contains("4bf92f3577b34da6a3ce929d0e0e4736"));
+    }
+}
@@ -0,0 +1,426 @@
+//! WebSockets, to push fragments to connected clients.
+//!
+//! The messages are compatible with the [htmx WebSockets extension](https://htmx.org/extensions/ws/).
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use tokio::sync::{broadcast, mpsc};
+
+use crate::htmx::{Fragment, FragmentDiff, InsertStrategy, render_fragment};
+
+/// The default number of messages buffered for slow clients.
+const DEFAULT_CAPACITY: usize = 64;
+
+/// The scope of the broadcast fragments in the [`FragmentDiff`] of a hub.
+const BROADCAST_SCOPE: &str = "*";
+
+/// The identifier of a client connected to a [`WsFragmentHub`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WsClientId(uuid::Uuid);
+
+impl Display for WsClientId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A handler for the messages sent by clients.
+type MessageHandler = Arc<dyn Fn(&WsFragmentHub, WsClientId, &str) + Send + Sync>;
+
+/// A hub of HTML fragments pushed to connected clients through WebSockets.
+///
+/// Fragments are sent as out-of-band swaps: their root element carries an `hx-swap-oob`
+/// attribute, so that a single WebSocket connection can update any element of the page. Fragments
+/// can be broadcast to all clients, or pushed to a single one. Cloning the hub returns a handle
+/// to the same set of clients.
+///
+/// Clients that fall behind by more than the capacity of the hub miss the oldest broadcasts.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let hub = WsFragmentHub::default().with_message_handler(|hub, client_id, message| {
+///     // `message` is the JSON sent by `ws-send` elements.
+///     hub.push_to(client_id, Notification::new("Received!"));
+/// });
+///
+/// let router = ControllerRouter::new(controller, |_| async {})
+///     .with_ws_endpoint("/ws", hub.clone());
+///
+/// // Anywhere in the application:
+/// hub.push(Notification::new("Saved!"));
+/// ```
+///
+/// With the page connecting to the hub:
+///
+/// ```html
+/// <div hx-ext="ws" ws-connect="/ws"></div>
+/// ```
+#[derive(Clone)]
+pub struct WsFragmentHub {
+    /// The sender of the broadcast messages.
+    sender: broadcast::Sender<String>,
+
+    /// The senders of the messages to specific clients.
+    clients: Arc<Mutex<HashMap<WsClientId, mpsc::UnboundedSender<String>>>>,
+
+    /// The handler for the messages sent by clients, if any.
+    message_handler: Option<MessageHandler>,
+
+    /// The record of the fragments last sent, to skip unchanged ones, if any.
+    diff: Option<FragmentDiff>,
+}
+
+impl std::fmt::Debug for WsFragmentHub {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WsFragmentHub")
+            .field("client_count", &self.client_count())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for WsFragmentHub {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl WsFragmentHub {
+    /// Create a new hub, buffering at most `capacity` broadcast messages for slow clients.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero, the call will panic.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+
+        Self {
+            sender,
+            clients: Default::default(),
+            message_handler: None,
+            diff: None,
+        }
+    }
+
+    /// Set the handler for the text messages sent by clients.
+    ///
+    /// With the htmx WebSockets extension, messages are sent by `ws-send` elements, as JSON
+    /// objects with the form values and a `HEADERS` entry.
+    pub fn with_message_handler(
+        mut self,
+        handler: impl Fn(&WsFragmentHub, WsClientId, &str) + Send + Sync + 'static,
+    ) -> Self {
+        self.message_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Skip the fragments whose HTML is unchanged since they were last pushed.
+    ///
+    /// Broadcast fragments are compared to the previous broadcasts, and fragments pushed to a
+    /// single client to the previous ones pushed to that client, which are forgotten when it
+    /// disconnects. Fragments pushed with a raw target are always sent.
+    pub fn with_fragment_diff(mut self, diff: FragmentDiff) -> Self {
+        self.diff = Some(diff);
+        self
+    }
+
+    /// Broadcast a fragment to all connected clients, using the fragment's swap strategy.
+    ///
+    /// Returns the number of clients the fragment was sent to, which is zero if it was skipped
+    /// as unchanged.
+    pub fn push(&self, fragment: impl Fragment) -> usize {
+        match self.render(BROADCAST_SCOPE, &fragment) {
+            Some(html) => self.broadcast(html),
+            None => 0,
+        }
+    }
+
+    /// Broadcast a fragment to all connected clients, with the specified swap strategy and
+    /// target.
+    ///
+    /// Returns the number of clients the fragment was sent to.
+    pub fn push_raw(
+        &self,
+        insert_strategy: InsertStrategy,
+        target: &str,
+        fragment: impl Display,
+    ) -> usize {
+        self.broadcast(oob_html(&insert_strategy, target, fragment))
+    }
+
+    /// Push a fragment to a single client, using the fragment's swap strategy.
+    ///
+    /// Returns whether the client is still connected.
+    pub fn push_to(&self, client_id: WsClientId, fragment: impl Fragment) -> bool {
+        let scope = client_id.to_string();
+
+        let sent = match self.render(&scope, &fragment) {
+            Some(html) => self.send_to(client_id, html),
+            None => self
+                .clients
+                .lock()
+                .expect("clients lock should not be poisoned")
+                .contains_key(&client_id),
+        };
+
+        // Do not keep records for clients that already disconnected.
+        if !sent && let Some(diff) = &self.diff {
+            diff.forget(&scope);
+        }
+
+        sent
+    }
+
+    /// Push a fragment to a single client, with the specified swap strategy and target.
+    ///
+    /// Returns whether the client is still connected.
+    pub fn push_raw_to(
+        &self,
+        client_id: WsClientId,
+        insert_strategy: InsertStrategy,
+        target: &str,
+        fragment: impl Display,
+    ) -> bool {
+        self.send_to(client_id, oob_html(&insert_strategy, target, fragment))
+    }
+
+    /// Render a fragment, unless it is unchanged in the scope of the fragment diff, if any.
+    fn render(&self, scope: &str, fragment: &impl Fragment) -> Option<String> {
+        match &self.diff {
+            Some(diff) => diff.render_if_changed(scope, fragment),
+            None => Some(render_fragment(fragment)),
+        }
+    }
+
+    /// Send some HTML to all connected clients.
+    fn broadcast(&self, html: String) -> usize {
+        // Sending only fails when there are no clients, which is not an error.
+        self.sender.send(html).unwrap_or_default()
+    }
+
+    /// Send some HTML to a single client.
+    fn send_to(&self, client_id: WsClientId, html: String) -> bool {
+        self.clients
+            .lock()
+            .expect("clients lock should not be poisoned")
+            .get(&client_id)
+            .is_some_and(|sender| sender.send(html).is_ok())
+    }
+
+    /// The number of connected clients.
+    pub fn client_count(&self) -> usize {
+        self.clients
+            .lock()
+            .expect("clients lock should not be poisoned")
+            .len()
+    }
+
+    /// Upgrade a request to a WebSocket connection to the hub.
+    ///
+    /// The client receives all the fragments broadcast or pushed to it from now on, until it
+    /// disconnects.
+    pub fn upgrade(&self, upgrade: WebSocketUpgrade) -> axum::response::Response {
+        let hub = self.clone();
+
+        upgrade.on_upgrade(move |socket| hub.serve(socket))
+    }
+
+    /// Serve a WebSocket connection.
+    async fn serve(self, mut socket: WebSocket) {
+        let mut client = self.connect();
+
+        tracing::debug!("WebSocket client `{}` connected.", client.id);
+
+        loop {
+            tokio::select! {
+                html = client.next_message() => {
+                    let Some(html) = html else { break };
+
+                    if socket.send(Message::Text(html.into())).await.is_err() {
+                        break;
+                    }
+                }
+                message = socket.recv() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        if let Some(handler) = &self.message_handler {
+                            handler(&self, client.id, text.as_str());
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+
+        tracing::debug!("WebSocket client `{}` disconnected.", client.id);
+    }
+
+    /// Register a new client.
+    fn connect(&self) -> WsClient {
+        let id = WsClientId(uuid::Uuid::new_v4());
+        let (sender, direct) = mpsc::unbounded_channel();
+
+        self.clients
+            .lock()
+            .expect("clients lock should not be poisoned")
+            .insert(id, sender);
+
+        WsClient {
+            id,
+            broadcast: self.sender.subscribe(),
+            direct,
+            clients: self.clients.clone(),
+            diff: self.diff.clone(),
+        }
+    }
+}
+
+/// A client registered in a hub, unregistered on drop.
+struct WsClient {
+    /// The identifier of the client.
+    id: WsClientId,
+
+    /// The receiver of the broadcast messages.
+    broadcast: broadcast::Receiver<String>,
+
+    /// The receiver of the messages to this client.
+    direct: mpsc::UnboundedReceiver<String>,
+
+    /// The clients of the hub.
+    clients: Arc<Mutex<HashMap<WsClientId, mpsc::UnboundedSender<String>>>>,
+
+    /// The record of the fragments last sent by the hub, if any.
+    diff: Option<FragmentDiff>,
+}
+
+impl WsClient {
+    /// Get the next message to send to the client.
+    ///
+    /// Returns `None` if the hub was dropped.
+    async fn next_message(&mut self) -> Option<String> {
+        loop {
+            tokio::select! {
+                html = self.direct.recv() => return html,
+                html = self.broadcast.recv() => match html {
+                    Ok(html) => return Some(html),
+                    Err(broadcast::error::RecvError::Lagged(count)) => {
+                        tracing::warn!(
+                            "WebSocket client `{}` lagged behind, {count} message(s) were dropped.",
+                            self.id
+                        );
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                },
+            }
+        }
+    }
+}
+
+impl Drop for WsClient {
+    fn drop(&mut self) {
+        if let Ok(mut clients) = self.clients.lock() {
+            clients.remove(&self.id);
+        }
+
+        if let Some(diff) = &self.diff {
+            diff.forget(&self.id.to_string());
+        }
+    }
+}
+
+/// Render a fragment as an out-of-band swap.
+fn oob_html(insert_strategy: &InsertStrategy, target: &str, fragment: impl Display) -> String {
+    crate::htmx::inject_oob_attribute(&fragment.to_string(), insert_strategy, target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::htmx::{HtmlId, Identity};
+
+    struct Counter(u32);
+
+    impl Display for Counter {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, r#"<span id="counter">{}</span>"#, self.0)
+        }
+    }
+
+    impl Identity for Counter {
+        fn id(&self) -> HtmlId {
+            HtmlId::from_static("counter").expect("valid ID")
+        }
+    }
+
+    impl Fragment for Counter {
+        fn insert_strategy(&self) -> InsertStrategy {
+            InsertStrategy::OuterHtml
+        }
+    }
+
+    #[tokio::test]
+    async fn test_push() {
+        let hub = WsFragmentHub::default();
+        let mut a = hub.connect();
+        let mut b = hub.connect();
+
+        assert_eq!(hub.client_count(), 2);
+        assert_eq!(hub.push(Counter(1)), 2);
+        assert_eq!(
+            a.next_message().await.unwrap(),
+            r#"<span id="counter" hx-swap-oob="true">1</span>"#
+        );
+        assert_eq!(
+            b.next_message().await.unwrap(),
+            r#"<span id="counter" hx-swap-oob="true">1</span>"#
+        );
+
+        assert!(hub.push_raw_to(b.id, InsertStrategy::BeforeEnd, "#log", "<li>b</li>"));
+        assert_eq!(
+            b.next_message().await.unwrap(),
+            r##"<li hx-swap-oob="beforeend:#log">b</li>"##
+        );
+
+        let a_id = a.id;
+        drop(a);
+
+        assert_eq!(hub.client_count(), 1);
+        assert!(!hub.push_to(a_id, Counter(2)));
+    }
+
+    #[tokio::test]
+    async fn test_push_with_fragment_diff() {
+        let hub = WsFragmentHub::default().with_fragment_diff(FragmentDiff::new());
+        let mut a = hub.connect();
+
+        assert_eq!(hub.push(Counter(1)), 1);
+        assert_eq!(hub.push(Counter(1)), 0);
+        assert_eq!(hub.push(Counter(2)), 1);
+        assert_eq!(
+            a.next_message().await.unwrap(),
+            r#"<span id="counter" hx-swap-oob="true">1</span>"#
+        );
+        assert_eq!(
+            a.next_message().await.unwrap(),
+            r#"<span id="counter" hx-swap-oob="true">2</span>"#
+        );
+
+        // Fragments pushed to a client are compared to the previous ones pushed to it.
+        assert!(hub.push_to(a.id, Counter(2)));
+        assert!(hub.push_to(a.id, Counter(2)));
+        assert_eq!(
+            a.next_message().await.unwrap(),
+            r#"<span id="counter" hx-swap-oob="true">2</span>"#
+        );
+
+        let a_id = a.id;
+        drop(a);
+
+        assert!(!hub.push_to(a_id, Counter(2)));
+    }
+}
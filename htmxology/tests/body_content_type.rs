@@ -0,0 +1,313 @@
+//! Integration tests for body content-type handling in the Route derive macro.
+
+#![cfg(feature = "derive")]
+
+use axum::extract::FromRequest;
+use htmxology::Route;
+use http::{Request, StatusCode};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct CommentForm {
+    author: String,
+    text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum CommentRoute {
+    #[route("comments", method = "POST")]
+    Create {
+        #[body("application/x-www-form-urlencoded")]
+        form: CommentForm,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct AttachmentForm {
+    title: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AttachmentRoute {
+    #[route("attachments", method = "POST")]
+    Upload {
+        #[body("multipart/form-data")]
+        upload: htmxology::MultipartForm<AttachmentForm>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+struct NewItem {
+    name: String,
+    quantity: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum ItemRoute {
+    #[route("items")]
+    List,
+
+    #[route("items", method = "POST")]
+    Create(#[body("application/json")] NewItem),
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum NegotiatedRoute {
+    #[route("items", method = "POST")]
+    Create {
+        #[body("application/x-www-form-urlencoded")]
+        #[body("application/json")]
+        item: NewItem,
+    },
+
+    #[route("items/{id}", method = "GET|POST")]
+    Edit {
+        id: u32,
+        #[body(any("application/json", "application/x-www-form-urlencoded"))]
+        item: Option<NewItem>,
+    },
+}
+
+fn make_request(content_type: &str, body: &'static [u8]) -> Request<axum::body::Body> {
+    Request::builder()
+        .uri("/comments")
+        .method("POST")
+        .header(http::header::CONTENT_TYPE, content_type)
+        .body(axum::body::Body::from(body))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_content_type_with_charset_and_case() {
+    for content_type in [
+        "application/x-www-form-urlencoded",
+        "application/x-www-form-urlencoded; charset=UTF-8",
+        "Application/X-WWW-Form-URLEncoded;charset=utf-8",
+    ] {
+        let request = make_request(content_type, b"author=Alice&text=Hello+world");
+        let route = CommentRoute::from_request(request, &()).await.unwrap();
+
+        assert_eq!(
+            route,
+            CommentRoute::Create {
+                form: CommentForm {
+                    author: "Alice".to_string(),
+                    text: "Hello world".to_string(),
+                },
+            },
+            "failed for {content_type:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_content_type_non_utf8_charset() {
+    let request = make_request(
+        "application/x-www-form-urlencoded; charset=windows-1252",
+        b"author=Ren%E9e&text=%80+5",
+    );
+    let route = CommentRoute::from_request(request, &()).await.unwrap();
+
+    assert_eq!(
+        route,
+        CommentRoute::Create {
+            form: CommentForm {
+                author: "Renée".to_string(),
+                text: "€ 5".to_string(),
+            },
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_content_type_mismatch() {
+    let request = make_request("text/plain", b"author=Alice&text=Hello");
+    let err = CommentRoute::from_request(request, &()).await.unwrap_err();
+
+    assert_eq!(err.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn test_multipart_body() {
+    let request = Request::builder()
+        .uri("/attachments")
+        .method("POST")
+        .header(
+            http::header::CONTENT_TYPE,
+            "multipart/form-data; boundary=----boundary",
+        )
+        .body(axum::body::Body::from(
+            "------boundary\r\n\
+            Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+            Report\r\n------boundary\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"report.csv\"\r\n\
+            Content-Type: text/csv\r\n\r\n\
+            a,b\r\n1,2\r\n------boundary--\r\n",
+        ))
+        .unwrap();
+    let AttachmentRoute::Upload { upload } =
+        AttachmentRoute::from_request(request, &()).await.unwrap();
+
+    assert_eq!(
+        upload.fields,
+        AttachmentForm {
+            title: "Report".to_string(),
+        }
+    );
+    assert_eq!(
+        upload.files,
+        [htmxology::UploadedFile {
+            field_name: "file".to_string(),
+            file_name: "report.csv".to_string(),
+            content_type: Some("text/csv".to_string()),
+            bytes: "a,b\r\n1,2".into(),
+        }]
+    );
+}
+
+#[tokio::test]
+async fn test_multipart_body_content_type_mismatch() {
+    let request = Request::builder()
+        .uri("/attachments")
+        .method("POST")
+        .header(
+            http::header::CONTENT_TYPE,
+            "application/x-www-form-urlencoded",
+        )
+        .body(axum::body::Body::from("title=Report"))
+        .unwrap();
+    let err = AttachmentRoute::from_request(request, &())
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn test_json_body() {
+    let item = NewItem {
+        name: "Apple".to_string(),
+        quantity: 3,
+    };
+    let route = ItemRoute::Create(item.clone());
+    let json = route.json_body().unwrap();
+    assert_eq!(json, r#"{"name":"Apple","quantity":3}"#);
+    assert_eq!(ItemRoute::List.json_body(), None);
+
+    let request = Request::builder()
+        .uri("/items")
+        .method("POST")
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(axum::body::Body::from(json))
+        .unwrap();
+
+    assert_eq!(ItemRoute::from_request(request, &()).await.unwrap(), route);
+}
+
+#[tokio::test]
+async fn test_json_body_rejections() {
+    for (content_type, body, status) in [
+        (
+            "text/plain",
+            r#"{"name":"Apple","quantity":3}"#,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        ),
+        (
+            "application/json",
+            r#"{"name":"Apple""#,
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            "application/json",
+            r#"{"name":"Apple"}"#,
+            StatusCode::UNPROCESSABLE_ENTITY,
+        ),
+    ] {
+        let request = Request::builder()
+            .uri("/items")
+            .method("POST")
+            .header(http::header::CONTENT_TYPE, content_type)
+            .body(axum::body::Body::from(body))
+            .unwrap();
+        let err = ItemRoute::from_request(request, &()).await.unwrap_err();
+
+        assert_eq!(err.status(), status, "failed for {body:?}");
+    }
+}
+
+#[tokio::test]
+async fn test_negotiated_body() {
+    let item = NewItem {
+        name: "Apple".to_string(),
+        quantity: 3,
+    };
+
+    for (uri, content_type, body) in [
+        (
+            "/items",
+            "application/json",
+            r#"{"name":"Apple","quantity":3}"#,
+        ),
+        (
+            "/items",
+            "application/x-www-form-urlencoded; charset=utf-8",
+            "name=Apple&quantity=3",
+        ),
+        (
+            "/items/1",
+            "Application/JSON",
+            r#"{"name":"Apple","quantity":3}"#,
+        ),
+        (
+            "/items/1",
+            "application/x-www-form-urlencoded",
+            "name=Apple&quantity=3",
+        ),
+    ] {
+        let request = Request::builder()
+            .uri(uri)
+            .method("POST")
+            .header(http::header::CONTENT_TYPE, content_type)
+            .body(axum::body::Body::from(body))
+            .unwrap();
+        let route = NegotiatedRoute::from_request(request, &()).await.unwrap();
+
+        let expected = match uri {
+            "/items" => NegotiatedRoute::Create { item: item.clone() },
+            _ => NegotiatedRoute::Edit {
+                id: 1,
+                item: Some(item.clone()),
+            },
+        };
+
+        assert_eq!(route, expected, "failed for {content_type:?}");
+    }
+
+    assert_eq!(
+        NegotiatedRoute::Create { item: item.clone() }.json_body(),
+        Some(r#"{"name":"Apple","quantity":3}"#.to_string())
+    );
+    assert_eq!(
+        NegotiatedRoute::Edit { id: 1, item: None }.json_body(),
+        None
+    );
+
+    let request = Request::builder()
+        .uri("/items")
+        .method("POST")
+        .header(http::header::CONTENT_TYPE, "text/plain")
+        .body(axum::body::Body::from("Apple"))
+        .unwrap();
+    let err = NegotiatedRoute::from_request(request, &())
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let body = axum::body::to_bytes(err.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(
+        body,
+        "Expected request with `Content-Type: application/x-www-form-urlencoded` or `application/json`"
+    );
+}
@@ -0,0 +1,88 @@
+//! Integration tests for the case-insensitive matching option of the Route derive macro.
+
+#![cfg(feature = "derive")]
+
+use std::str::FromStr;
+
+use axum::extract::FromRequest;
+use htmxology::Route;
+use http::Request;
+
+#[derive(Debug, Clone, PartialEq, Route)]
+#[route_opts(case_insensitive)]
+enum SiteRoute {
+    #[route("")]
+    Home,
+
+    #[route("About-Us")]
+    About,
+
+    #[route("promo/{code}")]
+    Promo { code: String },
+
+    #[route("shop/")]
+    Shop(#[subroute] ShopRoute),
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum ShopRoute {
+    #[route("cart")]
+    Cart,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+#[route_opts(case_insensitive, no_from_str)]
+enum CompactRoute {
+    #[route("Pricing")]
+    Pricing,
+}
+
+#[test]
+fn test_parse_ignores_literal_case() {
+    assert_eq!(SiteRoute::from_str("/").unwrap(), SiteRoute::Home);
+    assert_eq!(SiteRoute::from_str("/about-us").unwrap(), SiteRoute::About);
+    assert_eq!(SiteRoute::from_str("/ABOUT-US").unwrap(), SiteRoute::About);
+    assert_eq!(
+        SiteRoute::from_str("/PROMO/Summer24").unwrap(),
+        SiteRoute::Promo {
+            code: "Summer24".to_string()
+        }
+    );
+    assert_eq!(
+        SiteRoute::from_str("/Shop/cart").unwrap(),
+        SiteRoute::Shop(ShopRoute::Cart)
+    );
+    assert_eq!(
+        CompactRoute::from_str("/PRICING").unwrap(),
+        CompactRoute::Pricing
+    );
+}
+
+#[test]
+fn test_subroutes_keep_their_own_matching() {
+    assert!(SiteRoute::from_str("/shop/CART").is_err());
+}
+
+#[test]
+fn test_display_keeps_canonical_casing() {
+    let route = SiteRoute::from_str("/about-us").unwrap();
+
+    assert_eq!(route.to_string(), "/About-Us");
+    assert_eq!(
+        SiteRoute::from_str("/Promo/Summer24").unwrap().to_string(),
+        "/promo/Summer24"
+    );
+}
+
+#[tokio::test]
+async fn test_from_request() {
+    let request = Request::builder()
+        .uri("/About-us")
+        .body(axum::body::Body::empty())
+        .unwrap();
+
+    assert_eq!(
+        SiteRoute::from_request(request, &()).await.unwrap(),
+        SiteRoute::About
+    );
+}
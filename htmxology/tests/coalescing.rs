@@ -0,0 +1,93 @@
+//! Integration tests for request coalescing.
+
+#![cfg(feature = "derive")]
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+use std::time::Duration;
+
+use axum::response::IntoResponse;
+use htmxology::{Coalescing, CoalescingControllerExt, Controller, Route, ServerInfo};
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum ReportRoute {
+    #[route("summary")]
+    #[coalesce]
+    Summary,
+
+    #[route("live")]
+    Live,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    #[route("")]
+    Home,
+
+    #[route("reports/")]
+    Reports(#[subroute] ReportRoute),
+}
+
+#[derive(Clone, Default)]
+struct AppController {
+    handled: Arc<AtomicUsize>,
+}
+
+impl Controller for AppController {
+    type Route = AppRoute;
+    type Args = ();
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        _htmx: htmxology::htmx::Request,
+        _parts: http::request::Parts,
+        _server_info: &ServerInfo,
+        _args: Self::Args,
+    ) -> Self::Response {
+        self.handled.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        Ok(route.to_string().into_response())
+    }
+}
+
+#[test]
+fn test_coalesce_requests() {
+    assert!(!AppRoute::Home.coalesce_requests());
+    assert!(AppRoute::Reports(ReportRoute::Summary).coalesce_requests());
+    assert!(!AppRoute::Reports(ReportRoute::Live).coalesce_requests());
+}
+
+#[tokio::test]
+async fn test_coalescing_controller() {
+    let inner = AppController::default();
+    let controller = inner.clone().with_request_coalescing(Coalescing::default());
+    let server_info = ServerInfo {
+        base_url: "http://localhost".parse().unwrap(),
+    };
+    let handle = |route| {
+        let parts = http::Request::builder().body(()).unwrap().into_parts().0;
+
+        controller.handle_request(
+            route,
+            htmxology::htmx::Request::Classic,
+            parts,
+            &server_info,
+            (),
+        )
+    };
+
+    let responses = tokio::join!(
+        handle(AppRoute::Reports(ReportRoute::Summary)),
+        handle(AppRoute::Reports(ReportRoute::Summary)),
+        handle(AppRoute::Reports(ReportRoute::Live)),
+        handle(AppRoute::Reports(ReportRoute::Live)),
+    );
+
+    assert!(responses.0.is_ok() && responses.1.is_ok());
+    assert_eq!(inner.handled.load(Ordering::SeqCst), 3);
+}
@@ -0,0 +1,114 @@
+//! Integration tests for controller routers used as standalone services.
+
+#![cfg(feature = "derive")]
+
+use axum::response::IntoResponse;
+use htmxology::{Controller, ControllerRouter, Route, ServerInfo};
+use tower_service::Service;
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    #[route("")]
+    Home,
+}
+
+#[derive(Clone)]
+struct AppController;
+
+impl Controller for AppController {
+    type Route = AppRoute;
+    type Args = ();
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    async fn handle_request(
+        &self,
+        _route: Self::Route,
+        _htmx: htmxology::htmx::Request,
+        _parts: http::request::Parts,
+        server_info: &ServerInfo,
+        _args: Self::Args,
+    ) -> Self::Response {
+        Ok(format!("Hello from {}", server_info.base_url).into_response())
+    }
+}
+
+#[tokio::test]
+async fn test_into_service() {
+    let mut service = ControllerRouter::new(AppController, |_| async {}).into_service(ServerInfo {
+        base_url: http::Uri::from_static("https://example.com"),
+    });
+
+    std::future::poll_fn(|cx| Service::<http::Request<String>>::poll_ready(&mut service, cx))
+        .await
+        .unwrap();
+
+    let request = http::Request::get("/").body(String::new()).unwrap();
+    let response = service.call(request).await.unwrap();
+
+    assert_eq!(response.status(), http::StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(body, "Hello from https://example.com/");
+
+    let request = http::Request::get("/missing")
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let response = service.call(request).await.unwrap();
+
+    assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "lambda")]
+#[tokio::test]
+async fn test_lambda_request() {
+    let service = ControllerRouter::new(AppController, |_| async {}).into_service(ServerInfo {
+        base_url: http::Uri::from_static("https://example.com"),
+    });
+
+    // An API Gateway HTTP API event, as the runtime would receive it.
+    let event: lambda_http::request::LambdaRequest = serde_json::from_str(
+        r#"{
+            "version": "2.0",
+            "routeKey": "$default",
+            "rawPath": "/",
+            "rawQueryString": "",
+            "headers": { "host": "example.com" },
+            "requestContext": {
+                "accountId": "123456789012",
+                "apiId": "api-id",
+                "domainName": "example.com",
+                "domainPrefix": "example",
+                "http": {
+                    "method": "GET",
+                    "path": "/",
+                    "protocol": "HTTP/1.1",
+                    "sourceIp": "192.0.2.1",
+                    "userAgent": "agent"
+                },
+                "requestId": "id",
+                "routeKey": "$default",
+                "stage": "$default",
+                "time": "12/Mar/2020:19:03:58 +0000",
+                "timeEpoch": 1583348638390
+            },
+            "isBase64Encoded": false
+        }"#,
+    )
+    .unwrap();
+
+    // `run_on_lambda` serves the events through the adapter of `lambda_http::run`.
+    let mut adapter = lambda_http::Adapter::from(service);
+    let response = adapter
+        .call(lambda_http::LambdaEvent::new(
+            event,
+            lambda_http::Context::default(),
+        ))
+        .await
+        .unwrap();
+    let response = serde_json::to_value(response).unwrap();
+
+    assert_eq!(response["statusCode"], 200);
+    assert_eq!(response["body"], "Hello from https://example.com/");
+}
@@ -0,0 +1,90 @@
+//! Integration tests for routes using custom HTTP methods.
+
+#![cfg(feature = "derive")]
+
+use axum::extract::FromRequest;
+use htmxology::{METHOD_OVERRIDE_HEADER, Route};
+use http::{Request, StatusCode};
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum CalendarRoute {
+    #[route("calendar/{id}")]
+    Show { id: u32 },
+
+    #[route("calendar/{id}", method = "POST")]
+    Update { id: u32 },
+
+    #[route("calendar/{id}", method = "PROPFIND")]
+    Properties { id: u32 },
+
+    #[route("calendar/{id}", method = "REPORT")]
+    Report { id: u32 },
+}
+
+fn make_request(method: &str, uri: &str) -> Request<axum::body::Body> {
+    Request::builder()
+        .uri(uri)
+        .method(method)
+        .body(axum::body::Body::empty())
+        .unwrap()
+}
+
+#[test]
+fn test_custom_method() {
+    assert_eq!(
+        CalendarRoute::Properties { id: 1 }.method().as_str(),
+        "PROPFIND"
+    );
+    assert_eq!(CalendarRoute::Report { id: 1 }.method().as_str(), "REPORT");
+    assert_eq!(CalendarRoute::Update { id: 1 }.method(), http::Method::POST);
+}
+
+#[test]
+fn test_custom_method_htmx_attribute() {
+    assert_eq!(
+        CalendarRoute::Report { id: 7 }.as_htmx_attribute(),
+        r#"hx-post="/calendar/7" hx-headers='{"X-HTTP-Method-Override": "REPORT"}'"#
+    );
+    assert_eq!(
+        CalendarRoute::Update { id: 7 }.as_htmx_attribute(),
+        r#"hx-post="/calendar/7""#
+    );
+}
+
+#[tokio::test]
+async fn test_parse_custom_method() {
+    let route = CalendarRoute::from_request(make_request("PROPFIND", "/calendar/3"), &())
+        .await
+        .unwrap();
+    assert_eq!(route, CalendarRoute::Properties { id: 3 });
+
+    let route = CalendarRoute::from_request(make_request("REPORT", "/calendar/4"), &())
+        .await
+        .unwrap();
+    assert_eq!(route, CalendarRoute::Report { id: 4 });
+}
+
+#[tokio::test]
+async fn test_parse_method_override() {
+    let mut request = make_request("POST", "/calendar/5");
+    request
+        .headers_mut()
+        .insert(METHOD_OVERRIDE_HEADER, "REPORT".parse().unwrap());
+
+    let route = CalendarRoute::from_request(request, &()).await.unwrap();
+    assert_eq!(route, CalendarRoute::Report { id: 5 });
+
+    // Without the override header, a POST request reaches the POST route.
+    let route = CalendarRoute::from_request(make_request("POST", "/calendar/5"), &())
+        .await
+        .unwrap();
+    assert_eq!(route, CalendarRoute::Update { id: 5 });
+}
+
+#[tokio::test]
+async fn test_parse_unknown_method() {
+    let err = CalendarRoute::from_request(make_request("MKCOL", "/calendar/6"), &())
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
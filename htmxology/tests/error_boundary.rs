@@ -0,0 +1,146 @@
+//! Integration tests for subcontroller error boundaries.
+
+#![cfg(feature = "derive")]
+
+use axum::response::IntoResponse;
+use htmxology::{Controller, Route, RoutingController, ServerInfo, htmx};
+
+#[derive(Debug, Clone, PartialEq, Route)]
+pub enum SectionRoute {
+    #[route("ok")]
+    Ok,
+
+    #[route("fail")]
+    Fail,
+}
+
+/// A section, generic to be mounted twice.
+#[derive(Clone)]
+pub struct SectionController<const N: u8>;
+
+impl<const N: u8> Controller for SectionController<N> {
+    type Route = SectionRoute;
+    type Args = ();
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        _htmx: htmx::Request,
+        _parts: http::request::Parts,
+        _server_info: &ServerInfo,
+        _args: Self::Args,
+    ) -> Self::Response {
+        match route {
+            SectionRoute::Ok => Ok("section".into_response()),
+            SectionRoute::Fail => Err(http::StatusCode::INTERNAL_SERVER_ERROR.into_response()),
+        }
+    }
+}
+
+#[derive(Clone, RoutingController)]
+#[controller(AppRoute)]
+#[subcontroller(
+    SectionController<0>,
+    route = Guarded,
+    path = "guarded/",
+    convert_with = "Self::section",
+    error_boundary = "Self::render_section_error"
+)]
+#[subcontroller(
+    SectionController<1>,
+    route = Unguarded,
+    path = "unguarded/",
+    convert_with = "Self::section"
+)]
+struct AppController;
+
+impl AppController {
+    fn section<const N: u8>(&self) -> SectionController<N> {
+        SectionController
+    }
+
+    #[expect(clippy::result_large_err)]
+    fn render_section_error(
+        &self,
+        htmx: &htmx::Request,
+        _parts: &http::request::Parts,
+        _server_info: &ServerInfo,
+        err: axum::response::Response,
+    ) -> Result<axum::response::Response, axum::response::Response> {
+        let target = match htmx {
+            htmx::Request::Htmx {
+                target: Some(target),
+                ..
+            } => target.to_str().unwrap_or_default(),
+            _ => "page",
+        };
+
+        Ok(format!("error {} in #{target}", err.status().as_u16()).into_response())
+    }
+}
+
+async fn handle(route: &str, htmx: htmx::Request) -> axum::response::Response {
+    let (parts, _) = http::Request::builder()
+        .uri(route)
+        .body(())
+        .unwrap()
+        .into_parts();
+    let server_info = ServerInfo {
+        base_url: http::Uri::from_static("http://localhost"),
+    };
+
+    match AppController
+        .handle_request(route.parse().unwrap(), htmx, parts, &server_info, ())
+        .await
+    {
+        Ok(response) | Err(response) => response,
+    }
+}
+
+async fn body(response: axum::response::Response) -> String {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_error_boundary_renders_errors() {
+    let response = handle("/guarded/fail", htmx::Request::Classic).await;
+
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(body(response).await, "error 500 in #page");
+
+    let response = handle(
+        "/guarded/fail",
+        htmx::Request::Htmx {
+            boosted: false,
+            current_url: "http://localhost/".to_owned(),
+            history_restore_request: false,
+            prompt: String::new(),
+            target: Some(http::HeaderValue::from_static("sidebar")),
+            trigger_name: None,
+            trigger: None,
+        },
+    )
+    .await;
+
+    assert_eq!(body(response).await, "error 500 in #sidebar");
+}
+
+#[tokio::test]
+async fn test_error_boundary_passes_successes() {
+    let response = handle("/guarded/ok", htmx::Request::Classic).await;
+
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(body(response).await, "section");
+}
+
+#[tokio::test]
+async fn test_without_error_boundary() {
+    let response = handle("/unguarded/fail", htmx::Request::Classic).await;
+
+    assert_eq!(response.status(), http::StatusCode::INTERNAL_SERVER_ERROR);
+}
@@ -0,0 +1,164 @@
+//! Integration tests for the error renderers of controller routers.
+
+#![cfg(feature = "derive")]
+
+use axum::response::IntoResponse;
+use htmxology::{Controller, ControllerRouter, ErrorRenderer, Route, ServerInfo, htmx};
+use tower_service::Service;
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    #[route("")]
+    Home,
+
+    #[route("busy")]
+    Busy,
+
+    #[route("items/{id}")]
+    Item { id: u32 },
+}
+
+#[derive(Clone)]
+struct AppController;
+
+impl Controller for AppController {
+    type Route = AppRoute;
+    type Args = ();
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        _htmx: htmx::Request,
+        _parts: http::request::Parts,
+        _server_info: &ServerInfo,
+        _args: Self::Args,
+    ) -> Self::Response {
+        match route {
+            AppRoute::Home => Ok("home".into_response()),
+            AppRoute::Busy => Err((
+                http::StatusCode::SERVICE_UNAVAILABLE,
+                [(http::header::RETRY_AFTER, "30")],
+                "busy",
+            )
+                .into_response()),
+            AppRoute::Item { .. } => Err(http::StatusCode::NOT_FOUND.into_response()),
+        }
+    }
+}
+
+struct Errors;
+
+impl ErrorRenderer for Errors {
+    async fn render_page(
+        &self,
+        status: http::StatusCode,
+        parts: &http::request::Parts,
+    ) -> axum::response::Response {
+        axum::response::Html(format!(
+            "<html><body>Error {} on {}</body></html>",
+            status.as_u16(),
+            parts.uri.path()
+        ))
+        .into_response()
+    }
+
+    async fn render_fragment(
+        &self,
+        status: http::StatusCode,
+        _htmx: &htmx::Request,
+        _parts: &http::request::Parts,
+    ) -> axum::response::Response {
+        axum::response::Html(format!("<p>Error {}</p>", status.as_u16())).into_response()
+    }
+}
+
+async fn get(
+    router: ControllerRouter,
+    uri: &str,
+    htmx: bool,
+) -> (http::StatusCode, http::HeaderMap, String) {
+    let mut service = router.into_service(ServerInfo {
+        base_url: http::Uri::from_static("http://localhost"),
+    });
+
+    let mut request = http::Request::get(uri);
+
+    if htmx {
+        request = request.header("HX-Request", "true");
+    }
+
+    let response = service
+        .call(request.body(axum::body::Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let (parts, body) = response.into_parts();
+    let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+
+    (
+        parts.status,
+        parts.headers,
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+fn router() -> ControllerRouter {
+    ControllerRouter::new(AppController, |_| async {}).with_error_renderer(Errors)
+}
+
+#[tokio::test]
+async fn test_error_renderer_renders_errors() {
+    let (status, headers, body) = get(router(), "/busy", false).await;
+
+    assert_eq!(status, http::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(headers[http::header::RETRY_AFTER], "30");
+    assert_eq!(
+        headers[http::header::CONTENT_TYPE],
+        "text/html; charset=utf-8"
+    );
+    assert_eq!(body, "<html><body>Error 503 on /busy</body></html>");
+
+    let (status, _, body) = get(router(), "/items/1", true).await;
+
+    assert_eq!(status, http::StatusCode::NOT_FOUND);
+    assert_eq!(body, "<p>Error 404</p>");
+}
+
+#[tokio::test]
+async fn test_error_renderer_renders_rejections() {
+    let (status, _, body) = get(router(), "/missing", false).await;
+
+    assert_eq!(status, http::StatusCode::NOT_FOUND);
+    assert_eq!(body, "<html><body>Error 404 on /missing</body></html>");
+
+    let (status, _, body) = get(router(), "/items/abc", true).await;
+
+    assert_eq!(status, http::StatusCode::BAD_REQUEST);
+    assert_eq!(body, "<p>Error 400</p>");
+}
+
+#[tokio::test]
+async fn test_error_renderer_skips_successes() {
+    let (status, _, body) = get(router(), "/", false).await;
+
+    assert_eq!(status, http::StatusCode::OK);
+    assert_eq!(body, "home");
+}
+
+#[tokio::test]
+async fn test_fallback_takes_precedence() {
+    let fallback = |_htmx, _parts| async { "fallback".into_response() };
+
+    let before = ControllerRouter::new(AppController, |_| async {})
+        .with_fallback(fallback)
+        .with_error_renderer(Errors);
+    let after = router().with_fallback(fallback);
+
+    for router in [before, after] {
+        let (_, _, body) = get(router.clone(), "/missing", false).await;
+        assert_eq!(body, "fallback");
+
+        let (_, _, body) = get(router, "/items/1", false).await;
+        assert_eq!(body, "<html><body>Error 404 on /items/1</body></html>");
+    }
+}
@@ -0,0 +1,128 @@
+//! Test route fields resolved with axum extractors.
+
+#![cfg(feature = "derive")]
+
+use axum::extract::FromRequest;
+use axum::response::IntoResponse;
+use htmxology::{ParseError, Route};
+use serde::Deserialize;
+
+/// A user authenticated by an `X-User` header.
+#[derive(Debug, Clone, PartialEq)]
+struct User(String);
+
+impl<S: Send + Sync> axum::extract::FromRequestParts<S> for User {
+    type Rejection = axum::response::Response;
+
+    async fn from_request_parts(
+        parts: &mut http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        parts
+            .headers
+            .get("x-user")
+            .and_then(|value| value.to_str().ok())
+            .map(|name| Self(name.to_owned()))
+            .ok_or_else(|| http::StatusCode::UNAUTHORIZED.into_response())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct SettingsForm {
+    theme: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AccountRoute {
+    #[route("")]
+    #[builder]
+    Dashboard {
+        #[extract]
+        user: User,
+    },
+
+    #[route("settings", method = "GET|POST")]
+    Settings(#[extract] User, #[body] Option<SettingsForm>),
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    #[route("")]
+    Home,
+
+    #[route("account/")]
+    Account(#[subroute] AccountRoute),
+}
+
+async fn parse(request: http::request::Builder, body: &str) -> Result<AppRoute, http::StatusCode> {
+    let request = request
+        .header(
+            http::header::CONTENT_TYPE,
+            "application/x-www-form-urlencoded",
+        )
+        .body(axum::body::Body::from(body.to_owned()))
+        .unwrap();
+
+    AppRoute::from_request(request, &())
+        .await
+        .map_err(|response| response.status())
+}
+
+#[tokio::test]
+async fn test_from_request() {
+    assert_eq!(
+        parse(
+            http::Request::get("/account/").header("x-user", "alice"),
+            ""
+        )
+        .await,
+        Ok(AppRoute::Account(AccountRoute::Dashboard {
+            user: User("alice".to_owned())
+        }))
+    );
+    assert_eq!(
+        parse(
+            http::Request::post("/account/settings").header("x-user", "bob"),
+            "theme=dark"
+        )
+        .await,
+        Ok(AppRoute::Account(AccountRoute::Settings(
+            User("bob".to_owned()),
+            Some(SettingsForm {
+                theme: "dark".to_owned()
+            })
+        )))
+    );
+
+    // The rejections of the extractors are returned.
+    assert_eq!(
+        parse(http::Request::get("/account/settings"), "").await,
+        Err(http::StatusCode::UNAUTHORIZED)
+    );
+
+    assert_eq!(parse(http::Request::get("/"), "").await, Ok(AppRoute::Home));
+}
+
+#[test]
+fn test_display() {
+    let route = AppRoute::Account(AccountRoute::dashboard(User("alice".to_owned())).build());
+
+    assert_eq!(route.to_string(), "/account/");
+    assert_eq!(
+        AccountRoute::Settings(User("alice".to_owned()), None).to_string(),
+        "/settings"
+    );
+}
+
+#[test]
+fn test_from_uri() {
+    assert!(matches!(
+        "/account/settings".parse::<AppRoute>(),
+        Err(ParseError::ExtractionRequired { .. })
+    ));
+    assert!(matches!(
+        AppRoute::from_uri(&http::Uri::from_static("/account/"), &http::Method::GET),
+        Err(ParseError::ExtractionRequired { .. })
+    ));
+    assert_eq!("/".parse::<AppRoute>(), Ok(AppRoute::Home));
+}
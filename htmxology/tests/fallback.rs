@@ -0,0 +1,114 @@
+//! Integration tests for the fallback handler of controller routers.
+
+#![cfg(feature = "derive")]
+
+use axum::response::IntoResponse;
+use htmxology::{Controller, ControllerRouter, Route, ServerInfo, htmx};
+use tower_service::Service;
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    #[route("")]
+    Home,
+
+    #[route("items/{id}")]
+    Item { id: u32 },
+}
+
+#[derive(Clone)]
+struct AppController;
+
+impl AppController {
+    async fn render_not_found(
+        &self,
+        htmx: &htmx::Request,
+        parts: &http::request::Parts,
+    ) -> axum::response::Response {
+        let kind = match htmx {
+            htmx::Request::Htmx { .. } => "fragment",
+            htmx::Request::Classic => "page",
+        };
+
+        (
+            http::StatusCode::NOT_FOUND,
+            axum::response::Html(format!("<p>No {kind} at {}</p>", parts.uri.path())),
+        )
+            .into_response()
+    }
+}
+
+impl Controller for AppController {
+    type Route = AppRoute;
+    type Args = ();
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        _htmx: htmx::Request,
+        _parts: http::request::Parts,
+        _server_info: &ServerInfo,
+        _args: Self::Args,
+    ) -> Self::Response {
+        match route {
+            AppRoute::Home => Ok("home".into_response()),
+            AppRoute::Item { .. } => Err(http::StatusCode::NOT_FOUND.into_response()),
+        }
+    }
+}
+
+async fn get(uri: &str, headers: &[(&str, &str)]) -> (http::StatusCode, String) {
+    let router = ControllerRouter::new(AppController, |_| async {}).with_fallback(
+        |htmx, parts| async move { AppController.render_not_found(&htmx, &parts).await },
+    );
+    let mut service = router.into_service(ServerInfo {
+        base_url: http::Uri::from_static("http://localhost"),
+    });
+
+    let mut request = http::Request::get(uri);
+
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+
+    let response = service
+        .call(request.body(axum::body::Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_fallback_renders_unmatched_requests() {
+    assert_eq!(
+        get("/missing", &[]).await,
+        (
+            http::StatusCode::NOT_FOUND,
+            "<p>No page at /missing</p>".to_owned()
+        )
+    );
+    assert_eq!(
+        get("/items/1/edit", &[("HX-Request", "true")]).await,
+        (
+            http::StatusCode::NOT_FOUND,
+            "<p>No fragment at /items/1/edit</p>".to_owned()
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_fallback_skips_controller_responses() {
+    assert_eq!(
+        get("/", &[]).await,
+        (http::StatusCode::OK, "home".to_owned())
+    );
+    assert_eq!(
+        get("/items/1", &[]).await,
+        (http::StatusCode::NOT_FOUND, String::new())
+    );
+}
@@ -0,0 +1,128 @@
+//! Integration tests for `GET|POST` form routes.
+
+#![cfg(feature = "derive")]
+
+use std::str::FromStr;
+
+use axum::extract::FromRequest;
+use htmxology::{HtmlForm, Route, RouteEntry};
+use http::{Method, Request};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, HtmlForm)]
+#[html_form(route = AppRoute, submit = "Save")]
+pub struct SettingsForm {
+    theme: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RenameForm {
+    name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+pub enum AppRoute {
+    #[route("settings", method = "GET|POST")]
+    Settings {
+        #[body]
+        form: Option<SettingsForm>,
+    },
+
+    #[route("items/{id}/rename", method = "GET|POST")]
+    RenameItem(u32, #[body] Option<RenameForm>),
+}
+
+fn make_request(method: &str, uri: &str, body: &'static str) -> Request<axum::body::Body> {
+    Request::builder()
+        .uri(uri)
+        .method(method)
+        .header(
+            http::header::CONTENT_TYPE,
+            "application/x-www-form-urlencoded",
+        )
+        .body(axum::body::Body::from(body))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_from_request() {
+    let route = AppRoute::from_request(make_request("GET", "/settings", ""), &())
+        .await
+        .unwrap();
+
+    assert_eq!(route, AppRoute::Settings { form: None });
+    assert_eq!(route.method(), Method::GET);
+
+    let route = AppRoute::from_request(make_request("POST", "/settings", "theme=dark"), &())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        route,
+        AppRoute::Settings {
+            form: Some(SettingsForm {
+                theme: "dark".to_string(),
+            }),
+        }
+    );
+    assert_eq!(route.method(), Method::POST);
+
+    let route = AppRoute::from_request(make_request("POST", "/items/7/rename", "name=Foo"), &())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        route,
+        AppRoute::RenameItem(
+            7,
+            Some(RenameForm {
+                name: "Foo".to_string(),
+            })
+        )
+    );
+}
+
+#[test]
+fn test_from_str_and_from_uri() {
+    assert_eq!(
+        AppRoute::from_str("/items/3/rename").unwrap(),
+        AppRoute::RenameItem(3, None)
+    );
+    assert_eq!(
+        AppRoute::from_uri(&"/settings".parse().unwrap(), &Method::GET).unwrap(),
+        AppRoute::Settings { form: None }
+    );
+    assert!(matches!(
+        AppRoute::from_uri(&"/settings".parse().unwrap(), &Method::POST),
+        Err(htmxology::ParseError::BodyRequired { .. })
+    ));
+}
+
+#[test]
+fn test_route_table() {
+    assert_eq!(
+        AppRoute::route_table(),
+        vec![
+            RouteEntry::new("Settings", "GET", "/settings"),
+            RouteEntry::new("Settings", "POST", "/settings"),
+            RouteEntry::new("RenameItem", "GET", "/items/{id}/rename"),
+            RouteEntry::new("RenameItem", "POST", "/items/{id}/rename"),
+        ]
+    );
+}
+
+#[test]
+fn test_html_form_action() {
+    let form = SettingsForm {
+        theme: "light".to_string(),
+    };
+
+    assert_eq!(
+        form.to_string(),
+        r#"<form hx-post="/settings"><input type="text" name="theme" value="light"><button type="submit">Save</button></form>"#
+    );
+    assert_eq!(
+        AppRoute::Settings { form: None }.as_htmx_attribute(),
+        r#"hx-get="/settings""#
+    );
+}
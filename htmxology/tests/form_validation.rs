@@ -0,0 +1,150 @@
+//! Integration tests for the per-field validation routes of the HtmlForm derive macro.
+
+#![cfg(feature = "derive")]
+
+use axum::extract::FromRequest;
+use htmxology::{HtmlForm, Route};
+use http::Request;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, HtmlForm)]
+#[html_form(
+    route = AccountRoute,
+    action = "Self::action",
+    validation_route = "AccountRoute::validate"
+)]
+pub struct SignupForm {
+    #[html_form(input_type = "email", validate = "validate_email")]
+    email: String,
+    #[html_form(validate = "validate_age")]
+    age: Option<u8>,
+    #[serde(default)]
+    #[html_form(validate = "validate_terms")]
+    terms: bool,
+    nickname: String,
+}
+
+impl SignupForm {
+    fn action(&self) -> AccountRoute {
+        AccountRoute::Signup { form: self.clone() }
+    }
+}
+
+fn validate_email(email: &str) -> Result<(), String> {
+    if email.contains('@') {
+        Ok(())
+    } else {
+        Err("not an e-mail address".to_owned())
+    }
+}
+
+fn validate_age(age: &Option<u8>) -> Result<(), String> {
+    match age {
+        Some(age) if *age < 18 => Err("you must be an adult".to_owned()),
+        _ => Ok(()),
+    }
+}
+
+fn validate_terms(terms: &bool) -> Result<(), String> {
+    if *terms {
+        Ok(())
+    } else {
+        Err("you must accept the terms".to_owned())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+pub enum AccountRoute {
+    #[route("signup", method = "POST")]
+    Signup {
+        #[body]
+        form: SignupForm,
+    },
+
+    #[route("signup/validate/")]
+    Validate(#[subroute] SignupFormValidation),
+}
+
+impl AccountRoute {
+    fn validate(route: SignupFormValidation) -> Self {
+        Self::Validate(route)
+    }
+}
+
+async fn validate(uri: &str, body: &'static str) -> htmxology::htmx::FormInput {
+    let request = Request::builder()
+        .uri(uri)
+        .method("POST")
+        .header(
+            http::header::CONTENT_TYPE,
+            "application/x-www-form-urlencoded",
+        )
+        .body(axum::body::Body::from(body))
+        .unwrap();
+
+    match AccountRoute::from_request(request, &()).await.unwrap() {
+        AccountRoute::Validate(route) => route.validate(),
+        route => panic!("unexpected route: {route:?}"),
+    }
+}
+
+#[test]
+fn test_render() {
+    let form = SignupForm {
+        email: "alice@example.com".to_string(),
+        age: None,
+        terms: false,
+        nickname: "alice".to_string(),
+    };
+
+    assert_eq!(
+        form.email_input().to_string(),
+        concat!(
+            r#"<div class="form-field" hx-post="/signup/validate/email" hx-trigger="change" hx-include="this" hx-swap="outerHTML">"#,
+            r#"<input type="email" name="email" value="alice@example.com">"#,
+            "</div>",
+        )
+    );
+    assert_eq!(
+        form.nickname_input().to_string(),
+        r#"<input type="text" name="nickname" value="alice">"#
+    );
+}
+
+#[tokio::test]
+async fn test_validate() {
+    assert_eq!(
+        validate("/signup/validate/email", "email=bob%40example.com&age=12")
+            .await
+            .to_string(),
+        concat!(
+            r#"<div class="form-field form-field-valid" hx-post="/signup/validate/email" hx-trigger="change" hx-include="this" hx-swap="outerHTML">"#,
+            r#"<input type="email" name="email" value="bob@example.com" aria-invalid="false">"#,
+            "</div>",
+        )
+    );
+
+    let input = validate("/signup/validate/email", "email=bob").await;
+
+    assert_eq!(input.validation(), Some(Err("not an e-mail address")));
+
+    let input = validate("/signup/validate/age", "email=bob&age=12").await;
+
+    assert_eq!(input.validation(), Some(Err("you must be an adult")));
+
+    let input = validate("/signup/validate/age", "age=").await;
+
+    assert_eq!(input.validation(), Some(Ok(())));
+
+    let input = validate("/signup/validate/age", "age=old").await;
+
+    assert!(matches!(input.validation(), Some(Err(_))));
+
+    let input = validate("/signup/validate/terms", "").await;
+
+    assert_eq!(input.validation(), Some(Err("you must accept the terms")));
+
+    let input = validate("/signup/validate/terms", "terms=true").await;
+
+    assert_eq!(input.validation(), Some(Ok(())));
+}
@@ -0,0 +1,197 @@
+//! Integration tests for the fragment anchors of the Route derive macro.
+
+#![cfg(feature = "derive")]
+
+use std::str::FromStr;
+
+use axum::extract::FromRequest;
+use htmxology::Route;
+use http::Request;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+struct SearchQuery {
+    q: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum Section {
+    #[default]
+    Top,
+    Install,
+}
+
+impl std::fmt::Display for Section {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Top => "",
+            Self::Install => "install",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum DocsRoute {
+    #[route("docs/{page}#install")]
+    Install { page: String },
+
+    #[route("docs/{page}/sections")]
+    Section {
+        page: String,
+        #[fragment_anchor]
+        section: Section,
+    },
+
+    #[route("search")]
+    Search {
+        #[query]
+        query: SearchQuery,
+        #[fragment_anchor]
+        result: Option<String>,
+    },
+
+    #[route("api/")]
+    Api(#[subroute] ApiRoute),
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum ApiRoute {
+    #[route("reference")]
+    Reference(#[fragment_anchor] Option<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+#[route_opts(no_display, no_from_str)]
+enum CompactRoute {
+    #[route("faq#contact")]
+    Faq,
+
+    #[route("changelog")]
+    Changelog(#[fragment_anchor] Option<String>),
+}
+
+#[test]
+fn test_display() {
+    let route = DocsRoute::Install {
+        page: "getting started".to_string(),
+    };
+
+    assert_eq!(route.to_string(), "/docs/getting%20started#install");
+    assert_eq!(route.path(), "/docs/getting%20started");
+    assert_eq!(route.fragment().as_deref(), Some("install"));
+
+    let route = DocsRoute::Section {
+        page: "intro".to_string(),
+        section: Section::Install,
+    };
+
+    assert_eq!(route.to_string(), "/docs/intro/sections#install");
+
+    // Empty fragments are omitted.
+    let route = DocsRoute::Section {
+        page: "intro".to_string(),
+        section: Section::Top,
+    };
+
+    assert_eq!(route.to_string(), "/docs/intro/sections");
+    assert_eq!(route.fragment(), None);
+
+    let route = DocsRoute::Search {
+        query: SearchQuery {
+            q: "fragments".to_string(),
+        },
+        result: Some("result 3".to_string()),
+    };
+
+    assert_eq!(route.to_string(), "/search?q=fragments#result%203");
+    assert_eq!(route.query().as_deref(), Some("q=fragments"));
+    assert_eq!(route.fragment().as_deref(), Some("result%203"));
+
+    let route = DocsRoute::Api(ApiRoute::Reference(Some("route".to_string())));
+
+    assert_eq!(route.to_string(), "/api/reference#route");
+    assert_eq!(route.path(), "/api/reference");
+    assert_eq!(route.fragment().as_deref(), Some("route"));
+}
+
+#[test]
+fn test_display_without_dedicated_code() {
+    assert_eq!(CompactRoute::Faq.to_string(), "/faq#contact");
+    assert_eq!(
+        CompactRoute::Changelog(Some("v1".to_string())).to_string(),
+        "/changelog#v1"
+    );
+    assert_eq!(CompactRoute::Changelog(None).to_string(), "/changelog");
+    assert_eq!(
+        CompactRoute::from_str("/changelog#v1").unwrap(),
+        CompactRoute::Changelog(None)
+    );
+    assert_eq!(CompactRoute::from_str("/faq").unwrap(), CompactRoute::Faq);
+}
+
+#[test]
+fn test_parse_ignores_fragments() {
+    assert_eq!(
+        DocsRoute::from_str("/docs/intro/sections#install").unwrap(),
+        DocsRoute::Section {
+            page: "intro".to_string(),
+            section: Section::Top,
+        }
+    );
+    assert_eq!(
+        DocsRoute::from_str("/docs/intro#install").unwrap(),
+        DocsRoute::Install {
+            page: "intro".to_string(),
+        }
+    );
+    assert_eq!(
+        DocsRoute::from_str("/search?q=x#result-1").unwrap(),
+        DocsRoute::Search {
+            query: SearchQuery { q: "x".to_string() },
+            result: None,
+        }
+    );
+    assert_eq!(
+        DocsRoute::from_str("/api/reference#route").unwrap(),
+        DocsRoute::Api(ApiRoute::Reference(None))
+    );
+}
+
+#[tokio::test]
+async fn test_from_request() {
+    let request = Request::builder()
+        .uri("/docs/intro/sections")
+        .body(axum::body::Body::empty())
+        .unwrap();
+
+    assert_eq!(
+        DocsRoute::from_request(request, &()).await.unwrap(),
+        DocsRoute::Section {
+            page: "intro".to_string(),
+            section: Section::Top,
+        }
+    );
+}
+
+#[test]
+fn test_builder_defaults_fragment() {
+    #[derive(Debug, Clone, PartialEq, Route)]
+    enum GuideRoute {
+        #[route("guide/{chapter}")]
+        #[builder]
+        Chapter {
+            chapter: u32,
+            #[fragment_anchor]
+            anchor: Option<String>,
+        },
+    }
+
+    assert_eq!(GuideRoute::chapter(2).build().to_string(), "/guide/2");
+    assert_eq!(
+        GuideRoute::chapter(2)
+            .anchor(Some("summary".to_string()))
+            .build()
+            .to_string(),
+        "/guide/2#summary"
+    );
+}
@@ -0,0 +1,183 @@
+//! Test that routes can be parsed from a URI and a method, without a request.
+
+#![cfg(feature = "derive")]
+
+use htmxology::{ParseError, Route};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+struct ListQuery {
+    page: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct PostForm {
+    title: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum PostRoute {
+    #[route("")]
+    List {
+        #[query]
+        query: ListQuery,
+    },
+
+    #[route("", method = "POST")]
+    Create {
+        #[body]
+        form: PostForm,
+    },
+
+    #[route("{id}", method = "DELETE")]
+    Delete { id: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    #[route("")]
+    Home,
+
+    #[route("posts/")]
+    Posts {
+        #[subroute]
+        route: PostRoute,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum CatchAllRoute {
+    #[route("")]
+    Home,
+
+    #[catch_all]
+    Other(AppRoute),
+}
+
+fn uri(s: &'static str) -> http::Uri {
+    http::Uri::from_static(s)
+}
+
+#[test]
+fn test_from_uri_methods() {
+    assert_eq!(
+        AppRoute::from_uri(&uri("/"), &http::Method::GET).unwrap(),
+        AppRoute::Home
+    );
+    assert_eq!(
+        AppRoute::from_uri(&uri("/posts/7"), &http::Method::DELETE).unwrap(),
+        AppRoute::Posts {
+            route: PostRoute::Delete { id: 7 },
+        }
+    );
+}
+
+#[test]
+fn test_from_uri_subroute_query() {
+    assert_eq!(
+        AppRoute::from_uri(&uri("http://localhost/posts/?page=3"), &http::Method::GET).unwrap(),
+        AppRoute::Posts {
+            route: PostRoute::List {
+                query: ListQuery { page: Some(3) },
+            },
+        }
+    );
+}
+
+#[test]
+fn test_from_uri_errors() {
+    assert!(matches!(
+        AppRoute::from_uri(&uri("/posts/"), &http::Method::POST),
+        Err(ParseError::BodyRequired { .. })
+    ));
+    assert!(matches!(
+        AppRoute::from_uri(&uri("/posts/7"), &http::Method::GET),
+        Err(ParseError::MethodNotAllowed { .. })
+    ));
+    assert!(matches!(
+        AppRoute::from_uri(&uri("/nope"), &http::Method::GET),
+        Err(ParseError::NoMatchingRoute { .. })
+    ));
+}
+
+#[test]
+fn test_from_uri_suggestions() {
+    let err = AppRoute::from_uri(&uri("/posts/7/edit"), &http::Method::DELETE).unwrap_err();
+
+    // Suggestions are only computed on demand, for the top-level route type.
+    assert!(err.suggestions().is_empty());
+
+    let err = err.with_suggestions::<AppRoute>();
+    let suggestions: Vec<_> = err
+        .suggestions()
+        .iter()
+        .map(|suggestion| (suggestion.method.as_str(), suggestion.pattern.as_str()))
+        .collect();
+
+    assert_eq!(
+        suggestions,
+        [
+            ("DELETE", "/posts/{id}"),
+            ("GET", "/posts/"),
+            ("POST", "/posts/"),
+        ]
+    );
+    assert!(matches!(
+        err,
+        ParseError::NoMatchingRoute { method, .. } if method == "DELETE"
+    ));
+}
+
+#[test]
+fn test_from_uri_catch_all() {
+    assert_eq!(
+        CatchAllRoute::from_uri(&uri("/posts/7"), &http::Method::DELETE).unwrap(),
+        CatchAllRoute::Other(AppRoute::Posts {
+            route: PostRoute::Delete { id: 7 },
+        })
+    );
+}
+
+#[test]
+fn test_from_parts() {
+    let (parts, _) = http::Request::builder()
+        .method(http::Method::DELETE)
+        .uri("/posts/12")
+        .body(())
+        .unwrap()
+        .into_parts();
+
+    assert_eq!(
+        AppRoute::from_parts(&parts).unwrap(),
+        AppRoute::Posts {
+            route: PostRoute::Delete { id: 12 },
+        }
+    );
+}
+
+#[test]
+fn test_current_route() {
+    let htmx = htmxology::htmx::Request::Htmx {
+        boosted: false,
+        current_url: "http://localhost:3000/posts/?page=2".to_string(),
+        history_restore_request: false,
+        prompt: String::new(),
+        target: None,
+        trigger_name: None,
+        trigger: None,
+    };
+
+    assert_eq!(
+        htmx.current_route::<AppRoute>().unwrap().unwrap(),
+        AppRoute::Posts {
+            route: PostRoute::List {
+                query: ListQuery { page: Some(2) },
+            },
+        }
+    );
+    assert!(
+        htmxology::htmx::Request::Classic
+            .current_route::<AppRoute>()
+            .is_none()
+    );
+}
@@ -0,0 +1,119 @@
+//! Integration tests for axum handlers mounted in a controller tree.
+
+#![cfg(feature = "derive")]
+
+use axum::response::IntoResponse;
+use htmxology::{
+    Controller, ControllerRouter, HandlerController, Route, RoutingController, ServerInfo, htmx,
+};
+use tower_service::Service;
+
+#[derive(Debug, Clone, PartialEq, Route)]
+pub enum BlogRoute {
+    #[route("")]
+    Home,
+}
+
+#[derive(Clone)]
+pub struct BlogController;
+
+impl Controller for BlogController {
+    type Route = BlogRoute;
+    type Args = u32;
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    async fn handle_request(
+        &self,
+        _route: Self::Route,
+        _htmx: htmx::Request,
+        _parts: http::request::Parts,
+        _server_info: &ServerInfo,
+        args: Self::Args,
+    ) -> Self::Response {
+        Ok(format!("blog {args}").into_response())
+    }
+}
+
+#[derive(Clone, RoutingController)]
+#[controller(AppRoute, args = u32)]
+#[subcontroller(BlogController, route = Blog, path = "blog/", convert_with = "Self::blog")]
+#[subcontroller(HandlerController<u32>, route = Legacy, convert_with = "Self::legacy")]
+struct AppController {
+    legacy: HandlerController<u32>,
+}
+
+impl AppController {
+    fn blog(&self) -> BlogController {
+        BlogController
+    }
+
+    fn legacy(&self) -> HandlerController<u32> {
+        self.legacy.clone()
+    }
+}
+
+async fn call(method: http::Method, uri: &str, body: &'static str) -> (u16, String) {
+    let legacy = axum::Router::new().route(
+        "/users/{id}",
+        axum::routing::get(
+            |axum::extract::Path(id): axum::extract::Path<u32>| async move { format!("user {id}") },
+        )
+        .put(
+            |axum::extract::Path(id): axum::extract::Path<u32>, body: String| async move {
+                format!("updated user {id}: {body}")
+            },
+        ),
+    );
+    let controller = AppController {
+        legacy: HandlerController::from_router(legacy),
+    };
+    let mut service = ControllerRouter::new(controller, |_| async { 7 }).into_service(ServerInfo {
+        base_url: http::Uri::from_static("http://localhost"),
+    });
+
+    let response = service
+        .call(
+            http::Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status().as_u16();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_migrated_routes() {
+    assert_eq!(
+        call(http::Method::GET, "/blog/", "").await,
+        (200, "blog 7".to_owned())
+    );
+}
+
+#[tokio::test]
+async fn test_legacy_routes() {
+    assert_eq!(
+        call(http::Method::GET, "/users/3", "").await,
+        (200, "user 3".to_owned())
+    );
+    assert_eq!(
+        call(http::Method::PUT, "/users/3", "Alice").await,
+        (200, "updated user 3: Alice".to_owned())
+    );
+    assert_eq!(call(http::Method::GET, "/posts", "").await.0, 404);
+}
+
+#[test]
+fn test_legacy_route_parsing() {
+    let route: AppRoute = "/users/3?tab=posts".parse().unwrap();
+
+    assert!(matches!(route, AppRoute::Legacy(_)));
+    assert_eq!(route.to_string(), "/users/3?tab=posts");
+}
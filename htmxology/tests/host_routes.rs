@@ -0,0 +1,114 @@
+//! Integration tests for host-scoped routes.
+
+#![cfg(feature = "derive")]
+
+use std::sync::Arc;
+
+use axum::extract::FromRequest;
+use htmxology::{Route, ServerInfo};
+use http::Request;
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AdminRoute {
+    #[route("")]
+    Dashboard,
+
+    #[route("users")]
+    Users,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    #[route("")]
+    Home,
+
+    #[route("users")]
+    Users,
+
+    #[host("admin.{base}")]
+    #[catch_all]
+    Admin(AdminRoute),
+}
+
+fn make_request(host: &str, uri: &str) -> Request<axum::body::Body> {
+    let mut request = Request::builder()
+        .uri(uri)
+        .header(http::header::HOST, host)
+        .body(axum::body::Body::empty())
+        .unwrap();
+
+    request.extensions_mut().insert(Arc::new(ServerInfo {
+        base_url: "http://example.com".parse().unwrap(),
+    }));
+
+    request
+}
+
+#[tokio::test]
+async fn test_host_route() {
+    let route = AppRoute::from_request(make_request("admin.example.com", "/users"), &())
+        .await
+        .unwrap();
+    assert_eq!(route, AppRoute::Admin(AdminRoute::Users));
+
+    let route = AppRoute::from_request(make_request("admin.example.com:8080", "/"), &())
+        .await
+        .unwrap();
+    assert_eq!(route, AppRoute::Admin(AdminRoute::Dashboard));
+}
+
+#[tokio::test]
+async fn test_base_host_route() {
+    let route = AppRoute::from_request(make_request("example.com", "/users"), &())
+        .await
+        .unwrap();
+    assert_eq!(route, AppRoute::Users);
+}
+
+#[test]
+fn test_host_route_display() {
+    assert_eq!(AppRoute::Admin(AdminRoute::Users).to_string(), "/users");
+    assert_eq!(
+        AppRoute::Admin(AdminRoute::Users).method(),
+        http::Method::GET
+    );
+}
+
+#[test]
+fn test_host_route_absolute_url() {
+    let base_url = http::Uri::from_static("https://example.com:8443/");
+
+    assert_eq!(
+        AppRoute::Admin(AdminRoute::Users).host(),
+        Some("admin.{base}")
+    );
+    assert_eq!(AppRoute::Users.host(), None);
+
+    // Same-path routes on different hosts get different absolute URLs.
+    assert_eq!(
+        AppRoute::Admin(AdminRoute::Users).to_absolute_url(&base_url),
+        "https://admin.example.com:8443/users"
+    );
+    assert_eq!(
+        AppRoute::Users.to_absolute_url(&base_url),
+        "https://example.com:8443/users"
+    );
+}
+
+#[test]
+fn test_host_route_store_key() {
+    use htmxology::{CacheKey, RequestCacheKey};
+
+    let parts = http::Request::new(()).into_parts().0;
+    let key = |route: &AppRoute| {
+        RequestCacheKey::default()
+            .cache_key(route, &htmxology::htmx::Request::Classic, &parts)
+            .unwrap()
+    };
+
+    assert_eq!(key(&AppRoute::Users), "GET /users");
+    assert_eq!(
+        key(&AppRoute::Admin(AdminRoute::Users)),
+        "GET //admin.{base}/users"
+    );
+}
@@ -0,0 +1,86 @@
+//! Integration tests for the HtmlForm derive macro.
+
+#![cfg(feature = "derive")]
+
+use axum::extract::FromRequest;
+use htmxology::{HtmlForm, Route};
+use http::Request;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, HtmlForm)]
+#[html_form(route = AccountRoute, action = "Self::action", submit = "Sign up")]
+pub struct SignupForm {
+    #[html_form(input_type = "email", label = "E-mail", required)]
+    email: String,
+    #[serde(rename = "display-name")]
+    display_name: Option<String>,
+    age: u8,
+    #[serde(default)]
+    newsletter: bool,
+}
+
+impl SignupForm {
+    fn action(&self) -> AccountRoute {
+        AccountRoute::Signup { form: self.clone() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+pub enum AccountRoute {
+    #[route("signup", method = "POST")]
+    Signup {
+        #[body]
+        form: SignupForm,
+    },
+}
+
+#[test]
+fn test_render() {
+    let form = SignupForm {
+        email: "alice@example.com".to_string(),
+        display_name: None,
+        age: 32,
+        newsletter: true,
+    };
+
+    assert_eq!(
+        form.to_string(),
+        concat!(
+            r#"<form hx-post="/signup">"#,
+            r#"<label>E-mail <input type="email" name="email" value="alice@example.com" required></label>"#,
+            r#"<input type="text" name="display-name">"#,
+            r#"<input type="number" name="age" value="32">"#,
+            r#"<input type="checkbox" name="newsletter" value="true" checked>"#,
+            r#"<button type="submit">Sign up</button>"#,
+            "</form>",
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_submit() {
+    let request = Request::builder()
+        .uri("/signup")
+        .method("POST")
+        .header(
+            http::header::CONTENT_TYPE,
+            "application/x-www-form-urlencoded",
+        )
+        .body(axum::body::Body::from(
+            "email=bob%40example.com&display-name=Bob&age=41",
+        ))
+        .unwrap();
+    let route = AccountRoute::from_request(request, &()).await.unwrap();
+
+    assert_eq!(
+        route,
+        AccountRoute::Signup {
+            form: SignupForm {
+                email: "bob@example.com".to_string(),
+                display_name: Some("Bob".to_string()),
+                age: 41,
+                newsletter: false,
+            },
+        }
+    );
+}
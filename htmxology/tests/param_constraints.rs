@@ -0,0 +1,88 @@
+//! Integration tests for path parameter constraints.
+
+#![cfg(feature = "derive")]
+
+use axum::extract::FromRequest;
+use htmxology::Route;
+use http::{Request, StatusCode};
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum ItemRoute {
+    #[route("items/{id:u32:1..}")]
+    Item { id: u32 },
+
+    #[route("items/{slug}")]
+    #[param(slug, regex = "[0-9a-f]{8}")]
+    ItemBySlug { slug: String },
+
+    #[route("offsets/{offset:i8:-10..=10}")]
+    Offset { offset: i8 },
+}
+
+fn make_request(uri: &str) -> Request<axum::body::Body> {
+    Request::builder()
+        .uri(uri)
+        .body(axum::body::Body::empty())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_constrained_params_from_request() {
+    let route = ItemRoute::from_request(make_request("/items/42"), &())
+        .await
+        .unwrap();
+    assert_eq!(route, ItemRoute::Item { id: 42 });
+
+    let route = ItemRoute::from_request(make_request("/items/deadbeef"), &())
+        .await
+        .unwrap();
+    assert_eq!(
+        route,
+        ItemRoute::ItemBySlug {
+            slug: "deadbeef".to_string()
+        }
+    );
+
+    let route = ItemRoute::from_request(make_request("/offsets/-10"), &())
+        .await
+        .unwrap();
+    assert_eq!(route, ItemRoute::Offset { offset: -10 });
+}
+
+#[tokio::test]
+async fn test_constraint_violations_are_not_found() {
+    for uri in [
+        "/items/0",
+        "/items/99999999999",
+        "/items/not-a-slug",
+        "/offsets/11",
+        "/offsets/-300",
+    ] {
+        let err = ItemRoute::from_request(make_request(uri), &())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), StatusCode::NOT_FOUND, "{uri}");
+    }
+}
+
+#[test]
+fn test_constrained_params_from_str() {
+    assert_eq!(
+        "/items/7".parse::<ItemRoute>().unwrap(),
+        ItemRoute::Item { id: 7 }
+    );
+    // Eight digits are a valid slug, but not a valid `u32` within range.
+    assert_eq!(
+        "/items/00000000".parse::<ItemRoute>().unwrap(),
+        ItemRoute::ItemBySlug {
+            slug: "00000000".to_string()
+        }
+    );
+    assert!("/items/0".parse::<ItemRoute>().is_err());
+}
+
+#[test]
+fn test_constrained_params_display() {
+    assert_eq!(ItemRoute::Item { id: 3 }.to_string(), "/items/3");
+    assert_eq!(ItemRoute::Offset { offset: -2 }.to_string(), "/offsets/-2");
+}
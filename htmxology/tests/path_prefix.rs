@@ -0,0 +1,136 @@
+//! Integration tests for applications mounted under a path prefix.
+
+#![cfg(feature = "derive")]
+
+use std::io::{Read, Write};
+
+use axum::response::IntoResponse;
+use htmxology::{
+    Controller, ControllerRouter, Route, RouteExt, RoutingController, Server, ServerInfo,
+    ServerOptions, htmx,
+};
+
+#[derive(Debug, Clone, PartialEq, Route)]
+pub enum BlogRoute {
+    #[route("")]
+    Home,
+
+    #[route("{id}")]
+    Post(u32),
+
+    #[route("latest")]
+    Latest,
+}
+
+#[derive(Clone)]
+pub struct BlogController;
+
+impl Controller for BlogController {
+    type Route = BlogRoute;
+    type Args = ();
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        _htmx: htmx::Request,
+        _parts: http::request::Parts,
+        server_info: &ServerInfo,
+        _args: Self::Args,
+    ) -> Self::Response {
+        let route = AppRoute::Blog(route);
+
+        Ok(match route {
+            AppRoute::Blog(BlogRoute::Latest) => {
+                AppRoute::Blog(BlogRoute::Post(7)).as_redirect_response()
+            }
+            route => {
+                format!("{route} {}", route.to_absolute_url(&server_info.base_url)).into_response()
+            }
+        })
+    }
+}
+
+#[derive(Clone, RoutingController)]
+#[controller(AppRoute)]
+#[subcontroller(BlogController, route = Blog, path = "blog/", convert_with = "Self::blog")]
+struct AppController;
+
+impl AppController {
+    fn blog(&self) -> BlogController {
+        BlogController
+    }
+}
+
+async fn get(addr: std::net::SocketAddr, path: &'static str) -> String {
+    tokio::task::spawn_blocking(move || {
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n"
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_path_prefix() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let router = ControllerRouter::new(AppController, |_| async {});
+    let handle = Server::builder(listener)
+        .with_options(ServerOptions {
+            path_prefix: Some("/myapp/".to_owned()),
+            ..Default::default()
+        })
+        .build()
+        .spawn(router)
+        .unwrap();
+
+    handle.ready().await;
+
+    let addr = handle.local_addr();
+    let base_url = format!("http://127.0.0.1:{}/myapp/", addr.port());
+    assert_eq!(handle.server_info().base_url.to_string(), base_url);
+
+    // Routes are formatted with the prefix, once.
+    let response = get(addr, "/myapp/blog/3").await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+    assert!(
+        response.ends_with(&format!("/myapp/blog/3 {base_url}blog/3")),
+        "{response}"
+    );
+
+    // Requests already stripped by the proxy are served too.
+    let response = get(addr, "/blog/3").await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+
+    let response = get(addr, "/myapp/blog/latest").await;
+    assert!(response.starts_with("HTTP/1.1 303 See Other"), "{response}");
+    assert!(
+        response.contains("location: /myapp/blog/7\r\n"),
+        "{response}"
+    );
+
+    handle.shutdown_and_join().await.unwrap();
+}
+
+#[test]
+fn test_no_path_prefix_outside_requests() {
+    let route = AppRoute::Blog(BlogRoute::Post(3));
+
+    assert_eq!(route.to_string(), "/blog/3");
+    assert_eq!(
+        route.to_absolute_url(&http::Uri::from_static("https://example.com")),
+        "https://example.com/blog/3"
+    );
+    assert!(matches!(
+        "/blog/3".parse::<AppRoute>().unwrap(),
+        AppRoute::Blog(BlogRoute::Post(3))
+    ));
+}
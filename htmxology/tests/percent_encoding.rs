@@ -0,0 +1,196 @@
+//! Integration tests for the percent-encoding of path parameters in the Route derive macro.
+
+#![cfg(feature = "derive")]
+
+use std::str::FromStr;
+
+use axum::extract::FromRequest;
+use htmxology::Route;
+use http::Request;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+struct SearchQuery {
+    q: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum WikiRoute {
+    #[route("pages/{title}")]
+    Page { title: String },
+
+    #[route("pages/{title}/history")]
+    History(String),
+
+    #[route("raw/{slug}")]
+    #[param(slug, encoded)]
+    Raw { slug: String },
+
+    #[route("files/{*path}")]
+    File { path: String },
+
+    #[route("search/{scope}")]
+    Search {
+        scope: String,
+        #[query]
+        query: SearchQuery,
+    },
+
+    #[route("users/{user}/")]
+    User {
+        user: String,
+        #[subroute]
+        subroute: UserRoute,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum UserRoute {
+    #[route("")]
+    Profile,
+}
+
+#[test]
+fn test_display_encodes_path_parameters() {
+    assert_eq!(
+        WikiRoute::Page {
+            title: "AC/DC #1 hits".to_string()
+        }
+        .to_string(),
+        "/pages/AC%2FDC%20%231%20hits"
+    );
+    assert_eq!(
+        WikiRoute::History("100% été".to_string()).to_string(),
+        "/pages/100%25%20%C3%A9t%C3%A9/history"
+    );
+    assert_eq!(
+        WikiRoute::File {
+            path: "docs/my file?.md".to_string()
+        }
+        .to_string(),
+        "/files/docs/my%20file%3F.md"
+    );
+    assert_eq!(
+        WikiRoute::Search {
+            scope: "a b".to_string(),
+            query: SearchQuery {
+                q: "x&y=z #".to_string()
+            },
+        }
+        .to_string(),
+        "/search/a%20b?q=x%26y%3Dz+%23"
+    );
+    assert_eq!(
+        WikiRoute::User {
+            user: "jane/doe".to_string(),
+            subroute: UserRoute::Profile,
+        }
+        .to_string(),
+        "/users/jane%2Fdoe/"
+    );
+}
+
+#[test]
+fn test_encoded_parameters_are_verbatim() {
+    let route = WikiRoute::Raw {
+        slug: "a%2Fb".to_string(),
+    };
+
+    assert_eq!(route.to_string(), "/raw/a%2Fb");
+    assert_eq!(WikiRoute::from_str("/raw/a%2Fb").unwrap(), route);
+}
+
+#[test]
+fn test_display_roundtrip() {
+    for route in [
+        WikiRoute::Page {
+            title: "AC/DC #1 hits".to_string(),
+        },
+        WikiRoute::History("100% été".to_string()),
+        WikiRoute::File {
+            path: "docs/my file?.md".to_string(),
+        },
+        WikiRoute::Search {
+            scope: "a b".to_string(),
+            query: SearchQuery {
+                q: "x&y=z #".to_string(),
+            },
+        },
+        WikiRoute::User {
+            user: "jane/doe".to_string(),
+            subroute: UserRoute::Profile,
+        },
+    ] {
+        let url = route.to_string();
+
+        assert_eq!(WikiRoute::from_str(&url).unwrap(), route, "{url}");
+        assert_eq!(
+            WikiRoute::from_uri(&url.parse().unwrap(), &http::Method::GET).unwrap(),
+            route,
+            "{url}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_from_request_roundtrip() {
+    for route in [
+        WikiRoute::Page {
+            title: "AC/DC #1 hits".to_string(),
+        },
+        WikiRoute::Raw {
+            slug: "a%2Fb".to_string(),
+        },
+    ] {
+        let request = Request::builder()
+            .uri(route.to_string())
+            .body(axum::body::Body::empty())
+            .unwrap();
+
+        assert_eq!(WikiRoute::from_request(request, &()).await.unwrap(), route);
+    }
+}
+
+#[tokio::test]
+async fn test_invalid_escapes_do_not_match() {
+    // Malformed escapes and escapes that are not UTF-8 are both rejected as unknown paths.
+    for url in ["/pages/%ZZ", "/pages/%FF", "/pages/100%", "/files/a/%FF"] {
+        assert!(
+            matches!(
+                WikiRoute::from_str(url),
+                Err(htmxology::ParseError::NoMatchingRoute { .. })
+            ),
+            "{url}"
+        );
+
+        let request = Request::builder()
+            .uri(url)
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = WikiRoute::from_request(request, &()).await.unwrap_err();
+
+        assert_eq!(response.status(), http::StatusCode::NOT_FOUND, "{url}");
+    }
+
+    // Encoded parameters are parsed verbatim.
+    assert_eq!(
+        WikiRoute::from_str("/raw/%ZZ").unwrap(),
+        WikiRoute::Raw {
+            slug: "%ZZ".to_string()
+        }
+    );
+}
+
+#[test]
+fn test_empty_parameters_do_not_roundtrip() {
+    let route = WikiRoute::Page {
+        title: String::new(),
+    };
+
+    // Empty values format as empty segments, which parameters don't match.
+    assert_eq!(route.to_string(), "/pages/");
+    assert!(matches!(
+        WikiRoute::from_str(&route.to_string()),
+        Err(htmxology::ParseError::NoMatchingRoute { .. })
+    ));
+}
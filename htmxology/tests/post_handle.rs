@@ -0,0 +1,157 @@
+//! Integration tests for the `post_handle` hook of routing controllers.
+
+#![cfg(feature = "derive")]
+
+use axum::response::IntoResponse;
+use htmxology::{Controller, Route, RoutingController, ServerInfo, htmx};
+
+#[derive(Debug, Clone, PartialEq, Route)]
+pub enum SectionRoute {
+    #[route("ok")]
+    Ok,
+
+    #[route("fail")]
+    Fail,
+}
+
+/// A section, generic to be mounted twice.
+#[derive(Clone)]
+pub struct SectionController<const N: u8>;
+
+impl<const N: u8> Controller for SectionController<N> {
+    type Route = SectionRoute;
+    type Args = ();
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        _htmx: htmx::Request,
+        _parts: http::request::Parts,
+        _server_info: &ServerInfo,
+        _args: Self::Args,
+    ) -> Self::Response {
+        match route {
+            SectionRoute::Ok => Ok("section".into_response()),
+            SectionRoute::Fail => Err(http::StatusCode::INTERNAL_SERVER_ERROR.into_response()),
+        }
+    }
+}
+
+#[derive(Clone, RoutingController)]
+#[controller(
+    AppRoute,
+    pre_handler = "Self::reject_admin",
+    post_handle = "Self::wrap_in_layout"
+)]
+#[subcontroller(SectionController<0>, route = Section, path = "section/", convert_with = "Self::section")]
+#[subcontroller(SectionController<1>, route = Admin, path = "admin/", convert_with = "Self::section")]
+struct AppController;
+
+impl AppController {
+    fn section<const N: u8>(&self) -> SectionController<N> {
+        SectionController
+    }
+
+    async fn reject_admin(
+        &self,
+        route: &AppRoute,
+        _htmx: &htmx::Request,
+        _parts: &http::request::Parts,
+        _server_info: &ServerInfo,
+        _args: &mut (),
+    ) -> Option<Result<axum::response::Response, axum::response::Response>> {
+        match route {
+            AppRoute::Admin(_) => Some(Err(http::StatusCode::FORBIDDEN.into_response())),
+            _ => None,
+        }
+    }
+
+    async fn wrap_in_layout(
+        &self,
+        htmx: &htmx::Request,
+        parts: &http::request::Parts,
+        _server_info: &ServerInfo,
+        response: Result<axum::response::Response, axum::response::Response>,
+    ) -> Result<axum::response::Response, axum::response::Response> {
+        let response = response?;
+
+        if matches!(htmx, htmx::Request::Htmx { .. }) {
+            return Ok(response);
+        }
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap_or_default();
+        let body = String::from_utf8_lossy(&body);
+
+        Ok(format!("<main data-path=\"{}\">{body}</main>", parts.uri.path()).into_response())
+    }
+}
+
+async fn handle(route: &str, htmx: htmx::Request) -> axum::response::Response {
+    let (parts, _) = http::Request::builder()
+        .uri(route)
+        .body(())
+        .unwrap()
+        .into_parts();
+    let server_info = ServerInfo {
+        base_url: http::Uri::from_static("http://localhost"),
+    };
+
+    match AppController
+        .handle_request(route.parse().unwrap(), htmx, parts, &server_info, ())
+        .await
+    {
+        Ok(response) | Err(response) => response,
+    }
+}
+
+async fn body(response: axum::response::Response) -> String {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_post_handle_transforms_responses() {
+    let response = handle("/section/ok", htmx::Request::Classic).await;
+
+    assert_eq!(
+        body(response).await,
+        r#"<main data-path="/section/ok">section</main>"#
+    );
+
+    let response = handle(
+        "/section/ok",
+        htmx::Request::Htmx {
+            boosted: false,
+            current_url: "http://localhost/".to_owned(),
+            history_restore_request: false,
+            prompt: String::new(),
+            target: None,
+            trigger_name: None,
+            trigger: None,
+        },
+    )
+    .await;
+
+    assert_eq!(body(response).await, "section");
+}
+
+#[tokio::test]
+async fn test_post_handle_receives_errors() {
+    let response = handle("/section/fail", htmx::Request::Classic).await;
+
+    assert_eq!(response.status(), http::StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn test_post_handle_skips_pre_handler_responses() {
+    let response = handle("/admin/ok", htmx::Request::Classic).await;
+
+    assert_eq!(response.status(), http::StatusCode::FORBIDDEN);
+    assert_eq!(body(response).await, "");
+}
@@ -0,0 +1,126 @@
+//! Integration tests for the static route export.
+
+#![cfg(feature = "derive")]
+
+use axum::response::IntoResponse;
+use htmxology::{Controller, PrerenderError, Prerenderer, Route, ServerInfo};
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum SiteRoute {
+    #[route("")]
+    Home,
+
+    #[route("blog/{slug}")]
+    Post { slug: String },
+
+    #[route("missing")]
+    Missing,
+
+    #[route("contact", method = "POST")]
+    Contact,
+}
+
+#[derive(Clone)]
+struct SiteController;
+
+impl Controller for SiteController {
+    type Route = SiteRoute;
+    type Args = ();
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        _htmx: htmxology::htmx::Request,
+        _parts: http::request::Parts,
+        server_info: &ServerInfo,
+        _args: Self::Args,
+    ) -> Self::Response {
+        match route {
+            SiteRoute::Home => Ok(axum::response::Html(format!(
+                r#"<link href="/assets/style.css"><a href="{}">Home</a>"#,
+                server_info.base_url
+            ))
+            .into_response()),
+            SiteRoute::Post { slug } => Ok(axum::response::Html(format!(
+                r#"<img src='/assets/{slug}.png'>"#
+            ))
+            .into_response()),
+            SiteRoute::Missing => Err(http::StatusCode::NOT_FOUND.into_response()),
+            SiteRoute::Contact => Ok(().into_response()),
+        }
+    }
+}
+
+fn prerenderer() -> Prerenderer<SiteController, impl Fn(&SiteController) -> std::future::Ready<()>>
+{
+    Prerenderer::new(SiteController, |_| std::future::ready(()))
+        .with_base_url("https://example.com/".parse().unwrap())
+        .with_asset_url_rewrite("/assets/", "https://cdn.example.com/assets/")
+}
+
+#[tokio::test]
+async fn test_render() {
+    let prerenderer = prerenderer();
+
+    assert_eq!(
+        prerenderer.render(SiteRoute::Home).await.unwrap(),
+        r#"<link href="https://cdn.example.com/assets/style.css"><a href="https://example.com/">Home</a>"#
+    );
+    assert_eq!(
+        prerenderer
+            .render(SiteRoute::Post {
+                slug: "hello".to_string()
+            })
+            .await
+            .unwrap(),
+        r#"<img src='https://cdn.example.com/assets/hello.png'>"#
+    );
+}
+
+#[tokio::test]
+async fn test_render_errors() {
+    let prerenderer = prerenderer();
+
+    assert!(matches!(
+        prerenderer.render(SiteRoute::Missing).await,
+        Err(PrerenderError::Status { status, .. }) if status == http::StatusCode::NOT_FOUND
+    ));
+    assert!(matches!(
+        prerenderer.render(SiteRoute::Contact).await,
+        Err(PrerenderError::NotAGetRoute { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_export() {
+    let output_dir =
+        std::env::temp_dir().join(format!("htmxology-prerender-{}", std::process::id()));
+
+    let paths = prerenderer()
+        .export(
+            [
+                SiteRoute::Home,
+                SiteRoute::Post {
+                    slug: "hello".to_string(),
+                },
+            ],
+            &output_dir,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        paths,
+        vec![
+            output_dir.join("index.html"),
+            output_dir.join("blog/hello/index.html"),
+        ]
+    );
+    assert_eq!(
+        std::fs::read_to_string(&paths[1]).unwrap(),
+        r#"<img src='https://cdn.example.com/assets/hello.png'>"#
+    );
+
+    std::fs::remove_dir_all(&output_dir).unwrap();
+}
@@ -0,0 +1,134 @@
+//! Integration tests for the redirect tables of controller routers.
+
+#![cfg(all(feature = "derive", feature = "redirects"))]
+
+use axum::response::IntoResponse;
+use htmxology::{
+    Controller, ControllerRouter, Route, ServerInfo, StaticAssets, redirects::Redirects,
+};
+use tower_service::Service;
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    #[route("")]
+    Home,
+
+    #[route("blog/{slug}")]
+    Post { slug: String },
+}
+
+#[derive(Clone)]
+struct AppController;
+
+impl Controller for AppController {
+    type Route = AppRoute;
+    type Args = ();
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        _htmx: htmxology::htmx::Request,
+        parts: http::request::Parts,
+        _server_info: &ServerInfo,
+        _args: Self::Args,
+    ) -> Self::Response {
+        Ok(format!("{route:?} {}", parts.uri).into_response())
+    }
+}
+
+async fn get(
+    service: &mut htmxology::ControllerService,
+    host: &str,
+    uri: &str,
+) -> (http::StatusCode, Option<String>, String) {
+    let request = http::Request::get(uri)
+        .header(http::header::HOST, host)
+        .body(axum::body::Body::empty())
+        .unwrap();
+
+    let response = service.call(request).await.unwrap();
+    let status = response.status();
+    let location = response
+        .headers()
+        .get(http::header::LOCATION)
+        .map(|location| location.to_str().unwrap().to_owned());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    (status, location, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_redirects() {
+    let redirects = Redirects::from_toml(
+        r#"
+        [[redirect]]
+        from = "/old-blog/{slug}"
+        to = "/blog/{slug}"
+
+        [[redirect]]
+        host = "docs.{base}"
+        from = "/{*path}"
+        to = "https://docs.example.org/{path}"
+        status = 302
+
+        [[rewrite]]
+        from = "/latest"
+        to = "/blog/latest"
+
+        [[rewrite]]
+        from = "/logo.svg"
+        to = "/static/logo.svg"
+        "#,
+    )
+    .unwrap();
+
+    let mut service = ControllerRouter::new(AppController, |_| async {})
+        .with_static_assets(
+            "/static",
+            StaticAssets::embedded().with_file("logo.svg", b"<svg/>"),
+        )
+        .with_redirects(redirects)
+        .into_service(ServerInfo {
+            base_url: "http://example.com".parse().unwrap(),
+        });
+
+    assert_eq!(
+        get(&mut service, "example.com", "/old-blog/hello?page=2").await,
+        (
+            http::StatusCode::MOVED_PERMANENTLY,
+            Some("/blog/hello?page=2".to_owned()),
+            String::new()
+        )
+    );
+    assert_eq!(
+        get(&mut service, "docs.example.com", "/guide").await,
+        (
+            http::StatusCode::FOUND,
+            Some("https://docs.example.org/guide".to_owned()),
+            String::new()
+        )
+    );
+
+    // Rewrites are routed to the controller or to the other endpoints.
+    assert_eq!(
+        get(&mut service, "example.com", "/latest?page=2").await,
+        (
+            http::StatusCode::OK,
+            None,
+            r#"Post { slug: "latest" } /blog/latest?page=2"#.to_owned()
+        )
+    );
+    assert_eq!(
+        get(&mut service, "example.com", "/logo.svg").await,
+        (http::StatusCode::OK, None, "<svg/>".to_owned())
+    );
+
+    // Other requests are left untouched.
+    assert_eq!(
+        get(&mut service, "example.com", "/").await,
+        (http::StatusCode::OK, None, "Home /".to_owned())
+    );
+}
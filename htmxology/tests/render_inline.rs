@@ -0,0 +1,138 @@
+//! Integration tests for the inline rendering of routes by controllers.
+
+#![cfg(feature = "derive")]
+
+use axum::response::IntoResponse;
+use htmxology::{Controller, ControllerExt, ControllerRouter, RenderStack, Route, ServerInfo};
+use tower_service::Service;
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    /// A page rendering its sidebar inline.
+    #[route("")]
+    Home,
+
+    #[route("sidebar")]
+    Sidebar,
+
+    /// A route rendering itself inline, through another route.
+    #[route("loop")]
+    Loop,
+
+    #[route("loop/inner")]
+    LoopInner,
+
+    /// A route rendering its successor inline, up to a depth.
+    #[route("nested/{depth}")]
+    Nested { depth: u32 },
+}
+
+#[derive(Clone)]
+struct AppController;
+
+impl Controller for AppController {
+    type Route = AppRoute;
+    type Args = ();
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        htmx: htmxology::htmx::Request,
+        parts: http::request::Parts,
+        server_info: &ServerInfo,
+        args: Self::Args,
+    ) -> Self::Response {
+        let inner = match route {
+            AppRoute::Home => AppRoute::Sidebar,
+            AppRoute::Sidebar => {
+                return Ok(format!(
+                    "sidebar of {}",
+                    RenderStack::of(&parts).routes().join(" > ")
+                )
+                .into_response());
+            }
+            AppRoute::Loop => AppRoute::LoopInner,
+            AppRoute::LoopInner => AppRoute::Loop,
+            AppRoute::Nested { depth: 0 } => return Ok("bottom".into_response()),
+            AppRoute::Nested { depth } => AppRoute::Nested { depth: depth - 1 },
+        };
+
+        let response = Box::pin(self.render_inline(inner, htmx, &parts, server_info, args))
+            .await
+            .map_err(IntoResponse::into_response)??;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        Ok(format!("<main>{}</main>", String::from_utf8_lossy(&body)).into_response())
+    }
+}
+
+async fn get(service: &mut htmxology::ControllerService, uri: &str) -> (http::StatusCode, String) {
+    let request = http::Request::get(uri)
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let response = service.call(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+fn service(router: ControllerRouter) -> htmxology::ControllerService {
+    router.into_service(ServerInfo {
+        base_url: "http://localhost".parse().unwrap(),
+    })
+}
+
+#[tokio::test]
+async fn test_render_inline() {
+    let mut service = service(ControllerRouter::new(AppController, |_| async {}));
+
+    assert_eq!(
+        get(&mut service, "/").await,
+        (
+            http::StatusCode::OK,
+            "<main>sidebar of GET / > GET /sidebar</main>".to_owned()
+        )
+    );
+    assert_eq!(
+        get(&mut service, "/nested/3").await,
+        (
+            http::StatusCode::OK,
+            "<main><main><main>bottom</main></main></main>".to_owned()
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_render_cycle() {
+    let mut service = service(ControllerRouter::new(AppController, |_| async {}));
+
+    assert_eq!(
+        get(&mut service, "/loop").await,
+        (
+            http::StatusCode::LOOP_DETECTED,
+            "render cycle detected: GET /loop -> GET /loop/inner -> GET /loop".to_owned()
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_render_depth_limit() {
+    let mut service =
+        service(ControllerRouter::new(AppController, |_| async {}).with_render_depth_limit(2));
+
+    assert_eq!(get(&mut service, "/nested/2").await.0, http::StatusCode::OK);
+    assert_eq!(
+        get(&mut service, "/nested/3").await,
+        (
+            http::StatusCode::LOOP_DETECTED,
+            "render depth limit of 2 exceeded: GET /nested/3 -> GET /nested/2 -> GET /nested/1 -> GET /nested/0"
+                .to_owned()
+        )
+    );
+}
@@ -0,0 +1,99 @@
+//! Integration tests for args factories receiving the request parts.
+
+#![cfg(feature = "derive")]
+
+use axum::response::IntoResponse;
+use htmxology::{Controller, ControllerRouter, Route, ServerInfo, htmx};
+use tower_service::Service;
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    #[route("")]
+    Home,
+}
+
+/// The user set by an authentication middleware.
+#[derive(Debug, Clone)]
+struct User(String);
+
+#[derive(Debug)]
+struct AppArgs {
+    greeting: &'static str,
+    user: Option<User>,
+    locale: Option<String>,
+}
+
+#[derive(Clone)]
+struct AppController {
+    greeting: &'static str,
+}
+
+impl Controller for AppController {
+    type Route = AppRoute;
+    type Args = AppArgs;
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        _htmx: htmx::Request,
+        _parts: http::request::Parts,
+        _server_info: &ServerInfo,
+        args: Self::Args,
+    ) -> Self::Response {
+        match route {
+            AppRoute::Home => Ok(format!(
+                "{} {} ({})",
+                args.greeting,
+                args.user.map_or("anonymous".to_owned(), |User(name)| name),
+                args.locale.as_deref().unwrap_or("en"),
+            )
+            .into_response()),
+        }
+    }
+}
+
+async fn get(request: http::request::Builder) -> String {
+    let router = ControllerRouter::new_with_parts(
+        AppController { greeting: "Hello" },
+        |controller: &AppController, parts: &http::request::Parts| {
+            let args = AppArgs {
+                greeting: controller.greeting,
+                user: parts.extensions.get::<User>().cloned(),
+                locale: parts
+                    .headers
+                    .get(http::header::ACCEPT_LANGUAGE)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_owned),
+            };
+
+            async move { args }
+        },
+    );
+    let mut service = router.into_service(ServerInfo {
+        base_url: http::Uri::from_static("http://localhost"),
+    });
+
+    let response = service
+        .call(request.body(axum::body::Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_args_from_parts() {
+    assert_eq!(get(http::Request::get("/")).await, "Hello anonymous (en)");
+
+    assert_eq!(
+        get(http::Request::get("/")
+            .extension(User("alice".to_owned()))
+            .header(http::header::ACCEPT_LANGUAGE, "fr"))
+        .await,
+        "Hello alice (fr)"
+    );
+}
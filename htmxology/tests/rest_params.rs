@@ -0,0 +1,77 @@
+//! Integration tests for `{*rest}` path parameters in the Route derive macro.
+
+#![cfg(feature = "derive")]
+
+use std::str::FromStr;
+
+use axum::extract::FromRequest;
+use htmxology::Route;
+use http::Request;
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum FilesRoute {
+    #[route("files/{name}")]
+    File { name: String },
+
+    #[route("files/{*path}")]
+    Nested { path: String },
+
+    #[route("tree/{id}/{*segments}")]
+    Tree(u32, Vec<String>),
+}
+
+#[test]
+fn test_from_str() {
+    assert_eq!(
+        FilesRoute::from_str("/files/readme.md").unwrap(),
+        FilesRoute::File {
+            name: "readme.md".to_string()
+        }
+    );
+    assert_eq!(
+        FilesRoute::from_str("/files/docs/my%20notes.md").unwrap(),
+        FilesRoute::Nested {
+            path: "docs/my notes.md".to_string()
+        }
+    );
+    assert_eq!(
+        FilesRoute::from_str("/tree/4/a/b/").unwrap(),
+        FilesRoute::Tree(4, vec!["a".to_string(), "b".to_string()])
+    );
+    assert_eq!(
+        FilesRoute::from_str("/tree/4/").unwrap(),
+        FilesRoute::Tree(4, vec![])
+    );
+}
+
+#[test]
+fn test_display_roundtrip() {
+    for route in [
+        FilesRoute::Nested {
+            path: "docs/guide.md".to_string(),
+        },
+        FilesRoute::Tree(7, vec!["src".to_string(), "lib.rs".to_string()]),
+    ] {
+        assert_eq!(FilesRoute::from_str(&route.to_string()).unwrap(), route);
+    }
+
+    assert_eq!(
+        FilesRoute::Tree(7, vec!["src".to_string(), "lib.rs".to_string()]).to_string(),
+        "/tree/7/src/lib.rs"
+    );
+}
+
+#[tokio::test]
+async fn test_from_request() {
+    let request = Request::builder()
+        .uri("/files/a/b%2Fc")
+        .body(axum::body::Body::empty())
+        .unwrap();
+
+    assert_eq!(
+        FilesRoute::from_request(request, &()).await.unwrap(),
+        FilesRoute::Nested {
+            path: "a/b/c".to_string()
+        }
+    );
+}
@@ -0,0 +1,111 @@
+//! Integration tests for the route aliases of the Route derive macro.
+
+#![cfg(feature = "derive")]
+
+use std::str::FromStr;
+
+use axum::extract::FromRequest;
+use htmxology::Route;
+use http::Request;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+struct ListQuery {
+    page: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+struct CommentForm {
+    text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum BlogRoute {
+    #[route("articles/{id}")]
+    #[route_alias("posts/{id}")]
+    #[route_alias("blog/{id}/view", redirect)]
+    #[param(id, regex = "[0-9]+")]
+    Article { id: u32 },
+
+    #[route("articles")]
+    #[route_alias("archive", redirect)]
+    Articles {
+        #[query]
+        query: ListQuery,
+    },
+
+    #[route("articles/{id}/comments", method = "POST")]
+    #[route_alias("posts/{id}/comments", redirect)]
+    Comment {
+        id: u32,
+        #[body]
+        form: CommentForm,
+    },
+}
+
+async fn from_request(request: Request<axum::body::Body>) -> Result<BlogRoute, (u16, String)> {
+    BlogRoute::from_request(request, &())
+        .await
+        .map_err(|response| {
+            let location = response
+                .headers()
+                .get(http::header::LOCATION)
+                .map(|location| location.to_str().unwrap().to_owned())
+                .unwrap_or_default();
+
+            (response.status().as_u16(), location)
+        })
+}
+
+fn get(uri: &str) -> Request<axum::body::Body> {
+    Request::get(uri).body(axum::body::Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn test_alias_matches_route() {
+    assert_eq!(
+        from_request(get("/posts/12")).await,
+        Ok(BlogRoute::Article { id: 12 })
+    );
+    assert_eq!(
+        BlogRoute::from_str("/posts/12").unwrap(),
+        BlogRoute::Article { id: 12 }
+    );
+    assert_eq!(
+        BlogRoute::from_uri(&"/blog/12/view".parse().unwrap(), &http::Method::GET).unwrap(),
+        BlogRoute::Article { id: 12 }
+    );
+
+    // The parameter constraints apply to the aliases too.
+    assert!(BlogRoute::from_str("/posts/latest").is_err());
+}
+
+#[test]
+fn test_display_uses_route_url() {
+    assert_eq!(BlogRoute::Article { id: 12 }.to_string(), "/articles/12");
+}
+
+#[tokio::test]
+async fn test_redirect_alias() {
+    assert_eq!(
+        from_request(get("/blog/12/view")).await,
+        Err((301, "/articles/12".to_owned()))
+    );
+    assert_eq!(
+        from_request(get("/archive?page=3")).await,
+        Err((301, "/articles?page=3".to_owned()))
+    );
+
+    let request = Request::post("/posts/12/comments")
+        .header(
+            http::header::CONTENT_TYPE,
+            "application/x-www-form-urlencoded",
+        )
+        .body(axum::body::Body::from("text=hello"))
+        .unwrap();
+
+    assert_eq!(
+        from_request(request).await,
+        Err((308, "/articles/12/comments".to_owned()))
+    );
+}
@@ -0,0 +1,69 @@
+//! Integration tests for builder-style route constructors.
+
+#![cfg(feature = "derive")]
+
+use htmxology::Route;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+struct SearchQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    q: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    #[route("search")]
+    #[builder]
+    Search {
+        #[query]
+        query: SearchQuery,
+    },
+
+    #[route("users/{user_id}/posts")]
+    #[builder]
+    UserPosts {
+        user_id: u32,
+        #[query]
+        query: SearchQuery,
+    },
+}
+
+#[test]
+fn test_builder_defaults() {
+    let route = AppRoute::search().build();
+
+    assert_eq!(
+        route,
+        AppRoute::Search {
+            query: SearchQuery::default(),
+        }
+    );
+    assert_eq!(route.to_string(), "/search");
+}
+
+#[test]
+fn test_builder_with_query() {
+    let route = AppRoute::search()
+        .query_with(|q| {
+            q.q = Some("rust".to_string());
+            q.page = Some(2);
+        })
+        .build();
+
+    assert_eq!(route.to_string(), "/search?q=rust&page=2");
+}
+
+#[test]
+fn test_builder_with_path_params() {
+    let route: AppRoute = AppRoute::user_posts(42)
+        .query(SearchQuery {
+            q: None,
+            page: Some(3),
+        })
+        .into();
+
+    assert_eq!(route.to_string(), "/users/42/posts?page=3");
+}
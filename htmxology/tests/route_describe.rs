@@ -0,0 +1,113 @@
+//! Tests for the route descriptions generated by the `Route` derive macro.
+
+#![cfg(feature = "derive")]
+
+use htmxology::{ParameterLocation, Route, RouteDescription, RouteParameter};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SearchQuery {
+    q: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CommentForm {
+    body: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum PostRoute {
+    /// Show a post.
+    #[route("")]
+    Show,
+
+    #[route("comments", method = "POST")]
+    Comment {
+        #[body("application/json")]
+        form: CommentForm,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    /// The home page.
+    ///
+    /// Lists the latest posts.
+    #[route("")]
+    Home,
+
+    /// Search the posts.
+    #[route("search")]
+    Search {
+        #[query]
+        query: Option<SearchQuery>,
+    },
+
+    /// A post.
+    #[route("posts/{id:u32:1..}/")]
+    Post {
+        id: u32,
+        #[subroute]
+        route: PostRoute,
+    },
+}
+
+#[test]
+fn test_describe() {
+    let id = RouteParameter::new("id", ParameterLocation::Path, "u32");
+
+    assert_eq!(
+        AppRoute::describe(),
+        [
+            RouteDescription::new("Home", "GET", "/")
+                .with_doc("The home page.\n\nLists the latest posts."),
+            RouteDescription::new("Search", "GET", "/search")
+                .with_doc("Search the posts.")
+                .with_parameter(RouteParameter::new(
+                    "query",
+                    ParameterLocation::Query,
+                    "Option<SearchQuery>",
+                )),
+            RouteDescription::new("Post::Show", "GET", "/posts/{id:u32:1..}/")
+                .with_doc("Show a post.")
+                .with_parameter(id.clone()),
+            RouteDescription::new("Post::Comment", "POST", "/posts/{id:u32:1..}/comments")
+                .with_doc("A post.")
+                .with_parameter(id)
+                .with_parameter(
+                    RouteParameter::new("form", ParameterLocation::Body, "CommentForm")
+                        .with_content_types(&["application/json"])
+                ),
+        ]
+    );
+
+    assert_eq!(
+        AppRoute::describe()
+            .iter()
+            .map(RouteDescription::to_entry)
+            .collect::<Vec<_>>(),
+        AppRoute::route_table()
+    );
+}
+
+#[cfg(feature = "openapi")]
+#[test]
+fn test_openapi() {
+    let document = htmxology::openapi::OpenApi::new("Blog", "1.0.0")
+        .with_routes::<AppRoute>()
+        .to_json();
+
+    let post = &document["paths"]["/posts/{id}/comments"]["post"];
+
+    assert_eq!(post["operationId"], "Post_Comment");
+    assert_eq!(post["summary"], "A post.");
+    assert_eq!(post["parameters"][0]["schema"]["minimum"], 1);
+    assert_eq!(
+        post["requestBody"]["content"]["application/json"]["schema"]["x-htmxology-type"],
+        "CommentForm"
+    );
+    assert_eq!(
+        document["paths"]["/search"]["get"]["parameters"][0]["required"],
+        false
+    );
+}
@@ -0,0 +1,229 @@
+//! Test that the variants of a route type can be split across modules with `#[route_include]`.
+
+#![cfg(feature = "derive")]
+
+use axum::extract::FromRequest;
+use htmxology::{ParseError, Route, RouteEntry};
+
+mod blog {
+    use htmxology::Route;
+
+    #[derive(Debug, Clone, PartialEq, Route)]
+    pub enum BlogRoute {
+        #[route("posts")]
+        Posts,
+
+        #[route("posts/{id}")]
+        Post { id: u32 },
+
+        #[route("posts/{id}", method = "DELETE")]
+        DeletePost { id: u32 },
+    }
+}
+
+mod admin {
+    use htmxology::Route;
+
+    #[derive(Debug, Clone, PartialEq, Route)]
+    pub enum AdminRoute {
+        #[route("admin/users")]
+        Users,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    #[route("")]
+    Home,
+
+    #[route_include]
+    Blog(blog::BlogRoute),
+
+    #[route_include]
+    Admin(admin::AdminRoute),
+
+    #[route("about")]
+    About,
+}
+
+mod pages {
+    use htmxology::Route;
+
+    #[derive(Debug, Clone, PartialEq, Route)]
+    pub enum PageRoute {
+        #[route("about")]
+        About,
+
+        #[route("pages/{id}")]
+        Page { id: u32 },
+    }
+}
+
+/// A route type whose parameter route would shadow the included routes if they weren't matched in
+/// place.
+#[derive(Debug, Clone, PartialEq, Route)]
+enum SiteRoute {
+    #[route("{slug}")]
+    Page(String),
+
+    #[route("pages/latest")]
+    Latest,
+
+    #[route_include]
+    Pages(pages::PageRoute),
+}
+
+#[cfg(debug_assertions)]
+mod tags {
+    use htmxology::Route;
+
+    #[derive(Debug, Clone, PartialEq, Route)]
+    pub enum TagRoute {
+        #[route("{tag}")]
+        Tag(String),
+    }
+}
+
+/// A route type whose route can never be reached, because of an included route.
+#[cfg(debug_assertions)]
+#[derive(Debug, Clone, PartialEq, Route)]
+enum ShadowedRoute {
+    #[route("{slug}")]
+    Page(String),
+
+    #[route_include]
+    Tags(tags::TagRoute),
+}
+
+async fn from_request(method: &str, uri: &str) -> Result<AppRoute, http::StatusCode> {
+    let request = http::Request::builder()
+        .method(method)
+        .uri(uri)
+        .body(axum::body::Body::empty())
+        .unwrap();
+
+    AppRoute::from_request(request, &())
+        .await
+        .map_err(|response| response.status())
+}
+
+#[test]
+fn test_display_and_from_str() {
+    for route in [
+        AppRoute::Home,
+        AppRoute::Blog(blog::BlogRoute::Post { id: 7 }),
+        AppRoute::Admin(admin::AdminRoute::Users),
+        AppRoute::About,
+    ] {
+        assert_eq!(route.to_string().parse::<AppRoute>().unwrap(), route);
+    }
+
+    assert_eq!(
+        AppRoute::Blog(blog::BlogRoute::Post { id: 7 }).to_string(),
+        "/posts/7"
+    );
+    assert!(matches!(
+        "/nowhere".parse::<AppRoute>(),
+        Err(ParseError::NoMatchingRoute { .. })
+    ));
+}
+
+#[test]
+fn test_from_uri() {
+    assert_eq!(
+        AppRoute::from_uri(&http::Uri::from_static("/posts/7"), &http::Method::DELETE).unwrap(),
+        AppRoute::Blog(blog::BlogRoute::DeletePost { id: 7 })
+    );
+    assert!(matches!(
+        AppRoute::from_uri(&http::Uri::from_static("/admin/users"), &http::Method::POST),
+        Err(ParseError::MethodNotAllowed { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_from_request() {
+    assert_eq!(
+        from_request("DELETE", "/posts/7").await,
+        Ok(AppRoute::Blog(blog::BlogRoute::DeletePost { id: 7 }))
+    );
+    assert_eq!(
+        from_request("GET", "/admin/users").await,
+        Ok(AppRoute::Admin(admin::AdminRoute::Users))
+    );
+    assert_eq!(from_request("GET", "/about").await, Ok(AppRoute::About));
+    assert_eq!(
+        from_request("POST", "/posts").await,
+        Err(http::StatusCode::METHOD_NOT_ALLOWED)
+    );
+    assert_eq!(
+        from_request("GET", "/nowhere").await,
+        Err(http::StatusCode::NOT_FOUND)
+    );
+}
+
+#[test]
+fn test_route_table() {
+    assert_eq!(
+        AppRoute::route_table(),
+        vec![
+            RouteEntry::new("Home", "GET", "/"),
+            RouteEntry::new("Blog::Posts", "GET", "/posts"),
+            RouteEntry::new("Blog::Post", "GET", "/posts/{id}"),
+            RouteEntry::new("Blog::DeletePost", "DELETE", "/posts/{id}"),
+            RouteEntry::new("Admin::Users", "GET", "/admin/users"),
+            RouteEntry::new("About", "GET", "/about"),
+        ]
+    );
+    assert_eq!(
+        AppRoute::Blog(blog::BlogRoute::Posts).variant_name(),
+        "Blog::Posts"
+    );
+}
+
+#[tokio::test]
+async fn test_include_specificity() {
+    let about = SiteRoute::Pages(pages::PageRoute::About);
+
+    assert_eq!("/about".parse::<SiteRoute>().unwrap(), about);
+    assert_eq!(
+        SiteRoute::from_uri(&http::Uri::from_static("/about"), &http::Method::GET).unwrap(),
+        about
+    );
+    assert_eq!(
+        SiteRoute::from_request(
+            http::Request::get("/about")
+                .body(axum::body::Body::empty())
+                .unwrap(),
+            &()
+        )
+        .await
+        .unwrap(),
+        about
+    );
+    assert_eq!(
+        "/contact".parse::<SiteRoute>().unwrap(),
+        SiteRoute::Page("contact".to_string())
+    );
+    assert_eq!(
+        "/pages/latest".parse::<SiteRoute>().unwrap(),
+        SiteRoute::Latest
+    );
+    assert_eq!(
+        "/pages/3".parse::<SiteRoute>().unwrap(),
+        SiteRoute::Pages(pages::PageRoute::Page { id: 3 })
+    );
+}
+
+#[test]
+fn test_include_conflicts() {
+    assert_eq!(SiteRoute::route_table().len(), 4);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(
+    expected = "the route `/{slug}` of `ShadowedRoute::Page` is unreachable, as all its paths are matched first by the route `/{tag}` of `TagRoute::Tag`"
+)]
+fn test_include_conflicts_shadowed() {
+    ShadowedRoute::route_table();
+}
@@ -0,0 +1,112 @@
+//! Tests for the `#[route_opts(...)]` options of the `Route` derive macro.
+
+#![cfg(feature = "derive")]
+
+use htmxology::{ParseError, Route};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct SearchQuery {
+    q: Option<String>,
+    page: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum PostRoute {
+    #[route("")]
+    Show,
+
+    #[route("edit", method = "POST")]
+    Edit,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+#[route_opts(no_from_str, no_display)]
+enum AppRoute {
+    #[route("")]
+    Home,
+
+    #[route("search")]
+    Search {
+        #[query]
+        query: SearchQuery,
+    },
+
+    #[route("posts/{id}/")]
+    Post {
+        id: u32,
+        #[subroute]
+        route: PostRoute,
+    },
+}
+
+/// The same routes, with the default implementations.
+#[derive(Debug, Clone, PartialEq, Route)]
+enum DefaultRoute {
+    #[route("")]
+    Home,
+
+    #[route("search")]
+    Search {
+        #[query]
+        query: SearchQuery,
+    },
+
+    #[route("posts/{id}/")]
+    Post {
+        id: u32,
+        #[subroute]
+        route: PostRoute,
+    },
+}
+
+#[test]
+fn test_display_matches_default() {
+    let query = SearchQuery {
+        q: Some("rust htmx".to_owned()),
+        page: Some(2),
+    };
+
+    assert_eq!(AppRoute::Home.to_string(), DefaultRoute::Home.to_string());
+    assert_eq!(
+        AppRoute::Search {
+            query: query.clone()
+        }
+        .to_string(),
+        DefaultRoute::Search { query }.to_string()
+    );
+    assert_eq!(
+        AppRoute::Search {
+            query: SearchQuery::default()
+        }
+        .to_string(),
+        "/search"
+    );
+    assert_eq!(
+        AppRoute::Post {
+            id: 42,
+            route: PostRoute::Edit
+        }
+        .to_string(),
+        "/posts/42/edit"
+    );
+}
+
+#[test]
+fn test_from_str_matches_default() {
+    for url in ["/", "/search?q=rust&page=2", "/search", "/posts/42/"] {
+        let route: AppRoute = url.parse().expect("route should parse");
+        let expected: DefaultRoute = url.parse().expect("route should parse");
+
+        assert_eq!(route.to_string(), expected.to_string());
+    }
+
+    assert!(matches!(
+        "/unknown".parse::<AppRoute>(),
+        Err(ParseError::NoMatchingRoute { .. })
+    ));
+    assert!(matches!(
+        "not a url".parse::<AppRoute>(),
+        Err(ParseError::NoMatchingRoute { .. })
+    ));
+}
@@ -0,0 +1,188 @@
+//! Tests for the route paths, queries and comparisons of routes derived with the `Route` macro.
+
+#![cfg(feature = "derive")]
+
+use htmxology::Route;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct SearchQuery {
+    q: Option<String>,
+    page: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum PostRoute {
+    #[route("")]
+    Show,
+
+    #[route("comments")]
+    Comments {
+        #[query]
+        query: SearchQuery,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum FallbackRoute {
+    #[route("search")]
+    Search {
+        #[query]
+        query: SearchQuery,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    #[route("")]
+    Home,
+
+    #[route("search")]
+    Search {
+        #[query]
+        query: SearchQuery,
+    },
+
+    #[route("posts/{id}/")]
+    Post {
+        id: u32,
+        #[subroute]
+        route: PostRoute,
+    },
+
+    #[catch_all]
+    Fallback(FallbackRoute),
+}
+
+#[test]
+fn test_path_and_query_without_query() {
+    assert_eq!(AppRoute::Home.path(), "/");
+    assert_eq!(AppRoute::Home.query(), None);
+
+    let route = AppRoute::Search {
+        query: SearchQuery::default(),
+    };
+    assert_eq!(route.path(), "/search");
+    assert_eq!(route.query(), None);
+}
+
+#[test]
+fn test_path_and_query_with_query() {
+    let route = AppRoute::Search {
+        query: SearchQuery {
+            q: Some("rust".to_owned()),
+            page: Some(2),
+        },
+    };
+
+    assert_eq!(route.to_string(), "/search?q=rust&page=2");
+    assert_eq!(route.path(), "/search");
+    assert_eq!(route.query().as_deref(), Some("q=rust&page=2"));
+}
+
+#[test]
+fn test_path_and_query_of_subroutes() {
+    let route = AppRoute::Post {
+        id: 7,
+        route: PostRoute::Comments {
+            query: SearchQuery {
+                q: None,
+                page: Some(3),
+            },
+        },
+    };
+
+    assert_eq!(route.to_string(), "/posts/7/comments?page=3");
+    assert_eq!(route.path(), "/posts/7/comments");
+    assert_eq!(route.query().as_deref(), Some("page=3"));
+}
+
+#[test]
+fn test_path_and_query_of_catch_all() {
+    let route = AppRoute::Fallback(FallbackRoute::Search {
+        query: SearchQuery {
+            q: Some("a b".to_owned()),
+            page: None,
+        },
+    });
+
+    assert_eq!(route.path(), "/search");
+    assert_eq!(route.query().as_deref(), Some("q=a+b"));
+}
+
+#[test]
+fn test_is_same_route() {
+    let search = |page| AppRoute::Search {
+        query: SearchQuery {
+            q: None,
+            page: Some(page),
+        },
+    };
+
+    assert!(search(1).is_same_route(&search(2)));
+    assert!(AppRoute::Home.is_same_route(&AppRoute::Home));
+    assert!(!AppRoute::Home.is_same_route(&search(1)));
+
+    let post = |id| AppRoute::Post {
+        id,
+        route: PostRoute::Show,
+    };
+
+    assert!(post(1).is_same_route(&post(1)));
+    assert!(!post(1).is_same_route(&post(2)));
+}
+
+#[test]
+fn test_is_ancestor_of() {
+    let post = AppRoute::Post {
+        id: 7,
+        route: PostRoute::Show,
+    };
+    let comments = AppRoute::Post {
+        id: 7,
+        route: PostRoute::Comments {
+            query: SearchQuery::default(),
+        },
+    };
+    let other_comments = AppRoute::Post {
+        id: 8,
+        route: PostRoute::Comments {
+            query: SearchQuery::default(),
+        },
+    };
+
+    assert!(post.is_ancestor_of(&comments));
+    assert!(!post.is_ancestor_of(&post));
+    assert!(!post.is_ancestor_of(&other_comments));
+    assert!(!comments.is_ancestor_of(&post));
+    assert!(AppRoute::Home.is_ancestor_of(&post));
+    assert!(!AppRoute::Home.is_ancestor_of(&AppRoute::Home));
+}
+
+#[test]
+fn test_as_hx_vals() {
+    assert_eq!(AppRoute::Home.as_hx_vals(), "{}");
+    assert_eq!(
+        AppRoute::Search {
+            query: SearchQuery {
+                q: Some("rust & \"htmx\"".to_owned()),
+                page: Some(2),
+            },
+        }
+        .as_hx_vals(),
+        r#"{"page":"2","q":"rust & \"htmx\""}"#
+    );
+    assert_eq!(
+        AppRoute::Post {
+            id: 3,
+            route: PostRoute::Comments {
+                query: SearchQuery {
+                    q: None,
+                    page: Some(1),
+                },
+            },
+        }
+        .as_hx_vals(),
+        r#"{"page":"1"}"#
+    );
+}
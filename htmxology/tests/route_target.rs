@@ -0,0 +1,125 @@
+//! Tests for the expected targets of routes, declared with `#[route(..., target = "#...")]`.
+
+#![cfg(feature = "derive")]
+
+use axum::extract::FromRequestParts as _;
+use htmxology::{Route, htmx};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct NewFood {
+    name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum SettingsRoute {
+    #[route("")]
+    Show,
+
+    #[route("password", target = "#password-form")]
+    Password,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    #[route("")]
+    Home,
+
+    #[route("foods", target = "#food-list")]
+    Foods,
+
+    #[route("foods", method = "POST", target = "#food-list")]
+    AddFood(#[body] NewFood),
+
+    #[route("settings/", target = "#settings")]
+    Settings(#[subroute] SettingsRoute),
+}
+
+async fn htmx_request(headers: &[(&str, &str)]) -> htmx::Request {
+    let mut request = http::Request::get("/foods");
+
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+
+    let (mut parts, ()) = request.body(()).unwrap().into_parts();
+
+    htmx::Request::from_request_parts(&mut parts, &())
+        .await
+        .unwrap()
+}
+
+#[test]
+fn test_expected_target() {
+    assert_eq!(AppRoute::Home.expected_target(), None);
+    assert_eq!(AppRoute::Foods.expected_target(), Some("#food-list"));
+    assert_eq!(
+        AppRoute::AddFood(NewFood {
+            name: "Apple".to_owned()
+        })
+        .expected_target(),
+        Some("#food-list")
+    );
+
+    // Subroutes inherit the target of their parent variant, unless they have their own.
+    assert_eq!(
+        AppRoute::Settings(SettingsRoute::Show).expected_target(),
+        Some("#settings")
+    );
+    assert_eq!(
+        AppRoute::Settings(SettingsRoute::Password).expected_target(),
+        Some("#password-form")
+    );
+
+    let targets: Vec<_> = AppRoute::describe()
+        .into_iter()
+        .map(|description| (description.variant, description.target))
+        .collect();
+
+    assert_eq!(
+        targets,
+        [
+            ("Home".to_owned(), None),
+            ("Foods".to_owned(), Some("#food-list".to_owned())),
+            ("AddFood".to_owned(), Some("#food-list".to_owned())),
+            ("Settings::Show".to_owned(), Some("#settings".to_owned())),
+            (
+                "Settings::Password".to_owned(),
+                Some("#password-form".to_owned())
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_matches_target() {
+    let matching = htmx_request(&[("HX-Request", "true"), ("HX-Target", "food-list")]).await;
+    let other = htmx_request(&[("HX-Request", "true"), ("HX-Target", "main")]).await;
+    let untargeted = htmx_request(&[("HX-Request", "true")]).await;
+    let boosted = htmx_request(&[("HX-Request", "true"), ("HX-Boosted", "true")]).await;
+    let classic = htmx_request(&[]).await;
+
+    assert!(matching.matches_target(&AppRoute::Foods));
+    assert!(!other.matches_target(&AppRoute::Foods));
+    assert!(!untargeted.matches_target(&AppRoute::Foods));
+    assert!(boosted.matches_target(&AppRoute::Foods));
+    assert!(classic.matches_target(&AppRoute::Foods));
+    assert!(other.matches_target(&AppRoute::Home));
+
+    assert_eq!(
+        htmx::sanity::check_target(&AppRoute::Foods, &matching),
+        None
+    );
+    assert_eq!(
+        htmx::sanity::check_target(&AppRoute::Foods, &other)
+            .unwrap()
+            .to_string(),
+        "request to `Foods` targets `#main`, but the route expects `#food-list`: check the `hx-target` of the element issuing it"
+    );
+    assert_eq!(
+        htmx::sanity::check_target(&AppRoute::Settings(SettingsRoute::Show), &untargeted)
+            .unwrap()
+            .to_string(),
+        "request to `Settings::Show` targets an element without `id`, but the route expects `#settings`: check the `hx-target` of the element issuing it"
+    );
+}
@@ -0,0 +1,190 @@
+//! Integration tests for the controller router description.
+
+#![cfg(feature = "derive")]
+
+use axum::response::IntoResponse;
+use htmxology::{Controller, ControllerRouter, Route, RouteEntry, ServerInfo};
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum BlogRoute {
+    #[route("")]
+    Index,
+
+    #[route("{id:u32}", method = "DELETE")]
+    Delete { id: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AdminRoute {
+    #[route("users")]
+    Users,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    #[route("")]
+    Home,
+
+    #[route("blog/")]
+    Blog {
+        #[subroute]
+        route: BlogRoute,
+    },
+
+    #[catch_all]
+    #[host("admin.{base}")]
+    Admin(AdminRoute),
+}
+
+#[derive(Clone)]
+struct AppController;
+
+impl Controller for AppController {
+    type Route = AppRoute;
+    type Args = ();
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    async fn handle_request(
+        &self,
+        _route: Self::Route,
+        _htmx: htmxology::htmx::Request,
+        _parts: http::request::Parts,
+        _server_info: &ServerInfo,
+        _args: Self::Args,
+    ) -> Self::Response {
+        Ok(().into_response())
+    }
+}
+
+#[test]
+fn test_route_table() {
+    assert_eq!(
+        AppRoute::route_table(),
+        vec![
+            RouteEntry::new("Home", "GET", "/"),
+            RouteEntry::new("Blog::Index", "GET", "/blog/"),
+            RouteEntry::new("Blog::Delete", "DELETE", "/blog/{id:u32}"),
+            RouteEntry {
+                host: Some("admin.{base}".to_owned()),
+                ..RouteEntry::new("Admin::Users", "GET", "/users")
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_variant_name() {
+    assert_eq!(AppRoute::Home.variant_name(), "Home");
+    assert_eq!(
+        AppRoute::Blog {
+            route: BlogRoute::Delete { id: 7 }
+        }
+        .variant_name(),
+        "Blog::Delete"
+    );
+    assert_eq!(
+        AppRoute::Admin(AdminRoute::Users).variant_name(),
+        "Admin::Users"
+    );
+}
+
+#[test]
+fn test_describe() {
+    let router = ControllerRouter::new(AppController, |_| async {})
+        .nest(
+            "/v2",
+            ControllerRouter::new(AppController, |_| async {}).layer(axum::Extension(1u32)),
+        )
+        .layer(axum::Extension("outer"));
+
+    assert_eq!(
+        serde_json::to_value(router.describe()).unwrap(),
+        serde_json::json!({
+            "routes": [
+                { "variant": "Home", "method": "GET", "pattern": "/" },
+                { "variant": "Blog::Index", "method": "GET", "pattern": "/blog/" },
+                { "variant": "Blog::Delete", "method": "DELETE", "pattern": "/blog/{id:u32}" },
+                { "variant": "Admin::Users", "method": "GET", "pattern": "/users", "host": "admin.{base}" },
+            ],
+            "layers": ["axum::extension::Extension<&str>"],
+            "endpoints": [],
+            "nested": [
+                {
+                    "prefix": "/v2",
+                    "router": {
+                        "routes": router.describe().nested[0].router.routes,
+                        "layers": ["axum::extension::Extension<u32>"],
+                        "endpoints": [],
+                        "nested": [],
+                    },
+                },
+            ],
+        })
+    );
+    assert_eq!(
+        router.describe().nested[0].router.routes,
+        AppRoute::route_table()
+    );
+}
+
+#[cfg(debug_assertions)]
+#[tokio::test]
+async fn test_not_found_diagnostics() {
+    use tower_service::Service;
+
+    let mut router = axum::Router::from(ControllerRouter::new(AppController, |_| async {}));
+    let request = http::Request::get("/blog/7/edit")
+        .body(axum::body::Body::empty())
+        .unwrap();
+
+    let response = router.call(request).await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body = std::str::from_utf8(&body).unwrap();
+
+    assert!(body.contains("No matching GET route found for URL: /blog/7/edit"));
+    assert!(body.contains("<li><code>DELETE /blog/{id:u32}</code> (Blog::Delete)</li>"));
+}
+
+#[tokio::test]
+async fn test_route_debug_endpoint() {
+    use tower_service::Service;
+
+    let mut router = axum::Router::from(
+        ControllerRouter::new(AppController, |_| async {})
+            .nest("/v2", ControllerRouter::new(AppController, |_| async {}))
+            .with_route_debug_endpoint("/_routes"),
+    );
+
+    let mut get = async |uri: &str| {
+        let request = http::Request::get(uri)
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = router.call(request).await.unwrap();
+        assert_eq!(response.status(), http::StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    };
+
+    let body = get("/_routes").await;
+    assert!(body.contains(
+        "<tr><td>DELETE</td><td><code>/blog/{id:u32}</code></td><td></td><td>Blog::Delete</td></tr>"
+    ));
+    assert!(body.contains(
+        "<tr><td>DELETE</td><td><code>/v2/blog/{id:u32}</code></td><td></td><td>Blog::Delete</td></tr>"
+    ));
+    assert!(body.contains("<li><code>/_routes</code></li>"));
+
+    let body = get("/_routes?method=DELETE&url=/v2/blog/7").await;
+    assert!(body.contains("<p>Matches <code>Blog::Delete</code>, under <code>/v2</code>.</p>"));
+
+    let body = get("/_routes?url=/blog/7/edit").await;
+    assert!(body.contains("No matching GET route found for URL: /blog/7/edit"));
+    assert!(body.contains("<li><code>DELETE /blog/{id:u32}</code> (Blog::Delete)</li>"));
+}
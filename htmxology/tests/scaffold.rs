@@ -0,0 +1,133 @@
+//! Integration tests for the generation of starter projects.
+
+#![cfg(feature = "scaffold")]
+
+use std::path::{Path, PathBuf};
+
+use htmxology::scaffold::{Scaffold, ScaffoldError};
+
+/// The combinations of options of the generated projects.
+fn scaffolds() -> Vec<Scaffold> {
+    let scaffold = Scaffold::new("starter").unwrap();
+
+    vec![
+        scaffold.clone(),
+        scaffold.clone().with_auth(false),
+        scaffold
+            .clone()
+            .with_auth(false)
+            .with_sessions(false)
+            .with_static_assets(false),
+    ]
+}
+
+fn paths(scaffold: &Scaffold) -> Vec<PathBuf> {
+    scaffold.files().into_iter().map(|file| file.path).collect()
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("htmxology-scaffold-{name}-{}", std::process::id()))
+}
+
+#[test]
+fn test_files() {
+    let scaffold = Scaffold::new("starter").unwrap();
+
+    assert_eq!(
+        paths(&scaffold),
+        [
+            "Cargo.toml",
+            ".gitignore",
+            "README.md",
+            "src/main.rs",
+            "src/controller/mod.rs",
+            "src/controller/pages.rs",
+            "src/controller/account.rs",
+            "src/views.rs",
+            "templates/layout.html.jinja",
+            "templates/home.html.jinja",
+            "templates/login.html.jinja",
+            "static/app.css",
+        ]
+        .map(PathBuf::from)
+    );
+
+    let minimal = scaffold.with_auth(false).with_static_assets(false);
+
+    assert!(!paths(&minimal).contains(&PathBuf::from("src/controller/account.rs")));
+    assert!(!paths(&minimal).contains(&PathBuf::from("static/app.css")));
+}
+
+#[test]
+fn test_templates_are_rendered() {
+    for scaffold in scaffolds() {
+        for file in scaffold.files() {
+            assert!(
+                !file.contents.contains("${") && !file.contents.contains("%if"),
+                "{}",
+                file.path.display()
+            );
+        }
+    }
+
+    let manifest = Scaffold::new("starter")
+        .unwrap()
+        .with_htmxology_path("../htmxology")
+        .files()
+        .remove(0);
+
+    assert!(manifest.contents.contains("name = \"starter\""));
+    assert!(manifest.contents.contains(
+        r#"htmxology = { path = "../htmxology", features = ["auto-reload", "derive", "templating", "session"] }"#
+    ));
+}
+
+#[test]
+fn test_write_to() {
+    let dir = temp_dir("write");
+    let scaffold = Scaffold::new("starter").unwrap();
+
+    let written = scaffold.write_to(&dir).unwrap();
+
+    assert_eq!(written.len(), scaffold.files().len());
+    assert!(dir.join("src/controller/account.rs").is_file());
+
+    // Existing projects are left untouched.
+    assert!(matches!(
+        scaffold.write_to(&dir),
+        Err(ScaffoldError::AlreadyExists(path)) if path == dir.join("Cargo.toml")
+    ));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Check that the generated projects build against this version of the crate.
+///
+/// Run with `cargo test --features scaffold -- --ignored`.
+#[test]
+#[ignore = "builds the generated projects, which is slow"]
+fn test_generated_projects_build() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned());
+
+    for (i, scaffold) in scaffolds().into_iter().enumerate() {
+        let dir = temp_dir(&format!("build-{i}"));
+
+        scaffold
+            .with_htmxology_path(crate_dir)
+            .write_to(&dir)
+            .unwrap();
+
+        let status = std::process::Command::new(&cargo)
+            .args(["check", "--offline", "--quiet"])
+            .current_dir(&dir)
+            .env("CARGO_TARGET_DIR", crate_dir.join("../target/scaffold"))
+            .env("RUSTFLAGS", "-D warnings")
+            .status()
+            .unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(status.success(), "generated project {i} failed to build");
+    }
+}
@@ -0,0 +1,175 @@
+//! Integration tests for multi-step scenarios.
+
+#![cfg(all(feature = "derive", feature = "testing"))]
+
+use axum::response::IntoResponse;
+use htmxology::testing::{Scenario, ScenarioRequest};
+use htmxology::{Controller, ControllerRouter, Route, ServerInfo, htmx};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct LoginForm {
+    user: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct ProjectForm {
+    name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    #[route("")]
+    Home,
+
+    #[route("login", method = "POST")]
+    Login(#[body] LoginForm),
+
+    #[route("logout", method = "POST")]
+    Logout,
+
+    #[route("dashboard")]
+    Dashboard,
+
+    #[route("projects")]
+    Projects,
+
+    #[route("projects", method = "POST")]
+    CreateProject(#[body] ProjectForm),
+}
+
+#[derive(Clone)]
+struct AppController;
+
+fn user(parts: &http::request::Parts) -> Option<String> {
+    parts
+        .headers
+        .get(http::header::COOKIE)?
+        .to_str()
+        .ok()?
+        .split("; ")
+        .find_map(|cookie| cookie.strip_prefix("user="))
+        .map(str::to_owned)
+}
+
+impl Controller for AppController {
+    type Route = AppRoute;
+    type Args = ();
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        htmx: htmx::Request,
+        parts: http::request::Parts,
+        _server_info: &ServerInfo,
+        _args: Self::Args,
+    ) -> Self::Response {
+        let user = user(&parts);
+
+        Ok(match route {
+            AppRoute::Home => axum::response::Html("<h1>Welcome</h1>").into_response(),
+            AppRoute::Login(form) => (
+                http::StatusCode::SEE_OTHER,
+                [
+                    (http::header::LOCATION, "/dashboard".to_owned()),
+                    (
+                        http::header::SET_COOKIE,
+                        format!("user={}; Path=/", form.user),
+                    ),
+                ],
+            )
+                .into_response(),
+            AppRoute::Logout => (
+                [
+                    ("hx-redirect", "http://localhost/"),
+                    (http::header::SET_COOKIE.as_str(), "user=; Max-Age=0"),
+                ],
+                "",
+            )
+                .into_response(),
+            AppRoute::Dashboard => match user {
+                Some(user) => axum::response::Html(format!(
+                    r#"<h1>Hello {user}</h1><div id="main" hx-get="/projects"></div>"#
+                ))
+                .into_response(),
+                None => axum::response::Redirect::to("/").into_response(),
+            },
+            AppRoute::Projects => {
+                let from = match &htmx {
+                    htmx::Request::Htmx { current_url, .. } => current_url.clone(),
+                    htmx::Request::Classic => String::new(),
+                };
+
+                (
+                    [("hx-push-url", "/projects")],
+                    axum::response::Html(format!(
+                        r#"<ul data-from="{from}"><li class="project">A</li><li class="project">B</li></ul>"#
+                    )),
+                )
+                    .into_response()
+            }
+            AppRoute::CreateProject(form) => (
+                http::StatusCode::CREATED,
+                [(
+                    "hx-location",
+                    format!(
+                        r##"{{"path":"/projects?created={}","target":"#main"}}"##,
+                        form.name
+                    ),
+                )],
+                "",
+            )
+                .into_response(),
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_scenario() {
+    let mut scenario = Scenario::new(ControllerRouter::new(AppController, |_| async {}));
+
+    // Unauthenticated users are redirected home.
+    let response = scenario.get("/dashboard").await;
+    assert_eq!(response.url(), "/");
+    assert_eq!(response.select("h1"), ["<h1>Welcome</h1>"]);
+    assert_eq!(scenario.current_url(), Some("/"));
+
+    // Logging in sets a cookie, and follows the redirect to the dashboard.
+    let response = scenario.post_form("/login", &[("user", "Al Ice")]).await;
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(response.url(), "/dashboard");
+    assert_eq!(scenario.cookie("user"), Some("Al Ice"));
+    assert_eq!(response.select("h1"), ["<h1>Hello Al Ice</h1>"]);
+
+    // Fragments are loaded from the current page, and push their URL.
+    let response = scenario.hx_get("/projects").await;
+    assert_eq!(response.select("li.project").len(), 2);
+    assert_eq!(
+        response.select("ul")[0],
+        r#"<ul data-from="http://localhost/dashboard"><li class="project">A</li><li class="project">B</li></ul>"#
+    );
+    assert_eq!(scenario.current_url(), Some("/projects"));
+
+    // `HX-Location` responses are followed with an htmx request pushing its URL.
+    let response = scenario
+        .send(
+            ScenarioRequest::post("/projects")
+                .htmx()
+                .with_target("main")
+                .with_form(&[("name", "C")]),
+        )
+        .await;
+    assert_eq!(response.url(), "/projects?created=C");
+    assert_eq!(
+        response.select("ul")[0],
+        r#"<ul data-from="http://localhost/projects"><li class="project">A</li><li class="project">B</li></ul>"#
+    );
+    assert_eq!(scenario.current_url(), Some("/projects?created=C"));
+
+    // `HX-Redirect` responses are followed with a classic request, and expired cookies removed.
+    let response = scenario.hx_post("/logout", &[]).await;
+    assert_eq!(response.url(), "/");
+    assert_eq!(scenario.cookie("user"), None);
+    assert_eq!(scenario.current_url(), Some("/"));
+}
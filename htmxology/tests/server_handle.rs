@@ -0,0 +1,375 @@
+//! Integration tests for servers spawned in the background.
+
+#![cfg(feature = "derive")]
+
+use std::{
+    io::{Read, Write},
+    time::Duration,
+};
+
+use axum::response::IntoResponse;
+use htmxology::{Controller, ControllerRouter, Route, Server, ServerInfo};
+
+#[derive(Debug, Clone, PartialEq, Route)]
+enum AppRoute {
+    #[route("")]
+    Home,
+
+    #[route("slow")]
+    Slow,
+}
+
+#[derive(Clone)]
+struct AppController;
+
+impl Controller for AppController {
+    type Route = AppRoute;
+    type Args = ();
+    type Response = Result<axum::response::Response, axum::response::Response>;
+
+    async fn handle_request(
+        &self,
+        route: Self::Route,
+        _htmx: htmxology::htmx::Request,
+        _parts: http::request::Parts,
+        server_info: &ServerInfo,
+        _args: Self::Args,
+    ) -> Self::Response {
+        if route == AppRoute::Slow {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+        }
+
+        Ok(format!("Hello from {}", server_info.base_url).into_response())
+    }
+}
+
+/// Send a `GET /` request to a server, returning the raw response.
+async fn get(addr: std::net::SocketAddr) -> String {
+    get_path(addr, "/").await
+}
+
+/// Send a `GET` request to a server, returning the raw response.
+async fn get_path(addr: std::net::SocketAddr, path: &'static str) -> String {
+    tokio::task::spawn_blocking(move || {
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n"
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_spawn_and_shutdown() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let router = ControllerRouter::new(AppController, |_| async {});
+    let handle = Server::builder(listener).build().spawn(router).unwrap();
+
+    handle.ready().await;
+
+    let addr = handle.local_addr();
+    assert_ne!(addr.port(), 0);
+    assert_eq!(
+        handle.server_info().base_url.to_string(),
+        format!("http://127.0.0.1:{}/", addr.port())
+    );
+
+    let response = get(addr).await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+    assert!(
+        response.ends_with(&format!("Hello from http://127.0.0.1:{}/", addr.port())),
+        "{response}"
+    );
+
+    assert!(!handle.is_finished());
+    handle.shutdown_and_join().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_multiple_servers() {
+    let mut handles = Vec::new();
+
+    for _ in 0..2 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let router = ControllerRouter::new(AppController, |_| async {});
+        handles.push(Server::builder(listener).build().spawn(router).unwrap());
+    }
+
+    assert_ne!(handles[0].local_addr(), handles[1].local_addr());
+
+    for handle in &handles {
+        handle.ready().await;
+        assert!(
+            get(handle.local_addr())
+                .await
+                .starts_with("HTTP/1.1 200 OK")
+        );
+    }
+
+    for handle in handles {
+        handle.shutdown_and_join().await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_drain_timeout() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let router = ControllerRouter::new(AppController, |_| async {});
+    let handle = Server::builder(listener)
+        .with_drain_timeout(Duration::from_millis(100))
+        .build()
+        .spawn(router)
+        .unwrap();
+
+    handle.ready().await;
+
+    let slow = tokio::spawn(get_path(handle.local_addr(), "/slow"));
+
+    // Let the slow request reach the controller.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // The server stops despite the slow request, once the drain timeout elapses.
+    tokio::time::timeout(Duration::from_secs(5), handle.shutdown_and_join())
+        .await
+        .expect("the server should stop after the drain timeout")
+        .unwrap();
+
+    slow.abort();
+}
+
+#[cfg(feature = "tls")]
+#[tokio::test]
+async fn test_tls() {
+    use std::sync::Arc;
+
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+    use tokio_rustls::rustls::{self, pki_types::pem::PemObject as _};
+
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let router = ControllerRouter::new(AppController, |_| async {});
+    let handle = Server::builder(listener)
+        .with_tls(
+            fixtures.join("localhost.crt"),
+            fixtures.join("localhost.key"),
+        )
+        .unwrap()
+        .build()
+        .spawn(router)
+        .unwrap();
+
+    handle.ready().await;
+
+    let addr = handle.local_addr();
+    assert_eq!(
+        handle.server_info().base_url.to_string(),
+        format!("https://127.0.0.1:{}/", addr.port())
+    );
+
+    let mut roots = rustls::RootCertStore::empty();
+
+    for cert in
+        rustls::pki_types::CertificateDer::pem_file_iter(fixtures.join("localhost.crt")).unwrap()
+    {
+        roots.add(cert.unwrap()).unwrap();
+    }
+
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .unwrap()
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let mut stream = tokio_rustls::TlsConnector::from(Arc::new(config))
+        .connect("localhost".try_into().unwrap(), stream)
+        .await
+        .unwrap();
+
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+    assert!(
+        response.ends_with(&format!("Hello from https://127.0.0.1:{}/", addr.port())),
+        "{response}"
+    );
+
+    handle.shutdown_and_join().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_multiple_listeners() {
+    let first = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let second = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let router = ControllerRouter::new(AppController, |_| async {});
+    let handle = Server::builder(first)
+        .with_listener(second)
+        .build()
+        .spawn(router)
+        .unwrap();
+
+    handle.ready().await;
+
+    let addrs: Vec<_> = handle
+        .local_addrs()
+        .iter()
+        .map(|addr| addr.as_tcp().unwrap())
+        .collect();
+    assert_eq!(addrs.len(), 2);
+    assert_eq!(handle.local_addr(), addrs[0]);
+
+    // The base URL is guessed from the first listener.
+    for addr in addrs {
+        let response = get(addr).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(
+            response.ends_with(&format!(
+                "Hello from http://127.0.0.1:{}/",
+                handle.local_addr().port()
+            )),
+            "{response}"
+        );
+    }
+
+    handle.shutdown_and_join().await.unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_unix_listener() {
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    let dir = std::env::temp_dir().join(format!("htmxology-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("server.sock");
+    let _ = std::fs::remove_file(&path);
+
+    let listener = tokio::net::UnixListener::bind(&path).unwrap();
+    let router = ControllerRouter::new(AppController, |_| async {});
+    let handle = Server::builder(listener).build().spawn(router).unwrap();
+
+    handle.ready().await;
+
+    assert_eq!(
+        handle.local_addrs(),
+        [htmxology::ListenerAddr::Unix(Some(path.clone()))]
+    );
+    assert_eq!(
+        handle.server_info().base_url.to_string(),
+        "http://localhost/"
+    );
+
+    let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+    assert!(
+        response.ends_with("Hello from http://localhost/"),
+        "{response}"
+    );
+
+    handle.shutdown_and_join().await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_trusted_proxies() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let router = ControllerRouter::new(AppController, |_| async {});
+    let handle = Server::builder(listener)
+        .with_options(htmxology::ServerOptions {
+            trusted_proxies: vec!["127.0.0.0/8".parse().unwrap()],
+            ..Default::default()
+        })
+        .build()
+        .spawn(router)
+        .unwrap();
+
+    handle.ready().await;
+
+    let addr = handle.local_addr();
+    let response = tokio::task::spawn_blocking(move || {
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "GET / HTTP/1.1\r\nHost: localhost\r\nX-Forwarded-Proto: https\r\nX-Forwarded-Host: example.com\r\nConnection: close\r\n\r\n"
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    })
+    .await
+    .unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+    assert!(
+        response.ends_with("Hello from https://example.com/"),
+        "{response}"
+    );
+
+    // The server information of the server itself is unchanged.
+    assert_eq!(
+        handle.server_info().base_url.to_string(),
+        format!("http://127.0.0.1:{}/", addr.port())
+    );
+
+    handle.shutdown_and_join().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_shutdown_handle_and_readiness() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let router = ControllerRouter::new(AppController, |_| async {})
+        .with_health_endpoint("/healthz")
+        .with_readiness_endpoint("/readyz");
+    let builder = Server::builder(listener).with_shutdown_delay(Duration::from_millis(500));
+    let shutdown = builder.shutdown_handle();
+    let handle = builder.build().spawn(router).unwrap();
+
+    handle.ready().await;
+
+    let addr = handle.local_addr();
+
+    let response = get_path(addr, "/readyz").await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+
+    shutdown.shutdown();
+    assert!(handle.shutdown_handle().is_shutting_down());
+
+    // Connections are still accepted during the shutdown delay, but the server is not ready.
+    let response = get_path(addr, "/readyz").await;
+    assert!(
+        response.starts_with("HTTP/1.1 503 Service Unavailable"),
+        "{response}"
+    );
+
+    let response = get_path(addr, "/healthz").await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+
+    tokio::time::timeout(Duration::from_secs(5), handle.join())
+        .await
+        .expect("the server should stop after the shutdown delay")
+        .unwrap();
+}